@@ -1,8 +1,11 @@
 use std::time::Duration;
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use rand::{rngs::StdRng, SeedableRng};
-use rust_elgamal::{Commitment, DecryptionKey, RistrettoPoint, Scalar};
+use rust_elgamal::{
+    compress_batch, Ciphertext, CiphertextColumns, Commitment, DecryptionKey, DiscreteLogTable,
+    RistrettoPoint, Scalar,
+};
 
 const TEST_SEED: [u8; 32] = [
     1, 0, 0, 0, 23, 0, 0, 0, 200, 1, 0, 0, 210, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -24,6 +27,23 @@ fn bench_encrypt(c: &mut Criterion) {
     });
 }
 
+fn bench_pad_apply(c: &mut Criterion) {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+    let m = RistrettoPoint::random(&mut rng);
+
+    // the online step alone, which is one point addition
+    c.bench_function("bench_pad_apply", |b| {
+        b.iter_batched(
+            || ek.precompute_pad(&mut rng),
+            |pad| std::hint::black_box(pad.apply(m)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
 fn bench_decrypt(c: &mut Criterion) {
     let mut rng = StdRng::from_seed(TEST_SEED);
 
@@ -70,6 +90,133 @@ fn bench_verify_commitment(c: &mut Criterion) {
     });
 }
 
+fn bench_columns(c: &mut Criterion) {
+    const N: usize = 1_000_000;
+
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+    // successive multiples of two random points, which are much quicker to make than encryptions
+    let (p, q) = (
+        RistrettoPoint::random(&mut rng),
+        RistrettoPoint::random(&mut rng),
+    );
+    let mut ct = ek.encrypt(p, &mut rng);
+    let cts: Vec<Ciphertext> = (0..N)
+        .map(|_| {
+            ct = ct + Ciphertext::from((p, q));
+            ct
+        })
+        .collect();
+    let columns = CiphertextColumns::from(&cts[..]);
+
+    c.bench_function("bench_rerandomise_interleaved", |b| {
+        b.iter(|| {
+            std::hint::black_box(
+                cts.iter()
+                    .map(|ct| ek.rerandomise(*ct, &mut rng))
+                    .collect::<Vec<_>>(),
+            );
+        })
+    });
+    c.bench_function("bench_rerandomise_columns", |b| {
+        b.iter(|| {
+            let mut batch = columns.clone();
+            batch.rerandomise_batch(ek, &mut rng);
+            std::hint::black_box(batch);
+        })
+    });
+
+    c.bench_function("bench_add_assign_interleaved", |b| {
+        b.iter(|| {
+            let mut batch = cts.clone();
+            for (a, b) in batch.iter_mut().zip(&cts) {
+                *a = *a + b;
+            }
+            std::hint::black_box(batch);
+        })
+    });
+    c.bench_function("bench_add_assign_columns", |b| {
+        b.iter(|| {
+            let mut batch = columns.clone();
+            batch.add_assign_batch(&columns).unwrap();
+            std::hint::black_box(batch);
+        })
+    });
+
+    c.bench_function("bench_compress_interleaved", |b| {
+        b.iter(|| {
+            std::hint::black_box(compress_batch(&cts));
+        })
+    });
+    c.bench_function("bench_compress_columns", |b| {
+        b.iter(|| {
+            std::hint::black_box(columns.compress_batch());
+        })
+    });
+}
+
+// Recovering counters below 2^20 with a precomputed table, against a baby-step giant-step
+// search for each one.
+fn bench_dlog(c: &mut Criterion) {
+    const MAX: u32 = 1 << 20;
+
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+    let ct = ek.encrypt_scalar(MAX as u64 - 12345, &mut rng);
+
+    let table = DiscreteLogTable::new(MAX as u64);
+    c.bench_function("bench_decrypt_with_table", |b| {
+        b.iter(|| {
+            std::hint::black_box(dk.decrypt_with_table(ct, &table).unwrap());
+        })
+    });
+    c.bench_function("bench_decrypt_bsgs", |b| {
+        b.iter(|| {
+            std::hint::black_box(dk.decrypt_u32_bounded(ct, MAX).unwrap());
+        })
+    });
+}
+
+// Proving ballots with 1, 2, 4, ... threads, up to the number of cores, to show how proving
+// scales.
+#[cfg(feature = "parallel")]
+fn bench_parallel(c: &mut Criterion) {
+    use rust_elgamal::parallel::par_encrypt_ballots;
+
+    const N: usize = 256;
+
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+    let voters: Vec<(Vec<u8>, Vec<bool>)> = (0..N)
+        .map(|i| (vec![i as u8], vec![i % 2 == 0, i % 2 == 1]))
+        .collect();
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = 1;
+    while threads <= cores {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        c.bench_function(&format!("bench_par_encrypt_ballots_{}_threads", threads), |b| {
+            b.iter(|| {
+                let ballots = pool.install(|| par_encrypt_ballots(ek, b"bench", &voters, &mut rng));
+                std::hint::black_box(ballots);
+            })
+        });
+        threads *= 2;
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn bench_parallel(_: &mut Criterion) {}
+
 criterion_group! {
     name = commitment;
     config = Criterion::default().sample_size(20).measurement_time(Duration::from_secs(5));
@@ -83,6 +230,25 @@ criterion_group! {
     config = Criterion::default().sample_size(20).measurement_time(Duration::from_secs(5));
     targets =
         bench_encrypt,
+        bench_pad_apply,
         bench_decrypt
 }
-criterion_main!(encrypt_decrypt, commitment,);
+criterion_group! {
+    name = columns;
+    config = Criterion::default().sample_size(10).measurement_time(Duration::from_secs(60));
+    targets =
+        bench_columns
+}
+criterion_group! {
+    name = dlog;
+    config = Criterion::default().sample_size(10).measurement_time(Duration::from_secs(20));
+    targets =
+        bench_dlog
+}
+criterion_group! {
+    name = parallel;
+    config = Criterion::default().sample_size(10).measurement_time(Duration::from_secs(20));
+    targets =
+        bench_parallel
+}
+criterion_main!(encrypt_decrypt, commitment, columns, dlog, parallel,);
@@ -0,0 +1,29 @@
+// Generated by rust-elgamal's `ts` feature. Do not edit.
+
+export type Point = Array<number>;
+
+export type Scalar = Array<number>;
+
+export type EncryptionKey = Point;
+
+export type Ciphertext = [Point, Point];
+
+export type CompressedCiphertext = [Point, Point];
+
+export type Commitment = [Point, Ciphertext];
+
+export type Open = [Scalar, Scalar];
+
+export type DleqProof = { c: Scalar, s: Scalar, };
+
+export type PedersenLinkProof = { e: Scalar, z_m: Scalar, z_r: Scalar, z_b: Scalar, };
+
+export type ZeroOneProof = { c: [Scalar, Scalar], s: [Scalar, Scalar], };
+
+export type Ballot = Array<Ciphertext>;
+
+export type BallotProofs = { zero_one: Array<ZeroOneProof>, sum: DleqProof, };
+
+export type ProofPolicy = "ZeroOne" | { "ExactSelections": number };
+
+export type Receipt = { position: number, chain_hash: Array<number>, };
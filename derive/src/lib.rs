@@ -0,0 +1,268 @@
+// Derive macros for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Derive macros for [rust-elgamal](https://docs.rs/rust-elgamal). Do not depend on this crate
+//! directly: turn on the `derive` feature of rust-elgamal and use the macros from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Member, PathArguments, Type};
+
+// The types a wrapper may hold, and the length of their byte encoding.
+#[derive(Copy, Clone)]
+enum Inner {
+    Ciphertext,
+    Commitment,
+}
+
+impl Inner {
+    fn from_type(ty: &Type) -> Option<Inner> {
+        let segment = match ty {
+            Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+            _ => return None,
+        };
+        if !matches!(segment.arguments, PathArguments::None) {
+            return None;
+        }
+        if segment.ident == "Ciphertext" {
+            Some(Inner::Ciphertext)
+        } else if segment.ident == "Commitment" {
+            Some(Inner::Commitment)
+        } else {
+            None
+        }
+    }
+
+    fn byte_len(self) -> usize {
+        match self {
+            Inner::Ciphertext => 64,
+            Inner::Commitment => 96,
+        }
+    }
+}
+
+/// Derive the homomorphic operators, serde, byte encoding and conversions of a newtype over
+/// `Ciphertext` or `Commitment`, closed over the newtype so that different wrappers cannot be
+/// mixed.
+#[proc_macro_derive(ElgamalWrapper)]
+pub fn derive_elgamal_wrapper(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "ElgamalWrapper does not support generic parameters",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "ElgamalWrapper can only be derived for structs",
+            ))
+        }
+    };
+    if fields.len() != 1 {
+        return Err(Error::new(
+            fields.span(),
+            "ElgamalWrapper requires a struct with exactly one field",
+        ));
+    }
+    let field = fields.iter().next().unwrap();
+    let ty = &field.ty;
+    let inner = Inner::from_type(ty).ok_or_else(|| {
+        Error::new(
+            ty.span(),
+            "ElgamalWrapper requires a field of type `Ciphertext` or `Commitment`",
+        )
+    })?;
+
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(0.into()),
+    };
+    let construct = match fields {
+        Fields::Named(_) => quote!(#name { #member: inner }),
+        _ => quote!(#name(inner)),
+    };
+
+    let len = inner.byte_len();
+    let (to_bytes, from_bytes) = match inner {
+        Inner::Ciphertext => (
+            quote!(::rust_elgamal::derive::ciphertext_to_bytes),
+            quote!(::rust_elgamal::derive::ciphertext_from_bytes),
+        ),
+        Inner::Commitment => (
+            quote!(::rust_elgamal::derive::commitment_to_bytes),
+            quote!(::rust_elgamal::derive::commitment_from_bytes),
+        ),
+    };
+    // commitments carry their key, so there is no key-independent zero to start a sum from
+    let sum = match inner {
+        Inner::Ciphertext => quote! {
+            #[automatically_derived]
+            impl ::core::iter::Sum for #name {
+                fn sum<I: ::core::iter::Iterator<Item = #name>>(iter: I) -> #name {
+                    iter.fold(
+                        #name::new(<#ty as ::rust_elgamal::Identity>::identity()),
+                        ::core::ops::Add::add,
+                    )
+                }
+            }
+
+            #[automatically_derived]
+            impl<'a> ::core::iter::Sum<&'a #name> for #name {
+                fn sum<I: ::core::iter::Iterator<Item = &'a #name>>(iter: I) -> #name {
+                    iter.fold(
+                        #name::new(<#ty as ::rust_elgamal::Identity>::identity()),
+                        |acc, x| acc + x,
+                    )
+                }
+            }
+        },
+        Inner::Commitment => quote!(),
+    };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #name {
+            /// Wrap `inner`.
+            pub const fn new(inner: #ty) -> #name {
+                #construct
+            }
+
+            /// Unwrap the inner value.
+            pub fn into_inner(self) -> #ty {
+                self.#member
+            }
+
+            /// Encode the inner value in its canonical compressed form.
+            pub fn to_bytes(&self) -> [u8; #len] {
+                #to_bytes(&self.#member)
+            }
+
+            /// Decode a value written by `to_bytes`, checking every point.
+            pub fn from_bytes(bytes: &[u8; #len]) -> ::core::result::Result<#name, ::rust_elgamal::Error> {
+                #from_bytes(bytes).map(#name::new)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::convert::From<#ty> for #name {
+            fn from(inner: #ty) -> #name {
+                #name::new(inner)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::convert::From<#name> for #ty {
+            fn from(wrapper: #name) -> #ty {
+                wrapper.#member
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::convert::AsRef<#ty> for #name {
+            fn as_ref(&self) -> &#ty {
+                &self.#member
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Add for #name {
+            type Output = #name;
+
+            fn add(self, rhs: #name) -> #name {
+                #name::new(self.#member + rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Add<&#name> for #name {
+            type Output = #name;
+
+            fn add(self, rhs: &#name) -> #name {
+                #name::new(self.#member + &rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Add for &#name {
+            type Output = #name;
+
+            fn add(self, rhs: &#name) -> #name {
+                #name::new(&self.#member + &rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Sub for #name {
+            type Output = #name;
+
+            fn sub(self, rhs: #name) -> #name {
+                #name::new(self.#member - rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Sub<&#name> for #name {
+            type Output = #name;
+
+            fn sub(self, rhs: &#name) -> #name {
+                #name::new(self.#member - &rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Sub for &#name {
+            type Output = #name;
+
+            fn sub(self, rhs: &#name) -> #name {
+                #name::new(&self.#member - &rhs.#member)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Mul<::rust_elgamal::Scalar> for #name {
+            type Output = #name;
+
+            fn mul(self, rhs: ::rust_elgamal::Scalar) -> #name {
+                #name::new(self.#member * rhs)
+            }
+        }
+
+        #[automatically_derived]
+        impl ::core::ops::Mul<::rust_elgamal::Scalar> for &#name {
+            type Output = #name;
+
+            fn mul(self, rhs: ::rust_elgamal::Scalar) -> #name {
+                #name::new(&self.#member * rhs)
+            }
+        }
+
+        #sum
+
+        ::rust_elgamal::__elgamal_wrapper_serde!(#name, #ty);
+    })
+}
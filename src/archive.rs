@@ -0,0 +1,291 @@
+// Compressed ciphertext archives for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compressed archives of ciphertexts under one key, for publishing bulk exports such as the
+//! ballots of an election.
+//!
+//! An archive is a 49-byte header followed by a single zstd frame:
+//!
+//! | bytes | contents                                                     |
+//! |-------|--------------------------------------------------------------|
+//! | 8     | the magic `RELGZCTS`                                         |
+//! | 1     | the format version, [ARCHIVE_VERSION]                        |
+//! | 8     | the number of ciphertexts, little-endian                     |
+//! | 32    | SHA-256 of the uncompressed payload                          |
+//! | ...   | the payload, zstd-compressed                                 |
+//!
+//! The payload is the 32-byte encryption key followed by the 64-byte compressed ciphertexts.
+//! Auditors can check the hash with standard tools, by decompressing everything after the
+//! header and hashing the result.
+
+use std::io::{Read, Write};
+use std::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::{Ciphertext, CompressedCiphertext, EncryptionKey, Error};
+
+/// The format version written in the header of every archive.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// The largest number of ciphertexts [import_compressed] accepts, 2^30 (64 GiB uncompressed).
+pub const ARCHIVE_MAX_CIPHERTEXTS: u64 = 1 << 30;
+
+const MAGIC: &[u8; 8] = b"RELGZCTS";
+const HEADER_LEN: usize = 8 + 1 + 8 + 32;
+
+/// Write `cts`, encrypted under `ek`, to `writer` as a compressed archive.
+///
+/// The ciphertexts are encoded and compressed one at a time, so memory use does not grow with
+/// their number. They are read twice, once to hash them for the header and once to compress them.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{export_compressed, import_compressed, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+/// let cts: Vec<_> = (0..100)
+///     .map(|_| enc_key.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+///     .collect();
+///
+/// let mut archive = Vec::new();
+/// export_compressed(&mut archive, enc_key, &cts).unwrap();
+/// assert_eq!(import_compressed(&archive[..]), Ok((*enc_key, cts)));
+/// ```
+pub fn export_compressed<W: Write>(
+    mut writer: W,
+    ek: &EncryptionKey,
+    cts: &[Ciphertext],
+) -> Result<(), Error> {
+    let key = ek.0.compress().to_bytes();
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    for ct in cts {
+        hasher.update(ct.compress().to_bytes());
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[ARCHIVE_VERSION])?;
+    writer.write_all(&(cts.len() as u64).to_le_bytes())?;
+    writer.write_all(&hasher.finalize())?;
+
+    let mut encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+    encoder.include_checksum(true)?;
+    encoder.write_all(&key)?;
+    for ct in cts {
+        encoder.write_all(&ct.compress().to_bytes())?;
+    }
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+/// Read an archive written by [export_compressed], returning the encryption key and the
+/// ciphertexts.
+///
+/// This accepts at most [ARCHIVE_MAX_CIPHERTEXTS] ciphertexts; see
+/// [import_compressed_with_limit] to set another limit.
+pub fn import_compressed<R: Read>(reader: R) -> Result<(EncryptionKey, Vec<Ciphertext>), Error> {
+    import_compressed_with_limit(reader, ARCHIVE_MAX_CIPHERTEXTS)
+}
+
+/// Read an archive written by [export_compressed], accepting at most `max` ciphertexts.
+///
+/// The payload is decompressed and checked one ciphertext at a time, and no more than the header
+/// declares is ever decompressed, so a small archive cannot expand into an unbounded amount of
+/// memory.
+///
+/// Fails with:
+/// - [Error::InvalidArchive] if the data is not an archive, or the payload is shorter or longer
+///   than the header declares,
+/// - [Error::UnsupportedVersion] if it has another format version,
+/// - [Error::TooLarge] if it declares more than `max` ciphertexts,
+/// - [Error::VerificationFailed] if the payload does not match the hash in the header,
+/// - [Error::InvalidPoint] or [Error::IdentityPoint] if the key or a ciphertext is malformed,
+/// - [Error::Io] if reading or decompressing fails.
+pub fn import_compressed_with_limit<R: Read>(
+    mut reader: R,
+    max: u64,
+) -> Result<(EncryptionKey, Vec<Ciphertext>), Error> {
+    let mut header = [0u8; HEADER_LEN];
+    reader.read_exact(&mut header).map_err(truncated)?;
+    if &header[..8] != MAGIC {
+        return Err(Error::InvalidArchive);
+    }
+    if header[8] != ARCHIVE_VERSION {
+        return Err(Error::UnsupportedVersion);
+    }
+    let mut count = [0u8; 8];
+    count.copy_from_slice(&header[9..17]);
+    let count = u64::from_le_bytes(count);
+    if count > max {
+        return Err(Error::TooLarge);
+    }
+    let digest = &header[17..];
+
+    let mut decoder = zstd::Decoder::new(reader)?.single_frame();
+    let mut hasher = Sha256::new();
+
+    let mut key = [0u8; 32];
+    decoder.read_exact(&mut key).map_err(truncated)?;
+    hasher.update(key);
+    let ek = EncryptionKey::from_compressed_unchecked_const(key).get()?;
+
+    // grow with the data actually read rather than trusting the declared count
+    let mut cts = Vec::with_capacity(count.min(1 << 16) as usize);
+    let mut bytes = [0u8; 64];
+    for _ in 0..count {
+        decoder.read_exact(&mut bytes).map_err(truncated)?;
+        hasher.update(bytes);
+        cts.push(CompressedCiphertext::from_bytes(&bytes).decompress()?);
+    }
+    if decoder.read(&mut [0u8; 1])? != 0 {
+        return Err(Error::InvalidArchive);
+    }
+
+    if hasher.finalize()[..] != digest[..] {
+        return Err(Error::VerificationFailed);
+    }
+    Ok((ek, cts))
+}
+
+fn truncated(err: std::io::Error) -> Error {
+    match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::InvalidArchive,
+        kind => Error::Io(kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+    use sha2::{Digest, Sha256};
+
+    use super::{export_compressed, import_compressed, import_compressed_with_limit, HEADER_LEN};
+    use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error, RistrettoPoint};
+
+    fn ciphertexts(n: usize, rng: &mut StdRng) -> (EncryptionKey, Vec<Ciphertext>) {
+        let ek = *DecryptionKey::new(rng).encryption_key();
+        let cts = (0..n)
+            .map(|_| ek.encrypt(RistrettoPoint::random(rng), rng))
+            .collect();
+        (ek, cts)
+    }
+
+    fn export(ek: &EncryptionKey, cts: &[Ciphertext]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        export_compressed(&mut archive, ek, cts).unwrap();
+        archive
+    }
+
+    // Test that archives of various sizes round trip, and that the header hash covers the
+    // decompressed payload.
+    #[test]
+    fn archive_round_trip() {
+        let mut rng = StdRng::seed_from_u64(482);
+        for n in [0, 1, 1000] {
+            let (ek, cts) = ciphertexts(n, &mut rng);
+            let archive = export(&ek, &cts);
+            assert_eq!(&archive[..8], b"RELGZCTS");
+            assert_eq!(import_compressed(&archive[..]), Ok((ek, cts.clone())));
+
+            let payload = zstd::decode_all(&archive[HEADER_LEN..]).unwrap();
+            assert_eq!(payload.len(), 32 + 64 * n);
+            assert_eq!(Sha256::digest(&payload)[..], archive[17..HEADER_LEN]);
+        }
+
+        // repeated data compresses away
+        let (ek, cts) = ciphertexts(1, &mut rng);
+        let same: Vec<_> = (0..1000).map(|_| cts[0]).collect();
+        assert!(export(&ek, &same).len() < 64 * 100);
+    }
+
+    // Test that a payload not matching the header hash is rejected, as are truncated and
+    // malformed archives.
+    #[test]
+    fn archive_checksum_mismatch() {
+        let mut rng = StdRng::seed_from_u64(482);
+        let (ek, cts) = ciphertexts(10, &mut rng);
+        let archive = export(&ek, &cts);
+
+        let mut tampered = archive.clone();
+        tampered[17] ^= 1;
+        assert_eq!(
+            import_compressed(&tampered[..]),
+            Err(Error::VerificationFailed)
+        );
+
+        // a well-formed payload under another archive's header
+        let (_, other) = ciphertexts(10, &mut rng);
+        let mut swapped = archive[..HEADER_LEN].to_vec();
+        swapped.extend_from_slice(&export(&ek, &other)[HEADER_LEN..]);
+        assert_eq!(
+            import_compressed(&swapped[..]),
+            Err(Error::VerificationFailed)
+        );
+
+        // fewer and more ciphertexts than declared
+        let mut short = archive[..HEADER_LEN].to_vec();
+        short.extend_from_slice(&export(&ek, &cts[..9])[HEADER_LEN..]);
+        assert_eq!(import_compressed(&short[..]), Err(Error::InvalidArchive));
+        let mut long = export(&ek, &cts[..9])[..HEADER_LEN].to_vec();
+        long.extend_from_slice(&archive[HEADER_LEN..]);
+        assert_eq!(import_compressed(&long[..]), Err(Error::InvalidArchive));
+
+        assert_eq!(
+            import_compressed(&archive[..HEADER_LEN - 1]),
+            Err(Error::InvalidArchive)
+        );
+        let mut magic = archive.clone();
+        magic[0] ^= 1;
+        assert_eq!(import_compressed(&magic[..]), Err(Error::InvalidArchive));
+        let mut version = archive;
+        version[8] = 2;
+        assert_eq!(
+            import_compressed(&version[..]),
+            Err(Error::UnsupportedVersion)
+        );
+    }
+
+    // Test that an archive declaring more ciphertexts than the limit is refused before anything
+    // is decompressed.
+    #[test]
+    fn archive_declared_length_limit() {
+        let mut rng = StdRng::seed_from_u64(482);
+        let (ek, cts) = ciphertexts(10, &mut rng);
+        let archive = export(&ek, &cts);
+
+        let mut bomb = archive.clone();
+        bomb[9..17].copy_from_slice(&u64::MAX.to_le_bytes());
+        bomb.truncate(HEADER_LEN);
+        assert_eq!(import_compressed(&bomb[..]), Err(Error::TooLarge));
+
+        assert_eq!(
+            import_compressed_with_limit(&archive[..], 9),
+            Err(Error::TooLarge)
+        );
+        assert_eq!(
+            import_compressed_with_limit(&archive[..], 10),
+            Ok((ek, cts))
+        );
+    }
+}
@@ -0,0 +1,451 @@
+// ASCII armor for keys in rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Keys are armored in the style of OpenPGP (RFC 4880, section 6.2):
+//
+//     -----BEGIN RUST-ELGAMAL PUBLIC KEY-----
+//     Version: 1
+//
+//     <base64 of the 32-byte key, in lines of at most 64 characters>
+//     =<base64 of the 24-bit CRC of the key>
+//     -----END RUST-ELGAMAL PUBLIC KEY-----
+//
+// with SECRET KEY in place of PUBLIC KEY for decryption keys. Parsers ignore surrounding blank
+// lines and trailing whitespace (including the \r of CRLF line endings), and accept the base64
+// body wrapped at any width. Only version 1 exists.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use crate::{DecryptionKey, EncryptionKey, Error};
+
+/// The label of armored encryption keys.
+const PUBLIC_LABEL: &str = "RUST-ELGAMAL PUBLIC KEY";
+/// The label of armored decryption keys.
+const SECRET_LABEL: &str = "RUST-ELGAMAL SECRET KEY";
+
+const VERSION: &str = "1";
+const LINE_LEN: usize = 64;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl EncryptionKey {
+    /// Encode the encryption key as an ASCII-armored block, labelled
+    /// `-----BEGIN RUST-ELGAMAL PUBLIC KEY-----`, for pasting into email or storing as text.
+    ///
+    /// The body is the base64 of [to_bytes](EncryptionKey::to_bytes), followed by a CRC-24
+    /// checksum as in OpenPGP armor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, EncryptionKey};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let armored = enc_key.to_armored_string();
+    /// assert!(armored.starts_with("-----BEGIN RUST-ELGAMAL PUBLIC KEY-----\n"));
+    /// assert_eq!(&EncryptionKey::from_armored_string(&armored).unwrap(), enc_key);
+    /// ```
+    pub fn to_armored_string(&self) -> String {
+        armor(PUBLIC_LABEL, &self.to_bytes())
+    }
+
+    /// Decode an encryption key from an ASCII-armored block made by
+    /// [to_armored_string](EncryptionKey::to_armored_string).
+    ///
+    /// Fails with [Error::ArmorLabelMismatch] if the block holds a secret key,
+    /// [Error::InvalidChecksum] if the checksum does not match, and [Error::InvalidArmor] if the
+    /// block is otherwise malformed.
+    pub fn from_armored_string(s: &str) -> Result<EncryptionKey, Error> {
+        let bytes = dearmor(PUBLIC_LABEL, s)?;
+        <EncryptionKey>::from_bytes(&bytes)
+    }
+}
+
+impl DecryptionKey {
+    /// Encode the decryption key as an ASCII-armored block, labelled
+    /// `-----BEGIN RUST-ELGAMAL SECRET KEY-----`, in the format of
+    /// [EncryptionKey::to_armored_string].
+    ///
+    /// The result holds the secret in the clear: store it as carefully as the key itself.
+    pub fn to_armored_string(&self) -> String {
+        armor(SECRET_LABEL, &self.to_bytes())
+    }
+
+    /// Decode a decryption key from an ASCII-armored block made by
+    /// [to_armored_string](DecryptionKey::to_armored_string).
+    ///
+    /// Fails with [Error::ArmorLabelMismatch] if the block holds a public key, and otherwise as
+    /// [EncryptionKey::from_armored_string] does.
+    pub fn from_armored_string(s: &str) -> Result<DecryptionKey, Error> {
+        let bytes = dearmor(SECRET_LABEL, s)?;
+        <DecryptionKey>::from_bytes(&bytes)
+    }
+}
+
+fn armor(label: &str, bytes: &[u8; 32]) -> String {
+    let body = base64_encode(bytes);
+    let crc = crc24(bytes).to_be_bytes();
+
+    let mut out = String::new();
+    out.push_str("-----BEGIN ");
+    out.push_str(label);
+    out.push_str("-----\nVersion: ");
+    out.push_str(VERSION);
+    out.push_str("\n\n");
+    for line in body.as_bytes().chunks(LINE_LEN) {
+        // base64 output is ASCII, so every chunk is valid UTF-8
+        out.push_str(core::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push('=');
+    out.push_str(&base64_encode(&crc[1..]));
+    out.push_str("\n-----END ");
+    out.push_str(label);
+    out.push_str("-----\n");
+    out
+}
+
+// Parse an armored block with the expected label, returning the checked 32-byte body.
+fn dearmor(expected: &'static str, s: &str) -> Result<[u8; 32], Error> {
+    let mut lines = s
+        .lines()
+        .map(str::trim_end)
+        .skip_while(|line| line.is_empty());
+
+    let begin = lines.next().ok_or(Error::InvalidArmor)?;
+    let label = begin
+        .strip_prefix("-----BEGIN ")
+        .and_then(|rest| rest.strip_suffix("-----"))
+        .ok_or(Error::InvalidArmor)?;
+    check_label(expected, label)?;
+
+    let version = lines.next().ok_or(Error::InvalidArmor)?;
+    match version.strip_prefix("Version: ") {
+        Some(VERSION) => {}
+        Some(_) => return Err(Error::UnsupportedVersion),
+        None => return Err(Error::InvalidArmor),
+    }
+    if lines.next() != Some("") {
+        return Err(Error::InvalidArmor);
+    }
+
+    let mut body = String::new();
+    let checksum = loop {
+        let line = lines.next().ok_or(Error::InvalidArmor)?;
+        match line.strip_prefix('=') {
+            Some(checksum) => break checksum,
+            None => body.push_str(line),
+        }
+    };
+
+    let end = lines.next().ok_or(Error::InvalidArmor)?;
+    let end_label = end
+        .strip_prefix("-----END ")
+        .and_then(|rest| rest.strip_suffix("-----"))
+        .ok_or(Error::InvalidArmor)?;
+    if end_label != label || lines.any(|line| !line.is_empty()) {
+        return Err(Error::InvalidArmor);
+    }
+
+    let bytes = base64_decode(&body)?;
+    let bytes: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::LengthMismatch {
+            expected: 32,
+            got: bytes.len(),
+        })?;
+    let crc = base64_decode(checksum)?;
+    if crc.len() != 3 || crc[..] != crc24(&bytes).to_be_bytes()[1..] {
+        return Err(Error::InvalidChecksum);
+    }
+    Ok(bytes)
+}
+
+fn check_label(expected: &'static str, label: &str) -> Result<(), Error> {
+    let got = match label {
+        PUBLIC_LABEL => PUBLIC_LABEL,
+        SECRET_LABEL => SECRET_LABEL,
+        _ => return Err(Error::InvalidArmor),
+    };
+    if got != expected {
+        return Err(Error::ArmorLabelMismatch { expected, got });
+    }
+    Ok(())
+}
+
+// The CRC-24 of OpenPGP armor (RFC 4880, section 6.1).
+fn crc24(bytes: &[u8]) -> u32 {
+    const INIT: u32 = 0xb7_04ce;
+    const POLY: u32 = 0x186_4cfb;
+
+    let mut crc = INIT;
+    for &byte in bytes {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+    crc & 0xff_ffff
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Decode padded base64, rejecting stray characters and non-zero trailing bits so that every
+// encoding is canonical.
+fn base64_decode(s: &str) -> Result<Vec<u8>, Error> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return Err(Error::InvalidArmor);
+    }
+
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i + 1 == s.len() / 4;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(Error::InvalidArmor);
+        }
+
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64
+                .iter()
+                .position(|&b| b == c)
+                .ok_or(Error::InvalidArmor)?;
+            n = n << 6 | value as u32;
+        }
+        n <<= 6 * padding;
+        if n & ((1 << (8 * padding)) - 1) != 0 {
+            return Err(Error::InvalidArmor);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{base64_decode, base64_encode, crc24};
+    use crate::{DecryptionKey, EncryptionKey, Error, Scalar};
+
+    // Test that both kinds of key survive armoring, including with CRLF line endings, trailing
+    // whitespace, surrounding blank lines and the body wrapped at a different width.
+    #[test]
+    fn armor_round_trip() {
+        let mut rng = StdRng::seed_from_u64(512);
+
+        for _ in 0..20 {
+            let dk = DecryptionKey::new(&mut rng);
+            let ek = dk.encryption_key();
+
+            let public = ek.to_armored_string();
+            let secret = dk.to_armored_string();
+            assert!(public.starts_with("-----BEGIN RUST-ELGAMAL PUBLIC KEY-----\nVersion: 1\n\n"));
+            assert!(public.ends_with("-----END RUST-ELGAMAL PUBLIC KEY-----\n"));
+            assert!(secret.starts_with("-----BEGIN RUST-ELGAMAL SECRET KEY-----\nVersion: 1\n\n"));
+            assert_eq!(&EncryptionKey::from_armored_string(&public).unwrap(), ek);
+            assert_eq!(DecryptionKey::from_armored_string(&secret).unwrap(), dk);
+
+            let crlf: String = public
+                .lines()
+                .map(|line| line.to_owned() + " \t\r\n")
+                .collect();
+            assert_eq!(&EncryptionKey::from_armored_string(&crlf).unwrap(), ek);
+
+            let padded = alloc::format!("\n\n{}\n  \n", secret);
+            assert_eq!(DecryptionKey::from_armored_string(&padded).unwrap(), dk);
+
+            let lines: alloc::vec::Vec<&str> = secret.lines().collect();
+            let rewrapped = alloc::format!(
+                "{}\n{}\n\n{}\n{}\n{}\n{}\n",
+                lines[0],
+                lines[1],
+                &lines[3][..10],
+                &lines[3][10..],
+                lines[4],
+                lines[5]
+            );
+            assert_eq!(DecryptionKey::from_armored_string(&rewrapped).unwrap(), dk);
+        }
+    }
+
+    // Test the encoding against a fixed vector, so that it can never silently change.
+    #[test]
+    fn armor_known_vector() {
+        let dk = DecryptionKey::from(Scalar::ONE);
+        assert_eq!(
+            dk.encryption_key().to_armored_string(),
+            "-----BEGIN RUST-ELGAMAL PUBLIC KEY-----\n\
+             Version: 1\n\
+             \n\
+             4vKuCmq8TnGohKlhxQBRX1jjC2qlgt2NtqZZReCNLXY=\n\
+             =654r\n\
+             -----END RUST-ELGAMAL PUBLIC KEY-----\n"
+        );
+    }
+
+    // Test that each kind of key is refused by the other's parser, and that malformed blocks are
+    // refused with the matching error.
+    #[test]
+    fn armor_rejects_malformed() {
+        let mut rng = StdRng::seed_from_u64(512);
+        let dk = DecryptionKey::new(&mut rng);
+        let public = dk.encryption_key().to_armored_string();
+        let secret = dk.to_armored_string();
+
+        let err = EncryptionKey::from_armored_string(&secret).unwrap_err();
+        assert_eq!(
+            err,
+            Error::ArmorLabelMismatch {
+                expected: "RUST-ELGAMAL PUBLIC KEY",
+                got: "RUST-ELGAMAL SECRET KEY",
+            }
+        );
+        assert_eq!(
+            alloc::string::ToString::to_string(&err),
+            "expected a RUST-ELGAMAL PUBLIC KEY block, got a RUST-ELGAMAL SECRET KEY block"
+        );
+        assert_eq!(
+            DecryptionKey::from_armored_string(&public),
+            Err(Error::ArmorLabelMismatch {
+                expected: "RUST-ELGAMAL SECRET KEY",
+                got: "RUST-ELGAMAL PUBLIC KEY",
+            })
+        );
+
+        let cases = [
+            (String::new(), Error::InvalidArmor),
+            (
+                public.replace("PUBLIC KEY-----\nV", "KEY-----\nV"),
+                Error::InvalidArmor,
+            ),
+            (
+                public.replace("END RUST-ELGAMAL PUBLIC", "END RUST-ELGAMAL SECRET"),
+                Error::InvalidArmor,
+            ),
+            (
+                public.replace("Version: 1", "Version: 2"),
+                Error::UnsupportedVersion,
+            ),
+            (public.replace("Version: 1\n", ""), Error::InvalidArmor),
+            (
+                public.replace("Version: 1\n\n", "Version: 1\n"),
+                Error::InvalidArmor,
+            ),
+            (public.replace("\n=", "\n"), Error::InvalidArmor),
+            (
+                public.replace("-----END RUST-ELGAMAL PUBLIC KEY-----\n", ""),
+                Error::InvalidArmor,
+            ),
+            (public.clone() + "trailing\n", Error::InvalidArmor),
+            (public.replacen('=', "", 1), Error::InvalidArmor),
+            (public.replacen('=', "!", 1), Error::InvalidArmor),
+            (
+                public.replacen('=', "AAAA=", 1),
+                Error::LengthMismatch {
+                    expected: 32,
+                    got: 35,
+                },
+            ),
+        ];
+        for (armored, expected) in cases.iter() {
+            assert_eq!(EncryptionKey::from_armored_string(armored), Err(*expected));
+        }
+
+        // flip one character of the body, and of the checksum
+        let lines: alloc::vec::Vec<&str> = public.lines().collect();
+        let flip = |line: &str, i: usize| {
+            let mut line = String::from(line);
+            let c = if line.as_bytes()[i] == b'A' { "B" } else { "A" };
+            line.replace_range(i..i + 1, c);
+            line
+        };
+        let body = public.replace(lines[3], &flip(lines[3], 5));
+        assert_eq!(
+            EncryptionKey::from_armored_string(&body),
+            Err(Error::InvalidChecksum)
+        );
+        let checksum = public.replace(lines[4], &flip(lines[4], 2));
+        assert_eq!(
+            EncryptionKey::from_armored_string(&checksum),
+            Err(Error::InvalidChecksum)
+        );
+
+        // a zero secret is refused even with a valid checksum
+        let zero = super::armor(super::SECRET_LABEL, &[0u8; 32]);
+        assert_eq!(
+            DecryptionKey::from_armored_string(&zero),
+            Err(Error::InvalidScalar)
+        );
+    }
+
+    // Test the base64 and CRC-24 helpers against RFC 4648 vectors and the OpenPGP check value.
+    #[test]
+    fn armor_helpers() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(base64_encode(plain.as_bytes()), *encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+        }
+        // non-zero padding bits would make the encoding malleable
+        assert_eq!(base64_decode("Zh=="), Err(Error::InvalidArmor));
+        assert_eq!(base64_decode("Zm9="), Err(Error::InvalidArmor));
+        assert_eq!(base64_decode("Zg==Zg=="), Err(Error::InvalidArmor));
+        assert_eq!(base64_decode("Z==="), Err(Error::InvalidArmor));
+
+        assert_eq!(crc24(b""), 0xb704ce);
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+    }
+}
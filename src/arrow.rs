@@ -0,0 +1,348 @@
+// Apache Arrow columns for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ciphertexts and encryption keys as [Apache Arrow](https://arrow.apache.org/) columns.
+//!
+//! A ciphertext column is a `FixedSizeBinary(64)` array of [CompressedCiphertext] encodings, and
+//! a key column a `FixedSizeBinary(32)` array of compressed keys, so either can be written to
+//! Parquet as it is. Reading a column back checks every row. [ciphertext_field] and
+//! [with_key_fingerprint] describe a ciphertext column in a schema, recording the fingerprint of
+//! the key it is under as field metadata, for [check_key_fingerprint] to compare on the way in.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use arrow_array::RecordBatch;
+//! use arrow_schema::Schema;
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::arrow::{check_key_fingerprint, ciphertext_field, with_key_fingerprint};
+//! use rust_elgamal::{CiphertextColumns, DecryptionKey, RistrettoPoint};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let enc_key = dec_key.encryption_key();
+//!
+//! let m = RistrettoPoint::random(&mut rng);
+//! let cts: CiphertextColumns = (0..3).map(|_| enc_key.encrypt(m, &mut rng)).collect();
+//!
+//! let field = with_key_fingerprint(ciphertext_field("vote", false), enc_key);
+//! let schema = Arc::new(Schema::new(vec![field]));
+//! let batch = RecordBatch::try_new(schema, vec![Arc::new(cts.to_arrow())]).unwrap();
+//!
+//! // later, reading the batch back
+//! check_key_fingerprint(batch.schema().field(0), enc_key).unwrap();
+//! let column = batch.column(0).as_any().downcast_ref().unwrap();
+//! let decoded = CiphertextColumns::from_arrow(column).unwrap();
+//! assert_eq!(dec_key.decrypt(decoded.get(1).unwrap()), m);
+//! ```
+
+use std::string::String;
+use std::vec::Vec;
+
+use arrow_array::builder::FixedSizeBinaryBuilder;
+use arrow_array::{Array, FixedSizeBinaryArray};
+use arrow_schema::{DataType, Field};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::traits::IsIdentity;
+
+use crate::{CiphertextColumns, CompressedCiphertext, EncryptionKey, Error};
+
+/// The field metadata key under which [with_key_fingerprint] records the key fingerprint.
+pub const KEY_FINGERPRINT_METADATA: &str = "rust_elgamal.key_fingerprint";
+
+const CIPHERTEXT_LEN: usize = 64;
+const KEY_LEN: usize = 32;
+
+fn to_array<T: AsRef<[u8]>>(
+    len: usize,
+    width: usize,
+    rows: impl Iterator<Item = T>,
+) -> FixedSizeBinaryArray {
+    let mut builder = FixedSizeBinaryBuilder::with_capacity(len, width as i32);
+    for row in rows {
+        builder
+            .append_value(row)
+            .expect("rows have the column width");
+    }
+    builder.finish()
+}
+
+// The rows of a non-null FixedSizeBinary(width) column.
+fn rows(array: &FixedSizeBinaryArray, width: usize) -> Result<impl Iterator<Item = &[u8]>, Error> {
+    let got = array.value_length() as usize;
+    if got != width {
+        return Err(Error::LengthMismatch {
+            expected: width,
+            got,
+        });
+    }
+    if array.null_count() > 0 {
+        return Err(Error::InvalidParameters);
+    }
+    Ok(array.iter().flatten())
+}
+
+impl CiphertextColumns {
+    /// The ciphertexts as a `FixedSizeBinary(64)` array of their compressed encodings.
+    pub fn to_arrow(&self) -> FixedSizeBinaryArray {
+        to_array(
+            self.len(),
+            CIPHERTEXT_LEN,
+            self.compress_batch()
+                .iter()
+                .map(CompressedCiphertext::to_bytes),
+        )
+    }
+
+    /// Read ciphertexts from a `FixedSizeBinary(64)` array, as written by
+    /// [to_arrow](Self::to_arrow).
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if the column has another
+    /// width, with [Error::InvalidParameters](crate::Error::InvalidParameters) if it has nulls,
+    /// and with [Error::InvalidPoint](crate::Error::InvalidPoint) if any row is not a valid
+    /// ciphertext.
+    pub fn from_arrow(array: &FixedSizeBinaryArray) -> Result<CiphertextColumns, Error> {
+        let mut columns = CiphertextColumns::with_capacity(array.len());
+        for row in rows(array, CIPHERTEXT_LEN)? {
+            let mut bytes = [0u8; CIPHERTEXT_LEN];
+            bytes.copy_from_slice(row);
+            columns.push(CompressedCiphertext::from_bytes(&bytes).decompress()?);
+        }
+        Ok(columns)
+    }
+}
+
+/// The encryption keys as a `FixedSizeBinary(32)` array of their compressed encodings.
+pub fn encryption_keys_to_arrow(keys: &[EncryptionKey]) -> FixedSizeBinaryArray {
+    to_array(
+        keys.len(),
+        KEY_LEN,
+        keys.iter().map(|ek| ek.0.compress().to_bytes()),
+    )
+}
+
+/// Read encryption keys from a `FixedSizeBinary(32)` array, as written by
+/// [encryption_keys_to_arrow].
+///
+/// Fails as [CiphertextColumns::from_arrow] does, and with
+/// [Error::IdentityPoint](crate::Error::IdentityPoint) if any row is the identity.
+pub fn encryption_keys_from_arrow(
+    array: &FixedSizeBinaryArray,
+) -> Result<Vec<EncryptionKey>, Error> {
+    rows(array, KEY_LEN)?
+        .map(|row| {
+            let mut bytes = [0u8; KEY_LEN];
+            bytes.copy_from_slice(row);
+            let point = CompressedRistretto(bytes)
+                .decompress()
+                .ok_or(Error::InvalidPoint)?;
+            if point.is_identity() {
+                return Err(Error::IdentityPoint);
+            }
+            Ok(EncryptionKey(point))
+        })
+        .collect()
+}
+
+/// A field for a column of ciphertexts, of type `FixedSizeBinary(64)`.
+pub fn ciphertext_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::FixedSizeBinary(CIPHERTEXT_LEN as i32),
+        nullable,
+    )
+}
+
+/// Record the [fingerprint](EncryptionKey::fingerprint) of `key` in the metadata of `field`, as
+/// lowercase hex under [KEY_FINGERPRINT_METADATA].
+pub fn with_key_fingerprint(field: Field, key: &EncryptionKey) -> Field {
+    let mut metadata = field.metadata().clone();
+    metadata.insert(
+        String::from(KEY_FINGERPRINT_METADATA),
+        key.fingerprint()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    );
+    field.with_metadata(metadata)
+}
+
+/// The key fingerprint recorded in the metadata of `field`, if there is one.
+///
+/// Fails with [Error::InvalidEncoding](crate::Error::InvalidEncoding) if the recorded value is
+/// not 64 hex digits.
+pub fn key_fingerprint(field: &Field) -> Result<Option<[u8; 32]>, Error> {
+    let hex = match field.metadata().get(KEY_FINGERPRINT_METADATA) {
+        Some(hex) => hex.as_bytes(),
+        None => return Ok(None),
+    };
+    if hex.len() != 64 {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(hex.chunks(2)) {
+        let pair = core::str::from_utf8(pair).map_err(|_| Error::InvalidEncoding)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| Error::InvalidEncoding)?;
+    }
+    Ok(Some(out))
+}
+
+/// Check that `field` records the fingerprint of `key`.
+///
+/// Fails with [Error::KeyMismatch](crate::Error::KeyMismatch) if it records another key's, and
+/// with [Error::UnknownKey](crate::Error::UnknownKey) if it records none.
+pub fn check_key_fingerprint(field: &Field, key: &EncryptionKey) -> Result<(), Error> {
+    match key_fingerprint(field)? {
+        Some(fingerprint) if fingerprint == key.fingerprint() => Ok(()),
+        Some(_) => Err(Error::KeyMismatch),
+        None => Err(Error::UnknownKey),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::vec::Vec;
+
+    use arrow_array::{Array, FixedSizeBinaryArray, RecordBatch};
+    use arrow_schema::{Field, Schema};
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{
+        check_key_fingerprint, ciphertext_field, encryption_keys_from_arrow,
+        encryption_keys_to_arrow, key_fingerprint, with_key_fingerprint, KEY_FINGERPRINT_METADATA,
+    };
+    use crate::{CiphertextColumns, DecryptionKey, EncryptionKey, Error, Identity, RistrettoPoint};
+
+    fn column(batch: &RecordBatch, i: usize) -> &FixedSizeBinaryArray {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap()
+    }
+
+    // Test that ciphertext and key columns survive a trip through a record batch, fingerprint
+    // metadata included.
+    #[test]
+    fn arrow_record_batch_round_trip() {
+        let mut rng = StdRng::seed_from_u64(475);
+        let dks: Vec<_> = (0..5).map(|_| DecryptionKey::new(&mut rng)).collect();
+        let keys: Vec<EncryptionKey> = dks.iter().map(|dk| *dk.encryption_key()).collect();
+        let ms: Vec<_> = (0..5).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let cts: CiphertextColumns = ms.iter().map(|m| keys[0].encrypt(*m, &mut rng)).collect();
+
+        let schema = Schema::new(vec![
+            with_key_fingerprint(ciphertext_field("ct", false), &keys[0]),
+            Field::new("key", arrow_schema::DataType::FixedSizeBinary(32), false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(cts.to_arrow()),
+                Arc::new(encryption_keys_to_arrow(&keys)),
+            ],
+        )
+        .unwrap();
+        assert_eq!(batch.num_rows(), 5);
+
+        let schema = batch.schema();
+        assert_eq!(
+            key_fingerprint(schema.field(0)),
+            Ok(Some(keys[0].fingerprint()))
+        );
+        assert_eq!(check_key_fingerprint(schema.field(0), &keys[0]), Ok(()));
+        assert_eq!(
+            check_key_fingerprint(schema.field(0), &keys[1]),
+            Err(Error::KeyMismatch)
+        );
+        assert_eq!(
+            check_key_fingerprint(schema.field(1), &keys[0]),
+            Err(Error::UnknownKey)
+        );
+
+        let decoded = CiphertextColumns::from_arrow(column(&batch, 0)).unwrap();
+        assert_eq!(decoded, cts);
+        for (i, m) in ms.iter().enumerate() {
+            assert_eq!(dks[0].decrypt(decoded.get(i).unwrap()), *m);
+        }
+        assert_eq!(encryption_keys_from_arrow(column(&batch, 1)), Ok(keys));
+
+        let empty = CiphertextColumns::new().to_arrow();
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.value_length(), 64);
+        assert_eq!(
+            CiphertextColumns::from_arrow(&empty),
+            Ok(CiphertextColumns::new())
+        );
+    }
+
+    // Test that a column with a bad row, a null, or the wrong width is rejected.
+    #[test]
+    fn arrow_bad_rows() {
+        let mut rng = StdRng::seed_from_u64(475);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let cts: CiphertextColumns = (0..3)
+            .map(|_| ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+            .collect();
+
+        let mut rows: Vec<Vec<u8>> = cts
+            .to_arrow()
+            .iter()
+            .map(|row| row.unwrap().to_vec())
+            .collect();
+        // the top bit of an encoded point is always clear
+        rows[1][63] |= 0x80;
+        let bad = FixedSizeBinaryArray::try_from_iter(rows.iter()).unwrap();
+        assert_eq!(
+            CiphertextColumns::from_arrow(&bad),
+            Err(Error::InvalidPoint)
+        );
+
+        let nulls = FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+            vec![Some(rows[0].clone()), None].into_iter(),
+            64,
+        )
+        .unwrap();
+        assert_eq!(
+            CiphertextColumns::from_arrow(&nulls),
+            Err(Error::InvalidParameters)
+        );
+
+        let keys = encryption_keys_to_arrow(&[*ek]);
+        assert_eq!(
+            CiphertextColumns::from_arrow(&keys),
+            Err(Error::LengthMismatch {
+                expected: 64,
+                got: 32
+            })
+        );
+        let identity = encryption_keys_to_arrow(&[EncryptionKey::from(RistrettoPoint::identity())]);
+        assert_eq!(
+            encryption_keys_from_arrow(&identity),
+            Err(Error::IdentityPoint)
+        );
+
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(KEY_FINGERPRINT_METADATA.into(), "not hex".into());
+        let field = ciphertext_field("ct", false).with_metadata(metadata);
+        assert_eq!(key_fingerprint(&field), Err(Error::InvalidEncoding));
+    }
+}
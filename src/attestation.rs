@@ -0,0 +1,277 @@
+// Attestations of encryption keys by ed25519 identities for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{EncryptionKey, Error};
+
+const DOMAIN: &[u8] = b"rust-elgamal key attestation v1";
+
+/// A signed statement by an ed25519 identity that an [EncryptionKey] belongs to it, for use in
+/// `context`, until `expiry`.
+///
+/// The signature covers [payload](Self::payload), a canonical encoding of the key, context and
+/// expiry. Times are whatever the application measures them in, typically seconds since the Unix
+/// epoch; the attestation is valid up to and including `expiry`.
+///
+/// # Example
+///
+/// ```rust
+/// use ed25519_dalek::SigningKey;
+/// use rand::rngs::StdRng;
+/// use rand::{Rng, SeedableRng};
+/// use rust_elgamal::{DecryptionKey, KeyAttestation};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let identity = SigningKey::from_bytes(&rng.gen());
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let attestation =
+///     KeyAttestation::sign(&identity, dec_key.encryption_key(), b"mail", 1_700_000_000);
+/// let key = attestation
+///     .verify_in_context(&identity.verifying_key(), b"mail", 1_600_000_000)
+///     .unwrap();
+/// assert_eq!(&key, dec_key.encryption_key());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct KeyAttestation {
+    key: EncryptionKey,
+    context: Vec<u8>,
+    expiry: u64,
+    signature: Signature,
+}
+
+impl KeyAttestation {
+    /// Sign a statement that `key` is valid for `context` until `expiry`.
+    pub fn sign(
+        signer: &SigningKey,
+        key: &EncryptionKey,
+        context: &[u8],
+        expiry: u64,
+    ) -> KeyAttestation {
+        let signature = signer.sign(&payload(key, context, expiry));
+        KeyAttestation {
+            key: *key,
+            context: context.to_vec(),
+            expiry,
+            signature,
+        }
+    }
+
+    /// Check the signature against `verifier` and the expiry against `now`, returning the attested
+    /// key.
+    ///
+    /// Fails with [Error::Expired](crate::Error::Expired) if `now` is after the expiry, and with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if the signature does not
+    /// verify. The context is not checked; use [verify_in_context](Self::verify_in_context) to
+    /// check it too.
+    pub fn verify(&self, verifier: &VerifyingKey, now: u64) -> Result<EncryptionKey, Error> {
+        verifier
+            .verify_strict(&self.payload(), &self.signature)
+            .map_err(|_| Error::VerificationFailed)?;
+        if now > self.expiry {
+            return Err(Error::Expired);
+        }
+        Ok(self.key)
+    }
+
+    /// As [verify](Self::verify), also failing with
+    /// [Error::ContextMismatch](crate::Error::ContextMismatch) if the attestation was made for a
+    /// context other than `context`.
+    pub fn verify_in_context(
+        &self,
+        verifier: &VerifyingKey,
+        context: &[u8],
+        now: u64,
+    ) -> Result<EncryptionKey, Error> {
+        let key = self.verify(verifier, now)?;
+        if self.context != context {
+            return Err(Error::ContextMismatch);
+        }
+        Ok(key)
+    }
+
+    /// The attested key, unchecked.
+    pub fn key(&self) -> &EncryptionKey {
+        &self.key
+    }
+
+    /// The context the key is attested for.
+    pub fn context(&self) -> &[u8] {
+        &self.context
+    }
+
+    /// The last time at which the attestation is valid.
+    pub fn expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    /// The signature over [payload](Self::payload).
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The signed bytes: the length-prefixed domain separator, the compressed key, the expiry as
+    /// a little-endian `u64`, and the length-prefixed context, with lengths as little-endian
+    /// `u64`s.
+    pub fn payload(&self) -> Vec<u8> {
+        payload(&self.key, &self.context, self.expiry)
+    }
+}
+
+fn payload(key: &EncryptionKey, context: &[u8], expiry: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + DOMAIN.len() + 32 + 8 + 8 + context.len());
+    out.extend_from_slice(&(DOMAIN.len() as u64).to_le_bytes());
+    out.extend_from_slice(DOMAIN);
+    out.extend_from_slice(key.0.compress().as_bytes());
+    out.extend_from_slice(&expiry.to_le_bytes());
+    out.extend_from_slice(&(context.len() as u64).to_le_bytes());
+    out.extend_from_slice(context);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, EncryptionKey, Error, KeyAttestation};
+
+    const EXPIRY: u64 = 1_700_000_000;
+
+    fn attestation() -> (SigningKey, EncryptionKey, KeyAttestation) {
+        let mut rng = StdRng::seed_from_u64(463);
+        let identity = SigningKey::from_bytes(&rng.gen());
+        let key = *DecryptionKey::new(&mut rng).encryption_key();
+        let attestation = KeyAttestation::sign(&identity, &key, b"mail", EXPIRY);
+        (identity, key, attestation)
+    }
+
+    // Test that an attestation verifies up to and including its expiry, and not after.
+    #[test]
+    fn attestation_expiry() {
+        let (identity, key, attestation) = attestation();
+        let verifier = identity.verifying_key();
+        assert_eq!(attestation.verify(&verifier, 0), Ok(key));
+        assert_eq!(attestation.verify(&verifier, EXPIRY), Ok(key));
+        assert_eq!(
+            attestation.verify(&verifier, EXPIRY + 1),
+            Err(Error::Expired)
+        );
+        assert_eq!(
+            attestation.verify_in_context(&verifier, b"mail", u64::MAX),
+            Err(Error::Expired)
+        );
+    }
+
+    // Test that the context is checked on request.
+    #[test]
+    fn attestation_context() {
+        let (identity, key, attestation) = attestation();
+        let verifier = identity.verifying_key();
+        assert_eq!(attestation.context(), b"mail");
+        assert_eq!(
+            attestation.verify_in_context(&verifier, b"mail", EXPIRY),
+            Ok(key)
+        );
+        assert_eq!(
+            attestation.verify_in_context(&verifier, b"chat", EXPIRY),
+            Err(Error::ContextMismatch)
+        );
+        assert_eq!(
+            attestation.verify_in_context(&verifier, b"", EXPIRY),
+            Err(Error::ContextMismatch)
+        );
+    }
+
+    // Test that changing any signed field, the signature or the verifier fails verification.
+    #[test]
+    fn attestation_tampering() {
+        let (identity, _, attestation) = attestation();
+        let verifier = identity.verifying_key();
+        let mut rng = StdRng::seed_from_u64(463);
+
+        let mut tampered = attestation.clone();
+        tampered.key = *DecryptionKey::new(&mut rng).encryption_key();
+        assert_eq!(
+            tampered.verify(&verifier, 0),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut tampered = attestation.clone();
+        tampered.context = b"chat".to_vec();
+        assert_eq!(
+            tampered.verify(&verifier, 0),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut tampered = attestation.clone();
+        tampered.expiry += 1;
+        assert_eq!(
+            tampered.verify(&verifier, 0),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut bytes = attestation.signature.to_bytes();
+        for i in 0..bytes.len() * 8 {
+            bytes[i / 8] ^= 1 << (i % 8);
+            let mut tampered = attestation.clone();
+            tampered.signature = bytes.into();
+            assert_eq!(
+                tampered.verify(&verifier, 0),
+                Err(Error::VerificationFailed)
+            );
+            bytes[i / 8] ^= 1 << (i % 8);
+        }
+
+        let other = SigningKey::from_bytes(&rng.gen()).verifying_key();
+        assert_eq!(
+            attestation.verify(&other, 0),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that the signed payload is pinned.
+    #[test]
+    fn attestation_payload() {
+        let (_, key, attestation) = attestation();
+        let payload = attestation.payload();
+        assert_eq!(payload[..8], 31u64.to_le_bytes());
+        assert_eq!(&payload[8..39], b"rust-elgamal key attestation v1");
+        assert_eq!(payload[39..71], *key.0.compress().as_bytes());
+        assert_eq!(payload[71..79], EXPIRY.to_le_bytes());
+        assert_eq!(payload[79..87], 4u64.to_le_bytes());
+        assert_eq!(&payload[87..], b"mail");
+    }
+
+    // Test that attestations survive serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_attestation() {
+        let (identity, key, attestation) = attestation();
+        let encoded = bincode::serialize(&attestation).unwrap();
+        let decoded: KeyAttestation = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, attestation);
+        assert_eq!(decoded.verify(&identity.verifying_key(), EXPIRY), Ok(key));
+    }
+}
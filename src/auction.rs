@@ -0,0 +1,251 @@
+// Sealed-bid auctions for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! First-price sealed-bid auctions built on commitments.
+//!
+//! In the first phase, each bidder commits to its bid with [Bid::commit] and sends the
+//! [Commitment] to the auctioneer, who records it in an [AuctionBook]. Once bidding has closed,
+//! bidders send their [Open]ings, and [AuctionBook::resolve] checks every opening against its
+//! commitment and picks the highest bids. Bidders who never open, or whose opening does not match
+//! their commitment, are reported rather than ignored, so the auctioneer can penalise them.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Commitment, Error, Open};
+
+/// Identifies a bidder in an [AuctionBook].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BidderId(pub u64);
+
+/// Commits to bids.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Bid;
+
+impl Bid {
+    /// Commit to a bid of `amount`. The commitment is sent to the auctioneer straight away, and
+    /// the opening once bidding has closed.
+    pub fn commit<R: RngCore + CryptoRng>(amount: u64, rng: &mut R) -> (Open, Commitment) {
+        Commitment::commit(Scalar::from(amount), rng)
+    }
+}
+
+/// The outcome of an auction, as computed by [AuctionBook::resolve].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct AuctionResult {
+    /// The highest valid bid, or `None` if there were no valid bids.
+    pub winning_bid: Option<u64>,
+    /// The bidders who made the highest valid bid; more than one if there was a tie.
+    pub winners: Vec<BidderId>,
+    /// The other bidders with valid bids, and their bids.
+    pub losers: Vec<(BidderId, u64)>,
+    /// The bidders who committed but did not open, or opened to something other than their
+    /// commitment.
+    pub failed: Vec<BidderId>,
+}
+
+/// The auctioneer's record of commitments and openings.
+///
+/// The book holds the bidders' opens, so it is serialisable only with the `serde-secret` feature.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+pub struct AuctionBook {
+    commitments: BTreeMap<BidderId, Commitment>,
+    openings: BTreeMap<BidderId, Open>,
+}
+
+impl AuctionBook {
+    /// An empty auction book.
+    pub fn new() -> AuctionBook {
+        AuctionBook::default()
+    }
+
+    /// Record `bidder`'s commitment, during the first phase.
+    ///
+    /// Fails with [Error::InvalidParameters] if `bidder` has already committed, or if openings
+    /// are already being collected.
+    pub fn add_commitment(
+        &mut self,
+        bidder: BidderId,
+        commitment: Commitment,
+    ) -> Result<(), Error> {
+        if !self.openings.is_empty() || self.commitments.contains_key(&bidder) {
+            return Err(Error::InvalidParameters);
+        }
+        self.commitments.insert(bidder, commitment);
+        Ok(())
+    }
+
+    /// Record `bidder`'s opening, during the second phase. The opening is not checked until
+    /// [resolve](Self::resolve).
+    ///
+    /// Fails with [Error::InvalidParameters] if `bidder` did not commit, or has already opened.
+    pub fn add_opening(&mut self, bidder: BidderId, open: Open) -> Result<(), Error> {
+        if !self.commitments.contains_key(&bidder) || self.openings.contains_key(&bidder) {
+            return Err(Error::InvalidParameters);
+        }
+        self.openings.insert(bidder, open);
+        Ok(())
+    }
+
+    /// The number of bidders who have committed.
+    pub fn num_bidders(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Check every opening and determine the winners.
+    pub fn resolve(&self) -> AuctionResult {
+        let mut valid = Vec::new();
+        let mut failed = Vec::new();
+        for (bidder, commitment) in &self.commitments {
+            let amount = self
+                .openings
+                .get(bidder)
+                .filter(|open| commitment.verify(open))
+                .and_then(Open::message_u64);
+            match amount {
+                Some(amount) => valid.push((*bidder, amount)),
+                None => failed.push(*bidder),
+            }
+        }
+
+        let winning_bid = valid.iter().map(|(_, amount)| *amount).max();
+        let (winners, losers): (Vec<_>, Vec<_>) = valid
+            .into_iter()
+            .partition(|(_, amount)| Some(*amount) == winning_bid);
+        AuctionResult {
+            winning_bid,
+            winners: winners.into_iter().map(|(bidder, _)| bidder).collect(),
+            losers,
+            failed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::auction::{AuctionBook, Bid, BidderId};
+    use crate::Error;
+
+    // Run an auction with a clear winner, checking the phase rules along the way.
+    #[test]
+    fn auction_winner() {
+        let mut rng = StdRng::from_entropy();
+        let mut book = AuctionBook::new();
+
+        let bids = [(BidderId(1), 100), (BidderId(2), 250), (BidderId(3), 75)];
+        let openings: Vec<_> = bids
+            .iter()
+            .map(|(bidder, amount)| {
+                let (open, commitment) = Bid::commit(*amount, &mut rng);
+                book.add_commitment(*bidder, commitment).unwrap();
+                (*bidder, open)
+            })
+            .collect();
+        assert_eq!(book.num_bidders(), 3);
+
+        let (_, commitment) = Bid::commit(1, &mut rng);
+        assert_eq!(
+            book.add_commitment(BidderId(1), commitment),
+            Err(Error::InvalidParameters)
+        );
+
+        for (bidder, open) in &openings {
+            book.add_opening(*bidder, open.clone()).unwrap();
+        }
+        assert_eq!(
+            book.add_opening(BidderId(1), openings[0].1.clone()),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            book.add_opening(BidderId(9), openings[0].1.clone()),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            book.add_commitment(BidderId(4), commitment),
+            Err(Error::InvalidParameters)
+        );
+
+        let result = book.resolve();
+        assert_eq!(result.winning_bid, Some(250));
+        assert_eq!(result.winners, vec![BidderId(2)]);
+        assert_eq!(result.losers, vec![(BidderId(1), 100), (BidderId(3), 75)]);
+        assert!(result.failed.is_empty());
+    }
+
+    // Test that tied bids all win.
+    #[test]
+    fn auction_tie() {
+        let mut rng = StdRng::from_entropy();
+        let mut book = AuctionBook::new();
+
+        let bids = [(BidderId(1), 300), (BidderId(2), 200), (BidderId(3), 300)];
+        let mut openings = Vec::new();
+        for (bidder, amount) in bids {
+            let (open, commitment) = Bid::commit(amount, &mut rng);
+            book.add_commitment(bidder, commitment).unwrap();
+            openings.push((bidder, open));
+        }
+        for (bidder, open) in openings {
+            book.add_opening(bidder, open).unwrap();
+        }
+
+        let result = book.resolve();
+        assert_eq!(result.winning_bid, Some(300));
+        assert_eq!(result.winners, vec![BidderId(1), BidderId(3)]);
+        assert_eq!(result.losers, vec![(BidderId(2), 200)]);
+    }
+
+    // Test that invalid and missing openings are reported, and do not win.
+    #[test]
+    fn auction_failed_openings() {
+        let mut rng = StdRng::from_entropy();
+        let mut book = AuctionBook::new();
+
+        let (open1, commitment1) = Bid::commit(100, &mut rng);
+        let (_, commitment2) = Bid::commit(500, &mut rng);
+        let (_, commitment3) = Bid::commit(900, &mut rng);
+        let (forged, _) = Bid::commit(1000, &mut rng);
+        book.add_commitment(BidderId(1), commitment1).unwrap();
+        book.add_commitment(BidderId(2), commitment2).unwrap();
+        book.add_commitment(BidderId(3), commitment3).unwrap();
+
+        book.add_opening(BidderId(1), open1).unwrap();
+        // bidder 2 opens to a bid it did not commit to; bidder 3 never opens
+        book.add_opening(BidderId(2), forged).unwrap();
+
+        let result = book.resolve();
+        assert_eq!(result.winning_bid, Some(100));
+        assert_eq!(result.winners, vec![BidderId(1)]);
+        assert!(result.losers.is_empty());
+        assert_eq!(result.failed, vec![BidderId(2), BidderId(3)]);
+
+        assert_eq!(AuctionBook::new().resolve().winning_bid, None);
+    }
+}
@@ -0,0 +1,288 @@
+// Anonymous veto networks for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The anonymous veto network (AV-net) of Hao and Zieliński: a poll in which anyone may object,
+//! and everyone learns whether somebody did, but not who.
+//!
+//! Participant `i` of `n` picks a secret `x_i` and broadcasts `X_i = x_i G` with a proof of
+//! possession ([Round1]). From the broadcasts, everyone computes their masking key
+//! `Y_i = sum_{j < i} X_j - sum_{j > i} X_j`, and broadcasts `c_i Y_i` ([Round2]), where `c_i` is
+//! `x_i` to stay silent and a fresh random scalar to veto. The masking keys are arranged so that
+//! `sum_i x_i Y_i` is the identity, so [tally] sees the identity exactly when nobody vetoed.
+//!
+//! As in the paper, this assumes authenticated broadcast channels and honest-but-curious
+//! participants in round 2; a participant can always veto, but cannot tell who else did.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::avnet::{self, Participant};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let (participants, round1): (Vec<_>, Vec<_>) =
+//!     (0..3).map(|i| Participant::new(i, &mut rng)).unzip();
+//!
+//! let round2: Vec<_> = participants
+//!     .iter()
+//!     .map(|p| p.contribute(&round1, p.index() == 1, &mut rng).unwrap())
+//!     .collect();
+//! assert!(avnet::tally(&round2));
+//! ```
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_POINT, GENERATOR_TABLE};
+
+const DOMAIN: &[u8] = b"rust-elgamal avnet";
+
+/// A participant's secret state between the two rounds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Participant {
+    index: u32,
+    secret: DecryptionKey,
+}
+
+/// A Schnorr proof that the sender of a [Round1] message knows the secret of its key, bound to
+/// the sender's index.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct PossessionProof {
+    pub(crate) c: Scalar,
+    pub(crate) s: Scalar,
+}
+
+/// Participant `index`'s round-1 broadcast: its public key and a proof of possession.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Round1 {
+    pub index: u32,
+    pub key: EncryptionKey,
+    pub proof: PossessionProof,
+}
+
+/// Participant `index`'s round-2 broadcast: its masking key times its secret, or times a random
+/// scalar to veto.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Round2 {
+    pub index: u32,
+    pub contribution: RistrettoPoint,
+}
+
+impl Participant {
+    /// Join the poll as participant `index` (counting from zero), returning the secret state
+    /// and the round-1 message to broadcast.
+    pub fn new<R: RngCore + CryptoRng>(index: u32, rng: &mut R) -> (Participant, Round1) {
+        let secret = DecryptionKey::new(rng);
+        let key = *secret.encryption_key();
+
+        let k = Scalar::random(rng);
+        let c = challenge(index, &key.0, &(&k * GENERATOR_TABLE));
+        let proof = PossessionProof {
+            c,
+            s: k + c * secret.secret,
+        };
+        (Participant { index, secret }, Round1 { index, key, proof })
+    }
+
+    /// The participant's index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Compute the round-2 message from everyone's round-1 messages: silent if `veto` is
+    /// false, an objection if it is true.
+    ///
+    /// Fails as [masking_key] does.
+    pub fn contribute<R: RngCore + CryptoRng>(
+        &self,
+        round1: &[Round1],
+        veto: bool,
+        rng: &mut R,
+    ) -> Result<Round2, Error> {
+        let y = masking_key(round1, self.index)?;
+        if round1[self.index as usize].key != *self.secret.encryption_key() {
+            return Err(Error::KeyMismatch);
+        }
+        let c = if veto {
+            Scalar::random(rng)
+        } else {
+            self.secret.secret
+        };
+        Ok(Round2 {
+            index: self.index,
+            contribution: y * c,
+        })
+    }
+}
+
+impl Round1 {
+    /// Verify the proof of possession.
+    ///
+    /// Fails with [Error::IdentityPoint](crate::Error::IdentityPoint) if the key is the identity,
+    /// and with [Error::VerificationFailed](crate::Error::VerificationFailed) if the proof does
+    /// not hold.
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.key.0.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+        let a = RistrettoPoint::vartime_double_scalar_mul_basepoint(
+            &-self.proof.c,
+            &self.key.0,
+            &self.proof.s,
+        );
+        if challenge(self.index, &self.key.0, &a) != self.proof.c {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Participant `index`'s masking key `Y_i = sum_{j < i} X_j - sum_{j > i} X_j`, from everyone's
+/// round-1 messages in order of index.
+///
+/// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) if the messages are
+/// not numbered `0, 1, ...` in order or `index` is not among them, and as [Round1::verify] does if
+/// a proof of possession does not verify.
+pub fn masking_key(round1: &[Round1], index: u32) -> Result<RistrettoPoint, Error> {
+    if index as usize >= round1.len() {
+        return Err(Error::InvalidParameters);
+    }
+    let mut y = RistrettoPoint::identity();
+    for (j, msg) in round1.iter().enumerate() {
+        if msg.index as usize != j {
+            return Err(Error::InvalidParameters);
+        }
+        msg.verify()?;
+        match j.cmp(&(index as usize)) {
+            core::cmp::Ordering::Less => y += msg.key.0,
+            core::cmp::Ordering::Greater => y -= msg.key.0,
+            core::cmp::Ordering::Equal => {}
+        }
+    }
+    Ok(y)
+}
+
+/// Whether anyone vetoed: the round-2 contributions sum to the identity exactly when nobody did
+/// (except with negligible probability).
+pub fn tally(round2: &[Round2]) -> bool {
+    let sum: RistrettoPoint = round2.iter().map(|msg| msg.contribution).sum();
+    !sum.is_identity()
+}
+
+fn challenge(index: u32, key: &RistrettoPoint, a: &RistrettoPoint) -> Scalar {
+    Scalar::from_hash(
+        Sha512::new()
+            .chain_update((DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(DOMAIN)
+            .chain_update(index.to_le_bytes())
+            .chain_update(GENERATOR_POINT.compress().as_bytes())
+            .chain_update(key.compress().as_bytes())
+            .chain_update(a.compress().as_bytes()),
+    )
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::avnet::{self, masking_key, Participant, Round1};
+    use crate::{Error, Identity, RistrettoPoint};
+
+    fn poll(vetoes: &[u32]) -> bool {
+        let mut rng = StdRng::from_entropy();
+        let (participants, round1): (Vec<_>, Vec<_>) =
+            (0..5).map(|i| Participant::new(i, &mut rng)).unzip();
+        let round2: Vec<_> = participants
+            .iter()
+            .map(|p| {
+                p.contribute(&round1, vetoes.contains(&p.index()), &mut rng)
+                    .unwrap()
+            })
+            .collect();
+        avnet::tally(&round2)
+    }
+
+    // Test five participants with no veto, one veto and two simultaneous vetoes.
+    #[test]
+    fn avnet_poll() {
+        assert!(!poll(&[]));
+        for i in 0..5 {
+            assert!(poll(&[i]));
+        }
+        assert!(poll(&[1, 3]));
+    }
+
+    // Test that the masking keys cancel out and that bad round-1 messages are rejected.
+    #[test]
+    fn avnet_round1() {
+        let mut rng = StdRng::from_entropy();
+        let (participants, mut round1): (Vec<_>, Vec<Round1>) =
+            (0..4).map(|i| Participant::new(i, &mut rng)).unzip();
+        let sum: RistrettoPoint = participants
+            .iter()
+            .map(|p| masking_key(&round1, p.index()).unwrap() * p.secret.secret)
+            .sum();
+        assert_eq!(sum, RistrettoPoint::identity());
+
+        assert_eq!(masking_key(&round1, 4), Err(Error::InvalidParameters));
+        assert_eq!(
+            participants[0].contribute(&round1[1..], false, &mut rng),
+            Err(Error::InvalidParameters)
+        );
+
+        // a proof replayed under another index does not verify
+        let mut replayed = round1[2];
+        replayed.index = 3;
+        assert_eq!(replayed.verify(), Err(Error::VerificationFailed));
+
+        round1.swap(2, 3);
+        assert_eq!(masking_key(&round1, 0), Err(Error::InvalidParameters));
+    }
+
+    // Test that round messages survive serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_avnet() {
+        let mut rng = StdRng::from_entropy();
+        let (participants, round1): (Vec<_>, Vec<_>) =
+            (0..3).map(|i| Participant::new(i, &mut rng)).unzip();
+        let encoded = bincode::serialize(&round1).unwrap();
+        let decoded: Vec<Round1> = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, round1);
+
+        let round2 = participants[0]
+            .contribute(&decoded, false, &mut rng)
+            .unwrap();
+        let encoded = bincode::serialize(&round2).unwrap();
+        assert_eq!(
+            bincode::deserialize::<avnet::Round2>(&encoded).unwrap(),
+            round2
+        );
+    }
+}
@@ -0,0 +1,258 @@
+// Group backend abstraction for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::Formatter;
+
+#[cfg(feature = "alloc")]
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+#[cfg(feature = "alloc")]
+use curve25519_dalek::traits::MultiscalarMul;
+use ff::PrimeField;
+use group::{Group, GroupEncoding};
+
+/// A prime-order group in which the ElGamal scheme of this crate can be instantiated.
+///
+/// Every type in this crate is generic over the group, defaulting to ristretto255
+/// ([RistrettoPoint](crate::RistrettoPoint)), so `Ciphertext` means `Ciphertext<RistrettoPoint>`.
+/// Any group implementing the [group](https://docs.rs/group/) traits can be used by implementing
+/// this trait for it; the provided methods only need to be overridden where the backend has a
+/// faster way of computing them (such as a precomputed table of multiples of the generator). The
+/// group's scalar type must also implement [ScalarOperand], which takes one line with
+/// [to_scalar](ScalarOperand::to_scalar) returning `self`, and, with the `zeroize` feature,
+/// [SecretScalar].
+pub trait ElGamalGroup:
+    Group<Scalar: ScalarOperand<<Self as Group>::Scalar> + SecretScalar> + GroupEncoding
+{
+    /// Multiply the group generator by `s`.
+    fn mul_generator(s: &Self::Scalar) -> Self {
+        Self::generator() * s
+    }
+
+    /// Compute `a * G + b * p`, where `G` is the group generator.
+    fn mul_generator_add(a: &Self::Scalar, b: &Self::Scalar, p: &Self) -> Self {
+        Self::mul_generator(a) + *p * b
+    }
+
+    /// Format the point for `Debug` output.
+    fn fmt_point(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.to_bytes().as_ref())
+    }
+}
+
+impl ElGamalGroup for RistrettoPoint {
+    fn mul_generator(s: &Scalar) -> Self {
+        s * RISTRETTO_BASEPOINT_TABLE
+    }
+
+    fn mul_generator_add(a: &Scalar, b: &Scalar, p: &Self) -> Self {
+        // multiscalar multiplication needs an allocator, so fall back to the basepoint table
+        #[cfg(feature = "alloc")]
+        return RistrettoPoint::multiscalar_mul([a, b], [&RISTRETTO_BASEPOINT_POINT, p]);
+        #[cfg(not(feature = "alloc"))]
+        return a * RISTRETTO_BASEPOINT_TABLE + p * b;
+    }
+
+    fn fmt_point(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.compress())
+    }
+}
+
+// The unit tests below always run against secp256k1 as a second backend.
+#[cfg(any(test, feature = "k256"))]
+impl ElGamalGroup for k256::ProjectivePoint {}
+
+/// A scalar type that can hold secrets, such as the secret of a
+/// [DecryptionKey](crate::DecryptionKey) or an [Open](crate::Open).
+///
+/// With the `zeroize` feature these are the scalars that implement
+/// [Zeroize](zeroize::Zeroize), so that secrets are wiped on drop; the scalars of every backend in
+/// this crate do. Without it, every type is a `SecretScalar`.
+#[cfg(feature = "zeroize")]
+pub trait SecretScalar: zeroize::Zeroize {}
+
+#[cfg(feature = "zeroize")]
+impl<T: zeroize::Zeroize> SecretScalar for T {}
+
+/// A scalar type that can hold secrets, such as the secret of a
+/// [DecryptionKey](crate::DecryptionKey) or an [Open](crate::Open).
+///
+/// With the `zeroize` feature these are the scalars that implement `Zeroize`, so that secrets are
+/// wiped on drop; the scalars of every backend in this crate do. Without it, every type is a
+/// `SecretScalar`.
+#[cfg(not(feature = "zeroize"))]
+pub trait SecretScalar {}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> SecretScalar for T {}
+
+/// A right-hand side for multiplying a [Ciphertext](crate::Ciphertext),
+/// [Commitment](crate::Commitment) or [Open](crate::Open) with scalar field `F`: a scalar, by
+/// value or by reference, or a `u64` or `u32`.
+///
+/// Multiplying by an integer `n` means multiplying by `F::from(n)`, but small integers are
+/// multiplied by double-and-add on the points, so that weighting by `2u64` costs a point addition
+/// rather than a full scalar multiplication.
+pub trait ScalarOperand<F>: Copy {
+    /// The operand as a scalar.
+    fn to_scalar(self) -> F;
+
+    /// Multiply `p` by the operand.
+    fn mul_point<G: Group<Scalar = F>>(self, p: G) -> G {
+        p * self.to_scalar()
+    }
+}
+
+// Integers below this bound take at most 32 point operations by double-and-add.
+const SMALL_MULTIPLE: u64 = 1 << 16;
+
+impl<F: PrimeField> ScalarOperand<F> for u64 {
+    fn to_scalar(self) -> F {
+        F::from(self)
+    }
+
+    fn mul_point<G: Group<Scalar = F>>(self, p: G) -> G {
+        if self >= SMALL_MULTIPLE {
+            return p * F::from(self);
+        }
+        let mut acc = G::identity();
+        for bit in (0..64 - self.leading_zeros()).rev() {
+            acc = acc.double();
+            if (self >> bit) & 1 == 1 {
+                acc += p;
+            }
+        }
+        acc
+    }
+}
+
+impl<F: PrimeField> ScalarOperand<F> for u32 {
+    fn to_scalar(self) -> F {
+        F::from(self as u64)
+    }
+
+    fn mul_point<G: Group<Scalar = F>>(self, p: G) -> G {
+        (self as u64).mul_point(p)
+    }
+}
+
+macro_rules! scalar_operand {
+    ($scalar:ty) => {
+        impl $crate::ScalarOperand<$scalar> for $scalar {
+            fn to_scalar(self) -> $scalar {
+                self
+            }
+        }
+
+        impl $crate::ScalarOperand<$scalar> for &$scalar {
+            fn to_scalar(self) -> $scalar {
+                *self
+            }
+        }
+    };
+}
+#[cfg(feature = "ristretto448")]
+pub(crate) use scalar_operand;
+
+scalar_operand!(Scalar);
+#[cfg(any(test, feature = "k256"))]
+scalar_operand!(k256::Scalar);
+
+// Wraps a point so it can be passed to `write!` using the backend's `Debug` formatting.
+pub(crate) struct DebugPoint<'a, G>(pub(crate) &'a G);
+
+impl<G: ElGamalGroup> core::fmt::Debug for DebugPoint<'_, G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt_point(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Ciphertext, Commitment, DecryptionKey, ElGamalGroup, Error, Identity};
+
+    // Run the encryption and commitment suite in the group `G`.
+    fn suite<G: ElGamalGroup>() {
+        const N: usize = 20;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::<G>::random(&mut rng);
+        let ek = dk.encryption_key();
+
+        for _ in 0..N {
+            // encrypt / decrypt
+            let m1 = G::random(&mut rng);
+            let m2 = G::random(&mut rng);
+            let ct1 = ek.encrypt(m1, &mut rng);
+            let ct2 = ek.encrypt(m2, &mut rng);
+            assert_eq!(dk.decrypt(ct1), m1);
+            assert_eq!(ek.encrypt_with(m1, G::Scalar::ONE).0, G::generator());
+
+            // rerandomise
+            let ct3 = ek.rerandomise(ct1, &mut rng);
+            assert_ne!(ct1, ct3);
+            assert_eq!(dk.decrypt(ct3), m1);
+
+            // homomorphism
+            let s = G::Scalar::random(&mut rng);
+            assert_eq!(dk.decrypt(ct1 + ct2), m1 + m2);
+            assert_eq!(dk.decrypt(ct1 - ct2), m1 - m2);
+            assert_eq!(dk.decrypt(-ct1), -m1);
+            assert_eq!(dk.decrypt(ct1 * s), m1 * s);
+            assert_eq!(dk.decrypt(Ciphertext::identity()), G::identity());
+
+            // exponential encryption
+            let x = G::Scalar::random(&mut rng);
+            assert_eq!(
+                dk.decrypt(ek.exp_encrypt(x, &mut rng)),
+                G::mul_generator(&x)
+            );
+
+            // commitment
+            let r = G::Scalar::random(&mut rng);
+            let commit_key = crate::CommitKey::from_encryption_key_unchecked(ek);
+            let (open1, mut commitment1) = Commitment::commit_with(x, r, &commit_key);
+            let (open2, commitment2) = Commitment::commit_with(s, r + r, &commit_key);
+            assert!(commitment1.verify(&open1));
+            assert!(!commitment1.verify(&open2));
+            assert_eq!(commitment1.validate(), Ok(()));
+            assert!((commitment1 + commitment2).verify(&(&open1 + &open2)));
+            assert!((commitment1 * s).verify(&(&open1 * s)));
+
+            let open3 = commitment1.rerandomise(open1.clone(), &mut rng);
+            assert!(commitment1.verify(&open3));
+            assert_eq!(
+                commitment1.try_verify(&open1),
+                Err(Error::VerificationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn ristretto255() {
+        suite::<crate::RistrettoPoint>();
+    }
+
+    #[test]
+    fn secp256k1() {
+        suite::<k256::ProjectivePoint>();
+    }
+}
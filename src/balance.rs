@@ -0,0 +1,309 @@
+// Balance proofs over commitments for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{AsCommitKey, Ciphertext, CommitKey, Commitment, Error, Open, GENERATOR_TABLE};
+
+const DOMAIN: &[u8] = b"rust-elgamal balance proof";
+
+/// A non-interactive proof that the messages of some commitments (the positives) minus those of
+/// others (the negatives) sum to zero, as in a transfer whose inputs and outputs must match,
+/// without opening any of them.
+///
+/// All the commitments must be made under the same commitment key `Y`. The difference of the sums,
+/// `(RG, MG + RY)`, is a commitment to `M = 0` exactly when it is `(RG, RY)`, so the proof is a
+/// Schnorr-style proof of knowledge of `R` with `log_G(RG) = log_Y(RY)`. The challenge hashes
+/// every commitment on each side, so a proof does not carry over to another set of commitments,
+/// even one with the same difference.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{BalanceProof, CommitKey, Commitment, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let commit_key = CommitKey::derive(b"ledger master secret", b"balances");
+/// let mut commit = |amount: u32| {
+///     let r = Scalar::random(&mut rng);
+///     let (open, commitment) = Commitment::commit_with(Scalar::from(amount), r, &commit_key);
+///     (commitment, open)
+/// };
+///
+/// // 70 + 30 in, 99 + 1 out
+/// let inputs = [commit(70), commit(30)];
+/// let outputs = [commit(99), commit(1)];
+/// let proof = BalanceProof::prove(&commit_key, &inputs, &outputs, &mut rng).unwrap();
+///
+/// let inputs: Vec<_> = inputs.iter().map(|(c, _)| *c).collect();
+/// let outputs: Vec<_> = outputs.iter().map(|(c, _)| *c).collect();
+/// assert_eq!(proof.verify(&commit_key, &inputs, &outputs), Ok(()));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BalanceProof {
+    e: Scalar,
+    z: Scalar,
+}
+
+impl BalanceProof {
+    /// Prove that the messages of `positives` minus those of `negatives` sum to zero, given the
+    /// opening of each commitment.
+    ///
+    /// Fails with [Error::KeyMismatch] if a commitment is not under `commit_key`, with
+    /// [Error::VerificationFailed] if an opening does not open its commitment, and with
+    /// [Error::Unbalanced] if the messages do not sum to zero.
+    pub fn prove<K: AsCommitKey<RistrettoPoint>, R: RngCore + CryptoRng>(
+        commit_key: &K,
+        positives: &[(Commitment, Open)],
+        negatives: &[(Commitment, Open)],
+        rng: &mut R,
+    ) -> Result<BalanceProof, Error> {
+        let commit_key = &commit_key.as_commit_key();
+        let mut open_sum = Open(Scalar::ZERO, Scalar::ZERO);
+        for (commitment, open) in positives {
+            check_opening(commitment, open, commit_key)?;
+            open_sum = open_sum + open;
+        }
+        for (commitment, open) in negatives {
+            check_opening(commitment, open, commit_key)?;
+            open_sum = open_sum - open;
+        }
+        let Open(r, m) = open_sum;
+        if m != Scalar::ZERO {
+            return Err(Error::Unbalanced);
+        }
+
+        let k = Scalar::random(rng);
+        let t1 = &k * GENERATOR_TABLE;
+        let t2 = commit_key.0 * k;
+        let e = challenge(
+            commit_key,
+            positives.iter().map(|(commitment, _)| commitment),
+            negatives.iter().map(|(commitment, _)| commitment),
+            &t1,
+            &t2,
+        );
+        Ok(BalanceProof { e, z: k + e * r })
+    }
+
+    /// Verify a proof that the messages of `positives` minus those of `negatives` sum to zero.
+    ///
+    /// Fails with [Error::KeyMismatch] if a commitment is not under `commit_key`, and with
+    /// [Error::VerificationFailed] if the proof does not verify for these commitments.
+    pub fn verify<K: AsCommitKey<RistrettoPoint>>(
+        &self,
+        commit_key: &K,
+        positives: &[Commitment],
+        negatives: &[Commitment],
+    ) -> Result<(), Error> {
+        let commit_key = &commit_key.as_commit_key();
+        let mut difference = Ciphertext::identity();
+        for commitment in positives {
+            check_key(commitment, commit_key)?;
+            difference = difference + commitment.1;
+        }
+        for commitment in negatives {
+            check_key(commitment, commit_key)?;
+            difference = difference - commitment.1;
+        }
+
+        // recompute the prover's commitments from the response
+        let t1 = &self.z * GENERATOR_TABLE - difference.0 * self.e;
+        let t2 = commit_key.0 * self.z - difference.1 * self.e;
+        let e = challenge(commit_key, positives.iter(), negatives.iter(), &t1, &t2);
+        if e != self.e {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+fn check_key(commitment: &Commitment, commit_key: &CommitKey) -> Result<(), Error> {
+    if commitment.0 != commit_key.0 {
+        return Err(Error::KeyMismatch);
+    }
+    Ok(())
+}
+
+fn check_opening(
+    commitment: &Commitment,
+    open: &Open,
+    commit_key: &CommitKey,
+) -> Result<(), Error> {
+    check_key(commitment, commit_key)?;
+    commitment.try_verify(open)
+}
+
+// The Fiat-Shamir challenge, over the key, both lists of commitments with their lengths, and the
+// prover's commitments.
+fn challenge<'a>(
+    commit_key: &CommitKey,
+    positives: impl ExactSizeIterator<Item = &'a Commitment>,
+    negatives: impl ExactSizeIterator<Item = &'a Commitment>,
+    t1: &RistrettoPoint,
+    t2: &RistrettoPoint,
+) -> Scalar {
+    let mut hasher = Sha512::new()
+        .chain_update((DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(DOMAIN)
+        .chain_update(commit_key.0.compress().as_bytes());
+    hasher.update((positives.len() as u64).to_le_bytes());
+    for commitment in positives {
+        hasher.update(commitment.1.compress().to_bytes());
+    }
+    hasher.update((negatives.len() as u64).to_le_bytes());
+    for commitment in negatives {
+        hasher.update(commitment.1.compress().to_bytes());
+    }
+    hasher.update(t1.compress().as_bytes());
+    hasher.update(t2.compress().as_bytes());
+    Scalar::from_hash(hasher)
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::BalanceProof;
+    use crate::{CommitKey, Commitment, DecryptionKey, Error, Open, Scalar};
+
+    fn commit(amount: u64, key: &CommitKey, rng: &mut StdRng) -> (Commitment, Open) {
+        let r = Scalar::random(rng);
+        let (open, commitment) = Commitment::commit_with(Scalar::from(amount), r, key);
+        (commitment, open)
+    }
+
+    fn commitments(pairs: &[(Commitment, Open)]) -> Vec<Commitment> {
+        pairs.iter().map(|(commitment, _)| *commitment).collect()
+    }
+
+    // Test that a balanced transfer proves and verifies, including with no outputs at all
+    // balancing an input of zero.
+    #[test]
+    fn balance_accepts_balanced() {
+        let mut rng = StdRng::seed_from_u64(493);
+        let key =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+        let inputs = [commit(70, &key, &mut rng), commit(30, &key, &mut rng)];
+        let outputs = [
+            commit(25, &key, &mut rng),
+            commit(25, &key, &mut rng),
+            commit(50, &key, &mut rng),
+        ];
+
+        let proof = BalanceProof::prove(&key, &inputs, &outputs, &mut rng).unwrap();
+        assert_eq!(
+            proof.verify(&key, &commitments(&inputs), &commitments(&outputs)),
+            Ok(())
+        );
+
+        let zero = [commit(0, &key, &mut rng)];
+        let proof = BalanceProof::prove(&key, &zero, &[], &mut rng).unwrap();
+        assert_eq!(proof.verify(&key, &commitments(&zero), &[]), Ok(()));
+    }
+
+    // Test that an imbalance of one cannot be proved, and that a proof for it forged from the
+    // openings does not verify.
+    #[test]
+    fn balance_rejects_off_by_one() {
+        let mut rng = StdRng::seed_from_u64(493);
+        let key =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+        let inputs = [commit(70, &key, &mut rng), commit(30, &key, &mut rng)];
+        let outputs = [commit(99, &key, &mut rng), commit(2, &key, &mut rng)];
+        assert_eq!(
+            BalanceProof::prove(&key, &inputs, &outputs, &mut rng),
+            Err(Error::Unbalanced)
+        );
+
+        // prove knowledge of the blinding as if the difference were a commitment to zero
+        let r: Scalar = inputs.iter().map(|(_, open)| open.0).sum::<Scalar>()
+            - outputs.iter().map(|(_, open)| open.0).sum::<Scalar>();
+        let (_, zero) = Commitment::commit_with(Scalar::ZERO, r, &key);
+        let forged =
+            BalanceProof::prove(&key, &[(zero, Open(r, Scalar::ZERO))], &[], &mut rng).unwrap();
+        assert_eq!(
+            forged.verify(&key, &commitments(&inputs), &commitments(&outputs)),
+            Err(Error::VerificationFailed)
+        );
+
+        // a wrong opening is refused before proving
+        let mut bad = inputs;
+        bad[0].1 = Open(bad[0].1 .0, Scalar::from(71u32));
+        assert_eq!(
+            BalanceProof::prove(&key, &bad, &outputs, &mut rng),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that a proof does not verify against another set of commitments, even one with the
+    // same difference, nor under another key.
+    #[test]
+    fn balance_rejects_replay() {
+        let mut rng = StdRng::seed_from_u64(493);
+        let key =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+        let inputs = [commit(40, &key, &mut rng), commit(60, &key, &mut rng)];
+        let outputs = [commit(100, &key, &mut rng)];
+        let proof = BalanceProof::prove(&key, &inputs, &outputs, &mut rng).unwrap();
+        let (inputs, outputs) = (commitments(&inputs), commitments(&outputs));
+        assert_eq!(proof.verify(&key, &inputs, &outputs), Ok(()));
+
+        let other = [commit(100, &key, &mut rng)];
+        let other_outputs = commitments(&other);
+        let mut replayed = inputs.clone();
+        replayed[1] = commit(60, &key, &mut rng).0;
+        assert_eq!(
+            proof.verify(&key, &replayed, &outputs),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            proof.verify(&key, &inputs, &other_outputs),
+            Err(Error::VerificationFailed)
+        );
+
+        // the same difference, padded with a commitment on both sides
+        let extra = commit(5, &key, &mut rng).0;
+        let mut padded_inputs = inputs.clone();
+        padded_inputs.push(extra);
+        let mut padded_outputs = outputs.clone();
+        padded_outputs.push(extra);
+        assert_eq!(
+            proof.verify(&key, &padded_inputs, &padded_outputs),
+            Err(Error::VerificationFailed)
+        );
+
+        let other_key =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+        assert_eq!(
+            proof.verify(&other_key, &inputs, &outputs),
+            Err(Error::KeyMismatch)
+        );
+    }
+}
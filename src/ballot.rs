@@ -0,0 +1,613 @@
+// Verified ballots and ballot boxes for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encrypted ballots with validity proofs, and a ballot box that checks them on submission.
+//!
+//! A [Ballot] is one exponential ciphertext per option, each encrypting 0 or 1. Its
+//! [BallotProofs] show, without revealing the votes, that every ciphertext encrypts 0 or 1 (a
+//! disjunctive Chaum-Pedersen proof per option) and that the votes add up to the number of
+//! selections (a Chaum-Pedersen proof on the sum). The 0/1 proofs are bound to the election and
+//! the voter, so a ballot copied into another voter's submission or another election is
+//! rejected. A [BallotBox] verifies these on [submit](BallotBox::submit), so malformed ballots
+//! are rejected straight away rather than poisoning the tally, and keeps running encrypted
+//! totals and a hash chain over everything it accepted.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Ciphertext, DecryptionKey, DleqProof, EncryptionKey, HomomorphicTally, GENERATOR_POINT,
+    GENERATOR_TABLE,
+};
+
+const ZERO_ONE_DOMAIN: &[u8] = b"rust-elgamal zero-one";
+const CHAIN_DOMAIN: &[u8] = b"rust-elgamal ballot chain";
+
+/// A non-interactive proof that an exponential ciphertext encrypts 0 or 1, without revealing
+/// which (a disjunctive Chaum-Pedersen proof).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ZeroOneProof {
+    #[cfg_attr(feature = "ts", ts(as = "[crate::ts::Scalar; 2]"))]
+    pub(crate) c: [Scalar; 2],
+    #[cfg_attr(feature = "ts", ts(as = "[crate::ts::Scalar; 2]"))]
+    pub(crate) s: [Scalar; 2],
+}
+
+// The commitments of branch `j` for the challenge `c` and response `s`: `sG - ca` and
+// `sY - c(b - jG)`.
+fn branch(
+    ek: &EncryptionKey,
+    ct: &Ciphertext,
+    j: usize,
+    c: &Scalar,
+    s: &Scalar,
+) -> (RistrettoPoint, RistrettoPoint) {
+    let b = if j == 0 { ct.1 } else { ct.1 - GENERATOR_POINT };
+    (s * GENERATOR_TABLE - ct.0 * c, ek.0 * s - b * c)
+}
+
+fn zero_one_challenge(
+    ek: &EncryptionKey,
+    ct: &Ciphertext,
+    context: &[u8],
+    commitments: &[(RistrettoPoint, RistrettoPoint); 2],
+) -> Scalar {
+    let mut hasher = Sha512::new()
+        .chain_update((ZERO_ONE_DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(ZERO_ONE_DOMAIN)
+        .chain_update((context.len() as u64).to_le_bytes())
+        .chain_update(context);
+    for point in [
+        &ek.0,
+        &ct.0,
+        &ct.1,
+        &commitments[0].0,
+        &commitments[0].1,
+        &commitments[1].0,
+        &commitments[1].1,
+    ] {
+        hasher.update(point.compress().as_bytes());
+    }
+    Scalar::from_hash(hasher)
+}
+
+// The context of a ballot's 0/1 proofs: the election label and the voter id, each prefixed with
+// its length.
+fn ballot_context(election: &[u8], voter_id: &[u8]) -> Vec<u8> {
+    let mut context = Vec::with_capacity(16 + election.len() + voter_id.len());
+    for part in [election, voter_id] {
+        context.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        context.extend_from_slice(part);
+    }
+    context
+}
+
+impl ZeroOneProof {
+    /// Prove that `ct = ek.exp_encrypt_with(Scalar::from(vote as u64), r)` encrypts 0 or 1.
+    ///
+    /// The proof is only meaningful if `ct` really is that encryption; otherwise it will not
+    /// verify. `context` is hashed into the challenge, so the proof only verifies under the same
+    /// context; [Ballot::encrypt] uses it to bind proofs to the election and the voter.
+    pub fn prove<R: RngCore + CryptoRng>(
+        ek: &EncryptionKey,
+        ct: &Ciphertext,
+        vote: bool,
+        r: &Scalar,
+        context: &[u8],
+        rng: &mut R,
+    ) -> ZeroOneProof {
+        let real = vote as usize;
+        let fake = 1 - real;
+
+        // simulate the branch that is false, and commit honestly to the one that is true
+        let mut c = [Scalar::ZERO; 2];
+        let mut s = [Scalar::ZERO; 2];
+        c[fake] = Scalar::random(rng);
+        s[fake] = Scalar::random(rng);
+        let w = Scalar::random(rng);
+
+        let mut commitments = [(RistrettoPoint::identity(), RistrettoPoint::identity()); 2];
+        commitments[fake] = branch(ek, ct, fake, &c[fake], &s[fake]);
+        commitments[real] = (&w * GENERATOR_TABLE, ek.0 * w);
+
+        c[real] = zero_one_challenge(ek, ct, context, &commitments) - c[fake];
+        s[real] = w + c[real] * r;
+        ZeroOneProof { c, s }
+    }
+
+    /// Verify the proof that `ct` encrypts 0 or 1 under `ek`, made with the same `context`.
+    pub fn verify(&self, ek: &EncryptionKey, ct: &Ciphertext, context: &[u8]) -> bool {
+        let commitments = [
+            branch(ek, ct, 0, &self.c[0], &self.s[0]),
+            branch(ek, ct, 1, &self.c[1], &self.s[1]),
+        ];
+        zero_one_challenge(ek, ct, context, &commitments) == self.c[0] + self.c[1]
+    }
+}
+
+/// An encrypted ballot: one exponential ciphertext per option, each encrypting 0 or 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Ballot(
+    #[cfg_attr(feature = "ts", ts(as = "Vec<Ciphertext<crate::ts::Point>>"))]
+    pub(crate)  Vec<Ciphertext>,
+);
+
+/// The proofs accompanying a [Ballot]: one [ZeroOneProof] per option, and a proof that the
+/// votes add up to the number of selections.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct BallotProofs {
+    pub zero_one: Vec<ZeroOneProof>,
+    pub sum: DleqProof,
+}
+
+impl Ballot {
+    /// Encrypt a vote for each option under `ek`, with the proofs that the ballot is well formed.
+    ///
+    /// The sum proof shows that the ballot selects exactly as many options as `votes` does. The
+    /// 0/1 proofs are bound to `election` and `voter_id`, so the ballot is only accepted from that
+    /// voter by a [BallotBox] for that election.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        ek: &EncryptionKey,
+        election: &[u8],
+        voter_id: &[u8],
+        votes: &[bool],
+        rng: &mut R,
+    ) -> (Ballot, BallotProofs) {
+        let context = ballot_context(election, voter_id);
+        let mut cts = Vec::with_capacity(votes.len());
+        let mut zero_one = Vec::with_capacity(votes.len());
+        let mut total = Scalar::ZERO;
+        for &vote in votes {
+            let r = Scalar::random(rng);
+            let ct = ek.exp_encrypt_with(Scalar::from(vote as u64), r);
+            zero_one.push(ZeroOneProof::prove(ek, &ct, vote, &r, &context, rng));
+            cts.push(ct);
+            total += r;
+        }
+
+        // the sum `(RG, kG + RY)` has `log_G(RG) = log_Y(kG + RY - kG)`
+        let selections = votes.iter().filter(|&&vote| vote).count() as u64;
+        let sum: Ciphertext = cts.iter().fold(Ciphertext::identity(), |acc, ct| acc + ct);
+        let q = sum.1 - &Scalar::from(selections) * GENERATOR_TABLE;
+        let sum = DleqProof::prove(&DecryptionKey::from(total), &ek.0, &q, rng);
+
+        (Ballot(cts), BallotProofs { zero_one, sum })
+    }
+
+    /// The ciphertexts of the ballot, one per option.
+    pub fn ciphertexts(&self) -> &[Ciphertext] {
+        &self.0
+    }
+}
+
+/// What a [BallotBox] checks beyond every vote being 0 or 1.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub enum ProofPolicy {
+    /// Check only that every vote is 0 or 1, so voters may select any number of options.
+    ZeroOne,
+    /// Also check that exactly `selections` options are selected.
+    ExactSelections(#[cfg_attr(feature = "ts", ts(type = "number"))] u64),
+}
+
+/// Acknowledges a ballot accepted by a [BallotBox]: its position, and the head of the hash chain
+/// after it was appended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Receipt {
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub position: u64,
+    #[cfg_attr(feature = "ts", ts(type = "Array<number>"))]
+    pub chain_hash: [u8; 32],
+}
+
+/// Why a [BallotBox] rejected a ballot.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SubmitError {
+    /// The voter has already voted.
+    DuplicateVoter,
+    /// The ballot or its proofs do not have one entry per option.
+    LengthMismatch { expected: usize, got: usize },
+    /// The proof that the vote for this option is 0 or 1 does not verify.
+    InvalidZeroOneProof(usize),
+    /// The proof of the number of selections does not verify.
+    InvalidSumProof,
+}
+
+impl Display for SubmitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SubmitError::DuplicateVoter => write!(f, "voter has already voted"),
+            SubmitError::LengthMismatch { expected, got } => {
+                write!(f, "length mismatch: expected {}, got {}", expected, got)
+            }
+            SubmitError::InvalidZeroOneProof(option) => {
+                write!(f, "invalid 0/1 proof for option {}", option)
+            }
+            SubmitError::InvalidSumProof => write!(f, "invalid sum proof"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubmitError {}
+
+// Check a ballot's proofs against the election's key, label, number of options and policy, and
+// the voter submitting it.
+pub(crate) fn verify_ballot(
+    ek: &EncryptionKey,
+    election: &[u8],
+    voter_id: &[u8],
+    n: usize,
+    policy: ProofPolicy,
+    ballot: &Ballot,
+    proofs: &BallotProofs,
+) -> Result<(), SubmitError> {
+    for len in [ballot.0.len(), proofs.zero_one.len()] {
+        if len != n {
+            return Err(SubmitError::LengthMismatch {
+                expected: n,
+                got: len,
+            });
+        }
+    }
+
+    let context = ballot_context(election, voter_id);
+    for (i, (ct, proof)) in ballot.0.iter().zip(&proofs.zero_one).enumerate() {
+        if !proof.verify(ek, ct, &context) {
+            return Err(SubmitError::InvalidZeroOneProof(i));
+        }
+    }
+
+    if let ProofPolicy::ExactSelections(selections) = policy {
+        let sum: Ciphertext = ballot
+            .0
+            .iter()
+            .fold(Ciphertext::identity(), |acc, ct| acc + ct);
+        let q = sum.1 - &Scalar::from(selections) * GENERATOR_TABLE;
+        proofs
+            .sum
+            .verify(&EncryptionKey(sum.0), &ek.0, &q)
+            .map_err(|_| SubmitError::InvalidSumProof)?;
+    }
+
+    Ok(())
+}
+
+/// Collects ballots for an election, verifying each on submission.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::ballot::{Ballot, BallotBox, ProofPolicy, SubmitError};
+/// use rust_elgamal::DecryptionKey;
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let election = b"board election 2024";
+/// let policy = ProofPolicy::ExactSelections(1);
+/// let mut ballot_box = BallotBox::new(*enc_key, election, 3, policy);
+/// let votes = [false, true, false];
+/// let (ballot, proofs) = Ballot::encrypt(enc_key, election, b"alice", &votes, &mut rng);
+/// ballot_box.submit(ballot.clone(), proofs.clone(), b"alice").unwrap();
+/// assert_eq!(
+///     ballot_box.submit(ballot, proofs, b"alice"),
+///     Err(SubmitError::DuplicateVoter)
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BallotBox {
+    election: Vec<u8>,
+    policy: ProofPolicy,
+    tally: HomomorphicTally,
+    voters: BTreeSet<Vec<u8>>,
+    chain_hash: [u8; 32],
+}
+
+impl BallotBox {
+    /// An empty ballot box for ballots of `num_options` options under `ek`, for the election
+    /// labelled `election`.
+    ///
+    /// The label must be the one voters pass to [Ballot::encrypt], and should be unique to the
+    /// election, so that ballots cast in one election are not accepted in another.
+    pub fn new(
+        ek: EncryptionKey,
+        election: &[u8],
+        num_options: usize,
+        policy: ProofPolicy,
+    ) -> BallotBox {
+        BallotBox {
+            election: election.to_vec(),
+            policy,
+            tally: HomomorphicTally::new(ek, num_options),
+            voters: BTreeSet::new(),
+            chain_hash: [0u8; 32],
+        }
+    }
+
+    /// Verify a ballot and, if it is valid and `voter_id` has not voted, add it to the running
+    /// totals and the hash chain.
+    ///
+    /// A rejected ballot leaves the box unchanged.
+    pub fn submit(
+        &mut self,
+        ballot: Ballot,
+        proofs: BallotProofs,
+        voter_id: &[u8],
+    ) -> Result<Receipt, SubmitError> {
+        if self.voters.contains(voter_id) {
+            return Err(SubmitError::DuplicateVoter);
+        }
+
+        verify_ballot(
+            self.tally.encryption_key(),
+            &self.election,
+            voter_id,
+            self.tally.num_options(),
+            self.policy,
+            &ballot,
+            &proofs,
+        )?;
+
+        self.tally
+            .add_ballot(&ballot.0)
+            .expect("ballot length was checked");
+        self.voters.insert(voter_id.to_vec());
+        self.chain_hash = self.next_hash(&ballot, voter_id);
+
+        Ok(Receipt {
+            position: self.tally.num_ballots() - 1,
+            chain_hash: self.chain_hash,
+        })
+    }
+
+    fn next_hash(&self, ballot: &Ballot, voter_id: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha512::new()
+            .chain_update((CHAIN_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(CHAIN_DOMAIN)
+            .chain_update(self.chain_hash)
+            .chain_update((voter_id.len() as u64).to_le_bytes())
+            .chain_update(voter_id);
+        for ct in &ballot.0 {
+            hasher.update(ct.compress().to_bytes());
+        }
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize()[..32]);
+        hash
+    }
+
+    /// The label of the election the box collects ballots for.
+    pub fn election(&self) -> &[u8] {
+        &self.election
+    }
+
+    /// The head of the hash chain over every accepted ballot; all zeroes while the box is empty.
+    pub fn chain_hash(&self) -> &[u8; 32] {
+        &self.chain_hash
+    }
+
+    /// The number of ballots accepted.
+    pub fn num_ballots(&self) -> u64 {
+        self.tally.num_ballots()
+    }
+
+    /// The encrypted running totals, one per option.
+    pub fn totals(&self) -> &[Ciphertext] {
+        self.tally.totals()
+    }
+
+    /// Close the box, returning the encrypted totals for decryption.
+    pub fn finalize(self) -> Vec<Ciphertext> {
+        self.tally.totals().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use alloc::vec::Vec;
+
+    use crate::ballot::{Ballot, BallotBox, ProofPolicy, SubmitError, ZeroOneProof};
+    use crate::{Ciphertext, DecryptionKey, HomomorphicTally, Identity, Scalar};
+
+    const OPTIONS: usize = 4;
+    const ELECTION: &[u8] = b"test election";
+
+    // Test that 0/1 proofs verify for 0 and 1 and not for 2 or a different ciphertext.
+    #[test]
+    fn zero_one_proofs() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for vote in [false, true] {
+            let r = Scalar::random(&mut rng);
+            let ct = ek.exp_encrypt_with(Scalar::from(vote as u64), r);
+            let proof = ZeroOneProof::prove(ek, &ct, vote, &r, b"context", &mut rng);
+            assert!(proof.verify(ek, &ct, b"context"));
+            assert!(!proof.verify(ek, &ct, b"other context"));
+            assert!(!proof.verify(ek, &ek.rerandomise(ct, &mut rng), b"context"));
+        }
+
+        let r = Scalar::random(&mut rng);
+        let two = ek.exp_encrypt_with(Scalar::from(2u32), r);
+        for vote in [false, true] {
+            let proof = ZeroOneProof::prove(ek, &two, vote, &r, b"", &mut rng);
+            assert!(!proof.verify(ek, &two, b""));
+        }
+    }
+
+    // Test that the running totals agree with a recount from scratch, and decrypt to the votes.
+    #[test]
+    fn ballot_box_recount() {
+        const N: usize = 30;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+
+        let mut ballot_box = BallotBox::new(ek, ELECTION, OPTIONS, ProofPolicy::ExactSelections(1));
+        let mut ballots = Vec::new();
+        let mut expected = [0u64; OPTIONS];
+        for i in 0..N {
+            let choice = rng.gen_range(0..OPTIONS);
+            expected[choice] += 1;
+            let votes: Vec<bool> = (0..OPTIONS).map(|j| j == choice).collect();
+            let voter_id = (i as u32).to_le_bytes();
+            let (ballot, proofs) = Ballot::encrypt(&ek, ELECTION, &voter_id, &votes, &mut rng);
+
+            let before = *ballot_box.chain_hash();
+            let receipt = ballot_box
+                .submit(ballot.clone(), proofs, &voter_id)
+                .unwrap();
+            assert_eq!(receipt.position, i as u64);
+            assert_ne!(receipt.chain_hash, before);
+            ballots.push(ballot);
+        }
+
+        let mut recount = [Ciphertext::identity(); OPTIONS];
+        for ballot in &ballots {
+            for (total, ct) in recount.iter_mut().zip(ballot.ciphertexts()) {
+                *total = *total + ct;
+            }
+        }
+        assert_eq!(ballot_box.totals(), recount);
+        assert_eq!(ballot_box.num_ballots(), N as u64);
+
+        let mut tally = HomomorphicTally::new(ek, OPTIONS);
+        tally.add_ballot(&ballot_box.finalize()).unwrap();
+        assert_eq!(tally.finalize(&dk, N as u64).unwrap(), expected);
+    }
+
+    // Test that double votes, invalid proofs and malformed ballots are rejected without
+    // changing the box.
+    #[test]
+    fn ballot_box_rejects() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let encrypt = |voter_id: &[u8], votes: &[bool], rng: &mut StdRng| {
+            Ballot::encrypt(&ek, ELECTION, voter_id, votes, rng)
+        };
+
+        let mut ballot_box = BallotBox::new(ek, ELECTION, OPTIONS, ProofPolicy::ExactSelections(1));
+        let (ballot, proofs) = encrypt(b"alice", &[true, false, false, false], &mut rng);
+        ballot_box
+            .submit(ballot.clone(), proofs.clone(), b"alice")
+            .unwrap();
+        let snapshot = ballot_box.clone();
+
+        // double voting, even with a fresh ballot
+        let (ballot2, proofs2) = encrypt(b"alice", &[false, true, false, false], &mut rng);
+        assert_eq!(
+            ballot_box.submit(ballot2.clone(), proofs2.clone(), b"alice"),
+            Err(SubmitError::DuplicateVoter)
+        );
+
+        // a vote of 2 for one option, with the proof for another ciphertext
+        let (ballot3, proofs3) = encrypt(b"bob", &[false, true, false, false], &mut rng);
+        let mut stuffed = ballot3.clone();
+        stuffed.0[1] = ek.exp_encrypt(Scalar::from(2u32), &mut rng);
+        assert_eq!(
+            ballot_box.submit(stuffed, proofs3.clone(), b"bob"),
+            Err(SubmitError::InvalidZeroOneProof(1))
+        );
+
+        // selecting two options under a one-selection policy
+        let (double, double_proofs) = encrypt(b"bob", &[true, true, false, false], &mut rng);
+        assert_eq!(
+            ballot_box.submit(double.clone(), double_proofs.clone(), b"bob"),
+            Err(SubmitError::InvalidSumProof)
+        );
+
+        let (short, short_proofs) = encrypt(b"bob", &[true, false], &mut rng);
+        assert_eq!(
+            ballot_box.submit(short, short_proofs, b"bob"),
+            Err(SubmitError::LengthMismatch {
+                expected: OPTIONS,
+                got: 2
+            })
+        );
+        assert_eq!(ballot_box, snapshot);
+
+        // without a selection policy, any number of selections is fine
+        let mut open_box = BallotBox::new(ek, ELECTION, OPTIONS, ProofPolicy::ZeroOne);
+        assert!(open_box.submit(double, double_proofs, b"bob").is_ok());
+        assert!(open_box.submit(ballot3, proofs3, b"bob2").is_err());
+        let (ballot4, proofs4) = encrypt(b"carol", &[false, true, false, true], &mut rng);
+        assert!(open_box.submit(ballot4, proofs4, b"carol").is_ok());
+    }
+
+    // Test that a valid ballot copied into another voter's submission, or into a box for another
+    // election, is rejected, and is still accepted from its own voter.
+    #[test]
+    fn ballot_bound_to_voter_and_election() {
+        let mut rng = StdRng::seed_from_u64(446);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let policy = ProofPolicy::ExactSelections(1);
+
+        let (ballot, proofs) = Ballot::encrypt(
+            &ek,
+            ELECTION,
+            b"alice",
+            &[false, true, false, false],
+            &mut rng,
+        );
+
+        let mut ballot_box = BallotBox::new(ek, ELECTION, OPTIONS, policy);
+        assert_eq!(
+            ballot_box.submit(ballot.clone(), proofs.clone(), b"mallory"),
+            Err(SubmitError::InvalidZeroOneProof(0))
+        );
+        assert_eq!(ballot_box.num_ballots(), 0);
+
+        let mut other_box = BallotBox::new(ek, b"another election", OPTIONS, policy);
+        assert_eq!(
+            other_box.submit(ballot.clone(), proofs.clone(), b"alice"),
+            Err(SubmitError::InvalidZeroOneProof(0))
+        );
+
+        assert!(ballot_box.submit(ballot, proofs, b"alice").is_ok());
+        assert_eq!(ballot_box.election(), ELECTION);
+    }
+}
@@ -0,0 +1,376 @@
+// Allocation-free batch operations for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::trace::op_span;
+use crate::{
+    Ciphertext, CompressedCiphertext, DecryptionKey, ElGamalGroup, EncryptionKey, Error,
+    ScalarOperand,
+};
+
+// Fail unless an output buffer is exactly as long as the input.
+fn check_len(expected: usize, got: usize) -> Result<(), Error> {
+    if expected != got {
+        return Err(Error::LengthMismatch { expected, got });
+    }
+    Ok(())
+}
+
+impl<G: ElGamalGroup> EncryptionKey<G> {
+    /// Encrypt each of `msgs` with the blinding factor at the same position in `rs`, writing the
+    /// ciphertexts into `out`. Needs no allocator.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch), before writing
+    /// anything, unless `rs` and `out` are exactly as long as `msgs`.
+    pub fn encrypt_batch_into(
+        &self,
+        msgs: &[G],
+        rs: &[G::Scalar],
+        out: &mut [Ciphertext<G>],
+    ) -> Result<(), Error> {
+        check_len(msgs.len(), rs.len())?;
+        check_len(msgs.len(), out.len())?;
+        let _span = op_span!("encrypt_batch", count = msgs.len());
+        for ((m, r), ct) in msgs.iter().zip(rs).zip(out) {
+            *ct = self.encrypt_with(*m, *r);
+        }
+        Ok(())
+    }
+}
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Decrypt each of `cts`, writing the plaintexts into `out`. Needs no allocator.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch), before writing
+    /// anything, unless `out` is exactly as long as `cts`.
+    pub fn decrypt_batch_into(&self, cts: &[Ciphertext<G>], out: &mut [G]) -> Result<(), Error> {
+        check_len(cts.len(), out.len())?;
+        let _span = op_span!("decrypt_batch", count = cts.len());
+        for (ct, m) in cts.iter().zip(out) {
+            *m = self.decrypt(*ct);
+        }
+        Ok(())
+    }
+}
+
+/// Compress each of `cts`, writing the results into `out`. This is
+/// [compress_batch](crate::compress_batch) without an allocator.
+///
+/// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch), before writing anything,
+/// unless `out` is exactly as long as `cts`.
+pub fn compress_batch_into(
+    cts: &[Ciphertext],
+    out: &mut [CompressedCiphertext],
+) -> Result<(), Error> {
+    check_len(cts.len(), out.len())?;
+    for (ct, compressed) in cts.iter().zip(out) {
+        *compressed = ct.compress();
+    }
+    Ok(())
+}
+
+/// A fixed number `N` of ciphertexts under the same key, such as the entries of a ballot or a
+/// vector of counters, stored inline so that no allocator is needed.
+///
+/// Addition, subtraction, negation and multiplication by a scalar work elementwise, so adding two
+/// arrays adds the plaintexts position by position.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CiphertextArray, DecryptionKey, Scalar, GENERATOR_TABLE};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let ek = dec_key.encryption_key();
+///
+/// let mut votes = |choice: usize| {
+///     let msgs = core::array::from_fn(|i| &Scalar::from((i == choice) as u32) * GENERATOR_TABLE);
+///     CiphertextArray::<3>::encrypt(ek, &msgs, &mut rng)
+/// };
+/// let tally = votes(0) + votes(2) + votes(2);
+/// assert_eq!(tally.decrypt(&dec_key)[2], &Scalar::from(2u32) * GENERATOR_TABLE);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CiphertextArray<const N: usize, G: ElGamalGroup = RistrettoPoint>([Ciphertext<G>; N]);
+
+impl<const N: usize, G: ElGamalGroup> CiphertextArray<N, G> {
+    /// Wrap `N` ciphertexts.
+    pub fn new(cts: [Ciphertext<G>; N]) -> Self {
+        CiphertextArray(cts)
+    }
+
+    /// Encrypt each of `msgs` with a fresh blinding factor.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        ek: &EncryptionKey<G>,
+        msgs: &[G; N],
+        rng: &mut R,
+    ) -> Self {
+        CiphertextArray(core::array::from_fn(|i| ek.encrypt(msgs[i], &mut *rng)))
+    }
+
+    /// Encrypt each of `msgs` with the blinding factor at the same position in `rs`.
+    pub fn encrypt_with(ek: &EncryptionKey<G>, msgs: &[G; N], rs: &[G::Scalar; N]) -> Self {
+        CiphertextArray(core::array::from_fn(|i| ek.encrypt_with(msgs[i], rs[i])))
+    }
+
+    /// Decrypt each ciphertext.
+    pub fn decrypt(&self, dk: &DecryptionKey<G>) -> [G; N] {
+        core::array::from_fn(|i| dk.decrypt(self.0[i]))
+    }
+
+    /// Re-randomise each ciphertext with a fresh blinding factor.
+    #[must_use = "the array is not mutated, the function returns the new rerandomised array"]
+    pub fn rerandomise<R: RngCore + CryptoRng>(&self, ek: &EncryptionKey<G>, rng: &mut R) -> Self {
+        CiphertextArray(core::array::from_fn(|i| {
+            ek.rerandomise(self.0[i], &mut *rng)
+        }))
+    }
+
+    /// The homomorphic sum of all the ciphertexts: an encryption of the sum of the plaintexts.
+    pub fn sum(&self) -> Ciphertext<G> {
+        self.0
+            .iter()
+            .fold(Ciphertext::identity(), |sum, ct| sum + ct)
+    }
+
+    /// The ciphertexts as an array.
+    pub fn into_inner(self) -> [Ciphertext<G>; N] {
+        self.0
+    }
+
+    /// The ciphertexts as a slice.
+    pub fn as_slice(&self) -> &[Ciphertext<G>] {
+        &self.0
+    }
+}
+
+impl<const N: usize> CiphertextArray<N> {
+    /// Compress each ciphertext.
+    pub fn compress(&self) -> [CompressedCiphertext; N] {
+        core::array::from_fn(|i| self.0[i].compress())
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> From<[Ciphertext<G>; N]> for CiphertextArray<N, G> {
+    fn from(cts: [Ciphertext<G>; N]) -> Self {
+        CiphertextArray(cts)
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Identity for CiphertextArray<N, G> {
+    fn identity() -> Self {
+        CiphertextArray([Ciphertext::identity(); N])
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Index<usize> for CiphertextArray<N, G> {
+    type Output = Ciphertext<G>;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.0[i]
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> IndexMut<usize> for CiphertextArray<N, G> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.0[i]
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Add for CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        CiphertextArray(core::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Add for &CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        *self + *rhs
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Sub for CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        CiphertextArray(core::array::from_fn(|i| self.0[i] - rhs.0[i]))
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Sub for &CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        *self - *rhs
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Neg for CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn neg(self) -> Self::Output {
+        CiphertextArray(core::array::from_fn(|i| -self.0[i]))
+    }
+}
+
+impl<const N: usize, G: ElGamalGroup> Neg for &CiphertextArray<N, G> {
+    type Output = CiphertextArray<N, G>;
+
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+impl<const N: usize, G, S> Mul<S> for CiphertextArray<N, G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = CiphertextArray<N, G>;
+
+    fn mul(self, rhs: S) -> Self::Output {
+        CiphertextArray(core::array::from_fn(|i| self.0[i] * rhs))
+    }
+}
+
+impl<const N: usize, G, S> Mul<S> for &CiphertextArray<N, G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = CiphertextArray<N, G>;
+
+    fn mul(self, rhs: S) -> Self::Output {
+        *self * rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{
+        compress_batch_into, Ciphertext, CiphertextArray, CompressedCiphertext, DecryptionKey,
+        Error, Identity, RistrettoPoint, Scalar,
+    };
+
+    const N: usize = 4;
+
+    // Test the batch operations with exact-fit, too-small and too-large output buffers.
+    #[test]
+    fn batch_into_buffers() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let msgs: [_; N] = core::array::from_fn(|_| RistrettoPoint::random(&mut rng));
+        let rs: [_; N] = core::array::from_fn(|_| Scalar::random(&mut rng));
+
+        let mut cts = [Ciphertext::identity(); N];
+        ek.encrypt_batch_into(&msgs, &rs, &mut cts).unwrap();
+        for i in 0..N {
+            assert_eq!(cts[i], ek.encrypt_with(msgs[i], rs[i]));
+        }
+        let mut decrypted = [RistrettoPoint::identity(); N];
+        dk.decrypt_batch_into(&cts, &mut decrypted).unwrap();
+        assert_eq!(decrypted, msgs);
+        let mut compressed = [CompressedCiphertext::from_bytes(&[0; 64]); N];
+        compress_batch_into(&cts, &mut compressed).unwrap();
+        for i in 0..N {
+            assert_eq!(compressed[i], cts[i].compress());
+        }
+
+        for len in [N - 1, N + 1] {
+            let mismatch = Err(Error::LengthMismatch {
+                expected: N,
+                got: len,
+            });
+            let mut cts_out = [Ciphertext::identity(); N + 1];
+            assert_eq!(
+                ek.encrypt_batch_into(&msgs, &rs, &mut cts_out[..len]),
+                mismatch
+            );
+            assert!(cts_out.iter().all(|ct| *ct == Ciphertext::identity()));
+            let mut points_out = [RistrettoPoint::identity(); N + 1];
+            assert_eq!(
+                dk.decrypt_batch_into(&cts, &mut points_out[..len]),
+                mismatch
+            );
+            let mut compressed_out = [CompressedCiphertext::from_bytes(&[0; 64]); N + 1];
+            assert_eq!(
+                compress_batch_into(&cts, &mut compressed_out[..len]),
+                mismatch
+            );
+        }
+        assert_eq!(
+            ek.encrypt_batch_into(&msgs, &rs[..N - 1], &mut cts),
+            Err(Error::LengthMismatch {
+                expected: N,
+                got: N - 1
+            })
+        );
+
+        ek.encrypt_batch_into(&[], &[], &mut []).unwrap();
+    }
+
+    // Test that the elementwise operations on arrays act on the plaintexts position by position.
+    #[test]
+    fn ciphertext_array_ops() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let m1: [_; N] = core::array::from_fn(|_| RistrettoPoint::random(&mut rng));
+        let m2: [_; N] = core::array::from_fn(|_| RistrettoPoint::random(&mut rng));
+        let a = CiphertextArray::encrypt(ek, &m1, &mut rng);
+        let b = CiphertextArray::encrypt(ek, &m2, &mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let sum = (a + b).decrypt(&dk);
+        let diff = (a - b).decrypt(&dk);
+        let neg = (-a).decrypt(&dk);
+        let scaled = (a * s).decrypt(&dk);
+        let tripled = (&a * 3u64).decrypt(&dk);
+        for i in 0..N {
+            assert_eq!(sum[i], m1[i] + m2[i]);
+            assert_eq!(diff[i], m1[i] - m2[i]);
+            assert_eq!(neg[i], -m1[i]);
+            assert_eq!(scaled[i], m1[i] * s);
+            assert_eq!(tripled[i], m1[i] + m1[i] + m1[i]);
+        }
+        assert_eq!(dk.decrypt(a.sum()), m1.iter().sum());
+        assert_eq!(
+            (a + CiphertextArray::identity()).decrypt(&dk),
+            a.decrypt(&dk)
+        );
+
+        let rerandomised = a.rerandomise(ek, &mut rng);
+        assert_ne!(rerandomised, a);
+        assert_eq!(rerandomised.decrypt(&dk), m1);
+        let compressed = a.compress();
+        assert_eq!(compressed[1].decompress().unwrap(), a[1]);
+    }
+}
@@ -0,0 +1,163 @@
+// Bech32 encoding of keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ::bech32::primitives::decode::{
+    CharError, CheckedHrpstring, CheckedHrpstringError, UncheckedHrpstringError,
+};
+use ::bech32::{Bech32m, Hrp};
+use curve25519_dalek::ristretto::CompressedRistretto;
+
+use crate::{EncryptionKey, Error};
+
+impl EncryptionKey {
+    /// Encode the encryption key as a bech32m string with the human-readable prefix `hrp`.
+    ///
+    /// The data part is the 32-byte compressed point, so a typo in a pasted key is caught by the
+    /// checksum rather than silently producing a different key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, EncryptionKey};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let encoded = enc_key.to_bech32("elgpk").unwrap();
+    /// let (hrp, decoded) = EncryptionKey::from_bech32(&encoded).unwrap();
+    /// assert_eq!(hrp, "elgpk");
+    /// assert_eq!(enc_key, &decoded);
+    /// ```
+    pub fn to_bech32(&self, hrp: &str) -> Result<String, Error> {
+        let hrp = Hrp::parse(hrp).map_err(|_| Error::InvalidHrp)?;
+        ::bech32::encode::<Bech32m>(hrp, self.0.compress().as_bytes())
+            .map_err(|_| Error::InvalidEncoding)
+    }
+
+    /// Decode an encryption key from a bech32m string, returning the (lower-case) human-readable
+    /// prefix alongside the key so the caller can check it is the one they expect.
+    pub fn from_bech32(s: &str) -> Result<(String, EncryptionKey), Error> {
+        let checked = CheckedHrpstring::new::<Bech32m>(s).map_err(|e| match e {
+            CheckedHrpstringError::Checksum(_) => Error::InvalidChecksum,
+            CheckedHrpstringError::Parse(UncheckedHrpstringError::Char(CharError::MixedCase)) => {
+                Error::MixedCase
+            }
+            CheckedHrpstringError::Parse(UncheckedHrpstringError::Hrp(_)) => Error::InvalidHrp,
+            _ => Error::InvalidEncoding,
+        })?;
+
+        let data: Vec<u8> = checked.byte_iter().collect();
+        if data.len() != 32 {
+            return Err(Error::LengthMismatch {
+                expected: 32,
+                got: data.len(),
+            });
+        }
+
+        let point = CompressedRistretto::from_slice(&data)
+            .map_err(|_| Error::InvalidPoint)?
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        Ok((checked.hrp().to_lowercase(), EncryptionKey(point)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::bech32::{Bech32m, Hrp};
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, EncryptionKey, Error, Scalar};
+
+    // Test that encoding and decoding an encryption key produces an unchanged result.
+    #[test]
+    fn bech32_round_trip() {
+        const N: usize = 100;
+
+        let mut rng = StdRng::from_entropy();
+
+        for _ in 0..N {
+            let ek = *DecryptionKey::new(&mut rng).encryption_key();
+            let encoded = ek.to_bech32("elgpk").unwrap();
+            let (hrp, decoded) = EncryptionKey::from_bech32(&encoded).unwrap();
+            assert_eq!(hrp, "elgpk");
+            assert_eq!(ek, decoded);
+
+            // Upper case strings are valid as long as they are not mixed.
+            let (hrp, decoded) = EncryptionKey::from_bech32(&encoded.to_uppercase()).unwrap();
+            assert_eq!(hrp, "elgpk");
+            assert_eq!(ek, decoded);
+        }
+    }
+
+    // Test the encoding against a fixed vector, so that it can never silently change.
+    #[test]
+    fn bech32_known_vector() {
+        let ek = *DecryptionKey::from(Scalar::ONE).encryption_key();
+        let encoded = ek.to_bech32("elgpk").unwrap();
+        assert_eq!(
+            encoded,
+            "elgpk1ute2uzn2h388r2yy49su2qz3tavwxzm25kpdmrdk5ev5tcyd94mqqgt3ac"
+        );
+    }
+
+    // Test that each kind of malformed input produces its own error.
+    #[test]
+    fn bech32_errors() {
+        let ek = *DecryptionKey::from(Scalar::ONE).encryption_key();
+        let encoded = ek.to_bech32("elgpk").unwrap();
+
+        // Flip the last character of the checksum.
+        let mut corrupted = encoded.clone();
+        let last = corrupted.pop().unwrap();
+        corrupted.push(if last == 'q' { 'p' } else { 'q' });
+        assert_eq!(
+            EncryptionKey::from_bech32(&corrupted).unwrap_err(),
+            Error::InvalidChecksum
+        );
+
+        let mut mixed = encoded.clone();
+        mixed.replace_range(0..1, "E");
+        assert_eq!(
+            EncryptionKey::from_bech32(&mixed).unwrap_err(),
+            Error::MixedCase
+        );
+
+        let hrp = Hrp::parse("elgpk").unwrap();
+        let short = ::bech32::encode::<Bech32m>(hrp, &[0u8; 31]).unwrap();
+        assert_eq!(
+            EncryptionKey::from_bech32(&short).unwrap_err(),
+            Error::LengthMismatch {
+                expected: 32,
+                got: 31
+            }
+        );
+
+        let not_a_point = ::bech32::encode::<Bech32m>(hrp, &[0xffu8; 32]).unwrap();
+        assert_eq!(
+            EncryptionKey::from_bech32(&not_a_point).unwrap_err(),
+            Error::InvalidPoint
+        );
+
+        assert_eq!(ek.to_bech32("").unwrap_err(), Error::InvalidHrp);
+    }
+}
@@ -0,0 +1,301 @@
+// Benaloh challenges (cast-or-spoil) for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Display, Formatter};
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::{Ciphertext, EncryptionKey};
+
+const FINGERPRINT_DOMAIN: &[u8] = b"rust-elgamal ciphertext fingerprint";
+
+/// Benaloh challenges, which let a voter check that their device encrypts honestly.
+///
+/// The device encrypts the voter's choice and shows a [CiphertextFingerprint] before learning
+/// what the voter will do next. The voter then either casts the ballot, after which the
+/// randomness is wiped, or spoils it, after which the device reveals the randomness and the
+/// choice so that an independent checker can confirm the encryption with
+/// [verify_spoiled](Self::verify_spoiled). A spoiled ballot is never cast, and the voter starts
+/// again. A device that encrypts anything else is caught with good probability after a few
+/// challenges, since it cannot tell in advance which ballots will be audited.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{BenalohChallenge, CiphertextFingerprint, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+/// let vote = RistrettoPoint::random(&mut rng);
+///
+/// // the voter challenges the device
+/// let (pending, shown) = BenalohChallenge::commit(enc_key, vote, &mut rng);
+/// let (ct, r, m) = pending.spoil();
+/// assert_eq!(CiphertextFingerprint::new(enc_key, &ct), shown);
+/// assert_eq!(m, vote);
+/// assert!(BenalohChallenge::verify_spoiled(enc_key, &ct, m, r));
+///
+/// // and then votes for real
+/// let (pending, shown) = BenalohChallenge::commit(enc_key, vote, &mut rng);
+/// let ct = pending.cast();
+/// assert_eq!(CiphertextFingerprint::new(enc_key, &ct), shown);
+/// assert_eq!(dec_key.decrypt(ct), vote);
+/// ```
+#[derive(Debug)]
+pub struct BenalohChallenge;
+
+impl BenalohChallenge {
+    /// Encrypt `m` under `ek`, returning the ballot waiting for the voter's decision and the
+    /// fingerprint to show them.
+    pub fn commit<R: RngCore + CryptoRng>(
+        ek: &EncryptionKey,
+        m: RistrettoPoint,
+        rng: &mut R,
+    ) -> (PendingBallot, CiphertextFingerprint) {
+        let r = Scalar::random(rng);
+        let pending = PendingBallot {
+            ek: *ek,
+            ct: ek.encrypt_with(m, r),
+            m,
+            r,
+        };
+        let fingerprint = pending.fingerprint();
+        (pending, fingerprint)
+    }
+
+    /// Check that `ct` is the encryption of `m` under `ek` with randomness `r`, as revealed by
+    /// [PendingBallot::spoil].
+    ///
+    /// The checker should also compare the [CiphertextFingerprint] of `ct` with the one the
+    /// device showed before the challenge.
+    pub fn verify_spoiled(
+        ek: &EncryptionKey,
+        ct: &Ciphertext,
+        m: RistrettoPoint,
+        r: Scalar,
+    ) -> bool {
+        ek.encrypt_with(m, r) == *ct
+    }
+}
+
+/// A ballot encrypted by [BenalohChallenge::commit], waiting to be cast or spoiled.
+///
+/// The ballot holds the choice and the randomness of its encryption, so it is neither `Copy` nor
+/// `Clone`, and both are wiped when it is cast or dropped.
+pub struct PendingBallot {
+    ek: EncryptionKey,
+    ct: Ciphertext,
+    m: RistrettoPoint,
+    r: Scalar,
+}
+
+impl PendingBallot {
+    /// The ciphertext the device committed to.
+    pub fn ciphertext(&self) -> &Ciphertext {
+        &self.ct
+    }
+
+    /// The fingerprint of the ciphertext, to show the voter.
+    pub fn fingerprint(&self) -> CiphertextFingerprint {
+        CiphertextFingerprint::new(&self.ek, &self.ct)
+    }
+
+    /// Cast the ballot, wiping the choice and the randomness.
+    pub fn cast(mut self) -> Ciphertext {
+        self.seal()
+    }
+
+    /// Spoil the ballot, revealing the ciphertext, the randomness and the choice for audit.
+    ///
+    /// The ciphertext must not be cast afterwards: anyone who has seen the audit can decrypt it.
+    pub fn spoil(self) -> (Ciphertext, Scalar, RistrettoPoint) {
+        (self.ct, self.r, self.m)
+    }
+
+    fn seal(&mut self) -> Ciphertext {
+        self.wipe();
+        self.ct
+    }
+
+    fn wipe(&mut self) {
+        // volatile writes so the compiler does not drop them as dead stores
+        unsafe {
+            core::ptr::write_volatile(&mut self.r, Scalar::ZERO);
+            core::ptr::write_volatile(&mut self.m, RistrettoPoint::identity());
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Drop for PendingBallot {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+impl Debug for PendingBallot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "PendingBallot {{ ct: {:?}, .. }}", self.ct)
+    }
+}
+
+/// A 32-byte fingerprint of a ciphertext and the key it was made under, shown to a voter before
+/// a [BenalohChallenge] so that the device cannot swap the ciphertext once it learns whether the
+/// ballot will be audited.
+///
+/// This is SHA-512 of the key and the ciphertext, domain-separated and truncated. It displays as
+/// lowercase hex.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CiphertextFingerprint([u8; 32]);
+
+impl CiphertextFingerprint {
+    /// The fingerprint of `ct` under `ek`.
+    pub fn new(ek: &EncryptionKey, ct: &Ciphertext) -> CiphertextFingerprint {
+        let digest = Sha512::new()
+            .chain_update((FINGERPRINT_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update(ek.0.compress().as_bytes())
+            .chain_update(ct.compress().to_bytes())
+            .finalize();
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest[..32]);
+        CiphertextFingerprint(out)
+    }
+
+    /// The bytes of the fingerprint.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl Display for CiphertextFingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{BenalohChallenge, CiphertextFingerprint, PendingBallot};
+    use crate::{DecryptionKey, Identity, RistrettoPoint, Scalar};
+
+    // Test that an honest device's spoiled ballots pass the audit, and its cast ballots decrypt
+    // to the choice.
+    #[test]
+    fn benaloh_honest_spoil() {
+        let mut rng = StdRng::seed_from_u64(487);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for _ in 0..10 {
+            let vote = RistrettoPoint::random(&mut rng);
+            let (pending, shown) = BenalohChallenge::commit(ek, vote, &mut rng);
+            assert_eq!(pending.fingerprint(), shown);
+            let (ct, r, m) = pending.spoil();
+            assert_eq!(m, vote);
+            assert_eq!(CiphertextFingerprint::new(ek, &ct), shown);
+            assert!(BenalohChallenge::verify_spoiled(ek, &ct, m, r));
+            assert!(!BenalohChallenge::verify_spoiled(
+                ek,
+                &ct,
+                m,
+                r + Scalar::ONE
+            ));
+        }
+
+        let vote = RistrettoPoint::random(&mut rng);
+        let (pending, shown) = BenalohChallenge::commit(ek, vote, &mut rng);
+        let ct = pending.cast();
+        assert_eq!(CiphertextFingerprint::new(ek, &ct), shown);
+        assert_eq!(dk.decrypt(ct), vote);
+        assert_eq!(format!("{}", shown).len(), 64);
+    }
+
+    // Test that the audit catches a device that encrypts another choice, whether it owns up to
+    // the choice or lies about it, and one that swaps the ciphertext after showing the
+    // fingerprint.
+    #[test]
+    fn benaloh_cheating_device() {
+        let mut rng = StdRng::seed_from_u64(487);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let vote = RistrettoPoint::random(&mut rng);
+        let other = RistrettoPoint::random(&mut rng);
+
+        let r = Scalar::random(&mut rng);
+        let cheat = PendingBallot {
+            ek: *ek,
+            ct: ek.encrypt_with(other, r),
+            m: vote,
+            r,
+        };
+        let shown = cheat.fingerprint();
+        let (ct, r, m) = cheat.spoil();
+        assert_eq!(CiphertextFingerprint::new(ek, &ct), shown);
+        // the revealed choice is the voter's, but the ciphertext does not hold it
+        assert_eq!(m, vote);
+        assert!(!BenalohChallenge::verify_spoiled(ek, &ct, m, r));
+        // the ciphertext checks out only against a choice the voter did not make
+        assert!(BenalohChallenge::verify_spoiled(ek, &ct, other, r));
+
+        let (pending, shown) = BenalohChallenge::commit(ek, vote, &mut rng);
+        let (_, r, m) = pending.spoil();
+        let swapped = ek.encrypt_with(m, r + Scalar::ONE);
+        assert_ne!(CiphertextFingerprint::new(ek, &swapped), shown);
+
+        // the fingerprint binds the key
+        let other_ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let (pending, shown) = BenalohChallenge::commit(ek, vote, &mut rng);
+        assert_ne!(
+            CiphertextFingerprint::new(&other_ek, pending.ciphertext()),
+            shown
+        );
+    }
+
+    // Test that casting wipes the randomness and the choice, and that neither shows in debug
+    // output.
+    #[test]
+    fn benaloh_cast_wipes() {
+        let mut rng = StdRng::seed_from_u64(487);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let vote = RistrettoPoint::random(&mut rng);
+
+        let (mut pending, _) = BenalohChallenge::commit(ek, vote, &mut rng);
+        assert!(!format!("{:?}", pending).contains("Scalar"));
+        let ct = pending.seal();
+        assert_eq!(pending.r, Scalar::ZERO);
+        assert_eq!(pending.m, RistrettoPoint::identity());
+        assert_eq!(dk.decrypt(ct), vote);
+        assert_eq!(pending.cast(), ct);
+    }
+}
@@ -0,0 +1,264 @@
+// Blinded decryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::Field;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, ElGamalRng};
+
+/// A ciphertext blinded by [Ciphertext::blind], to be sent to the holder of the decryption key
+/// for [DecryptionKey::decrypt_blinded].
+///
+/// For a ciphertext `(c1, c2)` encrypting `m`, the client draws a non-zero scalar `s` and a point
+/// `P` uniformly at random, and sends `(s c1, s (c2 + P))`, which decrypts to `s (m + P)`. The
+/// server's view is independent of the plaintext and of the original ciphertext:
+///
+/// * `m + P` is uniform whatever `m` is, and multiplying by `s` is a bijection, so the decrypted
+///   point `s (m + P)` is uniform and independent of `s`;
+/// * `s c1` depends only on `s` and on the blinding factor of the original ciphertext, so it is a
+///   uniform non-zero point independent of the decrypted one;
+/// * the second component is fixed by these two and the server's own key.
+///
+/// The server therefore sees a uniformly random query and answer, even for the identity message
+/// that multiplicative blinding alone would give away, and cannot link two blindings of the same
+/// ciphertext to each other. This protects the client from a curious server; it does not stop the
+/// server from answering with a wrong point, which the client cannot detect without a proof.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+///
+/// // client
+/// let (blinded, factor) = ct.blind(&mut rng);
+/// // server
+/// let answer = dec_key.decrypt_blinded(&blinded);
+/// // client
+/// assert_eq!(answer.unblind(&factor), m);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BlindedCiphertext<G = RistrettoPoint>(Ciphertext<G>);
+
+/// The server's answer to a [BlindedCiphertext], which only the client holding the
+/// [BlindingFactor] can turn into the plaintext.
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BlindedPlaintext<G = RistrettoPoint>(G);
+
+/// The secret the client keeps to unblind the answer to a [BlindedCiphertext]: the inverse of
+/// the scale and the offset point.
+///
+/// Whoever learns it can link the blinded query to the plaintext, so it is serialised only with
+/// the `serde-secret` feature, and debug output leaves it out.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-secret",
+    serde(bound(
+        serialize = "G: Serialize, G::Scalar: Serialize",
+        deserialize = "G: Deserialize<'de>, G::Scalar: Deserialize<'de>"
+    ))
+)]
+pub struct BlindingFactor<G: ElGamalGroup = RistrettoPoint> {
+    inverse: G::Scalar,
+    offset: G,
+}
+
+impl<G: ElGamalGroup> Ciphertext<G> {
+    /// Blind the ciphertext for decryption by a server that should not learn the plaintext,
+    /// returning the blinded ciphertext to send and the factor to keep for
+    /// [BlindedPlaintext::unblind].
+    ///
+    /// See [BlindedCiphertext] for the protocol and its security. Every call draws a fresh factor,
+    /// so a blinding must not be reused.
+    pub fn blind<R: ElGamalRng<V>, V>(
+        &self,
+        rng: &mut R,
+    ) -> (BlindedCiphertext<G>, BlindingFactor<G>) {
+        let mut rng = rng.compat();
+        let (scale, inverse) = loop {
+            let scale = G::Scalar::random(&mut rng);
+            if let Some(inverse) = Option::<G::Scalar>::from(scale.invert()) {
+                break (scale, inverse);
+            }
+        };
+        let offset = G::random(&mut rng);
+
+        let blinded = Ciphertext(self.0 * scale, (self.1 + offset) * scale);
+        (
+            BlindedCiphertext(blinded),
+            BlindingFactor { inverse, offset },
+        )
+    }
+}
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Decrypt a ciphertext blinded by [Ciphertext::blind], without learning its plaintext.
+    ///
+    /// This is ordinary decryption; the answer is only meaningful to the client holding the
+    /// blinding factor.
+    pub fn decrypt_blinded(&self, blinded: &BlindedCiphertext<G>) -> BlindedPlaintext<G> {
+        BlindedPlaintext(self.decrypt(blinded.0))
+    }
+}
+
+impl<G: ElGamalGroup> BlindedCiphertext<G> {
+    /// The blinded ciphertext, for transports that carry plain ciphertexts.
+    pub fn as_ciphertext(&self) -> &Ciphertext<G> {
+        &self.0
+    }
+}
+
+impl<G: ElGamalGroup> From<Ciphertext<G>> for BlindedCiphertext<G> {
+    /// Treat a ciphertext received from a client as blinded.
+    fn from(ct: Ciphertext<G>) -> Self {
+        BlindedCiphertext(ct)
+    }
+}
+
+impl<G: ElGamalGroup> BlindedPlaintext<G> {
+    /// Remove the blinding with the factor returned by [Ciphertext::blind], recovering the
+    /// plaintext of the original ciphertext.
+    ///
+    /// With the factor of another blinding the result is an unrelated point.
+    pub fn unblind(&self, factor: &BlindingFactor<G>) -> G {
+        self.0 * factor.inverse - factor.offset
+    }
+
+    /// The blinded point, for transports that carry plain points.
+    pub fn as_point(&self) -> &G {
+        &self.0
+    }
+}
+
+impl<G: ElGamalGroup> From<G> for BlindedPlaintext<G> {
+    /// Treat a point received from a server as a blinded answer.
+    fn from(point: G) -> Self {
+        BlindedPlaintext(point)
+    }
+}
+
+impl<G: ElGamalGroup> Debug for BlindedCiphertext<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("BlindedCiphertext").field(&self.0).finish()
+    }
+}
+
+impl<G: ElGamalGroup> Debug for BlindedPlaintext<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("BlindedPlaintext(")?;
+        self.0.fmt_point(f)?;
+        f.write_str(")")
+    }
+}
+
+impl<G: ElGamalGroup> Debug for BlindingFactor<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("BlindingFactor(<redacted>)")
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    // Test that unblinding recovers what ordinary decryption gives, including for the identity,
+    // and that blinding twice gives unrelated queries and answers.
+    #[test]
+    fn blind_decrypt_round_trip() {
+        let mut rng = StdRng::seed_from_u64(514);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let messages = [
+            RistrettoPoint::random(&mut rng),
+            &Scalar::from(7u32) * GENERATOR_TABLE,
+            RistrettoPoint::default(),
+        ];
+        for m in messages {
+            let ct = ek.encrypt(m, &mut rng);
+            let (blinded, factor) = ct.blind(&mut rng);
+            let answer = dk.decrypt_blinded(&blinded);
+            assert_eq!(answer.unblind(&factor), dk.decrypt(ct));
+            assert_eq!(answer.unblind(&factor), m);
+            assert_ne!(*answer.as_point(), m);
+
+            let (again, other_factor) = ct.blind(&mut rng);
+            assert_ne!(again.as_ciphertext().0, blinded.as_ciphertext().0);
+            assert_ne!(again.as_ciphertext().1, blinded.as_ciphertext().1);
+            assert_ne!(again.as_ciphertext().0, ct.0);
+            let other_answer = dk.decrypt_blinded(&again);
+            assert_ne!(other_answer, answer);
+            assert_eq!(other_answer.unblind(&other_factor), m);
+            assert_ne!(other_answer.unblind(&factor), m);
+        }
+    }
+
+    // Test that blinding works on exponential ciphertexts and sums of them, and that the
+    // conversions let the protocol run over transports carrying plain ciphertexts and points.
+    #[test]
+    fn blind_decrypt_conversions() {
+        let mut rng = StdRng::seed_from_u64(514);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let sum = ek.exp_encrypt(Scalar::from(3u32), &mut rng)
+            + ek.exp_encrypt(Scalar::from(4u32), &mut rng);
+        let (blinded, factor) = sum.blind(&mut rng);
+        let received = super::BlindedCiphertext::from(*blinded.as_ciphertext());
+        let answer = dk.decrypt_blinded(&received);
+        let returned = super::BlindedPlaintext::from(*answer.as_point());
+        assert_eq!(
+            returned.unblind(&factor),
+            &Scalar::from(7u32) * GENERATOR_TABLE
+        );
+
+        assert_eq!(alloc::format!("{:?}", factor), "BlindingFactor(<redacted>)");
+
+        #[cfg(feature = "enable-serde")]
+        {
+            let encoded = bincode::serialize(&blinded).unwrap();
+            assert_eq!(encoded.len(), 64);
+            let decoded: super::BlindedCiphertext = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(decoded, blinded);
+            let encoded = bincode::serialize(&answer).unwrap();
+            let decoded: super::BlindedPlaintext = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(decoded, answer);
+        }
+        #[cfg(feature = "serde-secret")]
+        {
+            let encoded = bincode::serialize(&factor).unwrap();
+            let decoded: super::BlindingFactor = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(answer.unblind(&decoded), returned.unblind(&factor));
+        }
+    }
+}
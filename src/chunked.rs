@@ -0,0 +1,178 @@
+// Chunked encryption of byte strings for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{decode, encode, FINAL_FLAG, POINT_MSG_LEN};
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error};
+
+impl EncryptionKey {
+    /// Encrypt an arbitrary byte string as a sequence of ciphertexts, one per 28-byte chunk.
+    ///
+    /// Each chunk is encoded into a point together with its length, a checksum, and a flag
+    /// marking the last chunk, so the ciphertexts keep the homomorphic properties of
+    /// [encrypt](Self::encrypt) (they can be rerandomised, for instance) while
+    /// [decrypt_bytes_chunked](DecryptionKey::decrypt_bytes_chunked) can detect a corrupted or
+    /// truncated sequence. The empty string is encrypted as a single empty chunk.
+    ///
+    /// The ciphertexts are not bound to their positions, so the chunks of a message can be
+    /// reordered or dropped from the front without detection; if that matters and the
+    /// homomorphism does not, use `seal` (with the `hybrid` feature) instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let msg = b"a message rather longer than a single chunk";
+    /// let cts = dec_key.encryption_key().encrypt_bytes_chunked(msg, &mut rng);
+    /// assert_eq!(cts.len(), 2);
+    /// assert_eq!(dec_key.decrypt_bytes_chunked(&cts).unwrap(), msg);
+    /// ```
+    pub fn encrypt_bytes_chunked<R: RngCore + CryptoRng>(
+        &self,
+        data: &[u8],
+        rng: &mut R,
+    ) -> Vec<Ciphertext> {
+        if data.is_empty() {
+            return Vec::from([self.encrypt(encode(&[], FINAL_FLAG), rng)]);
+        }
+
+        let n = data.len().div_ceil(POINT_MSG_LEN);
+        data.chunks(POINT_MSG_LEN)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let flag = if i + 1 == n { FINAL_FLAG } else { 0 };
+                self.encrypt(encode(chunk, flag), rng)
+            })
+            .collect()
+    }
+}
+
+impl DecryptionKey {
+    /// Decrypt a sequence of ciphertexts produced by
+    /// [EncryptionKey::encrypt_bytes_chunked](crate::EncryptionKey::encrypt_bytes_chunked).
+    ///
+    /// Fails with [Error::VerificationFailed] if a chunk does not decrypt to a valid encoding
+    /// (it was corrupted, or encrypted under a different key), and with [Error::InvalidEncoding]
+    /// if the chunks do not form a complete message (it is empty, truncated, or a chunk before
+    /// the last is not full).
+    pub fn decrypt_bytes_chunked(&self, cts: &[Ciphertext]) -> Result<Vec<u8>, Error> {
+        if cts.is_empty() {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut data = Vec::with_capacity(cts.len() * POINT_MSG_LEN);
+        for (i, ct) in cts.iter().enumerate() {
+            let (payload, meta) = decode(&self.decrypt(*ct)).ok_or(Error::VerificationFailed)?;
+            let len = (meta & !FINAL_FLAG) as usize;
+            let last = i + 1 == cts.len();
+
+            if (meta & FINAL_FLAG != 0) != last || (!last && len != POINT_MSG_LEN) {
+                return Err(Error::InvalidEncoding);
+            }
+            data.extend_from_slice(&payload[..len]);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand::RngCore;
+    use rand_core::SeedableRng;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::{DecryptionKey, Error, RistrettoPoint};
+
+    // Test that messages of every interesting length round-trip, with the expected number of
+    // chunks.
+    #[test]
+    fn chunked_lengths() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for (len, chunks) in [
+            (0, 1),
+            (1, 1),
+            (27, 1),
+            (28, 1),
+            (29, 2),
+            (56, 2),
+            (57, 3),
+            (1000, 36),
+        ] {
+            let mut msg = vec![0u8; len];
+            rng.fill_bytes(&mut msg);
+
+            let cts = ek.encrypt_bytes_chunked(&msg, &mut rng);
+            assert_eq!(cts.len(), chunks);
+            assert_eq!(dk.decrypt_bytes_chunked(&cts).unwrap(), msg);
+
+            // the chunks survive rerandomisation
+            let cts: Vec<_> = cts.iter().map(|ct| ek.rerandomise(*ct, &mut rng)).collect();
+            assert_eq!(dk.decrypt_bytes_chunked(&cts).unwrap(), msg);
+        }
+    }
+
+    // Test that corrupted, truncated and foreign sequences are rejected.
+    #[test]
+    fn chunked_invalid() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let msg = [7u8; 80];
+        let cts = ek.encrypt_bytes_chunked(&msg, &mut rng);
+        assert_eq!(cts.len(), 3);
+
+        let mut corrupted = cts.clone();
+        corrupted[1] = corrupted[1] + ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        assert_eq!(
+            dk.decrypt_bytes_chunked(&corrupted),
+            Err(Error::VerificationFailed)
+        );
+
+        assert_eq!(
+            dk.decrypt_bytes_chunked(&cts[..2]),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(dk.decrypt_bytes_chunked(&[]), Err(Error::InvalidEncoding));
+
+        let mut swapped = cts.clone();
+        swapped.swap(1, 2);
+        assert_eq!(
+            dk.decrypt_bytes_chunked(&swapped),
+            Err(Error::InvalidEncoding)
+        );
+
+        let other = DecryptionKey::new(&mut rng);
+        assert_eq!(
+            other.decrypt_bytes_chunked(&cts),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
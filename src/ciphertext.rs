@@ -14,53 +14,295 @@
 // limitations under the License.
 
 use core::fmt::{Debug, Formatter};
-use core::ops::{Add, Neg, Mul, Sub};
+use core::ops::{Add, Neg, Mul, MulAssign, Sub};
 
-use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::traits::Identity;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+#[cfg(feature = "alloc")]
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+#[cfg(feature = "alloc")]
+use curve25519_dalek::traits::MultiscalarMul;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Serialize, Deserialize};
 
+#[cfg(feature = "alloc")]
+use ff::Field;
+#[cfg(feature = "alloc")]
+use rand_core::{CryptoRng, RngCore};
+
+use crate::backend::DebugPoint;
+#[cfg(feature = "alloc")]
+use crate::trace::op_span;
+use crate::{ElGamalGroup, Error, ScalarOperand};
+#[cfg(feature = "alloc")]
+use crate::EncryptionKey;
+
 /// An ElGamal ciphertext.
 ///
 /// Represented as a pair of the form (rG, M + rY) where r is a blinding factor, G is the group
 /// generator, M is the message, and Y is the public key.
 #[derive(Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
-pub struct Ciphertext(pub(crate) RistrettoPoint, pub(crate) RistrettoPoint);
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(concrete(G = crate::ts::Point)))]
+pub struct Ciphertext<G = RistrettoPoint>(pub(crate) G, pub(crate) G);
 
-impl Ciphertext {
+impl<G: ElGamalGroup> Ciphertext<G> {
     /// Returns the pair-of-points representation of the ciphertext. Intended for advanced use only.
-    pub fn inner(&self) -> (RistrettoPoint, RistrettoPoint) {
+    pub fn inner(&self) -> (G, G) {
         (self.0, self.1)
     }
 }
 
-impl Debug for Ciphertext {
+impl Ciphertext {
+    /// Compress both points of the ciphertext for transit.
+    pub fn compress(&self) -> CompressedCiphertext {
+        CompressedCiphertext(self.0.compress(), self.1.compress())
+    }
+}
+
+impl<G: ElGamalGroup> Debug for Ciphertext<G> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Ciphertext({:?}, {:?})", self.0.compress(), self.1.compress())
+        write!(f, "Ciphertext({:?}, {:?})", DebugPoint(&self.0), DebugPoint(&self.1))
+    }
+}
+
+/// An ElGamal ciphertext with both points compressed, as produced by
+/// [Ciphertext::compress](crate::Ciphertext::compress) or [compress_batch](crate::compress_batch).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct CompressedCiphertext(
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Point"))] pub(crate) CompressedRistretto,
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Point"))] pub(crate) CompressedRistretto,
+);
+
+impl CompressedCiphertext {
+    /// Returns the pair-of-points representation of the compressed ciphertext. Intended for
+    /// advanced use only.
+    pub fn inner(&self) -> (CompressedRistretto, CompressedRistretto) {
+        (self.0, self.1)
+    }
+
+    /// Attempt to decompress both points of the ciphertext.
+    pub fn decompress(&self) -> Result<Ciphertext, Error> {
+        let c1 = self.0.decompress().ok_or(Error::InvalidPoint)?;
+        let c2 = self.1.decompress().ok_or(Error::InvalidPoint)?;
+        Ok(Ciphertext(c1, c2))
+    }
+
+    /// The 64-byte encoding of the ciphertext: the two compressed points in order.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.0.as_bytes());
+        bytes[32..].copy_from_slice(self.1.as_bytes());
+        bytes
+    }
+
+    /// Read a compressed ciphertext from its 64-byte encoding. The points are not checked until
+    /// [decompress](Self::decompress) is called.
+    pub fn from_bytes(bytes: &[u8; 64]) -> CompressedCiphertext {
+        let mut c1 = [0u8; 32];
+        let mut c2 = [0u8; 32];
+        c1.copy_from_slice(&bytes[..32]);
+        c2.copy_from_slice(&bytes[32..]);
+        CompressedCiphertext(CompressedRistretto(c1), CompressedRistretto(c2))
+    }
+}
+
+/// Compress a slice of ciphertexts, one point at a time.
+///
+/// This is a convenience for serialising many ciphertexts at once, and costs the same as calling
+/// [Ciphertext::compress] on each. There is no cheaper batched path: `curve25519-dalek` 4 only
+/// batches the compression of *doubled* points (`RistrettoPoint::double_and_compress_batch`),
+/// sharing one field inversion between them, and halving a point first costs a full scalar
+/// multiplication, far more than the inverse square root that compressing it directly takes. The
+/// field arithmetic is private to `curve25519-dalek`, so the work cannot be shared any other way.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{compress_batch, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let cts: Vec<_> = (0..10)
+///     .map(|_| enc_key.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+///     .collect();
+/// let compressed = compress_batch(&cts);
+/// assert_eq!(compressed[3].decompress().unwrap(), cts[3]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn compress_batch(cts: &[Ciphertext]) -> Vec<CompressedCiphertext> {
+    cts.iter().map(Ciphertext::compress).collect()
+}
+
+/// The Lagrange coefficients for interpolating at `x` from the values of a polynomial at `xs`.
+///
+/// The value at `x` of the polynomial of degree less than `xs.len()` through the points
+/// `(xs[i], y[i])` is `Σ λ[i] y[i]`, for the returned `λ`. The coefficients depend only on the
+/// x-coordinates, so they can be computed once and reused for many sets of values, in the clear
+/// or in the exponent with [Ciphertext::interpolate].
+///
+/// Fails with [Error::DuplicateIndex] if two of `xs` are equal.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::{lagrange_coefficients, Scalar};
+///
+/// // f(x) = 3 + 2x, known at x = 1 and x = 2
+/// let xs = [Scalar::from(1u32), Scalar::from(2u32)];
+/// let ys = [Scalar::from(5u32), Scalar::from(7u32)];
+/// let lambda = lagrange_coefficients(&xs, Scalar::ZERO).unwrap();
+/// assert_eq!(lambda[0] * ys[0] + lambda[1] * ys[1], Scalar::from(3u32));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn lagrange_coefficients(xs: &[Scalar], x: Scalar) -> Result<Vec<Scalar>, Error> {
+    xs.iter()
+        .enumerate()
+        .map(|(i, xi)| {
+            let mut num = Scalar::ONE;
+            let mut den = Scalar::ONE;
+            for (j, xj) in xs.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                if xi == xj {
+                    return Err(Error::DuplicateIndex);
+                }
+                num *= x - xj;
+                den *= xi - xj;
+            }
+            Ok(num * den.invert())
+        })
+        .collect()
+}
+
+impl Ciphertext {
+    /// Interpolate in the exponent: given encryptions of the values of a polynomial `f` at
+    /// distinct points, compute an encryption of `f(x)`.
+    ///
+    /// With `points` holding `(x_i, Enc(f(x_i) G))` for at least `deg f + 1` distinct `x_i`, all
+    /// under the same key, the result encrypts `f(x) G`; interpolating at zero gives an
+    /// encryption of the constant term, as in publicly verifiable secret sharing. Both components
+    /// are combined with one multiscalar multiplication each, using the coefficients from
+    /// [lagrange_coefficients]. No points give the identity ciphertext.
+    ///
+    /// Fails with [Error::DuplicateIndex] if two x-coordinates are equal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{Ciphertext, DecryptionKey, Scalar, GENERATOR_TABLE};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// // f(x) = 3 + 2x, encrypted at x = 1 and x = 2
+    /// let points: Vec<_> = [(1u32, 5u32), (2, 7)]
+    ///     .iter()
+    ///     .map(|&(x, y)| {
+    ///         let m = &Scalar::from(y) * GENERATOR_TABLE;
+    ///         (Scalar::from(x), enc_key.encrypt(m, &mut rng))
+    ///     })
+    ///     .collect();
+    /// let ct = Ciphertext::interpolate(&points, Scalar::ZERO).unwrap();
+    /// assert_eq!(dec_key.decrypt(ct), &Scalar::from(3u32) * GENERATOR_TABLE);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn interpolate(points: &[(Scalar, Ciphertext)], x: Scalar) -> Result<Ciphertext, Error> {
+        let _span = op_span!("interpolate", points = points.len());
+        let xs: Vec<Scalar> = points.iter().map(|(xi, _)| *xi).collect();
+        let lambda = lagrange_coefficients(&xs, x)?;
+        let c1 = RistrettoPoint::multiscalar_mul(&lambda, points.iter().map(|(_, ct)| ct.0));
+        let c2 = RistrettoPoint::multiscalar_mul(&lambda, points.iter().map(|(_, ct)| ct.1));
+        Ok(Ciphertext(c1, c2))
+    }
+}
+
+impl<G: ElGamalGroup> Ciphertext<G> {
+    /// Split the ciphertext into `k` ciphertexts under `ek` whose homomorphic sum is the original,
+    /// so that it can be handed out to `k` parties none of whom holds it alone.
+    ///
+    /// The first `k - 1` parts are fresh encryptions of independent uniformly random points, and
+    /// the last is the original minus their sum, so each part on its own decrypts to a uniformly
+    /// random point. `ek` must be the key the ciphertext is encrypted under.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{Ciphertext, DecryptionKey, RistrettoPoint};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = RistrettoPoint::random(&mut rng);
+    /// let ct = enc_key.encrypt(m, &mut rng);
+    /// let parts = ct.split_additive(3, enc_key, &mut rng);
+    /// assert_eq!(dec_key.decrypt(Ciphertext::recombine(&parts)), m);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn split_additive<R: RngCore + CryptoRng>(
+        &self,
+        k: usize,
+        ek: &EncryptionKey<G>,
+        rng: &mut R,
+    ) -> Vec<Ciphertext<G>> {
+        assert!(k > 0, "cannot split a ciphertext into zero parts");
+        let mut last = *self;
+        let mut parts: Vec<_> = (1..k)
+            .map(|_| {
+                let part = ek.encrypt_with(G::random(&mut *rng), G::Scalar::random(&mut *rng));
+                last = last - part;
+                part
+            })
+            .collect();
+        parts.push(last);
+        parts
+    }
+
+    /// The homomorphic sum of the parts from [split_additive](Self::split_additive), which
+    /// encrypts the original message. The sum of no parts is the identity ciphertext.
+    pub fn recombine(parts: &[Ciphertext<G>]) -> Ciphertext<G> {
+        parts.iter().fold(Ciphertext::identity(), |sum, part| sum + part)
     }
 }
 
 // Conversion traits
 
-impl From<(RistrettoPoint, RistrettoPoint)> for Ciphertext {
-    fn from(pair: (RistrettoPoint, RistrettoPoint)) -> Self {
+impl<G: ElGamalGroup> From<(G, G)> for Ciphertext<G> {
+    fn from(pair: (G, G)) -> Self {
         Self(pair.0, pair.1)
     }
 }
 
 // Arithmetic traits for homomorphisms
 
-impl Identity for Ciphertext {
+impl<G: ElGamalGroup> Identity for Ciphertext<G> {
     fn identity() -> Self {
-        Self(RistrettoPoint::identity(), RistrettoPoint::identity())
+        Self(G::identity(), G::identity())
     }
 }
 
-impl Add for Ciphertext {
+impl<G: ElGamalGroup> Add for Ciphertext<G> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -68,31 +310,31 @@ impl Add for Ciphertext {
     }
 }
 
-impl Add for &Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Add for &Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Ciphertext(&self.0 + &rhs.0, &self.1 + &rhs.1)
+        Ciphertext(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Add<&Ciphertext> for Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Add<&Ciphertext<G>> for Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
-    fn add(self, rhs: &Ciphertext) -> Self::Output {
-        Ciphertext(self.0 + &rhs.0, self.1 + &rhs.1)
+    fn add(self, rhs: &Ciphertext<G>) -> Self::Output {
+        Ciphertext(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Add<Ciphertext> for &Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Add<Ciphertext<G>> for &Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
-    fn add(self, rhs: Ciphertext) -> Self::Output {
-        Ciphertext(&self.0 + rhs.0, &self.1 + rhs.1)
+    fn add(self, rhs: Ciphertext<G>) -> Self::Output {
+        Ciphertext(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Sub for Ciphertext {
+impl<G: ElGamalGroup> Sub for Ciphertext<G> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -100,86 +342,265 @@ impl Sub for Ciphertext {
     }
 }
 
-impl Sub for &Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Sub for &Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Ciphertext(&self.0 - &rhs.0, &self.1 - &rhs.1)
+        Ciphertext(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<&Ciphertext> for Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Sub<&Ciphertext<G>> for Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
-    fn sub(self, rhs: &Ciphertext) -> Self::Output {
-        Ciphertext(self.0 - &rhs.0, self.1 - &rhs.1)
+    fn sub(self, rhs: &Ciphertext<G>) -> Self::Output {
+        Ciphertext(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<Ciphertext> for &Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Sub<Ciphertext<G>> for &Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
-    fn sub(self, rhs: Ciphertext) -> Self::Output {
-        Ciphertext(&self.0 - rhs.0, &self.1 - rhs.1)
+    fn sub(self, rhs: Ciphertext<G>) -> Self::Output {
+        Ciphertext(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Neg for Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Neg for Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
     fn neg(self) -> Self::Output {
         Ciphertext(-self.0, -self.1)
     }
 }
 
-impl Neg for &Ciphertext {
-    type Output = Ciphertext;
+impl<G: ElGamalGroup> Neg for &Ciphertext<G> {
+    type Output = Ciphertext<G>;
 
     fn neg(self) -> Self::Output {
         Ciphertext(-self.0, -self.1)
     }
 }
 
-impl Mul<Scalar> for Ciphertext {
-    type Output = Ciphertext;
-
-    fn mul(self, rhs: Scalar) -> Self::Output {
-        Ciphertext(self.0 * rhs, self.1 * rhs)
-    }
-}
-
-impl Mul<Scalar> for &Ciphertext {
-    type Output = Ciphertext;
-
-    fn mul(self, rhs: Scalar) -> Self::Output {
-        Ciphertext(self.0 * rhs, self.1 * rhs)
+// Generic over the operand so that scalars by value and by reference and small integers are all
+// covered: separate impls for them would overlap as far as coherence can tell.
+impl<G, S> Mul<S> for Ciphertext<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = Ciphertext<G>;
+
+    fn mul(self, rhs: S) -> Self::Output {
+        Ciphertext(rhs.mul_point(self.0), rhs.mul_point(self.1))
     }
 }
 
-impl Mul<&Scalar> for Ciphertext {
-    type Output = Ciphertext;
+impl<G, S> Mul<S> for &Ciphertext<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = Ciphertext<G>;
 
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Ciphertext(self.0 * rhs, self.1 * rhs)
+    fn mul(self, rhs: S) -> Self::Output {
+        *self * rhs
     }
 }
 
-impl Mul<&Scalar> for &Ciphertext {
-    type Output = Ciphertext;
-
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Ciphertext(self.0 * rhs, self.1 * rhs)
+impl<G, S> MulAssign<S> for Ciphertext<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    fn mul_assign(&mut self, rhs: S) {
+        *self = *self * rhs;
     }
 }
 
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "alloc")]
 #[cfg(test)]
 mod tests {
     use rand::prelude::StdRng;
     use rand_core::SeedableRng;
 
-    use crate::{DecryptionKey, RistrettoPoint};
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    use crate::{
+        compress_batch, lagrange_coefficients, Ciphertext, CompressedCiphertext, DecryptionKey,
+        Error, Identity, RistrettoPoint, Scalar, GENERATOR_TABLE,
+    };
+
+    // Test that batch compression agrees with compressing each ciphertext individually.
+    #[test]
+    fn compress_batch_matches_compress() {
+        const N: usize = 100;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let cts: Vec<_> = (0..N)
+            .map(|_| ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+            .collect();
+        let batch = compress_batch(&cts);
+        assert_eq!(batch.len(), N);
+
+        for (ct, compressed) in cts.iter().zip(batch.iter()) {
+            assert_eq!(&ct.compress(), compressed);
+            assert_eq!(compressed.decompress().unwrap(), *ct);
+
+            let bytes = compressed.to_bytes();
+            assert_eq!(bytes[..32], *ct.0.compress().as_bytes());
+            assert_eq!(bytes[32..], *ct.1.compress().as_bytes());
+        }
+
+        assert!(compress_batch(&[]).is_empty());
+    }
+
+    // Test that multiplying by integers agrees with multiplying by the scalar, on both sides of
+    // the double-and-add cutoff, and multiplies the plaintext.
+    #[test]
+    fn mul_integer() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = dk.encryption_key().encrypt(m, &mut rng);
+        let ct_ref = &ct;
+
+        for n in [0u64, 1, 2, 3, 7, 0xffff, 0x10000, u64::MAX] {
+            let expected = ct * Scalar::from(n);
+            assert_eq!(ct * n, expected);
+            assert_eq!(ct_ref * n, expected);
+            let mut assigned = ct;
+            assigned *= n;
+            assert_eq!(assigned, expected);
+            if let Ok(n) = u32::try_from(n) {
+                assert_eq!(ct * n, expected);
+            }
+        }
+        assert_eq!(dk.decrypt(ct * 3u64), m * Scalar::from(3u32));
+        assert_eq!(dk.decrypt(ct * 3u32), m + m + m);
+    }
+
+    // Test that each part of a split decrypts to an unrelated point, and that the parts
+    // recombine to the original.
+    #[test]
+    fn split_additive() {
+        let mut rng = StdRng::seed_from_u64(462);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = ek.encrypt(m, &mut rng);
+
+        for k in [1, 2, 5] {
+            let parts = ct.split_additive(k, ek, &mut rng);
+            assert_eq!(parts.len(), k);
+            assert_eq!(dk.decrypt(Ciphertext::recombine(&parts)), m);
+            if k == 1 {
+                assert_eq!(parts[0], ct);
+                continue;
+            }
+
+            let decrypted: Vec<_> = parts.iter().map(|part| dk.decrypt(*part)).collect();
+            for (i, p) in decrypted.iter().enumerate() {
+                assert_ne!(*p, m);
+                assert_ne!(*p, RistrettoPoint::identity());
+                assert!(decrypted[i + 1..].iter().all(|q| q != p));
+            }
+        }
+
+        // splitting again gives fresh parts
+        let a = ct.split_additive(3, ek, &mut rng);
+        let b = ct.split_additive(3, ek, &mut rng);
+        assert!(a.iter().zip(&b).all(|(x, y)| x != y));
+        assert_eq!(Ciphertext::recombine(&a), Ciphertext::recombine(&b));
+        assert_eq!(Ciphertext::<RistrettoPoint>::recombine(&[]), Ciphertext::identity());
+    }
+
+    // Test that interpolating encrypted evaluations of a polynomial at zero gives an encryption
+    // of its constant term, from any large enough subset of the evaluations.
+    #[test]
+    fn interpolate() {
+        let mut rng = StdRng::seed_from_u64(483);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        // f(x) = 7 + 3x + 5x^2
+        let coeffs = [7u32, 3, 5].map(Scalar::from);
+        let f = |x: Scalar| coeffs.iter().rev().fold(Scalar::ZERO, |acc, c| acc * x + c);
+        let points: Vec<_> = (1..=5u32)
+            .map(|x| {
+                let x = Scalar::from(x);
+                (x, ek.encrypt(&f(x) * GENERATOR_TABLE, &mut rng))
+            })
+            .collect();
+
+        let constant = &coeffs[0] * GENERATOR_TABLE;
+        for subset in [&points[..3], &points[2..], &points[..]] {
+            let ct = Ciphertext::interpolate(subset, Scalar::ZERO).unwrap();
+            assert_eq!(dk.decrypt(ct), constant);
+        }
+        // too few points interpolate a different polynomial
+        let ct = Ciphertext::interpolate(&points[..2], Scalar::ZERO).unwrap();
+        assert_ne!(dk.decrypt(ct), constant);
+
+        // at another point, and at one of the given points
+        let x = Scalar::from(10u32);
+        let ct = Ciphertext::interpolate(&points[..3], x).unwrap();
+        assert_eq!(dk.decrypt(ct), &f(x) * GENERATOR_TABLE);
+        let ct = Ciphertext::interpolate(&points, points[1].0).unwrap();
+        assert_eq!(ct, points[1].1);
+
+        // the coefficients are the ones applied to both components
+        let xs: Vec<_> = points[..3].iter().map(|(x, _)| *x).collect();
+        let lambda = lagrange_coefficients(&xs, Scalar::ZERO).unwrap();
+        assert_eq!(
+            lambda.iter().fold(Scalar::ZERO, |acc, l| acc + l),
+            Scalar::ONE
+        );
+        let manual = points[..3]
+            .iter()
+            .zip(&lambda)
+            .fold(Ciphertext::identity(), |acc, ((_, ct), l)| acc + ct * l);
+        assert_eq!(
+            Ciphertext::interpolate(&points[..3], Scalar::ZERO),
+            Ok(manual)
+        );
+        assert_eq!(
+            Ciphertext::interpolate(&[], Scalar::ZERO),
+            Ok(Ciphertext::identity())
+        );
+    }
+
+    // Test that repeated x-coordinates are rejected.
+    #[test]
+    fn interpolate_duplicate_x() {
+        let mut rng = StdRng::seed_from_u64(483);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let one = Scalar::ONE;
+
+        let points = [(one, ct), (Scalar::from(2u32), ct), (one, ct)];
+        assert_eq!(
+            Ciphertext::interpolate(&points, Scalar::ZERO),
+            Err(Error::DuplicateIndex)
+        );
+        assert_eq!(
+            lagrange_coefficients(&[one, one], Scalar::ZERO),
+            Err(Error::DuplicateIndex)
+        );
+    }
+
+    // Test that decompressing bytes which are not valid points fails cleanly.
+    #[test]
+    fn decompress_invalid() {
+        let compressed = CompressedCiphertext::from_bytes(&[0xff; 64]);
+        assert_eq!(compressed.decompress().unwrap_err(), Error::InvalidPoint);
+    }
 
+    #[cfg(feature = "enable-serde")]
     #[test]
     fn serde_ciphertext() {
         const N: usize = 100;
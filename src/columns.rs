@@ -0,0 +1,302 @@
+// Column-wise storage of ciphertexts for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+
+use curve25519_dalek::ristretto::{RistrettoBasepointTable, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, CompressedCiphertext, EncryptionKey, Error, GENERATOR_TABLE};
+
+/// A batch of ciphertexts stored as two columns, one of the first components and one of the
+/// second, rather than as a slice of pairs.
+///
+/// The batch operations each make a pass over one column at a time, which keeps the working set
+/// of every pass to half the size of the interleaved layout.
+/// [rerandomise_batch](Self::rerandomise_batch) also builds a table for the encryption key once,
+/// so the second column costs a fixed-base multiplication per element, like the first.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CiphertextColumns, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let ms: Vec<_> = (0..4).map(|_| RistrettoPoint::random(&mut rng)).collect();
+/// let cts: Vec<_> = ms.iter().map(|m| enc_key.encrypt(*m, &mut rng)).collect();
+///
+/// let mut columns = CiphertextColumns::from(&cts[..]);
+/// columns.rerandomise_batch(enc_key, &mut rng);
+/// assert_eq!(dec_key.decrypt(columns.get(2).unwrap()), ms[2]);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct CiphertextColumns {
+    c1: Vec<RistrettoPoint>,
+    c2: Vec<RistrettoPoint>,
+}
+
+impl CiphertextColumns {
+    /// Create an empty batch.
+    pub fn new() -> CiphertextColumns {
+        CiphertextColumns::default()
+    }
+
+    /// Create an empty batch with room for `capacity` ciphertexts.
+    pub fn with_capacity(capacity: usize) -> CiphertextColumns {
+        CiphertextColumns {
+            c1: Vec::with_capacity(capacity),
+            c2: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of ciphertexts.
+    pub fn len(&self) -> usize {
+        self.c1.len()
+    }
+
+    /// Whether the batch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.c1.is_empty()
+    }
+
+    /// The ciphertext at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<Ciphertext> {
+        Some(Ciphertext(*self.c1.get(index)?, self.c2[index]))
+    }
+
+    /// Append a ciphertext.
+    pub fn push(&mut self, ct: Ciphertext) {
+        self.c1.push(ct.0);
+        self.c2.push(ct.1);
+    }
+
+    /// The column of first components, `rG`.
+    pub fn c1(&self) -> &[RistrettoPoint] {
+        &self.c1
+    }
+
+    /// The column of second components, `M + rY`.
+    pub fn c2(&self) -> &[RistrettoPoint] {
+        &self.c2
+    }
+
+    /// Gather the ciphertexts back into pairs.
+    pub fn to_ciphertexts(&self) -> Vec<Ciphertext> {
+        self.c1
+            .iter()
+            .zip(&self.c2)
+            .map(|(c1, c2)| Ciphertext(*c1, *c2))
+            .collect()
+    }
+
+    /// Re-randomise every ciphertext under `ek` with randomly-generated blinding factors.
+    pub fn rerandomise_batch<R: RngCore + CryptoRng>(&mut self, ek: &EncryptionKey, rng: &mut R) {
+        let rs: Vec<_> = (0..self.len()).map(|_| Scalar::random(rng)).collect();
+        self.add_blinding(ek, &rs);
+    }
+
+    /// Re-randomise every ciphertext under `ek`, the `i`th with the blinding factor `rs[i]`.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if there are not as many
+    /// blinding factors as ciphertexts.
+    pub fn rerandomise_batch_with(
+        &mut self,
+        ek: &EncryptionKey,
+        rs: &[Scalar],
+    ) -> Result<(), Error> {
+        check_len(self.len(), rs.len())?;
+        self.add_blinding(ek, rs);
+        Ok(())
+    }
+
+    fn add_blinding(&mut self, ek: &EncryptionKey, rs: &[Scalar]) {
+        for (c1, r) in self.c1.iter_mut().zip(rs) {
+            *c1 += r * GENERATOR_TABLE;
+        }
+        let table = RistrettoBasepointTable::create(&ek.0);
+        for (c2, r) in self.c2.iter_mut().zip(rs) {
+            *c2 += r * &table;
+        }
+    }
+
+    /// Add `other` to the batch element-wise, homomorphically.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if the batches differ in
+    /// length.
+    pub fn add_assign_batch(&mut self, other: &CiphertextColumns) -> Result<(), Error> {
+        check_len(self.len(), other.len())?;
+        for (a, b) in self.c1.iter_mut().zip(&other.c1) {
+            *a += b;
+        }
+        for (a, b) in self.c2.iter_mut().zip(&other.c2) {
+            *a += b;
+        }
+        Ok(())
+    }
+
+    /// Compress every ciphertext, as [compress_batch](crate::compress_batch) does for a slice.
+    pub fn compress_batch(&self) -> Vec<CompressedCiphertext> {
+        let c1: Vec<_> = self.c1.iter().map(RistrettoPoint::compress).collect();
+        c1.into_iter()
+            .zip(self.c2.iter().map(RistrettoPoint::compress))
+            .map(|(c1, c2)| CompressedCiphertext(c1, c2))
+            .collect()
+    }
+}
+
+fn check_len(expected: usize, got: usize) -> Result<(), Error> {
+    if expected != got {
+        return Err(Error::LengthMismatch { expected, got });
+    }
+    Ok(())
+}
+
+// Conversion traits
+
+impl From<&[Ciphertext]> for CiphertextColumns {
+    fn from(cts: &[Ciphertext]) -> Self {
+        CiphertextColumns {
+            c1: cts.iter().map(|ct| ct.0).collect(),
+            c2: cts.iter().map(|ct| ct.1).collect(),
+        }
+    }
+}
+
+impl From<CiphertextColumns> for Vec<Ciphertext> {
+    fn from(columns: CiphertextColumns) -> Self {
+        columns.to_ciphertexts()
+    }
+}
+
+impl FromIterator<Ciphertext> for CiphertextColumns {
+    fn from_iter<I: IntoIterator<Item = Ciphertext>>(iter: I) -> Self {
+        let mut columns = CiphertextColumns::new();
+        for ct in iter {
+            columns.push(ct);
+        }
+        columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{
+        compress_batch, Ciphertext, CiphertextColumns, DecryptionKey, Error, RistrettoPoint, Scalar,
+    };
+
+    const N: usize = 50;
+
+    fn batch(rng: &mut StdRng) -> (DecryptionKey, Vec<RistrettoPoint>, Vec<Ciphertext>) {
+        let dk = DecryptionKey::new(rng);
+        let ms: Vec<_> = (0..N).map(|_| RistrettoPoint::random(rng)).collect();
+        let cts = ms
+            .iter()
+            .map(|m| dk.encryption_key().encrypt(*m, rng))
+            .collect();
+        (dk, ms, cts)
+    }
+
+    // Test that the conversions preserve the ciphertexts and their order.
+    #[test]
+    fn columns_round_trip() {
+        let mut rng = StdRng::seed_from_u64(471);
+        let (_, _, cts) = batch(&mut rng);
+        let columns = CiphertextColumns::from(&cts[..]);
+        assert_eq!(columns.len(), N);
+        assert_eq!(columns.get(7), Some(cts[7]));
+        assert_eq!(columns.get(N), None);
+        assert_eq!(columns.c1()[3], cts[3].0);
+        assert_eq!(columns.c2()[3], cts[3].1);
+        assert_eq!(columns.to_ciphertexts(), cts);
+        assert_eq!(cts.iter().copied().collect::<CiphertextColumns>(), columns);
+        assert_eq!(Vec::<Ciphertext>::from(columns), cts);
+        assert!(CiphertextColumns::from(&[][..]).is_empty());
+    }
+
+    // Test that each batch operation agrees with the per-ciphertext code path.
+    #[test]
+    fn columns_match_per_element() {
+        let mut rng = StdRng::seed_from_u64(471);
+        let (dk, ms, cts) = batch(&mut rng);
+        let ek = dk.encryption_key();
+
+        let rs: Vec<_> = (0..N).map(|_| Scalar::random(&mut rng)).collect();
+        let mut columns = CiphertextColumns::from(&cts[..]);
+        columns.rerandomise_batch_with(ek, &rs).unwrap();
+        let expected: Vec<_> = cts
+            .iter()
+            .zip(&rs)
+            .map(|(ct, r)| ek.rerandomise_with(*ct, *r))
+            .collect();
+        assert_eq!(columns.to_ciphertexts(), expected);
+
+        columns.rerandomise_batch(ek, &mut rng);
+        for (i, m) in ms.iter().enumerate() {
+            let ct = columns.get(i).unwrap();
+            assert_ne!(ct, expected[i]);
+            assert_eq!(dk.decrypt(ct), *m);
+        }
+
+        let mut sum = CiphertextColumns::from(&cts[..]);
+        sum.add_assign_batch(&columns).unwrap();
+        for (i, m) in ms.iter().enumerate() {
+            assert_eq!(sum.get(i), Some(cts[i] + columns.get(i).unwrap()));
+            assert_eq!(dk.decrypt(sum.get(i).unwrap()), m + m);
+        }
+
+        assert_eq!(sum.compress_batch(), compress_batch(&sum.to_ciphertexts()));
+    }
+
+    // Test that operations on batches of different lengths are rejected untouched.
+    #[test]
+    fn columns_length_mismatch() {
+        let mut rng = StdRng::seed_from_u64(471);
+        let (dk, _, cts) = batch(&mut rng);
+        let mut columns = CiphertextColumns::from(&cts[..]);
+        let short = CiphertextColumns::from(&cts[1..]);
+
+        assert_eq!(
+            columns.add_assign_batch(&short),
+            Err(Error::LengthMismatch {
+                expected: N,
+                got: N - 1
+            })
+        );
+        assert_eq!(
+            columns.rerandomise_batch_with(dk.encryption_key(), &[Scalar::ONE]),
+            Err(Error::LengthMismatch {
+                expected: N,
+                got: 1
+            })
+        );
+        assert_eq!(columns.to_ciphertexts(), cts);
+    }
+}
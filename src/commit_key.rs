@@ -0,0 +1,236 @@
+// Commitment keys, kept apart from encryption keys, for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::backend::DebugPoint;
+use crate::hash::hash_to_point;
+use crate::{ElGamalGroup, EncryptionKey};
+
+const DERIVE_DOMAIN: &[u8] = b"rust-elgamal commit key";
+const FINGERPRINT_DOMAIN: &[u8] = b"rust-elgamal commit key fingerprint";
+
+/// The key `Y` of a [Commitment](crate::Commitment) `(rG, mG + rY)`.
+///
+/// A commitment key is a point, like an [EncryptionKey], but a different type, so that a key
+/// meant for one protocol cannot be passed to the other by accident. Derive one with
+/// [derive](CommitKey::derive), or convert an encryption key with the loudly named
+/// [from_encryption_key_unchecked](CommitKey::from_encryption_key_unchecked). Fingerprints of the
+/// two kinds of key are separated by domain, so they never collide.
+///
+/// Equality compares the encoded keys in constant time, through [ConstantTimeEq].
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(
+        serialize = "G: Serialize",
+        deserialize = "G: ElGamalGroup + Deserialize<'de>"
+    ))
+)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(concrete(G = crate::ts::Point)))]
+pub struct CommitKey<G = RistrettoPoint>(
+    #[cfg_attr(
+        feature = "enable-serde",
+        serde(deserialize_with = "crate::encrypt::deserialize_key_point")
+    )]
+    pub(crate) G,
+);
+
+impl CommitKey {
+    /// Derive the commitment key named `label` from `master_secret`.
+    ///
+    /// The key is a hash of the label and the secret to a point, under a domain of its own, so
+    /// keys with different labels, or from different secrets, are independent, nobody knows the
+    /// discrete log of any of them, and none is related to an encryption key made from the same
+    /// secret.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{CommitKey, Commitment, Scalar};
+    ///
+    /// let master_secret = [7u8; 32];
+    /// let key = CommitKey::derive(&master_secret, b"balances");
+    /// assert_eq!(key, CommitKey::derive(&master_secret, b"balances"));
+    /// assert_ne!(key, CommitKey::derive(&master_secret, b"votes"));
+    ///
+    /// let (open, commitment) = Commitment::commit_with(Scalar::from(7u32), Scalar::from(8u32), &key);
+    /// assert!(commitment.verify(&open));
+    /// ```
+    pub fn derive(master_secret: &[u8], label: &[u8]) -> CommitKey {
+        let digest = Sha512::new()
+            .chain_update((label.len() as u64).to_le_bytes())
+            .chain_update(label)
+            .chain_update(master_secret)
+            .finalize();
+        CommitKey(hash_to_point(DERIVE_DOMAIN, &digest))
+    }
+}
+
+impl<G: ElGamalGroup> CommitKey<G> {
+    /// Use an encryption key as a commitment key.
+    ///
+    /// Whoever holds the matching decryption key can decrypt commitments under it to `mG`, and
+    /// the same key then serves two protocols; prefer a key from [derive](CommitKey::derive).
+    pub fn from_encryption_key_unchecked(ek: &EncryptionKey<G>) -> CommitKey<G> {
+        CommitKey(ek.0)
+    }
+
+    /// Use the commitment key as an encryption key, for example to look it up among encryption
+    /// keys.
+    pub fn to_encryption_key_unchecked(&self) -> EncryptionKey<G> {
+        EncryptionKey(self.0)
+    }
+
+    /// A 32-byte fingerprint of the key, as [EncryptionKey::fingerprint] but under a domain of
+    /// its own, so a commitment key and an encryption key at the same point do not share one.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let digest = Sha512::new()
+            .chain_update((FINGERPRINT_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update(self.0.to_bytes())
+            .finalize();
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest[..32]);
+        out
+    }
+}
+
+impl<G: ElGamalGroup> Debug for CommitKey<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "CommitKey({:?})", DebugPoint(&self.0))
+    }
+}
+
+impl<G: ElGamalGroup> ConstantTimeEq for CommitKey<G> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0
+            .to_bytes()
+            .as_ref()
+            .ct_eq(other.0.to_bytes().as_ref())
+    }
+}
+
+impl<G: ElGamalGroup> PartialEq for CommitKey<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<G: ElGamalGroup> Eq for CommitKey<G> {}
+
+impl<G: ElGamalGroup> AsRef<G> for CommitKey<G> {
+    fn as_ref(&self) -> &G {
+        &self.0
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The keys [Commitment::commit_with](crate::Commitment::commit_with) and
+/// [BalanceProof](crate::BalanceProof) accept: a [CommitKey], or, for this release only, an
+/// [EncryptionKey].
+///
+/// Passing an encryption key is deprecated; convert it with
+/// [CommitKey::from_encryption_key_unchecked] instead. The trait is sealed.
+pub trait AsCommitKey<G: ElGamalGroup>: private::Sealed {
+    /// The commitment key.
+    fn as_commit_key(&self) -> CommitKey<G>;
+}
+
+impl<G: ElGamalGroup> private::Sealed for CommitKey<G> {}
+
+impl<G: ElGamalGroup> AsCommitKey<G> for CommitKey<G> {
+    fn as_commit_key(&self) -> CommitKey<G> {
+        *self
+    }
+}
+
+impl<G: ElGamalGroup> private::Sealed for EncryptionKey<G> {}
+
+impl<G: ElGamalGroup> AsCommitKey<G> for EncryptionKey<G> {
+    fn as_commit_key(&self) -> CommitKey<G> {
+        CommitKey::from_encryption_key_unchecked(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{CommitKey, DERIVE_DOMAIN};
+    use crate::hash::hash_to_point;
+    use crate::{Commitment, DecryptionKey, Scalar};
+
+    // Test that derived keys depend on both the secret and the label, including where the two
+    // meet, and are not the encryption key of the secret read as a scalar.
+    #[test]
+    fn commit_key_derive() {
+        let secret = [3u8; 32];
+        let key = CommitKey::derive(&secret, b"balances");
+        assert_eq!(key, CommitKey::derive(&secret, b"balances"));
+        assert_ne!(key, CommitKey::derive(&secret, b"votes"));
+        assert_ne!(key, CommitKey::derive(&[4u8; 32], b"balances"));
+        assert_ne!(
+            CommitKey::derive(b"bc", b"a"),
+            CommitKey::derive(b"c", b"ab")
+        );
+        assert_ne!(
+            key.0,
+            hash_to_point(DERIVE_DOMAIN, &secret),
+            "the label is hashed in"
+        );
+
+        let dk = DecryptionKey::from(Scalar::from_bytes_mod_order(secret));
+        assert_ne!(key.0, dk.encryption_key().0);
+
+        let (open, commitment) =
+            Commitment::commit_with(Scalar::from(5u32), Scalar::from(9u32), &key);
+        assert!(commitment.verify(&open));
+        assert_eq!(commitment.commit_key(), key);
+    }
+
+    // Test that the unchecked conversions keep the point, that the fingerprints of the two kinds
+    // of key differ, and that a converted key commits as the encryption key did before.
+    #[test]
+    fn commit_key_conversions() {
+        let mut rng = StdRng::seed_from_u64(499);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let key = CommitKey::from_encryption_key_unchecked(ek);
+        assert_eq!(key.0, ek.0);
+        assert_eq!(&key.to_encryption_key_unchecked(), ek);
+        assert_ne!(key.fingerprint(), ek.fingerprint());
+
+        let (m, r) = (Scalar::from(5u32), Scalar::from(9u32));
+        let (open, commitment) = Commitment::commit_with(m, r, &key);
+        let (legacy_open, legacy) = Commitment::commit_with(m, r, ek);
+        assert_eq!(legacy, commitment);
+        assert_eq!((legacy_open.0, legacy_open.1), (open.0, open.1));
+        // whoever holds the decryption key can open commitments under a converted key
+        assert_eq!(dk.decrypt(commitment.1), &m * crate::GENERATOR_TABLE);
+    }
+}
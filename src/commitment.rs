@@ -13,33 +13,117 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{
     fmt::{Debug, Formatter},
-    ops::{Add, Mul, Neg, Sub},
+    ops::{Add, Mul, MulAssign, Neg, Sub},
 };
 
 use curve25519_dalek::{RistrettoPoint, Scalar};
-use rand_core::{CryptoRng, RngCore};
+use ff::Field;
+#[cfg(feature = "os-rng")]
+use rand_core::OsRng;
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{Ciphertext, DecryptionKey, EncryptionKey, Open, GENERATOR_TABLE};
+use crate::backend::DebugPoint;
+use crate::{
+    AsCommitKey, Ciphertext, CommitKey, DecryptionKey, ElGamalGroup, ElGamalRng, EncryptionKey,
+    Error, Open, ScalarOperand,
+};
 
 /// Elgamal Commitment Scheme uses exactly the same as encrption logic where the bindling factor
 /// and the message are kept secret and then later revealed to verify the commitment.
 #[derive(Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
-pub struct Commitment(pub(crate) RistrettoPoint, pub(crate) Ciphertext);
-
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(
+        serialize = "G: Serialize",
+        deserialize = "G: ElGamalGroup + Deserialize<'de>"
+    ))
+)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(concrete(G = crate::ts::Point)))]
+pub struct Commitment<G = RistrettoPoint>(
+    #[cfg_attr(
+        feature = "enable-serde",
+        serde(deserialize_with = "crate::encrypt::deserialize_key_point")
+    )]
+    pub(crate) G,
+    pub(crate) Ciphertext<G>,
+);
+
+/// The message of a [Commitment], a scalar. Short byte strings can be encoded as messages with
+/// [CommitMessageBytes].
 pub type CommitMessage = Scalar;
 
-impl Commitment {
-    /// Get the encryption key from the commitment.
-    pub fn encryption_key(&self) -> EncryptionKey {
-        EncryptionKey(self.0)
+/// The longest byte string [CommitMessageBytes::encode_bytes] accepts.
+pub const COMMIT_MESSAGE_MAX_LEN: usize = 31;
+
+/// Exact encoding of short byte strings, such as UUIDs and 20-byte hashes, as [CommitMessage]s.
+///
+/// A message of `len` bytes is written into the low bytes of the scalar (little-endian), followed
+/// by a `0x01` byte that marks its length, so the scalar is `256^len + msg`. This stays below
+/// 2^249 and so is never reduced modulo the group order, and distinct messages, including ones
+/// that differ only in trailing zeros, give distinct scalars. (A separate length byte would not
+/// leave room for 31 bytes below the group order.)
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CommitMessage, CommitMessageBytes, Commitment};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let id = b"0f8fad5b-d9cb-469f-a165";
+/// let m = CommitMessage::encode_bytes(id).unwrap();
+/// let (open, commitment) = Commitment::commit(m, &mut rng);
+///
+/// assert!(commitment.verify(&open));
+/// assert_eq!(CommitMessage::decode_bytes(m).unwrap(), id);
+/// ```
+pub trait CommitMessageBytes: Sized {
+    /// Encode `msg`, which may be up to [COMMIT_MESSAGE_MAX_LEN] bytes long.
+    ///
+    /// Fails with [Error::MessageTooLong] for longer messages.
+    fn encode_bytes(msg: &[u8]) -> Result<Self, Error>;
+
+    /// Recover the message encoded in `m` by [encode_bytes](Self::encode_bytes).
+    ///
+    /// Fails with [Error::InvalidEncoding] if `m` is not such an encoding.
+    #[cfg(feature = "alloc")]
+    fn decode_bytes(m: Self) -> Result<Vec<u8>, Error>;
+}
+
+impl CommitMessageBytes for Scalar {
+    fn encode_bytes(msg: &[u8]) -> Result<Scalar, Error> {
+        if msg.len() > COMMIT_MESSAGE_MAX_LEN {
+            return Err(Error::MessageTooLong);
+        }
+        let mut bytes = [0u8; 32];
+        bytes[..msg.len()].copy_from_slice(msg);
+        bytes[msg.len()] = 1;
+        // below 2^249, so not reduced
+        Ok(Scalar::from_bytes_mod_order(bytes))
     }
 
+    #[cfg(feature = "alloc")]
+    fn decode_bytes(m: Scalar) -> Result<Vec<u8>, Error> {
+        let bytes = m.to_bytes();
+        match bytes.iter().rposition(|b| *b != 0) {
+            Some(len) if bytes[len] == 1 => Ok(bytes[..len].to_vec()),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+}
+
+impl Commitment {
     /// Commit to a message using the Elgamal Commitment Scheme.
     ///
     /// `M` is commonly refered as message in the crate but here `m` is the message before multiply
@@ -56,40 +140,92 @@ impl Commitment {
     /// let (open, commitment) = Commitment::commit(m, &mut rng);
     /// assert!(commitment.verify(&open));
     /// ```
-    pub fn commit<R: RngCore + CryptoRng>(m: CommitMessage, rng: &mut R) -> (Open, Commitment) {
+    pub fn commit<R: ElGamalRng<V>, V>(m: CommitMessage, rng: &mut R) -> (Open, Commitment) {
         // commitment = (rG, mG + rY), where open = (r, m)
 
         let decrypt_key = DecryptionKey::new(rng); // secret will not be used anymore
-        let y = decrypt_key.encryption_key();
-        let r = Scalar::random(rng);
+        let y = CommitKey::from_encryption_key_unchecked(decrypt_key.encryption_key());
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut r = Scalar::random(&mut rng.compat());
+
+        let committed = Commitment::commit_with(m, r, &y);
+        // the open keeps its own copy of r
+        #[cfg(feature = "zeroize")]
+        r.zeroize();
+        committed
+    }
 
-        Commitment::commit_with(m, r, y)
+    /// Commit to a message using the Elgamal Commitment Scheme, drawing the key and the blinding
+    /// factor from the operating system's randomness source.
+    ///
+    /// This is a cryptographically safe default for callers who do not need to control the
+    /// randomness source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{Commitment, Scalar};
+    ///
+    /// let m = Scalar::from(7u32);
+    /// let (open, commitment) = Commitment::commit_os(m);
+    /// assert!(commitment.verify(&open));
+    /// ```
+    #[cfg(feature = "os-rng")]
+    pub fn commit_os(m: CommitMessage) -> (Open, Commitment) {
+        Commitment::commit(m, &mut OsRng)
+    }
+}
+
+impl<G: ElGamalGroup> Commitment<G> {
+    /// Get the commitment key from the commitment.
+    pub fn commit_key(&self) -> CommitKey<G> {
+        CommitKey(self.0)
     }
 
-    /// Commit to a message using the Elgamal Commitment Scheme with a given encryption key.
+    /// Get the commitment key from the commitment, as an encryption key.
+    #[deprecated(
+        note = "use `commit_key`, and `CommitKey::to_encryption_key_unchecked` if an encryption key is really needed"
+    )]
+    pub fn encryption_key(&self) -> EncryptionKey<G> {
+        EncryptionKey(self.0)
+    }
+
+    /// Returns the encryption key and ciphertext that make up the commitment. Intended for
+    /// advanced use only.
+    pub fn inner(&self) -> (EncryptionKey<G>, Ciphertext<G>) {
+        (EncryptionKey(self.0), self.1)
+    }
+
+    /// Commit to a message using the Elgamal Commitment Scheme with a given commitment key.
     ///
     /// `M` is commonly refered as message in the crate but here `m` is the message before multiply
     /// by G, group generator. i.e. M = mG.
     ///
+    /// `y` should be a [CommitKey]. An [EncryptionKey] is still accepted, through [AsCommitKey],
+    /// for this release only; convert it with [CommitKey::from_encryption_key_unchecked] instead.
+    ///
     /// # Example
     ///
     /// ```rust
-    /// use rand::{rngs::StdRng, SeedableRng};
-    /// use rust_elgamal::{DecryptionKey, Commitment, Scalar};
-    ///
-    /// let mut rng = StdRng::from_entropy();
+    /// use rust_elgamal::{CommitKey, Commitment, Scalar};
     ///
-    /// let decrypt_key = DecryptionKey::new(&mut rng);
-    /// let y = decrypt_key.encryption_key();
+    /// let y = CommitKey::derive(b"master secret", b"example");
     /// let m = Scalar::from(7u32);
     /// let r = Scalar::from(8u32);
-    /// let (open, commitment) = Commitment::commit_with(m, r, y);
+    /// let (open, commitment) = Commitment::commit_with(m, r, &y);
     /// assert!(commitment.verify(&open));
     /// ```
-    pub fn commit_with(m: CommitMessage, r: Scalar, y: &EncryptionKey) -> (Open, Commitment) {
+    pub fn commit_with<K: AsCommitKey<G>>(
+        m: G::Scalar,
+        r: G::Scalar,
+        y: &K,
+    ) -> (Open<G::Scalar>, Commitment<G>) {
         // commitment = (rG, mG + rY), where open = (r, m)
 
-        let ciphertext = y.encrypt_with(&m * GENERATOR_TABLE, r);
+        let y = y.as_commit_key();
+        let ciphertext = y
+            .to_encryption_key_unchecked()
+            .encrypt_with(G::mul_generator(&m), r);
         let commitment = Commitment(y.0, ciphertext);
         let open = Open(r, m);
         (open, commitment)
@@ -101,22 +237,53 @@ impl Commitment {
     ///
     /// ```rust
     /// use rand::{rngs::StdRng, SeedableRng};
-    /// use rust_elgamal::{DecryptionKey, Commitment, Scalar};
+    /// use rust_elgamal::{CommitKey, Commitment, Scalar};
     ///
     /// let mut rng = StdRng::from_entropy();
     ///
-    /// let decrypt_key = DecryptionKey::new(&mut rng);
-    /// let y = decrypt_key.encryption_key();
+    /// let y = CommitKey::derive(b"master secret", b"example");
     /// let m = Scalar::from(7u32);
     /// let r = Scalar::from(8u32);
-    /// let (open, mut commitment) = Commitment::commit_with(m, r, y);
+    /// let (open, mut commitment) = Commitment::commit_with(m, r, &y);
     ///
     /// let new_open = commitment.rerandomise(open, &mut rng);
     /// assert!(commitment.verify(&new_open));
     /// ```
     #[must_use = "the Open input is not mutated, the function returns the new rerandomised Commitment and Open"]
-    pub fn rerandomise<R: RngCore + CryptoRng>(&mut self, open: Open, rng: &mut R) -> Open {
-        self.rerandomise_with(open, Scalar::random(rng), Scalar::random(rng))
+    pub fn rerandomise<R: ElGamalRng<V>, V>(
+        &mut self,
+        open: Open<G::Scalar>,
+        rng: &mut R,
+    ) -> Open<G::Scalar> {
+        let mut rng = rng.compat();
+        self.rerandomise_with(
+            open,
+            G::Scalar::random(&mut rng),
+            G::Scalar::random(&mut rng),
+        )
+    }
+
+    /// Rerandomise the commitment and open, drawing the new randomness from the operating
+    /// system's randomness source.
+    ///
+    /// This is a cryptographically safe default for callers who do not need to control the
+    /// randomness source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{Commitment, Scalar};
+    ///
+    /// let m = Scalar::from(7u32);
+    /// let (open, mut commitment) = Commitment::commit_os(m);
+    ///
+    /// let new_open = commitment.rerandomise_os(open);
+    /// assert!(commitment.verify(&new_open));
+    /// ```
+    #[cfg(feature = "os-rng")]
+    #[must_use = "the Open input is not mutated, the function returns the new rerandomised Commitment and Open"]
+    pub fn rerandomise_os(&mut self, open: Open<G::Scalar>) -> Open<G::Scalar> {
+        self.rerandomise(open, &mut OsRng)
     }
 
     /// Rerandomise the commitment and open with a new pair of randomness.
@@ -125,15 +292,14 @@ impl Commitment {
     ///
     /// ```rust
     /// use rand::{rngs::StdRng, SeedableRng};
-    /// use rust_elgamal::{DecryptionKey, Commitment, Scalar};
+    /// use rust_elgamal::{CommitKey, Commitment, Scalar};
     ///
     /// let mut rng = StdRng::from_entropy();
     ///
-    /// let decrypt_key = DecryptionKey::new(&mut rng);
-    /// let y = decrypt_key.encryption_key();
+    /// let y = CommitKey::derive(b"master secret", b"example");
     /// let m = Scalar::from(7u32);
     /// let r = Scalar::from(8u32);
-    /// let (open, mut commitment) = Commitment::commit_with(m, r, y);
+    /// let (open, mut commitment) = Commitment::commit_with(m, r, &y);
     ///
     /// let new_r1 = Scalar::from(9u32);
     /// let new_r2 = Scalar::from(10u32);
@@ -141,10 +307,15 @@ impl Commitment {
     /// assert!(commitment.verify(&new_open));
     /// ```
     #[must_use = "the Open input is not mutated, the function returns the new rerandomised Commitment and Open"]
-    pub fn rerandomise_with(&mut self, open: Open, r1: Scalar, r2: Scalar) -> Open {
+    pub fn rerandomise_with(
+        &mut self,
+        open: Open<G::Scalar>,
+        r1: G::Scalar,
+        r2: G::Scalar,
+    ) -> Open<G::Scalar> {
         let Commitment(y, Ciphertext(r_g, m_g_r_y)) = self;
-        *r_g += &r1 * GENERATOR_TABLE;
-        *m_g_r_y += *y * r1 + &r2 * GENERATOR_TABLE;
+        *r_g += G::mul_generator(&r1);
+        *m_g_r_y += G::mul_generator_add(&r2, &r1, y);
         Open(open.0 + r1, open.1 + r2)
     }
 
@@ -166,26 +337,144 @@ impl Commitment {
     /// assert!(!commitment2.verify(&open)); // wrong pair of open and commitment
     /// assert!(!commitment.verify(&open2)); // wrong pair of open and commitment
     /// ```
-    pub fn verify(&self, open: &Open) -> bool {
+    pub fn verify(&self, open: &Open<G::Scalar>) -> bool {
         let Commitment(_, ct) = self;
         let Open(r, m) = open;
 
-        let ct2 = self.encryption_key().encrypt_with(m * GENERATOR_TABLE, *r);
+        let ct2 = EncryptionKey(self.0).encrypt_with(G::mul_generator(m), *r);
 
         ct == &ct2
     }
+
+    /// Verify the commitment in constant time, returning a [Choice] rather than a `bool` so that
+    /// the result can be combined with other secret-dependent conditions without branching.
+    ///
+    /// The points are compared through their encodings, so this does not rely on the backend's
+    /// `PartialEq` being constant-time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use rust_elgamal::{Commitment, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let (open, commitment) = Commitment::commit(Scalar::from(8u32), &mut rng);
+    /// assert!(bool::from(commitment.verify_ct(&open)));
+    /// ```
+    pub fn verify_ct(&self, open: &Open<G::Scalar>) -> Choice {
+        let Commitment(_, Ciphertext(c1, c2)) = self;
+        let Open(r, m) = open;
+
+        let Ciphertext(d1, d2) = EncryptionKey(self.0).encrypt_with(G::mul_generator(m), *r);
+
+        c1.to_bytes().as_ref().ct_eq(d1.to_bytes().as_ref())
+            & c2.to_bytes().as_ref().ct_eq(d2.to_bytes().as_ref())
+    }
+
+    /// Verify the commitment, returning [Error::VerificationFailed](crate::Error::VerificationFailed)
+    /// if the open does not match so that the check can be chained with `?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use rust_elgamal::{Commitment, Error, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let (open, commitment) = Commitment::commit(Scalar::from(8u32), &mut rng);
+    /// let (open2, _) = Commitment::commit(Scalar::from(9u32), &mut rng);
+    /// assert_eq!(commitment.try_verify(&open), Ok(()));
+    /// assert_eq!(commitment.try_verify(&open2), Err(Error::VerificationFailed));
+    /// ```
+    pub fn try_verify(&self, open: &Open<G::Scalar>) -> Result<(), Error> {
+        if self.verify(open) {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+
+    /// Check the commitment for weaknesses that deserialisation cannot rule out on its own.
+    ///
+    /// Returns [Error::IdentityPoint](crate::Error::IdentityPoint) if the encryption key is the
+    /// identity (making the commitment trivially openable to any message) or if the blinding
+    /// component is the identity (meaning the blinding factor was zero, so the commitment does
+    /// not hide the message).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use rust_elgamal::{CommitKey, Commitment, Error, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let y = CommitKey::derive(b"master secret", b"example");
+    ///
+    /// let (_, commitment) = Commitment::commit(Scalar::from(7u32), &mut rng);
+    /// assert_eq!(commitment.validate(), Ok(()));
+    ///
+    /// let (_, unblinded) = Commitment::commit_with(Scalar::from(7u32), Scalar::ZERO, &y);
+    /// assert_eq!(unblinded.validate(), Err(Error::IdentityPoint));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let Commitment(y, Ciphertext(r_g, _)) = self;
+        if bool::from(y.is_identity() | r_g.is_identity()) {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(())
+    }
+
+    /// Add two commitments, checking that they were made under the same encryption key.
+    ///
+    /// The `Add` operator keeps the key of the left-hand side without looking at the right-hand
+    /// side; use this instead when the commitments come from an untrusted source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use rust_elgamal::{Commitment, Error, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let (_, commitment) = Commitment::commit(Scalar::from(8u32), &mut rng);
+    /// let (_, commitment2) = Commitment::commit(Scalar::from(9u32), &mut rng);
+    /// assert_eq!(commitment.checked_add(&commitment2), Err(Error::KeyMismatch));
+    /// ```
+    pub fn checked_add(&self, rhs: &Commitment<G>) -> Result<Commitment<G>, Error> {
+        if self.0 != rhs.0 {
+            return Err(Error::KeyMismatch);
+        }
+        Ok(self + rhs)
+    }
+
+    /// Subtract two commitments, checking that they were made under the same encryption key.
+    pub fn checked_sub(&self, rhs: &Commitment<G>) -> Result<Commitment<G>, Error> {
+        if self.0 != rhs.0 {
+            return Err(Error::KeyMismatch);
+        }
+        Ok(self - rhs)
+    }
 }
 
-impl Debug for Commitment {
+impl<G: ElGamalGroup> Debug for Commitment<G> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Commitment({:?}, {:?})", self.0.compress(), self.1)
+        write!(f, "Commitment({:?}, {:?})", DebugPoint(&self.0), self.1)
+    }
+}
+
+// Conversion traits
+
+impl<G: ElGamalGroup> From<(EncryptionKey<G>, Ciphertext<G>)> for Commitment<G> {
+    fn from(pair: (EncryptionKey<G>, Ciphertext<G>)) -> Self {
+        Self(pair.0 .0, pair.1)
     }
 }
 
 // Arithmetic traits for homomorphisms
 // Note: the first element (encryption key) of the commitment is not going to change.
 
-impl Add for Commitment {
+impl<G: ElGamalGroup> Add for Commitment<G> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -193,31 +482,31 @@ impl Add for Commitment {
     }
 }
 
-impl Add for &Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Add for &Commitment<G> {
+    type Output = Commitment<G>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Commitment(self.0, &self.1 + &rhs.1)
+        Commitment(self.0, self.1 + rhs.1)
     }
 }
 
-impl Add<&Commitment> for Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Add<&Commitment<G>> for Commitment<G> {
+    type Output = Commitment<G>;
 
-    fn add(self, rhs: &Commitment) -> Self::Output {
-        Commitment(self.0, self.1 + &rhs.1)
+    fn add(self, rhs: &Commitment<G>) -> Self::Output {
+        Commitment(self.0, self.1 + rhs.1)
     }
 }
 
-impl Add<Commitment> for &Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Add<Commitment<G>> for &Commitment<G> {
+    type Output = Commitment<G>;
 
-    fn add(self, rhs: Commitment) -> Self::Output {
-        Commitment(self.0, &self.1 + rhs.1)
+    fn add(self, rhs: Commitment<G>) -> Self::Output {
+        Commitment(self.0, self.1 + rhs.1)
     }
 }
 
-impl Sub for Commitment {
+impl<G: ElGamalGroup> Sub for Commitment<G> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -225,86 +514,144 @@ impl Sub for Commitment {
     }
 }
 
-impl Sub for &Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Sub for &Commitment<G> {
+    type Output = Commitment<G>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Commitment(self.0, &self.1 - &rhs.1)
+        Commitment(self.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<&Commitment> for Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Sub<&Commitment<G>> for Commitment<G> {
+    type Output = Commitment<G>;
 
-    fn sub(self, rhs: &Commitment) -> Self::Output {
-        Commitment(self.0, self.1 - &rhs.1)
+    fn sub(self, rhs: &Commitment<G>) -> Self::Output {
+        Commitment(self.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<Commitment> for &Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Sub<Commitment<G>> for &Commitment<G> {
+    type Output = Commitment<G>;
 
-    fn sub(self, rhs: Commitment) -> Self::Output {
-        Commitment(self.0, &self.1 - rhs.1)
+    fn sub(self, rhs: Commitment<G>) -> Self::Output {
+        Commitment(self.0, self.1 - rhs.1)
     }
 }
 
-impl Neg for Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Neg for Commitment<G> {
+    type Output = Commitment<G>;
 
     fn neg(self) -> Self::Output {
         Commitment(self.0, -self.1)
     }
 }
 
-impl Neg for &Commitment {
-    type Output = Commitment;
+impl<G: ElGamalGroup> Neg for &Commitment<G> {
+    type Output = Commitment<G>;
 
     fn neg(self) -> Self::Output {
         Commitment(self.0, -self.1)
     }
 }
 
-impl Mul<Scalar> for Commitment {
-    type Output = Commitment;
+// Generic over the operand for the same reason as the `Mul` impls on `Ciphertext`.
+impl<G, S> Mul<S> for Commitment<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = Commitment<G>;
 
-    fn mul(self, rhs: Scalar) -> Self::Output {
+    fn mul(self, rhs: S) -> Self::Output {
         Commitment(self.0, self.1 * rhs)
     }
 }
 
-impl Mul<Scalar> for &Commitment {
-    type Output = Commitment;
+impl<G, S> Mul<S> for &Commitment<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    type Output = Commitment<G>;
 
-    fn mul(self, rhs: Scalar) -> Self::Output {
+    fn mul(self, rhs: S) -> Self::Output {
         Commitment(self.0, self.1 * rhs)
     }
 }
 
-impl Mul<&Scalar> for Commitment {
-    type Output = Commitment;
-
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Commitment(self.0, self.1 * rhs)
+impl<G, S> MulAssign<S> for Commitment<G>
+where
+    G: ElGamalGroup,
+    S: ScalarOperand<G::Scalar>,
+{
+    fn mul_assign(&mut self, rhs: S) {
+        self.1 *= rhs;
     }
 }
 
-impl Mul<&Scalar> for &Commitment {
-    type Output = Commitment;
-
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Commitment(self.0, self.1 * rhs)
-    }
-}
-
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "alloc")]
 #[cfg(test)]
 mod tests {
+    use alloc::vec;
+
     use rand::{rngs::StdRng, SeedableRng};
 
-    use crate::{Commitment, Scalar};
+    use crate::{
+        CommitKey, Commitment, DecryptionKey, EncryptionKey, Error, Identity, RistrettoPoint,
+        Scalar,
+    };
+
+    // Test that mismatched opens and keys are reported with their specific errors.
+    #[test]
+    fn commitment_errors() {
+        let mut rng = StdRng::from_entropy();
+        let y =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+
+        let (open, commitment) =
+            Commitment::commit_with(Scalar::from(1u32), Scalar::from(2u32), &y);
+        let (open2, commitment2) =
+            Commitment::commit_with(Scalar::from(3u32), Scalar::from(4u32), &y);
+        assert_eq!(commitment.try_verify(&open), Ok(()));
+        assert_eq!(
+            commitment.try_verify(&open2),
+            Err(Error::VerificationFailed)
+        );
+
+        let sum = commitment.checked_add(&commitment2).unwrap();
+        assert!(sum.verify(&(&open + &open2)));
+        let diff = commitment.checked_sub(&commitment2).unwrap();
+        assert!(diff.verify(&(&open - &open2)));
+
+        let (_, other) = Commitment::commit(Scalar::from(1u32), &mut rng);
+        assert_eq!(commitment.checked_add(&other), Err(Error::KeyMismatch));
+        assert_eq!(commitment.checked_sub(&other), Err(Error::KeyMismatch));
+    }
+
+    // Test that validation catches identity keys and zero blinding factors.
+    #[test]
+    fn commitment_validate() {
+        let mut rng = StdRng::from_entropy();
+        let y =
+            CommitKey::from_encryption_key_unchecked(DecryptionKey::new(&mut rng).encryption_key());
+
+        let (_, commitment) = Commitment::commit_with(Scalar::from(1u32), Scalar::from(2u32), &y);
+        assert_eq!(commitment.validate(), Ok(()));
+
+        let (_, unblinded) = Commitment::commit_with(Scalar::from(1u32), Scalar::ZERO, &y);
+        assert_eq!(unblinded.validate(), Err(Error::IdentityPoint));
+
+        let identity_key = CommitKey::from_encryption_key_unchecked(&EncryptionKey::from(
+            RistrettoPoint::identity(),
+        ));
+        let (open, weak) =
+            Commitment::commit_with(Scalar::from(1u32), Scalar::from(2u32), &identity_key);
+        assert!(weak.verify(&open));
+        assert_eq!(weak.validate(), Err(Error::IdentityPoint));
+    }
 
     // Test that serialising and deserialising a commitment.
+    #[cfg(feature = "enable-serde")]
     #[test]
     fn serde_commitment() {
         const N: usize = 100;
@@ -322,4 +669,77 @@ mod tests {
             assert_eq!(commitment, decoded);
         }
     }
+
+    // Test that a commitment embedding the identity as its encryption key is rejected.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_rejects_identity_key() {
+        let mut rng = StdRng::from_entropy();
+        let (_, commitment) = Commitment::commit(Scalar::random(&mut rng), &mut rng);
+        let mut malicious = bincode::serialize(&commitment).unwrap();
+
+        // The encryption key comes first; the identity point compresses to 32 zero bytes.
+        malicious[..32].copy_from_slice(&[0u8; 32]);
+        let err = bincode::deserialize::<Commitment>(&malicious).unwrap_err();
+        assert!(err.to_string().contains("identity"));
+    }
+
+    // Test that byte strings of every length up to the limit round trip exactly, through a
+    // commitment, and that trailing zeros are kept.
+    #[test]
+    fn commit_message_bytes_round_trip() {
+        use rand_core::RngCore;
+
+        use crate::{CommitMessage, CommitMessageBytes, COMMIT_MESSAGE_MAX_LEN};
+
+        let mut rng = StdRng::seed_from_u64(489);
+        for _ in 0..200 {
+            let len = (rng.next_u32() as usize) % (COMMIT_MESSAGE_MAX_LEN + 1);
+            let mut msg = vec![0u8; len];
+            rng.fill_bytes(&mut msg);
+            let m = CommitMessage::encode_bytes(&msg).unwrap();
+            assert_eq!(CommitMessage::decode_bytes(m), Ok(msg.clone()));
+
+            let (open, commitment) = Commitment::commit(m, &mut rng);
+            assert!(commitment.verify(&open));
+            assert_eq!(CommitMessage::decode_bytes(open.1), Ok(msg));
+        }
+
+        for msg in [&[][..], &[0], &[0, 0], &[0xff; 31]] {
+            let m = CommitMessage::encode_bytes(msg).unwrap();
+            assert_eq!(m.to_bytes()[msg.len()], 1);
+            assert_eq!(CommitMessage::decode_bytes(m), Ok(msg.to_vec()));
+        }
+        assert_ne!(
+            CommitMessage::encode_bytes(&[0]),
+            CommitMessage::encode_bytes(&[])
+        );
+    }
+
+    // Test that oversize messages and scalars that are not encodings are rejected.
+    #[test]
+    fn commit_message_bytes_rejects() {
+        use crate::{CommitMessage, CommitMessageBytes};
+
+        assert_eq!(
+            CommitMessage::encode_bytes(&[0; 32]),
+            Err(Error::MessageTooLong)
+        );
+        assert_eq!(
+            CommitMessage::decode_bytes(Scalar::ZERO),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            CommitMessage::decode_bytes(Scalar::from(0x0203u32)),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            CommitMessage::decode_bytes(-Scalar::ONE),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            CommitMessage::decode_bytes(Scalar::from(0x0102u32)),
+            Ok(vec![2])
+        );
+    }
 }
@@ -0,0 +1,146 @@
+// Conversions to and from other curve25519-dalek versions for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checked conversions between this crate's `curve25519-dalek` 4 types and the byte encodings used
+//! by `curve25519-dalek` 3.
+//!
+//! Both versions share the ristretto255 point encoding and the little-endian scalar encoding, so
+//! values are exchanged as bytes: on the 3.x side use `CompressedRistretto::to_bytes` /
+//! `CompressedRistretto(bytes).decompress()` and `Scalar::to_bytes` /
+//! `Scalar::from_canonical_bytes`. The functions here perform the canonicity checks on the 4.x side
+//! that are easy to forget when converting by hand.
+//!
+//! `curve25519-dalek` 3 pins `zeroize` below 1.4, which cannot be resolved alongside the rest of
+//! this crate's dependency tree, so no `TryFrom` impls for the 3.x types themselves are provided.
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use crate::{DecryptionKey, EncryptionKey, Error};
+
+/// Convert the bytes of a `curve25519-dalek` 3 `CompressedRistretto` into a point.
+///
+/// Fails with [Error::InvalidPoint](crate::Error::InvalidPoint) if the bytes are not the canonical
+/// encoding of a ristretto255 point.
+pub fn from_dalek3_point_bytes(bytes: &[u8; 32]) -> Result<RistrettoPoint, Error> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or(Error::InvalidPoint)
+}
+
+/// Convert a point into the bytes of a `curve25519-dalek` 3 `CompressedRistretto`.
+pub fn to_dalek3_point_bytes(point: &RistrettoPoint) -> [u8; 32] {
+    point.compress().to_bytes()
+}
+
+/// Convert the bytes of a `curve25519-dalek` 3 `Scalar` into a scalar.
+///
+/// `curve25519-dalek` 3 allows unreduced scalars (through `Scalar::from_bits`), which version 4
+/// no longer represents, so this fails with [Error::InvalidScalar](crate::Error::InvalidScalar)
+/// rather than silently reducing them.
+pub fn from_dalek3_scalar_bytes(bytes: &[u8; 32]) -> Result<Scalar, Error> {
+    Option::from(Scalar::from_canonical_bytes(*bytes)).ok_or(Error::InvalidScalar)
+}
+
+/// Convert a scalar into the bytes of a `curve25519-dalek` 3 `Scalar`.
+pub fn to_dalek3_scalar_bytes(scalar: &Scalar) -> [u8; 32] {
+    scalar.to_bytes()
+}
+
+impl EncryptionKey {
+    /// Create an encryption key from the bytes of a `curve25519-dalek` 3 `CompressedRistretto`.
+    pub fn from_dalek3_bytes(bytes: &[u8; 32]) -> Result<EncryptionKey, Error> {
+        from_dalek3_point_bytes(bytes).map(EncryptionKey)
+    }
+}
+
+impl DecryptionKey {
+    /// Create a decryption key from the bytes of a `curve25519-dalek` 3 `Scalar`.
+    pub fn from_dalek3_bytes(bytes: &[u8; 32]) -> Result<DecryptionKey, Error> {
+        from_dalek3_scalar_bytes(bytes).map(DecryptionKey::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::{
+        from_dalek3_point_bytes, from_dalek3_scalar_bytes, to_dalek3_point_bytes,
+        to_dalek3_scalar_bytes,
+    };
+    use crate::{DecryptionKey, EncryptionKey, Error, Scalar, GENERATOR_TABLE};
+
+    // Values produced by curve25519-dalek 3.2.1:
+    //   s = Scalar::from_bytes_mod_order_wide(&[0, 1, 2, ..., 63])
+    //   P = s * RISTRETTO_BASEPOINT_POINT
+    const DALEK3_SCALAR: &str = "7a3c6282f02d37a05023b60d5428e6cc5961d4c31221937adae0b574e4d07205";
+    const DALEK3_POINT: &str = "7c107ed2840904ea12ce0be6d4d774a14c00b91c21f71dc96c1de2b087a33228";
+    // Scalar::from_bits([0xff; 31] ++ [0x7f]), which curve25519-dalek 3 accepts unreduced.
+    const DALEK3_UNREDUCED: &str =
+        "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f";
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    // Test that values from curve25519-dalek 3 convert to the same values computed here.
+    #[test]
+    fn dalek3_fixture() {
+        let mut wide = [0u8; 64];
+        for (i, byte) in wide.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let s = Scalar::from_bytes_mod_order_wide(&wide);
+        let p = &s * GENERATOR_TABLE;
+
+        let s3 = from_dalek3_scalar_bytes(&from_hex(DALEK3_SCALAR)).unwrap();
+        let p3 = from_dalek3_point_bytes(&from_hex(DALEK3_POINT)).unwrap();
+        assert_eq!(s, s3);
+        assert_eq!(p, p3);
+
+        assert_eq!(to_dalek3_scalar_bytes(&s), from_hex(DALEK3_SCALAR));
+        assert_eq!(to_dalek3_point_bytes(&p), from_hex(DALEK3_POINT));
+
+        let dk = DecryptionKey::from_dalek3_bytes(&from_hex(DALEK3_SCALAR)).unwrap();
+        let ek = EncryptionKey::from_dalek3_bytes(&from_hex(DALEK3_POINT)).unwrap();
+        assert_eq!(dk.encryption_key(), &ek);
+    }
+
+    // Test that non-canonical inputs are rejected rather than reduced.
+    #[test]
+    fn dalek3_rejects_non_canonical() {
+        let unreduced = from_hex(DALEK3_UNREDUCED);
+        assert_eq!(
+            from_dalek3_scalar_bytes(&unreduced),
+            Err(Error::InvalidScalar)
+        );
+        assert_eq!(
+            DecryptionKey::from_dalek3_bytes(&unreduced).unwrap_err(),
+            Error::InvalidScalar
+        );
+
+        // Flipping the low bit makes the encoding "negative", which is never canonical.
+        let mut negative = from_hex(DALEK3_POINT);
+        negative[0] ^= 1;
+        assert_eq!(from_dalek3_point_bytes(&negative), Err(Error::InvalidPoint));
+        assert_eq!(
+            EncryptionKey::from_dalek3_bytes(&[0xff; 32]).unwrap_err(),
+            Error::InvalidPoint
+        );
+    }
+}
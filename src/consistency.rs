@@ -0,0 +1,249 @@
+// Verifiable encryption of one message under several keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_points_to_scalar;
+use crate::{Ciphertext, EncryptionKey, Error, Plaintext, GENERATOR_POINT, GENERATOR_TABLE};
+
+const DOMAIN: &[u8] = b"rust-elgamal consistent encryption";
+
+/// A proof that ciphertexts under a list of keys, one per key in the same order, all encrypt the
+/// same message.
+///
+/// It is a single sigma protocol over the whole list: for ciphertexts `(r_i G, M + r_i Y_i)` it
+/// shows knowledge of every `r_i` with `C1_i = r_i G` and `C2_i - r_i Y_i = C2_0 - r_0 Y_0`, under
+/// one Fiat-Shamir challenge over all the keys and ciphertexts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct ConsistencyProof {
+    c: Scalar,
+    z: Vec<Scalar>,
+}
+
+/// Encrypt `m` under each of `keys`, with a proof that the ciphertexts all encrypt the same
+/// message.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{encrypt_consistent, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let authorities: Vec<_> = (0..3).map(|_| DecryptionKey::new(&mut rng)).collect();
+/// let keys: Vec<_> = authorities.iter().map(|dk| *dk.encryption_key()).collect();
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let (cts, proof) = encrypt_consistent(m, &keys, &mut rng);
+/// assert!(proof.verify(&keys, &cts).is_ok());
+/// assert_eq!(authorities[2].decrypt(cts[2]), m);
+/// ```
+pub fn encrypt_consistent<R: RngCore + CryptoRng>(
+    m: impl Into<Plaintext>,
+    keys: &[EncryptionKey],
+    rng: &mut R,
+) -> (Vec<Ciphertext>, ConsistencyProof) {
+    let m = m.into().to_point();
+    let rs: Vec<_> = keys.iter().map(|_| Scalar::random(rng)).collect();
+    let cts: Vec<_> = keys
+        .iter()
+        .zip(&rs)
+        .map(|(ek, r)| ek.encrypt_with(m, *r))
+        .collect();
+
+    let ss: Vec<_> = keys.iter().map(|_| Scalar::random(rng)).collect();
+    let t: Vec<_> = ss.iter().map(|s| s * GENERATOR_TABLE).collect();
+    let u = differences(keys, &ss);
+    let c = challenge(keys, &cts, &t, &u);
+    let z = ss.iter().zip(&rs).map(|(s, r)| s + c * r).collect();
+    (cts, ConsistencyProof { c, z })
+}
+
+impl ConsistencyProof {
+    /// Verify that `cts[i]` is under `keys[i]` for every `i`, and that they all encrypt the same
+    /// message.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if there are not as many
+    /// ciphertexts and responses as keys, with
+    /// [Error::InvalidParameters](crate::Error::InvalidParameters) if there are no keys, and with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if the proof does not hold.
+    pub fn verify(&self, keys: &[EncryptionKey], cts: &[Ciphertext]) -> Result<(), Error> {
+        for len in [cts.len(), self.z.len()] {
+            if len != keys.len() {
+                return Err(Error::LengthMismatch {
+                    expected: keys.len(),
+                    got: len,
+                });
+            }
+        }
+        if keys.is_empty() {
+            return Err(Error::InvalidParameters);
+        }
+
+        // t_i = z_i G - c C1_i, u_i = (z_i Y_i - z_0 Y_0) - c (C2_i - C2_0)
+        let t: Vec<_> = cts
+            .iter()
+            .zip(&self.z)
+            .map(|(ct, z)| RistrettoPoint::vartime_double_scalar_mul_basepoint(&-self.c, &ct.0, z))
+            .collect();
+        let u: Vec<_> = differences(keys, &self.z)
+            .into_iter()
+            .zip(&cts[1..])
+            .map(|(d, ct)| d - (ct.1 - cts[0].1) * self.c)
+            .collect();
+        if challenge(keys, cts, &t, &u) != self.c {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+// s_i Y_i - s_0 Y_0 for every i > 0
+fn differences(keys: &[EncryptionKey], ss: &[Scalar]) -> Vec<RistrettoPoint> {
+    let mut out = Vec::with_capacity(keys.len().saturating_sub(1));
+    if let (Some(y0), Some(s0)) = (keys.first(), ss.first()) {
+        let base = y0.0 * s0;
+        for (y, s) in keys[1..].iter().zip(&ss[1..]) {
+            out.push(y.0 * s - base);
+        }
+    }
+    out
+}
+
+fn challenge(
+    keys: &[EncryptionKey],
+    cts: &[Ciphertext],
+    t: &[RistrettoPoint],
+    u: &[RistrettoPoint],
+) -> Scalar {
+    let mut points = Vec::with_capacity(1 + 5 * keys.len());
+    points.push(&GENERATOR_POINT);
+    points.extend(keys.iter().map(|ek| &ek.0));
+    for ct in cts {
+        points.push(&ct.0);
+        points.push(&ct.1);
+    }
+    points.extend(t);
+    points.extend(u);
+    hash_points_to_scalar(DOMAIN, &points)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{encrypt_consistent, DecryptionKey, EncryptionKey, Error, RistrettoPoint};
+
+    fn authorities(k: usize) -> (StdRng, Vec<DecryptionKey>, Vec<EncryptionKey>) {
+        let mut rng = StdRng::seed_from_u64(470);
+        let dks: Vec<_> = (0..k).map(|_| DecryptionKey::new(&mut rng)).collect();
+        let eks = dks.iter().map(|dk| *dk.encryption_key()).collect();
+        (rng, dks, eks)
+    }
+
+    // Test that honest encryptions under three keys verify and decrypt to the message.
+    #[test]
+    fn consistent_honest() {
+        let (mut rng, dks, eks) = authorities(3);
+        let m = RistrettoPoint::random(&mut rng);
+        let (cts, proof) = encrypt_consistent(m, &eks, &mut rng);
+        assert_eq!(proof.verify(&eks, &cts), Ok(()));
+        for (dk, ct) in dks.iter().zip(&cts) {
+            assert_eq!(dk.decrypt(*ct), m);
+        }
+
+        // a single key is trivially consistent
+        let (cts, proof) = encrypt_consistent(m, &eks[..1], &mut rng);
+        assert_eq!(proof.verify(&eks[..1], &cts), Ok(()));
+        let (cts, proof) = encrypt_consistent(m, &[], &mut rng);
+        assert_eq!(proof.verify(&[], &cts), Err(Error::InvalidParameters));
+    }
+
+    // Test that a ciphertext of another message, even a valid one under the right key, is caught.
+    #[test]
+    fn consistent_swapped_message() {
+        let (mut rng, _, eks) = authorities(3);
+        let m = RistrettoPoint::random(&mut rng);
+        let (mut cts, proof) = encrypt_consistent(m, &eks, &mut rng);
+        cts[1] = eks[1].encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        assert_eq!(proof.verify(&eks, &cts), Err(Error::VerificationFailed));
+
+        // nor does a whole consistent set for another message pass with this proof
+        let (other, _) = encrypt_consistent(RistrettoPoint::random(&mut rng), &eks, &mut rng);
+        assert_eq!(proof.verify(&eks, &other), Err(Error::VerificationFailed));
+    }
+
+    // Test that ciphertexts out of order with respect to the keys, or with keys missing, fail.
+    #[test]
+    fn consistent_reordered() {
+        let (mut rng, _, eks) = authorities(3);
+        let m = RistrettoPoint::random(&mut rng);
+        let (cts, proof) = encrypt_consistent(m, &eks, &mut rng);
+
+        let mut reordered = cts.clone();
+        reordered.swap(0, 2);
+        assert_eq!(
+            proof.verify(&eks, &reordered),
+            Err(Error::VerificationFailed)
+        );
+        let mut keys = eks.clone();
+        keys.swap(0, 2);
+        assert_eq!(proof.verify(&keys, &cts), Err(Error::VerificationFailed));
+        assert_eq!(
+            proof.verify(&keys, &reordered),
+            Err(Error::VerificationFailed)
+        );
+
+        assert_eq!(
+            proof.verify(&eks[..2], &cts[..2]),
+            Err(Error::LengthMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(
+            proof.verify(&eks, &cts[..2]),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+    }
+
+    // Test that proofs survive serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_consistency() {
+        use crate::ConsistencyProof;
+
+        let (mut rng, _, eks) = authorities(3);
+        let (cts, proof) = encrypt_consistent(RistrettoPoint::random(&mut rng), &eks, &mut rng);
+        let decoded: ConsistencyProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded.verify(&eks, &cts), Ok(()));
+    }
+}
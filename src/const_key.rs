@@ -0,0 +1,177 @@
+// Encryption keys embedded at compile time for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::{EncryptionKey, Error};
+
+/// The compressed bytes of an encryption key, built in a `const` context so that a key can be
+/// baked into a `static`, and decompressed on first use.
+///
+/// With the `std` feature the key is decompressed once and cached, so [get](Self::get) is cheap
+/// after the first call; without it, every call decompresses. Use
+/// [static_encryption_key](crate::static_encryption_key) to define such a static together with a
+/// unit test that checks its bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::{CompressedEncryptionKey, EncryptionKey, RistrettoPoint};
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// static RECIPIENT: CompressedEncryptionKey = EncryptionKey::from_compressed_unchecked_const([
+///     0xb6, 0x2d, 0x65, 0x68, 0xdf, 0x78, 0x56, 0xad, 0x2b, 0xfd, 0x67, 0x15, 0x8b, 0xc2, 0xfe,
+///     0x51, 0x17, 0xba, 0xfe, 0xdc, 0xb4, 0x03, 0x90, 0x31, 0x5e, 0x97, 0xcc, 0x60, 0x1b, 0x36,
+///     0x51, 0x56,
+/// ]);
+///
+/// let mut rng = StdRng::from_entropy();
+/// let ct = RECIPIENT.get().unwrap().encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+/// ```
+pub struct CompressedEncryptionKey {
+    bytes: [u8; 32],
+    #[cfg(feature = "std")]
+    decoded: OnceLock<Result<EncryptionKey, Error>>,
+}
+
+impl CompressedEncryptionKey {
+    /// The compressed bytes, as given.
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    /// The decompressed key.
+    ///
+    /// Fails with [Error::InvalidPoint](crate::Error::InvalidPoint) if the bytes do not encode a
+    /// point, and with [Error::IdentityPoint](crate::Error::IdentityPoint) if they encode the
+    /// identity.
+    pub fn get(&self) -> Result<EncryptionKey, Error> {
+        #[cfg(feature = "std")]
+        return *self
+            .decoded
+            .get_or_init(|| <EncryptionKey>::from_bytes(&self.bytes));
+        #[cfg(not(feature = "std"))]
+        return <EncryptionKey>::from_bytes(&self.bytes);
+    }
+}
+
+impl EncryptionKey {
+    /// Wrap the compressed bytes of an encryption key without checking them, in a `const`
+    /// context. The bytes are checked when the key is first used, by
+    /// [CompressedEncryptionKey::get].
+    pub const fn from_compressed_unchecked_const(bytes: [u8; 32]) -> CompressedEncryptionKey {
+        CompressedEncryptionKey {
+            bytes,
+            #[cfg(feature = "std")]
+            decoded: OnceLock::new(),
+        }
+    }
+}
+
+impl Debug for CompressedEncryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "CompressedEncryptionKey({:?})", self.bytes)
+    }
+}
+
+/// Define a `static` [CompressedEncryptionKey] from its bytes, together with a unit test that
+/// fails if the bytes do not decode to a valid key.
+///
+/// The test lives in a module named after the static, so the macro is meant for module scope.
+///
+/// # Example
+///
+/// ```rust
+/// rust_elgamal::static_encryption_key! {
+///     /// The recipient of our telemetry.
+///     pub static RECIPIENT = [
+///         0xb6, 0x2d, 0x65, 0x68, 0xdf, 0x78, 0x56, 0xad, 0x2b, 0xfd, 0x67, 0x15, 0x8b, 0xc2,
+///         0xfe, 0x51, 0x17, 0xba, 0xfe, 0xdc, 0xb4, 0x03, 0x90, 0x31, 0x5e, 0x97, 0xcc, 0x60,
+///         0x1b, 0x36, 0x51, 0x56,
+///     ];
+/// }
+///
+/// assert!(RECIPIENT.get().is_ok());
+/// ```
+#[macro_export]
+macro_rules! static_encryption_key {
+    ($(#[$attr:meta])* $vis:vis static $name:ident = $bytes:expr;) => {
+        $(#[$attr])*
+        $vis static $name: $crate::CompressedEncryptionKey =
+            $crate::EncryptionKey::from_compressed_unchecked_const($bytes);
+
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $name {
+            #[test]
+            fn decodes() {
+                if let Err(err) = super::$name.get() {
+                    panic!("{} is not a valid encryption key: {}", stringify!($name), err);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::ristretto::CompressedRistretto;
+
+    use crate::{
+        CompressedEncryptionKey, EncryptionKey, Error, RistrettoPoint, Scalar, GENERATOR_TABLE,
+    };
+
+    // 459 * G
+    const KEY_BYTES: [u8; 32] = [
+        0xb6, 0x2d, 0x65, 0x68, 0xdf, 0x78, 0x56, 0xad, 0x2b, 0xfd, 0x67, 0x15, 0x8b, 0xc2, 0xfe,
+        0x51, 0x17, 0xba, 0xfe, 0xdc, 0xb4, 0x03, 0x90, 0x31, 0x5e, 0x97, 0xcc, 0x60, 0x1b, 0x36,
+        0x51, 0x56,
+    ];
+
+    static_encryption_key! {
+        static EMBEDDED = KEY_BYTES;
+    }
+
+    // Test that a key embedded in a static encrypts exactly as the same key parsed at runtime.
+    #[test]
+    fn static_key_matches_runtime() {
+        let runtime: EncryptionKey = CompressedRistretto(KEY_BYTES).decompress().unwrap().into();
+        assert_eq!(runtime.0, &Scalar::from(459u32) * GENERATOR_TABLE);
+
+        let embedded = EMBEDDED.get().unwrap();
+        assert_eq!(embedded, runtime);
+        assert_eq!(EMBEDDED.get(), Ok(runtime));
+        assert_eq!(EMBEDDED.as_bytes(), &KEY_BYTES);
+
+        let m = RistrettoPoint::hash_from_bytes::<sha2::Sha512>(b"telemetry");
+        let r = Scalar::from(1234u32);
+        assert_eq!(embedded.encrypt_with(m, r), runtime.encrypt_with(m, r));
+    }
+
+    // Test that bad bytes are only reported when the key is used.
+    #[test]
+    fn static_key_invalid() {
+        static INVALID: CompressedEncryptionKey =
+            EncryptionKey::from_compressed_unchecked_const([0xff; 32]);
+        static IDENTITY: CompressedEncryptionKey =
+            EncryptionKey::from_compressed_unchecked_const([0; 32]);
+        assert_eq!(INVALID.get(), Err(Error::InvalidPoint));
+        assert_eq!(INVALID.get(), Err(Error::InvalidPoint));
+        assert_eq!(IDENTITY.get(), Err(Error::IdentityPoint));
+    }
+}
@@ -0,0 +1,269 @@
+// CRT encoding of large values for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::dlog::bounded_dlog;
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, EncryptionKey, Error};
+
+/// Encodes values too large for a discrete-logarithm search as their residues modulo several
+/// small, pairwise coprime moduli, one exponential ciphertext per modulus.
+///
+/// Residue vectors add homomorphically, and decryption solves one small discrete logarithm per
+/// modulus and recombines the residues with the Chinese remainder theorem. Values are recovered
+/// modulo the product of the moduli, so sums must stay below it.
+///
+/// The exponent of a ciphertext is not reduced as ciphertexts are added, so the number of values
+/// that may be summed has to be declared with [with_max_summands](Self::with_max_summands);
+/// decryption searches each residue up to `max_summands * (modulus - 1)`, costing about
+/// `2 * sqrt(max_summands * modulus)` group operations per modulus.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CrtEncoder, DecryptionKey};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let encoder = CrtEncoder::new(&[1009, 1013, 1019]).unwrap().with_max_summands(2);
+/// let a = encoder.encrypt(enc_key, 500_000_000, &mut rng);
+/// let b = encoder.encrypt(enc_key, 500_000_000, &mut rng);
+/// let sum = encoder.add(&a, &b).unwrap();
+/// assert_eq!(encoder.decrypt(&dec_key, &sum), Some(1_000_000_000));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrtEncoder {
+    moduli: Vec<u64>,
+    max_summands: u64,
+}
+
+impl CrtEncoder {
+    /// Create an encoder for the given moduli, which must be at least 2, pairwise coprime, and
+    /// have a product that fits in a `u128`.
+    ///
+    /// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) otherwise.
+    pub fn new(moduli: &[u64]) -> Result<CrtEncoder, Error> {
+        if moduli.is_empty() || moduli.iter().any(|m| *m < 2) {
+            return Err(Error::InvalidParameters);
+        }
+        for (i, a) in moduli.iter().enumerate() {
+            if moduli[i + 1..].iter().any(|b| gcd(*a, *b) != 1) {
+                return Err(Error::InvalidParameters);
+            }
+        }
+        moduli
+            .iter()
+            .try_fold(1u128, |acc, m| acc.checked_mul(*m as u128))
+            .ok_or(Error::InvalidParameters)?;
+
+        Ok(CrtEncoder {
+            moduli: moduli.to_vec(),
+            max_summands: 1,
+        })
+    }
+
+    /// Set the number of encrypted values that may be summed before decryption (1 by default).
+    pub fn with_max_summands(mut self, max_summands: u64) -> CrtEncoder {
+        self.max_summands = max_summands.max(1);
+        self
+    }
+
+    /// The moduli of the encoding.
+    pub fn moduli(&self) -> &[u64] {
+        &self.moduli
+    }
+
+    /// The product of the moduli, below which values are recovered exactly.
+    pub fn product(&self) -> u128 {
+        self.moduli.iter().map(|m| *m as u128).product()
+    }
+
+    /// Encrypt `value` as one exponential ciphertext per modulus.
+    pub fn encrypt<G: ElGamalGroup, R: RngCore + CryptoRng>(
+        &self,
+        ek: &EncryptionKey<G>,
+        value: u128,
+        rng: &mut R,
+    ) -> Vec<Ciphertext<G>> {
+        self.moduli
+            .iter()
+            .map(|m| ek.exp_encrypt(G::Scalar::from((value % *m as u128) as u64), rng))
+            .collect()
+    }
+
+    /// Add two encrypted residue vectors.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if either does not have
+    /// one ciphertext per modulus.
+    pub fn add<G: ElGamalGroup>(
+        &self,
+        a: &[Ciphertext<G>],
+        b: &[Ciphertext<G>],
+    ) -> Result<Vec<Ciphertext<G>>, Error> {
+        self.check_len(a.len())?;
+        self.check_len(b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x + y).collect())
+    }
+
+    /// Decrypt an encrypted residue vector.
+    ///
+    /// Returns `None` if the vector does not have one ciphertext per modulus, or if a residue lies
+    /// outside the range allowed by the declared number of summands.
+    pub fn decrypt<G: ElGamalGroup>(
+        &self,
+        dk: &DecryptionKey<G>,
+        cts: &[Ciphertext<G>],
+    ) -> Option<u128> {
+        self.check_len(cts.len()).ok()?;
+
+        let mut value = 0u128;
+        let mut modulus = 1u128;
+        for (m, ct) in self.moduli.iter().zip(cts) {
+            let bound = self.max_summands.checked_mul(m - 1)?;
+            let residue = bounded_dlog(&dk.decrypt(*ct), bound)? % m;
+
+            // Garner's step: find t with value + modulus * t = residue (mod m)
+            let m = *m as u128;
+            let diff = (residue as u128 + m - value % m) % m;
+            let t = diff * mod_inverse((modulus % m) as u64, m as u64)? as u128 % m;
+            value += modulus * t;
+            modulus *= m;
+        }
+        Some(value)
+    }
+
+    fn check_len(&self, got: usize) -> Result<(), Error> {
+        if got != self.moduli.len() {
+            return Err(Error::LengthMismatch {
+                expected: self.moduli.len(),
+                got,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// The inverse of `a` modulo `m`, if it exists.
+fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+    let (mut old_r, mut r) = (a as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    if old_r != 1 {
+        return None;
+    }
+    Some(old_s.rem_euclid(m as i128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::crt::mod_inverse;
+    use crate::{CrtEncoder, DecryptionKey, Error, Scalar};
+
+    const MODULI: [u64; 3] = [65521, 65519, 65497];
+
+    // Test that invalid moduli are rejected.
+    #[test]
+    fn crt_invalid_moduli() {
+        assert_eq!(CrtEncoder::new(&[]), Err(Error::InvalidParameters));
+        assert_eq!(CrtEncoder::new(&[1, 3]), Err(Error::InvalidParameters));
+        assert_eq!(CrtEncoder::new(&[6, 9]), Err(Error::InvalidParameters));
+        assert_eq!(
+            CrtEncoder::new(&[u64::MAX, u64::MAX - 1, u64::MAX - 2]),
+            Err(Error::InvalidParameters)
+        );
+        assert!(CrtEncoder::new(&[u64::MAX, u64::MAX - 1]).is_ok());
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(2, 4), None);
+    }
+
+    // Test that values up to just below the product of the moduli are recovered, including sums.
+    #[test]
+    fn crt_round_trip() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let encoder = CrtEncoder::new(&MODULI).unwrap();
+        let product = encoder.product();
+        for value in [0, 1, 65521, product / 2, product - 1] {
+            let cts = encoder.encrypt(ek, value, &mut rng);
+            assert_eq!(encoder.decrypt(&dk, &cts), Some(value));
+        }
+        // values wrap around at the product
+        let cts = encoder.encrypt(ek, product + 5, &mut rng);
+        assert_eq!(encoder.decrypt(&dk, &cts), Some(5));
+
+        let encoder = encoder.with_max_summands(3);
+        let a = encoder.encrypt(ek, product / 3, &mut rng);
+        let b = encoder.encrypt(ek, product / 3, &mut rng);
+        let c = encoder.encrypt(ek, product / 3 - 1, &mut rng);
+        let sum = encoder.add(&encoder.add(&a, &b).unwrap(), &c).unwrap();
+        assert_eq!(encoder.decrypt(&dk, &sum), Some(product / 3 * 3 - 1));
+    }
+
+    // Test that malformed residue vectors are detected.
+    #[test]
+    fn crt_inconsistent_residues() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let encoder = CrtEncoder::new(&MODULI).unwrap();
+        let mut cts = encoder.encrypt(ek, 12345, &mut rng);
+
+        assert_eq!(encoder.decrypt(&dk, &cts[..2]), None);
+        assert_eq!(
+            encoder.add(&cts, &cts[..2]),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+
+        // a residue beyond what a single value can produce
+        cts[1] = ek.exp_encrypt(Scalar::from(MODULI[1]), &mut rng);
+        assert_eq!(encoder.decrypt(&dk, &cts), None);
+
+        // the sum of two values is out of range until two summands are declared
+        let cts = encoder.encrypt(ek, MODULI[0] as u128 - 1, &mut rng);
+        let sum = encoder.add(&cts, &cts).unwrap();
+        assert_eq!(encoder.decrypt(&dk, &sum), None);
+        let encoder = encoder.with_max_summands(2);
+        assert_eq!(
+            encoder.decrypt(&dk, &sum),
+            Some(2 * (MODULI[0] as u128 - 1))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+// Timing-leak test harness for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A dudect-style harness for checking that operations run in constant time.
+//!
+//! Each measurement runs the operation on an input from one of two classes: a single fixed input,
+//! or a freshly generated random one. The classes are interleaved at random so that drift in the
+//! machine's state affects both equally. The slowest measurements are cropped, and Welch's t-test
+//! is applied to the rest; a large |t| means the running time depends on the input.
+//!
+//! The checks on this crate's own operations are `#[ignore]`d tests, run with
+//! `cargo test --release --features ct-test -- --ignored`. They are statistical, so a failure
+//! should be reproduced before being trusted, and a pass is evidence rather than proof.
+
+use std::hint::black_box;
+use std::time::Instant;
+use std::vec::Vec;
+
+use rand_core::RngCore;
+
+/// The |t| above which [check] reports a timing leak. dudect treats values above 10 as a
+/// definite leak.
+pub const T_THRESHOLD: f64 = 10.0;
+
+/// Which input class a measurement used.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Class {
+    /// The same input every time.
+    Fixed,
+    /// A new random input every time.
+    Random,
+}
+
+/// The outcome of a timing check.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimingReport {
+    /// Welch's t-statistic comparing the two classes.
+    pub t: f64,
+    /// The number of measurements kept after cropping.
+    pub samples: usize,
+}
+
+impl TimingReport {
+    /// Whether |t| exceeds [T_THRESHOLD].
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > T_THRESHOLD
+    }
+}
+
+/// Time `op` on `samples` inputs produced by `input`, and compare the two classes with Welch's
+/// t-test.
+///
+/// `input` is called with the class to produce; all inputs are generated before any measurement
+/// so that input generation does not disturb the timings. Measurements above the 90th percentile
+/// are discarded as interference from the rest of the system.
+pub fn check<T, R, I, F>(samples: usize, rng: &mut R, mut input: I, mut op: F) -> TimingReport
+where
+    R: RngCore,
+    I: FnMut(Class, &mut R) -> T,
+    F: FnMut(&T),
+{
+    let inputs: Vec<(Class, T)> = (0..samples)
+        .map(|_| {
+            let class = if rng.next_u32() & 1 == 0 {
+                Class::Fixed
+            } else {
+                Class::Random
+            };
+            (class, input(class, rng))
+        })
+        .collect();
+
+    let timings: Vec<(Class, f64)> = inputs
+        .iter()
+        .map(|(class, x)| {
+            let start = Instant::now();
+            op(black_box(x));
+            (*class, start.elapsed().as_nanos() as f64)
+        })
+        .collect();
+
+    let mut sorted: Vec<f64> = timings.iter().map(|(_, t)| *t).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let cutoff = sorted[sorted.len() * 9 / 10];
+
+    let kept = |c: Class| -> Vec<f64> {
+        timings
+            .iter()
+            .filter(|(class, t)| *class == c && *t <= cutoff)
+            .map(|(_, t)| *t)
+            .collect()
+    };
+    let fixed = kept(Class::Fixed);
+    let random = kept(Class::Random);
+
+    TimingReport {
+        t: welch_t(&fixed, &random),
+        samples: fixed.len() + random.len(),
+    }
+}
+
+/// Welch's t-statistic for the difference in means of two samples of unequal variance.
+///
+/// Returns 0 if either sample has fewer than two elements, or if both have zero variance.
+pub fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    fn mean_var(x: &[f64]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().sum::<f64>() / n;
+        let var = x.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+        (mean, var)
+    }
+
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let (mean_a, var_a) = mean_var(a);
+    let (mean_b, var_b) = mean_var(b);
+    let se = (var_a / a.len() as f64 + var_b / b.len() as f64).sqrt();
+    if se == 0.0 {
+        return 0.0;
+    }
+    (mean_a - mean_b) / se
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::{RngCore, SeedableRng};
+    use subtle::ConstantTimeEq;
+
+    use crate::ct_test::{check, welch_t, Class, TimingReport};
+    use crate::{Commitment, DecryptionKey, DiscreteLogTable, Open, RistrettoPoint, Scalar};
+
+    const SAMPLES: usize = 200_000;
+
+    fn assert_no_leak(name: &str, report: TimingReport) {
+        assert!(!report.leaks(), "{} leaks timing: {:?}", name, report);
+    }
+
+    // Test Welch's t-statistic against values computed by hand.
+    #[test]
+    fn welch_t_known_values() {
+        assert_eq!(welch_t(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert_eq!(welch_t(&[1.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(welch_t(&[5.0, 5.0], &[5.0, 5.0]), 0.0);
+
+        // means 2 and 5, variances 1 and 1, n = 3: t = -3 / sqrt(2/3)
+        let t = welch_t(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]);
+        assert!((t + 3.0 / (2.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    }
+
+    // Test that the harness reports the classes it was asked for.
+    #[test]
+    fn check_counts_samples() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let report = check(1000, &mut rng, |class, _| class, |_| ());
+        assert!(report.samples <= 1000 && report.samples >= 800);
+
+        let mut fixed = 0;
+        check(
+            1000,
+            &mut rng,
+            |class, _| fixed += (class == Class::Fixed) as usize,
+            |_| (),
+        );
+        assert!(fixed > 400 && fixed < 600);
+    }
+
+    #[test]
+    #[ignore]
+    fn ct_decrypt() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let fixed = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+
+        let report = check(
+            SAMPLES,
+            &mut rng,
+            |class, rng| match class {
+                Class::Fixed => fixed,
+                Class::Random => ek.encrypt(RistrettoPoint::random(rng), rng),
+            },
+            |ct| {
+                std::hint::black_box(dk.decrypt(*ct));
+            },
+        );
+        assert_no_leak("DecryptionKey::decrypt", report);
+    }
+
+    #[test]
+    #[ignore]
+    fn ct_commitment_verify() {
+        let mut rng = StdRng::from_entropy();
+        let (open, commitment) = Commitment::commit(Scalar::random(&mut rng), &mut rng);
+
+        let report = check(
+            SAMPLES,
+            &mut rng,
+            |class, rng| match class {
+                Class::Fixed => open.clone(),
+                Class::Random => Open(Scalar::random(rng), Scalar::random(rng)),
+            },
+            |open| {
+                std::hint::black_box(commitment.verify_ct(open));
+            },
+        );
+        assert_no_leak("Commitment::verify_ct", report);
+    }
+
+    #[test]
+    #[ignore]
+    fn ct_point_eq() {
+        let mut rng = StdRng::from_entropy();
+        let p = RistrettoPoint::random(&mut rng);
+
+        let report = check(
+            SAMPLES,
+            &mut rng,
+            |class, rng| match class {
+                Class::Fixed => p,
+                Class::Random => RistrettoPoint::random(rng),
+            },
+            |q| {
+                std::hint::black_box(p.ct_eq(q));
+            },
+        );
+        assert_no_leak("RistrettoPoint::ct_eq", report);
+    }
+
+    #[test]
+    #[ignore]
+    fn ct_scalar_eq() {
+        let mut rng = StdRng::from_entropy();
+        let s = Scalar::random(&mut rng);
+
+        let report = check(
+            SAMPLES,
+            &mut rng,
+            |class, rng| match class {
+                Class::Fixed => s,
+                Class::Random => Scalar::random(rng),
+            },
+            |t| {
+                std::hint::black_box(s.ct_eq(t));
+            },
+        );
+        assert_no_leak("Scalar::ct_eq", report);
+    }
+
+    #[test]
+    #[ignore]
+    fn ct_decrypt_u16() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let table = DiscreteLogTable::new(1024);
+        // a hit at the very start of the table, against hits anywhere and misses
+        let fixed = ek.exp_encrypt(Scalar::ZERO, &mut rng);
+
+        let report = check(
+            SAMPLES / 10,
+            &mut rng,
+            |class, rng| match class {
+                Class::Fixed => fixed,
+                Class::Random => ek.exp_encrypt(Scalar::from(rng.next_u32() % 2048), rng),
+            },
+            |ct| {
+                std::hint::black_box(dk.decrypt_u16_ct(*ct, &table));
+            },
+        );
+        assert_no_leak("DecryptionKey::decrypt_u16_ct", report);
+    }
+}
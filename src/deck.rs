@@ -0,0 +1,407 @@
+// Mental poker deck for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mental poker: shuffling an encrypted deck of cards and revealing cards jointly.
+//!
+//! The players aggregate their encryption keys into a joint key with [Deck::joint_key], and start
+//! from [Deck::new_standard], the 52 cards in order. Each player in turn applies
+//! [Deck::shuffle_pass], which permutes and rerandomises every card, so once everybody has
+//! shuffled nobody knows the order. A card is revealed once every player has published a
+//! [PartialDecryption] of it, each carrying a proof that it was computed correctly; no player can
+//! decrypt a card alone.
+//!
+//! Cards are encoded as hashed points (see [encode_hashed](crate::encode_hashed)), and mapped back
+//! with a lookup table of all 52 encodings.
+//!
+//! The joint key is only as good as the keys going into it: a player who chooses its key after
+//! seeing the others' can cancel them out, so players should prove knowledge of their secrets
+//! (or commit to their keys first) before the joint key is formed. A [ShuffleWitness] can be kept
+//! to open a pass later with [ShuffleWitness::verify], but this module does not prove shuffles
+//! correct.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::Range;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_to_point;
+use crate::trace::op_span;
+use crate::{Ciphertext, DecryptionKey, DleqProof, EncryptionKey, Error, Permutation};
+
+const CARD_DOMAIN: &[u8] = b"rust-elgamal deck";
+
+/// The number of cards in a standard deck.
+pub const DECK_SIZE: usize = 52;
+
+/// A card of the standard deck, identified by its index: `13 * suit + rank`, with suits and ranks
+/// counted from zero.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Card(u8);
+
+impl Card {
+    /// The card with the given index, or `None` if it is not below [DECK_SIZE].
+    pub fn from_index(index: u8) -> Option<Card> {
+        (usize::from(index) < DECK_SIZE).then_some(Card(index))
+    }
+
+    /// The index of the card.
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    /// The suit of the card, from 0 to 3.
+    pub fn suit(&self) -> u8 {
+        self.0 / 13
+    }
+
+    /// The rank of the card, from 0 to 12.
+    pub fn rank(&self) -> u8 {
+        self.0 % 13
+    }
+
+    /// The point encoding the card.
+    pub fn to_point(&self) -> RistrettoPoint {
+        hash_to_point(CARD_DOMAIN, &[self.0])
+    }
+
+    /// The card encoded by `point`, or `None` if it does not encode a card.
+    pub fn from_point(point: &RistrettoPoint) -> Option<Card> {
+        let table = card_table();
+        let compressed = point.compress();
+        (0..DECK_SIZE as u8)
+            .find(|&i| table[usize::from(i)] == compressed)
+            .map(Card)
+    }
+}
+
+// The encodings of every card, indexed by card.
+fn card_table() -> Vec<CompressedRistretto> {
+    (0..DECK_SIZE as u8)
+        .map(|i| Card(i).to_point().compress())
+        .collect()
+}
+
+/// A deck of encrypted cards, all under the same (joint) encryption key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Deck {
+    ek: EncryptionKey,
+    cards: Vec<Ciphertext>,
+}
+
+/// What a player used in one [Deck::shuffle_pass]: the permutation (the card at position `i`
+/// came from position `permutation[i]`) and the rerandomisation factor applied to each card.
+///
+/// This is secret: anyone holding the witnesses of every pass can follow the cards through the
+/// shuffle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct ShuffleWitness {
+    pub permutation: Vec<usize>,
+    pub randomness: Vec<Scalar>,
+}
+
+impl ShuffleWitness {
+    /// Check that `output` is `input` shuffled and rerandomised under `ek` with this witness.
+    ///
+    /// This opens a pass, so it is for audits after the game, not for checking a shuffle during
+    /// play. Fails with [Error::LengthMismatch] if the lengths differ, with
+    /// [Error::InvalidPermutation] if the permutation is not one, and with
+    /// [Error::VerificationFailed] if a card does not match.
+    pub fn verify(
+        &self,
+        ek: &EncryptionKey,
+        input: &[Ciphertext],
+        output: &[Ciphertext],
+    ) -> Result<(), Error> {
+        self.check_shape(input.len(), output.len())?;
+        let _span = op_span!("verify_shuffle", cards = output.len());
+        self.verify_range(ek, input, output, 0..output.len())
+    }
+
+    // Check the lengths and the permutation, so that ranges can then be checked independently.
+    pub(crate) fn check_shape(&self, input_len: usize, output_len: usize) -> Result<(), Error> {
+        for got in [output_len, self.permutation.len(), self.randomness.len()] {
+            if got != input_len {
+                return Err(Error::LengthMismatch {
+                    expected: input_len,
+                    got,
+                });
+            }
+        }
+        Permutation::try_from(self.permutation.clone())?;
+        Ok(())
+    }
+
+    // Check the cards at positions `range` of the output; the shape must have been checked.
+    pub(crate) fn verify_range(
+        &self,
+        ek: &EncryptionKey,
+        input: &[Ciphertext],
+        output: &[Ciphertext],
+        range: Range<usize>,
+    ) -> Result<(), Error> {
+        for i in range {
+            let expected =
+                input[self.permutation[i]] + ek.encrypt_with(RistrettoPoint::identity(), self.randomness[i]);
+            if output[i] != expected {
+                return Err(Error::VerificationFailed);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One player's share of the decryption of a card, `x_i * rG`, with a proof that it used the
+/// secret of its encryption key.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct PartialDecryption {
+    key: EncryptionKey,
+    share: RistrettoPoint,
+    proof: DleqProof,
+}
+
+impl PartialDecryption {
+    /// Compute `dk`'s share of the decryption of `ct`.
+    pub fn new<R: RngCore + CryptoRng>(
+        dk: &DecryptionKey,
+        ct: &Ciphertext,
+        rng: &mut R,
+    ) -> PartialDecryption {
+        let share = ct.0 * dk.secret;
+        PartialDecryption {
+            key: *dk.encryption_key(),
+            share,
+            proof: DleqProof::prove(dk, &ct.0, &share, rng),
+        }
+    }
+
+    /// The encryption key of the player who computed the share.
+    pub fn encryption_key(&self) -> &EncryptionKey {
+        &self.key
+    }
+
+    /// Check the proof of the share against `ct`.
+    ///
+    /// Fails with [Error::VerificationFailed] if the share was not computed correctly.
+    pub fn verify(&self, ct: &Ciphertext) -> Result<(), Error> {
+        self.proof.verify(&self.key, &ct.0, &self.share)
+    }
+}
+
+impl Deck {
+    /// The joint encryption key of the players with the given keys: the sum of the keys.
+    pub fn joint_key(keys: &[EncryptionKey]) -> EncryptionKey {
+        EncryptionKey(keys.iter().map(|key| key.0).sum())
+    }
+
+    /// The 52 cards in order, encrypted under `ek` with no randomness, so anyone can check the
+    /// starting deck.
+    pub fn new_standard(ek: &EncryptionKey) -> Deck {
+        let cards = (0..DECK_SIZE as u8)
+            .map(|i| Ciphertext(RistrettoPoint::identity(), Card(i).to_point()))
+            .collect();
+        Deck { ek: *ek, cards }
+    }
+
+    /// The key the cards are encrypted under.
+    pub fn encryption_key(&self) -> &EncryptionKey {
+        &self.ek
+    }
+
+    /// The encrypted cards, in order.
+    pub fn cards(&self) -> &[Ciphertext] {
+        &self.cards
+    }
+
+    /// Shuffle and rerandomise the deck, returning what was used to do so.
+    pub fn shuffle_pass<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> ShuffleWitness {
+        let n = self.cards.len();
+        let _span = op_span!("shuffle_pass", cards = n);
+
+        let permutation = Permutation::random(n, rng).into_vec();
+
+        let randomness: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        self.cards = permutation
+            .iter()
+            .zip(&randomness)
+            .map(|(&from, r)| {
+                self.cards[from] + self.ek.encrypt_with(RistrettoPoint::identity(), *r)
+            })
+            .collect();
+
+        ShuffleWitness {
+            permutation,
+            randomness,
+        }
+    }
+
+    /// Reveal the card at `index`, given the partial decryptions of it by every player.
+    ///
+    /// Fails with [Error::LengthMismatch] if `index` is out of range, with
+    /// [Error::VerificationFailed] if a partial decryption is invalid or the result is not a
+    /// card, and with [Error::KeyMismatch] if the partial decryptions do not come from exactly
+    /// the players whose keys make up the deck's key.
+    pub fn reveal_card(&self, index: usize, partials: &[PartialDecryption]) -> Result<Card, Error> {
+        let ct = self.cards.get(index).ok_or(Error::LengthMismatch {
+            expected: self.cards.len(),
+            got: index,
+        })?;
+        let _span = op_span!("reveal_card", shares = partials.len());
+
+        let mut keys = RistrettoPoint::identity();
+        let mut shares = RistrettoPoint::identity();
+        for partial in partials {
+            partial.verify(ct)?;
+            keys += partial.key.0;
+            shares += partial.share;
+        }
+        if keys != self.ek.0 {
+            return Err(Error::KeyMismatch);
+        }
+
+        Card::from_point(&(ct.1 - shares)).ok_or(Error::VerificationFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use alloc::vec::Vec;
+
+    use crate::deck::{Card, Deck, PartialDecryption, DECK_SIZE};
+    use crate::{DecryptionKey, EncryptionKey, Error};
+
+    // Test the card encoding and its lookup table.
+    #[test]
+    fn card_points() {
+        for i in 0..DECK_SIZE as u8 {
+            let card = Card::from_index(i).unwrap();
+            assert_eq!(Card::from_point(&card.to_point()), Some(card));
+            assert_eq!(13 * card.suit() + card.rank(), i);
+        }
+        assert_eq!(Card::from_index(52), None);
+    }
+
+    // Run a 3-player shuffle and reveal every card.
+    #[test]
+    fn three_player_game() {
+        let mut rng = StdRng::from_entropy();
+        let players: Vec<_> = (0..3).map(|_| DecryptionKey::new(&mut rng)).collect();
+        let keys: Vec<EncryptionKey> = players.iter().map(|dk| *dk.encryption_key()).collect();
+
+        let mut deck = Deck::new_standard(&Deck::joint_key(&keys));
+        let start = deck.clone();
+        for _ in &players {
+            let witness = deck.shuffle_pass(&mut rng);
+            assert_eq!(witness.permutation.len(), DECK_SIZE);
+        }
+        assert_ne!(deck, start);
+
+        let mut revealed: Vec<Card> = (0..DECK_SIZE)
+            .map(|i| {
+                let partials: Vec<_> = players
+                    .iter()
+                    .map(|dk| PartialDecryption::new(dk, &deck.cards()[i], &mut rng))
+                    .collect();
+                deck.reveal_card(i, &partials).unwrap()
+            })
+            .collect();
+        revealed.sort();
+        let full: Vec<Card> = (0..DECK_SIZE as u8)
+            .map(|i| Card::from_index(i).unwrap())
+            .collect();
+        assert_eq!(revealed, full);
+    }
+
+    // Test that one player, or a forged share, cannot reveal a card.
+    #[test]
+    fn reveal_needs_everyone() {
+        let mut rng = StdRng::from_entropy();
+        let players: Vec<_> = (0..3).map(|_| DecryptionKey::new(&mut rng)).collect();
+        let keys: Vec<EncryptionKey> = players.iter().map(|dk| *dk.encryption_key()).collect();
+
+        let mut deck = Deck::new_standard(&Deck::joint_key(&keys));
+        deck.shuffle_pass(&mut rng);
+        let ct = deck.cards()[0];
+
+        // a player decrypting alone gets a point which is not a card
+        assert_eq!(Card::from_point(&players[0].decrypt(ct)), None);
+
+        let partials: Vec<_> = players
+            .iter()
+            .map(|dk| PartialDecryption::new(dk, &ct, &mut rng))
+            .collect();
+        assert_eq!(deck.reveal_card(0, &partials[..1]), Err(Error::KeyMismatch));
+        assert_eq!(deck.reveal_card(0, &partials[..2]), Err(Error::KeyMismatch));
+
+        // a share for a different card does not verify
+        let other = PartialDecryption::new(&players[2], &deck.cards()[1], &mut rng);
+        let forged = [partials[0], partials[1], other];
+        assert_eq!(deck.reveal_card(0, &forged), Err(Error::VerificationFailed));
+
+        assert_eq!(
+            deck.reveal_card(52, &partials),
+            Err(Error::LengthMismatch {
+                expected: 52,
+                got: 52
+            })
+        );
+    }
+
+    // Test that a witness opens its own pass, and not a tampered pass or a malformed witness.
+    #[test]
+    fn shuffle_witness_opens_pass() {
+        let mut rng = StdRng::seed_from_u64(491);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let mut deck = Deck::new_standard(&ek);
+        deck.shuffle_pass(&mut rng);
+        let input = deck.cards().to_vec();
+        let witness = deck.shuffle_pass(&mut rng);
+        let output = deck.cards();
+        assert_eq!(witness.verify(&ek, &input, output), Ok(()));
+
+        let mut swapped = output.to_vec();
+        swapped.swap(3, 40);
+        assert_eq!(
+            witness.verify(&ek, &input, &swapped),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            witness.verify(&ek, &input, &output[1..]),
+            Err(Error::LengthMismatch {
+                expected: 52,
+                got: 51
+            })
+        );
+        let mut repeated = witness.clone();
+        repeated.permutation[0] = repeated.permutation[1];
+        assert_eq!(
+            repeated.verify(&ek, &input, output),
+            Err(Error::InvalidPermutation)
+        );
+    }
+}
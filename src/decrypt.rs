@@ -13,25 +13,71 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter};
 
-use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
-use rand_core::{CryptoRng, RngCore};
+use ff::Field;
+#[cfg(feature = "os-rng")]
+use rand_core::OsRng;
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "serde-secret")]
+use core::marker::PhantomData;
+#[cfg(feature = "serde-secret")]
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 
-use crate::{Ciphertext, EncryptionKey};
+#[cfg(feature = "alloc")]
+use crate::dlog::{bounded_dlog, bounded_dlog_signed};
+use crate::{Ciphertext, ElGamalGroup, ElGamalRng, EncryptionKey, Error};
+
+const SEED_DOMAIN: &[u8] = b"rust-elgamal decryption key seed";
 
 /// An ElGamal decryption key (also called a private key in other implementations).
-#[derive(Copy, Clone, Eq, PartialEq)]
-#[cfg_attr(feature = "enable-serde", derive(Serialize))]
-pub struct DecryptionKey {
-    pub(crate) secret: Scalar,
-    #[cfg_attr(feature = "enable-serde", serde(skip_serializing))]
-    pub(crate) ek: EncryptionKey,
+///
+/// Decryption keys are `Clone` but not `Copy`, so the secret is only duplicated where the code
+/// says so. Passing a key by value moves it:
+///
+/// ```compile_fail
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::DecryptionKey;
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let moved = dec_key;
+/// let enc_key = dec_key.encryption_key();
+/// ```
+///
+/// and a second owned copy has to be asked for:
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::DecryptionKey;
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let copy = dec_key.clone();
+/// assert_eq!(copy, dec_key);
+/// ```
+///
+/// Equality compares the secrets in constant time, through [ConstantTimeEq].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize))]
+#[cfg_attr(
+    feature = "serde-secret",
+    serde(bound(serialize = "G::Scalar: Serialize"))
+)]
+pub struct DecryptionKey<G: ElGamalGroup = RistrettoPoint> {
+    pub(crate) secret: G::Scalar,
+    #[cfg_attr(feature = "serde-secret", serde(skip_serializing))]
+    pub(crate) ek: EncryptionKey<G>,
 }
 
 impl DecryptionKey {
@@ -48,12 +94,138 @@ impl DecryptionKey {
     /// let mut rng = StdRng::from_entropy();
     /// let dec_key = DecryptionKey::new(&mut rng);
     /// ```
-    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        let secret = Scalar::random(rng);
-        let ek = EncryptionKey(&secret * RISTRETTO_BASEPOINT_TABLE);
+    pub fn new<R: ElGamalRng<V>, V>(rng: &mut R) -> Self {
+        Self::random(rng)
+    }
+
+    /// Generate a new ElGamal decryption key using the operating system's randomness source,
+    /// together with its corresponding encryption key.
+    ///
+    /// This is a cryptographically safe default for callers who do not need to control the
+    /// randomness source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let dec_key = DecryptionKey::generate();
+    /// ```
+    #[cfg(feature = "os-rng")]
+    pub fn generate() -> Self {
+        Self::new(&mut OsRng)
+    }
+
+    /// Derive a decryption key deterministically from a 32-byte `seed`, for example the output of
+    /// a KDF or a fixed seed for test fixtures.
+    ///
+    /// The secret is a SHA-512 hash of the seed under a domain of its own, reduced modulo the
+    /// group order. The derivation is pinned by known-answer tests and will not change between
+    /// releases; the seed must be as secret, and as uniformly random, as the key itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let dk = DecryptionKey::from_seed([7u8; 32]);
+    /// assert_eq!(dk, DecryptionKey::from_seed([7u8; 32]));
+    /// assert_ne!(dk, DecryptionKey::from_seed([8u8; 32]));
+    /// ```
+    pub fn from_seed(seed: [u8; 32]) -> DecryptionKey {
+        let digest = Sha512::new()
+            .chain_update((SEED_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(SEED_DOMAIN)
+            .chain_update(seed)
+            .finalize();
+        DecryptionKey::from(Scalar::from_bytes_mod_order_wide(&digest.into()))
+    }
+
+    /// The 32-byte little-endian encoding of the secret scalar.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dk = DecryptionKey::new(&mut rng);
+    /// assert_eq!(<DecryptionKey>::from_bytes(&dk.to_bytes()), Ok(dk));
+    /// ```
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// Read a decryption key from its 32-byte encoding, failing with
+    /// [Error::InvalidScalar](crate::Error::InvalidScalar) if the scalar is not canonical or is
+    /// zero.
+    ///
+    /// As with [EncryptionKey::from_bytes], write `<DecryptionKey>::from_bytes` where the
+    /// `ristretto448` feature may be enabled.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<DecryptionKey, Error> {
+        let secret: Option<Scalar> = Scalar::from_canonical_bytes(*bytes).into();
+        match secret {
+            Some(secret) if secret != Scalar::ZERO => Ok(DecryptionKey::from(secret)),
+            _ => Err(Error::InvalidScalar),
+        }
+    }
+}
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Generate a new ElGamal decryption key in the group `G` using the randomness source `rng`,
+    /// together with its corresponding encryption key.
+    ///
+    /// This is [new](DecryptionKey::new) for any backend; the group usually has to be named.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::<RistrettoPoint>::random(&mut rng);
+    /// ```
+    pub fn random<R: ElGamalRng<V>, V>(rng: &mut R) -> Self {
+        let secret = G::Scalar::random(rng.compat());
+        let ek = EncryptionKey(G::mul_generator(&secret));
         Self { secret, ek }
     }
 
+    /// Wrap a secret scalar made elsewhere, for example inside an MPC protocol, as a decryption
+    /// key, failing with [Error::InvalidScalar](crate::Error::InvalidScalar) for zero, whose
+    /// encryption key is the identity.
+    ///
+    /// The scalar must be uniformly random and kept secret; to make a key in this crate use
+    /// [random](DecryptionKey::random) or [from_seed](DecryptionKey::from_seed) instead. The
+    /// group cannot be inferred from the scalar, so name it, as in `<DecryptionKey>::from_scalar`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{DecryptionKey, Error, Scalar};
+    ///
+    /// let dk = <DecryptionKey>::from_scalar(Scalar::from(42u32)).unwrap();
+    /// assert_eq!(dk.as_scalar(), &Scalar::from(42u32));
+    /// assert_eq!(<DecryptionKey>::from_scalar(Scalar::ZERO), Err(Error::InvalidScalar));
+    /// ```
+    pub fn from_scalar(secret: G::Scalar) -> Result<Self, Error> {
+        if bool::from(secret.is_zero()) {
+            return Err(Error::InvalidScalar);
+        }
+        let ek = EncryptionKey(G::mul_generator(&secret));
+        Ok(Self { secret, ek })
+    }
+
+    /// The secret scalar, for handing back to a protocol outside this crate. Prefer
+    /// [to_bytes](DecryptionKey::to_bytes) for storing the key.
+    pub fn as_scalar(&self) -> &G::Scalar {
+        &self.secret
+    }
+
     /// Decrypt the ciphertext `ct`.
     ///
     /// # Example
@@ -71,33 +243,201 @@ impl DecryptionKey {
     /// let decrypted = dec_key.decrypt(ct);
     /// assert_eq!(m, decrypted);
     /// ```
-    pub fn decrypt(&self, ct: Ciphertext) -> RistrettoPoint {
-        ct.1 - ct.0 * &self.secret
+    pub fn decrypt(&self, ct: Ciphertext<G>) -> G {
+        ct.1 - ct.0 * self.secret
+    }
+
+    /// Check whether `ct` encrypts `expected`, in constant time.
+    ///
+    /// The decryption is compared with `expected` through their encodings, as
+    /// [Commitment::verify_ct](crate::Commitment::verify_ct) does, so neither the answer nor the
+    /// plaintext leaks through a branch or an early exit. Use this for checking ciphertexts
+    /// against a small allow-list of plaintexts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let m = RistrettoPoint::random(&mut rng);
+    /// let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+    ///
+    /// assert!(bool::from(dec_key.is_encryption_of(&ct, &m)));
+    /// assert!(!bool::from(dec_key.is_encryption_of(&ct, &RistrettoPoint::random(&mut rng))));
+    /// ```
+    pub fn is_encryption_of(&self, ct: &Ciphertext<G>, expected: &G) -> Choice {
+        self.decrypt(*ct)
+            .to_bytes()
+            .as_ref()
+            .ct_eq(expected.to_bytes().as_ref())
+    }
+
+    /// Decrypt an exponential ciphertext `ct` of a `u32`, such as one made by
+    /// [EncryptionKey::encrypt_scalar] or a homomorphic sum of them, by baby-step giant-step.
+    ///
+    /// The result is none if the exponent is not a `u32`, which is also what a ciphertext of a
+    /// point that is not a small multiple of the generator gives. This searches the whole `u32`
+    /// range, costing about 2<sup>17</sup> group operations and a table of 2<sup>16</sup> points;
+    /// use [decrypt_u32_bounded](Self::decrypt_u32_bounded) when the values are known to be
+    /// smaller.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let sum = enc_key.encrypt_scalar(40u64, &mut rng) + enc_key.encrypt_scalar(2u64, &mut rng);
+    /// assert_eq!(dec_key.decrypt_u32(sum), Some(42));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_u32(&self, ct: Ciphertext<G>) -> Option<u32> {
+        self.decrypt_u32_bounded(ct, u32::MAX)
+    }
+
+    /// Decrypt an exponential ciphertext `ct` of a value in `[0, bound]`, as
+    /// [decrypt_u32](Self::decrypt_u32) does, with a search costing about `2 * sqrt(bound)` group
+    /// operations.
+    ///
+    /// The result is none if the value is greater than `bound`.
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_u32_bounded(&self, ct: Ciphertext<G>, bound: u32) -> Option<u32> {
+        bounded_dlog(&self.decrypt(ct), bound as u64).map(|x| x as u32)
+    }
+
+    /// Decrypt an exponential ciphertext `ct` of a value in `[-bound, bound]`, such as a difference
+    /// of counters made by subtracting ciphertexts.
+    ///
+    /// The result is none if the value is outside the range, or does not fit in an `i64`. The
+    /// search for `x` and `-x` shares one baby-step table, costing about `3 * sqrt(bound)` group
+    /// operations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let yes = enc_key.encrypt_scalar(40u64, &mut rng);
+    /// let no = enc_key.encrypt_scalar(52u64, &mut rng);
+    /// assert_eq!(dec_key.decrypt_i64(yes - no, 1000), Some(-12));
+    /// assert_eq!(dec_key.decrypt_i64(no - yes, 1000), Some(12));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_i64(&self, ct: Ciphertext<G>, bound: u64) -> Option<i64> {
+        bounded_dlog_signed(&self.decrypt(ct), bound)
+    }
+
+    /// Check in constant time whether each of `cts` encrypts `expected`, as
+    /// [is_encryption_of](Self::is_encryption_of) does for one, encoding `expected` only once.
+    #[cfg(feature = "alloc")]
+    pub fn filter_encryptions_of(&self, cts: &[Ciphertext<G>], expected: &G) -> Vec<Choice> {
+        let expected = expected.to_bytes();
+        cts.iter()
+            .map(|ct| {
+                self.decrypt(*ct)
+                    .to_bytes()
+                    .as_ref()
+                    .ct_eq(expected.as_ref())
+            })
+            .collect()
+    }
+
+    /// Strip this key's layer from a ciphertext under a sum of encryption keys that includes this
+    /// key's, leaving the first component for the next key holder.
+    ///
+    /// With `ct = (rG, m + r(Y_1 + ... + Y_n))`, each holder of an `x_i` subtracts `x_i rG` from
+    /// the second component, in any order; once all have, the second component is `m`. Each
+    /// intermediate ciphertext is an encryption of `m` under the keys that are left.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, EncryptionKey, GENERATOR_TABLE, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dks: Vec<_> = (0..3).map(|_| DecryptionKey::new(&mut rng)).collect();
+    /// let joint: EncryptionKey = dks.iter().map(|dk| dk.encryption_key()).sum();
+    ///
+    /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+    /// let ct = joint.encrypt(m, &mut rng);
+    /// let stripped = dks.iter().rev().fold(ct, |ct, dk| dk.partial_decrypt(ct));
+    /// assert_eq!(stripped.inner().1, m);
+    /// ```
+    pub fn partial_decrypt(&self, ct: Ciphertext<G>) -> Ciphertext<G> {
+        Ciphertext(ct.0, ct.1 - ct.0 * self.secret)
     }
 
     /// Retrieve the encryption key corresponding to this decryption key.
-    pub fn encryption_key(&self) -> &EncryptionKey {
+    pub fn encryption_key(&self) -> &EncryptionKey<G> {
         &self.ek
     }
 }
 
-impl Debug for DecryptionKey {
+impl<G: ElGamalGroup> Debug for DecryptionKey<G> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "DecryptionKey({:?})", self.secret)
+        f.write_str("DecryptionKey(<redacted>)")
+    }
+}
+
+impl<G: ElGamalGroup> ConstantTimeEq for DecryptionKey<G> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.secret.ct_eq(&other.secret) & self.ek.ct_eq(&other.ek)
+    }
+}
+
+impl<G: ElGamalGroup> PartialEq for DecryptionKey<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<G: ElGamalGroup> Eq for DecryptionKey<G> {}
+
+#[cfg(feature = "zeroize")]
+impl<G: ElGamalGroup> Zeroize for DecryptionKey<G> {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<G: ElGamalGroup> Drop for DecryptionKey<G> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<G: ElGamalGroup> ZeroizeOnDrop for DecryptionKey<G> {}
+
 // Conversion traits
 
 impl From<Scalar> for DecryptionKey {
     fn from(secret: Scalar) -> Self {
-        let ek = EncryptionKey(&secret * RISTRETTO_BASEPOINT_TABLE);
+        let ek = EncryptionKey(RistrettoPoint::mul_generator(&secret));
         Self { secret, ek }
     }
 }
 
-impl AsRef<Scalar> for DecryptionKey {
-    fn as_ref(&self) -> &Scalar {
+impl<G: ElGamalGroup> AsRef<G::Scalar> for DecryptionKey<G> {
+    fn as_ref(&self) -> &G::Scalar {
         &self.secret
     }
 }
@@ -105,47 +445,141 @@ impl AsRef<Scalar> for DecryptionKey {
 // serde traits
 
 // Here we want to deserialise the decryption key, then create its corresponding encryption key.
-#[cfg(feature = "enable-serde")]
-impl<'de> Deserialize<'de> for DecryptionKey {
+#[cfg(feature = "serde-secret")]
+impl<'de, G: ElGamalGroup> Deserialize<'de> for DecryptionKey<G>
+where
+    G::Scalar: Deserialize<'de>,
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct DecryptionKeyVisitor;
+        struct DecryptionKeyVisitor<G>(PhantomData<G>);
 
-        impl<'de> Visitor<'de> for DecryptionKeyVisitor {
-            type Value = DecryptionKey;
+        impl<'de, G: ElGamalGroup> Visitor<'de> for DecryptionKeyVisitor<G>
+        where
+            G::Scalar: Deserialize<'de>,
+        {
+            type Value = DecryptionKey<G>;
 
             fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 formatter.write_str("a valid ElGamal decryption key")
             }
 
-            fn visit_seq<A>(self, mut seq: A) -> Result<DecryptionKey, A::Error>
+            fn visit_seq<A>(self, mut seq: A) -> Result<DecryptionKey<G>, A::Error>
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                let secret = seq.next_element()?.ok_or(serde::de::Error::invalid_length(
-                    0,
-                    &"expected decryption key (32 bytes)",
-                ))?;
-                let ek = EncryptionKey(&secret * RISTRETTO_BASEPOINT_TABLE);
+                let secret: G::Scalar = seq.next_element()?.ok_or(
+                    serde::de::Error::invalid_length(0, &"expected decryption key (32 bytes)"),
+                )?;
+                // A zero secret corresponds to the identity encryption key.
+                if bool::from(secret.is_zero()) {
+                    return Err(serde::de::Error::custom("decryption key must not be zero"));
+                }
+                let ek = EncryptionKey(G::mul_generator(&secret));
                 Ok(DecryptionKey { secret, ek })
             }
         }
 
-        deserializer.deserialize_tuple(32, DecryptionKeyVisitor)
+        deserializer.deserialize_tuple(32, DecryptionKeyVisitor(PhantomData))
     }
 }
 
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "alloc")]
 #[cfg(test)]
 mod tests {
     use rand::prelude::StdRng;
     use rand_core::SeedableRng;
+    use subtle::ConstantTimeEq;
+
+    use alloc::vec::Vec;
+
+    use crate::{
+        Commitment, DecryptionKey, EncryptionKey, Error, Identity, RistrettoPoint, Scalar,
+        GENERATOR_POINT,
+    };
+
+    // Test that an explicit clone is an independent key that decrypts as the original does.
+    #[test]
+    fn clone_decryption_key() {
+        let mut rng = StdRng::seed_from_u64(479);
+        let m = RistrettoPoint::random(&mut rng);
+        let (copy, ct) = {
+            let dk = DecryptionKey::new(&mut rng);
+            let copy = dk.clone();
+            assert_eq!(copy, dk);
+            assert_eq!(copy.encryption_key(), dk.encryption_key());
+            (copy, dk.encryption_key().encrypt(m, &mut rng))
+        };
+        // the clone outlives the original
+        assert_eq!(copy.decrypt(ct), m);
+    }
 
-    use crate::DecryptionKey;
+    // Test that constant-time equality of decryption and encryption keys agrees with comparing
+    // the secrets and the points, for random keys and for keys differing in a single byte.
+    #[test]
+    fn ct_eq_keys() {
+        let mut rng = StdRng::seed_from_u64(480);
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+
+        assert!(bool::from(dk.ct_eq(&dk.clone())));
+        assert_eq!(dk, DecryptionKey::from(dk.secret));
+        assert_eq!(dk == other, dk.secret == other.secret);
+        assert_eq!(ek == *other.encryption_key(), ek.0 == other.ek.0);
+        assert_eq!(ek, EncryptionKey::from(ek.0));
+
+        for i in 0..32 {
+            let mut bytes = dk.secret.to_bytes();
+            bytes[i] ^= 1;
+            let close = DecryptionKey::from(Scalar::from_bytes_mod_order(bytes));
+            assert_ne!(close.secret, dk.secret);
+            assert!(!bool::from(dk.ct_eq(&close)));
+            assert_ne!(dk, close);
+            assert_ne!(close.encryption_key(), &ek);
+        }
+
+        // neighbouring points
+        let next = EncryptionKey::from(ek.0 + GENERATOR_POINT);
+        assert!(!bool::from(ek.ct_eq(&next)));
+        assert_ne!(ek, next);
+    }
+
+    // Test that the constant-time plaintext check agrees with decrypting and comparing, one at a
+    // time and in batches, including against the identity and for ciphertexts under another key.
+    #[test]
+    fn is_encryption_of() {
+        let mut rng = StdRng::seed_from_u64(494);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let other = *DecryptionKey::new(&mut rng).encryption_key();
+
+        let allowed: Vec<_> = (0..4).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let mut cts: Vec<_> = (0..20)
+            .map(|i| ek.encrypt(allowed[i % 4], &mut rng))
+            .collect();
+        cts.push(ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng));
+        cts.push(ek.encrypt(RistrettoPoint::identity(), &mut rng));
+        cts.push(other.encrypt(allowed[0], &mut rng));
+
+        for expected in allowed.iter().chain([RistrettoPoint::identity()].iter()) {
+            let batch = dk.filter_encryptions_of(&cts, expected);
+            assert_eq!(batch.len(), cts.len());
+            for (ct, choice) in cts.iter().zip(&batch) {
+                let naive = dk.decrypt(*ct) == *expected;
+                assert_eq!(bool::from(dk.is_encryption_of(ct, expected)), naive);
+                assert_eq!(bool::from(*choice), naive);
+            }
+            assert!(batch.iter().any(|choice| bool::from(*choice)));
+        }
+        assert!(!bool::from(dk.is_encryption_of(&cts[22], &allowed[0])));
+        assert!(dk.filter_encryptions_of(&[], &allowed[0]).is_empty());
+    }
 
     // Test that serialising and deserialising a decryption key produces an unchanged result.
+    #[cfg(feature = "serde-secret")]
     #[test]
     fn serde_decryption_key() {
         const N: usize = 100;
@@ -163,4 +597,288 @@ mod tests {
             assert_eq!(dk, decoded);
         }
     }
+
+    // Test that decryption keys round-trip through their bytes, and that non-canonical, corrupted
+    // and zero scalars are refused rather than reduced.
+    #[test]
+    fn decryption_key_bytes() {
+        let mut rng = StdRng::seed_from_u64(501);
+        for _ in 0..10 {
+            let dk = DecryptionKey::new(&mut rng);
+            let bytes = dk.to_bytes();
+            assert_eq!(bytes, dk.secret.to_bytes());
+            let decoded = <DecryptionKey>::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, dk);
+            assert_eq!(decoded.encryption_key(), dk.encryption_key());
+
+            // setting the top bit takes any scalar out of range
+            let mut corrupted = bytes;
+            corrupted[31] |= 0x80;
+            assert_eq!(
+                <DecryptionKey>::from_bytes(&corrupted),
+                Err(Error::InvalidScalar)
+            );
+        }
+
+        // the group order, which would reduce to zero, and the order plus one
+        let mut l = (-Scalar::ONE).to_bytes();
+        l[0] += 1;
+        assert_eq!(<DecryptionKey>::from_bytes(&l), Err(Error::InvalidScalar));
+        l[0] += 1;
+        assert_eq!(<DecryptionKey>::from_bytes(&l), Err(Error::InvalidScalar));
+        assert_eq!(
+            <DecryptionKey>::from_bytes(&[0u8; 32]),
+            Err(Error::InvalidScalar)
+        );
+    }
+
+    // Test that a zero decryption key is rejected on deserialisation.
+    #[cfg(feature = "serde-secret")]
+    #[test]
+    fn serde_rejects_zero_key() {
+        let malicious = [0u8; 32];
+        let err = bincode::deserialize::<DecryptionKey>(&malicious).unwrap_err();
+        assert!(err.to_string().contains("zero"));
+    }
+
+    // Test that debug output, including that of a struct holding a key, shows none of the
+    // secret, in any of the ways a scalar is usually printed, for keys, key shares and the opens
+    // of ElGamal and Pedersen commitments.
+    #[test]
+    fn debug_redacted() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            key: DecryptionKey,
+        }
+
+        fn assert_hides(printed: &str, secret: &Scalar) {
+            let bytes = secret.to_bytes();
+            let hex: alloc::string::String =
+                bytes.iter().map(|b| alloc::format!("{:02x}", b)).collect();
+            let secret_debug = alloc::format!("{:?}", secret);
+            let bytes_debug = alloc::format!("{:?}", bytes);
+            assert!(!printed.contains(&hex));
+            assert!(!printed.contains(&secret_debug));
+            assert!(!printed.contains(&bytes_debug[1..bytes_debug.len() - 1]));
+        }
+
+        let mut rng = StdRng::seed_from_u64(505);
+        let dk = DecryptionKey::new(&mut rng);
+        assert_eq!(alloc::format!("{:?}", dk), "DecryptionKey(<redacted>)");
+        for printed in [
+            alloc::format!("{:?}", dk),
+            alloc::format!("{:#?}", Config { key: dk.clone() }),
+        ] {
+            assert!(printed.contains("<redacted>"));
+            assert_hides(&printed, &dk.secret);
+        }
+
+        let shares = dk.split(2, 3, &mut rng);
+        for share in &shares {
+            let printed = alloc::format!("{:?}", share);
+            assert!(printed.ends_with(".. }"));
+            assert_hides(&printed, &share.secret);
+        }
+
+        let (open, commitment) = Commitment::commit(Scalar::from(1234u32), &mut rng);
+        let (_, pedersen) = commitment.to_pedersen(&open);
+        assert_eq!(alloc::format!("{:?}", open), "Open(<redacted>)");
+        assert_eq!(alloc::format!("{:?}", pedersen), "PedersenOpen(<redacted>)");
+        for secret in [open.0, open.1, pedersen.value(), pedersen.blinding()] {
+            assert_hides(&alloc::format!("{:#?}", (&open, &pedersen)), &secret);
+        }
+    }
+
+    // Test that raw scalars and points round-trip through the keys, and that zero and the
+    // identity are rejected.
+    #[test]
+    fn raw_key_parts() {
+        let mut rng = StdRng::seed_from_u64(504);
+        let dk = DecryptionKey::new(&mut rng);
+        let wrapped = DecryptionKey::from_scalar(*dk.as_scalar()).unwrap();
+        assert_eq!(wrapped.encryption_key(), dk.encryption_key());
+        assert_eq!(wrapped, dk);
+        assert_eq!(
+            DecryptionKey::<RistrettoPoint>::from_scalar(Scalar::ZERO),
+            Err(Error::InvalidScalar)
+        );
+
+        let ek = dk.encryption_key();
+        assert_eq!(EncryptionKey::from_point(*ek.as_point()).as_ref(), Ok(ek));
+        assert_eq!(
+            EncryptionKey::from_point(RistrettoPoint::identity()),
+            Err(Error::IdentityPoint)
+        );
+    }
+
+    // Test that a ciphertext under the sum of three keys is stripped to the message in every
+    // order, that skipping a party leaves a wrong point, and that what is left is an encryption
+    // under the remaining keys.
+    #[test]
+    // the operators on references are under test too
+    #[allow(clippy::op_ref)]
+    fn aggregate_partial_decrypt() {
+        let mut rng = StdRng::seed_from_u64(509);
+        let dks: Vec<_> = (0..3).map(|_| DecryptionKey::new(&mut rng)).collect();
+        let eks: Vec<EncryptionKey> = dks.iter().map(|dk| *dk.encryption_key()).collect();
+        let joint: EncryptionKey = eks.iter().sum();
+        assert_eq!(joint, eks[0] + eks[1] + &eks[2]);
+        assert_eq!(joint, eks.iter().copied().sum());
+        assert_eq!(joint, &(&eks[0] + &eks[1]) + eks[2]);
+        let secret: Scalar = dks.iter().map(|dk| dk.secret).sum();
+        assert_eq!(joint, *DecryptionKey::from(secret).encryption_key());
+
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = joint.encrypt(m, &mut rng);
+        for order in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            let stripped = order.iter().fold(ct, |ct, &i| dks[i].partial_decrypt(ct));
+            assert_eq!(stripped.0, ct.0);
+            assert_eq!(stripped.1, m);
+
+            let skipped = order[..2]
+                .iter()
+                .fold(ct, |ct, &i| dks[i].partial_decrypt(ct));
+            assert_ne!(skipped.1, m);
+            assert_eq!(dks[order[2]].decrypt(skipped), m);
+        }
+
+        let empty: EncryptionKey = core::iter::empty::<EncryptionKey>().sum();
+        assert_eq!(empty.0, RistrettoPoint::identity());
+    }
+
+    // Known answers for from_seed, for the seeds [0; 32], [0xff; 32] and [0, 1, ..., 31]: the
+    // secret, checked against SHA-512 reduced modulo the group order computed independently, and
+    // the encryption key. These must never change, or stored keys would no longer match.
+    const SEED_KATS: [(&str, &str); 3] = [
+        (
+            "caea39efc221693cbced2929806e76d6b4c0df712e411f1a4c2b34fc4727cf0d",
+            "96cf56c1f6902ff3c722acb2ccc8dcc7005438282a84f62aaec9692cfd5df762",
+        ),
+        (
+            "02c294831e61e2dd5cfc113149c4ffa25e25fb7a4b7bf8ec658ac05da6467305",
+            "9e84a36e51ccdbb2e1d8f0f68a58bf0d4596c0c87d69ee8f2477626078f3332d",
+        ),
+        (
+            "979f851792d0200ec1135fc221a09f7c2b4c502c9c97ba9192119734205f0b0f",
+            "5c15cf630359ff990ccfc25f855d42428b4cf84146d7db8933e89c051d1c6201",
+        ),
+    ];
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    // Test that seeded keys match the known answers, are reproducible, and differ between seeds
+    // and from the seed read as a scalar.
+    #[test]
+    fn from_seed_kat() {
+        let mut counting = [0u8; 32];
+        for (i, byte) in counting.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let seeds = [[0u8; 32], [0xffu8; 32], counting];
+        for (seed, (secret, ek)) in seeds.iter().zip(SEED_KATS.iter()) {
+            let dk = DecryptionKey::from_seed(*seed);
+            assert_eq!(dk.to_bytes(), from_hex(secret));
+            assert_eq!(dk.encryption_key().to_bytes(), from_hex(ek));
+            assert_eq!(dk, DecryptionKey::from_seed(*seed));
+            assert_ne!(dk.secret, Scalar::from_bytes_mod_order(*seed));
+        }
+
+        let mut flipped = counting;
+        flipped[31] ^= 1;
+        assert_ne!(
+            DecryptionKey::from_seed(flipped),
+            DecryptionKey::from_seed(counting)
+        );
+    }
+
+    // Test that small integers decrypt at the edges of the range, that sums decrypt to sums, and
+    // that values past the bound, and points that are not small multiples, give none.
+    #[test]
+    fn decrypt_u32_range() {
+        let mut rng = StdRng::seed_from_u64(515);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for x in [0u32, 1, 65536, u32::MAX] {
+            let ct = ek.encrypt_scalar(x as u64, &mut rng);
+            assert_eq!(dk.decrypt_u32(ct), Some(x));
+        }
+        let ct = ek.encrypt_scalar(Scalar::from(7u32), &mut rng);
+        assert_eq!(dk.decrypt_u32(ct), Some(7));
+
+        let sum =
+            ek.encrypt_scalar(1_000_000u64, &mut rng) + ek.encrypt_scalar(234_567u64, &mut rng);
+        assert_eq!(dk.decrypt_u32(sum), Some(1_234_567));
+        let sum = ek.encrypt_scalar(u32::MAX as u64, &mut rng) + ek.encrypt_scalar(1u64, &mut rng);
+        assert_eq!(dk.decrypt_u32(sum), None);
+
+        assert_eq!(
+            dk.decrypt_u32(ek.encrypt_scalar(1u64 << 32, &mut rng)),
+            None
+        );
+        assert_eq!(
+            dk.decrypt_u32(ek.encrypt_scalar(-Scalar::ONE, &mut rng)),
+            None
+        );
+        let point = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        assert_eq!(dk.decrypt_u32(point), None);
+
+        let ct = ek.encrypt_scalar(1000u64, &mut rng);
+        assert_eq!(dk.decrypt_u32_bounded(ct, 1000), Some(1000));
+        assert_eq!(dk.decrypt_u32_bounded(ct, 999), None);
+        assert_eq!(
+            dk.decrypt_u32_bounded(ek.encrypt_scalar(0u64, &mut rng), 0),
+            Some(0)
+        );
+        assert_eq!(
+            dk.decrypt_u32_bounded(ek.encrypt_scalar(1u64, &mut rng), 0),
+            None
+        );
+    }
+
+    // Test that differences of counters decrypt to signed values, at both ends of the range and
+    // not beyond them.
+    #[test]
+    fn decrypt_i64_range() {
+        const N: u64 = 10_000;
+
+        let mut rng = StdRng::seed_from_u64(517);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let a = ek.encrypt_scalar(3u64, &mut rng);
+        let b = ek.encrypt_scalar(10u64, &mut rng);
+        assert_eq!(dk.decrypt_i64(a - b, N), Some(-7));
+        assert_eq!(dk.decrypt_i64(b - a, N), Some(7));
+        assert_eq!(dk.decrypt_i64(a - a, N), Some(0));
+        assert_eq!(dk.decrypt_i64(a - b + b, N), Some(3));
+
+        let zero = ek.encrypt_scalar(0u64, &mut rng);
+        let max = ek.encrypt_scalar(N, &mut rng);
+        let past = ek.encrypt_scalar(N + 1, &mut rng);
+        assert_eq!(dk.decrypt_i64(max, N), Some(N as i64));
+        assert_eq!(dk.decrypt_i64(zero - max, N), Some(-(N as i64)));
+        assert_eq!(dk.decrypt_i64(past, N), None);
+        assert_eq!(dk.decrypt_i64(zero - past, N), None);
+        assert_eq!(dk.decrypt_i64(max - past, N), Some(-1));
+        assert_eq!(dk.decrypt_i64(max - past, 0), None);
+        assert_eq!(dk.decrypt_i64(zero, 0), Some(0));
+
+        let point = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        assert_eq!(dk.decrypt_i64(point, N), None);
+    }
 }
@@ -0,0 +1,293 @@
+// DER encoding of proofs for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Proofs are encoded as
+//
+//     Proof ::= SEQUENCE {
+//         version  INTEGER (0),
+//         c        OCTET STRING (SIZE (32)),
+//         s        OCTET STRING (SIZE (32))
+//     }
+//
+// with the scalars in their canonical little-endian encoding. Only version 0 exists.
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::avnet::PossessionProof;
+use crate::{DleqProof, Error};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_SEQUENCE: u8 = 0x30;
+
+const VERSION: u8 = 0;
+
+fn push_tlv(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.push(tag);
+    // every value this module writes is shorter than 128 bytes, so the short form always applies
+    debug_assert!(value.len() < 0x80);
+    out.push(value.len() as u8);
+    out.extend_from_slice(value);
+}
+
+fn encode_scalars(c: &Scalar, s: &Scalar) -> Vec<u8> {
+    let mut body = Vec::with_capacity(3 + 2 * 34);
+    push_tlv(&mut body, TAG_INTEGER, &[VERSION]);
+    push_tlv(&mut body, TAG_OCTET_STRING, c.as_bytes());
+    push_tlv(&mut body, TAG_OCTET_STRING, s.as_bytes());
+
+    let mut out = Vec::with_capacity(2 + body.len());
+    push_tlv(&mut out, TAG_SEQUENCE, &body);
+    out
+}
+
+// Read one TLV with the given tag, returning its value and the remaining bytes. Lengths must be
+// definite and minimally encoded.
+fn read_tlv(bytes: &[u8], tag: u8) -> Result<(&[u8], &[u8]), Error> {
+    let (&got, rest) = bytes.split_first().ok_or(Error::InvalidDer)?;
+    if got != tag {
+        return Err(Error::InvalidDer);
+    }
+    let (&first, mut rest) = rest.split_first().ok_or(Error::InvalidDer)?;
+    let len = match first {
+        0x00..=0x7f => first as usize,
+        // indefinite length, or reserved
+        0x80 | 0xff => return Err(Error::InvalidDer),
+        _ => {
+            let n = (first & 0x7f) as usize;
+            if n > core::mem::size_of::<usize>() || rest.len() < n || rest[0] == 0 {
+                return Err(Error::InvalidDer);
+            }
+            let len = rest[..n]
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | b as usize);
+            if len < 0x80 {
+                return Err(Error::InvalidDer);
+            }
+            rest = &rest[n..];
+            len
+        }
+    };
+    if rest.len() < len {
+        return Err(Error::InvalidDer);
+    }
+    Ok(rest.split_at(len))
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<(Scalar, &[u8]), Error> {
+    let (value, rest) = read_tlv(bytes, TAG_OCTET_STRING)?;
+    let mut scalar = [0u8; 32];
+    if value.len() != scalar.len() {
+        return Err(Error::LengthMismatch {
+            expected: scalar.len(),
+            got: value.len(),
+        });
+    }
+    scalar.copy_from_slice(value);
+    let scalar = Option::from(Scalar::from_canonical_bytes(scalar)).ok_or(Error::InvalidScalar)?;
+    Ok((scalar, rest))
+}
+
+fn decode_scalars(bytes: &[u8]) -> Result<(Scalar, Scalar), Error> {
+    let (body, rest) = read_tlv(bytes, TAG_SEQUENCE)?;
+    if !rest.is_empty() {
+        return Err(Error::InvalidDer);
+    }
+
+    let (version, body) = read_tlv(body, TAG_INTEGER)?;
+    match version {
+        [] => return Err(Error::InvalidDer),
+        // a non-minimal integer
+        [0x00, next, ..] if next & 0x80 == 0 => return Err(Error::InvalidDer),
+        [0xff, next, ..] if next & 0x80 != 0 => return Err(Error::InvalidDer),
+        [VERSION] => {}
+        _ => return Err(Error::UnsupportedVersion),
+    }
+
+    let (c, body) = read_scalar(body)?;
+    let (s, body) = read_scalar(body)?;
+    if !body.is_empty() {
+        return Err(Error::InvalidDer);
+    }
+    Ok((c, s))
+}
+
+impl DleqProof {
+    /// Encode the proof in DER, as a SEQUENCE of a version INTEGER (0) and two 32-byte OCTET
+    /// STRINGs holding the challenge and response scalars.
+    pub fn to_der(&self) -> Vec<u8> {
+        encode_scalars(&self.c, &self.s)
+    }
+
+    /// Decode a proof from [to_der](Self::to_der)'s encoding.
+    ///
+    /// Parsing is strict: fails with [Error::InvalidDer](crate::Error::InvalidDer) on any other
+    /// structure, indefinite or non-minimal lengths, or trailing bytes, with
+    /// [Error::UnsupportedVersion](crate::Error::UnsupportedVersion) if the version is not 0,
+    /// with [Error::LengthMismatch](crate::Error::LengthMismatch) if a scalar is not 32 bytes, and
+    /// with [Error::InvalidScalar](crate::Error::InvalidScalar) if it is not canonical.
+    pub fn from_der(bytes: &[u8]) -> Result<DleqProof, Error> {
+        let (c, s) = decode_scalars(bytes)?;
+        Ok(DleqProof { c, s })
+    }
+}
+
+impl PossessionProof {
+    /// Encode the proof in DER, in the same shape as [DleqProof::to_der].
+    pub fn to_der(&self) -> Vec<u8> {
+        encode_scalars(&self.c, &self.s)
+    }
+
+    /// Decode a proof from [to_der](Self::to_der)'s encoding, failing as [DleqProof::from_der]
+    /// does.
+    pub fn from_der(bytes: &[u8]) -> Result<PossessionProof, Error> {
+        let (c, s) = decode_scalars(bytes)?;
+        Ok(PossessionProof { c, s })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::avnet::{Participant, PossessionProof};
+    use crate::{DecryptionKey, DleqProof, Error, RistrettoPoint};
+
+    fn hex(bytes: &[u8]) -> alloc::string::String {
+        bytes.iter().map(|b| alloc::format!("{:02x}", b)).collect()
+    }
+
+    fn dleq_proof() -> (DecryptionKey, RistrettoPoint, RistrettoPoint, DleqProof) {
+        let mut rng = StdRng::seed_from_u64(457);
+        let dk = DecryptionKey::new(&mut rng);
+        let p = RistrettoPoint::random(&mut rng);
+        let q = p * dk.as_ref();
+        let proof = DleqProof::prove(&dk, &p, &q, &mut rng);
+        (dk, p, q, proof)
+    }
+
+    // Test that the DER bytes of deterministic proofs are pinned, and that they decode again.
+    #[test]
+    fn der_vectors() {
+        let (dk, p, q, proof) = dleq_proof();
+        let der = proof.to_der();
+        assert_eq!(
+            hex(&der),
+            concat!(
+                "30470201000420",
+                "2a9dc62efe0c17fc4bb2adee6e78c7c2fa374bc55e181f1c3d681b040d651506",
+                "0420",
+                "391f6cc0f32b2b2354e4ee60607acae73ceb45245ede1a220e44099598b2960d",
+            )
+        );
+        let decoded = DleqProof::from_der(&der).unwrap();
+        assert_eq!(decoded, proof);
+        assert_eq!(decoded.verify(dk.encryption_key(), &p, &q), Ok(()));
+
+        let mut rng = StdRng::seed_from_u64(457);
+        let (_, round1) = Participant::new(3, &mut rng);
+        let der = round1.proof.to_der();
+        assert_eq!(
+            hex(&der),
+            concat!(
+                "30470201000420",
+                "6d13b652f7070e577840492dd237fda4755aa5c3b1d10ee55c10e2ebcf32bc06",
+                "0420",
+                "80d71365d80b1a98178732b30c771a7c7398fa593fe6ef9a9981513b01a59507",
+            )
+        );
+        assert_eq!(PossessionProof::from_der(&der), Ok(round1.proof));
+    }
+
+    // Test that mangled encodings are rejected with the specific error.
+    #[test]
+    fn der_rejects_mangled() {
+        let (_, _, _, proof) = dleq_proof();
+        let der = proof.to_der();
+        let mangle = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut bytes = der.clone();
+            f(&mut bytes);
+            DleqProof::from_der(&bytes)
+        };
+
+        // trailing bytes, after the sequence and inside it
+        assert_eq!(mangle(&|b| b.push(0)), Err(Error::InvalidDer));
+        assert_eq!(
+            mangle(&|b| {
+                b[1] += 2;
+                b.extend_from_slice(&[0x05, 0x00]);
+            }),
+            Err(Error::InvalidDer)
+        );
+        // truncation anywhere
+        for len in 0..der.len() {
+            assert!(DleqProof::from_der(&der[..len]).is_err());
+        }
+        // wrong tags
+        assert_eq!(mangle(&|b| b[0] = 0x31), Err(Error::InvalidDer));
+        assert_eq!(mangle(&|b| b[2] = 0x04), Err(Error::InvalidDer));
+        assert_eq!(mangle(&|b| b[5] = 0x03), Err(Error::InvalidDer));
+        // indefinite length
+        assert_eq!(
+            mangle(&|b| {
+                b[1] = 0x80;
+                b.extend_from_slice(&[0, 0]);
+            }),
+            Err(Error::InvalidDer)
+        );
+        // long-form length where the short form fits
+        assert_eq!(
+            mangle(&|b| b.splice(1..2, [0x81, 0x47]).for_each(drop)),
+            Err(Error::InvalidDer)
+        );
+        // non-minimal and unsupported versions
+        assert_eq!(
+            mangle(&|b| {
+                b[1] += 1;
+                b.splice(3..5, [0x02, 0x00, 0x00]).for_each(drop);
+            }),
+            Err(Error::InvalidDer)
+        );
+        assert_eq!(mangle(&|b| b[4] = 1), Err(Error::UnsupportedVersion));
+        // a short scalar and a non-canonical one
+        assert_eq!(
+            mangle(&|b| {
+                b[1] -= 1;
+                b[6] = 31;
+                b.remove(7);
+            }),
+            Err(Error::LengthMismatch {
+                expected: 32,
+                got: 31
+            })
+        );
+        assert_eq!(
+            mangle(&|b| b[7..39].copy_from_slice(&[0xff; 32])),
+            Err(Error::InvalidScalar)
+        );
+
+        // a single flipped bit either fails to parse or yields a different proof
+        for i in 0..der.len() * 8 {
+            let mut bytes = der.clone();
+            bytes[i / 8] ^= 1 << (i % 8);
+            assert_ne!(DleqProof::from_der(&bytes), Ok(proof));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// Support code for the ElgamalWrapper derive for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Items the code generated by [ElgamalWrapper](crate::ElgamalWrapper) refers to. Not part of the
+//! public API.
+
+#[cfg(feature = "enable-serde")]
+pub use serde;
+
+use crate::{Ciphertext, Commitment, CompressedCiphertext, EncryptionKey, Error};
+
+pub fn ciphertext_to_bytes(ct: &Ciphertext) -> [u8; 64] {
+    ct.compress().to_bytes()
+}
+
+pub fn ciphertext_from_bytes(bytes: &[u8; 64]) -> Result<Ciphertext, Error> {
+    CompressedCiphertext::from_bytes(bytes).decompress()
+}
+
+/// The key followed by the ciphertext.
+pub fn commitment_to_bytes(commitment: &Commitment) -> [u8; 96] {
+    let mut bytes = [0u8; 96];
+    bytes[..32].copy_from_slice(commitment.0.compress().as_bytes());
+    bytes[32..].copy_from_slice(&commitment.1.compress().to_bytes());
+    bytes
+}
+
+pub fn commitment_from_bytes(bytes: &[u8; 96]) -> Result<Commitment, Error> {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&bytes[..32]);
+    let mut ct = [0u8; 64];
+    ct.copy_from_slice(&bytes[32..]);
+    let ek = EncryptionKey::from_compressed_unchecked_const(key).get()?;
+    Ok(Commitment::from((ek, ciphertext_from_bytes(&ct)?)))
+}
+
+#[cfg(feature = "enable-serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __elgamal_wrapper_serde {
+    ($wrapper:ident, $inner:ty) => {
+        #[automatically_derived]
+        impl $crate::derive::serde::Serialize for $wrapper {
+            fn serialize<S: $crate::derive::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::core::result::Result<S::Ok, S::Error> {
+                $crate::derive::serde::Serialize::serialize(
+                    ::core::convert::AsRef::<$inner>::as_ref(self),
+                    serializer,
+                )
+            }
+        }
+
+        #[automatically_derived]
+        impl<'de> $crate::derive::serde::Deserialize<'de> for $wrapper {
+            fn deserialize<D: $crate::derive::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::core::result::Result<$wrapper, D::Error> {
+                <$inner as $crate::derive::serde::Deserialize<'de>>::deserialize(deserializer)
+                    .map($wrapper::new)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "enable-serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __elgamal_wrapper_serde {
+    ($wrapper:ident, $inner:ty) => {};
+}
@@ -0,0 +1,449 @@
+// On-disk discrete-log tables for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use memmap2::Mmap;
+use sha2::{Digest, Sha512};
+
+use crate::dlog::isqrt;
+use crate::trace::op_span;
+use crate::{Ciphertext, DecryptionKey, Error, GENERATOR_POINT, GENERATOR_TABLE};
+
+const MAGIC: &[u8; 8] = b"RELGDLOG";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 64;
+const PREFIX_LEN: usize = 8;
+const RECORD_LEN: usize = PREFIX_LEN + 8;
+const DEFAULT_MEMORY_RECORDS: usize = 1 << 20;
+
+// A baby step: the first bytes of the compressed point `j * G`, then `j`. Records sort by prefix
+// first, so they can be binary searched on it.
+type Record = ([u8; PREFIX_LEN], u64);
+
+/// A baby-step table for discrete logarithms, kept in a file and memory-mapped, so that ranges
+/// whose tables do not fit in memory can still be decrypted.
+///
+/// The file is a 64-byte header followed by fixed-width records, sorted so they can be binary
+/// searched. Each record holds the first 8 bytes of the compressed point `jG` and the index `j`;
+/// a match on the truncated point is confirmed by recomputing `jG`. The header records the range
+/// the table covers and a SHA-512 checksum of itself and all records, which
+/// [open](DiskDiscreteLogTable::open) checks, so corrupt or truncated files are rejected.
+///
+/// Build a table with [DiskTableBuilder] and decrypt with
+/// [DecryptionKey::decrypt_with_disk_table](crate::DecryptionKey::decrypt_with_disk_table).
+#[derive(Debug)]
+pub struct DiskDiscreteLogTable {
+    map: Mmap,
+    baby_steps: u64,
+    max: u64,
+}
+
+/// Builds a [DiskDiscreteLogTable] with bounded memory.
+///
+/// The baby steps are generated and sorted in runs of at most
+/// [memory_records](DiskTableBuilder::memory_records) records, each spilled to a temporary file
+/// next to the table, and the runs are then merged into the table.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, DiskTableBuilder, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let ct = dec_key.encryption_key().exp_encrypt(Scalar::from(123456u32), &mut rng);
+///
+/// let path = std::env::temp_dir().join("rust-elgamal-doctest.dlog");
+/// let table = DiskTableBuilder::new(1 << 20).build(&path).unwrap();
+/// assert_eq!(dec_key.decrypt_with_disk_table(ct, &table), Ok(123456));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DiskTableBuilder {
+    max: u64,
+    baby_steps: u64,
+    memory_records: usize,
+}
+
+impl DiskTableBuilder {
+    /// A builder for a table covering discrete logarithms in `[0, max]`, with about `sqrt(max)`
+    /// baby steps.
+    pub fn new(max: u64) -> DiskTableBuilder {
+        DiskTableBuilder {
+            max,
+            baby_steps: isqrt(max) + 1,
+            memory_records: DEFAULT_MEMORY_RECORDS,
+        }
+    }
+
+    /// Use `baby_steps` baby steps instead. A larger table needs fewer giant steps per lookup,
+    /// which take about `max / baby_steps` group operations.
+    pub fn baby_steps(mut self, baby_steps: u64) -> DiskTableBuilder {
+        self.baby_steps = baby_steps.max(1);
+        self
+    }
+
+    /// Keep at most `memory_records` records (16 bytes each) in memory while building.
+    pub fn memory_records(mut self, memory_records: usize) -> DiskTableBuilder {
+        self.memory_records = memory_records.max(1);
+        self
+    }
+
+    /// Write the table to `path`, replacing any file there, and open it.
+    ///
+    /// Fails with [Error::Io](crate::Error::Io) if a file cannot be written.
+    pub fn build<P: AsRef<Path>>(&self, path: P) -> Result<DiskDiscreteLogTable, Error> {
+        let _span = op_span!("build_disk_table", max = self.max);
+        let path = path.as_ref();
+        let runs = self.write_runs(path)?;
+        let result = self.merge_runs(path, &runs);
+        for run in &runs {
+            let _ = fs::remove_file(run);
+        }
+        result?;
+        DiskDiscreteLogTable::open(path)
+    }
+
+    // Generate the baby steps in sorted runs of at most `memory_records`, one file per run.
+    fn write_runs(&self, path: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut runs = Vec::new();
+        let mut point = RistrettoPoint::default();
+        let mut j = 0u64;
+        while j < self.baby_steps {
+            let len = (self.baby_steps - j).min(self.memory_records as u64) as usize;
+            let mut records: Vec<Record> = Vec::with_capacity(len);
+            for _ in 0..len {
+                records.push((prefix(&point), j));
+                point += GENERATOR_POINT;
+                j += 1;
+            }
+            records.sort_unstable();
+
+            let mut run = path.as_os_str().to_owned();
+            run.push(format!(".run{}", runs.len()));
+            let run = PathBuf::from(run);
+            let mut writer = BufWriter::new(File::create(&run)?);
+            for record in &records {
+                writer.write_all(&encode_record(record))?;
+            }
+            writer.flush()?;
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    // Merge the sorted runs into the table, then fill in the header.
+    fn merge_runs(&self, path: &Path, runs: &[PathBuf]) -> Result<(), Error> {
+        let mut readers = runs
+            .iter()
+            .map(|run| Ok(BufReader::new(File::open(run)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut heap = BinaryHeap::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(record) = read_record(reader)? {
+                heap.push(Reverse((record, i)));
+            }
+        }
+
+        let mut file = File::create(path)?;
+        let mut writer = BufWriter::new(&mut file);
+        writer.write_all(&[0u8; HEADER_LEN])?;
+        let mut hasher = Sha512::new();
+        while let Some(Reverse((record, i))) = heap.pop() {
+            let bytes = encode_record(&record);
+            hasher.update(bytes);
+            writer.write_all(&bytes)?;
+            if let Some(next) = read_record(&mut readers[i])? {
+                heap.push(Reverse((next, i)));
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+
+        let header = header(self.baby_steps, self.max, hasher);
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl DiskDiscreteLogTable {
+    /// Open a table written by [DiskTableBuilder::build], checking its header and checksum.
+    ///
+    /// Fails with [Error::CorruptTable](crate::Error::CorruptTable) if the file is not a table,
+    /// is truncated or fails its checksum, and with [Error::Io](crate::Error::Io) if it cannot be
+    /// read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<DiskDiscreteLogTable, Error> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only; the table file must not be modified while it is open.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_LEN || &map[..8] != MAGIC || read_u32(&map[8..12]) != VERSION {
+            return Err(Error::CorruptTable);
+        }
+        let baby_steps = read_u64(&map[16..24]);
+        let max = read_u64(&map[24..32]);
+        let expected_len = (baby_steps as u128) * RECORD_LEN as u128 + HEADER_LEN as u128;
+        if baby_steps == 0 || map.len() as u128 != expected_len {
+            return Err(Error::CorruptTable);
+        }
+
+        let mut hasher = Sha512::new();
+        hasher.update(&map[HEADER_LEN..]);
+        if header(baby_steps, max, hasher)[..] != map[..HEADER_LEN] {
+            return Err(Error::CorruptTable);
+        }
+        Ok(DiskDiscreteLogTable {
+            map,
+            baby_steps,
+            max,
+        })
+    }
+
+    /// The largest discrete logarithm the table covers.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Find `x` in `[0, max]` with `xG == target`.
+    pub fn lookup(&self, target: &RistrettoPoint) -> Option<u64> {
+        let m = self.baby_steps;
+        let giant = -(&Scalar::from(m) * GENERATOR_TABLE);
+        let mut point = *target;
+        for i in 0..=self.max / m {
+            if let Some(j) = self.baby_step(&point) {
+                let x = i as u128 * m as u128 + j as u128;
+                return if x <= self.max as u128 {
+                    Some(x as u64)
+                } else {
+                    None
+                };
+            }
+            point += giant;
+        }
+        None
+    }
+
+    // The index `j` with `jG == point`, if it is a baby step.
+    fn baby_step(&self, point: &RistrettoPoint) -> Option<u64> {
+        let key = prefix(point);
+        let records = &self.map[HEADER_LEN..];
+        let record_prefix = |i: u64| {
+            let start = i as usize * RECORD_LEN;
+            &records[start..start + PREFIX_LEN]
+        };
+
+        // the first record whose prefix is not below `key`
+        let (mut lo, mut hi) = (0u64, self.baby_steps);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if record_prefix(mid) < &key[..] {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        // truncated points can collide, so confirm every candidate
+        while lo < self.baby_steps && record_prefix(lo) == &key[..] {
+            let start = lo as usize * RECORD_LEN + PREFIX_LEN;
+            let j = read_u64(&records[start..start + 8]);
+            if &Scalar::from(j) * GENERATOR_TABLE == *point {
+                return Some(j);
+            }
+            lo += 1;
+        }
+        None
+    }
+}
+
+impl DecryptionKey {
+    /// Decrypt an exponential ciphertext `ct` of a value in `[0, table.max()]`, looking the
+    /// discrete logarithm up in an on-disk table.
+    ///
+    /// Fails with [Error::OutOfRange](crate::Error::OutOfRange) if the value is not in range.
+    pub fn decrypt_with_disk_table(
+        &self,
+        ct: Ciphertext,
+        table: &DiskDiscreteLogTable,
+    ) -> Result<u64, Error> {
+        table.lookup(&self.decrypt(ct)).ok_or(Error::OutOfRange)
+    }
+}
+
+fn prefix(point: &RistrettoPoint) -> [u8; PREFIX_LEN] {
+    let mut out = [0u8; PREFIX_LEN];
+    out.copy_from_slice(&point.compress().as_bytes()[..PREFIX_LEN]);
+    out
+}
+
+fn encode_record((prefix, j): &Record) -> [u8; RECORD_LEN] {
+    let mut out = [0u8; RECORD_LEN];
+    out[..PREFIX_LEN].copy_from_slice(prefix);
+    out[PREFIX_LEN..].copy_from_slice(&j.to_le_bytes());
+    out
+}
+
+fn read_record<R: Read>(reader: &mut R) -> Result<Option<Record>, Error> {
+    let mut bytes = [0u8; RECORD_LEN];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut prefix = [0u8; PREFIX_LEN];
+    prefix.copy_from_slice(&bytes[..PREFIX_LEN]);
+    Ok(Some((prefix, read_u64(&bytes[PREFIX_LEN..]))))
+}
+
+// The header: magic, version, record width, baby steps, max, then the first 32 bytes of the
+// SHA-512 checksum over the fields before it and the records (already fed to `records`).
+fn header(baby_steps: u64, max: u64, records: Sha512) -> [u8; HEADER_LEN] {
+    let mut out = [0u8; HEADER_LEN];
+    out[..8].copy_from_slice(MAGIC);
+    out[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    out[12..16].copy_from_slice(&(RECORD_LEN as u32).to_le_bytes());
+    out[16..24].copy_from_slice(&baby_steps.to_le_bytes());
+    out[24..32].copy_from_slice(&max.to_le_bytes());
+    let checksum = Sha512::new()
+        .chain_update(&out[..32])
+        .chain_update(records.finalize())
+        .finalize();
+    out[32..].copy_from_slice(&checksum[..32]);
+    out
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut out = [0u8; 4];
+    out.copy_from_slice(bytes);
+    u32::from_le_bytes(out)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(bytes);
+    u64::from_le_bytes(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, DiskDiscreteLogTable, DiskTableBuilder, Error, Scalar};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rust-elgamal-{}-{}.dlog", name, std::process::id()))
+    }
+
+    // Test that a 2^20 table built in several runs finds values in range, misses values out of
+    // range, and still does after being reopened.
+    #[test]
+    fn disk_table_lookup() {
+        const MAX: u64 = 1 << 20;
+        let path = temp_path("lookup");
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let table = DiskTableBuilder::new(MAX)
+            .memory_records(100)
+            .build(&path)
+            .unwrap();
+        assert_eq!(table.max(), MAX);
+        for m in [0, 1, 1024, 1025, 777_777, MAX] {
+            let ct = ek.exp_encrypt(Scalar::from(m), &mut rng);
+            assert_eq!(dk.decrypt_with_disk_table(ct, &table), Ok(m));
+        }
+        let ct = ek.exp_encrypt(Scalar::from(MAX + 1), &mut rng);
+        assert_eq!(
+            dk.decrypt_with_disk_table(ct, &table),
+            Err(Error::OutOfRange)
+        );
+        drop(table);
+
+        let table = DiskDiscreteLogTable::open(&path).unwrap();
+        let ct = ek.exp_encrypt(Scalar::from(424_242u64), &mut rng);
+        assert_eq!(dk.decrypt_with_disk_table(ct, &table), Ok(424_242));
+        drop(table);
+
+        // a bigger table with fewer giant steps answers the same way
+        let table = DiskTableBuilder::new(MAX)
+            .baby_steps(5000)
+            .build(&path)
+            .unwrap();
+        let ct = ek.exp_encrypt(Scalar::from(999_999u64), &mut rng);
+        assert_eq!(dk.decrypt_with_disk_table(ct, &table), Ok(999_999));
+        drop(table);
+
+        fs::remove_file(&path).unwrap();
+        let mut run = path.into_os_string();
+        run.push(".run0");
+        assert!(!PathBuf::from(run).exists());
+    }
+
+    // Test that corrupt and truncated tables are rejected on opening.
+    #[test]
+    fn disk_table_corrupt() {
+        let path = temp_path("corrupt");
+        drop(DiskTableBuilder::new(10_000).build(&path).unwrap());
+        let bytes = fs::read(&path).unwrap();
+
+        let mut corrupt = bytes.clone();
+        corrupt[100] ^= 1;
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(
+            DiskDiscreteLogTable::open(&path).unwrap_err(),
+            Error::CorruptTable
+        );
+
+        // changing the range in the header is caught by the checksum too
+        let mut corrupt = bytes.clone();
+        corrupt[24] ^= 1;
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(
+            DiskDiscreteLogTable::open(&path).unwrap_err(),
+            Error::CorruptTable
+        );
+
+        fs::write(&path, &bytes[..bytes.len() - 16]).unwrap();
+        assert_eq!(
+            DiskDiscreteLogTable::open(&path).unwrap_err(),
+            Error::CorruptTable
+        );
+        fs::write(&path, b"not a table").unwrap();
+        assert_eq!(
+            DiskDiscreteLogTable::open(&path).unwrap_err(),
+            Error::CorruptTable
+        );
+
+        fs::write(&path, &bytes).unwrap();
+        assert!(DiskDiscreteLogTable::open(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+}
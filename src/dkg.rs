@@ -0,0 +1,467 @@
+// Distributed key generation for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pedersen's distributed key generation: `n` participants make a `t`-of-`n` [KeyShare] each of
+//! a joint key, without a dealer, so that nobody ever holds the whole
+//! [DecryptionKey](crate::DecryptionKey).
+//!
+//! Participant `i` (counting from one) picks a random polynomial `f_i` of degree `t - 1` and
+//! broadcasts a [PolynomialCommitment] to it, with a proof of possession of its constant term
+//! ([Round1]). It then sends `f_i(j)` to each participant `j` over a private channel ([Round2]).
+//! Everyone checks the shares they receive against the senders' commitments, and ends with the
+//! share `sum_i f_i(j)` of the secret `sum_i f_i(0)`, whose encryption key is the sum of the
+//! constant terms of the commitments ([Participant::finish]). The shares decrypt with
+//! [KeyShare::partial_decrypt] and [Ciphertext::combine_shares](crate::Ciphertext::combine_shares).
+//!
+//! As in FROST's key generation, the proofs of possession stop a participant from choosing its
+//! commitment after seeing the others' to cancel their keys. A participant whose share or proof
+//! fails is named in [Error::InvalidShare]; what to do about it (abort, or exclude it and start
+//! over) is up to the caller. This assumes an authenticated broadcast channel for round 1 and
+//! private, authenticated channels for round 2.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::dkg::Participant;
+//! use rust_elgamal::{Scalar, GENERATOR_TABLE};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let (participants, round1): (Vec<_>, Vec<_>) =
+//!     (1..=3).map(|i| Participant::new(i, 2, 3, &mut rng)).unzip();
+//! let round2: Vec<_> = participants.iter().flat_map(|p| p.deal()).collect();
+//!
+//! let results: Vec<_> = participants
+//!     .iter()
+//!     .map(|p| {
+//!         let mine: Vec<_> = round2.iter().filter(|m| m.to == p.index()).cloned().collect();
+//!         p.finish(&round1, &mine).unwrap()
+//!     })
+//!     .collect();
+//! let ek = results[0].1;
+//! assert!(results.iter().all(|(_, key)| *key == ek));
+//!
+//! let m = &Scalar::from(7u32) * GENERATOR_TABLE;
+//! let ct = ek.encrypt(m, &mut rng);
+//! let parts = [results[0].0.partial_decrypt(&ct), results[2].0.partial_decrypt(&ct)];
+//! assert_eq!(ct.combine_shares(&parts), Ok(m));
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::avnet::PossessionProof;
+use crate::{
+    EncryptionKey, Error, KeyShare, PolynomialCommitment, GENERATOR_POINT, GENERATOR_TABLE,
+};
+
+const DOMAIN: &[u8] = b"rust-elgamal dkg";
+
+/// A participant's secret state between the rounds: its index, the parameters and its
+/// polynomial, which debug output leaves out.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Participant {
+    index: u32,
+    threshold: usize,
+    n: u32,
+    coefficients: Vec<Scalar>,
+}
+
+/// Participant `index`'s round-1 broadcast: the commitment to its polynomial and a proof of
+/// possession of the constant term.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Round1 {
+    pub index: u32,
+    pub commitment: PolynomialCommitment,
+    pub proof: PossessionProof,
+}
+
+/// The share `f_from(to)` that participant `from` deals to participant `to` in round 2.
+///
+/// The share is secret, to be sent to `to` alone, so it is serialisable only with the
+/// `serde-secret` feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+pub struct Round2 {
+    pub from: u32,
+    pub to: u32,
+    pub share: Scalar,
+}
+
+impl Participant {
+    /// Join a `threshold`-of-`n` key generation as participant `index`, from 1 to `n`,
+    /// returning the secret state and the round-1 message to broadcast.
+    ///
+    /// # Panics
+    ///
+    /// If `threshold` is zero or greater than `n`, or `index` is not between 1 and `n`.
+    pub fn new<R: RngCore + CryptoRng>(
+        index: u32,
+        threshold: usize,
+        n: u32,
+        rng: &mut R,
+    ) -> (Participant, Round1) {
+        assert!(
+            threshold >= 1 && threshold <= n as usize,
+            "the threshold must be between 1 and n"
+        );
+        assert!(
+            index >= 1 && index <= n,
+            "the index must be between 1 and n"
+        );
+
+        let coefficients: Vec<_> = (0..threshold).map(|_| Scalar::random(rng)).collect();
+        let commitment = PolynomialCommitment::commit(&coefficients);
+        let key = commitment.coefficients()[0];
+        let k = Scalar::random(rng);
+        let c = challenge(index, &key, &(&k * GENERATOR_TABLE));
+        let proof = PossessionProof {
+            c,
+            s: k + c * coefficients[0],
+        };
+        let participant = Participant {
+            index,
+            threshold,
+            n,
+            coefficients,
+        };
+        let round1 = Round1 {
+            index,
+            commitment,
+            proof,
+        };
+        (participant, round1)
+    }
+
+    /// The participant's index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The round-2 shares of this participant's polynomial, one for each participant from 1 to
+    /// `n`, including itself.
+    pub fn deal(&self) -> Vec<Round2> {
+        (1..=self.n)
+            .map(|to| Round2 {
+                from: self.index,
+                to,
+                share: self.evaluate(to),
+            })
+            .collect()
+    }
+
+    /// Check everyone's round-1 messages and the round-2 shares dealt to this participant, and
+    /// combine them into this participant's [KeyShare] and the joint [EncryptionKey].
+    ///
+    /// `round1` holds one message from each participant, in order of index, this participant's
+    /// own included; `round2` holds one share from each participant, in any order.
+    ///
+    /// Fails with [Error::InvalidShare] naming the lowest participant whose proof of possession
+    /// or share does not verify, or whose commitment has the wrong degree;
+    /// [Error::InvalidParameters] if the messages are not one per participant, or a share is
+    /// addressed to someone else; [Error::KeyMismatch] if `round1` does not hold this
+    /// participant's own commitment; and [Error::IdentityPoint] if the joint key is the identity.
+    pub fn finish(
+        &self,
+        round1: &[Round1],
+        round2: &[Round2],
+    ) -> Result<(KeyShare, EncryptionKey), Error> {
+        let n = self.n as usize;
+        if round1.len() != n || round2.len() != n {
+            return Err(Error::InvalidParameters);
+        }
+        for (j, msg) in (1..).zip(round1) {
+            if msg.index != j {
+                return Err(Error::InvalidParameters);
+            }
+            msg.verify(self.threshold)?;
+        }
+        let own = &round1[self.index as usize - 1].commitment;
+        if own != &PolynomialCommitment::commit(&self.coefficients) {
+            return Err(Error::KeyMismatch);
+        }
+
+        let mut received: Vec<Option<&Round2>> = vec![None; n];
+        for msg in round2 {
+            if msg.to != self.index || msg.from == 0 || msg.from > self.n {
+                return Err(Error::InvalidParameters);
+            }
+            let slot = &mut received[msg.from as usize - 1];
+            if slot.is_some() {
+                return Err(Error::InvalidParameters);
+            }
+            *slot = Some(msg);
+        }
+
+        let x = Scalar::from(self.index);
+        let mut secret = Scalar::ZERO;
+        let mut key = RistrettoPoint::default();
+        for (dealer, msg) in round1.iter().zip(received) {
+            // every slot is filled: there are n distinct senders among 1..=n
+            let share = msg.ok_or(Error::InvalidParameters)?.share;
+            if dealer.commitment.evaluate_in_exponent(x) != &share * GENERATOR_TABLE {
+                return Err(Error::InvalidShare {
+                    index: dealer.index,
+                });
+            }
+            secret += share;
+            key += dealer.commitment.coefficients()[0];
+        }
+        if key.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+
+        let group_key = EncryptionKey(key);
+        let share = KeyShare {
+            index: self.index,
+            threshold: self.threshold,
+            secret,
+            group_key,
+        };
+        Ok((share, group_key))
+    }
+
+    fn evaluate(&self, to: u32) -> Scalar {
+        // Horner's rule
+        let x = Scalar::from(to);
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Scalar::ZERO, |acc, a| acc * x + a)
+    }
+}
+
+impl Debug for Participant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Participant")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .field("n", &self.n)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Round1 {
+    /// Check that the commitment is to a polynomial of degree `threshold - 1`, and verify the
+    /// proof of possession of its constant term.
+    ///
+    /// Fails with [Error::InvalidShare] naming the sender if either check fails.
+    pub fn verify(&self, threshold: usize) -> Result<(), Error> {
+        let invalid = Error::InvalidShare { index: self.index };
+        if Some(self.commitment.degree()) != threshold.checked_sub(1) {
+            return Err(invalid);
+        }
+        let key = self.commitment.coefficients()[0];
+        let a = RistrettoPoint::vartime_double_scalar_mul_basepoint(
+            &-self.proof.c,
+            &key,
+            &self.proof.s,
+        );
+        if challenge(self.index, &key, &a) != self.proof.c {
+            return Err(invalid);
+        }
+        Ok(())
+    }
+}
+
+fn challenge(index: u32, key: &RistrettoPoint, a: &RistrettoPoint) -> Scalar {
+    Scalar::from_hash(
+        Sha512::new()
+            .chain_update((DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(DOMAIN)
+            .chain_update(index.to_le_bytes())
+            .chain_update(GENERATOR_POINT.compress().as_bytes())
+            .chain_update(key.compress().as_bytes())
+            .chain_update(a.compress().as_bytes()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{Participant, Round1, Round2};
+    use crate::{lagrange_coefficients, EncryptionKey, Error, KeyShare, Scalar, GENERATOR_TABLE};
+
+    fn run(
+        participants: &[Participant],
+        round1: &[Round1],
+        round2: &[Round2],
+    ) -> Vec<Result<(KeyShare, EncryptionKey), Error>> {
+        participants
+            .iter()
+            .map(|p| {
+                let mine: Vec<_> = round2
+                    .iter()
+                    .filter(|m| m.to == p.index())
+                    .cloned()
+                    .collect();
+                p.finish(round1, &mine)
+            })
+            .collect()
+    }
+
+    // Test that five parties agree on a joint key whose secret is the sum of their constant
+    // terms, held by nobody, and that any three of them decrypt under it while two cannot.
+    #[test]
+    fn dkg_threshold_decrypt() {
+        let mut rng = StdRng::seed_from_u64(508);
+        let (participants, round1): (Vec<_>, Vec<_>) =
+            (1..=5).map(|i| Participant::new(i, 3, 5, &mut rng)).unzip();
+        let round2: Vec<_> = participants.iter().flat_map(|p| p.deal()).collect();
+        let results: Vec<_> = run(&participants, &round1, &round2)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        let ek = results[0].1;
+        let joint: Scalar = participants.iter().map(|p| p.coefficients[0]).sum();
+        assert_eq!(ek.0, &joint * GENERATOR_TABLE);
+        for (p, (share, key)) in participants.iter().zip(&results) {
+            assert_eq!(key, &ek);
+            assert_eq!(share.group_key(), &ek);
+            assert_eq!(share.index(), p.index());
+            assert_eq!(share.threshold(), 3);
+            assert_ne!(share.secret, joint);
+            assert_ne!(p.coefficients[0], joint);
+        }
+
+        // the shares interpolate to the joint secret, though nobody computed it
+        let xs: Vec<_> = (1..=3u32).map(Scalar::from).collect();
+        let lambda = lagrange_coefficients(&xs, Scalar::ZERO).unwrap();
+        let secret: Scalar = lambda
+            .iter()
+            .zip(&results)
+            .map(|(l, r)| l * r.0.secret)
+            .sum();
+        assert_eq!(secret, joint);
+
+        let m = &Scalar::from(99u32) * GENERATOR_TABLE;
+        let ct = ek.encrypt(m, &mut rng);
+        let parts: Vec<_> = results.iter().map(|r| r.0.partial_decrypt(&ct)).collect();
+        assert_eq!(ct.combine_shares(&parts[..3]), Ok(m));
+        assert_eq!(ct.combine_shares(&parts[2..]), Ok(m));
+        assert_eq!(ct.combine_shares(&[parts[1], parts[3], parts[4]]), Ok(m));
+        assert_eq!(
+            ct.combine_shares(&parts[..2]),
+            Err(Error::InsufficientShares {
+                threshold: 3,
+                got: 2
+            })
+        );
+    }
+
+    // Test that a party dealing a bad share is named by its recipient only, and that a bad proof
+    // of possession, a commitment of the wrong degree or missing and misaddressed messages are
+    // refused by everyone.
+    #[test]
+    fn dkg_flags_cheaters() {
+        let mut rng = StdRng::seed_from_u64(508);
+        let (participants, round1): (Vec<_>, Vec<_>) =
+            (1..=5).map(|i| Participant::new(i, 3, 5, &mut rng)).unzip();
+        let round2: Vec<_> = participants.iter().flat_map(|p| p.deal()).collect();
+
+        // participant 2 deals participant 4 a wrong share
+        let mut cheating = round2.clone();
+        let bad = cheating
+            .iter_mut()
+            .find(|m| m.from == 2 && m.to == 4)
+            .unwrap();
+        bad.share += Scalar::ONE;
+        let results = run(&participants, &round1, &cheating);
+        assert_eq!(results[3], Err(Error::InvalidShare { index: 2 }));
+        for i in [0, 1, 2, 4] {
+            assert!(results[i].is_ok());
+        }
+
+        // participant 3 claims participant 1's key as its own constant term
+        let mut forged = round1.clone();
+        forged[2].proof = round1[0].proof;
+        for result in run(&participants, &forged, &round2) {
+            assert_eq!(result, Err(Error::InvalidShare { index: 3 }));
+        }
+
+        // participant 5 commits to a polynomial of too low a degree
+        let (_, short) = Participant::new(5, 2, 5, &mut rng);
+        let mut forged = round1.clone();
+        forged[4] = short;
+        let mine: Vec<_> = round2.iter().filter(|m| m.to == 1).cloned().collect();
+        assert_eq!(
+            participants[0].finish(&forged, &mine),
+            Err(Error::InvalidShare { index: 5 })
+        );
+
+        // a missing round-1 message, a repeated share, and a share for someone else
+        assert_eq!(
+            participants[0].finish(&round1[..4], &mine),
+            Err(Error::InvalidParameters)
+        );
+        let mut repeated = mine.clone();
+        repeated[1] = repeated[0].clone();
+        assert_eq!(
+            participants[0].finish(&round1, &repeated),
+            Err(Error::InvalidParameters)
+        );
+        let mut misaddressed = mine.clone();
+        misaddressed[0].to = 2;
+        assert_eq!(
+            participants[0].finish(&round1, &misaddressed),
+            Err(Error::InvalidParameters)
+        );
+
+        // someone else's state does not finish with this participant's commitment
+        let mut swapped = round1.clone();
+        swapped[0] = Participant::new(1, 3, 5, &mut rng).1;
+        assert_eq!(
+            participants[0].finish(&swapped, &mine),
+            Err(Error::KeyMismatch)
+        );
+    }
+
+    // Test that the messages survive serialisation, the secret shares only with `serde-secret`.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_dkg_messages() {
+        let mut rng = StdRng::seed_from_u64(508);
+        let (participant, round1) = Participant::new(1, 2, 3, &mut rng);
+        let encoded = bincode::serialize(&round1).unwrap();
+        let decoded: Round1 = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, round1);
+        assert_eq!(decoded.verify(2), Ok(()));
+        assert_eq!(decoded.index, participant.index());
+
+        #[cfg(feature = "serde-secret")]
+        for msg in participant.deal() {
+            let encoded = bincode::serialize(&msg).unwrap();
+            let decoded: Round2 = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(decoded, msg);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Discrete-logarithm equality proofs for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_points_to_scalar;
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_POINT, GENERATOR_TABLE};
+
+const DOMAIN: &[u8] = b"rust-elgamal dleq";
+
+/// A non-interactive Chaum-Pedersen proof that `Q = xP` for the secret `x` of a decryption key,
+/// i.e. that `log_G(Y) = log_P(Q)` where `Y` is the encryption key.
+///
+/// This is how a key holder shows that it applied its key correctly to a point (for example when
+/// decrypting or evaluating a PRF) without revealing the key.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, DleqProof, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let p = RistrettoPoint::random(&mut rng);
+/// let q = p * dec_key.as_ref();
+/// let proof = DleqProof::prove(&dec_key, &p, &q, &mut rng);
+/// assert!(proof.verify(dec_key.encryption_key(), &p, &q).is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct DleqProof {
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) c: Scalar,
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) s: Scalar,
+}
+
+impl DleqProof {
+    /// Prove that `q = x * p`, where `x` is the secret of `dk`.
+    ///
+    /// The proof is only meaningful if `q` really is `x * p`; otherwise it will not verify.
+    pub fn prove<R: RngCore + CryptoRng>(
+        dk: &DecryptionKey,
+        p: &RistrettoPoint,
+        q: &RistrettoPoint,
+        rng: &mut R,
+    ) -> DleqProof {
+        DleqProof::prove_with(dk, p, q, &Scalar::random(rng))
+    }
+
+    // Prove with the nonce `k`, which must be secret and never reused.
+    pub(crate) fn prove_with(
+        dk: &DecryptionKey,
+        p: &RistrettoPoint,
+        q: &RistrettoPoint,
+        k: &Scalar,
+    ) -> DleqProof {
+        let a = k * GENERATOR_TABLE;
+        let b = p * k;
+        let c = challenge(&dk.encryption_key().0, p, q, &a, &b);
+        DleqProof {
+            c,
+            s: k + c * dk.secret,
+        }
+    }
+
+    /// Verify the proof that `q = x * p`, where `ek = x * G`.
+    ///
+    /// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not
+    /// hold.
+    pub fn verify(
+        &self,
+        ek: &EncryptionKey,
+        p: &RistrettoPoint,
+        q: &RistrettoPoint,
+    ) -> Result<(), Error> {
+        let a = RistrettoPoint::vartime_double_scalar_mul_basepoint(&-self.c, &ek.0, &self.s);
+        let b = p * self.s - q * self.c;
+        if challenge(&ek.0, p, q, &a, &b) != self.c {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+fn challenge(
+    y: &RistrettoPoint,
+    p: &RistrettoPoint,
+    q: &RistrettoPoint,
+    a: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> Scalar {
+    hash_points_to_scalar(DOMAIN, &[&GENERATOR_POINT, y, p, q, a, b])
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, DleqProof, Error, RistrettoPoint};
+
+    // Test that honest proofs verify and that proofs for the wrong key or point do not.
+    #[test]
+    fn dleq_prove_verify() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let p = RistrettoPoint::random(&mut rng);
+        let q = p * dk.as_ref();
+        let proof = DleqProof::prove(&dk, &p, &q, &mut rng);
+        assert_eq!(proof.verify(dk.encryption_key(), &p, &q), Ok(()));
+
+        assert_eq!(
+            proof.verify(other.encryption_key(), &p, &q),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            proof.verify(dk.encryption_key(), &p, &(q + p)),
+            Err(Error::VerificationFailed)
+        );
+
+        // a proof for the wrong exponent does not verify
+        let wrong = p * other.as_ref();
+        let proof = DleqProof::prove(&dk, &p, &wrong, &mut rng);
+        assert_eq!(
+            proof.verify(dk.encryption_key(), &p, &wrong),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
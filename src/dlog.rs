@@ -0,0 +1,151 @@
+// Bounded discrete logarithms for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::trace::op_span;
+use crate::ElGamalGroup;
+
+// Find `x` in `[0, max]` with `x * G == target` by baby-step giant-step, where `G` is the group
+// generator. Takes about `2 * sqrt(max)` group operations and stores `sqrt(max)` encoded points.
+pub(crate) fn bounded_dlog<G: ElGamalGroup>(target: &G, max: u64) -> Option<u64> {
+    let _span = op_span!("bounded_dlog", max = max);
+    let m = isqrt(max) + 1;
+    let baby = baby_steps::<G>(m);
+
+    // giant steps: target - i * m * G for i in [0, m]
+    let giant = -G::mul_generator(&G::Scalar::from(m));
+    let mut point = *target;
+    for i in 0..=m {
+        if let Some(j) = baby.get(&encode(&point)) {
+            return in_range(i, m, *j, max);
+        }
+        point += giant;
+    }
+    None
+}
+
+// Find `x` in `[-max, max]` with `x * G == target`, searching for `target` and `-target` with
+// the giant steps of one baby-step table. Takes about `3 * sqrt(max)` group operations, rather
+// than the `4 * sqrt(max)` of two separate searches.
+pub(crate) fn bounded_dlog_signed<G: ElGamalGroup>(target: &G, max: u64) -> Option<i64> {
+    let _span = op_span!("bounded_dlog_signed", max = max);
+    let m = isqrt(max) + 1;
+    let baby = baby_steps::<G>(m);
+
+    // giant steps: target - i * m * G and -target - i * m * G for i in [0, m]
+    let giant = -G::mul_generator(&G::Scalar::from(m));
+    let mut positive = *target;
+    let mut negative = -*target;
+    for i in 0..=m {
+        if let Some(j) = baby.get(&encode(&positive)) {
+            return in_range(i, m, *j, max).and_then(|x| i64::try_from(x).ok());
+        }
+        if let Some(j) = baby.get(&encode(&negative)) {
+            return in_range(i, m, *j, max).and_then(|x| i64::try_from(x).ok().map(|x| -x));
+        }
+        positive += giant;
+        negative += giant;
+    }
+    None
+}
+
+// baby steps: j * G for j in [0, m)
+fn baby_steps<G: ElGamalGroup>(m: u64) -> BTreeMap<Vec<u8>, u64> {
+    let mut baby = BTreeMap::new();
+    let mut point = G::identity();
+    for j in 0..m {
+        baby.insert(encode(&point), j);
+        point += G::generator();
+    }
+    baby
+}
+
+// The exponent `i * m + j` found by a search, if it is at most `max`.
+fn in_range(i: u64, m: u64, j: u64, max: u64) -> Option<u64> {
+    let x = i as u128 * m as u128 + j as u128;
+    if x <= max as u128 {
+        Some(x as u64)
+    } else {
+        None
+    }
+}
+
+fn encode<G: ElGamalGroup>(point: &G) -> Vec<u8> {
+    point.to_bytes().as_ref().to_vec()
+}
+
+// The integer square root, rounded down. Computed bit by bit, since `f64::sqrt` needs `std`.
+pub(crate) fn isqrt(n: u64) -> u64 {
+    let mut x = 0u64;
+    for bit in (0..32).rev() {
+        let candidate = x | (1 << bit);
+        if candidate * candidate <= n {
+            x = candidate;
+        }
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dlog::{bounded_dlog, bounded_dlog_signed, isqrt};
+    use crate::{ElGamalGroup, RistrettoPoint, Scalar};
+
+    // Test that exponents are recovered up to and including the bound, and not beyond it.
+    #[test]
+    fn bounded_dlog_range() {
+        for max in [0u64, 1, 2, 15, 16, 17, 1000] {
+            for x in [0, max / 2, max] {
+                let p = RistrettoPoint::mul_generator(&Scalar::from(x));
+                assert_eq!(bounded_dlog(&p, max), Some(x));
+            }
+            let p = RistrettoPoint::mul_generator(&Scalar::from(max + 1));
+            assert_eq!(bounded_dlog(&p, max), None);
+        }
+    }
+
+    // Test that signed exponents are recovered at both ends of the range, and not beyond them.
+    #[test]
+    fn bounded_dlog_signed_range() {
+        for max in [0u64, 1, 2, 15, 16, 17, 1000] {
+            for x in [0, max.min(1), max / 2, max] {
+                let x = x as i64;
+                for x in [x, -x] {
+                    let s = if x < 0 {
+                        -Scalar::from(x.unsigned_abs())
+                    } else {
+                        Scalar::from(x as u64)
+                    };
+                    let p = RistrettoPoint::mul_generator(&s);
+                    assert_eq!(bounded_dlog_signed(&p, max), Some(x));
+                }
+            }
+            let p = RistrettoPoint::mul_generator(&Scalar::from(max + 1));
+            assert_eq!(bounded_dlog_signed(&p, max), None);
+            assert_eq!(bounded_dlog_signed(&-p, max), None);
+        }
+    }
+
+    #[test]
+    fn isqrt_exact() {
+        for n in [0u64, 1, 3, 4, 15, 16, 17, u32::MAX as u64, u64::MAX] {
+            let r = isqrt(n) as u128;
+            assert!(r * r <= n as u128 && (r + 1) * (r + 1) > n as u128);
+        }
+    }
+}
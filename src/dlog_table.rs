@@ -0,0 +1,390 @@
+// In-memory discrete logarithm tables for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+#[cfg(feature = "enable-serde")]
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+#[cfg(feature = "enable-serde")]
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess, CtOption};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::trace::op_span;
+#[cfg(feature = "enable-serde")]
+use crate::GENERATOR_TABLE;
+use crate::{Ciphertext, DecryptionKey, Error, GENERATOR_POINT};
+
+/// A table of the compressed points `jG` for every `j` in `[0, max]`, for recovering small
+/// exponents from exponential ciphertexts, such as the totals of many homomorphic counters.
+///
+/// The table is precomputed once, with `max` point additions, `max + 1` point compressions and a
+/// sort, and takes `36 * (max + 1)` bytes: 32 for each point and 4 for its place in a sorted
+/// index. A table of 2<sup>20</sup> values takes about 38 MB.
+///
+/// [lookup](Self::lookup) and [DecryptionKey::decrypt_with_table] binary-search the index, so a
+/// lookup costs one point compression and about `log2(max)` comparisons, against the
+/// `2 * sqrt(max)` group operations of a baby-step giant-step search such as
+/// [DecryptionKey::decrypt_u32_bounded]; the time taken depends on the value.
+/// [lookup_ct](Self::lookup_ct) instead scans the whole table, so its running time depends only
+/// on the size of the table and not on the value looked up, nor on whether it is in the table at
+/// all; it costs `max + 1` constant-time comparisons of 32 bytes, and is meant for small ranges,
+/// such as the 65536 values of a `u16`.
+///
+/// With the `enable-serde` feature a table can be built once and shipped with a binary. Only the
+/// points are serialised, and the index is rebuilt when deserialising, which checks that the
+/// first and last points are `0G` and `max G` and that the points are distinct. The other points
+/// are not recomputed, so a table from an untrusted source can still give wrong answers: load
+/// tables only from where the code itself comes from.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, DiscreteLogTable, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let table = DiscreteLogTable::new(1000);
+///
+/// let ct = dec_key.encryption_key().exp_encrypt(Scalar::from(465u32), &mut rng);
+/// assert_eq!(dec_key.decrypt_u16_ct(ct, &table).unwrap(), 465);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "enable-serde",
+    derive(Deserialize),
+    serde(try_from = "TableRepr")
+)]
+pub struct DiscreteLogTable {
+    points: Vec<[u8; 32]>,
+    // the indices of `points`, sorted by point
+    order: Vec<u32>,
+}
+
+impl DiscreteLogTable {
+    /// Build a table covering discrete logarithms in `[0, max]`.
+    ///
+    /// # Panics
+    ///
+    /// If `max` is not less than 2<sup>32</sup>, as such a table would not fit in memory anyway.
+    pub fn new(max: u64) -> DiscreteLogTable {
+        let _span = op_span!("build_dlog_table", max = max);
+        assert!(
+            max < 1 << 32,
+            "discrete log tables cover at most 2^32 values"
+        );
+        let mut points = Vec::with_capacity(max as usize + 1);
+        let mut point = RistrettoPoint::identity();
+        for _ in 0..max {
+            points.push(point.compress().to_bytes());
+            point += GENERATOR_POINT;
+        }
+        points.push(point.compress().to_bytes());
+        let order = sorted_index(&points);
+        DiscreteLogTable { points, order }
+    }
+
+    /// Find `x` in `[0, max]` with `xG == point`, by binary search, or none if there is no such
+    /// `x`.
+    ///
+    /// The running time depends on `point`; use [lookup_ct](Self::lookup_ct) where that matters.
+    pub fn lookup(&self, point: &RistrettoPoint) -> Option<u64> {
+        let target = point.compress().to_bytes();
+        self.order
+            .binary_search_by(|&j| self.points[j as usize].cmp(&target))
+            .ok()
+            .map(|k| self.order[k] as u64)
+    }
+
+    /// The largest discrete logarithm the table covers.
+    pub fn max(&self) -> u64 {
+        self.points.len() as u64 - 1
+    }
+
+    /// Find `x` in `[0, max]` with `xG == point`, in constant time: every entry is compared, and
+    /// the match is selected without branching on it.
+    pub fn lookup_ct(&self, point: &RistrettoPoint) -> CtOption<u64> {
+        let target = point.compress().to_bytes();
+        let mut found = Choice::from(0);
+        let mut index = 0u64;
+        for (j, entry) in self.points.iter().enumerate() {
+            let matches = entry.ct_eq(&target);
+            index.conditional_assign(&(j as u64), matches);
+            found |= matches;
+        }
+        CtOption::new(index, found)
+    }
+}
+
+impl DecryptionKey {
+    /// Decrypt an exponential ciphertext `ct` of a value in `[0, table.max()]`, looking the
+    /// discrete logarithm up with [DiscreteLogTable::lookup].
+    ///
+    /// Fails with [Error::OutOfRange](crate::Error::OutOfRange) if the value is not in range.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, DiscreteLogTable};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    /// let table = DiscreteLogTable::new(1 << 13);
+    ///
+    /// let zero = enc_key.encrypt_scalar(0u64, &mut rng);
+    /// let total = (1..100u64).fold(zero, |sum, i| sum + enc_key.encrypt_scalar(i, &mut rng));
+    /// assert_eq!(dec_key.decrypt_with_table(total, &table).unwrap(), 4950);
+    /// ```
+    pub fn decrypt_with_table(
+        &self,
+        ct: Ciphertext,
+        table: &DiscreteLogTable,
+    ) -> Result<u64, Error> {
+        table.lookup(&self.decrypt(ct)).ok_or(Error::OutOfRange)
+    }
+
+    /// Decrypt an exponential ciphertext `ct` of a `u16`, in constant time, with
+    /// [DiscreteLogTable::lookup_ct].
+    ///
+    /// The result is none if the value is not in the table or does not fit in a `u16`. A table
+    /// with `max` at least `u16::MAX` decrypts every `u16`; the cost is that of decrypting plus
+    /// scanning the whole table, whatever the value.
+    pub fn decrypt_u16_ct(&self, ct: Ciphertext, table: &DiscreteLogTable) -> CtOption<u16> {
+        let x = table.lookup_ct(&self.decrypt(ct));
+        let value = x.unwrap_or(0);
+        CtOption::new(value as u16, x.is_some() & value.ct_lt(&(1 << 16)))
+    }
+}
+
+fn sorted_index(points: &[[u8; 32]]) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..points.len() as u32).collect();
+    order.sort_unstable_by(|&a, &b| points[a as usize].cmp(&points[b as usize]));
+    order
+}
+
+// The serialised form of a table, which leaves out the index.
+#[cfg(feature = "enable-serde")]
+#[derive(Deserialize)]
+#[serde(rename = "DiscreteLogTable")]
+struct TableRepr {
+    points: Vec<[u8; 32]>,
+}
+
+#[cfg(feature = "enable-serde")]
+#[derive(Serialize)]
+#[serde(rename = "DiscreteLogTable")]
+struct TableReprRef<'a> {
+    points: &'a [[u8; 32]],
+}
+
+#[cfg(feature = "enable-serde")]
+impl Serialize for DiscreteLogTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TableReprRef {
+            points: &self.points,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "enable-serde")]
+impl TryFrom<TableRepr> for DiscreteLogTable {
+    type Error = Error;
+
+    fn try_from(repr: TableRepr) -> Result<Self, Error> {
+        let points = repr.points;
+        let max = match points.len().checked_sub(1) {
+            Some(max) if (max as u64) < 1 << 32 => max as u64,
+            _ => return Err(Error::CorruptTable),
+        };
+        let last = (&Scalar::from(max) * GENERATOR_TABLE).compress().to_bytes();
+        if points[0] != RistrettoPoint::identity().compress().to_bytes()
+            || points[max as usize] != last
+        {
+            return Err(Error::CorruptTable);
+        }
+
+        let order = sorted_index(&points);
+        let distinct = order
+            .windows(2)
+            .all(|pair| points[pair[0] as usize] != points[pair[1] as usize]);
+        if !distinct {
+            return Err(Error::CorruptTable);
+        }
+        Ok(DiscreteLogTable { points, order })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::dlog::bounded_dlog;
+    use crate::{DecryptionKey, DiscreteLogTable, ElGamalGroup, Error, RistrettoPoint, Scalar};
+
+    const MAX: u64 = 300;
+
+    // Test hits at the start, middle and end of the table, and misses just past it and far away.
+    #[test]
+    fn lookup_ct_positions() {
+        let table = DiscreteLogTable::new(MAX);
+        assert_eq!(table.max(), MAX);
+        for x in [0, 1, MAX / 2, MAX - 1, MAX] {
+            let p = RistrettoPoint::mul_generator(&Scalar::from(x));
+            assert_eq!(Option::<u64>::from(table.lookup_ct(&p)), Some(x));
+        }
+
+        let mut rng = StdRng::seed_from_u64(465);
+        for p in [
+            RistrettoPoint::mul_generator(&Scalar::from(MAX + 1)),
+            RistrettoPoint::mul_generator(&-Scalar::ONE),
+            RistrettoPoint::random(&mut rng),
+        ] {
+            assert!(bool::from(table.lookup_ct(&p).is_none()));
+        }
+
+        let single = DiscreteLogTable::new(0);
+        assert_eq!(single.max(), 0);
+        let identity = RistrettoPoint::mul_generator(&Scalar::ZERO);
+        assert_eq!(Option::<u64>::from(single.lookup_ct(&identity)), Some(0));
+    }
+
+    // Test that constant-time decryption agrees with the variable-time search, in range and out.
+    #[test]
+    fn decrypt_u16_ct_matches_variable_time() {
+        let mut rng = StdRng::seed_from_u64(465);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let table = DiscreteLogTable::new(MAX);
+
+        for x in (0..=MAX + 5).step_by(7).chain([MAX, MAX + 1, 1 << 16]) {
+            let ct = ek.exp_encrypt(Scalar::from(x), &mut rng);
+            let expected = bounded_dlog(&dk.decrypt(ct), MAX).map(|x| x as u16);
+            assert_eq!(Option::<u16>::from(dk.decrypt_u16_ct(ct, &table)), expected);
+        }
+
+        // homomorphic sums decrypt too
+        let sum = ek.exp_encrypt(Scalar::from(100u32), &mut rng)
+            + ek.exp_encrypt(Scalar::from(65u32), &mut rng);
+        assert_eq!(
+            Option::<u16>::from(dk.decrypt_u16_ct(sum, &table)),
+            Some(165)
+        );
+    }
+
+    // Test that values past `u16::MAX` are not truncated when the table covers them.
+    #[test]
+    fn decrypt_u16_ct_overflow() {
+        let mut rng = StdRng::seed_from_u64(465);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let table = DiscreteLogTable::new(1 << 16);
+
+        let ct = ek.exp_encrypt(Scalar::from(u16::MAX), &mut rng);
+        assert_eq!(
+            Option::<u16>::from(dk.decrypt_u16_ct(ct, &table)),
+            Some(u16::MAX)
+        );
+        let ct = ek.exp_encrypt(Scalar::from(1u64 << 16), &mut rng);
+        assert_eq!(
+            Option::<u64>::from(table.lookup_ct(&dk.decrypt(ct))),
+            Some(1 << 16)
+        );
+        assert!(bool::from(dk.decrypt_u16_ct(ct, &table).is_none()));
+    }
+
+    // Test that the binary search agrees with the constant-time scan over the whole table and
+    // around its boundary, and that decrypting with the table fails past it.
+    #[test]
+    fn lookup_boundary() {
+        let table = DiscreteLogTable::new(MAX);
+        for x in 0..=MAX + 2 {
+            let p = RistrettoPoint::mul_generator(&Scalar::from(x));
+            assert_eq!(table.lookup(&p), Option::<u64>::from(table.lookup_ct(&p)));
+            assert_eq!(table.lookup(&p), Some(x).filter(|&x| x <= MAX));
+        }
+        let mut rng = StdRng::seed_from_u64(516);
+        assert_eq!(table.lookup(&RistrettoPoint::random(&mut rng)), None);
+        let single = DiscreteLogTable::new(0);
+        assert_eq!(
+            single.lookup(&RistrettoPoint::mul_generator(&Scalar::ZERO)),
+            Some(0)
+        );
+        assert_eq!(
+            single.lookup(&RistrettoPoint::mul_generator(&Scalar::ONE)),
+            None
+        );
+
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        for x in [0, 1, MAX - 1, MAX] {
+            let ct = ek.encrypt_scalar(x, &mut rng);
+            assert_eq!(dk.decrypt_with_table(ct, &table), Ok(x));
+        }
+        let ct = ek.encrypt_scalar(MAX + 1, &mut rng);
+        assert_eq!(dk.decrypt_with_table(ct, &table), Err(Error::OutOfRange));
+        let sum = ek.encrypt_scalar(MAX - 10, &mut rng) + ek.encrypt_scalar(10u64, &mut rng);
+        assert_eq!(dk.decrypt_with_table(sum, &table), Ok(MAX));
+        let sum = sum + ek.encrypt_scalar(1u64, &mut rng);
+        assert_eq!(dk.decrypt_with_table(sum, &table), Err(Error::OutOfRange));
+    }
+
+    // Test that a table survives serialisation with its index rebuilt, and that empty tables and
+    // tables with the wrong ends or repeated points are refused.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_table() {
+        let table = DiscreteLogTable::new(MAX);
+        let encoded = bincode::serialize(&table).unwrap();
+        assert_eq!(encoded.len(), 8 + 32 * (MAX as usize + 1));
+        let decoded: DiscreteLogTable = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, table);
+        let p = RistrettoPoint::mul_generator(&Scalar::from(123u32));
+        assert_eq!(decoded.lookup(&p), Some(123));
+
+        let reject = |points: &[[u8; 32]]| {
+            let encoded = bincode::serialize(&super::TableReprRef { points }).unwrap();
+            bincode::deserialize::<DiscreteLogTable>(&encoded).is_err()
+        };
+        assert!(!reject(&table.points));
+        assert!(reject(&[]));
+        assert!(reject(&table.points[1..]));
+        let mut wrong_end = table.points.clone();
+        wrong_end[MAX as usize] = RistrettoPoint::mul_generator(&Scalar::from(MAX + 1))
+            .compress()
+            .to_bytes();
+        assert!(reject(&wrong_end));
+        // a truncated table is a smaller table
+        let encoded = bincode::serialize(&super::TableReprRef {
+            points: &table.points[..MAX as usize],
+        })
+        .unwrap();
+        let truncated: DiscreteLogTable = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(truncated, DiscreteLogTable::new(MAX - 1));
+        let mut repeated = table.points.clone();
+        repeated[5] = repeated[6];
+        assert!(reject(&repeated));
+    }
+}
@@ -0,0 +1,198 @@
+// Dummy ciphertexts for padding batches in rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error};
+
+/// The point that dummy ciphertexts encrypt, compressed.
+///
+/// This is the hash of the empty string to a point under the domain `rust-elgamal dummy`, so
+/// nobody knows its discrete log, and a real message, whether a point, encoded bytes or a value in
+/// the exponent, is the dummy point only with negligible probability.
+pub const DUMMY_POINT_COMPRESSED: CompressedRistretto = CompressedRistretto([
+    0x84, 0x47, 0xd4, 0x14, 0x1c, 0xb5, 0x06, 0xd3, 0x49, 0x08, 0x1a, 0x52, 0x71, 0xcd, 0x95, 0xe1,
+    0x9e, 0x49, 0x1c, 0x85, 0xa9, 0x04, 0xc0, 0x7e, 0x86, 0xf9, 0xa7, 0x6a, 0x85, 0x9e, 0x96, 0x2b,
+]);
+
+/// The point that dummy ciphertexts encrypt; see
+/// [DUMMY_POINT_COMPRESSED](crate::DUMMY_POINT_COMPRESSED).
+pub fn dummy_point() -> RistrettoPoint {
+    DUMMY_POINT_COMPRESSED
+        .decompress()
+        .expect("the dummy point is a valid point")
+}
+
+impl EncryptionKey {
+    /// Encrypt the [dummy point](dummy_point) `count` times, for padding a batch.
+    ///
+    /// Each dummy is a fresh encryption, indistinguishable from a real ciphertext to anyone
+    /// without the decryption key, and stays a dummy through rerandomisation and shuffling.
+    pub fn generate_dummies<R: RngCore + CryptoRng>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Vec<Ciphertext> {
+        let dummy = dummy_point();
+        (0..count).map(|_| self.encrypt(dummy, rng)).collect()
+    }
+}
+
+/// Pad `batch` with dummy ciphertexts under `ek` until it holds `target_len` ciphertexts, so that
+/// its length does not reveal how many real messages it carries.
+///
+/// The dummies are appended; shuffle the batch afterwards to hide their positions. Fails with
+/// [Error::LengthMismatch], leaving the batch as it is, if it already holds more than `target_len`
+/// ciphertexts.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{pad_to, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let messages: Vec<_> = (0..3).map(|_| RistrettoPoint::random(&mut rng)).collect();
+/// let mut batch: Vec<_> = messages.iter().map(|m| enc_key.encrypt(*m, &mut rng)).collect();
+/// pad_to(&mut batch, 10, enc_key, &mut rng).unwrap();
+/// assert_eq!(batch.len(), 10);
+///
+/// let (real, dummies) = dec_key.partition_dummies(&batch);
+/// assert_eq!(real, messages);
+/// assert_eq!(dummies, 7);
+/// ```
+pub fn pad_to<R: RngCore + CryptoRng>(
+    batch: &mut Vec<Ciphertext>,
+    target_len: usize,
+    ek: &EncryptionKey,
+    rng: &mut R,
+) -> Result<(), Error> {
+    if batch.len() > target_len {
+        return Err(Error::LengthMismatch {
+            expected: target_len,
+            got: batch.len(),
+        });
+    }
+    let count = target_len - batch.len();
+    batch.extend(ek.generate_dummies(count, rng));
+    Ok(())
+}
+
+impl DecryptionKey {
+    /// Decrypt `cts` and separate the real plaintexts from the dummies, returning the real ones in
+    /// their order in `cts` and the number of dummies.
+    pub fn partition_dummies(&self, cts: &[Ciphertext]) -> (Vec<RistrettoPoint>, usize) {
+        let dummy = dummy_point();
+        let mut real = Vec::with_capacity(cts.len());
+        for ct in cts {
+            let m = self.decrypt(*ct);
+            if m != dummy {
+                real.push(m);
+            }
+        }
+        let dummies = cts.len() - real.len();
+        (real, dummies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{dummy_point, pad_to, DUMMY_POINT_COMPRESSED};
+    use crate::hash::hash_to_point;
+    use crate::{DecryptionKey, Error, Permutation, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    // Test that the dummy point is the documented hash.
+    #[test]
+    fn dummy_point_is_hash() {
+        let point = hash_to_point(b"rust-elgamal dummy", b"");
+        assert_eq!(point.compress(), DUMMY_POINT_COMPRESSED);
+        assert_eq!(dummy_point(), point);
+    }
+
+    // Test that dummies padded into a batch survive rerandomisation and shuffling, and are
+    // filtered out after decryption, leaving the real messages in shuffled order.
+    #[test]
+    fn dummies_survive_mixing() {
+        let mut rng = StdRng::seed_from_u64(496);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        // include a message encoded in the exponent, zero among them
+        let mut messages: Vec<_> = (0..5).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        messages.push(&Scalar::ZERO * GENERATOR_TABLE);
+        messages.push(&Scalar::from(7u32) * GENERATOR_TABLE);
+        let mut batch: Vec<_> = messages.iter().map(|m| ek.encrypt(*m, &mut rng)).collect();
+        pad_to(&mut batch, 16, ek, &mut rng).unwrap();
+        assert_eq!(batch.len(), 16);
+        assert_eq!(&dk.partition_dummies(&batch).0, &messages);
+
+        // two mix passes
+        let mut order: Vec<usize> = (0..16).collect();
+        for _ in 0..2 {
+            let permutation = Permutation::random(16, &mut rng);
+            batch = permutation.apply(&batch).unwrap();
+            order = permutation.apply(&order).unwrap();
+            for ct in batch.iter_mut() {
+                *ct = ek.rerandomise(*ct, &mut rng);
+            }
+        }
+
+        let (real, dummies) = dk.partition_dummies(&batch);
+        assert_eq!(dummies, 9);
+        let expected: Vec<_> = order
+            .iter()
+            .filter(|&&i| i < messages.len())
+            .map(|&i| messages[i])
+            .collect();
+        assert_eq!(real, expected);
+    }
+
+    // Test padding at and over the target length.
+    #[test]
+    fn pad_to_limits() {
+        let mut rng = StdRng::seed_from_u64(496);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let mut batch = ek.generate_dummies(4, &mut rng);
+        pad_to(&mut batch, 4, ek, &mut rng).unwrap();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(
+            pad_to(&mut batch, 3, ek, &mut rng),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert_eq!(batch.len(), 4);
+        assert_eq!(dk.partition_dummies(&batch), (Vec::new(), 4));
+
+        let mut empty = Vec::new();
+        pad_to(&mut empty, 0, ek, &mut rng).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,699 @@
+// Uniform byte representations of ciphertexts for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Each component of a ciphertext is written as a preimage under the Elligator map that ristretto255
+// uses for hashing to the group (RFC 9496, section 4.3.4). curve25519-dalek only exposes the map
+// itself, so the inverse, which follows the "lizard" construction through the Jacobi quartic,
+// and the field arithmetic it needs live here.
+//
+// A point has at most 8 preimages with a non-negative encoding. Picking one of the 8 slots at
+// random and giving up when it is empty makes every non-negative field element equally likely,
+// and a random sign and top bit then make the 32 bytes uniform (up to the 19 non-canonical
+// encodings, which are never output).
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallyNegatable, ConditionallySelectable, ConstantTimeEq};
+
+use crate::{Ciphertext, EncryptionKey};
+
+impl Ciphertext {
+    /// Write the ciphertext as 64 bytes that are indistinguishable from uniformly random ones, or
+    /// return `None` if the random choice made for either component has no representative.
+    ///
+    /// Compressed ristretto255 points are easy to tell apart from random strings (their last bit
+    /// is always clear, for a start), so this writes each component as a random preimage under
+    /// ristretto255's Elligator map instead, which [from_uniform_bytes](Self::from_uniform_bytes)
+    /// maps back. To keep the output uniform, a component is only represented with probability
+    /// proportional to its number of preimages: each call succeeds with probability about 1/4 for
+    /// a freshly encrypted ciphertext, and a ciphertext with no representative at all fails every
+    /// time. On `None`, encrypt again with fresh randomness, or encrypt with
+    /// [EncryptionKey::encrypt_uniform], which does so. Calling this again on the same ciphertext
+    /// until it succeeds also gives an encoding when one exists, but ciphertexts kept that way are
+    /// no longer uniform in the group, and neither are their encodings.
+    ///
+    /// The conversion is not constant time.
+    pub fn to_uniform_bytes<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Option<[u8; 64]> {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&representative(&self.0, rng)?);
+        out[32..].copy_from_slice(&representative(&self.1, rng)?);
+        Some(out)
+    }
+
+    /// Read a ciphertext written by [to_uniform_bytes](Self::to_uniform_bytes).
+    ///
+    /// Every 64-byte string gives a ciphertext, so this cannot fail; random bytes give a
+    /// ciphertext that decrypts to a random point.
+    pub fn from_uniform_bytes(bytes: &[u8; 64]) -> Ciphertext {
+        let mut c1 = [0u8; 32];
+        let mut c2 = [0u8; 32];
+        c1.copy_from_slice(&bytes[..32]);
+        c2.copy_from_slice(&bytes[32..]);
+        Ciphertext(elligator(&c1), elligator(&c2))
+    }
+}
+
+impl EncryptionKey {
+    /// Encrypt `m`, drawing fresh randomness until the ciphertext can be written with
+    /// [Ciphertext::to_uniform_bytes], and return it with its uniform encoding.
+    ///
+    /// This takes 4 encryptions on average.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{Ciphertext, DecryptionKey, RistrettoPoint};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = RistrettoPoint::random(&mut rng);
+    /// let (ct, bytes) = enc_key.encrypt_uniform(m, &mut rng);
+    /// assert_eq!(Ciphertext::from_uniform_bytes(&bytes), ct);
+    /// assert_eq!(dec_key.decrypt(ct), m);
+    /// ```
+    pub fn encrypt_uniform<R: RngCore + CryptoRng>(
+        &self,
+        m: RistrettoPoint,
+        rng: &mut R,
+    ) -> (Ciphertext, [u8; 64]) {
+        loop {
+            let ct = self.encrypt_with(m, Scalar::random(rng));
+            if let Some(bytes) = ct.to_uniform_bytes(rng) {
+                return (ct, bytes);
+            }
+        }
+    }
+}
+
+// The Elligator map of ristretto255 on one field element, the top bit of the bytes ignored. Only
+// the sum of two maps is exposed, but the map sends zero to the identity.
+fn elligator(bytes: &[u8; 32]) -> RistrettoPoint {
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(bytes);
+    RistrettoPoint::from_uniform_bytes(&wide)
+}
+
+// A random representative of `point` from a random slot, or `None` if the slot is empty.
+fn representative<R: RngCore + CryptoRng>(point: &RistrettoPoint, rng: &mut R) -> Option<[u8; 32]> {
+    let (mask, preimages) = preimages(&point.compress());
+    let mut choice = [0u8; 1];
+    rng.fill_bytes(&mut choice);
+    let slot = usize::from(choice[0] & 7);
+    if mask & (1 << slot) == 0 {
+        return None;
+    }
+    // both signs map to the point, and the top bit is ignored
+    let mut f = preimages[slot];
+    f.conditional_negate(Choice::from(choice[0] >> 3 & 1));
+    let mut bytes = f.to_bytes();
+    bytes[31] |= choice[0] & 0x80;
+    Some(bytes)
+}
+
+// The non-negative field elements that the Elligator map sends to `point`, in the slots whose bits
+// are set in the mask.
+fn preimages(point: &CompressedRistretto) -> (u8, [Fe; 8]) {
+    let (x, y) = decode(point);
+    let mut mask = 0u8;
+    let mut out = [Fe::ZERO; 8];
+    for (i, jc) in jacobi_quartic(&x, &y).iter().enumerate() {
+        for (j, jc) in [*jc, jc.dual()].iter().enumerate() {
+            let (ok, f) = jc.elligator_inverse();
+            out[2 * i + j] = f;
+            mask |= u8::from(bool::from(ok)) << (2 * i + j);
+        }
+    }
+    (mask, out)
+}
+
+// The affine Edwards coordinates of one of the points in the coset a ristretto255 encoding stands
+// for (RFC 9496, section 4.3.1). The encoding is known to be valid.
+fn decode(point: &CompressedRistretto) -> (Fe, Fe) {
+    let s = Fe::from_bytes(point.as_bytes());
+    let ss = s.square();
+    let u1 = Fe::ONE - ss;
+    let u2 = Fe::ONE + ss;
+    let u2_sqr = u2.square();
+    let v = -(D * u1.square()) - u2_sqr;
+    let (_, invsqrt) = Fe::sqrt_ratio_m1(&Fe::ONE, &(v * u2_sqr));
+    let den_x = invsqrt * u2;
+    let den_y = invsqrt * den_x * v;
+    let x = (s + s) * den_x;
+    (x.abs(), u1 * den_y)
+}
+
+// A point `(s, t)` on the Jacobi quartic `t^2 = (a - d) s^4 + 2 (a + d) s^2 + 1` isogenous to the
+// curve, where Elligator does its work.
+#[derive(Copy, Clone)]
+struct JacobiPoint {
+    s: Fe,
+    t: Fe,
+}
+
+impl JacobiPoint {
+    fn dual(&self) -> JacobiPoint {
+        JacobiPoint {
+            s: -self.s,
+            t: -self.t,
+        }
+    }
+
+    // The non-negative field element that Elligator sends to this point, if there is one.
+    fn elligator_inverse(&self) -> (Choice, Fe) {
+        let mut out = Fe::ZERO;
+
+        // s = 0: then t is 1, with preimage sqrt(i d), or -1, with preimage 0
+        let s_is_zero = self.s.is_zero();
+        out.conditional_assign(&SQRT_ID, self.t.ct_eq(&Fe::ONE));
+        let mut ok = s_is_zero;
+        let mut done = s_is_zero;
+
+        // a = (t + 1) (d + 1) / (d - 1)
+        let a = (self.t + Fe::ONE) * DP1_OVER_DM1;
+        let s2 = self.s.square();
+
+        // y = 1 / sqrt(i (s^4 - a^2)), which must exist
+        let (is_square, y) = Fe::sqrt_ratio_m1(&Fe::ONE, &((s2.square() - a.square()) * SQRT_M1));
+        ok |= is_square;
+        done |= !is_square;
+
+        // x = (a + sign(s) s^2) y, taken non-negative
+        let mut signed_s2 = s2;
+        signed_s2.conditional_negate(self.s.is_negative());
+        let x = ((a + signed_s2) * y).abs();
+        out.conditional_assign(&x, !done);
+        (ok, out)
+    }
+}
+
+// The points on the Jacobi quartic for the four points in the same ristretto255 coset as `(x, y)`.
+fn jacobi_quartic(x: &Fe, y: &Fe) -> [JacobiPoint; 4] {
+    let z = Fe::ONE;
+    let i = SQRT_M1;
+    let x2 = x.square();
+    let y2 = y.square();
+    let z2_min_y2 = z.square() - y2;
+
+    // gamma = 1 / sqrt(y^4 x^2 (z^2 - y^2))
+    let (_, gamma) = Fe::sqrt_ratio_m1(&Fe::ONE, &(y2.square() * x2 * z2_min_y2));
+
+    let den = gamma * y2;
+    let s_over_x = den * (z - *y);
+    let sp_over_xp = den * (z + *y);
+    let s0 = s_over_x * *x;
+    let s1 = -sp_over_xp * *x;
+    let tmp = MDOUBLE_INVSQRT_A_MINUS_D * z;
+    let mut t0 = tmp * s_over_x;
+    let mut t1 = tmp * sp_over_xp;
+
+    // the same with (x, y, z) replaced by (y, x, iz)
+    let den = -z2_min_y2 * -INVSQRT_ONE_PLUS_D * gamma;
+    let iz = i * z;
+    let s_over_y = den * (iz - *x);
+    let sp_over_yp = den * (iz + *x);
+    let mut s2 = s_over_y * *y;
+    let mut s3 = -sp_over_yp * *y;
+    let tmp = MDOUBLE_INVSQRT_A_MINUS_D * iz;
+    let mut t2 = tmp * s_over_y;
+    let mut t3 = tmp * sp_over_yp;
+
+    // x = 0 or y = 0: (0, 1), (1, -2i / sqrt(a - d)) and (-1, -2i / sqrt(a - d))
+    let x_or_y_is_zero = x.is_zero() | y.is_zero();
+    t0.conditional_assign(&Fe::ONE, x_or_y_is_zero);
+    t1.conditional_assign(&Fe::ONE, x_or_y_is_zero);
+    t2.conditional_assign(&(MDOUBLE_INVSQRT_A_MINUS_D * i), x_or_y_is_zero);
+    t3.conditional_assign(&(MDOUBLE_INVSQRT_A_MINUS_D * i), x_or_y_is_zero);
+    s2.conditional_assign(&Fe::ONE, x_or_y_is_zero);
+    s3.conditional_assign(&-Fe::ONE, x_or_y_is_zero);
+
+    [
+        JacobiPoint { s: s0, t: t0 },
+        JacobiPoint { s: s1, t: t1 },
+        JacobiPoint { s: s2, t: t2 },
+        JacobiPoint { s: s3, t: t3 },
+    ]
+}
+
+// An element of the field of integers modulo p = 2^255 - 19, as four little-endian 64-bit limbs,
+// always fully reduced.
+#[derive(Copy, Clone, Debug)]
+struct Fe([u64; 4]);
+
+const P: [u64; 4] = [
+    0xffff_ffff_ffff_ffed,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+];
+
+// -121665 / 121666
+const D: Fe = Fe([
+    0x75eb_4dca_1359_78a3,
+    0x0070_0a4d_4141_d8ab,
+    0x8cc7_4079_7779_e898,
+    0x5203_6cee_2b6f_fe73,
+]);
+
+// The non-negative square root of -1.
+const SQRT_M1: Fe = Fe([
+    0xc4ee_1b27_4a0e_a0b0,
+    0x2f43_1806_ad2f_e478,
+    0x2b4d_0099_3dfb_d7a7,
+    0x2b83_2480_4fc1_df0b,
+]);
+
+// sqrt(i d)
+const SQRT_ID: Fe = Fe([
+    0x7530_2acb_4a5c_1ba8,
+    0xcdbc_a92d_0eea_6daa,
+    0x7534_5499_73eb_6e15,
+    0x05b3_5558_f47b_eb97,
+]);
+
+// (d + 1) / (d - 1)
+const DP1_OVER_DM1: Fe = Fe([
+    0x277f_ac5f_9b81_bb2c,
+    0x489a_e7f1_cd24_1dc8,
+    0xaef8_d95f_999f_1824,
+    0x0e67_c830_a7e8_d89d,
+]);
+
+// -2 / sqrt(a - d), with a = -1
+const MDOUBLE_INVSQRT_A_MINUS_D: Fe = Fe([
+    0xcc6e_04aa_ff45_7e06,
+    0xc5a1_d3d1_4b7d_1a82,
+    0xd27b_08dc_03fc_4f7e,
+    0x0f26_edf4_60a0_06bb,
+]);
+
+// 1 / sqrt(1 + d)
+const INVSQRT_ONE_PLUS_D: Fe = Fe([
+    0x0b2e_db88_31bb_ddec,
+    0x4e52_f71c_6bda_b64e,
+    0x1ef0_2c42_9405_ac88,
+    0x0a0d_85b4_032b_1ea8,
+]);
+
+impl Fe {
+    const ZERO: Fe = Fe([0; 4]);
+    const ONE: Fe = Fe([1, 0, 0, 0]);
+
+    #[cfg(test)]
+    fn from_u64(n: u64) -> Fe {
+        Fe([n, 0, 0, 0])
+    }
+
+    // Read 32 little-endian bytes, ignoring the top bit and reducing.
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
+        }
+        limbs[3] &= (1 << 63) - 1;
+        Fe::reduce(limbs)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    // Reduce a value below 2^256.
+    fn reduce(mut limbs: [u64; 4]) -> Fe {
+        // 2^255 = 19
+        let top = limbs[3] >> 63;
+        limbs[3] &= (1 << 63) - 1;
+        let mut carry = u128::from(top * 19);
+        for limb in limbs.iter_mut() {
+            let sum = u128::from(*limb) + carry;
+            *limb = sum as u64;
+            carry = sum >> 64;
+        }
+        // now below p + 38, so at most one subtraction of p
+        let mut reduced = [0u64; 4];
+        let mut borrow = 0u64;
+        for i in 0..4 {
+            let (diff, b1) = limbs[i].overflowing_sub(P[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow);
+            reduced[i] = diff;
+            borrow = u64::from(b1 | b2);
+        }
+        let keep = Choice::from(borrow as u8);
+        Fe::conditional_select(&Fe(reduced), &Fe(limbs), keep)
+    }
+
+    fn square(&self) -> Fe {
+        *self * *self
+    }
+
+    fn pow2k(&self, k: u32) -> Fe {
+        let mut out = *self;
+        for _ in 0..k {
+            out = out.square();
+        }
+        out
+    }
+
+    // self^(2^250 - 1) and self^11, from which the exponents below are built.
+    fn pow22501(&self) -> (Fe, Fe) {
+        let t0 = self.square();
+        let t1 = t0.pow2k(2) * *self;
+        let t11 = t0 * t1;
+        let t31 = t11.square() * t1;
+        let t10 = t31.pow2k(5) * t31;
+        let t20 = t10.pow2k(10) * t10;
+        let t40 = t20.pow2k(20) * t20;
+        let t50 = t40.pow2k(10) * t10;
+        let t100 = t50.pow2k(50) * t50;
+        let t200 = t100.pow2k(100) * t100;
+        (t200.pow2k(50) * t50, t11)
+    }
+
+    // self^((p - 5) / 8) = self^(2^252 - 3)
+    fn pow_p58(&self) -> Fe {
+        let (t250, _) = self.pow22501();
+        t250.pow2k(2) * *self
+    }
+
+    #[cfg(test)]
+    fn invert(&self) -> Fe {
+        // self^(p - 2) = self^(2^255 - 21)
+        let (t250, t11) = self.pow22501();
+        t250.pow2k(5) * t11
+    }
+
+    fn is_zero(&self) -> Choice {
+        self.ct_eq(&Fe::ZERO)
+    }
+
+    fn is_negative(&self) -> Choice {
+        Choice::from((self.0[0] & 1) as u8)
+    }
+
+    fn abs(&self) -> Fe {
+        let mut out = *self;
+        out.conditional_negate(self.is_negative());
+        out
+    }
+
+    // The non-negative square root of u / v, or of i u / v if u / v is not square, and whether
+    // u / v is square (RFC 9496, section 4.2).
+    fn sqrt_ratio_m1(u: &Fe, v: &Fe) -> (Choice, Fe) {
+        let v3 = v.square() * *v;
+        let v7 = v3.square() * *v;
+        let mut r = *u * v3 * (*u * v7).pow_p58();
+        let check = *v * r.square();
+        let correct_sign = check.ct_eq(u);
+        let flipped_sign = check.ct_eq(&-*u);
+        let flipped_sign_i = check.ct_eq(&(-*u * SQRT_M1));
+        r.conditional_assign(&(SQRT_M1 * r), flipped_sign | flipped_sign_i);
+        (correct_sign | flipped_sign, r.abs())
+    }
+}
+
+impl ConstantTimeEq for Fe {
+    fn ct_eq(&self, other: &Fe) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl ConditionallySelectable for Fe {
+    fn conditional_select(a: &Fe, b: &Fe, choice: Choice) -> Fe {
+        let mut out = [0u64; 4];
+        for (out, (a, b)) in out.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+            *out = u64::conditional_select(a, b, choice);
+        }
+        Fe(out)
+    }
+}
+
+impl core::ops::Add for Fe {
+    type Output = Fe;
+
+    fn add(self, rhs: Fe) -> Fe {
+        // both below p, so the sum is below 2^256
+        let mut out = [0u64; 4];
+        let mut carry = 0u128;
+        for (out, (a, b)) in out.iter_mut().zip(self.0.iter().zip(rhs.0.iter())) {
+            let sum = u128::from(*a) + u128::from(*b) + carry;
+            *out = sum as u64;
+            carry = sum >> 64;
+        }
+        Fe::reduce(out)
+    }
+}
+
+impl core::ops::Neg for Fe {
+    type Output = Fe;
+
+    fn neg(self) -> Fe {
+        // p - self, which is p itself for zero
+        let mut out = [0u64; 4];
+        let mut borrow = 0u64;
+        for i in 0..4 {
+            let (diff, b1) = P[i].overflowing_sub(self.0[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow);
+            out[i] = diff;
+            borrow = u64::from(b1 | b2);
+        }
+        Fe::reduce(out)
+    }
+}
+
+impl core::ops::Neg for &Fe {
+    type Output = Fe;
+
+    fn neg(self) -> Fe {
+        -*self
+    }
+}
+
+impl core::ops::Sub for Fe {
+    type Output = Fe;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Fe) -> Fe {
+        self + -rhs
+    }
+}
+
+impl core::ops::Mul for Fe {
+    type Output = Fe;
+
+    fn mul(self, rhs: Fe) -> Fe {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let product =
+                    u128::from(self.0[i]) * u128::from(rhs.0[j]) + u128::from(wide[i + j]) + carry;
+                wide[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+        // 2^256 = 38
+        let mut out = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let sum = u128::from(wide[i]) + 38 * u128::from(wide[i + 4]) + carry;
+            out[i] = sum as u64;
+            carry = sum >> 64;
+        }
+        let mut carry = carry * 38;
+        for limb in out.iter_mut() {
+            let sum = u128::from(*limb) + carry;
+            *limb = sum as u64;
+            carry = sum >> 64;
+        }
+        // a last carry leaves a small value, which cannot carry again
+        out[0] += (carry * 38) as u64;
+        Fe::reduce(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use super::{
+        elligator, preimages, Fe, D, DP1_OVER_DM1, INVSQRT_ONE_PLUS_D, MDOUBLE_INVSQRT_A_MINUS_D,
+        SQRT_ID, SQRT_M1,
+    };
+    use crate::{Ciphertext, DecryptionKey, RistrettoPoint, Scalar};
+
+    // Test the field arithmetic against scalars, for values below the group order where the two
+    // agree as integers.
+    #[test]
+    fn elligator_field_arithmetic() {
+        let mut rng = StdRng::seed_from_u64(495);
+        for _ in 0..100 {
+            let a: u64 = rng.gen();
+            let b: u64 = rng.gen();
+            let product = Scalar::from(a) * Scalar::from(b);
+            assert_eq!(
+                (Fe::from_u64(a) * Fe::from_u64(b)).to_bytes(),
+                product.to_bytes()
+            );
+            let x = Fe::from_bytes(&Scalar::random(&mut rng).to_bytes());
+            let y = Fe::from_bytes(&Scalar::random(&mut rng).to_bytes());
+            assert_eq!((x * y.invert() * y).to_bytes(), x.to_bytes());
+            assert_eq!((x - y + y).to_bytes(), x.to_bytes());
+            assert!(bool::from((x - x).is_zero()));
+        }
+
+        // the constants
+        let d = -Fe::from_u64(121_665) * Fe::from_u64(121_666).invert();
+        assert_eq!(D.to_bytes(), d.to_bytes());
+        assert_eq!((SQRT_M1.square() + Fe::ONE).to_bytes(), [0; 32]);
+        assert!(!bool::from(SQRT_M1.is_negative()));
+        assert_eq!(SQRT_ID.square().to_bytes(), (SQRT_M1 * D).to_bytes());
+        let dp1_over_dm1 = (D + Fe::ONE) * (D - Fe::ONE).invert();
+        assert_eq!(DP1_OVER_DM1.to_bytes(), dp1_over_dm1.to_bytes());
+        let minus_two = -Fe::from_u64(2);
+        let a_minus_d = -Fe::ONE - D;
+        assert_eq!(
+            (MDOUBLE_INVSQRT_A_MINUS_D.square() * a_minus_d).to_bytes(),
+            (minus_two * minus_two).to_bytes()
+        );
+        assert_eq!(
+            (INVSQRT_ONE_PLUS_D.square() * (Fe::ONE + D)).to_bytes(),
+            Fe::ONE.to_bytes()
+        );
+        // p itself and 2^255 - 1 reduce
+        let mut p = [0xff; 32];
+        p[0] = 0xed;
+        p[31] = 0x7f;
+        assert_eq!(Fe::from_bytes(&p).to_bytes(), [0; 32]);
+        p[0] = 0xff;
+        assert_eq!(Fe::from_bytes(&p).to_bytes(), Fe::from_u64(18).to_bytes());
+    }
+
+    // Test that the inverse finds the preimage a point was mapped from, and that every preimage
+    // it finds maps back to the point.
+    #[test]
+    fn elligator_inverse() {
+        let mut rng = StdRng::seed_from_u64(495);
+        let mut total = 0;
+        for _ in 0..50 {
+            let mut bytes = [0u8; 32];
+            rng.fill(&mut bytes);
+            let point = elligator(&bytes);
+            let f = Fe::from_bytes(&bytes).abs();
+
+            let (mask, found) = preimages(&point.compress());
+            assert!((0..8).any(|i| mask >> i & 1 == 1 && found[i].to_bytes() == f.to_bytes()));
+            for (i, g) in found.iter().enumerate() {
+                if mask >> i & 1 == 1 {
+                    assert_eq!(elligator(&g.to_bytes()), point);
+                    assert_eq!(elligator(&(-*g).to_bytes()), point);
+                    total += 1;
+                }
+            }
+        }
+        // a point reached from random bytes has between 4 and 5 preimages on average
+        assert!((150..300).contains(&total), "{}", total);
+    }
+
+    // Test that ciphertexts survive the round trip, and that about a quarter of fresh ciphertexts
+    // are represented on each try.
+    #[test]
+    fn elligator_round_trip() {
+        let mut rng = StdRng::seed_from_u64(495);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let mut represented = 0;
+        for _ in 0..200 {
+            let m = RistrettoPoint::random(&mut rng);
+            let ct = ek.encrypt(m, &mut rng);
+            if let Some(bytes) = ct.to_uniform_bytes(&mut rng) {
+                assert_eq!(Ciphertext::from_uniform_bytes(&bytes), ct);
+                represented += 1;
+            }
+        }
+        assert!((30..70).contains(&represented), "{}", represented);
+
+        for _ in 0..20 {
+            let m = RistrettoPoint::random(&mut rng);
+            let (ct, bytes) = ek.encrypt_uniform(m, &mut rng);
+            assert_eq!(Ciphertext::from_uniform_bytes(&bytes), ct);
+            assert_eq!(dk.decrypt(ct), m);
+        }
+    }
+
+    // Count the set bits at each position and the occurrences of each byte value, and check both
+    // against what uniform bytes give, well outside the expected deviation.
+    fn looks_uniform(samples: &[[u8; 64]]) -> bool {
+        let n = samples.len() as f64;
+        let mut bits = [0u32; 512];
+        let mut counts = [0u32; 256];
+        for sample in samples {
+            for (i, byte) in sample.iter().enumerate() {
+                counts[usize::from(*byte)] += 1;
+                for bit in 0..8 {
+                    bits[8 * i + bit] += u32::from(byte >> bit & 1);
+                }
+            }
+        }
+        // each bit is set about half the time: allow 6 standard deviations
+        let bits_ok = bits
+            .iter()
+            .all(|&b| (f64::from(b) - n / 2.0).abs() < 6.0 * (n / 4.0).sqrt());
+        // chi-squared over the byte values, with 255 degrees of freedom (standard deviation 22.6)
+        let expected = n * 64.0 / 256.0;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (f64::from(c) - expected).powi(2) / expected)
+            .sum();
+        bits_ok && chi2 < 255.0 + 6.0 * 22.6
+    }
+
+    // Test that the encodings pass basic uniformity checks, which plain compressed ciphertexts
+    // fail. Fresh ciphertexts are uniform in the group, so random ones stand in for encryptions,
+    // which are slow in debug builds.
+    #[test]
+    fn elligator_uniformity() {
+        let mut rng = StdRng::seed_from_u64(495);
+        let mut compressed = Vec::new();
+        let mut uniform = Vec::new();
+        while uniform.len() < 400 {
+            let ct = Ciphertext(
+                RistrettoPoint::random(&mut rng),
+                RistrettoPoint::random(&mut rng),
+            );
+            if let Some(bytes) = ct.to_uniform_bytes(&mut rng) {
+                compressed.push(ct.compress().to_bytes());
+                uniform.push(bytes);
+            }
+        }
+        assert!(looks_uniform(&uniform));
+        assert!(!looks_uniform(&compressed));
+    }
+}
@@ -0,0 +1,253 @@
+// Invertible encoding of bytes into points for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Invertible encoding of short byte strings into points, used directly by [encode_to_point] and
+//! chunk by chunk by [EncryptionKey::encrypt_bytes_chunked](crate::EncryptionKey).
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::hash::hash_to_point;
+use crate::{Ciphertext, EncryptionKey};
+
+/// The number of bytes [encode_to_point] fits into one point.
+pub const POINT_MSG_LEN: usize = 28;
+
+// Set in the metadata byte to mark the last chunk of a longer message.
+pub(crate) const FINAL_FLAG: u8 = 0x20;
+
+const CHECKSUM_DOMAIN: &[u8] = b"rust-elgamal encoding";
+// The high bit of the counter, in the metadata byte.
+const COUNTER_HIGH: u8 = 0x40;
+
+fn checksum(payload: &[u8; POINT_MSG_LEN], meta: u8) -> [u8; 2] {
+    let digest = Sha512::new()
+        .chain_update((CHECKSUM_DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(CHECKSUM_DOMAIN)
+        .chain_update([meta])
+        .chain_update(payload)
+        .finalize();
+    [digest[0], digest[1]]
+}
+
+// Encode up to `POINT_MSG_LEN` bytes into a point, with `flag` (zero or `FINAL_FLAG`) in the
+// metadata byte.
+pub(crate) fn encode(data: &[u8], flag: u8) -> RistrettoPoint {
+    debug_assert!(data.len() <= POINT_MSG_LEN && flag & !FINAL_FLAG == 0);
+
+    let mut payload = [0u8; POINT_MSG_LEN];
+    payload[..data.len()].copy_from_slice(data);
+    let meta = data.len() as u8 | flag;
+
+    let mut bytes = [0u8; 32];
+    bytes[1..29].copy_from_slice(&payload);
+    bytes[29..31].copy_from_slice(&checksum(&payload, meta));
+
+    (0..=u8::MAX)
+        .find_map(|counter| {
+            bytes[0] = counter << 1;
+            bytes[31] = meta | if counter & 0x80 != 0 { COUNTER_HIGH } else { 0 };
+            CompressedRistretto(bytes).decompress()
+        })
+        .expect("no counter value gave a valid point")
+}
+
+// Decode a point produced by `encode`, returning the zero-padded payload and the metadata byte,
+// or `None` if the point is not such an encoding.
+pub(crate) fn decode(point: &RistrettoPoint) -> Option<([u8; POINT_MSG_LEN], u8)> {
+    let bytes = point.compress().to_bytes();
+    let meta = bytes[31] & !COUNTER_HIGH;
+    let len = (meta & !FINAL_FLAG) as usize;
+    if len > POINT_MSG_LEN {
+        return None;
+    }
+
+    let mut payload = [0u8; POINT_MSG_LEN];
+    payload.copy_from_slice(&bytes[1..29]);
+    if payload[len..].iter().any(|&b| b != 0) || bytes[29..31] != checksum(&payload, meta) {
+        return None;
+    }
+    Some((payload, meta))
+}
+
+/// Encode a 28-byte message into a point, such that [decode_from_point] recovers it exactly.
+///
+/// This is how to encrypt bytes rather than points: encrypt the result with
+/// [EncryptionKey::encrypt](crate::EncryptionKey::encrypt), and decode the decrypted point.
+///
+/// A point is found by filling in a candidate 32-byte point encoding and retrying with the next
+/// value of an 8-bit counter until it decompresses. The candidate is laid out as
+///
+/// | byte  | contents                                                         |
+/// |-------|------------------------------------------------------------------|
+/// | 0     | the low 7 bits of the counter, shifted left by one               |
+/// | 1-28  | the message                                                      |
+/// | 29-30 | a checksum over the message and metadata                         |
+/// | 31    | the metadata (the length 28), with the high bit of the counter in bit 6 |
+///
+/// which keeps every candidate canonical: the low bit of byte 0 and the high bit of byte 31 are
+/// always clear. About one candidate in four decompresses, so the encoding fails for all 256
+/// counter values (and this function panics) with probability about 2^-106.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::{decode_from_point, encode_to_point};
+///
+/// let msg = *b"twenty-eight bytes of secret";
+/// let point = encode_to_point(&msg);
+/// assert_eq!(decode_from_point(&point), Some(msg));
+/// ```
+pub fn encode_to_point(msg: &[u8; POINT_MSG_LEN]) -> RistrettoPoint {
+    encode(msg, 0)
+}
+
+/// Decode a point produced by [encode_to_point], or return `None` if it is not such an encoding.
+///
+/// A random point is accepted with probability below 2^-16, by the checksum alone.
+pub fn decode_from_point(point: &RistrettoPoint) -> Option<[u8; POINT_MSG_LEN]> {
+    match decode(point)? {
+        (payload, meta) if meta as usize == POINT_MSG_LEN => Some(payload),
+        _ => None,
+    }
+}
+
+/// Hash `msg` to a point, separated by `domain`.
+///
+/// This encoding cannot be inverted, but equal messages (in the same domain) always map to equal
+/// points, which is all that plaintext equality tests and deduplication need. The point is
+/// `RistrettoPoint::hash_from_bytes::<Sha512>` of the domain's length (as 8 little-endian bytes),
+/// the domain, and the message, so messages from different domains never collide.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::encode_hashed;
+///
+/// assert_eq!(encode_hashed(b"my app", b"alice"), encode_hashed(b"my app", b"alice"));
+/// assert_ne!(encode_hashed(b"my app", b"alice"), encode_hashed(b"other app", b"alice"));
+/// ```
+pub fn encode_hashed(domain: &[u8], msg: &[u8]) -> RistrettoPoint {
+    hash_to_point(domain, msg)
+}
+
+impl EncryptionKey {
+    /// Encrypt the point [encode_hashed] maps `msg` to in `domain`.
+    pub fn encrypt_hashed<R: RngCore + CryptoRng>(
+        &self,
+        domain: &[u8],
+        msg: &[u8],
+        rng: &mut R,
+    ) -> Ciphertext {
+        self.encrypt(encode_hashed(domain, msg), rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand::RngCore;
+    use rand_core::SeedableRng;
+    use sha2::Sha512;
+
+    use crate::encoding::{decode, encode, FINAL_FLAG, POINT_MSG_LEN};
+    use crate::{decode_from_point, encode_hashed, encode_to_point, DecryptionKey, RistrettoPoint};
+
+    // Test that every length and flag round-trips.
+    #[test]
+    fn encode_decode() {
+        let mut rng = StdRng::from_entropy();
+
+        for len in 0..=POINT_MSG_LEN {
+            for flag in [0, FINAL_FLAG] {
+                let mut data = [0u8; POINT_MSG_LEN];
+                rng.fill_bytes(&mut data[..len]);
+
+                let (payload, meta) = decode(&encode(&data[..len], flag)).unwrap();
+                assert_eq!(payload, data);
+                assert_eq!(meta, len as u8 | flag);
+            }
+        }
+    }
+
+    // Test that random messages round-trip through encryption.
+    #[test]
+    fn encode_to_point_roundtrip() {
+        const N: usize = 1000;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+
+        for _ in 0..N {
+            let mut msg = [0u8; POINT_MSG_LEN];
+            rng.fill_bytes(&mut msg);
+
+            let ct = dk.encryption_key().encrypt(encode_to_point(&msg), &mut rng);
+            assert_eq!(decode_from_point(&dk.decrypt(ct)), Some(msg));
+        }
+
+        // a chunk with a flag or a short payload is not a full message
+        assert_eq!(
+            decode_from_point(&encode(&[1; POINT_MSG_LEN], FINAL_FLAG)),
+            None
+        );
+        assert_eq!(decode_from_point(&encode(&[1; 3], 0)), None);
+    }
+
+    // Test that a random point is almost never mistaken for an encoding.
+    #[test]
+    fn decode_random() {
+        let mut rng = StdRng::from_entropy();
+        for _ in 0..100 {
+            let point = RistrettoPoint::random(&mut rng);
+            assert!(decode(&point).is_none());
+            assert!(decode_from_point(&point).is_none());
+        }
+    }
+
+    // Test the hashed encoding against a known answer and against its definition.
+    #[test]
+    fn encode_hashed_known_answer() {
+        let point = encode_hashed(b"rust-elgamal test", b"hello");
+        let expected = [
+            0x18, 0xe5, 0x88, 0xa3, 0x81, 0x14, 0x28, 0x56, 0x7e, 0xaf, 0x12, 0xcd, 0x9a, 0x0c,
+            0xef, 0xec, 0x19, 0x6b, 0x89, 0x46, 0x43, 0x04, 0x1a, 0xc7, 0x05, 0xe6, 0xda, 0x22,
+            0xd5, 0x11, 0xf1, 0x49,
+        ];
+        assert_eq!(point.compress().to_bytes(), expected);
+
+        let mut input = [0u8; 30];
+        input[..8].copy_from_slice(&17u64.to_le_bytes());
+        input[8..25].copy_from_slice(b"rust-elgamal test");
+        input[25..].copy_from_slice(b"hello");
+        assert_eq!(point, RistrettoPoint::hash_from_bytes::<Sha512>(&input));
+    }
+
+    // Test that domains separate the encodings, including where a naive concatenation would not.
+    #[test]
+    fn encode_hashed_domains() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+
+        let point = encode_hashed(b"a", b"msg");
+        assert_ne!(point, encode_hashed(b"b", b"msg"));
+        assert_ne!(point, encode_hashed(b"am", b"sg"));
+        assert_ne!(point, encode_hashed(b"a", b"msh"));
+
+        let ct = dk.encryption_key().encrypt_hashed(b"a", b"msg", &mut rng);
+        assert_eq!(dk.decrypt(ct), point);
+    }
+}
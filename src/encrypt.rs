@@ -14,30 +14,56 @@
 // limitations under the License.
 
 use core::fmt::{Debug, Formatter};
+use core::iter::Sum;
+use core::ops::Add;
 
-use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
-use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
-use rand_core::{CryptoRng, RngCore};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::traits::IsIdentity;
+use ff::Field;
+#[cfg(feature = "os-rng")]
+use rand_core::OsRng;
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 #[cfg(feature = "enable-serde")]
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 
-use crate::{Ciphertext, DecryptionKey};
+use crate::backend::DebugPoint;
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, ElGamalRng, Error, Plaintext};
+
+const FINGERPRINT_DOMAIN: &[u8] = b"rust-elgamal fingerprint";
 
 /// An ElGamal encryption key (also called a public key in other implementations).
 /// To create a new encryption key, see [DecryptionKey](crate::decrypt::DecryptionKey).
-#[derive(Copy, Clone, Eq, PartialEq)]
+///
+/// Equality compares the encoded keys in constant time, through [ConstantTimeEq].
+#[derive(Copy, Clone)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
-pub struct EncryptionKey(pub(crate) RistrettoPoint);
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(
+        serialize = "G: Serialize",
+        deserialize = "G: ElGamalGroup + Deserialize<'de>"
+    ))
+)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(concrete(G = crate::ts::Point)))]
+pub struct EncryptionKey<G = RistrettoPoint>(
+    #[cfg_attr(
+        feature = "enable-serde",
+        serde(deserialize_with = "deserialize_key_point")
+    )]
+    pub(crate) G,
+);
 
-impl EncryptionKey {
+impl<G: ElGamalGroup> EncryptionKey<G> {
     /// Encrypt `mG` with a randomly-generated blinding factor, where `G` is the group generator.
     ///
     /// This is computationally intensive to decrypt to the original scalar, and not relevant to
     /// the majority of users. This function takes advantage of a fast implementation for multiple
-    /// multiplications in `curve25519-dalek`.
+    /// multiplications where the backend provides one (see
+    /// [ElGamalGroup::mul_generator_add](crate::ElGamalGroup::mul_generator_add)).
     ///
     /// # Example
     ///
@@ -53,15 +79,46 @@ impl EncryptionKey {
     /// let m = Scalar::from(5u32);
     /// let encrypted = enc_key.exp_encrypt(m, &mut rng);
     /// ```
-    pub fn exp_encrypt<R: RngCore + CryptoRng>(&self, m: Scalar, rng: &mut R) -> Ciphertext {
-        self.exp_encrypt_with(m, Scalar::random(rng))
+    pub fn exp_encrypt<R: ElGamalRng<V>, V>(&self, m: G::Scalar, rng: &mut R) -> Ciphertext<G> {
+        self.exp_encrypt_with(m, G::Scalar::random(rng.compat()))
+    }
+
+    /// Encrypt the integer or scalar `m` as `mG`, as [exp_encrypt](Self::exp_encrypt) does, for
+    /// homomorphic counters and tallies: the sum of such ciphertexts encrypts the sum of the
+    /// messages.
+    ///
+    /// Small values are recovered with [DecryptionKey::decrypt_u32].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let votes = enc_key.encrypt_scalar(1u64, &mut rng)
+    ///     + enc_key.encrypt_scalar(Scalar::from(1u32), &mut rng)
+    ///     + enc_key.encrypt_scalar(0u64, &mut rng);
+    /// assert_eq!(dec_key.decrypt_u32(votes), Some(2));
+    /// ```
+    pub fn encrypt_scalar<R: ElGamalRng<V>, V>(
+        &self,
+        m: impl Into<G::Scalar>,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        self.exp_encrypt(m.into(), rng)
     }
 
     /// Encrypt `mG` with the blinding factor `r`, where `G` is the group generator.
     ///
     /// This is computationally intensive to decrypt to the original scalar, and not relevant to
     /// the majority of users. This function takes advantage of a fast implementation for multiple
-    /// multiplications in `curve25519-dalek`.
+    /// multiplications where the backend provides one (see
+    /// [ElGamalGroup::mul_generator_add](crate::ElGamalGroup::mul_generator_add)).
     ///
     /// # Example
     ///
@@ -78,14 +135,18 @@ impl EncryptionKey {
     /// let r = Scalar::from(10u32);
     /// let encrypted = enc_key.exp_encrypt_with(m, r);
     /// ```
-    pub fn exp_encrypt_with(&self, m: Scalar, r: Scalar) -> Ciphertext {
-        let c1 = &r * RISTRETTO_BASEPOINT_TABLE;
+    pub fn exp_encrypt_with(&self, m: G::Scalar, r: G::Scalar) -> Ciphertext<G> {
+        let c1 = G::mul_generator(&r);
         // mG + rY
-        let c2 = RistrettoPoint::multiscalar_mul(&[m, r], &[RISTRETTO_BASEPOINT_POINT, self.0]);
+        let c2 = G::mul_generator_add(&m, &r, &self.0);
         Ciphertext(c1, c2)
     }
 
-    /// Encrypt the curve point `m` with a randomly-generated blinding factor.
+    /// Encrypt the message `m` with a randomly-generated blinding factor.
+    ///
+    /// `m` is usually a curve point, but can be anything convertible into a
+    /// [Plaintext](crate::Plaintext), such as an [ExponentMessage](crate::ExponentMessage) to
+    /// encrypt a scalar in the exponent.
     ///
     /// # Example
     ///
@@ -101,8 +162,40 @@ impl EncryptionKey {
     /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
     /// let encrypted = enc_key.encrypt(m, &mut rng);
     /// ```
-    pub fn encrypt<R: RngCore + CryptoRng>(&self, m: RistrettoPoint, rng: &mut R) -> Ciphertext {
-        self.encrypt_with(m, Scalar::random(rng))
+    pub fn encrypt<R: ElGamalRng<V>, V>(
+        &self,
+        m: impl Into<Plaintext<G>>,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut r = G::Scalar::random(rng.compat());
+        let ct = self.encrypt_with(m.into().to_point(), r);
+        #[cfg(feature = "zeroize")]
+        r.zeroize();
+        ct
+    }
+
+    /// Encrypt the curve point `m` with a blinding factor drawn from the operating system's
+    /// randomness source.
+    ///
+    /// This is a cryptographically safe default for callers who do not need to control the
+    /// randomness source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{DecryptionKey, GENERATOR_TABLE, Scalar};
+    ///
+    /// let dec_key = DecryptionKey::generate();
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+    /// let encrypted = enc_key.encrypt_os(m);
+    /// assert_eq!(dec_key.decrypt(encrypted), m);
+    /// ```
+    #[cfg(feature = "os-rng")]
+    pub fn encrypt_os(&self, m: impl Into<Plaintext<G>>) -> Ciphertext<G> {
+        self.encrypt(m, &mut OsRng)
     }
 
     /// Encrypt the curve point `m` with the blinding factor `r`.
@@ -122,9 +215,9 @@ impl EncryptionKey {
     /// let r = Scalar::from(10u32);
     /// let encrypted = enc_key.encrypt_with(m, r);
     /// ```
-    pub fn encrypt_with(&self, m: RistrettoPoint, r: Scalar) -> Ciphertext {
-        let c1 = &r * RISTRETTO_BASEPOINT_TABLE;
-        let c2 = m + r * &self.0;
+    pub fn encrypt_with(&self, m: G, r: G::Scalar) -> Ciphertext<G> {
+        let c1 = G::mul_generator(&r);
+        let c2 = m + self.0 * r;
         Ciphertext(c1, c2)
     }
 
@@ -148,8 +241,37 @@ impl EncryptionKey {
     /// assert_eq!(dec_key.decrypt(ct1), dec_key.decrypt(ct2));
     /// ```
     #[must_use = "the Ciphertext input is not mutated, the function returns the new rerandomised Ciphertext"]
-    pub fn rerandomise<R: RngCore + CryptoRng>(&self, ct: Ciphertext, rng: &mut R) -> Ciphertext {
-        self.rerandomise_with(ct, Scalar::random(rng))
+    pub fn rerandomise<R: ElGamalRng<V>, V>(
+        &self,
+        ct: Ciphertext<G>,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        self.rerandomise_with(ct, G::Scalar::random(rng.compat()))
+    }
+
+    /// Re-randomise the ciphertext `ct` with a blinding factor drawn from the operating system's
+    /// randomness source. This will generate a new encryption of the same curve point.
+    ///
+    /// This is a cryptographically safe default for callers who do not need to control the
+    /// randomness source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{DecryptionKey, GENERATOR_TABLE, Scalar};
+    ///
+    /// let dec_key = DecryptionKey::generate();
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+    /// let ct1 = enc_key.encrypt_os(m);
+    /// let ct2 = enc_key.rerandomise_os(ct1);
+    /// assert_eq!(dec_key.decrypt(ct1), dec_key.decrypt(ct2));
+    /// ```
+    #[cfg(feature = "os-rng")]
+    #[must_use = "the Ciphertext input is not mutated, the function returns the new rerandomised Ciphertext"]
+    pub fn rerandomise_os(&self, ct: Ciphertext<G>) -> Ciphertext<G> {
+        self.rerandomise(ct, &mut OsRng)
     }
 
     /// Re-randomise the ciphertext `ct` with the provided blinding factor.
@@ -175,35 +297,399 @@ impl EncryptionKey {
     /// assert_eq!(dec_key.decrypt(ct1), dec_key.decrypt(ct2));
     /// ```
     #[must_use = "the Ciphertext input is not mutated, the function returns the new rerandomised Ciphertext"]
-    pub fn rerandomise_with(&self, ct: Ciphertext, r: Scalar) -> Ciphertext {
-        let c1 = ct.0 + &r * RISTRETTO_BASEPOINT_TABLE;
-        let c2 = ct.1 + &self.0 * r;
+    pub fn rerandomise_with(&self, ct: Ciphertext<G>, r: G::Scalar) -> Ciphertext<G> {
+        let c1 = ct.0 + G::mul_generator(&r);
+        let c2 = ct.1 + self.0 * r;
         Ciphertext(c1, c2)
     }
+
+    /// Re-randomise the ciphertext `ct` with a randomly-generated blinding factor, returning the
+    /// new ciphertext together with the blinding factor `δ` that was added.
+    ///
+    /// `rerandomise_with(ct, δ)` reproduces the new ciphertext exactly, so `δ` can serve as the
+    /// witness of a rerandomisation proof or be kept to audit a mix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, GENERATOR_TABLE, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+    /// let ct1 = enc_key.encrypt(m, &mut rng);
+    /// let (ct2, delta) = enc_key.rerandomise_returning(ct1, &mut rng);
+    /// assert_eq!(enc_key.rerandomise_with(ct1, delta), ct2);
+    /// assert_eq!(dec_key.decrypt(ct2), m);
+    /// ```
+    pub fn rerandomise_returning<R: ElGamalRng<V>, V>(
+        &self,
+        ct: Ciphertext<G>,
+        rng: &mut R,
+    ) -> (Ciphertext<G>, G::Scalar) {
+        let delta = G::Scalar::random(rng.compat());
+        (self.rerandomise_with(ct, delta), delta)
+    }
+
+    /// Re-randomise the ciphertext `ct` in place with a randomly-generated blinding factor, and
+    /// return the blinding factor `δ` that was added.
+    ///
+    /// Afterwards `ct` equals `rerandomise_with(old_ct, δ)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, GENERATOR_TABLE, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+    /// let ct1 = enc_key.encrypt(m, &mut rng);
+    /// let mut ct2 = ct1;
+    /// let delta = enc_key.rerandomise_in_place(&mut ct2, &mut rng);
+    /// assert_eq!(enc_key.rerandomise_with(ct1, delta), ct2);
+    /// assert_eq!(dec_key.decrypt(ct2), m);
+    /// ```
+    pub fn rerandomise_in_place<R: ElGamalRng<V>, V>(
+        &self,
+        ct: &mut Ciphertext<G>,
+        rng: &mut R,
+    ) -> G::Scalar {
+        let (new_ct, delta) = self.rerandomise_returning(*ct, rng);
+        *ct = new_ct;
+        delta
+    }
+
+    /// Wrap a point made elsewhere, for example the joint key of an MPC protocol, as an
+    /// encryption key, failing with [Error::IdentityPoint](crate::Error::IdentityPoint) for the
+    /// identity, under which encryption hides nothing.
+    ///
+    /// Nothing else about the point is checked, in particular not that anyone knows its discrete
+    /// log; only wrap points from a protocol that guarantees this.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_elgamal::{EncryptionKey, Error, RistrettoPoint, Scalar, GENERATOR_TABLE};
+    /// use rust_elgamal::Identity;
+    ///
+    /// let point = &Scalar::from(42u32) * GENERATOR_TABLE;
+    /// let ek = EncryptionKey::from_point(point).unwrap();
+    /// assert_eq!(ek.as_point(), &point);
+    /// assert_eq!(
+    ///     EncryptionKey::from_point(RistrettoPoint::identity()),
+    ///     Err(Error::IdentityPoint)
+    /// );
+    /// ```
+    pub fn from_point(point: G) -> Result<Self, Error> {
+        if bool::from(point.is_identity()) {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(EncryptionKey(point))
+    }
+
+    /// The point of the key.
+    pub fn as_point(&self) -> &G {
+        &self.0
+    }
+
+    /// A 32-byte fingerprint of the key: SHA-512 of its encoding, domain-separated and truncated.
+    ///
+    /// Fingerprints are a compact way to name a key, for example to tell which key a ciphertext
+    /// was made for.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let ek = *DecryptionKey::new(&mut rng).encryption_key();
+    /// let ek2 = *DecryptionKey::new(&mut rng).encryption_key();
+    /// assert_ne!(ek.fingerprint(), ek2.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let digest = Sha512::new()
+            .chain_update((FINGERPRINT_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update(self.0.to_bytes())
+            .finalize();
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest[..32]);
+        out
+    }
 }
 
-impl Debug for EncryptionKey {
+impl EncryptionKey {
+    /// The 32-byte encoding of the encryption key: the compressed point.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, EncryptionKey};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let ek = *DecryptionKey::new(&mut rng).encryption_key();
+    /// assert_eq!(<EncryptionKey>::from_bytes(&ek.to_bytes()), Ok(ek));
+    /// ```
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+
+    /// Read an encryption key from its 32-byte encoding.
+    ///
+    /// Fails with [Error::InvalidPoint](crate::Error::InvalidPoint) if the bytes are not the
+    /// canonical encoding of a point, and with [Error::IdentityPoint](crate::Error::IdentityPoint)
+    /// for the identity.
+    ///
+    /// The `ristretto448` feature gives `EncryptionKey<DecafPoint>` a `from_bytes` of its own, so
+    /// name the default group, as in `<EncryptionKey>::from_bytes`, where both may be in use.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<EncryptionKey, Error> {
+        let point = CompressedRistretto(*bytes)
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        if point.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(EncryptionKey(point))
+    }
+}
+
+impl<G: ElGamalGroup> Debug for EncryptionKey<G> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "EncryptionKey({:?})", self.0.compress())
+        write!(f, "EncryptionKey({:?})", DebugPoint(&self.0))
+    }
+}
+
+impl<G: ElGamalGroup> ConstantTimeEq for EncryptionKey<G> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0
+            .to_bytes()
+            .as_ref()
+            .ct_eq(other.0.to_bytes().as_ref())
+    }
+}
+
+impl<G: ElGamalGroup> PartialEq for EncryptionKey<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<G: ElGamalGroup> Eq for EncryptionKey<G> {}
+
+// Arithmetic traits for key aggregation
+
+// The sum of keys is the key of the sum of their secrets. Nothing stops a party who publishes
+// its key last from choosing it to cancel the others', so aggregate only keys that come with a
+// proof of possession, such as those of `avnet` or `joint`. An empty sum is the identity.
+
+impl<G: ElGamalGroup> Add for EncryptionKey<G> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        EncryptionKey(self.0 + rhs.0)
+    }
+}
+
+impl<G: ElGamalGroup> Add for &EncryptionKey<G> {
+    type Output = EncryptionKey<G>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        EncryptionKey(self.0 + rhs.0)
+    }
+}
+
+impl<G: ElGamalGroup> Add<&EncryptionKey<G>> for EncryptionKey<G> {
+    type Output = EncryptionKey<G>;
+
+    fn add(self, rhs: &EncryptionKey<G>) -> Self::Output {
+        EncryptionKey(self.0 + rhs.0)
+    }
+}
+
+impl<G: ElGamalGroup> Add<EncryptionKey<G>> for &EncryptionKey<G> {
+    type Output = EncryptionKey<G>;
+
+    fn add(self, rhs: EncryptionKey<G>) -> Self::Output {
+        EncryptionKey(self.0 + rhs.0)
+    }
+}
+
+impl<G: ElGamalGroup> Sum for EncryptionKey<G> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(EncryptionKey(G::identity()), |acc, key| acc + key)
+    }
+}
+
+impl<'a, G: ElGamalGroup> Sum<&'a EncryptionKey<G>> for EncryptionKey<G> {
+    fn sum<I: Iterator<Item = &'a EncryptionKey<G>>>(iter: I) -> Self {
+        iter.fold(EncryptionKey(G::identity()), |acc, key| acc + key)
     }
 }
 
 // Conversion traits
 
-impl From<DecryptionKey> for EncryptionKey {
-    fn from(dk: DecryptionKey) -> Self {
+impl<G: ElGamalGroup> From<DecryptionKey<G>> for EncryptionKey<G> {
+    fn from(dk: DecryptionKey<G>) -> Self {
         dk.ek
     }
 }
 
-impl From<RistrettoPoint> for EncryptionKey {
-    fn from(y: RistrettoPoint) -> Self {
+impl<G: ElGamalGroup> From<G> for EncryptionKey<G> {
+    fn from(y: G) -> Self {
         Self(y)
     }
 }
 
-impl AsRef<RistrettoPoint> for EncryptionKey {
-    fn as_ref(&self) -> &RistrettoPoint {
+impl<G: ElGamalGroup> AsRef<G> for EncryptionKey<G> {
+    fn as_ref(&self) -> &G {
         &self.0
     }
 }
+
+// serde traits
+
+// An identity encryption key makes every encryption (and commitment) under it trivially
+// breakable, so refuse to deserialise one.
+#[cfg(feature = "enable-serde")]
+pub(crate) fn deserialize_key_point<'de, D, G>(deserializer: D) -> Result<G, D::Error>
+where
+    D: Deserializer<'de>,
+    G: ElGamalGroup + Deserialize<'de>,
+{
+    let point = G::deserialize(deserializer)?;
+    if bool::from(point.is_identity()) {
+        return Err(D::Error::custom(
+            "encryption key must not be the identity point",
+        ));
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, EncryptionKey, Error, RistrettoPoint, Scalar};
+
+    // Test that the returned blinding factor reproduces the rerandomised ciphertext, and that
+    // rerandomising leaves the plaintext unchanged.
+    #[test]
+    fn rerandomise_returning_delta() {
+        let mut rng = StdRng::seed_from_u64(481);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for _ in 0..10 {
+            let m = RistrettoPoint::random(&mut rng);
+            let ct = ek.encrypt(m, &mut rng);
+
+            let (new_ct, delta) = ek.rerandomise_returning(ct, &mut rng);
+            assert_ne!(new_ct, ct);
+            assert_eq!(ek.rerandomise_with(ct, delta), new_ct);
+            assert_eq!(dk.decrypt(new_ct), m);
+
+            let mut in_place = new_ct;
+            let delta2 = ek.rerandomise_in_place(&mut in_place, &mut rng);
+            assert_ne!(in_place, new_ct);
+            assert_eq!(ek.rerandomise_with(new_ct, delta2), in_place);
+            assert_eq!(ek.rerandomise_with(ct, delta + delta2), in_place);
+            assert_eq!(dk.decrypt(in_place), m);
+        }
+
+        // the same generator state gives the same blinding factor either way
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let (returned, delta) = ek.rerandomise_returning(ct, &mut StdRng::seed_from_u64(481));
+        let mut in_place = ct;
+        let delta2 = ek.rerandomise_in_place(&mut in_place, &mut StdRng::seed_from_u64(481));
+        assert_eq!((returned, delta), (in_place, delta2));
+        assert_eq!(
+            ek.rerandomise(ct, &mut StdRng::seed_from_u64(481)),
+            returned
+        );
+        assert_ne!(delta, Scalar::ZERO);
+    }
+
+    // Test that encryption keys round-trip through their bytes, and that non-canonical, corrupted
+    // and identity encodings are refused.
+    #[test]
+    fn encryption_key_bytes() {
+        let mut rng = StdRng::seed_from_u64(501);
+        for _ in 0..10 {
+            let ek = *DecryptionKey::new(&mut rng).encryption_key();
+            let bytes = ek.to_bytes();
+            assert_eq!(bytes, ek.0.compress().to_bytes());
+            assert_eq!(<EncryptionKey>::from_bytes(&bytes), Ok(ek));
+
+            // a flipped bit is either not a point or another key
+            for bit in [0, 9, 100, 254] {
+                let mut corrupted = bytes;
+                corrupted[bit / 8] ^= 1 << (bit % 8);
+                match <EncryptionKey>::from_bytes(&corrupted) {
+                    Ok(other) => assert_ne!(other, ek),
+                    Err(e) => assert_eq!(e, Error::InvalidPoint),
+                }
+            }
+        }
+
+        // the field modulus, a non-canonical encoding of zero
+        let mut p = [0xffu8; 32];
+        p[0] = 0xed;
+        p[31] = 0x7f;
+        assert_eq!(<EncryptionKey>::from_bytes(&p), Err(Error::InvalidPoint));
+        // canonical field elements that encode no point, and the top bit set
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(<EncryptionKey>::from_bytes(&one), Err(Error::InvalidPoint));
+        assert_eq!(
+            <EncryptionKey>::from_bytes(&[0xff; 32]),
+            Err(Error::InvalidPoint)
+        );
+        assert_eq!(
+            <EncryptionKey>::from_bytes(&[0u8; 32]),
+            Err(Error::IdentityPoint)
+        );
+    }
+
+    // Test that serialising and deserialising an encryption key produces an unchanged result.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_encryption_key() {
+        const N: usize = 100;
+
+        let mut rng = StdRng::from_entropy();
+
+        for _ in 0..N {
+            let ek = *DecryptionKey::new(&mut rng).encryption_key();
+            let encoded = bincode::serialize(&ek).unwrap();
+            assert_eq!(encoded.len(), 32);
+
+            let decoded: EncryptionKey = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(ek, decoded);
+        }
+    }
+
+    // Test that an identity encryption key is rejected on deserialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_rejects_identity_key() {
+        // The identity point compresses to 32 zero bytes.
+        let malicious = [0u8; 32];
+        let err = bincode::deserialize::<EncryptionKey>(&malicious).unwrap_err();
+        assert!(err.to_string().contains("identity"));
+    }
+}
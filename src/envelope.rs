@@ -0,0 +1,294 @@
+// Ciphertext envelopes for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[cfg(feature = "enable-serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Ciphertext, CompressedCiphertext, DecryptionKey, EncryptionKey, Error};
+
+/// The major version of the envelope format written by this crate. Envelopes with a different
+/// major version are rejected.
+pub const ENVELOPE_VERSION_MAJOR: u8 = 1;
+
+/// The minor version of the envelope format written by this crate. Envelopes with a later minor
+/// version are still read; any fields they append after the ciphertext are skipped.
+pub const ENVELOPE_VERSION_MINOR: u8 = 0;
+
+const MAX_LABEL_LEN: usize = u16::MAX as usize;
+
+/// A ciphertext labelled with the fingerprint of the key it was made for, the format version and
+/// an optional context label, so that it cannot be decrypted with the wrong key by mistake.
+///
+/// The byte encoding is the major and minor version (one byte each), the 32-byte
+/// [fingerprint](crate::EncryptionKey::fingerprint) of the key, the length of the label as two
+/// big-endian bytes and the label in UTF-8, and finally the 64-byte ciphertext. With the
+/// `enable-serde` feature, envelopes serialise as these bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CiphertextEnvelope, DecryptionKey, Error, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let old_key = DecryptionKey::new(&mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+/// let envelope = CiphertextEnvelope::seal(dec_key.encryption_key(), ct).with_label("ballots");
+///
+/// assert_eq!(envelope.open(&old_key), Err(Error::KeyMismatch));
+/// assert_eq!(dec_key.decrypt(envelope.open(&dec_key).unwrap()), m);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CiphertextEnvelope {
+    minor: u8,
+    fingerprint: [u8; 32],
+    label: Option<String>,
+    ct: Ciphertext,
+}
+
+impl CiphertextEnvelope {
+    /// Wrap `ct`, made under `ek`, in an envelope with no label.
+    pub fn seal(ek: &EncryptionKey, ct: Ciphertext) -> CiphertextEnvelope {
+        CiphertextEnvelope {
+            minor: ENVELOPE_VERSION_MINOR,
+            fingerprint: ek.fingerprint(),
+            label: None,
+            ct,
+        }
+    }
+
+    /// Attach a context label, truncated to 65535 bytes if it is longer.
+    pub fn with_label(mut self, label: &str) -> CiphertextEnvelope {
+        let mut end = label.len().min(MAX_LABEL_LEN);
+        while !label.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.label = Some(label[..end].to_string());
+        self
+    }
+
+    /// Take the ciphertext out of the envelope, for decryption with `dk`.
+    ///
+    /// Fails with [Error::KeyMismatch](crate::Error::KeyMismatch) if the envelope was sealed for
+    /// a different key.
+    pub fn open(&self, dk: &DecryptionKey) -> Result<Ciphertext, Error> {
+        if dk.encryption_key().fingerprint() != self.fingerprint {
+            return Err(Error::KeyMismatch);
+        }
+        Ok(self.ct)
+    }
+
+    /// The fingerprint of the key the envelope was sealed for.
+    pub fn fingerprint(&self) -> &[u8; 32] {
+        &self.fingerprint
+    }
+
+    /// The context label, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The format version the envelope was written with, as `(major, minor)`.
+    pub fn version(&self) -> (u8, u8) {
+        (ENVELOPE_VERSION_MAJOR, self.minor)
+    }
+
+    /// Encode the envelope. Envelopes read from a later minor version are written back as the
+    /// current version, without the fields this crate does not know.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let label = self.label.as_deref().unwrap_or("").as_bytes();
+        let mut bytes = Vec::with_capacity(2 + 32 + 2 + label.len() + 64);
+        bytes.push(ENVELOPE_VERSION_MAJOR);
+        bytes.push(ENVELOPE_VERSION_MINOR);
+        bytes.extend_from_slice(&self.fingerprint);
+        bytes.extend_from_slice(&(label.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(label);
+        bytes.extend_from_slice(&self.ct.compress().to_bytes());
+        bytes
+    }
+
+    /// Decode an envelope.
+    ///
+    /// Fails with [Error::UnsupportedVersion](crate::Error::UnsupportedVersion) if the major
+    /// version is not [ENVELOPE_VERSION_MAJOR], with
+    /// [Error::LengthMismatch](crate::Error::LengthMismatch) if the bytes are too short (or too
+    /// long, for the current minor version), and with
+    /// [Error::InvalidPoint](crate::Error::InvalidPoint) if the ciphertext does not decode.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CiphertextEnvelope, Error> {
+        const FIXED_LEN: usize = 2 + 32 + 2 + 64;
+        if bytes.len() < 2 {
+            return Err(Error::LengthMismatch {
+                expected: FIXED_LEN,
+                got: bytes.len(),
+            });
+        }
+        let (major, minor) = (bytes[0], bytes[1]);
+        if major != ENVELOPE_VERSION_MAJOR {
+            return Err(Error::UnsupportedVersion);
+        }
+        if bytes.len() < FIXED_LEN {
+            return Err(Error::LengthMismatch {
+                expected: FIXED_LEN,
+                got: bytes.len(),
+            });
+        }
+
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&bytes[2..34]);
+        let label_len = u16::from_be_bytes([bytes[34], bytes[35]]) as usize;
+        let expected = FIXED_LEN + label_len;
+        // later minor versions may append fields, which are skipped
+        if bytes.len() < expected || (minor == ENVELOPE_VERSION_MINOR && bytes.len() != expected) {
+            return Err(Error::LengthMismatch {
+                expected,
+                got: bytes.len(),
+            });
+        }
+        let label = match label_len {
+            0 => None,
+            _ => Some(
+                core::str::from_utf8(&bytes[36..36 + label_len])
+                    .map_err(|_| Error::InvalidEncoding)?
+                    .to_string(),
+            ),
+        };
+        let mut ct = [0u8; 64];
+        ct.copy_from_slice(&bytes[36 + label_len..expected]);
+        let ct = CompressedCiphertext::from_bytes(&ct).decompress()?;
+
+        Ok(CiphertextEnvelope {
+            minor,
+            fingerprint,
+            label,
+            ct,
+        })
+    }
+}
+
+// serde traits
+
+#[cfg(feature = "enable-serde")]
+impl Serialize for CiphertextEnvelope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "enable-serde")]
+impl<'de> Deserialize<'de> for CiphertextEnvelope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: Vec<u8> = serde::de::Deserialize::deserialize(deserializer)?;
+        CiphertextEnvelope::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{CiphertextEnvelope, DecryptionKey, Error, RistrettoPoint};
+
+    // Test that envelopes round-trip and refuse to open for the wrong key.
+    #[test]
+    fn envelope_wrong_key() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = dk.encryption_key().encrypt(m, &mut rng);
+        for envelope in [
+            CiphertextEnvelope::seal(dk.encryption_key(), ct),
+            CiphertextEnvelope::seal(dk.encryption_key(), ct).with_label("epoch 7"),
+        ] {
+            let decoded = CiphertextEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+            assert_eq!(decoded, envelope);
+            assert_eq!(decoded.open(&dk), Ok(ct));
+            assert_eq!(decoded.open(&other), Err(Error::KeyMismatch));
+        }
+
+        let long = "é".repeat(40_000);
+        let envelope = CiphertextEnvelope::seal(dk.encryption_key(), ct).with_label(&long);
+        assert_eq!(envelope.label().unwrap().len(), 65534);
+        let decoded = CiphertextEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(decoded.label(), envelope.label());
+    }
+
+    // Test that a later minor version is read with its extra fields skipped, while a different
+    // major version and malformed lengths are rejected.
+    #[test]
+    fn envelope_versions() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ct = dk
+            .encryption_key()
+            .encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let envelope = CiphertextEnvelope::seal(dk.encryption_key(), ct).with_label("ctx");
+        let bytes = envelope.to_bytes();
+        assert_eq!(envelope.version(), (1, 0));
+
+        let mut newer = bytes.clone();
+        newer[1] = 3;
+        newer.extend_from_slice(b"some future field");
+        let decoded = CiphertextEnvelope::from_bytes(&newer).unwrap();
+        assert_eq!(decoded.version(), (1, 3));
+        assert_eq!(decoded.label(), Some("ctx"));
+        assert_eq!(decoded.open(&dk), Ok(ct));
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            CiphertextEnvelope::from_bytes(&trailing),
+            Err(Error::LengthMismatch {
+                expected: bytes.len(),
+                got: bytes.len() + 1
+            })
+        );
+        assert!(CiphertextEnvelope::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut major = bytes.clone();
+        major[0] = 2;
+        assert_eq!(
+            CiphertextEnvelope::from_bytes(&major),
+            Err(Error::UnsupportedVersion)
+        );
+    }
+
+    // Test that envelopes serialise compactly as their byte encoding.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_envelope() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ct = dk
+            .encryption_key()
+            .encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let envelope = CiphertextEnvelope::seal(dk.encryption_key(), ct);
+
+        let encoded = bincode::serialize(&envelope).unwrap();
+        assert_eq!(encoded.len(), 8 + 100); // bincode's length prefix, then the bytes
+        let decoded: CiphertextEnvelope = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, envelope);
+    }
+}
@@ -0,0 +1,293 @@
+// Forward-secure decryption keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error, Plaintext};
+
+const CHAIN_DOMAIN: &[u8] = b"rust-elgamal epoch chain";
+const KEY_DOMAIN: &[u8] = b"rust-elgamal epoch key";
+
+/// A decryption key that evolves from epoch to epoch, so that stealing it does not expose
+/// ciphertexts of earlier epochs.
+///
+/// The key holds a 32-byte state `s_e`, from which the epoch's secret is derived by hashing.
+/// [evolve](Self::evolve) replaces the state with a one-way hash of itself, overwriting the old
+/// state in place, so later states cannot be wound back to earlier epochs. Senders encrypt to the
+/// per-epoch keys published in [EpochEncryptionKeys], which are computed once when the key is
+/// created.
+///
+/// A single root [EncryptionKey] from which anyone could derive every epoch's key would have to be
+/// related to the epoch keys by public tweaks, and then any one epoch secret would reveal all the
+/// others; forward security from one short public key needs pairings, which ristretto255 lacks.
+///
+/// The key is deliberately not `Clone`, as a copy would keep old epochs alive.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{Error, EvolvingDecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let (mut dec_key, enc_keys) = EvolvingDecryptionKey::new(12, &mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = enc_keys.encrypt_for_epoch(0, m, &mut rng).unwrap();
+/// assert_eq!(dec_key.decrypt(&ct), Ok(m));
+///
+/// dec_key.evolve();
+/// assert_eq!(dec_key.decrypt(&ct), Err(Error::Expired));
+/// ```
+pub struct EvolvingDecryptionKey {
+    state: [u8; 32],
+    epoch: u64,
+    epochs: u64,
+}
+
+/// The public encryption keys of every epoch of an [EvolvingDecryptionKey].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EpochEncryptionKeys {
+    keys: Vec<EncryptionKey>,
+}
+
+/// A ciphertext for one epoch of an [EvolvingDecryptionKey].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EpochCiphertext {
+    pub epoch: u64,
+    pub ciphertext: Ciphertext,
+}
+
+impl EvolvingDecryptionKey {
+    /// Generate a key for `epochs` epochs, starting at epoch 0, and the public keys of all of
+    /// them. Takes `epochs` hashes and scalar multiplications.
+    pub fn new<R: RngCore + CryptoRng>(
+        epochs: u64,
+        rng: &mut R,
+    ) -> (EvolvingDecryptionKey, EpochEncryptionKeys) {
+        let mut state = [0u8; 32];
+        rng.fill_bytes(&mut state);
+
+        let mut keys = Vec::with_capacity(epochs as usize);
+        let mut s = state;
+        for _ in 0..epochs {
+            keys.push(*epoch_key(&s).encryption_key());
+            s = next_state(&s);
+        }
+
+        let dk = EvolvingDecryptionKey {
+            state,
+            epoch: 0,
+            epochs,
+        };
+        (dk, EpochEncryptionKeys { keys })
+    }
+
+    /// The current epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The encryption key of the current epoch.
+    pub fn encryption_key(&self) -> EncryptionKey {
+        *epoch_key(&self.state).encryption_key()
+    }
+
+    /// Move to the next epoch, overwriting the state of the current one.
+    pub fn evolve(&mut self) {
+        self.state = next_state(&self.state);
+        self.epoch += 1;
+    }
+
+    /// Decrypt a ciphertext for the current epoch or a later one.
+    ///
+    /// Fails with [Error::Expired](crate::Error::Expired) if the key has already evolved past the
+    /// ciphertext's epoch, and with [Error::InvalidParameters](crate::Error::InvalidParameters) if
+    /// the epoch is beyond the last one.
+    pub fn decrypt(&self, ct: &EpochCiphertext) -> Result<RistrettoPoint, Error> {
+        if ct.epoch < self.epoch {
+            return Err(Error::Expired);
+        }
+        if ct.epoch >= self.epochs {
+            return Err(Error::InvalidParameters);
+        }
+        let mut s = self.state;
+        for _ in self.epoch..ct.epoch {
+            s = next_state(&s);
+        }
+        Ok(epoch_key(&s).decrypt(ct.ciphertext))
+    }
+}
+
+impl Debug for EvolvingDecryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "EvolvingDecryptionKey {{ epoch: {}, epochs: {} }}",
+            self.epoch, self.epochs
+        )
+    }
+}
+
+impl EpochEncryptionKeys {
+    /// The number of epochs.
+    pub fn epochs(&self) -> u64 {
+        self.keys.len() as u64
+    }
+
+    /// The encryption key of `epoch`, if there is one.
+    pub fn key(&self, epoch: u64) -> Option<&EncryptionKey> {
+        self.keys.get(usize::try_from(epoch).ok()?)
+    }
+
+    /// Encrypt `m` for `epoch`.
+    ///
+    /// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) if the epoch is
+    /// beyond the last one.
+    pub fn encrypt_for_epoch<R: RngCore + CryptoRng>(
+        &self,
+        epoch: u64,
+        m: impl Into<Plaintext>,
+        rng: &mut R,
+    ) -> Result<EpochCiphertext, Error> {
+        let ek = self.key(epoch).ok_or(Error::InvalidParameters)?;
+        Ok(EpochCiphertext {
+            epoch,
+            ciphertext: ek.encrypt(m, rng),
+        })
+    }
+}
+
+fn next_state(state: &[u8; 32]) -> [u8; 32] {
+    let digest = Sha512::new()
+        .chain_update((CHAIN_DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(CHAIN_DOMAIN)
+        .chain_update(state)
+        .finalize();
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&digest[..32]);
+    out
+}
+
+fn epoch_key(state: &[u8; 32]) -> DecryptionKey {
+    DecryptionKey::from(Scalar::from_hash(
+        Sha512::new()
+            .chain_update((KEY_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(KEY_DOMAIN)
+            .chain_update(state),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Error, EvolvingDecryptionKey, RistrettoPoint};
+
+    // Test that each epoch decrypts its own ciphertexts and later ones, and not earlier ones.
+    #[test]
+    fn evolve_epochs() {
+        let mut rng = StdRng::seed_from_u64(466);
+        let (mut dk, eks) = EvolvingDecryptionKey::new(5, &mut rng);
+        assert_eq!(eks.epochs(), 5);
+
+        let messages: Vec<_> = (0..5).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let cts: Vec<_> = messages
+            .iter()
+            .enumerate()
+            .map(|(e, m)| eks.encrypt_for_epoch(e as u64, *m, &mut rng).unwrap())
+            .collect();
+
+        for epoch in 0..5 {
+            assert_eq!(dk.epoch(), epoch);
+            assert_eq!(&dk.encryption_key(), eks.key(epoch).unwrap());
+            for (e, (ct, m)) in cts.iter().zip(&messages).enumerate() {
+                if (e as u64) < epoch {
+                    assert_eq!(dk.decrypt(ct), Err(Error::Expired));
+                } else {
+                    assert_eq!(dk.decrypt(ct), Ok(*m));
+                }
+            }
+            dk.evolve();
+        }
+
+        // past the last epoch, nothing decrypts
+        assert_eq!(dk.decrypt(&cts[4]), Err(Error::Expired));
+    }
+
+    // Test that the epoch keys are distinct, that evolving changes the state, and that epochs out
+    // of range are rejected.
+    #[test]
+    fn evolve_keys() {
+        let mut rng = StdRng::seed_from_u64(466);
+        let (mut dk, eks) = EvolvingDecryptionKey::new(3, &mut rng);
+        assert_ne!(eks.key(0), eks.key(1));
+        assert_ne!(eks.key(1), eks.key(2));
+        assert_eq!(eks.key(3), None);
+
+        let before = dk.state;
+        dk.evolve();
+        assert_ne!(dk.state, before);
+        assert_eq!(
+            format!("{:?}", dk),
+            "EvolvingDecryptionKey { epoch: 1, epochs: 3 }"
+        );
+
+        let m = RistrettoPoint::random(&mut rng);
+        assert_eq!(
+            eks.encrypt_for_epoch(3, m, &mut rng),
+            Err(Error::InvalidParameters)
+        );
+        let mut ct = eks.encrypt_for_epoch(2, m, &mut rng).unwrap();
+        ct.epoch = 3;
+        assert_eq!(dk.decrypt(&ct), Err(Error::InvalidParameters));
+        // a ciphertext relabelled with another epoch decrypts to garbage
+        ct.epoch = 1;
+        assert_ne!(dk.decrypt(&ct), Ok(m));
+    }
+
+    // Test that the public keys and ciphertexts survive serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_epoch() {
+        use crate::{EpochCiphertext, EpochEncryptionKeys};
+
+        let mut rng = StdRng::seed_from_u64(466);
+        let (dk, eks) = EvolvingDecryptionKey::new(4, &mut rng);
+        let eks: EpochEncryptionKeys =
+            bincode::deserialize(&bincode::serialize(&eks).unwrap()).unwrap();
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = eks.encrypt_for_epoch(2, m, &mut rng).unwrap();
+        let ct: EpochCiphertext = bincode::deserialize(&bincode::serialize(&ct).unwrap()).unwrap();
+        assert_eq!(dk.decrypt(&ct), Ok(m));
+    }
+}
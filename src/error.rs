@@ -0,0 +1,186 @@
+// Error definitions for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Display, Formatter};
+
+/// Errors returned by the fallible APIs of this crate.
+///
+/// New variants may be added as the crate grows, so matches on this type need a wildcard arm.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The bytes do not encode a valid ristretto255 point.
+    InvalidPoint,
+    /// A key or ciphertext component is the identity point where that would be insecure.
+    IdentityPoint,
+    /// The bytes do not encode a canonical scalar.
+    InvalidScalar,
+    /// The input has the wrong length.
+    LengthMismatch { expected: usize, got: usize },
+    /// The objects were produced under different keys.
+    KeyMismatch,
+    /// A commitment, proof or checksum failed to verify.
+    VerificationFailed,
+    /// Fewer shares were provided than the threshold requires.
+    InsufficientShares { threshold: usize, got: usize },
+    /// The human-readable part of a bech32 string is invalid.
+    InvalidHrp,
+    /// The bech32 string mixes upper- and lower-case characters.
+    MixedCase,
+    /// A checksum, such as that of a bech32 string or a tracker code, does not match.
+    InvalidChecksum,
+    /// The string is not a well-formed bech32 string.
+    InvalidEncoding,
+    /// The decrypted value lies outside the range that was searched.
+    OutOfRange,
+    /// The parameters passed to a constructor are not valid together.
+    InvalidParameters,
+    /// The JSON does not have the expected shape.
+    InvalidJson,
+    /// The bytes are not a well-formed byte tree.
+    InvalidByteTree,
+    /// The data is encoded for a group this crate does not implement.
+    UnsupportedGroup,
+    /// A discrete log table is truncated, or fails its checksum or consistency checks.
+    CorruptTable,
+    /// The name is already in use.
+    DuplicateName,
+    /// The key is already present under another name.
+    DuplicateKey,
+    /// The key is not one of the known keys.
+    UnknownKey,
+    /// The data was written with a format version this crate cannot read.
+    UnsupportedVersion,
+    /// The bytes are not the expected DER structure.
+    InvalidDer,
+    /// The statement is past its expiry time, or the key has evolved past the epoch.
+    Expired,
+    /// The statement was made for a different context.
+    ContextMismatch,
+    /// The objects were made under different system parameters.
+    ParamsMismatch,
+    /// A context has used up its sequence of nonces.
+    MessageLimitReached,
+    /// The data is not a well-formed archive.
+    InvalidArchive,
+    /// The data declares a size larger than the reader accepts.
+    TooLarge,
+    /// Two shares or points have the same index.
+    DuplicateIndex,
+    /// The indices are not a permutation: one is out of range or repeated.
+    InvalidPermutation,
+    /// The message is too long to encode.
+    MessageTooLong,
+    /// The messages of the commitments do not sum to zero.
+    Unbalanced,
+    /// The share from the participant with this index does not verify.
+    InvalidShare { index: u32 },
+    /// The text is not a well-formed ASCII-armored block.
+    InvalidArmor,
+    /// The armored block holds a different kind of key than the one being parsed.
+    ArmorLabelMismatch {
+        expected: &'static str,
+        got: &'static str,
+    },
+    /// Reading or writing a stream failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidPoint => write!(f, "invalid ristretto255 point"),
+            Error::IdentityPoint => write!(f, "unexpected identity point"),
+            Error::InvalidScalar => write!(f, "non-canonical scalar"),
+            Error::LengthMismatch { expected, got } => {
+                write!(f, "length mismatch: expected {}, got {}", expected, got)
+            }
+            Error::KeyMismatch => write!(f, "objects were produced under different keys"),
+            Error::VerificationFailed => write!(f, "verification failed"),
+            Error::InsufficientShares { threshold, got } => {
+                write!(f, "insufficient shares: need {}, got {}", threshold, got)
+            }
+            Error::InvalidHrp => write!(f, "invalid bech32 human-readable part"),
+            Error::MixedCase => write!(f, "bech32 string mixes upper and lower case"),
+            Error::InvalidChecksum => write!(f, "invalid checksum"),
+            Error::InvalidEncoding => write!(f, "malformed bech32 string"),
+            Error::OutOfRange => write!(f, "decrypted value out of range"),
+            Error::InvalidParameters => write!(f, "invalid parameters"),
+            Error::InvalidJson => write!(f, "malformed JSON"),
+            Error::InvalidByteTree => write!(f, "malformed byte tree"),
+            Error::UnsupportedGroup => write!(f, "unsupported group"),
+            Error::CorruptTable => write!(f, "corrupt or truncated table"),
+            Error::DuplicateName => write!(f, "name is already in use"),
+            Error::DuplicateKey => write!(f, "key is already present"),
+            Error::UnknownKey => write!(f, "unknown key"),
+            Error::UnsupportedVersion => write!(f, "unsupported format version"),
+            Error::InvalidDer => write!(f, "malformed DER"),
+            Error::Expired => write!(f, "expired"),
+            Error::ContextMismatch => write!(f, "context mismatch"),
+            Error::ParamsMismatch => write!(f, "parameters mismatch"),
+            Error::MessageLimitReached => write!(f, "message limit reached"),
+            Error::InvalidArchive => write!(f, "malformed or truncated archive"),
+            Error::TooLarge => write!(f, "declared size exceeds the limit"),
+            Error::DuplicateIndex => write!(f, "duplicate index"),
+            Error::InvalidPermutation => write!(f, "not a permutation"),
+            Error::MessageTooLong => write!(f, "message too long to encode"),
+            Error::Unbalanced => write!(f, "commitments do not balance"),
+            Error::InvalidShare { index } => {
+                write!(f, "invalid share from participant {}", index)
+            }
+            Error::InvalidArmor => write!(f, "malformed armored text"),
+            Error::ArmorLabelMismatch { expected, got } => {
+                write!(f, "expected a {} block, got a {} block", expected, got)
+            }
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "I/O error: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.kind())
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+
+    use crate::Error;
+
+    // Test that errors carry their details into the message and work as `std::error::Error`.
+    #[test]
+    fn error_display() {
+        let err = Error::LengthMismatch {
+            expected: 32,
+            got: 31,
+        };
+        assert_eq!(err.to_string(), "length mismatch: expected 32, got 31");
+
+        let boxed: Box<dyn std::error::Error> = Box::new(Error::InsufficientShares {
+            threshold: 3,
+            got: 2,
+        });
+        assert_eq!(boxed.to_string(), "insufficient shares: need 3, got 2");
+    }
+}
@@ -0,0 +1,275 @@
+// Verifiable escrow of decryption keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ballot::ZeroOneProof;
+use crate::{
+    Ciphertext, DecryptionKey, DleqProof, EncryptionKey, Error, Identity, GENERATOR_POINT,
+};
+
+/// The number of bits escrowed: every scalar is less than `2^253`.
+const BITS: usize = 253;
+
+/// A decryption key encrypted to a recovery authority, bit by bit.
+///
+/// Bit `i` of the secret `x` is exponentially encrypted to the authority's key, so the authority
+/// can [recover](Self::recover) `x` by decrypting each bit, while an [EscrowProof] shows anyone
+/// that the escrow really holds the secret of a given encryption key.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, Escrow};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let authority = DecryptionKey::new(&mut rng);
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let (escrow, proof) = Escrow::create(&dec_key, authority.encryption_key(), &mut rng);
+/// assert!(proof
+///     .verify(&escrow, dec_key.encryption_key(), authority.encryption_key())
+///     .is_ok());
+/// assert_eq!(escrow.recover(&authority).unwrap(), dec_key);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Escrow {
+    bits: Vec<Ciphertext>,
+}
+
+/// A proof that an [Escrow] holds the secret of an encryption key, encrypted to an authority.
+///
+/// It consists of a proof per bit that the bit's ciphertext encrypts 0 or 1, and a Chaum-Pedersen
+/// proof that the weighted sum `sum_i 2^i C_i` of the bit ciphertexts encrypts the key itself, so
+/// the bits are the binary digits of its secret.
+/// The bit proofs are bound to the escrowed encryption key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EscrowProof {
+    bits: Vec<ZeroOneProof>,
+    sum: DleqProof,
+}
+
+impl Escrow {
+    /// Escrow `dk` to the authority holding the decryption key of `escrow_key`, with a proof that
+    /// the escrow holds `dk`.
+    pub fn create<R: RngCore + CryptoRng>(
+        dk: &DecryptionKey,
+        escrow_key: &EncryptionKey,
+        rng: &mut R,
+    ) -> (Escrow, EscrowProof) {
+        let secret = dk.secret.as_bytes();
+        let context = dk.encryption_key().to_bytes();
+        let mut bits = Vec::with_capacity(BITS);
+        let mut proofs = Vec::with_capacity(BITS);
+        let mut r_sum = Scalar::ZERO;
+        let mut weight = Scalar::ONE;
+        for i in 0..BITS {
+            let bit = (secret[i / 8] >> (i % 8)) & 1 == 1;
+            let r = Scalar::random(rng);
+            let ct = escrow_key.exp_encrypt_with(Scalar::from(bit as u64), r);
+            proofs.push(ZeroOneProof::prove(escrow_key, &ct, bit, &r, &context, rng));
+            bits.push(ct);
+            r_sum += weight * r;
+            weight += weight;
+        }
+
+        // the weighted sum is (RG, X + RA), so prove log_G(RG) = log_A(X + RA - X)
+        let sum = weighted_sum(&bits);
+        let sum = DleqProof::prove(
+            &DecryptionKey::from(r_sum),
+            &escrow_key.0,
+            &(sum.1 - dk.encryption_key().0),
+            rng,
+        );
+        (Escrow { bits }, EscrowProof { bits: proofs, sum })
+    }
+
+    /// Recover the escrowed decryption key with the authority's decryption key.
+    ///
+    /// Fails with [Error::OutOfRange](crate::Error::OutOfRange) if a bit does not decrypt to 0 or
+    /// 1, which cannot happen for an escrow whose [EscrowProof] verifies under `authority_dk`'s
+    /// key, and with [Error::LengthMismatch](crate::Error::LengthMismatch) if the escrow has the
+    /// wrong number of bits.
+    pub fn recover(&self, authority_dk: &DecryptionKey) -> Result<DecryptionKey, Error> {
+        check_len(self.bits.len())?;
+        let mut secret = [0u8; 32];
+        for (i, ct) in self.bits.iter().enumerate() {
+            let m = authority_dk.decrypt(*ct);
+            if m == GENERATOR_POINT {
+                secret[i / 8] |= 1 << (i % 8);
+            } else if !m.is_identity() {
+                return Err(Error::OutOfRange);
+            }
+        }
+        // a dishonest escrow may hold x + l, which still verifies, so reduce
+        Ok(DecryptionKey::from(Scalar::from_bytes_mod_order(secret)))
+    }
+
+    /// The encryptions of the bits of the secret, least significant first.
+    pub fn ciphertexts(&self) -> &[Ciphertext] {
+        &self.bits
+    }
+}
+
+impl EscrowProof {
+    /// Verify that `escrow` holds the secret of `subject`, encrypted to `escrow_key`.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if the escrow or proof has
+    /// the wrong number of bits, and with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if the proof does not hold.
+    pub fn verify(
+        &self,
+        escrow: &Escrow,
+        subject: &EncryptionKey,
+        escrow_key: &EncryptionKey,
+    ) -> Result<(), Error> {
+        check_len(escrow.bits.len())?;
+        check_len(self.bits.len())?;
+        let context = subject.to_bytes();
+        for (ct, proof) in escrow.bits.iter().zip(&self.bits) {
+            if !proof.verify(escrow_key, ct, &context) {
+                return Err(Error::VerificationFailed);
+            }
+        }
+        let sum = weighted_sum(&escrow.bits);
+        self.sum
+            .verify(&sum.0.into(), &escrow_key.0, &(sum.1 - subject.0))
+    }
+}
+
+fn check_len(len: usize) -> Result<(), Error> {
+    if len != BITS {
+        return Err(Error::LengthMismatch {
+            expected: BITS,
+            got: len,
+        });
+    }
+    Ok(())
+}
+
+// sum_i 2^i C_i, by Horner's rule from the most significant bit
+fn weighted_sum(bits: &[Ciphertext]) -> Ciphertext {
+    bits.iter()
+        .rev()
+        .fold(Ciphertext::identity(), |acc, ct| acc + acc + ct)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, Error, Escrow, Scalar};
+
+    // Test that an escrow verifies for its key and recovers it exactly.
+    #[test]
+    fn escrow_recover() {
+        let mut rng = StdRng::seed_from_u64(464);
+        let authority = DecryptionKey::new(&mut rng);
+        let ek = authority.encryption_key();
+
+        // the largest scalar, so every escrowed bit is used
+        let dk = DecryptionKey::from(-Scalar::ONE);
+        let (escrow, proof) = Escrow::create(&dk, ek, &mut rng);
+        assert_eq!(escrow.ciphertexts().len(), 253);
+        assert_eq!(proof.verify(&escrow, dk.encryption_key(), ek), Ok(()));
+        let recovered = escrow.recover(&authority).unwrap();
+        assert_eq!(recovered, dk);
+        assert_eq!(recovered.as_ref().as_bytes(), dk.as_ref().as_bytes());
+    }
+
+    // Test that an escrow does not verify for another key, under another authority, or with a
+    // tampered bit.
+    #[test]
+    fn escrow_wrong_key() {
+        let mut rng = StdRng::seed_from_u64(464);
+        let authority = DecryptionKey::new(&mut rng);
+        let ek = authority.encryption_key();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let (escrow, proof) = Escrow::create(&dk, ek, &mut rng);
+        assert_eq!(
+            proof.verify(&escrow, other.encryption_key(), ek),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            proof.verify(&escrow, dk.encryption_key(), other.encryption_key()),
+            Err(Error::VerificationFailed)
+        );
+
+        // flipping a bit by adding an encryption of G breaks the bit proof
+        let mut tampered = escrow.clone();
+        tampered.bits[5] = tampered.bits[5] + ek.exp_encrypt(Scalar::ONE, &mut rng);
+        assert_eq!(
+            proof.verify(&tampered, dk.encryption_key(), ek),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut truncated = escrow.clone();
+        truncated.bits.pop();
+        assert_eq!(
+            proof.verify(&truncated, dk.encryption_key(), ek),
+            Err(Error::LengthMismatch {
+                expected: 253,
+                got: 252
+            })
+        );
+        assert_eq!(
+            truncated.recover(&authority).unwrap_err(),
+            Error::LengthMismatch {
+                expected: 253,
+                got: 252
+            }
+        );
+
+        // a bit that does not decrypt to 0 or 1 cannot be recovered
+        let mut garbled = escrow;
+        garbled.bits[0] = ek.exp_encrypt(Scalar::from(2u32), &mut rng);
+        assert_eq!(garbled.recover(&authority).unwrap_err(), Error::OutOfRange);
+    }
+
+    // Test that escrows and proofs survive serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_escrow() {
+        use crate::EscrowProof;
+
+        let mut rng = StdRng::seed_from_u64(464);
+        let authority = DecryptionKey::new(&mut rng);
+        let dk = DecryptionKey::new(&mut rng);
+        let (escrow, proof) = Escrow::create(&dk, authority.encryption_key(), &mut rng);
+
+        let escrow: Escrow = bincode::deserialize(&bincode::serialize(&escrow).unwrap()).unwrap();
+        let proof: EscrowProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(
+            proof.verify(&escrow, dk.encryption_key(), authority.encryption_key()),
+            Ok(())
+        );
+        assert_eq!(escrow.recover(&authority).unwrap(), dk);
+    }
+}
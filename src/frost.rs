@@ -0,0 +1,624 @@
+// FROST threshold Schnorr signatures for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Two-round threshold Schnorr signing with [KeyShare]s, following FROST(ristretto255, SHA-512)
+//! of RFC 9591. The signature is an ordinary [SchnorrSignature] under the group key, checked with
+//! [EncryptionKey::verify_signature]; the key is never reconstructed.
+//!
+//! 1. Each signer calls [commit] and sends the [SigningCommitments] to the coordinator, keeping the
+//!    [SigningNonces].
+//! 2. The coordinator gathers the commitments of at least a threshold of signers and the message
+//!    into a [SigningPackage], and sends it to each of them.
+//! 3. Each signer calls [sign] with its nonces, which are used up, and returns the
+//!    [SignatureShare].
+//! 4. The coordinator combines the shares with [Aggregator::aggregate], which checks the
+//!    signature and, if it does not verify, names the signer whose share is wrong.
+//!
+//! The commitments, package and shares are public, and serializable with the `enable-serde`
+//! feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::frost::{commit, sign, Aggregator, SigningPackage};
+//! use rust_elgamal::DecryptionKey;
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let shares = dec_key.split(2, 3, &mut rng);
+//!
+//! // signers 1 and 3
+//! let signers = [&shares[0], &shares[2]];
+//! let (nonces, commitments): (Vec<_>, Vec<_>) =
+//!     signers.iter().map(|share| commit(share, &mut rng)).unzip();
+//! let package = SigningPackage::new(commitments, b"tally").unwrap();
+//! let signature_shares: Vec<_> = signers
+//!     .iter()
+//!     .zip(nonces)
+//!     .map(|(share, nonces)| sign(share, nonces, &package).unwrap())
+//!     .collect();
+//!
+//! let verification_shares: Vec<_> = shares
+//!     .iter()
+//!     .map(|share| (share.index(), share.verification_share()))
+//!     .collect();
+//! let aggregator = Aggregator::new(*dec_key.encryption_key(), 2, &verification_shares);
+//! let signature = aggregator.aggregate(&package, &signature_shares).unwrap();
+//! assert_eq!(dec_key.encryption_key().verify_signature(b"tally", &signature), Ok(()));
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Digest;
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::schnorr::{challenge, hash};
+use crate::{
+    lagrange_coefficients, EncryptionKey, Error, KeyShare, SchnorrSignature, GENERATOR_TABLE,
+};
+
+/// A signer's secret nonces for one signing session, from [commit] and used up by [sign].
+///
+/// Signing twice with the same nonces would reveal the signer's share, so they are neither
+/// `Copy` nor `Clone`. With the `zeroize` feature, they are wiped when dropped.
+pub struct SigningNonces {
+    hiding: Scalar,
+    binding: Scalar,
+    commitments: SigningCommitments,
+}
+
+impl Debug for SigningNonces {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SigningNonces {{ .. }}")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for SigningNonces {
+    fn zeroize(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SigningNonces {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SigningNonces {}
+
+/// A signer's public commitments `(D, E)` to its nonces, sent to the coordinator in the first
+/// round.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SigningCommitments {
+    index: u32,
+    hiding: RistrettoPoint,
+    binding: RistrettoPoint,
+}
+
+impl SigningCommitments {
+    /// The index of the signer's key share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// The message and the commitments of the signers taking part, sent by the coordinator to each
+/// of them in the second round.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SigningPackage {
+    commitments: Vec<SigningCommitments>,
+    message: Vec<u8>,
+}
+
+impl SigningPackage {
+    /// Gather the signers' commitments and the message to sign.
+    ///
+    /// Fails with [Error::DuplicateIndex] if two commitments are from the same signer.
+    pub fn new(
+        mut commitments: Vec<SigningCommitments>,
+        message: &[u8],
+    ) -> Result<SigningPackage, Error> {
+        commitments.sort_by_key(|c| c.index);
+        let package = SigningPackage {
+            commitments,
+            message: message.to_vec(),
+        };
+        package.check()?;
+        Ok(package)
+    }
+
+    /// The commitments, ordered by signer.
+    pub fn commitments(&self) -> &[SigningCommitments] {
+        &self.commitments
+    }
+
+    /// The message to sign.
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    // A deserialized package need not have come from `new`.
+    fn check(&self) -> Result<(), Error> {
+        for pair in self.commitments.windows(2) {
+            if pair[0].index >= pair[1].index {
+                return Err(Error::DuplicateIndex);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A signer's share `z_i` of the signature, returned to the coordinator in the second round.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SignatureShare {
+    index: u32,
+    z: Scalar,
+}
+
+impl SignatureShare {
+    /// The index of the signer's key share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+// What every party derives from a package: per signer, its commitments, binding factor and
+// Lagrange coefficient, then the group commitment and the challenge.
+struct Session {
+    signers: Vec<(SigningCommitments, Scalar, Scalar)>,
+    challenge: Scalar,
+    r: RistrettoPoint,
+}
+
+fn session(
+    package: &SigningPackage,
+    group_key: &EncryptionKey,
+    threshold: usize,
+) -> Result<Session, Error> {
+    package.check()?;
+    let commitments = &package.commitments;
+    if commitments.len() < threshold {
+        return Err(Error::InsufficientShares {
+            threshold,
+            got: commitments.len(),
+        });
+    }
+
+    // binding factors H1(Y || H4(msg) || H5(commitment list) || i)
+    let msg_hash = hash(b"msg", &[&package.message]).finalize();
+    let mut list = hash(b"com", &[]);
+    for c in commitments {
+        list.update(Scalar::from(c.index).as_bytes());
+        list.update(c.hiding.compress().as_bytes());
+        list.update(c.binding.compress().as_bytes());
+    }
+    let list_hash = list.finalize();
+    let key = group_key.0.compress();
+
+    let xs: Vec<_> = commitments.iter().map(|c| Scalar::from(c.index)).collect();
+    let lambdas = lagrange_coefficients(&xs, Scalar::ZERO)?;
+    let mut r = RistrettoPoint::default();
+    let mut signers = Vec::with_capacity(commitments.len());
+    for ((c, x), lambda) in commitments.iter().zip(&xs).zip(lambdas) {
+        let rho = Scalar::from_hash(hash(
+            b"rho",
+            &[key.as_bytes(), &msg_hash, &list_hash, x.as_bytes()],
+        ));
+        r += c.hiding + c.binding * rho;
+        signers.push((*c, rho, lambda));
+    }
+    Ok(Session {
+        signers,
+        challenge: challenge(&r, group_key, &package.message),
+        r,
+    })
+}
+
+// H3(random bytes || secret), as RFC 9591 draws nonces, so a weak generator alone does not leak
+// the share.
+fn nonce<R: RngCore + CryptoRng>(secret: &Scalar, rng: &mut R) -> Scalar {
+    let mut random = [0u8; 32];
+    rng.fill_bytes(&mut random);
+    Scalar::from_hash(hash(b"nonce", &[&random, secret.as_bytes()]))
+}
+
+/// The first round for a signer: draw the nonces for one signing session, and the commitments to
+/// send to the coordinator.
+pub fn commit<R: RngCore + CryptoRng>(
+    share: &KeyShare,
+    rng: &mut R,
+) -> (SigningNonces, SigningCommitments) {
+    let hiding = nonce(&share.secret, rng);
+    let binding = nonce(&share.secret, rng);
+    let commitments = SigningCommitments {
+        index: share.index,
+        hiding: &hiding * GENERATOR_TABLE,
+        binding: &binding * GENERATOR_TABLE,
+    };
+    let nonces = SigningNonces {
+        hiding,
+        binding,
+        commitments,
+    };
+    (nonces, commitments)
+}
+
+/// The second round for a signer: sign the package's message, using up the nonces from [commit].
+///
+/// Fails with [Error::VerificationFailed] if the package does not hold the commitments made
+/// with `nonces`, with [Error::InsufficientShares] if it has fewer signers than the threshold, and
+/// with [Error::DuplicateIndex] if two of its commitments are from the same signer.
+pub fn sign(
+    share: &KeyShare,
+    nonces: SigningNonces,
+    package: &SigningPackage,
+) -> Result<SignatureShare, Error> {
+    let session = session(package, &share.group_key, share.threshold)?;
+    let (_, rho, lambda) = session
+        .signers
+        .iter()
+        .find(|(c, _, _)| c.index == share.index)
+        .filter(|(c, _, _)| *c == nonces.commitments)
+        .ok_or(Error::VerificationFailed)?;
+    let z = nonces.hiding + nonces.binding * rho + lambda * share.secret * session.challenge;
+    Ok(SignatureShare {
+        index: share.index,
+        z,
+    })
+}
+
+/// The coordinator's view of the key: the group key, the threshold and each signer's
+/// [verification share](KeyShare::verification_share), against which it checks signature
+/// shares.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Aggregator {
+    group_key: EncryptionKey,
+    threshold: usize,
+    verification_shares: Vec<(u32, RistrettoPoint)>,
+}
+
+impl Aggregator {
+    /// An aggregator for shares of `group_key` with the given threshold, and the verification
+    /// share of each signer by index.
+    pub fn new(
+        group_key: EncryptionKey,
+        threshold: usize,
+        verification_shares: &[(u32, RistrettoPoint)],
+    ) -> Aggregator {
+        Aggregator {
+            group_key,
+            threshold,
+            verification_shares: verification_shares.to_vec(),
+        }
+    }
+
+    /// Combine the signature shares of the signers in `package` into a signature on its message,
+    /// checking it under the group key.
+    ///
+    /// Fails with [Error::InvalidShare] naming the signer with the lowest index whose share is
+    /// wrong; use [invalid_shares](Self::invalid_shares) to find them all. Also fails with
+    /// [Error::LengthMismatch] if there is not one share per signer, with [Error::UnknownKey] if a
+    /// share is from a signer outside the package or without a verification share, with
+    /// [Error::DuplicateIndex] if two shares or commitments are from the same signer, and with
+    /// [Error::InsufficientShares] if the package has fewer signers than the threshold.
+    pub fn aggregate(
+        &self,
+        package: &SigningPackage,
+        shares: &[SignatureShare],
+    ) -> Result<SchnorrSignature, Error> {
+        let session = session(package, &self.group_key, self.threshold)?;
+        let shares = self.match_shares(&session, shares)?;
+        let signature = SchnorrSignature {
+            r: session.r,
+            z: shares.iter().map(|share| share.z).sum(),
+        };
+        if self
+            .group_key
+            .verify_signature(&package.message, &signature)
+            .is_ok()
+        {
+            return Ok(signature);
+        }
+        for (signer, share) in session.signers.iter().zip(&shares) {
+            if !self.share_is_valid(&session, signer, share)? {
+                return Err(Error::InvalidShare { index: share.index });
+            }
+        }
+        Err(Error::VerificationFailed)
+    }
+
+    /// The indices of the signers whose signature shares are wrong, in order.
+    ///
+    /// Fails as [aggregate](Self::aggregate) does when the shares do not match the package.
+    pub fn invalid_shares(
+        &self,
+        package: &SigningPackage,
+        shares: &[SignatureShare],
+    ) -> Result<Vec<u32>, Error> {
+        let session = session(package, &self.group_key, self.threshold)?;
+        let shares = self.match_shares(&session, shares)?;
+        let mut invalid = Vec::new();
+        for (signer, share) in session.signers.iter().zip(&shares) {
+            if !self.share_is_valid(&session, signer, share)? {
+                invalid.push(share.index);
+            }
+        }
+        Ok(invalid)
+    }
+
+    // The shares in the order of the signers, one each.
+    fn match_shares(
+        &self,
+        session: &Session,
+        shares: &[SignatureShare],
+    ) -> Result<Vec<SignatureShare>, Error> {
+        if shares.len() != session.signers.len() {
+            return Err(Error::LengthMismatch {
+                expected: session.signers.len(),
+                got: shares.len(),
+            });
+        }
+        let mut sorted = shares.to_vec();
+        sorted.sort_by_key(|share| share.index);
+        if sorted.windows(2).any(|pair| pair[0].index == pair[1].index) {
+            return Err(Error::DuplicateIndex);
+        }
+        // as many shares as signers, all different, so this makes them match one to one
+        for share in &sorted {
+            if !session
+                .signers
+                .iter()
+                .any(|(c, _, _)| c.index == share.index)
+            {
+                return Err(Error::UnknownKey);
+            }
+        }
+        Ok(sorted)
+    }
+
+    // z_i G = D_i + rho_i E_i + c lambda_i Y_i
+    fn share_is_valid(
+        &self,
+        session: &Session,
+        signer: &(SigningCommitments, Scalar, Scalar),
+        share: &SignatureShare,
+    ) -> Result<bool, Error> {
+        let (commitments, rho, lambda) = signer;
+        let (_, y) = self
+            .verification_shares
+            .iter()
+            .find(|(index, _)| *index == share.index)
+            .ok_or(Error::UnknownKey)?;
+        let expected =
+            commitments.hiding + commitments.binding * rho + y * (session.challenge * lambda);
+        Ok(&share.z * GENERATOR_TABLE == expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{commit, sign, Aggregator, SignatureShare, SigningPackage};
+    use crate::{DecryptionKey, Error, KeyShare, Scalar};
+
+    fn aggregator(shares: &[KeyShare]) -> Aggregator {
+        let verification_shares: Vec<_> = shares
+            .iter()
+            .map(|share| (share.index(), share.verification_share()))
+            .collect();
+        Aggregator::new(
+            *shares[0].group_key(),
+            shares[0].threshold(),
+            &verification_shares,
+        )
+    }
+
+    // Run a signing session with the given signers, returning the package and the shares.
+    fn run(
+        signers: &[&KeyShare],
+        msg: &[u8],
+        rng: &mut StdRng,
+    ) -> (SigningPackage, Vec<SignatureShare>) {
+        let (nonces, commitments): (Vec<_>, Vec<_>) =
+            signers.iter().map(|share| commit(share, rng)).unzip();
+        let package = SigningPackage::new(commitments, msg).unwrap();
+        let shares = signers
+            .iter()
+            .zip(nonces)
+            .map(|(share, nonces)| sign(share, nonces, &package).unwrap())
+            .collect();
+        (package, shares)
+    }
+
+    // Test a 3-of-5 session with several signer sets, including more than the threshold.
+    #[test]
+    fn frost_3_of_5() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let shares = dk.split(3, 5, &mut rng);
+        let aggregator = aggregator(&shares);
+
+        let subsets: [&[usize]; 3] = [&[0, 2, 4], &[3, 1, 2], &[0, 1, 2, 3, 4]];
+        for subset in &subsets {
+            let signers: Vec<_> = subset.iter().map(|&i| &shares[i]).collect();
+            let (package, signature_shares) = run(&signers, b"final tally", &mut rng);
+            let signature = aggregator.aggregate(&package, &signature_shares).unwrap();
+            assert_eq!(ek.verify_signature(b"final tally", &signature), Ok(()));
+            assert_eq!(
+                ek.verify_signature(b"another tally", &signature),
+                Err(Error::VerificationFailed)
+            );
+            assert_eq!(
+                aggregator.invalid_shares(&package, &signature_shares),
+                Ok(Vec::new())
+            );
+        }
+    }
+
+    // Test that a corrupted share is pinpointed, and that other malformed sessions are refused.
+    #[test]
+    fn frost_pinpoints_corrupted_share() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let shares = dk.split(3, 5, &mut rng);
+        let aggregator = aggregator(&shares);
+        let signers = [&shares[0], &shares[2], &shares[3]];
+        let (package, mut signature_shares) = run(&signers, b"final tally", &mut rng);
+
+        signature_shares[1].z += Scalar::ONE;
+        assert_eq!(
+            aggregator.aggregate(&package, &signature_shares),
+            Err(Error::InvalidShare { index: 3 })
+        );
+        signature_shares[2].z = Scalar::ZERO;
+        assert_eq!(
+            aggregator.invalid_shares(&package, &signature_shares),
+            Ok(vec![3, 4])
+        );
+
+        // a share signed by the wrong key share, claiming to be signer 3's
+        let mut impostor = shares[2].clone();
+        impostor.secret = shares[1].secret;
+        let (nonces, commitments) = commit(&impostor, &mut rng);
+        let (others, mut all): (Vec<_>, Vec<_>) = [&shares[0], &shares[3]]
+            .iter()
+            .map(|s| commit(s, &mut rng))
+            .unzip();
+        all.push(commitments);
+        let package = SigningPackage::new(all, b"final tally").unwrap();
+        let forged = sign(&impostor, nonces, &package).unwrap();
+        let honest: Vec<_> = [&shares[0], &shares[3]]
+            .iter()
+            .zip(others)
+            .map(|(s, n)| sign(s, n, &package).unwrap())
+            .collect();
+        let all_shares = [honest[0], forged, honest[1]];
+        assert_eq!(
+            aggregator.aggregate(&package, &all_shares),
+            Err(Error::InvalidShare { index: 3 })
+        );
+
+        // too few signers, missing, duplicated and foreign shares
+        let (nonces, c0) = commit(&shares[0], &mut rng);
+        let (_, c1) = commit(&shares[1], &mut rng);
+        let small = SigningPackage::new(vec![c0, c1], b"x").unwrap();
+        let too_few = Error::InsufficientShares {
+            threshold: 3,
+            got: 2,
+        };
+        assert_eq!(sign(&shares[0], nonces, &small), Err(too_few));
+        assert_eq!(aggregator.aggregate(&small, &[]), Err(too_few));
+        let (package, signature_shares) = run(&signers, b"final tally", &mut rng);
+        assert_eq!(
+            aggregator.aggregate(&package, &signature_shares[..2]),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        let twice = [
+            signature_shares[0],
+            signature_shares[0],
+            signature_shares[1],
+        ];
+        assert_eq!(
+            aggregator.aggregate(&package, &twice),
+            Err(Error::DuplicateIndex)
+        );
+        let mut foreign = signature_shares.clone();
+        foreign[0].index = 2;
+        assert_eq!(
+            aggregator.aggregate(&package, &foreign),
+            Err(Error::UnknownKey)
+        );
+    }
+
+    // Test that a signer refuses a package without its own commitments, and duplicate signers.
+    #[test]
+    fn frost_signer_checks_package() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let shares = dk.split(2, 3, &mut rng);
+        let (nonces0, c0) = commit(&shares[0], &mut rng);
+        let (_, c1) = commit(&shares[1], &mut rng);
+        let (_, c0_again) = commit(&shares[0], &mut rng);
+        let (_, c2) = commit(&shares[2], &mut rng);
+
+        assert_eq!(
+            SigningPackage::new(vec![c0, c0_again], b"x"),
+            Err(Error::DuplicateIndex)
+        );
+        let package = SigningPackage::new(vec![c2, c1], b"x").unwrap();
+        assert_eq!(
+            sign(&shares[0], nonces0, &package),
+            Err(Error::VerificationFailed)
+        );
+        let (nonces0, _) = commit(&shares[0], &mut rng);
+        let stale = SigningPackage::new(vec![c0, c1], b"x").unwrap();
+        assert_eq!(
+            sign(&shares[0], nonces0, &stale),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that the messages of a session survive serialization.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn frost_serde() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let shares = dk.split(2, 3, &mut rng);
+        let (package, signature_shares) = run(&[&shares[0], &shares[1]], b"x", &mut rng);
+
+        let package: SigningPackage =
+            bincode::deserialize(&bincode::serialize(&package).unwrap()).unwrap();
+        let signature_shares: Vec<SignatureShare> =
+            bincode::deserialize(&bincode::serialize(&signature_shares).unwrap()).unwrap();
+        let aggregator: Aggregator =
+            bincode::deserialize(&bincode::serialize(&aggregator(&shares)).unwrap()).unwrap();
+        let signature = aggregator.aggregate(&package, &signature_shares).unwrap();
+        let signature: crate::SchnorrSignature =
+            bincode::deserialize(&bincode::serialize(&signature).unwrap()).unwrap();
+        assert_eq!(
+            dk.encryption_key().verify_signature(b"x", &signature),
+            Ok(())
+        );
+    }
+}
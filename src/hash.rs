@@ -0,0 +1,61 @@
+// Hashing helpers for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+
+// Derive a 32-byte key from a shared point and any points it should be bound to, separated by
+// `domain`.
+pub(crate) fn kdf(domain: &[u8], points: &[&RistrettoPoint]) -> [u8; 32] {
+    let mut hasher = Sha512::new()
+        .chain_update((domain.len() as u64).to_le_bytes())
+        .chain_update(domain);
+    for point in points {
+        hasher.update(point.compress().as_bytes());
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize()[..32]);
+    key
+}
+
+pub(crate) fn xor32(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (o, (x, y)) in out.iter_mut().zip(a.iter().zip(b)) {
+        *o = x ^ y;
+    }
+    out
+}
+
+// Hash `data` to a point, separated by `domain`.
+pub(crate) fn hash_to_point(domain: &[u8], data: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_hash(
+        Sha512::new()
+            .chain_update((domain.len() as u64).to_le_bytes())
+            .chain_update(domain)
+            .chain_update(data),
+    )
+}
+
+// Hash a list of points to a scalar, separated by `domain`; used for Fiat-Shamir challenges.
+pub(crate) fn hash_points_to_scalar(domain: &[u8], points: &[&RistrettoPoint]) -> Scalar {
+    let mut hasher = Sha512::new()
+        .chain_update((domain.len() as u64).to_le_bytes())
+        .chain_update(domain);
+    for point in points {
+        hasher.update(point.compress().as_bytes());
+    }
+    Scalar::from_hash(hasher)
+}
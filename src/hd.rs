@@ -0,0 +1,174 @@
+// Hierarchical key derivation for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+
+use crate::{DecryptionKey, EncryptionKey, GENERATOR_TABLE};
+
+const CHILD_DOMAIN: &[u8] = b"rust-elgamal child key";
+
+impl DecryptionKey {
+    /// Derive the child key number `index` under `chain_code`, as in BIP32's non-hardened
+    /// derivation: the child secret is `x + H(chain_code || index || Y)`, for this key's secret
+    /// `x` and encryption key `Y`.
+    ///
+    /// The hash is SHA-512 under a domain of its own, reduced modulo the group order, and is
+    /// pinned by known-answer tests. Whoever holds the encryption key and the chain code can
+    /// derive the child encryption keys with [EncryptionKey::derive_child], and, as with BIP32,
+    /// anyone who learns a child secret as well recovers this key's secret, so keep the chain
+    /// code private where child secrets may leak.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let master = DecryptionKey::new(&mut rng);
+    /// let chain_code = b"sessions";
+    ///
+    /// let child = master.derive_child(7, chain_code);
+    /// assert_eq!(
+    ///     child.encryption_key(),
+    ///     &master.encryption_key().derive_child(7, chain_code)
+    /// );
+    /// ```
+    pub fn derive_child(&self, index: u32, chain_code: &[u8]) -> DecryptionKey {
+        DecryptionKey::from(self.secret + tweak(&self.ek.0, index, chain_code))
+    }
+}
+
+impl EncryptionKey {
+    /// Derive the encryption key of the child key number `index` under `chain_code`, without
+    /// the secret: `Y + H(chain_code || index || Y) G`. See [DecryptionKey::derive_child].
+    pub fn derive_child(&self, index: u32, chain_code: &[u8]) -> EncryptionKey {
+        EncryptionKey(self.0 + &tweak(&self.0, index, chain_code) * GENERATOR_TABLE)
+    }
+}
+
+fn tweak(key: &RistrettoPoint, index: u32, chain_code: &[u8]) -> Scalar {
+    Scalar::from_hash(
+        Sha512::new()
+            .chain_update((CHILD_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(CHILD_DOMAIN)
+            .chain_update((chain_code.len() as u64).to_le_bytes())
+            .chain_update(chain_code)
+            .chain_update(index.to_le_bytes())
+            .chain_update(key.compress().as_bytes()),
+    )
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, RistrettoPoint};
+
+    // Test that the public derivation matches the secret one for many indices, that children
+    // differ by index and chain code, and that a child decrypts what was encrypted to its key.
+    #[test]
+    fn derive_child_matches_public() {
+        let mut rng = StdRng::seed_from_u64(511);
+        let master = DecryptionKey::new(&mut rng);
+        let ek = master.encryption_key();
+
+        let children: Vec<_> = (0..64u32)
+            .chain([1 << 31, u32::MAX])
+            .map(|i| master.derive_child(i, b"chain"))
+            .collect();
+        for (child, i) in children.iter().zip((0..64u32).chain([1 << 31, u32::MAX])) {
+            assert_eq!(child.encryption_key(), &ek.derive_child(i, b"chain"));
+            assert_ne!(child, &master);
+        }
+        for (a, b) in children.iter().zip(&children[1..]) {
+            assert_ne!(a, b);
+        }
+        assert_ne!(
+            master.derive_child(0, b"chain"),
+            master.derive_child(0, b"chain2")
+        );
+        assert_ne!(master.derive_child(0, b""), master.derive_child(0, b"\0"));
+
+        let grandchild = children[3].derive_child(5, b"chain");
+        assert_eq!(
+            grandchild.encryption_key(),
+            &ek.derive_child(3, b"chain").derive_child(5, b"chain")
+        );
+
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = ek.derive_child(9, b"chain").encrypt(m, &mut rng);
+        assert_eq!(children[9].decrypt(ct), m);
+        assert_ne!(master.decrypt(ct), m);
+    }
+
+    // Known answers for derive_child from the key with seed [0; 32], for index 0 and 1 under the
+    // chain code "chain" and index u32::MAX under the empty chain code: the child secret, checked
+    // against SHA-512 reduced modulo the group order computed independently, and the child key.
+    // These must never change, or derived keys would no longer match.
+    const CHILD_KATS: [(u32, &[u8], &str, &str); 3] = [
+        (
+            0,
+            b"chain",
+            "3655d75027be592220e42645e7577010bd5ff17186ad9fb25dd02f1da55b5202",
+            "7c6ddaf148d84d721f10e4a555d2e480426ea0c0ddba1e4586bba52b92f15728",
+        ),
+        (
+            1,
+            b"chain",
+            "62d3af8b61a6a965b2db4fa7cb7f7697804ec625b1f3b07e908eefeed61fbe0f",
+            "bcd0c0cdf444a88674f38389827b3b14fb568657bb7e1c2b086bd431d4a9e907",
+        ),
+        (
+            u32::MAX,
+            b"",
+            "82c8dc8839c934e3829e2074166b33f0cb32e73befc6ffc084bb00503c73e603",
+            "1ea610d0b1a0cc8dfb48959e928d0c84efb64804f4186d17ba159ef63d0d7e7a",
+        ),
+    ];
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    // Test that derived keys, secret and public, match the known answers.
+    #[test]
+    fn derive_child_kat() {
+        let master = DecryptionKey::from_seed([0u8; 32]);
+        for (index, chain_code, secret, ek) in CHILD_KATS.iter() {
+            let child = master.derive_child(*index, chain_code);
+            assert_eq!(child.to_bytes(), from_hex(secret));
+            assert_eq!(child.encryption_key().to_bytes(), from_hex(ek));
+            assert_eq!(
+                master
+                    .encryption_key()
+                    .derive_child(*index, chain_code)
+                    .to_bytes(),
+                from_hex(ek)
+            );
+        }
+    }
+}
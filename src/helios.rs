@@ -0,0 +1,643 @@
+// Helios-style JSON for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ciphertexts and proofs in the JSON shape of Helios-style election verifiers.
+//!
+//! Helios writes a ciphertext as `{"alpha": .., "beta": ..}` and every proof as
+//! `{"commitment": {"A": .., "B": ..}, "challenge": .., "response": ..}`; a disjunctive proof is
+//! a list of these, one per allowed plaintext. Group elements are written as the hex encoding of
+//! the compressed point, while challenges and responses are decimal integers as in Helios.
+//!
+//! Only the layout is borrowed: Helios itself works in a mod-p group, so neither its documents
+//! nor its proofs can be read or checked here. Challenges are SHA-1 of a domain label followed
+//! by the whole statement and then the commitments, as written in the JSON and joined with
+//! commas, read as a big-endian integer. The statement is the generator, the encryption key, the
+//! ciphertext, and the allowed plaintexts or the claimed decryption factor, so that a prover
+//! cannot pick the challenge first and then solve for a statement that fits it. This crate's
+//! [ZeroOneProof](crate::ballot::ZeroOneProof) and [DleqProof](crate::DleqProof) use other
+//! transcripts, so they do not verify here and cannot be converted; proofs in this format must
+//! be made with [DisjunctiveProof::prove] and [DecryptionProof::prove].
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::Write;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error, GENERATOR_POINT, GENERATOR_TABLE};
+
+const DISJUNCTIVE_DOMAIN: &str = "rust-elgamal helios disjunctive proof";
+const DECRYPTION_DOMAIN: &str = "rust-elgamal helios decryption proof";
+
+/// Conversion to and from Helios-style JSON.
+pub trait HeliosJson: Sized {
+    /// Write `self` as Helios-style JSON.
+    fn to_helios_json(&self) -> String;
+
+    /// Read a value written by [to_helios_json](Self::to_helios_json) or by a Helios-style
+    /// implementation.
+    ///
+    /// Fails with [Error::InvalidJson](crate::Error::InvalidJson) if the JSON does not have the
+    /// expected shape, and with the usual point and scalar errors if a field does not decode.
+    fn from_helios_json(json: &str) -> Result<Self, Error>;
+}
+
+/// One Chaum-Pedersen proof in Helios's shape: the commitments `A` and `B`, the challenge and
+/// the response.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ZkProof {
+    pub commitment: (RistrettoPoint, RistrettoPoint),
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+/// A proof that an exponential ciphertext encrypts one of `0, 1, ..., max`, with one [ZkProof]
+/// per allowed value, as Helios attaches to each answer of a ballot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisjunctiveProof(pub Vec<ZkProof>);
+
+/// A proof that a decryption factor `x * alpha` was computed with the secret `x` of the
+/// encryption key, as Helios attaches to each trustee's partial decryption.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DecryptionProof(pub ZkProof);
+
+impl DisjunctiveProof {
+    /// Prove that `ct = ek.exp_encrypt_with(Scalar::from(value), r)` encrypts one of
+    /// `0, 1, ..., max`.
+    ///
+    /// The proof is only meaningful if `ct` really is that encryption and `value <= max`;
+    /// otherwise it will not verify.
+    pub fn prove<R: RngCore + CryptoRng>(
+        ek: &EncryptionKey,
+        ct: &Ciphertext,
+        value: u64,
+        max: u64,
+        r: &Scalar,
+        rng: &mut R,
+    ) -> DisjunctiveProof {
+        let identity = (RistrettoPoint::identity(), RistrettoPoint::identity());
+        let mut proofs: Vec<ZkProof> = (0..=max)
+            .map(|_| ZkProof {
+                commitment: identity,
+                challenge: Scalar::ZERO,
+                response: Scalar::ZERO,
+            })
+            .collect();
+
+        // simulate every branch but the true one, and commit honestly to that
+        let mut fake_sum = Scalar::ZERO;
+        for (j, proof) in proofs.iter_mut().enumerate() {
+            if j as u64 == value {
+                continue;
+            }
+            proof.challenge = Scalar::random(rng);
+            proof.response = Scalar::random(rng);
+            proof.commitment = branch(ek, ct, j as u64, &proof.challenge, &proof.response);
+            fake_sum += proof.challenge;
+        }
+        let w = Scalar::random(rng);
+        let real = (value as usize).min(proofs.len() - 1);
+        proofs[real].commitment = (&w * GENERATOR_TABLE, ek.0 * w);
+
+        let commitments: Vec<_> = proofs.iter().map(|proof| proof.commitment).collect();
+        let statement = disjunctive_statement(ek, ct, max);
+        let challenge = challenge(DISJUNCTIVE_DOMAIN, &statement, &commitments) - fake_sum;
+        proofs[real].challenge = challenge;
+        proofs[real].response = w + challenge * r;
+        DisjunctiveProof(proofs)
+    }
+
+    /// Verify that `ct` encrypts one of `0, 1, ..., max` under `ek`.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) if the proof does not
+    /// have `max + 1` branches, and with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify(&self, ek: &EncryptionKey, ct: &Ciphertext, max: u64) -> Result<(), Error> {
+        let expected = max as usize + 1;
+        if self.0.len() != expected {
+            return Err(Error::LengthMismatch {
+                expected,
+                got: self.0.len(),
+            });
+        }
+
+        let mut sum = Scalar::ZERO;
+        for (j, proof) in self.0.iter().enumerate() {
+            if branch(ek, ct, j as u64, &proof.challenge, &proof.response) != proof.commitment {
+                return Err(Error::VerificationFailed);
+            }
+            sum += proof.challenge;
+        }
+
+        let commitments: Vec<_> = self.0.iter().map(|proof| proof.commitment).collect();
+        let statement = disjunctive_statement(ek, ct, max);
+        if challenge(DISJUNCTIVE_DOMAIN, &statement, &commitments) != sum {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+impl DecryptionProof {
+    /// Compute the decryption factor `x * alpha` of `ct` for the secret `x` of `dk`, with a proof
+    /// that it is correct.
+    pub fn prove<R: RngCore + CryptoRng>(
+        dk: &DecryptionKey,
+        ct: &Ciphertext,
+        rng: &mut R,
+    ) -> (RistrettoPoint, DecryptionProof) {
+        let factor = ct.0 * dk.secret;
+        let w = Scalar::random(rng);
+        let commitment = (&w * GENERATOR_TABLE, ct.0 * w);
+        let statement = decryption_statement(dk.encryption_key(), ct, &factor);
+        let challenge = challenge(DECRYPTION_DOMAIN, &statement, &[commitment]);
+        let proof = ZkProof {
+            commitment,
+            challenge,
+            response: w + challenge * dk.secret,
+        };
+        (factor, DecryptionProof(proof))
+    }
+
+    /// Verify that `factor` is `x * alpha` for the `alpha` of `ct`, where `ek = x * G`.
+    ///
+    /// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if it is not.
+    pub fn verify(
+        &self,
+        ek: &EncryptionKey,
+        ct: &Ciphertext,
+        factor: &RistrettoPoint,
+    ) -> Result<(), Error> {
+        let ZkProof {
+            commitment,
+            challenge,
+            response,
+        } = self.0;
+        let a = &response * GENERATOR_TABLE - ek.0 * challenge;
+        let b = ct.0 * response - factor * challenge;
+        let statement = decryption_statement(ek, ct, factor);
+        if (a, b) != commitment
+            || self::challenge(DECRYPTION_DOMAIN, &statement, &[commitment]) != challenge
+        {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+// The commitments of branch `j` for the challenge `c` and response `s`: `sG - c * alpha` and
+// `sY - c(beta - jG)`.
+fn branch(
+    ek: &EncryptionKey,
+    ct: &Ciphertext,
+    j: u64,
+    c: &Scalar,
+    s: &Scalar,
+) -> (RistrettoPoint, RistrettoPoint) {
+    let b = ct.1 - GENERATOR_POINT * Scalar::from(j);
+    (s * GENERATOR_TABLE - ct.0 * c, ek.0 * s - b * c)
+}
+
+// The statement of a disjunctive proof: the generator, the key, the ciphertext and the allowed
+// plaintexts `0, G, ..., max * G`.
+fn disjunctive_statement(ek: &EncryptionKey, ct: &Ciphertext, max: u64) -> Vec<RistrettoPoint> {
+    let mut statement = vec![GENERATOR_POINT, ek.0, ct.0, ct.1];
+    statement.extend((0..=max).map(|j| GENERATOR_POINT * Scalar::from(j)));
+    statement
+}
+
+// The statement of a decryption proof: the generator, the key, the ciphertext and the claimed
+// decryption factor.
+fn decryption_statement(
+    ek: &EncryptionKey,
+    ct: &Ciphertext,
+    factor: &RistrettoPoint,
+) -> [RistrettoPoint; 5] {
+    [GENERATOR_POINT, ek.0, ct.0, ct.1, *factor]
+}
+
+// The challenge: SHA-1 of the domain label, then the statement and the commitments as written in
+// the JSON, joined with commas, read as a big-endian integer. Every point is 64 hex digits and the
+// labels contain no commas, so the input is unambiguous.
+fn challenge(
+    domain: &str,
+    statement: &[RistrettoPoint],
+    commitments: &[(RistrettoPoint, RistrettoPoint)],
+) -> Scalar {
+    let mut hasher = Sha1::new();
+    hasher.update(domain.as_bytes());
+    let points = commitments.iter().flat_map(|(a, b)| [a, b]);
+    for point in statement.iter().chain(points) {
+        hasher.update(b",");
+        hasher.update(point_to_hex(point).as_bytes());
+    }
+    let digest = hasher.finalize();
+
+    // a 160-bit digest is already below the group order
+    let mut bytes = [0u8; 32];
+    for (byte, digest_byte) in bytes.iter_mut().zip(digest.iter().rev()) {
+        *byte = *digest_byte;
+    }
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+// JSON shapes
+
+#[derive(Serialize, Deserialize)]
+struct CiphertextJson {
+    alpha: String,
+    beta: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CommitmentJson {
+    #[serde(rename = "A")]
+    a: String,
+    #[serde(rename = "B")]
+    b: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ZkProofJson {
+    commitment: CommitmentJson,
+    challenge: String,
+    response: String,
+}
+
+impl From<&ZkProof> for ZkProofJson {
+    fn from(proof: &ZkProof) -> Self {
+        ZkProofJson {
+            commitment: CommitmentJson {
+                a: point_to_hex(&proof.commitment.0),
+                b: point_to_hex(&proof.commitment.1),
+            },
+            challenge: scalar_to_decimal(&proof.challenge),
+            response: scalar_to_decimal(&proof.response),
+        }
+    }
+}
+
+impl TryFrom<ZkProofJson> for ZkProof {
+    type Error = Error;
+
+    fn try_from(json: ZkProofJson) -> Result<Self, Self::Error> {
+        Ok(ZkProof {
+            commitment: (
+                point_from_hex(&json.commitment.a)?,
+                point_from_hex(&json.commitment.b)?,
+            ),
+            challenge: scalar_from_decimal(&json.challenge)?,
+            response: scalar_from_decimal(&json.response)?,
+        })
+    }
+}
+
+impl HeliosJson for Ciphertext {
+    fn to_helios_json(&self) -> String {
+        let json = CiphertextJson {
+            alpha: point_to_hex(&self.0),
+            beta: point_to_hex(&self.1),
+        };
+        serde_json::to_string(&json).expect("serialising to a string cannot fail")
+    }
+
+    fn from_helios_json(json: &str) -> Result<Self, Error> {
+        let json: CiphertextJson = serde_json::from_str(json).map_err(|_| Error::InvalidJson)?;
+        Ok(Ciphertext(
+            point_from_hex(&json.alpha)?,
+            point_from_hex(&json.beta)?,
+        ))
+    }
+}
+
+impl HeliosJson for DisjunctiveProof {
+    fn to_helios_json(&self) -> String {
+        let json: Vec<ZkProofJson> = self.0.iter().map(ZkProofJson::from).collect();
+        serde_json::to_string(&json).expect("serialising to a string cannot fail")
+    }
+
+    fn from_helios_json(json: &str) -> Result<Self, Error> {
+        let json: Vec<ZkProofJson> = serde_json::from_str(json).map_err(|_| Error::InvalidJson)?;
+        json.into_iter()
+            .map(ZkProof::try_from)
+            .collect::<Result<_, _>>()
+            .map(DisjunctiveProof)
+    }
+}
+
+impl HeliosJson for DecryptionProof {
+    fn to_helios_json(&self) -> String {
+        serde_json::to_string(&ZkProofJson::from(&self.0))
+            .expect("serialising to a string cannot fail")
+    }
+
+    fn from_helios_json(json: &str) -> Result<Self, Error> {
+        let json: ZkProofJson = serde_json::from_str(json).map_err(|_| Error::InvalidJson)?;
+        ZkProof::try_from(json).map(DecryptionProof)
+    }
+}
+
+// Field encodings
+
+fn point_to_hex(point: &RistrettoPoint) -> String {
+    let mut out = String::with_capacity(64);
+    for byte in point.compress().as_bytes() {
+        write!(out, "{:02x}", byte).expect("writing to a string cannot fail");
+    }
+    out
+}
+
+fn point_from_hex(s: &str) -> Result<RistrettoPoint, Error> {
+    if s.len() != 64 {
+        return Err(Error::LengthMismatch {
+            expected: 64,
+            got: s.len(),
+        });
+    }
+    let mut bytes = [0u8; 32];
+    for (byte, pair) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+        let pair = core::str::from_utf8(pair).map_err(|_| Error::InvalidJson)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| Error::InvalidJson)?;
+    }
+    CompressedRistretto(bytes)
+        .decompress()
+        .ok_or(Error::InvalidPoint)
+}
+
+fn scalar_to_decimal(s: &Scalar) -> String {
+    // repeatedly divide the little-endian integer by ten, collecting the remainders
+    let mut n = s.to_bytes();
+    let mut digits = Vec::new();
+    while n.iter().any(|&b| b != 0) {
+        let mut rem = 0u16;
+        for byte in n.iter_mut().rev() {
+            let cur = (rem << 8) | *byte as u16;
+            *byte = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.iter().rev().map(|&d| d as char).collect()
+}
+
+fn scalar_from_decimal(s: &str) -> Result<Scalar, Error> {
+    if s.is_empty() {
+        return Err(Error::InvalidJson);
+    }
+    let mut n = [0u8; 32];
+    for c in s.bytes() {
+        if !c.is_ascii_digit() {
+            return Err(Error::InvalidJson);
+        }
+        // n = 10 * n + digit, rejecting anything that overflows 256 bits
+        let mut carry = (c - b'0') as u16;
+        for byte in n.iter_mut() {
+            let cur = *byte as u16 * 10 + carry;
+            *byte = cur as u8;
+            carry = cur >> 8;
+        }
+        if carry != 0 {
+            return Err(Error::InvalidScalar);
+        }
+    }
+    Option::from(Scalar::from_canonical_bytes(n)).ok_or(Error::InvalidScalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{
+        branch, challenge, scalar_from_decimal, scalar_to_decimal, DECRYPTION_DOMAIN,
+        DISJUNCTIVE_DOMAIN,
+    };
+    use crate::helios::{DecryptionProof, DisjunctiveProof, HeliosJson, ZkProof};
+    use crate::{
+        Ciphertext, DecryptionKey, Error, RistrettoPoint, Scalar, GENERATOR_POINT, GENERATOR_TABLE,
+    };
+
+    // A ballot answer written by this module from `StdRng::seed_from_u64(449)`, kept to catch
+    // accidental changes to the format or the challenge derivation.
+    const FIXTURE_CIPHERTEXT: &str = include_str!("../tests/fixtures/helios_ciphertext.json");
+    const FIXTURE_PROOF: &str = include_str!("../tests/fixtures/helios_disjunctive_proof.json");
+
+    // Test that scalars are written as decimal integers and read back, rejecting bad input.
+    #[test]
+    fn helios_decimal() {
+        assert_eq!(scalar_to_decimal(&Scalar::ZERO), "0");
+        assert_eq!(
+            scalar_to_decimal(&Scalar::from(1234567890u64)),
+            "1234567890"
+        );
+        assert_eq!(
+            scalar_to_decimal(&-Scalar::ONE),
+            "7237005577332262213973186563042994240857116359379907606001950938285454250988"
+        );
+
+        let mut rng = StdRng::from_entropy();
+        let s = Scalar::random(&mut rng);
+        assert_eq!(scalar_from_decimal(&scalar_to_decimal(&s)), Ok(s));
+
+        // the group order itself is not canonical
+        assert_eq!(
+            scalar_from_decimal(
+                "7237005577332262213973186563042994240857116359379907606001950938285454250989"
+            ),
+            Err(Error::InvalidScalar)
+        );
+        assert_eq!(scalar_from_decimal("12a"), Err(Error::InvalidJson));
+        assert_eq!(scalar_from_decimal(""), Err(Error::InvalidJson));
+    }
+
+    // Test that ciphertexts and proofs round-trip through JSON and that the proofs verify.
+    #[test]
+    fn helios_round_trip() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for (value, max) in [(0, 1), (1, 1), (2, 3)] {
+            let r = Scalar::random(&mut rng);
+            let ct = ek.exp_encrypt_with(Scalar::from(value), r);
+            let proof = DisjunctiveProof::prove(ek, &ct, value, max, &r, &mut rng);
+            assert_eq!(proof.verify(ek, &ct, max), Ok(()));
+
+            let json = ct.to_helios_json();
+            assert!(json.starts_with("{\"alpha\":\""));
+            let ct2 = Ciphertext::from_helios_json(&json).unwrap();
+            assert_eq!(ct, ct2);
+
+            let json = proof.to_helios_json();
+            assert!(json.starts_with("[{\"commitment\":{\"A\":\""));
+            let proof2 = DisjunctiveProof::from_helios_json(&json).unwrap();
+            assert_eq!(proof, proof2);
+            assert_eq!(proof2.verify(ek, &ct2, max), Ok(()));
+        }
+
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let (factor, proof) = DecryptionProof::prove(&dk, &ct, &mut rng);
+        assert_eq!(ct.1 - factor, dk.decrypt(ct));
+        let proof2 = DecryptionProof::from_helios_json(&proof.to_helios_json()).unwrap();
+        assert_eq!(proof, proof2);
+        assert_eq!(proof2.verify(ek, &ct, &factor), Ok(()));
+    }
+
+    // Test that proofs of out-of-range values, wrong factors and tampered JSON are rejected.
+    #[test]
+    fn helios_rejects() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let r = Scalar::random(&mut rng);
+        let ct = ek.exp_encrypt_with(Scalar::from(2u32), r);
+        let proof = DisjunctiveProof::prove(ek, &ct, 2, 1, &r, &mut rng);
+        assert_eq!(proof.verify(ek, &ct, 1), Err(Error::VerificationFailed));
+        assert_eq!(
+            proof.verify(ek, &ct, 2),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+
+        let (factor, proof) = DecryptionProof::prove(&dk, &ct, &mut rng);
+        assert_eq!(
+            proof.verify(ek, &ct, &(factor + GENERATOR_POINT)),
+            Err(Error::VerificationFailed)
+        );
+        let mut forged = proof;
+        forged.0 = ZkProof {
+            challenge: forged.0.challenge + Scalar::ONE,
+            ..forged.0
+        };
+        assert_eq!(
+            forged.verify(ek, &ct, &factor),
+            Err(Error::VerificationFailed)
+        );
+
+        assert_eq!(
+            Ciphertext::from_helios_json("{\"alpha\":\"00\"}"),
+            Err(Error::InvalidJson)
+        );
+        assert_eq!(
+            Ciphertext::from_helios_json("{\"alpha\":\"00\",\"beta\":\"00\"}"),
+            Err(Error::LengthMismatch {
+                expected: 64,
+                got: 2
+            })
+        );
+    }
+
+    // Test that proofs forged in the weak Fiat-Shamir form, with the challenge taken from the
+    // commitments alone and the statement solved for afterwards, are rejected: a trustee's wrong
+    // decryption factor, and an encryption of neither 0 nor 1 with a disjunctive proof.
+    #[test]
+    fn helios_rejects_weak_fiat_shamir() {
+        let mut rng = StdRng::seed_from_u64(449);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+
+        // fix the commitments and the challenge, then solve for the factor
+        let (w, t) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let commitment = (&w * GENERATOR_TABLE, &t * GENERATOR_TABLE);
+        let c = challenge(DECRYPTION_DOMAIN, &[], &[commitment]);
+        let response = w + c * dk.as_scalar();
+        let factor = (ct.0 * response - commitment.1) * c.invert();
+        assert_ne!(factor, ct.0 * dk.as_scalar());
+        assert_eq!(ct.0 * response - factor * c, commitment.1);
+        let forged = DecryptionProof(ZkProof {
+            commitment,
+            challenge: c,
+            response,
+        });
+        assert_eq!(
+            forged.verify(ek, &ct, &factor),
+            Err(Error::VerificationFailed)
+        );
+
+        // fix the commitments and the challenge, with branch 0 taking none of it, then solve for
+        // a ciphertext that branch 1 accepts
+        let (a0, a1) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let b1 = RistrettoPoint::random(&mut rng);
+        let commitments = [
+            (&a0 * GENERATOR_TABLE, ek.as_point() * a0),
+            (&a1 * GENERATOR_TABLE, b1),
+        ];
+        let c = challenge(DISJUNCTIVE_DOMAIN, &[], &commitments);
+        let r = Scalar::random(&mut rng);
+        let ct = Ciphertext(
+            &r * GENERATOR_TABLE,
+            ek.as_point() * r + GENERATOR_POINT - (b1 - ek.as_point() * a1) * c.invert(),
+        );
+        let proof = DisjunctiveProof(vec![
+            ZkProof {
+                commitment: commitments[0],
+                challenge: Scalar::ZERO,
+                response: a0,
+            },
+            ZkProof {
+                commitment: commitments[1],
+                challenge: c,
+                response: a1 + c * r,
+            },
+        ]);
+        let m = dk.decrypt(ct);
+        assert!(m != RistrettoPoint::default() && m != GENERATOR_POINT);
+        for (j, branch_proof) in proof.0.iter().enumerate() {
+            assert_eq!(
+                branch(
+                    ek,
+                    &ct,
+                    j as u64,
+                    &branch_proof.challenge,
+                    &branch_proof.response
+                ),
+                branch_proof.commitment
+            );
+        }
+        assert_eq!(proof.verify(ek, &ct, 1), Err(Error::VerificationFailed));
+    }
+
+    // Test that the checked-in fixture still parses and verifies.
+    #[test]
+    fn helios_fixture() {
+        let mut rng = StdRng::seed_from_u64(449);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let ct = Ciphertext::from_helios_json(FIXTURE_CIPHERTEXT.trim()).unwrap();
+        let proof = DisjunctiveProof::from_helios_json(FIXTURE_PROOF.trim()).unwrap();
+        assert_eq!(proof.verify(ek, &ct, 1), Ok(()));
+        assert_eq!(dk.decrypt(ct), GENERATOR_POINT);
+
+        assert_eq!(ct.to_helios_json(), FIXTURE_CIPHERTEXT.trim());
+        assert_eq!(proof.to_helios_json(), FIXTURE_PROOF.trim());
+    }
+}
@@ -0,0 +1,391 @@
+// Hybrid (KEM + AEAD) encryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE};
+
+/// The format version written as the first byte of every sealed message.
+pub const SEAL_VERSION: u8 = 1;
+
+const INFO: &[u8] = b"rust-elgamal hybrid v1";
+const ANONYMOUS_INFO: &[u8] = b"rust-elgamal sealed box v1";
+const NONCE_DOMAIN: &[u8] = b"rust-elgamal sealed box nonce";
+const HEADER_LEN: usize = 1 + 32;
+const TAG_LEN: usize = 16;
+
+// Derive the AEAD key from the shared point, bound to both the ephemeral and recipient keys.
+fn derive_key(shared: &RistrettoPoint, ephemeral: &[u8; 32], recipient: &RistrettoPoint) -> Key {
+    let hkdf = Hkdf::<Sha512>::new(None, shared.compress().as_bytes());
+    let mut key = Key::default();
+    hkdf.expand_multi_info(
+        &[INFO, ephemeral, recipient.compress().as_bytes()],
+        &mut key,
+    )
+    .expect("32 bytes is a valid HKDF-SHA512 output length");
+    key
+}
+
+// Derive the AEAD key of a sealed box as `derive_key` does, and its nonce from the hash of the
+// ephemeral and recipient keys, as libsodium does.
+fn derive_anonymous(
+    shared: &RistrettoPoint,
+    ephemeral: &[u8; 32],
+    recipient: &RistrettoPoint,
+) -> (Key, Nonce) {
+    let hkdf = Hkdf::<Sha512>::new(None, shared.compress().as_bytes());
+    let mut key = Key::default();
+    hkdf.expand_multi_info(
+        &[ANONYMOUS_INFO, ephemeral, recipient.compress().as_bytes()],
+        &mut key,
+    )
+    .expect("32 bytes is a valid HKDF-SHA512 output length");
+
+    let digest = Sha512::new()
+        .chain_update((NONCE_DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(NONCE_DOMAIN)
+        .chain_update(ephemeral)
+        .chain_update(recipient.compress().as_bytes())
+        .finalize();
+    let mut nonce = Nonce::default();
+    nonce.copy_from_slice(&digest[..12]);
+    (key, nonce)
+}
+
+impl EncryptionKey {
+    /// Encrypt an arbitrary byte string, authenticating it together with `aad`.
+    ///
+    /// This is hybrid encryption: an ephemeral Diffie-Hellman exchange with this key is fed
+    /// through HKDF-SHA512 to derive a one-time ChaCha20-Poly1305 key. The output is the format
+    /// version ([SEAL_VERSION]), the 32-byte ephemeral point, and the AEAD ciphertext with its
+    /// 16-byte tag, so it is 49 bytes longer than `plaintext`.
+    ///
+    /// Unlike [encrypt](Self::encrypt), this mode is **not homomorphic** and sealed messages
+    /// cannot be rerandomised: any change to the output makes [open](DecryptionKey::open) fail.
+    /// That is the point of it; use it when you need to encrypt data rather than compute on it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let sealed = dec_key.encryption_key().seal(b"attack at dawn", b"header", &mut rng);
+    /// assert_eq!(dec_key.open(&sealed, b"header").unwrap(), b"attack at dawn");
+    /// ```
+    pub fn seal<R: RngCore + CryptoRng>(
+        &self,
+        plaintext: &[u8],
+        aad: &[u8],
+        rng: &mut R,
+    ) -> Vec<u8> {
+        self.seal_with(plaintext, aad, &Scalar::random(rng))
+    }
+
+    fn seal_with(&self, plaintext: &[u8], aad: &[u8], e: &Scalar) -> Vec<u8> {
+        let ephemeral = (e * GENERATOR_TABLE).compress().to_bytes();
+        let key = derive_key(&(self.0 * e), &ephemeral, &self.0);
+
+        // every key is used for exactly one message, so a fixed nonce is safe
+        let ct = ChaCha20Poly1305::new(&key)
+            .encrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .expect("plaintext is within the ChaCha20-Poly1305 length limit");
+
+        let mut sealed = Vec::with_capacity(HEADER_LEN + ct.len());
+        sealed.push(SEAL_VERSION);
+        sealed.extend_from_slice(&ephemeral);
+        sealed.extend_from_slice(&ct);
+        sealed
+    }
+
+    /// Encrypt an arbitrary byte string to this key anonymously, in the manner of libsodium's
+    /// sealed boxes.
+    ///
+    /// A fresh ephemeral key takes the place of the sender's, so the output says nothing about
+    /// who sealed it and the sender keeps no state. The shared point is fed through HKDF-SHA512
+    /// to a ChaCha20-Poly1305 key, and the nonce is derived from the ephemeral and recipient
+    /// keys. The output is the 32-byte ephemeral point followed by the AEAD ciphertext with its
+    /// 16-byte tag, so it is 48 bytes longer than `plaintext`. Like [seal](Self::seal), it
+    /// authenticates the message but not the sender.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let sealed = dec_key.encryption_key().seal_anonymous(b"tip-off", &mut rng);
+    /// assert_eq!(dec_key.open_anonymous(&sealed).unwrap(), b"tip-off");
+    /// ```
+    pub fn seal_anonymous<R: RngCore + CryptoRng>(&self, plaintext: &[u8], rng: &mut R) -> Vec<u8> {
+        self.seal_anonymous_with(plaintext, &Scalar::random(rng))
+    }
+
+    fn seal_anonymous_with(&self, plaintext: &[u8], e: &Scalar) -> Vec<u8> {
+        let ephemeral = (e * GENERATOR_TABLE).compress().to_bytes();
+        let (key, nonce) = derive_anonymous(&(self.0 * e), &ephemeral, &self.0);
+        let ct = ChaCha20Poly1305::new(&key)
+            .encrypt(&nonce, plaintext)
+            .expect("plaintext is within the ChaCha20-Poly1305 length limit");
+
+        let mut sealed = Vec::with_capacity(32 + ct.len());
+        sealed.extend_from_slice(&ephemeral);
+        sealed.extend_from_slice(&ct);
+        sealed
+    }
+}
+
+impl DecryptionKey {
+    /// Decrypt and authenticate a message produced by [EncryptionKey::seal], with the same `aad`.
+    ///
+    /// Fails with [Error::VerificationFailed] if the message was tampered with, was sealed for a
+    /// different key, or `aad` does not match; with [Error::InvalidEncoding] if the version byte
+    /// is unknown; and with [Error::LengthMismatch] if it is too short to be a sealed message.
+    pub fn open(&self, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+        if sealed.len() < HEADER_LEN + TAG_LEN {
+            return Err(Error::LengthMismatch {
+                expected: HEADER_LEN + TAG_LEN,
+                got: sealed.len(),
+            });
+        }
+        if sealed[0] != SEAL_VERSION {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut ephemeral = [0u8; 32];
+        ephemeral.copy_from_slice(&sealed[1..HEADER_LEN]);
+        let point = CompressedRistretto(ephemeral)
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        if point.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+
+        let key = derive_key(&(point * self.secret), &ephemeral, &self.ek.0);
+        ChaCha20Poly1305::new(&key)
+            .decrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: &sealed[HEADER_LEN..],
+                    aad,
+                },
+            )
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    /// Decrypt and authenticate a message produced by [EncryptionKey::seal_anonymous].
+    ///
+    /// Fails with [Error::VerificationFailed] if the message was tampered with or was sealed for a
+    /// different key, with [Error::InvalidPoint] or [Error::IdentityPoint] if the ephemeral key is
+    /// not a valid point, and with [Error::LengthMismatch] if it is too short to be a sealed box.
+    pub fn open_anonymous(&self, sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        if sealed.len() < 32 + TAG_LEN {
+            return Err(Error::LengthMismatch {
+                expected: 32 + TAG_LEN,
+                got: sealed.len(),
+            });
+        }
+
+        let mut ephemeral = [0u8; 32];
+        ephemeral.copy_from_slice(&sealed[..32]);
+        let point = CompressedRistretto(ephemeral)
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        if point.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+
+        let (key, nonce) = derive_anonymous(&(point * self.secret), &ephemeral, &self.ek.0);
+        ChaCha20Poly1305::new(&key)
+            .decrypt(&nonce, &sealed[32..])
+            .map_err(|_| Error::VerificationFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use crate::{DecryptionKey, Error, Scalar};
+
+    // Test that sealed messages of various lengths open to the original bytes.
+    #[test]
+    fn seal_open() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for len in [0, 1, 31, 32, 33, 1000] {
+            let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sealed = ek.seal(&msg, b"aad", &mut rng);
+            assert_eq!(sealed.len(), msg.len() + 49);
+            assert_eq!(dk.open(&sealed, b"aad").unwrap(), msg);
+            assert_ne!(ek.seal(&msg, b"aad", &mut rng), sealed);
+        }
+    }
+
+    // Test that flipping any bit, changing the aad, or using the wrong key is detected.
+    #[test]
+    fn seal_tampered() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let sealed = dk.encryption_key().seal(b"hello world", b"aad", &mut rng);
+
+        for i in 1..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert!(dk.open(&tampered, b"aad").is_err());
+        }
+        assert_eq!(dk.open(&sealed, b"abd"), Err(Error::VerificationFailed));
+
+        let other = DecryptionKey::new(&mut rng);
+        assert_eq!(other.open(&sealed, b"aad"), Err(Error::VerificationFailed));
+
+        let mut tampered = sealed.clone();
+        tampered[0] = 2;
+        assert_eq!(dk.open(&tampered, b"aad"), Err(Error::InvalidEncoding));
+        assert_eq!(
+            dk.open(&sealed[..48], b"aad"),
+            Err(Error::LengthMismatch {
+                expected: 49,
+                got: 48
+            })
+        );
+    }
+
+    // Test that the output format is stable against a fixed vector.
+    #[test]
+    fn seal_vector() {
+        let dk = DecryptionKey::from(Scalar::from(7u32));
+        let sealed = dk
+            .encryption_key()
+            .seal_with(b"rust-elgamal", b"", &Scalar::from(11u32));
+        let hex: String = sealed.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            concat!(
+                "01",
+                "bce83f8ba5dd2fa572864c24ba1810f9522bc6004afe95877ac73241cafdab42",
+                "ac0ed6603faf1f3dd719f12b58cace852d9c77f6555f77ff3355d24a"
+            )
+        );
+        assert_eq!(dk.open(&sealed, b"").unwrap(), b"rust-elgamal");
+    }
+
+    // Test that sealed boxes open for their recipient only, differ every time, and are length
+    // checked.
+    #[test]
+    fn seal_anonymous_open() {
+        let mut rng = StdRng::seed_from_u64(473);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for len in [0, 1, 32, 1000] {
+            let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sealed = ek.seal_anonymous(&msg, &mut rng);
+            assert_eq!(sealed.len(), msg.len() + 48);
+            assert_eq!(dk.open_anonymous(&sealed).unwrap(), msg);
+            // two seals of the same message share neither the ephemeral key nor the ciphertext
+            let again = ek.seal_anonymous(&msg, &mut rng);
+            assert_ne!(again[..32], sealed[..32]);
+            assert_ne!(again[32..], sealed[32..]);
+        }
+
+        let sealed = ek.seal_anonymous(b"hello world", &mut rng);
+        let other = DecryptionKey::new(&mut rng);
+        assert_eq!(
+            other.open_anonymous(&sealed),
+            Err(Error::VerificationFailed)
+        );
+        // a sealed box is not a sealed message, nor the other way round
+        assert!(dk.open(&sealed, b"").is_err());
+        assert!(dk
+            .open_anonymous(&ek.seal(b"hello world", b"", &mut rng))
+            .is_err());
+        assert_eq!(
+            dk.open_anonymous(&sealed[..47]),
+            Err(Error::LengthMismatch {
+                expected: 48,
+                got: 47
+            })
+        );
+    }
+
+    // Test that flipping any bit of a sealed box is detected.
+    #[test]
+    fn seal_anonymous_tampered() {
+        let mut rng = StdRng::seed_from_u64(473);
+        let dk = DecryptionKey::new(&mut rng);
+        let sealed = dk.encryption_key().seal_anonymous(b"hello world", &mut rng);
+
+        for i in 0..sealed.len() * 8 {
+            let mut tampered = sealed.clone();
+            tampered[i / 8] ^= 1 << (i % 8);
+            assert!(dk.open_anonymous(&tampered).is_err());
+        }
+        let mut truncated = sealed.clone();
+        truncated.pop();
+        assert_eq!(
+            dk.open_anonymous(&truncated),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that the sealed box format is stable against a fixed vector.
+    #[test]
+    fn seal_anonymous_vector() {
+        let dk = DecryptionKey::from(Scalar::from(7u32));
+        let sealed = dk
+            .encryption_key()
+            .seal_anonymous_with(b"rust-elgamal", &Scalar::from(11u32));
+        let hex: String = sealed.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            concat!(
+                "bce83f8ba5dd2fa572864c24ba1810f9522bc6004afe95877ac73241cafdab42",
+                "a1e210ac97a8bb928464fd5bacb8aad0fe5824a727d19a03015c5a90"
+            )
+        );
+        assert_eq!(dk.open_anonymous(&sealed).unwrap(), b"rust-elgamal");
+    }
+}
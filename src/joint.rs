@@ -0,0 +1,442 @@
+// Two-party joint commitments for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commitments made jointly by two parties, so that neither holds a trapdoor alone.
+//!
+//! A [Commitment] `(rG, mG + rY)` is binding whatever the key, since `rG` fixes `r`, but anyone
+//! who knows the discrete log `y` of the commitment key `Y` can decrypt it to `mG`, and so read a
+//! small message long before it is opened. Here the two parties, `A` and `B`, each contribute a
+//! key `Y_A = y_A G` or `Y_B = y_B G` with a proof of possession ([PartyState::contribute_key]),
+//! and commit under the sum `Y = Y_A + Y_B` ([aggregate_key]), whose discrete log `y_A + y_B`
+//! neither knows. The proofs of possession stop the party that speaks second from choosing its
+//! key as `Y' - Y_A` for a `Y'` it knows the log of.
+//!
+//! Each party then commits to its share of the message with a blinding factor of its own
+//! ([PartyState::commit]), and the commitment is the sum of the two shares
+//! ([JointCommitment::assemble]), so its blinding factor is split between the parties. To commit
+//! to a message that one side chose, that side commits to it and the other to zero. Opening takes
+//! both opens ([JointCommitment::open]).
+//!
+//! Every message between the parties is serialisable with the `enable-serde` feature, except an
+//! [OpenShare], which reveals the party's open and needs `serde-secret`; the [PartyState] holds a
+//! secret and stays with its party.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::joint::{self, JointCommitment, PartyState, Role};
+//! use rust_elgamal::Scalar;
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let (party_a, key_a) = PartyState::contribute_key(Role::A, &mut rng);
+//! let (party_b, key_b) = PartyState::contribute_key(Role::B, &mut rng);
+//! let commit_key = joint::aggregate_key(&key_a, &key_b).unwrap();
+//!
+//! // A commits to the amount held in escrow, B only to blinding
+//! let (open_a, share_a) = party_a.commit(&commit_key, Scalar::from(250u32), &mut rng);
+//! let (open_b, share_b) = party_b.commit(&commit_key, Scalar::ZERO, &mut rng);
+//! let commitment = JointCommitment::assemble(&commit_key, &share_a, &share_b).unwrap();
+//!
+//! let open = commitment.open(&open_a, &open_b).unwrap();
+//! assert_eq!(open.message_u64(), Some(250));
+//! ```
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::avnet::PossessionProof;
+use crate::{
+    CommitKey, Commitment, DecryptionKey, EncryptionKey, Error, Open, GENERATOR_POINT,
+    GENERATOR_TABLE,
+};
+
+const DOMAIN: &[u8] = b"rust-elgamal joint commitment";
+
+/// Which of the two parties a message comes from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum Role {
+    A,
+    B,
+}
+
+/// A party's secret state: its role and the secret of its key contribution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PartyState {
+    role: Role,
+    secret: DecryptionKey,
+}
+
+/// A party's key contribution, with a proof of possession bound to its role.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct KeyContribution {
+    pub role: Role,
+    pub key: EncryptionKey,
+    pub proof: PossessionProof,
+}
+
+/// A party's share of a joint commitment: a commitment to its share of the message under the
+/// joint key.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct CommitShare {
+    pub role: Role,
+    pub commitment: Commitment,
+}
+
+/// The open of a party's [CommitShare], revealed to open the joint commitment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+pub struct OpenShare {
+    pub role: Role,
+    pub open: Open,
+}
+
+/// A commitment under a joint key, assembled from a share of each party.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct JointCommitment(Commitment);
+
+impl PartyState {
+    /// Pick a key share as the party with `role`, returning the secret state and the
+    /// contribution to send to the other party.
+    pub fn contribute_key<R: RngCore + CryptoRng>(
+        role: Role,
+        rng: &mut R,
+    ) -> (PartyState, KeyContribution) {
+        let secret = DecryptionKey::new(rng);
+        let key = *secret.encryption_key();
+
+        let k = Scalar::random(rng);
+        let c = challenge(role, &key.0, &(&k * GENERATOR_TABLE));
+        let proof = PossessionProof {
+            c,
+            s: k + c * secret.secret,
+        };
+        (
+            PartyState { role, secret },
+            KeyContribution { role, key, proof },
+        )
+    }
+
+    /// The party's role.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Commit to this party's share `m` of the message under the joint key, with a fresh
+    /// blinding factor, returning the open to keep and the share to send.
+    pub fn commit<R: RngCore + CryptoRng>(
+        &self,
+        key: &CommitKey,
+        m: Scalar,
+        rng: &mut R,
+    ) -> (OpenShare, CommitShare) {
+        let (open, commitment) = Commitment::commit_with(m, Scalar::random(rng), key);
+        (
+            OpenShare {
+                role: self.role,
+                open,
+            },
+            CommitShare {
+                role: self.role,
+                commitment,
+            },
+        )
+    }
+}
+
+impl KeyContribution {
+    /// Verify the proof of possession.
+    ///
+    /// Fails with [Error::IdentityPoint](crate::Error::IdentityPoint) if the key is the identity,
+    /// and with [Error::VerificationFailed](crate::Error::VerificationFailed) if the proof does
+    /// not hold.
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.key.0.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+        let a = RistrettoPoint::vartime_double_scalar_mul_basepoint(
+            &-self.proof.c,
+            &self.key.0,
+            &self.proof.s,
+        );
+        if challenge(self.role, &self.key.0, &a) != self.proof.c {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+/// The joint commitment key `Y_A + Y_B`, from the contributions of `A` and `B`.
+///
+/// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) if `a` is not from `A`
+/// or `b` not from `B`, as [KeyContribution::verify] does if a proof of possession does not
+/// verify, and with [Error::IdentityPoint](crate::Error::IdentityPoint) if the keys cancel out.
+pub fn aggregate_key(a: &KeyContribution, b: &KeyContribution) -> Result<CommitKey, Error> {
+    if a.role != Role::A || b.role != Role::B {
+        return Err(Error::InvalidParameters);
+    }
+    a.verify()?;
+    b.verify()?;
+    let key = a.key.0 + b.key.0;
+    if key.is_identity() {
+        return Err(Error::IdentityPoint);
+    }
+    Ok(CommitKey(key))
+}
+
+impl JointCommitment {
+    /// Add the shares of `A` and `B` into the joint commitment.
+    ///
+    /// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) if `a` is not from
+    /// `A` or `b` not from `B`, and with [Error::KeyMismatch](crate::Error::KeyMismatch) if a share
+    /// is not under `key`.
+    pub fn assemble(
+        key: &CommitKey,
+        a: &CommitShare,
+        b: &CommitShare,
+    ) -> Result<JointCommitment, Error> {
+        if a.role != Role::A || b.role != Role::B {
+            return Err(Error::InvalidParameters);
+        }
+        if a.commitment.commit_key() != *key || b.commitment.commit_key() != *key {
+            return Err(Error::KeyMismatch);
+        }
+        Ok(JointCommitment(a.commitment + b.commitment))
+    }
+
+    /// The commitment itself.
+    pub fn commitment(&self) -> &Commitment {
+        &self.0
+    }
+
+    /// Open the commitment with the opens of both parties, returning the joint open of the whole
+    /// message.
+    ///
+    /// Fails with [Error::InvalidParameters](crate::Error::InvalidParameters) if `a` is not from
+    /// `A` or `b` not from `B`, and with [Error::VerificationFailed](crate::Error::VerificationFailed)
+    /// if the opens do not open the commitment.
+    pub fn open(&self, a: &OpenShare, b: &OpenShare) -> Result<Open, Error> {
+        if a.role != Role::A || b.role != Role::B {
+            return Err(Error::InvalidParameters);
+        }
+        let open = &a.open + &b.open;
+        self.0.try_verify(&open)?;
+        Ok(open)
+    }
+
+    /// Check that the opens of both parties open the commitment; see [open](Self::open).
+    pub fn verify(&self, a: &OpenShare, b: &OpenShare) -> Result<(), Error> {
+        self.open(a, b).map(|_| ())
+    }
+}
+
+fn challenge(role: Role, key: &RistrettoPoint, a: &RistrettoPoint) -> Scalar {
+    let role: &[u8] = match role {
+        Role::A => b"A",
+        Role::B => b"B",
+    };
+    Scalar::from_hash(
+        Sha512::new()
+            .chain_update((DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(DOMAIN)
+            .chain_update(role)
+            .chain_update(GENERATOR_POINT.compress().as_bytes())
+            .chain_update(key.compress().as_bytes())
+            .chain_update(a.compress().as_bytes()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{aggregate_key, JointCommitment, KeyContribution, OpenShare, PartyState, Role};
+    use crate::avnet::PossessionProof;
+    use crate::{CommitKey, DecryptionKey, EncryptionKey, Error, Open, Scalar, GENERATOR_TABLE};
+
+    fn setup(rng: &mut StdRng) -> (PartyState, PartyState, CommitKey) {
+        let (a, key_a) = PartyState::contribute_key(Role::A, rng);
+        let (b, key_b) = PartyState::contribute_key(Role::B, rng);
+        let key = aggregate_key(&key_a, &key_b).unwrap();
+        (a, b, key)
+    }
+
+    // Test that the honest joint opening verifies, and that neither party alone, with its own
+    // open and the secret of its own key, can open the commitment to another message or decrypt
+    // it, while the two secrets together decrypt it but still cannot open it to another message.
+    #[test]
+    fn joint_commitment_binds_each_party() {
+        let mut rng = StdRng::seed_from_u64(500);
+        let (a, b, key) = setup(&mut rng);
+        let m = Scalar::from(250u32);
+        let (open_a, share_a) = a.commit(&key, m, &mut rng);
+        let (open_b, share_b) = b.commit(&key, Scalar::ZERO, &mut rng);
+        let commitment = JointCommitment::assemble(&key, &share_a, &share_b).unwrap();
+        assert_eq!(commitment.verify(&open_a, &open_b), Ok(()));
+        let open = commitment.open(&open_a, &open_b).unwrap();
+        assert_eq!(open.message_u64(), Some(250));
+
+        // the trapdoor shift of the blinding by (m - m') / y, and decryption with y
+        let m2 = Scalar::from(1u32);
+        let ct = commitment.commitment().1;
+        let shift = |open: Open, y: Scalar| Open(open.0 + (open.1 - m2) * y.invert(), m2);
+        for (party, own) in [(&a, &open_a), (&b, &open_b)] {
+            let y = party.secret.secret;
+            let (sa, sb) = match party.role() {
+                Role::A => (shift(open_a.open.clone(), y), open_b.open.clone()),
+                Role::B => (open_a.open.clone(), shift(open_b.open.clone(), y)),
+            };
+            let forged_a = OpenShare {
+                role: Role::A,
+                open: sa,
+            };
+            let forged_b = OpenShare {
+                role: Role::B,
+                open: sb,
+            };
+            assert_eq!(own.role, party.role());
+            assert_eq!(
+                commitment.verify(&forged_a, &forged_b),
+                Err(Error::VerificationFailed)
+            );
+            assert_eq!(
+                commitment.commitment().try_verify(&shift(open.clone(), y)),
+                Err(Error::VerificationFailed)
+            );
+            assert_ne!(party.secret.decrypt(ct), &m * GENERATOR_TABLE);
+        }
+
+        let joint = DecryptionKey::from(a.secret.secret + b.secret.secret);
+        assert_eq!(joint.decrypt(ct), &m * GENERATOR_TABLE);
+        assert_eq!(
+            commitment
+                .commitment()
+                .try_verify(&shift(open, joint.secret)),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that a rogue key cancelling the other party's key, sent without a valid proof, is
+    // refused, as are swapped roles and replayed proofs.
+    #[test]
+    fn joint_key_rejects_rogue_keys() {
+        let mut rng = StdRng::seed_from_u64(500);
+        let (a, key_a) = PartyState::contribute_key(Role::A, &mut rng);
+        let (_, key_b) = PartyState::contribute_key(Role::B, &mut rng);
+
+        // B picks a trapdoor t and the key tG - Y_A, whose log it does not know
+        let t = Scalar::from(7u32);
+        let rogue = KeyContribution {
+            role: Role::B,
+            key: EncryptionKey::from(&t * GENERATOR_TABLE - key_a.key.0),
+            proof: key_b.proof,
+        };
+        assert_eq!(
+            aggregate_key(&key_a, &rogue),
+            Err(Error::VerificationFailed)
+        );
+
+        assert_eq!(aggregate_key(&key_b, &key_a), Err(Error::InvalidParameters));
+        // A's contribution relabelled as B's does not verify
+        let replayed = KeyContribution {
+            role: Role::B,
+            ..key_a
+        };
+        assert_eq!(
+            aggregate_key(&key_a, &replayed),
+            Err(Error::VerificationFailed)
+        );
+        let cancelling = KeyContribution {
+            role: Role::B,
+            key: EncryptionKey::from(-key_a.key.0),
+            proof: PossessionProof {
+                c: Scalar::ZERO,
+                s: Scalar::ZERO,
+            },
+        };
+        assert_eq!(
+            aggregate_key(&key_a, &cancelling),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(a.role(), Role::A);
+    }
+
+    // Test that shares under another key or from the wrong roles are refused.
+    #[test]
+    fn joint_commitment_rejects_mismatched_shares() {
+        let mut rng = StdRng::seed_from_u64(500);
+        let (a, b, key) = setup(&mut rng);
+        let (_, _, other_key) = setup(&mut rng);
+        let (open_a, share_a) = a.commit(&key, Scalar::from(3u32), &mut rng);
+        let (open_b, share_b) = b.commit(&key, Scalar::ZERO, &mut rng);
+        let (_, stray) = b.commit(&other_key, Scalar::ZERO, &mut rng);
+
+        assert_eq!(
+            JointCommitment::assemble(&key, &share_a, &stray),
+            Err(Error::KeyMismatch)
+        );
+        assert_eq!(
+            JointCommitment::assemble(&key, &share_b, &share_a),
+            Err(Error::InvalidParameters)
+        );
+        let commitment = JointCommitment::assemble(&key, &share_a, &share_b).unwrap();
+        assert_eq!(
+            commitment.verify(&open_b, &open_a),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            commitment.verify(&open_a, &open_a),
+            Err(Error::InvalidParameters)
+        );
+    }
+
+    // Test that every message survives serialisation.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_joint_messages() {
+        let mut rng = StdRng::seed_from_u64(500);
+        let (a, key_a) = PartyState::contribute_key(Role::A, &mut rng);
+        let (b, key_b) = PartyState::contribute_key(Role::B, &mut rng);
+
+        let encoded = bincode::serialize(&key_b).unwrap();
+        let decoded: KeyContribution = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, key_b);
+        let key = aggregate_key(&key_a, &decoded).unwrap();
+
+        let (open_a, share_a) = a.commit(&key, Scalar::from(9u32), &mut rng);
+        let (open_b, share_b) = b.commit(&key, Scalar::ZERO, &mut rng);
+        let encoded = bincode::serialize(&share_b).unwrap();
+        let share_b: super::CommitShare = bincode::deserialize(&encoded).unwrap();
+        let commitment = JointCommitment::assemble(&key, &share_a, &share_b).unwrap();
+        let encoded = bincode::serialize(&commitment).unwrap();
+        let commitment: JointCommitment = bincode::deserialize(&encoded).unwrap();
+        #[cfg(feature = "serde-secret")]
+        let open_b: OpenShare =
+            bincode::deserialize(&bincode::serialize(&open_b).unwrap()).unwrap();
+        assert_eq!(commitment.verify(&open_a, &open_b), Ok(()));
+    }
+}
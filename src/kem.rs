@@ -0,0 +1,274 @@
+// Key encapsulation for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "kem")]
+use core::convert::Infallible;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::kdf;
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE};
+
+const KDF_DOMAIN: &[u8] = b"rust-elgamal kem";
+
+/// The public half of a key encapsulation: the sender's ephemeral point, to be sent to the
+/// recipient.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EncapsulatedKey(pub(crate) RistrettoPoint);
+
+impl EncapsulatedKey {
+    /// Returns the ephemeral point. Intended for advanced use only.
+    pub fn inner(&self) -> RistrettoPoint {
+        self.0
+    }
+
+    /// The 32-byte encoding of the encapsulated key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+
+    /// Read an encapsulated key from its 32-byte encoding, rejecting invalid points and the
+    /// identity.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, Error> {
+        let point = CompressedRistretto(*bytes)
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        if point.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(Self(point))
+    }
+}
+
+/// A 32-byte secret shared between the sender and recipient of an [EncapsulatedKey], suitable
+/// as the key of a symmetric cipher.
+#[derive(Clone)]
+pub struct SharedSecret(pub(crate) [u8; 32]);
+
+impl SharedSecret {
+    /// The bytes of the shared secret.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for SharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for SharedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SharedSecret {}
+
+impl Debug for SharedSecret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("SharedSecret(<redacted>)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for SharedSecret {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SharedSecret {}
+
+// Derive the shared secret from the Diffie-Hellman point, bound to both the ephemeral and the
+// recipient keys.
+fn derive(
+    shared: &RistrettoPoint,
+    ephemeral: &RistrettoPoint,
+    recipient: &RistrettoPoint,
+) -> SharedSecret {
+    SharedSecret(kdf(KDF_DOMAIN, &[shared, ephemeral, recipient]))
+}
+
+impl EncryptionKey {
+    /// Generate a fresh shared secret for the holder of the matching decryption key, together with
+    /// the [EncapsulatedKey] from which they can recover it.
+    ///
+    /// This is the key-encapsulation half of hybrid encryption, for use with a symmetric cipher of
+    /// your choice. With the `kem` feature, it is also available through the
+    /// [kem](https://docs.rs/kem/) crate's `Encapsulate` trait.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let (encapsulated, secret) = dec_key.encryption_key().encapsulate(&mut rng);
+    /// assert_eq!(dec_key.decapsulate(&encapsulated), secret);
+    /// ```
+    pub fn encapsulate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> (EncapsulatedKey, SharedSecret) {
+        let e = Scalar::random(rng);
+        let ephemeral = &e * GENERATOR_TABLE;
+        let secret = derive(&(self.0 * e), &ephemeral, &self.0);
+        (EncapsulatedKey(ephemeral), secret)
+    }
+}
+
+impl DecryptionKey {
+    /// Recover the shared secret from an [EncapsulatedKey] produced by
+    /// [EncryptionKey::encapsulate].
+    ///
+    /// An encapsulation for a different key yields an unrelated secret rather than an error, so
+    /// the secret should be used with an authenticated cipher.
+    pub fn decapsulate(&self, encapsulated: &EncapsulatedKey) -> SharedSecret {
+        derive(&(encapsulated.0 * self.secret), &encapsulated.0, &self.ek.0)
+    }
+}
+
+#[cfg(feature = "kem")]
+impl ::kem::Encapsulate<EncapsulatedKey, SharedSecret> for EncryptionKey {
+    type Error = Infallible;
+
+    fn encapsulate(
+        &self,
+        rng: &mut impl rand_core::CryptoRngCore,
+    ) -> Result<(EncapsulatedKey, SharedSecret), Self::Error> {
+        Ok(EncryptionKey::encapsulate(self, rng))
+    }
+}
+
+#[cfg(feature = "kem")]
+impl ::kem::Decapsulate<EncapsulatedKey, SharedSecret> for DecryptionKey {
+    type Error = Infallible;
+
+    fn decapsulate(&self, encapsulated_key: &EncapsulatedKey) -> Result<SharedSecret, Self::Error> {
+        Ok(DecryptionKey::decapsulate(self, encapsulated_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, EncapsulatedKey, Error, Identity, RistrettoPoint};
+
+    // Test that both sides derive the same secret, and that encapsulations are fresh.
+    #[test]
+    fn encapsulate_decapsulate() {
+        const N: usize = 20;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for _ in 0..N {
+            let (encapsulated, secret) = ek.encapsulate(&mut rng);
+            assert_eq!(dk.decapsulate(&encapsulated), secret);
+
+            let (encapsulated2, secret2) = ek.encapsulate(&mut rng);
+            assert_ne!(encapsulated, encapsulated2);
+            assert_ne!(secret, secret2);
+
+            let decoded = EncapsulatedKey::from_bytes(&encapsulated.to_bytes()).unwrap();
+            assert_eq!(dk.decapsulate(&decoded), secret);
+        }
+    }
+
+    // Test that the wrong key decapsulates to a different secret.
+    #[test]
+    fn decapsulate_wrong_key() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let (encapsulated, secret) = dk.encryption_key().encapsulate(&mut rng);
+        assert_ne!(other.decapsulate(&encapsulated), secret);
+    }
+
+    // Test that the shared secret's Debug output does not reveal its bytes.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn shared_secret_redacted() {
+        let mut rng = StdRng::seed_from_u64(438);
+        let dk = DecryptionKey::new(&mut rng);
+        let (_, secret) = dk.encryption_key().encapsulate(&mut rng);
+
+        let printed = alloc::format!("{:?}", secret);
+        assert_eq!(printed, "SharedSecret(<redacted>)");
+
+        let bytes = secret.as_bytes();
+        let hex: alloc::string::String =
+            bytes.iter().map(|b| alloc::format!("{:02x}", b)).collect();
+        let bytes_debug = alloc::format!("{:?}", bytes);
+        assert!(!printed.contains(&hex));
+        assert!(!printed.contains(&bytes_debug[1..bytes_debug.len() - 1]));
+    }
+
+    // Test that invalid encodings are rejected.
+    #[test]
+    fn encapsulated_key_bytes() {
+        assert_eq!(
+            EncapsulatedKey::from_bytes(&[0xff; 32]),
+            Err(Error::InvalidPoint)
+        );
+        assert_eq!(
+            EncapsulatedKey::from_bytes(&RistrettoPoint::identity().compress().to_bytes()),
+            Err(Error::IdentityPoint)
+        );
+    }
+
+    // Test that the RustCrypto traits agree with the inherent methods.
+    #[cfg(feature = "kem")]
+    #[test]
+    fn kem_traits() {
+        use kem::{Decapsulate, Encapsulate};
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+
+        let (encapsulated, secret) =
+            Encapsulate::encapsulate(dk.encryption_key(), &mut rng).unwrap();
+        assert_eq!(Decapsulate::decapsulate(&dk, &encapsulated), Ok(secret));
+    }
+}
@@ -0,0 +1,173 @@
+// Key pairs for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+#[cfg(feature = "serde-secret")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, ElGamalRng, EncryptionKey, Plaintext};
+
+/// A decryption key together with its encryption key, for applications that keep both.
+///
+/// The pair holds only the decryption key, which already carries its encryption key, so the two
+/// cannot disagree. It debug-prints and compares as the decryption key does, and with the
+/// `serde-secret` feature it serialises as the decryption key alone.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{KeyPair, Scalar, GENERATOR_TABLE};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let keys = KeyPair::generate(&mut rng);
+///
+/// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+/// let ct = keys.rerandomise(keys.encrypt(m, &mut rng), &mut rng);
+/// assert_eq!(keys.decrypt(ct), m);
+///
+/// let (dec_key, enc_key) = keys.into_parts();
+/// assert_eq!(dec_key.encryption_key(), &enc_key);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-secret",
+    serde(
+        transparent,
+        bound(
+            serialize = "G::Scalar: Serialize",
+            deserialize = "G::Scalar: Deserialize<'de>"
+        )
+    )
+)]
+pub struct KeyPair<G: ElGamalGroup = RistrettoPoint> {
+    secret: DecryptionKey<G>,
+}
+
+impl KeyPair {
+    /// Generate a new key pair using the randomness source `rng`.
+    ///
+    /// For other groups, generate the decryption key with
+    /// [DecryptionKey::random] and convert it with `KeyPair::from`.
+    pub fn generate<R: ElGamalRng<V>, V>(rng: &mut R) -> KeyPair {
+        KeyPair::from(DecryptionKey::new(rng))
+    }
+}
+
+impl<G: ElGamalGroup> KeyPair<G> {
+    /// The encryption key.
+    pub fn public(&self) -> &EncryptionKey<G> {
+        self.secret.encryption_key()
+    }
+
+    /// The decryption key.
+    pub fn secret(&self) -> &DecryptionKey<G> {
+        &self.secret
+    }
+
+    /// Encrypt `m` under the encryption key, as [EncryptionKey::encrypt].
+    pub fn encrypt<R: ElGamalRng<V>, V>(
+        &self,
+        m: impl Into<Plaintext<G>>,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        self.public().encrypt(m, rng)
+    }
+
+    /// Decrypt `ct` with the decryption key, as [DecryptionKey::decrypt].
+    pub fn decrypt(&self, ct: Ciphertext<G>) -> G {
+        self.secret.decrypt(ct)
+    }
+
+    /// Re-randomise `ct` under the encryption key, as [EncryptionKey::rerandomise].
+    #[must_use = "the Ciphertext input is not mutated, the function returns the new rerandomised Ciphertext"]
+    pub fn rerandomise<R: ElGamalRng<V>, V>(
+        &self,
+        ct: Ciphertext<G>,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        self.public().rerandomise(ct, rng)
+    }
+
+    /// Split the pair into its decryption and encryption keys, for code that takes them apart.
+    pub fn into_parts(self) -> (DecryptionKey<G>, EncryptionKey<G>) {
+        let ek = *self.secret.encryption_key();
+        (self.secret, ek)
+    }
+}
+
+impl<G: ElGamalGroup> From<DecryptionKey<G>> for KeyPair<G> {
+    fn from(secret: DecryptionKey<G>) -> Self {
+        KeyPair { secret }
+    }
+}
+
+impl<G: ElGamalGroup> From<KeyPair<G>> for DecryptionKey<G> {
+    fn from(keys: KeyPair<G>) -> Self {
+        keys.secret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::KeyPair;
+    use crate::{DecryptionKey, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    // Test that the pass-through methods give what the underlying keys give for the same
+    // randomness, and that the parts are the keys of the pair.
+    #[test]
+    fn key_pair_matches_keys() {
+        let mut rng = StdRng::seed_from_u64(506);
+        let keys = KeyPair::generate(&mut rng.clone());
+        let dk = DecryptionKey::new(&mut rng);
+        assert_eq!(keys.secret(), &dk);
+        assert_eq!(keys.public(), dk.encryption_key());
+
+        let m = &Scalar::from(17u32) * GENERATOR_TABLE;
+        let ct = keys.encrypt(m, &mut rng.clone());
+        assert_eq!(ct, dk.encryption_key().encrypt(m, &mut rng));
+        let rerandomised = keys.rerandomise(ct, &mut rng.clone());
+        assert_eq!(rerandomised, dk.encryption_key().rerandomise(ct, &mut rng));
+        assert_ne!(rerandomised, ct);
+        assert_eq!(keys.decrypt(rerandomised), dk.decrypt(rerandomised));
+        assert_eq!(keys.decrypt(rerandomised), m);
+
+        let other = KeyPair::<RistrettoPoint>::from(DecryptionKey::random(&mut rng));
+        assert_ne!(other, keys);
+        let (dec_key, enc_key) = keys.clone().into_parts();
+        assert_eq!(dec_key, dk);
+        assert_eq!(enc_key, *dk.encryption_key());
+        assert_eq!(DecryptionKey::from(keys), dk);
+    }
+
+    // Test that a pair serialises as its decryption key and comes back whole.
+    #[cfg(feature = "serde-secret")]
+    #[test]
+    fn serde_key_pair() {
+        let mut rng = StdRng::seed_from_u64(506);
+        let keys = KeyPair::generate(&mut rng);
+        let encoded = bincode::serialize(&keys).unwrap();
+        assert_eq!(encoded, bincode::serialize(keys.secret()).unwrap());
+        let decoded: KeyPair = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded.public(), keys.public());
+        assert_eq!(decoded, keys);
+    }
+}
@@ -0,0 +1,292 @@
+// Named collections of encryption keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::convert::TryFrom;
+
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, Commitment, EncryptionKey, Error, Plaintext};
+
+/// A collection of trusted encryption keys, each under a unique name, that can also be looked up
+/// by [fingerprint](crate::EncryptionKey::fingerprint).
+///
+/// No two entries share a name or a key: [insert](Self::insert) refuses both, and
+/// [replace](Self::replace) is the explicit way to change the key behind a name. With the
+/// `enable-serde` feature, a keyring serialises as a map from names to keys, and deserialising
+/// one that holds the same key twice fails.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, Keyring, GENERATOR_TABLE, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let mut keyring = Keyring::new();
+/// keyring.insert("election-2024", *dec_key.encryption_key()).unwrap();
+///
+/// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+/// let ct = keyring.encrypt_for("election-2024", m, &mut rng).unwrap();
+/// assert_eq!(dec_key.decrypt(ct), m);
+/// assert!(keyring.encrypt_for("election-2025", m, &mut rng).is_err());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(
+        try_from = "BTreeMap<String, EncryptionKey>",
+        into = "BTreeMap<String, EncryptionKey>"
+    )
+)]
+pub struct Keyring {
+    keys: BTreeMap<String, EncryptionKey>,
+    names: BTreeMap<[u8; 32], String>,
+}
+
+impl Keyring {
+    /// Create an empty keyring.
+    pub fn new() -> Keyring {
+        Keyring::default()
+    }
+
+    /// Add `ek` under `name`.
+    ///
+    /// Fails with [Error::DuplicateName](crate::Error::DuplicateName) if the name is taken, and
+    /// with [Error::DuplicateKey](crate::Error::DuplicateKey) if the key is already in the
+    /// keyring under another name.
+    pub fn insert(&mut self, name: &str, ek: EncryptionKey) -> Result<(), Error> {
+        if self.keys.contains_key(name) {
+            return Err(Error::DuplicateName);
+        }
+        let fingerprint = ek.fingerprint();
+        if self.names.contains_key(&fingerprint) {
+            return Err(Error::DuplicateKey);
+        }
+        self.keys.insert(name.to_string(), ek);
+        self.names.insert(fingerprint, name.to_string());
+        Ok(())
+    }
+
+    /// Put `ek` under `name`, returning the key it replaces, if any.
+    ///
+    /// Fails with [Error::DuplicateKey](crate::Error::DuplicateKey) if the key is already in the
+    /// keyring under another name.
+    pub fn replace(
+        &mut self,
+        name: &str,
+        ek: EncryptionKey,
+    ) -> Result<Option<EncryptionKey>, Error> {
+        let fingerprint = ek.fingerprint();
+        if let Some(other) = self.names.get(&fingerprint) {
+            if other != name {
+                return Err(Error::DuplicateKey);
+            }
+        }
+        let old = self.remove(name);
+        self.keys.insert(name.to_string(), ek);
+        self.names.insert(fingerprint, name.to_string());
+        Ok(old)
+    }
+
+    /// Remove the key under `name`, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<EncryptionKey> {
+        let ek = self.keys.remove(name)?;
+        self.names.remove(&ek.fingerprint());
+        Some(ek)
+    }
+
+    /// The key under `name`.
+    pub fn get(&self, name: &str) -> Option<&EncryptionKey> {
+        self.keys.get(name)
+    }
+
+    /// The name and key with the given fingerprint.
+    pub fn get_by_fingerprint(&self, fingerprint: &[u8; 32]) -> Option<(&str, &EncryptionKey)> {
+        let name = self.names.get(fingerprint)?;
+        Some((name, &self.keys[name]))
+    }
+
+    /// The names and keys, in order of name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &EncryptionKey)> {
+        self.keys.iter().map(|(name, ek)| (name.as_str(), ek))
+    }
+
+    /// The number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the keyring is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Encrypt `m` under the key called `name`.
+    ///
+    /// Fails with [Error::UnknownKey](crate::Error::UnknownKey) if there is no such key.
+    pub fn encrypt_for<R: RngCore + CryptoRng>(
+        &self,
+        name: &str,
+        m: impl Into<Plaintext>,
+        rng: &mut R,
+    ) -> Result<Ciphertext, Error> {
+        let ek = self.get(name).ok_or(Error::UnknownKey)?;
+        Ok(ek.encrypt(m, rng))
+    }
+
+    /// Check that `commitment` was made under one of the keys in the keyring, returning that
+    /// key's name.
+    ///
+    /// Fails with [Error::UnknownKey](crate::Error::UnknownKey) if it was not.
+    pub fn verify_known(&self, commitment: &Commitment) -> Result<&str, Error> {
+        let ek = commitment.commit_key().to_encryption_key_unchecked();
+        self.get_by_fingerprint(&ek.fingerprint())
+            .map(|(name, _)| name)
+            .ok_or(Error::UnknownKey)
+    }
+}
+
+impl From<Keyring> for BTreeMap<String, EncryptionKey> {
+    fn from(keyring: Keyring) -> Self {
+        keyring.keys
+    }
+}
+
+impl TryFrom<BTreeMap<String, EncryptionKey>> for Keyring {
+    type Error = Error;
+
+    fn try_from(keys: BTreeMap<String, EncryptionKey>) -> Result<Self, Self::Error> {
+        let mut keyring = Keyring::new();
+        for (name, ek) in keys {
+            keyring.insert(&name, ek)?;
+        }
+        Ok(keyring)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Commitment, DecryptionKey, Error, Keyring, Scalar, GENERATOR_TABLE};
+
+    // Test lookups by name and fingerprint, explicit handling of duplicates, and removal.
+    #[test]
+    fn keyring_entries() {
+        let mut rng = StdRng::from_entropy();
+        let ek1 = *DecryptionKey::new(&mut rng).encryption_key();
+        let ek2 = *DecryptionKey::new(&mut rng).encryption_key();
+        let ek3 = *DecryptionKey::new(&mut rng).encryption_key();
+
+        let mut keyring = Keyring::new();
+        keyring.insert("b", ek1).unwrap();
+        keyring.insert("a", ek2).unwrap();
+        assert_eq!(keyring.insert("a", ek3), Err(Error::DuplicateName));
+        assert_eq!(keyring.insert("c", ek1), Err(Error::DuplicateKey));
+        assert_eq!(keyring.len(), 2);
+
+        assert_eq!(keyring.get("b"), Some(&ek1));
+        assert_eq!(
+            keyring.get_by_fingerprint(&ek2.fingerprint()),
+            Some(("a", &ek2))
+        );
+        let names: Vec<_> = keyring.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        assert_eq!(keyring.replace("a", ek1), Err(Error::DuplicateKey));
+        assert_eq!(keyring.replace("a", ek3), Ok(Some(ek2)));
+        assert_eq!(keyring.get_by_fingerprint(&ek2.fingerprint()), None);
+        assert_eq!(keyring.replace("a", ek3), Ok(Some(ek3)));
+        assert_eq!(keyring.replace("d", ek2), Ok(None));
+
+        assert_eq!(keyring.remove("b"), Some(ek1));
+        assert_eq!(keyring.remove("b"), None);
+        assert_eq!(keyring.get_by_fingerprint(&ek1.fingerprint()), None);
+        keyring.insert("c", ek1).unwrap();
+        assert_eq!(keyring.len(), 3);
+    }
+
+    // Test encryption for named keys and commitment checks, including unknown keys.
+    #[test]
+    fn keyring_unknown_key() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let mut keyring = Keyring::new();
+        keyring.insert("trusted", *dk.encryption_key()).unwrap();
+
+        let m = &Scalar::from(3u32) * GENERATOR_TABLE;
+        let ct = keyring.encrypt_for("trusted", m, &mut rng).unwrap();
+        assert_eq!(dk.decrypt(ct), m);
+        assert_eq!(
+            keyring.encrypt_for("untrusted", m, &mut rng),
+            Err(Error::UnknownKey)
+        );
+
+        let (_, commitment) = Commitment::commit_with(
+            Scalar::from(1u32),
+            Scalar::from(2u32),
+            &crate::CommitKey::from_encryption_key_unchecked(dk.encryption_key()),
+        );
+        assert_eq!(keyring.verify_known(&commitment), Ok("trusted"));
+        let (_, commitment) = Commitment::commit(Scalar::from(1u32), &mut rng);
+        assert_eq!(keyring.verify_known(&commitment), Err(Error::UnknownKey));
+    }
+
+    // Test that a keyring survives serialisation, and that duplicate keys are rejected.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_keyring() {
+        use alloc::collections::BTreeMap;
+        use alloc::string::String;
+
+        use crate::EncryptionKey;
+
+        let mut rng = StdRng::from_entropy();
+        let mut keyring = Keyring::new();
+        for name in ["epoch-1", "epoch-2", "epoch-3"] {
+            let ek = *DecryptionKey::new(&mut rng).encryption_key();
+            keyring.insert(name, ek).unwrap();
+        }
+
+        let encoded = bincode::serialize(&keyring).unwrap();
+        let decoded: Keyring = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(keyring, decoded);
+        let (_, ek) = decoded.iter().next().unwrap();
+        assert_eq!(
+            decoded.get_by_fingerprint(&ek.fingerprint()).unwrap().0,
+            "epoch-1"
+        );
+
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let mut duplicated: BTreeMap<String, EncryptionKey> = BTreeMap::new();
+        duplicated.insert("x".into(), ek);
+        duplicated.insert("y".into(), ek);
+        let encoded = bincode::serialize(&duplicated).unwrap();
+        let err = bincode::deserialize::<Keyring>(&encoded).unwrap_err();
+        assert!(err.to_string().contains("already"));
+    }
+}
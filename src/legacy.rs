@@ -0,0 +1,286 @@
+// Readers for data serialized by earlier rust-elgamal releases.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Readers for ciphertexts and keys that the upstream rust-elgamal releases wrote with serde and
+//! bincode 1, for archives made before this fork.
+//!
+//! The upstream types serialize as this crate's do: a ciphertext as its two points in order, an
+//! encryption key as its point, and a decryption key as its secret scalar alone. What differs
+//! is how `curve25519-dalek` serialized each 32-byte value:
+//! - `curve25519-dalek` 2 and 3 (and 4, used here) write a fixed-size tuple of 32 bytes, with no
+//!   length, so a ciphertext is 64 bytes;
+//! - `curve25519-dalek` 1 wrote a byte string, which bincode prefixes with its length as a
+//!   little-endian `u64` (always 32), so a ciphertext is 80 bytes.
+//!
+//! The functions here accept either layout, telling them apart by length, and apply the checks the
+//! current decoders make: points must be canonical encodings, scalars must be reduced, and keys
+//! must not be the identity or zero, which the upstream decoders allowed. Lists are bincode
+//! sequences, a `u64` count followed by the elements. Trailing bytes, which bincode 1 ignores, are
+//! rejected.
+//!
+//! Values read here are ordinary values of this crate, to be written in its current formats; with
+//! the `enable-serde` feature, bincode writes them in the `curve25519-dalek` 3 layout again.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error};
+
+// The length of a 32-byte value in the `curve25519-dalek` 1 layout.
+const PREFIXED_LEN: usize = 40;
+
+// Split off the next 32-byte value, in the layout of `prefixed`.
+fn take32<'a>(bytes: &mut &'a [u8], prefixed: bool) -> Result<&'a [u8; 32], Error> {
+    if prefixed {
+        let (len, rest) = bytes.split_at(8);
+        if len != 32u64.to_le_bytes() {
+            return Err(Error::InvalidEncoding);
+        }
+        *bytes = rest;
+    }
+    let (value, rest) = bytes.split_at(32);
+    *bytes = rest;
+    Ok(<&[u8; 32]>::try_from(value).expect("split at 32"))
+}
+
+// Read a single 32-byte value, in whichever layout its length gives.
+fn single32(bytes: &[u8]) -> Result<&[u8; 32], Error> {
+    let prefixed = match bytes.len() {
+        32 => false,
+        PREFIXED_LEN => true,
+        _ => return Err(Error::InvalidEncoding),
+    };
+    let mut bytes = bytes;
+    take32(&mut bytes, prefixed)
+}
+
+fn point(bytes: &[u8; 32]) -> Result<RistrettoPoint, Error> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or(Error::InvalidPoint)
+}
+
+fn ciphertext(bytes: &mut &[u8], prefixed: bool) -> Result<Ciphertext, Error> {
+    let c1 = point(take32(bytes, prefixed)?)?;
+    let c2 = point(take32(bytes, prefixed)?)?;
+    Ok(Ciphertext(c1, c2))
+}
+
+/// Read a ciphertext serialized with bincode 1 by an upstream release.
+///
+/// Fails with [Error::InvalidEncoding] if the bytes are not 64 or 80 bytes long or a length
+/// prefix is not 32, and with [Error::InvalidPoint] if a point is not canonically encoded.
+pub fn ciphertext_from_legacy_bincode(bytes: &[u8]) -> Result<Ciphertext, Error> {
+    let prefixed = match bytes.len() {
+        64 => false,
+        80 => true,
+        _ => return Err(Error::InvalidEncoding),
+    };
+    let mut bytes = bytes;
+    ciphertext(&mut bytes, prefixed)
+}
+
+/// Read a list of ciphertexts (a `Vec<Ciphertext>`) serialized with bincode 1 by an upstream
+/// release.
+///
+/// Fails with [Error::InvalidEncoding] if the length of the data does not match the count it
+/// starts with in either layout, or a length prefix is not 32, and with [Error::InvalidPoint] if a
+/// point is not canonically encoded.
+pub fn ciphertexts_from_legacy_bincode(bytes: &[u8]) -> Result<Vec<Ciphertext>, Error> {
+    if bytes.len() < 8 {
+        return Err(Error::InvalidEncoding);
+    }
+    let (count, mut rest) = bytes.split_at(8);
+    let count = u64::from_le_bytes(<[u8; 8]>::try_from(count).expect("split at 8"));
+    // compare in u64, so a hostile count cannot overflow or allocate
+    let prefixed = if count.checked_mul(64) == Some(rest.len() as u64) {
+        false
+    } else if count.checked_mul(80) == Some(rest.len() as u64) {
+        true
+    } else {
+        return Err(Error::InvalidEncoding);
+    };
+    let mut cts = Vec::with_capacity(count as usize);
+    while !rest.is_empty() {
+        cts.push(ciphertext(&mut rest, prefixed)?);
+    }
+    Ok(cts)
+}
+
+/// Read an encryption key serialized with bincode 1 by an upstream release.
+///
+/// Fails with [Error::InvalidEncoding] if the bytes are not 32 or 40 bytes long or the length
+/// prefix is not 32, with [Error::InvalidPoint] if the point is not canonically encoded, and with
+/// [Error::IdentityPoint] if it is the identity.
+pub fn encryption_key_from_legacy(bytes: &[u8]) -> Result<EncryptionKey, Error> {
+    <EncryptionKey>::from_bytes(single32(bytes)?)
+}
+
+/// Read a decryption key serialized with bincode 1 by an upstream release, which holds only the
+/// secret scalar.
+///
+/// Fails with [Error::InvalidEncoding] if the bytes are not 32 or 40 bytes long or the length
+/// prefix is not 32, and with [Error::InvalidScalar] if the scalar is not reduced or is zero.
+pub fn decryption_key_from_legacy(bytes: &[u8]) -> Result<DecryptionKey, Error> {
+    <DecryptionKey>::from_bytes(single32(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{
+        ciphertext_from_legacy_bincode, ciphertexts_from_legacy_bincode,
+        decryption_key_from_legacy, encryption_key_from_legacy,
+    };
+    use crate::{CompressedCiphertext, DecryptionKey, Error, Scalar, GENERATOR_TABLE};
+
+    // Three encryptions of 1, 2 and 3 times the generator and the keys, from
+    // `StdRng::seed_from_u64(497)`, in the upstream layouts: the list with `curve25519-dalek` 3
+    // and with `curve25519-dalek` 1, and the keys with `curve25519-dalek` 3.
+    const FIXTURE_CIPHERTEXTS: &[u8] =
+        include_bytes!("../tests/fixtures/legacy_ciphertexts.bincode");
+    const FIXTURE_CIPHERTEXTS_DALEK1: &[u8] =
+        include_bytes!("../tests/fixtures/legacy_ciphertexts_dalek1.bincode");
+    const FIXTURE_ENCRYPTION_KEY: &[u8] =
+        include_bytes!("../tests/fixtures/legacy_encryption_key.bincode");
+    const FIXTURE_DECRYPTION_KEY: &[u8] =
+        include_bytes!("../tests/fixtures/legacy_decryption_key.bincode");
+
+    // Prefix each 32-byte value with its length, as `curve25519-dalek` 1 did.
+    fn dalek1(values: &[u8]) -> Vec<u8> {
+        values
+            .chunks(32)
+            .flat_map(|value| {
+                32u64
+                    .to_le_bytes()
+                    .iter()
+                    .chain(value)
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    // Test that the fixtures decode to the values they were made from, in both layouts, and
+    // round-trip into the current encodings.
+    #[test]
+    fn legacy_fixtures() {
+        let mut rng = StdRng::seed_from_u64(497);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let cts: Vec<_> = (1u32..=3)
+            .map(|m| ek.encrypt(&Scalar::from(m) * GENERATOR_TABLE, &mut rng))
+            .collect();
+
+        let decoded = ciphertexts_from_legacy_bincode(FIXTURE_CIPHERTEXTS).unwrap();
+        assert_eq!(decoded, cts);
+        assert_eq!(
+            ciphertexts_from_legacy_bincode(FIXTURE_CIPHERTEXTS_DALEK1),
+            Ok(cts.clone())
+        );
+        assert_eq!(encryption_key_from_legacy(FIXTURE_ENCRYPTION_KEY), Ok(*ek));
+        let legacy_dk = decryption_key_from_legacy(FIXTURE_DECRYPTION_KEY).unwrap();
+        assert_eq!(legacy_dk, dk);
+        for (m, ct) in (1u32..=3).zip(decoded.iter()) {
+            assert_eq!(legacy_dk.decrypt(*ct), &Scalar::from(m) * GENERATOR_TABLE);
+        }
+
+        // a single ciphertext, and the keys, in the `curve25519-dalek` 1 layout
+        let single = &FIXTURE_CIPHERTEXTS[8..72];
+        assert_eq!(ciphertext_from_legacy_bincode(single), Ok(cts[0]));
+        assert_eq!(ciphertext_from_legacy_bincode(&dalek1(single)), Ok(cts[0]));
+        assert_eq!(
+            encryption_key_from_legacy(&dalek1(FIXTURE_ENCRYPTION_KEY)),
+            Ok(*ek)
+        );
+        assert_eq!(
+            decryption_key_from_legacy(&dalek1(FIXTURE_DECRYPTION_KEY)),
+            Ok(dk.clone())
+        );
+
+        // the current compressed encoding holds the same bytes
+        let compressed = CompressedCiphertext::from_bytes(&<[u8; 64]>::try_from(single).unwrap());
+        assert_eq!(compressed.decompress(), Ok(cts[0]));
+        assert_eq!(cts[0].compress().to_bytes()[..], single[..]);
+
+        #[cfg(feature = "enable-serde")]
+        {
+            assert_eq!(bincode::serialize(&decoded).unwrap(), FIXTURE_CIPHERTEXTS);
+            assert_eq!(bincode::serialize(ek).unwrap(), FIXTURE_ENCRYPTION_KEY);
+            #[cfg(feature = "serde-secret")]
+            assert_eq!(bincode::serialize(&dk).unwrap(), FIXTURE_DECRYPTION_KEY);
+        }
+    }
+
+    // Test that malformed data is rejected with the matching error.
+    #[test]
+    fn legacy_rejects_malformed() {
+        let single = &FIXTURE_CIPHERTEXTS[8..72];
+
+        // lengths that fit neither layout, and trailing bytes
+        assert_eq!(
+            ciphertext_from_legacy_bincode(&single[..63]),
+            Err(Error::InvalidEncoding)
+        );
+        let mut trailing = FIXTURE_CIPHERTEXTS.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            ciphertexts_from_legacy_bincode(&trailing),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            ciphertexts_from_legacy_bincode(&[0xff; 8]),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            ciphertexts_from_legacy_bincode(&[0; 7]),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(ciphertexts_from_legacy_bincode(&[0; 8]), Ok(Vec::new()));
+
+        // a length prefix other than 32
+        let mut prefixed = dalek1(single);
+        prefixed[0] = 33;
+        assert_eq!(
+            ciphertext_from_legacy_bincode(&prefixed),
+            Err(Error::InvalidEncoding)
+        );
+
+        // invalid points, the identity key, and unreduced or zero secrets
+        assert_eq!(
+            ciphertext_from_legacy_bincode(&[0xff; 64]),
+            Err(Error::InvalidPoint)
+        );
+        assert_eq!(
+            encryption_key_from_legacy(&[0; 32]),
+            Err(Error::IdentityPoint)
+        );
+        assert_eq!(
+            decryption_key_from_legacy(&[0xff; 32]),
+            Err(Error::InvalidScalar)
+        );
+        assert_eq!(
+            decryption_key_from_legacy(&dalek1(&[0; 32])),
+            Err(Error::InvalidScalar)
+        );
+    }
+}
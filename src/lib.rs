@@ -13,11 +13,149 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+// `define_proof!` calls helper macros by their bare names
+#[cfg(feature = "zkp")]
+#[macro_use]
+extern crate zkp as zkp_macros;
+
+#[cfg(feature = "zstd")]
+mod archive;
+#[cfg(feature = "armor")]
+mod armor;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "ed25519")]
+mod attestation;
+#[cfg(feature = "alloc")]
+pub mod auction;
+pub mod avnet;
+mod backend;
+mod balance;
+mod batch;
+mod benaloh;
+mod blind;
+#[cfg(feature = "alloc")]
+pub mod ballot;
+#[cfg(feature = "bech32")]
+mod bech32;
+#[cfg(feature = "canonical-json")]
+pub mod canonical_json;
+#[cfg(feature = "alloc")]
+mod chunked;
 mod ciphertext;
+#[cfg(feature = "alloc")]
+mod columns;
+mod commit_key;
 mod commitment;
+#[cfg(feature = "alloc")]
+mod consistency;
+mod const_key;
+#[cfg(feature = "dalek-compat")]
+pub mod compat;
+#[cfg(feature = "alloc")]
+mod crt;
+#[cfg(feature = "ct-test")]
+pub mod ct_test;
+#[cfg(feature = "alloc")]
+pub mod deck;
 mod decrypt;
+#[cfg(feature = "der")]
+mod der;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+#[cfg(feature = "mmap")]
+mod disk_table;
+#[cfg(feature = "alloc")]
+pub mod dkg;
+mod dleq;
+#[cfg(feature = "alloc")]
+mod dlog;
+#[cfg(feature = "alloc")]
+mod dlog_table;
+#[cfg(feature = "alloc")]
+mod dummy;
+mod encoding;
 mod encrypt;
+#[cfg(feature = "elligator")]
+mod elligator;
+#[cfg(feature = "alloc")]
+mod envelope;
+#[cfg(feature = "alloc")]
+mod escrow;
+#[cfg(feature = "alloc")]
+mod epoch;
+mod error;
+#[cfg(feature = "alloc")]
+pub mod frost;
+mod hash;
+mod hd;
+#[cfg(feature = "helios")]
+pub mod helios;
+#[cfg(feature = "hybrid")]
+pub mod hpke;
+#[cfg(feature = "hybrid")]
+mod hybrid;
+pub mod joint;
+mod kem;
+mod keypair;
+#[cfg(feature = "alloc")]
+mod keyring;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
+#[cfg(feature = "alloc")]
+mod merkle;
+#[cfg(feature = "async")]
+pub mod nonblocking;
 mod open;
+pub mod oprf;
+pub mod ot;
+mod packed;
+mod pad;
+mod params;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+mod pedersen;
+#[cfg(feature = "alloc")]
+mod permutation;
+mod plaintext;
+#[cfg(feature = "alloc")]
+mod polycommit;
+mod reencrypt;
+#[cfg(feature = "ristretto448")]
+pub mod ristretto448;
+mod rng;
+mod schnorr;
+#[cfg(feature = "secure-memory")]
+mod secure_memory;
+#[cfg(feature = "alloc")]
+mod share;
+#[cfg(feature = "hybrid")]
+mod signcrypt;
+#[cfg(feature = "alloc")]
+mod solver;
+#[cfg(feature = "sqlx")]
+mod sqlx;
+mod stealth;
+#[cfg(all(feature = "hybrid", feature = "std"))]
+mod stream;
+#[cfg(feature = "alloc")]
+mod tally;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+mod trace;
+pub mod tracker;
+#[cfg(feature = "ts")]
+pub mod ts;
+mod universal;
+#[cfg(feature = "verificatum")]
+pub mod verificatum;
+#[cfg(feature = "zkp")]
+pub mod zkp;
 
 use curve25519_dalek::constants::{
     RISTRETTO_BASEPOINT_COMPRESSED, RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE,
@@ -32,11 +170,132 @@ pub use curve25519_dalek::traits::Identity;
 pub use curve25519_dalek::traits::IsIdentity;
 pub use curve25519_dalek::traits::MultiscalarMul;
 
-pub use ciphertext::Ciphertext;
-pub use commitment::Commitment;
+// `curve25519-dalek` implements the `group` and `ff` traits for `RistrettoPoint` and `Scalar`
+// directly, so the crate's types can be fed into generic code without a wrapper.
+pub use ff;
+pub use group;
+pub use subtle;
+
+#[cfg(feature = "zstd")]
+pub use archive::{
+    export_compressed, import_compressed, import_compressed_with_limit, ARCHIVE_MAX_CIPHERTEXTS,
+    ARCHIVE_VERSION,
+};
+#[cfg(feature = "ed25519")]
+pub use attestation::KeyAttestation;
+pub use backend::{ElGamalGroup, ScalarOperand, SecretScalar};
+pub use balance::BalanceProof;
+pub use batch::{compress_batch_into, CiphertextArray};
+pub use benaloh::{BenalohChallenge, CiphertextFingerprint, PendingBallot};
+pub use blind::{BlindedCiphertext, BlindedPlaintext, BlindingFactor};
+#[cfg(feature = "alloc")]
+pub use ciphertext::{compress_batch, lagrange_coefficients};
+pub use ciphertext::{Ciphertext, CompressedCiphertext};
+#[cfg(feature = "alloc")]
+pub use columns::CiphertextColumns;
+pub use commit_key::{AsCommitKey, CommitKey};
+pub use commitment::{CommitMessage, CommitMessageBytes, Commitment, COMMIT_MESSAGE_MAX_LEN};
+#[cfg(feature = "alloc")]
+pub use consistency::{encrypt_consistent, ConsistencyProof};
+pub use const_key::CompressedEncryptionKey;
+#[cfg(feature = "alloc")]
+pub use crt::CrtEncoder;
 pub use decrypt::DecryptionKey;
+/// Give a newtype over [Ciphertext] or [Commitment] the operators and encodings of the type it
+/// wraps, closed over the newtype, so that a `VoteCiphertext` can only be added to another
+/// `VoteCiphertext`.
+///
+/// The struct must have exactly one field, written as `Ciphertext` or `Commitment` (optionally
+/// with a path, but not through a type alias), and no generic parameters. The derive generates:
+/// - `Add` and `Sub` with the newtype, and `Mul<Scalar>`, for owned values and references,
+/// - `Sum` for ciphertext wrappers (a sum of commitments has no key to start from),
+/// - `new`, `into_inner`, `From` in both directions and `AsRef` of the inner type,
+/// - `to_bytes` and `from_bytes`, using the compressed encoding of the inner type (for a
+///   commitment, the key followed by the ciphertext), checking every point on decode,
+/// - with `enable-serde`, `Serialize` and `Deserialize` in the same format as the inner type.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{Ciphertext, DecryptionKey, ElgamalWrapper, Scalar, GENERATOR_TABLE};
+///
+/// #[derive(Copy, Clone, Debug, PartialEq, ElgamalWrapper)]
+/// struct VoteCiphertext(Ciphertext);
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let votes: Vec<VoteCiphertext> = (0..3u32)
+///     .map(|v| VoteCiphertext::new(enc_key.encrypt(&Scalar::from(v) * GENERATOR_TABLE, &mut rng)))
+///     .collect();
+/// let tally: VoteCiphertext = votes.iter().sum();
+/// assert_eq!(dec_key.decrypt(tally.into_inner()), &Scalar::from(3u32) * GENERATOR_TABLE);
+///
+/// let bytes = votes[0].to_bytes();
+/// assert_eq!(VoteCiphertext::from_bytes(&bytes), Ok(votes[0]));
+/// ```
+#[cfg(feature = "derive")]
+pub use rust_elgamal_derive::ElgamalWrapper;
+#[cfg(feature = "mmap")]
+pub use disk_table::{DiskDiscreteLogTable, DiskTableBuilder};
+pub use dleq::DleqProof;
+#[cfg(feature = "alloc")]
+pub use dlog_table::DiscreteLogTable;
+#[cfg(feature = "alloc")]
+pub use dummy::{dummy_point, pad_to, DUMMY_POINT_COMPRESSED};
+pub use encoding::{decode_from_point, encode_hashed, encode_to_point, POINT_MSG_LEN};
 pub use encrypt::EncryptionKey;
+#[cfg(feature = "alloc")]
+pub use envelope::{CiphertextEnvelope, ENVELOPE_VERSION_MAJOR, ENVELOPE_VERSION_MINOR};
+#[cfg(feature = "alloc")]
+pub use epoch::{EpochCiphertext, EpochEncryptionKeys, EvolvingDecryptionKey};
+pub use error::Error;
+#[cfg(feature = "alloc")]
+pub use escrow::{Escrow, EscrowProof};
+#[cfg(feature = "hybrid")]
+pub use hybrid::SEAL_VERSION;
+pub use kem::{EncapsulatedKey, SharedSecret};
+pub use keypair::KeyPair;
+#[cfg(feature = "alloc")]
+pub use keyring::Keyring;
+#[cfg(feature = "alloc")]
+pub use merkle::{CiphertextTree, InclusionProof};
 pub use open::Open;
+pub use packed::pack_counters;
+pub use pad::EncryptionPad;
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+pub use pad::PadStore;
+pub use params::{ElGamalParams, WithParams};
+pub use pedersen::{
+    blinding_generator, PedersenLinkProof, PedersenOpen, BLINDING_GENERATOR_COMPRESSED,
+};
+#[cfg(feature = "alloc")]
+pub use permutation::Permutation;
+pub use plaintext::{DecryptedMessage, ExponentMessage, Plaintext, PointMessage};
+#[cfg(feature = "alloc")]
+pub use polycommit::{EvalWitness, PolynomialCommitment};
+pub use reencrypt::ReEncryptionKey;
+pub use rng::{ElGamalRng, RandCore06};
+#[cfg(feature = "rand09")]
+pub use rng::{Rand09Rng, RandCore09};
+pub use schnorr::SchnorrSignature;
+#[cfg(feature = "secure-memory")]
+pub use secure_memory::{MemoryProtection, SecretDecryptionKey};
+#[cfg(feature = "alloc")]
+pub use share::{DecryptionShare, KeyShare};
+#[cfg(feature = "hybrid")]
+pub use signcrypt::{signcrypt, unsigncrypt, SigncryptedMessage};
+#[cfg(feature = "alloc")]
+pub use solver::{DlogSolver, SolverStatus};
+pub use stealth::{EphemeralTag, StealthAddress};
+#[cfg(all(feature = "hybrid", feature = "std"))]
+pub use stream::{STREAM_CHUNK_LEN, STREAM_VERSION};
+#[cfg(feature = "alloc")]
+pub use tally::HomomorphicTally;
+pub use universal::UniversalCiphertext;
 
 /// The group generator as a single point.
 /// If you're trying to create a scalar multiple of the generator, you probably want
@@ -58,7 +317,10 @@ mod tests {
     use rand::prelude::StdRng;
     use rand_core::SeedableRng;
 
-    use crate::{Commitment, DecryptionKey, RistrettoPoint};
+    use ff::Field;
+    use group::{Group, GroupEncoding};
+
+    use crate::{Ciphertext, CommitKey, Commitment, DecryptionKey, EncryptionKey, RistrettoPoint};
 
     // Test that encrypting a point and decrypting the result does not change a point.
     #[test]
@@ -121,7 +383,7 @@ mod tests {
     fn homomorphism_commitment() {
         let mut rng = StdRng::from_entropy();
         let decrypt_key = DecryptionKey::new(&mut rng);
-        let y = decrypt_key.encryption_key();
+        let y = &CommitKey::from_encryption_key_unchecked(decrypt_key.encryption_key());
 
         let m = Scalar::random(&mut rng);
         let r = Scalar::random(&mut rng);
@@ -137,4 +399,78 @@ mod tests {
         let sum_open = open + open2;
         assert!(sum_commitment.verify(&sum_open));
     }
+
+    // Test that the OS-randomness helpers produce fresh randomness on every call and still
+    // decrypt and verify correctly.
+    #[cfg(feature = "os-rng")]
+    #[test]
+    fn os_rng() {
+        let dk = DecryptionKey::generate();
+        assert_ne!(dk, DecryptionKey::generate());
+        let ek = dk.encryption_key();
+
+        let m = RistrettoPoint::random(&mut rand_core::OsRng);
+        let ct1 = ek.encrypt_os(m);
+        let ct2 = ek.encrypt_os(m);
+        assert_ne!(ct1, ct2);
+        assert_eq!(dk.decrypt(ct1), m);
+        assert_eq!(dk.decrypt(ct2), m);
+
+        let ct3 = ek.rerandomise_os(ct1);
+        assert_ne!(ct1, ct3);
+        assert_eq!(dk.decrypt(ct3), m);
+
+        let m = Scalar::from(7u32);
+        let (open1, mut commitment1) = Commitment::commit_os(m);
+        let (open2, commitment2) = Commitment::commit_os(m);
+        assert_ne!(commitment1, commitment2);
+        assert!(commitment1.verify(&open1));
+        assert!(commitment2.verify(&open2));
+
+        let open3 = commitment1.rerandomise_os(open1.clone());
+        assert_ne!(open1, open3);
+        assert!(commitment1.verify(&open3));
+    }
+
+    // Test that an encryption re-derived by code generic over `group::Group` matches the native API.
+    #[test]
+    fn group_generic_encryption() {
+        fn generic_encrypt<G: Group>(y: G, m: G, r: G::Scalar) -> (G, G) {
+            (G::generator() * r, m + y * r)
+        }
+
+        fn generic_decrypt<G: Group>(x: G::Scalar, ct: (G, G)) -> G {
+            ct.1 - ct.0 * x
+        }
+
+        const N: usize = 100;
+
+        let mut rng = StdRng::from_entropy();
+
+        for _ in 0..N {
+            let x = <Scalar as Field>::random(&mut rng);
+            let dk = DecryptionKey::from(x);
+            let ek = dk.encryption_key();
+
+            let m = <RistrettoPoint as Group>::random(&mut rng);
+            let r = <Scalar as Field>::random(&mut rng);
+
+            let generic = generic_encrypt(*ek.as_ref(), m, r);
+            let native = ek.encrypt_with(m, r);
+            assert_eq!(Ciphertext::from(generic), native);
+            assert_eq!(native.inner(), generic);
+            assert_eq!(generic_decrypt(x, native.inner()), dk.decrypt(native));
+
+            // Keys and commitments round-trip through the generic encoding.
+            let repr = ek.as_ref().to_bytes();
+            let y = Option::<RistrettoPoint>::from(RistrettoPoint::from_bytes(&repr)).unwrap();
+            assert_eq!(&EncryptionKey::from(y), ek);
+
+            let (open, commitment) =
+                Commitment::commit_with(x, r, &CommitKey::from_encryption_key_unchecked(ek));
+            let (key, ct) = commitment.inner();
+            assert_eq!(Commitment::from((key, ct)), commitment);
+            assert!(Commitment::from((key, ct)).verify(&open));
+        }
+    }
 }
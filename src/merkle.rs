@@ -0,0 +1,322 @@
+// Merkle trees over ciphertexts for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Ciphertext;
+
+// RFC 6962 prefixes, which keep a leaf from being passed off as an inner node and vice versa
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// A Merkle tree over a list of ciphertexts, for publishing a single root hash and proving that
+/// individual ciphertexts are in the list.
+///
+/// The tree is the one of RFC 6962 (and RFC 9162) over the 64-byte compressed ciphertexts, with
+/// SHA-256: a leaf hashes as `SHA-256(0x00 || ct)`, an inner node as `SHA-256(0x01 || left ||
+/// right)`, and a list whose length is not a power of two is split so that the left subtree is
+/// the largest power of two. Roots and proofs can be checked with tooling for certificate
+/// transparency logs. The root of an empty tree is `SHA-256("")`.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{CiphertextTree, DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+/// let ballots: Vec<_> = (0..7)
+///     .map(|_| enc_key.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+///     .collect();
+///
+/// let tree = CiphertextTree::new(&ballots);
+/// let root = tree.root();
+///
+/// let proof = tree.prove_inclusion(3).unwrap();
+/// assert!(proof.verify(&root, &ballots[3], 3));
+/// assert!(!proof.verify(&root, &ballots[4], 3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CiphertextTree {
+    // the leaf hashes, then each level above, up to the root; an unpaired last node moves up
+    // unchanged, which gives the same tree as RFC 6962's split
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl CiphertextTree {
+    /// Build the tree over `cts`, in order.
+    pub fn new(cts: &[Ciphertext]) -> CiphertextTree {
+        let mut levels = alloc::vec![cts.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        CiphertextTree { levels }
+    }
+
+    /// The number of ciphertexts in the tree.
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Whether the tree has no ciphertexts.
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// The root hash of the tree.
+    pub fn root(&self) -> [u8; 32] {
+        match self.levels.last().unwrap().first() {
+            Some(root) => *root,
+            None => Sha256::digest([]).into(),
+        }
+    }
+
+    /// Prove that the ciphertext at `index` is in the tree, or return `None` if `index` is out of
+    /// range.
+    pub fn prove_inclusion(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::with_capacity(self.levels.len() - 1);
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            // the last node of a level may have no sibling
+            if let Some(sibling) = level.get(i ^ 1) {
+                path.push(*sibling);
+            }
+            i >>= 1;
+        }
+        Some(InclusionProof {
+            tree_size: self.len() as u64,
+            path,
+        })
+    }
+}
+
+/// A proof that a ciphertext is at a given index in a [CiphertextTree], made by
+/// [CiphertextTree::prove_inclusion].
+///
+/// This is RFC 9162's audit path: the size of the tree and the sibling hashes from the leaf up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct InclusionProof {
+    tree_size: u64,
+    path: Vec<[u8; 32]>,
+}
+
+impl InclusionProof {
+    /// The number of ciphertexts in the tree the proof was made for.
+    pub fn tree_size(&self) -> u64 {
+        self.tree_size
+    }
+
+    /// The sibling hashes, from the leaf up.
+    pub fn path(&self) -> &[[u8; 32]] {
+        &self.path
+    }
+
+    /// Check that `ct` is at `index` in the tree with root `root`.
+    ///
+    /// The proof carries the size of the tree, which should also be checked against the size
+    /// published with the root.
+    pub fn verify(&self, root: &[u8; 32], ct: &Ciphertext, index: u64) -> bool {
+        if index >= self.tree_size {
+            return false;
+        }
+        // RFC 9162, section 2.1.3.2
+        let (mut f, mut s) = (index, self.tree_size - 1);
+        let mut r = leaf_hash(ct);
+        for p in &self.path {
+            if s == 0 {
+                return false;
+            }
+            if f & 1 == 1 || f == s {
+                r = node_hash(p, &r);
+                while f & 1 == 0 && f != 0 {
+                    f >>= 1;
+                    s >>= 1;
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+            f >>= 1;
+            s >>= 1;
+        }
+        s == 0 && r == *root
+    }
+}
+
+fn leaf_hash(ct: &Ciphertext) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(ct.compress().to_bytes())
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+    use sha2::{Digest, Sha256};
+
+    use super::{leaf_hash, node_hash, CiphertextTree, InclusionProof};
+    use crate::{Ciphertext, DecryptionKey, RistrettoPoint};
+
+    fn ciphertexts(n: usize, rng: &mut StdRng) -> Vec<Ciphertext> {
+        let ek = *DecryptionKey::new(rng).encryption_key();
+        (0..n)
+            .map(|_| ek.encrypt(RistrettoPoint::random(rng), rng))
+            .collect()
+    }
+
+    // RFC 6962's recursive definition of the tree hash.
+    fn mth(leaves: &[[u8; 32]]) -> [u8; 32] {
+        match leaves.len() {
+            0 => Sha256::digest([]).into(),
+            1 => leaves[0],
+            n => {
+                let k = n.next_power_of_two() / 2;
+                node_hash(&mth(&leaves[..k]), &mth(&leaves[k..]))
+            }
+        }
+    }
+
+    // Test that trees of every size up to 33, odd sizes included, have RFC 6962's root and prove
+    // every leaf at its own index only.
+    #[test]
+    fn merkle_odd_sizes() {
+        let mut rng = StdRng::seed_from_u64(490);
+        let cts = ciphertexts(33, &mut rng);
+        assert_eq!(
+            CiphertextTree::new(&[]).root(),
+            <[u8; 32]>::from(Sha256::digest([]))
+        );
+
+        for n in 0..=cts.len() {
+            let tree = CiphertextTree::new(&cts[..n]);
+            assert_eq!(tree.len(), n);
+            let leaves: Vec<_> = cts[..n].iter().map(leaf_hash).collect();
+            let root = tree.root();
+            assert_eq!(root, mth(&leaves));
+
+            for (i, ct) in cts[..n].iter().enumerate() {
+                let proof = tree.prove_inclusion(i).unwrap();
+                assert_eq!(proof.tree_size(), n as u64);
+                assert!(proof.verify(&root, ct, i as u64));
+                if n > 1 {
+                    let other = (i + 1) % n;
+                    assert!(!proof.verify(&root, ct, other as u64));
+                    assert!(!proof.verify(&root, &cts[other], i as u64));
+                }
+            }
+        }
+    }
+
+    // Test that a proof with a tampered, missing or extra sibling, or for another tree size, is
+    // rejected.
+    #[test]
+    fn merkle_tampered_path() {
+        let mut rng = StdRng::seed_from_u64(490);
+        let cts = ciphertexts(11, &mut rng);
+        let tree = CiphertextTree::new(&cts);
+        let root = tree.root();
+
+        for (i, ct) in cts.iter().enumerate() {
+            let proof = tree.prove_inclusion(i).unwrap();
+            for j in 0..proof.path.len() {
+                let mut tampered = proof.clone();
+                tampered.path[j][0] ^= 1;
+                assert!(!tampered.verify(&root, ct, i as u64));
+            }
+
+            let mut short = proof.clone();
+            short.path.pop();
+            assert!(!short.verify(&root, ct, i as u64));
+            let mut long = proof.clone();
+            long.path.push([0; 32]);
+            assert!(!long.verify(&root, ct, i as u64));
+        }
+
+        // the last leaf has no sibling at the bottom in a tree of 11, but would in one of 12
+        let resized = InclusionProof {
+            tree_size: 12,
+            ..tree.prove_inclusion(10).unwrap()
+        };
+        assert!(!resized.verify(&root, &cts[10], 10));
+
+        // an inner node cannot stand in for a leaf
+        let inner = node_hash(&leaf_hash(&cts[0]), &leaf_hash(&cts[1]));
+        assert_ne!(inner, leaf_hash(&cts[0]));
+    }
+
+    // Test that indices past the end are refused by both sides.
+    #[test]
+    fn merkle_index_out_of_range() {
+        let mut rng = StdRng::seed_from_u64(490);
+        let cts = ciphertexts(5, &mut rng);
+        let tree = CiphertextTree::new(&cts);
+        assert_eq!(tree.prove_inclusion(5), None);
+        assert_eq!(CiphertextTree::new(&[]).prove_inclusion(0), None);
+
+        let proof = tree.prove_inclusion(4).unwrap();
+        assert!(!proof.verify(&tree.root(), &cts[4], 5));
+        assert!(!proof.verify(&tree.root(), &cts[4], u64::MAX));
+    }
+
+    // Test that proofs survive a serde round trip.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn merkle_proof_serde() {
+        let mut rng = StdRng::seed_from_u64(490);
+        let cts = ciphertexts(6, &mut rng);
+        let tree = CiphertextTree::new(&cts);
+        let proof = tree.prove_inclusion(5).unwrap();
+
+        let encoded = bincode::serialize(&proof).unwrap();
+        let decoded: InclusionProof = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&tree.root(), &cts[5], 5));
+    }
+}
@@ -0,0 +1,425 @@
+// Async batch decryption and verification for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decrypting and verifying large batches from async code without stalling the executor,
+//! through [tokio](https://docs.rs/tokio/)'s blocking pool.
+//!
+//! [AsyncDecryptor] and [AsyncVerifier] split a batch into chunks (256 items by default) and run
+//! each chunk as one blocking task, with at most a fixed number of chunks in flight at once (by
+//! default the number of cores). Clones of a decryptor or verifier share that limit, so it bounds
+//! all the batches running through them together. Results come back in the order of the input,
+//! whatever order the chunks finish in.
+//!
+//! Dropping a batch future cancels it between chunks: chunks already running finish, but no
+//! further chunk starts, so a smaller chunk size makes cancellation more prompt at the cost of
+//! more tasks.
+//!
+//! The futures need a tokio runtime, and panic if polled outside one.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::nonblocking::AsyncDecryptor;
+//! use rust_elgamal::{DecryptionKey, RistrettoPoint};
+//!
+//! # tokio_test::block_on(async {
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let enc_key = dec_key.encryption_key();
+//!
+//! let ms: Vec<_> = (0..100).map(|_| RistrettoPoint::random(&mut rng)).collect();
+//! let cts: Vec<_> = ms.iter().map(|m| enc_key.encrypt(*m, &mut rng)).collect();
+//!
+//! let decryptor = AsyncDecryptor::new(dec_key).with_chunk_size(16);
+//! assert_eq!(decryptor.decrypt_batch(cts).await, ms);
+//! # });
+//! ```
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinSet};
+
+use crate::deck::ShuffleWitness;
+use crate::{Ciphertext, DecryptionKey, DleqProof, EncryptionKey, Error};
+
+const DEFAULT_CHUNK_SIZE: usize = 256;
+
+/// Decrypts batches of ciphertexts with one key on tokio's blocking pool.
+#[derive(Clone, Debug)]
+pub struct AsyncDecryptor {
+    dk: Arc<DecryptionKey>,
+    pool: Pool,
+}
+
+impl AsyncDecryptor {
+    /// A decryptor for `dk`, with the default chunk size and concurrency limit.
+    pub fn new(dk: DecryptionKey) -> AsyncDecryptor {
+        AsyncDecryptor {
+            dk: Arc::new(dk),
+            pool: Pool::new(),
+        }
+    }
+
+    /// Set the number of ciphertexts decrypted by each blocking task (at least 1).
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> AsyncDecryptor {
+        self.pool.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of blocking tasks that may run at once (at least 1), for this decryptor and
+    /// the clones made from it afterwards.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> AsyncDecryptor {
+        self.pool.set_max_concurrency(max_concurrency);
+        self
+    }
+
+    /// Decrypt every ciphertext, as [DecryptionKey::decrypt] does for one, keeping their order.
+    pub async fn decrypt_batch(&self, cts: Vec<Ciphertext>) -> Vec<RistrettoPoint> {
+        let dk = Arc::clone(&self.dk);
+        let cts = Arc::new(cts);
+        let len = cts.len();
+        let chunks = self
+            .pool
+            .run(len, move |range| {
+                cts[range]
+                    .iter()
+                    .map(|ct| dk.decrypt(*ct))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+        chunks.into_iter().flatten().collect()
+    }
+}
+
+/// Verifies batches of decryption proofs and shuffles under one key on tokio's blocking pool.
+#[derive(Clone, Debug)]
+pub struct AsyncVerifier {
+    ek: EncryptionKey,
+    pool: Pool,
+}
+
+impl AsyncVerifier {
+    /// A verifier for statements under `ek`, with the default chunk size and concurrency limit.
+    pub fn new(ek: EncryptionKey) -> AsyncVerifier {
+        AsyncVerifier {
+            ek,
+            pool: Pool::new(),
+        }
+    }
+
+    /// Set the number of items checked by each blocking task (at least 1).
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> AsyncVerifier {
+        self.pool.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of blocking tasks that may run at once (at least 1), for this verifier and
+    /// the clones made from it afterwards.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> AsyncVerifier {
+        self.pool.set_max_concurrency(max_concurrency);
+        self
+    }
+
+    /// Check proofs of correct decryption: for each `(ct, m, proof)`, that `proof` shows that
+    /// `ct` decrypts to `m` under the key, i.e. that `ct.1 - m = x * ct.0` where `x` is the secret
+    /// of the key.
+    ///
+    /// The results are in the order of the proofs, each failing with
+    /// [Error::VerificationFailed] as [DleqProof::verify] does.
+    pub async fn verify_batch(
+        &self,
+        proofs: Vec<(Ciphertext, RistrettoPoint, DleqProof)>,
+    ) -> Vec<Result<(), Error>> {
+        let ek = self.ek;
+        let proofs = Arc::new(proofs);
+        let len = proofs.len();
+        let chunks = self
+            .pool
+            .run(len, move |range| {
+                proofs[range]
+                    .iter()
+                    .map(|(ct, m, proof)| proof.verify(&ek, &ct.0, &(ct.1 - m)))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+        chunks.into_iter().flatten().collect()
+    }
+
+    /// Check that `output` is `input` shuffled and rerandomised under the key with `witness`, as
+    /// [ShuffleWitness::verify] does.
+    pub async fn verify_shuffle(
+        &self,
+        input: Vec<Ciphertext>,
+        output: Vec<Ciphertext>,
+        witness: ShuffleWitness,
+    ) -> Result<(), Error> {
+        witness.check_shape(input.len(), output.len())?;
+        let ek = self.ek;
+        let len = output.len();
+        self.pool
+            .run(len, move |range| {
+                witness.verify_range(&ek, &input, &output, range)
+            })
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+// The chunking and the concurrency limit shared by the batch types.
+#[derive(Clone, Debug)]
+struct Pool {
+    chunk_size: usize,
+    permits: Arc<Semaphore>,
+}
+
+impl Pool {
+    fn new() -> Pool {
+        let cores = std::thread::available_parallelism().map_or(1, usize::from);
+        Pool {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            permits: Arc::new(Semaphore::new(cores)),
+        }
+    }
+
+    fn set_max_concurrency(&mut self, max_concurrency: usize) {
+        self.permits = Arc::new(Semaphore::new(max_concurrency.max(1)));
+    }
+
+    // Run `work` on each chunk of `0..len` on the blocking pool, returning the results of the
+    // chunks in order. A chunk only starts once it holds a permit, and not at all once the
+    // returned future has been dropped.
+    async fn run<T, F>(&self, len: usize, work: F) -> Vec<T>
+    where
+        T: Send + 'static,
+        F: Fn(Range<usize>) -> T + Send + Sync + 'static,
+    {
+        let work = Arc::new(work);
+        let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+        let starts = (0..len).step_by(self.chunk_size);
+        let mut results: Vec<Option<T>> = starts.clone().map(|_| None).collect();
+        let mut tasks = JoinSet::new();
+
+        for (index, start) in starts.enumerate() {
+            let permit = Arc::clone(&self.permits)
+                .acquire_owned()
+                .await
+                .expect("the semaphore is never closed");
+            let range = start..len.min(start + self.chunk_size);
+            let work = Arc::clone(&work);
+            let cancelled = Arc::clone(&cancel.0);
+            tasks.spawn_blocking(move || {
+                let _permit = permit;
+                // tokio may still start a blocking task after its handle is aborted
+                if cancelled.load(Ordering::Acquire) {
+                    return (index, None);
+                }
+                (index, Some(work(range)))
+            });
+            while let Some(done) = tasks.try_join_next() {
+                store(&mut results, done);
+            }
+        }
+        while let Some(done) = tasks.join_next().await {
+            store(&mut results, done);
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every chunk ran"))
+            .collect()
+    }
+}
+
+fn store<T>(results: &mut [Option<T>], done: Result<(usize, Option<T>), JoinError>) {
+    match done {
+        Ok((index, result)) => results[index] = result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => panic!("blocking task failed: {}", err),
+    }
+}
+
+// Stops any chunk that has not started yet when the batch future is dropped.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{AsyncDecryptor, AsyncVerifier, Pool};
+    use crate::deck::Deck;
+    use crate::{DecryptionKey, DleqProof, Error, RistrettoPoint};
+
+    // Test that batches give what the sync APIs give, in order, for chunk sizes that do and do
+    // not divide the batch.
+    #[test]
+    fn async_matches_sync() {
+        let mut rng = StdRng::seed_from_u64(491);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let cts: Vec<_> = (0..50)
+            .map(|_| ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+            .collect();
+        let ms: Vec<_> = cts.iter().map(|ct| dk.decrypt(*ct)).collect();
+
+        let mut proofs: Vec<_> = cts
+            .iter()
+            .zip(&ms)
+            .map(|(ct, m)| {
+                let proof = DleqProof::prove(&dk, &ct.0, &(ct.1 - m), &mut rng);
+                (*ct, *m, proof)
+            })
+            .collect();
+        // a wrong plaintext, and a proof for another ciphertext
+        proofs[7].1 += RistrettoPoint::random(&mut rng);
+        proofs[31].2 = proofs[30].2;
+        let expected: Vec<_> = proofs
+            .iter()
+            .map(|(ct, m, proof)| proof.verify(&ek, &ct.0, &(ct.1 - m)))
+            .collect();
+        assert_eq!(expected[7], Err(Error::VerificationFailed));
+        assert_eq!(expected[31], Err(Error::VerificationFailed));
+
+        let mut deck = Deck::new_standard(&ek);
+        let input = deck.cards().to_vec();
+        let witness = deck.shuffle_pass(&mut rng);
+        let output = deck.cards().to_vec();
+        let mut tampered = output.clone();
+        tampered[51] = tampered[0];
+
+        tokio_test::block_on(async {
+            for chunk_size in [1, 7, 50, 1000] {
+                let decryptor = AsyncDecryptor::new(dk.clone()).with_chunk_size(chunk_size);
+                assert_eq!(decryptor.decrypt_batch(cts.clone()).await, ms);
+                assert_eq!(decryptor.decrypt_batch(Vec::new()).await, Vec::new());
+
+                let verifier = AsyncVerifier::new(ek)
+                    .with_chunk_size(chunk_size)
+                    .with_max_concurrency(3);
+                assert_eq!(verifier.verify_batch(proofs.clone()).await, expected);
+                let shuffled =
+                    verifier.verify_shuffle(input.clone(), output.clone(), witness.clone());
+                assert_eq!(shuffled.await, witness.verify(&ek, &input, &output));
+                let shuffled =
+                    verifier.verify_shuffle(input.clone(), tampered.clone(), witness.clone());
+                assert_eq!(shuffled.await, Err(Error::VerificationFailed));
+                let shuffled =
+                    verifier.verify_shuffle(input.clone(), output[1..].to_vec(), witness.clone());
+                assert_eq!(
+                    shuffled.await,
+                    Err(Error::LengthMismatch {
+                        expected: 52,
+                        got: 51
+                    })
+                );
+            }
+        });
+    }
+
+    // Test that dropping a batch part way stops the chunks that have not started.
+    #[test]
+    fn async_cancel_mid_batch() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let started = Arc::new(AtomicUsize::new(0));
+        let mut pool = Pool::new();
+        pool.chunk_size = 1;
+        pool.set_max_concurrency(2);
+
+        let counter = Arc::clone(&started);
+        let batch = pool.run(100, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+        });
+        runtime.block_on(async {
+            let timed_out = tokio::time::timeout(Duration::from_millis(50), batch).await;
+            assert!(timed_out.is_err());
+            // let the chunks that were running finish
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        });
+        let after_cancel = started.load(Ordering::SeqCst);
+        assert!(
+            (1..=8).contains(&after_cancel),
+            "{} chunks ran",
+            after_cancel
+        );
+
+        runtime.block_on(async { tokio::time::sleep(Duration::from_millis(100)).await });
+        assert_eq!(started.load(Ordering::SeqCst), after_cancel);
+    }
+
+    // Test that batches sharing a limit never run more chunks at once than it allows, and that
+    // the results stay in order however the chunks finish.
+    #[test]
+    fn async_concurrency_limit() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .build()
+            .unwrap();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut pool = Pool::new();
+        pool.chunk_size = 2;
+        pool.set_max_concurrency(3);
+
+        let batch = |pool: Pool| {
+            let running = Arc::clone(&running);
+            let peak = Arc::clone(&peak);
+            async move {
+                pool.run(40, move |range| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    // later chunks finish first
+                    std::thread::sleep(Duration::from_millis(1 + (40 - range.start as u64) / 4));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    range.start
+                })
+                .await
+            }
+        };
+        let (a, b) = runtime.block_on(async {
+            let a = tokio::spawn(batch(pool.clone()));
+            let b = tokio::spawn(batch(pool.clone()));
+            (a.await.unwrap(), b.await.unwrap())
+        });
+
+        let expected: Vec<usize> = (0..40).step_by(2).collect();
+        assert_eq!(a, expected);
+        assert_eq!(b, expected);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(peak.load(Ordering::SeqCst) >= 2);
+    }
+}
@@ -14,26 +14,93 @@
 // limitations under the License.
 
 use curve25519_dalek::Scalar;
-use std::{
+use ff::Field;
+use core::{
     fmt::{Debug, Formatter},
-    ops::{Add, Mul, Neg, Sub},
+    ops::{Add, Mul, MulAssign, Neg, Sub},
 };
 
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "serde-secret")]
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{Commitment, ElGamalGroup, ScalarOperand, SecretScalar};
 
 /// Open is the pair of the blinding factor and the message used in the commitment.
-#[derive(Copy, Clone, Eq, PartialEq)]
-#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
-pub struct Open(pub(crate) Scalar, pub(crate) Scalar);
+///
+/// Equality compares both scalars in constant time, through [ConstantTimeEq].
+///
+/// Opens are `Clone` but not `Copy`, so the secret is only duplicated where the code says so, and
+/// with the `zeroize` feature both scalars are wiped when an open is dropped.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(concrete(F = crate::ts::Scalar)))]
+pub struct Open<F: SecretScalar = Scalar>(pub(crate) F, pub(crate) F);
+
+impl Open {
+    /// The committed message `m` as a small integer, or `None` if it does not fit in a `u64`.
+    ///
+    /// This is how to recover amounts, counts and the like from the opening of a commitment to
+    /// `Scalar::from(n)`.
+    pub fn message_u64(&self) -> Option<u64> {
+        let bytes = self.1.to_bytes();
+        if bytes[8..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let mut low = [0u8; 8];
+        low.copy_from_slice(&bytes[..8]);
+        Some(u64::from_le_bytes(low))
+    }
+}
+
+impl<F: Field + SecretScalar> Open<F> {
+    /// Check that this opens `commitment`, in constant time.
+    ///
+    /// This recomputes the commitment and compares the encodings without branching on secret
+    /// data, as [Commitment::verify_ct] does, so it is the comparison to use when checking an
+    /// open received from someone else against an expected commitment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use rust_elgamal::{Commitment, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let (open, commitment) = Commitment::commit(Scalar::from(8u32), &mut rng);
+    /// assert!(bool::from(open.ct_verify_against(&commitment)));
+    /// ```
+    pub fn ct_verify_against<G: ElGamalGroup<Scalar = F>>(
+        &self,
+        commitment: &Commitment<G>,
+    ) -> Choice {
+        commitment.verify_ct(self)
+    }
+}
+
+impl<F: Field + SecretScalar> ConstantTimeEq for Open<F> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0) & self.1.ct_eq(&other.1)
+    }
+}
+
+impl<F: Field + SecretScalar> PartialEq for Open<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
 
-impl Debug for Open {
+impl<F: Field + SecretScalar> Eq for Open<F> {}
+
+impl<F: Field + SecretScalar> Debug for Open<F> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Open({:?}, {:?})", self.0, self.1)
+        f.write_str("Open(<redacted>)")
     }
 }
 
-impl Add for Open {
+impl<F: Field + SecretScalar> Add for Open<F> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -41,31 +108,31 @@ impl Add for Open {
     }
 }
 
-impl Add for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Add for &Open<F> {
+    type Output = Open<F>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Open(&self.0 + &rhs.0, &self.1 + &rhs.1)
+        Open(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Add<&Open> for Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Add<&Open<F>> for Open<F> {
+    type Output = Open<F>;
 
-    fn add(self, rhs: &Open) -> Self::Output {
-        Open(self.0 + &rhs.0, self.1 + &rhs.1)
+    fn add(self, rhs: &Open<F>) -> Self::Output {
+        Open(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Add<Open> for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Add<Open<F>> for &Open<F> {
+    type Output = Open<F>;
 
-    fn add(self, rhs: Open) -> Self::Output {
-        Open(&self.0 + rhs.0, &self.1 + rhs.1)
+    fn add(self, rhs: Open<F>) -> Self::Output {
+        Open(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
-impl Sub for Open {
+impl<F: Field + SecretScalar> Sub for Open<F> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -73,86 +140,130 @@ impl Sub for Open {
     }
 }
 
-impl Sub for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Sub for &Open<F> {
+    type Output = Open<F>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Open(&self.0 - &rhs.0, &self.1 - &rhs.1)
+        Open(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<&Open> for Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Sub<&Open<F>> for Open<F> {
+    type Output = Open<F>;
 
-    fn sub(self, rhs: &Open) -> Self::Output {
-        Open(self.0 - &rhs.0, self.1 - &rhs.1)
+    fn sub(self, rhs: &Open<F>) -> Self::Output {
+        Open(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Sub<Open> for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Sub<Open<F>> for &Open<F> {
+    type Output = Open<F>;
 
-    fn sub(self, rhs: Open) -> Self::Output {
-        Open(&self.0 - rhs.0, &self.1 - rhs.1)
+    fn sub(self, rhs: Open<F>) -> Self::Output {
+        Open(self.0 - rhs.0, self.1 - rhs.1)
     }
 }
 
-impl Neg for Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Neg for Open<F> {
+    type Output = Open<F>;
 
     fn neg(self) -> Self::Output {
         Open(-self.0, -self.1)
     }
 }
 
-impl Neg for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar> Neg for &Open<F> {
+    type Output = Open<F>;
 
     fn neg(self) -> Self::Output {
         Open(-self.0, -self.1)
     }
 }
 
-impl Mul<Scalar> for Open {
-    type Output = Open;
+// Generic over the operand for the same reason as the `Mul` impls on `Ciphertext`.
+impl<F: Field + SecretScalar, S: ScalarOperand<F>> Mul<S> for Open<F> {
+    type Output = Open<F>;
 
-    fn mul(self, rhs: Scalar) -> Self::Output {
+    fn mul(self, rhs: S) -> Self::Output {
+        let rhs = rhs.to_scalar();
         Open(self.0 * rhs, self.1 * rhs)
     }
 }
 
-impl Mul<Scalar> for &Open {
-    type Output = Open;
+impl<F: Field + SecretScalar, S: ScalarOperand<F>> Mul<S> for &Open<F> {
+    type Output = Open<F>;
 
-    fn mul(self, rhs: Scalar) -> Self::Output {
+    fn mul(self, rhs: S) -> Self::Output {
+        let rhs = rhs.to_scalar();
         Open(self.0 * rhs, self.1 * rhs)
     }
 }
 
-impl Mul<&Scalar> for Open {
-    type Output = Open;
-
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Open(self.0 * rhs, self.1 * rhs)
+impl<F: Field + SecretScalar, S: ScalarOperand<F>> MulAssign<S> for Open<F> {
+    fn mul_assign(&mut self, rhs: S) {
+        let rhs = rhs.to_scalar();
+        self.0 *= rhs;
+        self.1 *= rhs;
     }
 }
 
-impl Mul<&Scalar> for &Open {
-    type Output = Open;
+#[cfg(feature = "zeroize")]
+impl<F: SecretScalar> Zeroize for Open<F> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+        self.1.zeroize();
+    }
+}
 
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        Open(self.0 * rhs, self.1 * rhs)
+#[cfg(feature = "zeroize")]
+impl<F: SecretScalar> Drop for Open<F> {
+    fn drop(&mut self) {
+        self.zeroize();
     }
 }
 
-#[cfg(feature = "enable-serde")]
+#[cfg(feature = "zeroize")]
+impl<F: SecretScalar> ZeroizeOnDrop for Open<F> {}
+
+#[cfg(feature = "alloc")]
 #[cfg(test)]
 mod tests {
     use rand::{rngs::StdRng, SeedableRng};
+    use subtle::ConstantTimeEq;
+
+    use super::Open;
+    use crate::{Commitment, DecryptionKey, Scalar};
 
-    use crate::{Commitment, Scalar};
+    // Test that small messages are recovered and large ones are not.
+    #[test]
+    fn message_u64() {
+        let mut rng = StdRng::from_entropy();
+
+        for n in [0, 1, 1000, u64::MAX] {
+            let (open, _) = Commitment::commit(Scalar::from(n), &mut rng);
+            assert_eq!(open.message_u64(), Some(n));
+        }
+
+        let (open, _) = Commitment::commit(Scalar::from(u64::MAX) + Scalar::ONE, &mut rng);
+        assert_eq!(open.message_u64(), None);
+        let (open, _) = Commitment::commit(-Scalar::ONE, &mut rng);
+        assert_eq!(open.message_u64(), None);
+    }
+
+    // Test that debug output shows neither scalar of an open.
+    #[test]
+    fn debug_redacted() {
+        let mut rng = StdRng::seed_from_u64(505);
+        let (open, _) = Commitment::commit(Scalar::random(&mut rng), &mut rng);
+        let printed = alloc::format!("{:?}", open);
+        assert_eq!(printed, "Open(<redacted>)");
+        for scalar in [open.0, open.1] {
+            assert!(!printed.contains(&alloc::format!("{:?}", scalar)));
+        }
+    }
 
     // Test that serialising and deserialising an open.
+    #[cfg(feature = "serde-secret")]
     #[test]
     fn serde_open() {
         const N: usize = 100;
@@ -170,4 +281,88 @@ mod tests {
             assert_eq!(open, decoded);
         }
     }
+
+    // Test that multiplying commitments and opens by integers agrees with the scalar path and
+    // keeps them consistent.
+    #[test]
+    fn mul_integer() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let commit_key = crate::CommitKey::from_encryption_key_unchecked(dk.encryption_key());
+        let (open, commitment) =
+            Commitment::commit_with(Scalar::from(5u32), Scalar::from(9u32), &commit_key);
+        let (open_ref, commitment_ref) = (&open, &commitment);
+
+        for n in [0u32, 2, 3, 1000, u32::MAX] {
+            let weighted = commitment * n;
+            assert_eq!(weighted, commitment * Scalar::from(n));
+            assert_eq!(commitment_ref * u64::from(n), weighted);
+            assert_eq!(open.clone() * n, open.clone() * Scalar::from(n));
+            assert!(weighted.verify(&(open_ref * u64::from(n))));
+
+            let (mut c, mut o) = (commitment, open.clone());
+            c *= n;
+            o *= n;
+            assert!(c.verify(&o));
+        }
+        assert_eq!((open_ref * 3u64).message_u64(), Some(15));
+    }
+
+    // Test that constant-time equality agrees with comparing the scalars, for random opens and
+    // for opens differing in a single byte of one scalar.
+    #[test]
+    fn ct_eq_open() {
+        let mut rng = StdRng::seed_from_u64(480);
+        let (open, commitment) = Commitment::commit(Scalar::random(&mut rng), &mut rng);
+        let (other, _) = Commitment::commit(Scalar::random(&mut rng), &mut rng);
+
+        let same = |a: &Open, b: &Open| a.0 == b.0 && a.1 == b.1;
+        assert!(bool::from(open.ct_eq(&open)));
+        assert_eq!(open == other, same(&open, &other));
+        assert_eq!(open, Open(open.0, open.1));
+
+        for i in 0..32 {
+            let mut bytes = open.1.to_bytes();
+            bytes[i] ^= 1;
+            let close = Open(open.0, Scalar::from_bytes_mod_order(bytes));
+            assert!(!same(&open, &close));
+            assert!(!bool::from(open.ct_eq(&close)));
+            assert_ne!(open, close);
+            assert!(!bool::from(close.ct_verify_against(&commitment)));
+
+            let mut bytes = open.0.to_bytes();
+            bytes[i] ^= 1;
+            let close = Open(Scalar::from_bytes_mod_order(bytes), open.1);
+            assert_ne!(open, close);
+            assert!(!bool::from(close.ct_verify_against(&commitment)));
+        }
+
+        assert!(bool::from(open.ct_verify_against(&commitment)));
+        assert!(!bool::from(other.ct_verify_against(&commitment)));
+        assert_eq!(
+            bool::from(open.ct_verify_against(&commitment)),
+            commitment.verify(&open)
+        );
+    }
+
+    // Test that decryption keys and opens are wiped on drop, and that wiping clears the scalars.
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_secrets() {
+        use zeroize::{Zeroize, ZeroizeOnDrop};
+
+        fn wiped_on_drop<T: ZeroizeOnDrop>() {}
+        wiped_on_drop::<DecryptionKey>();
+        wiped_on_drop::<Open>();
+
+        let mut rng = StdRng::seed_from_u64(502);
+        let (mut open, commitment) = Commitment::commit(Scalar::from(5u32), &mut rng);
+        assert!(commitment.verify(&open));
+        open.zeroize();
+        assert_eq!((open.0, open.1), (Scalar::ZERO, Scalar::ZERO));
+
+        let mut dk = DecryptionKey::new(&mut rng);
+        dk.zeroize();
+        assert_eq!(dk.secret, Scalar::ZERO);
+    }
 }
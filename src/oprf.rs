@@ -0,0 +1,257 @@
+// Oblivious pseudorandom function for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The 2HashDH oblivious pseudorandom function, keyed by a [DecryptionKey].
+//!
+//! The PRF is `F(x, input) = H(input, x * H'(input))`. A client [blind]s the hashed input with a
+//! random factor, the server multiplies the blinded element by its secret
+//! ([DecryptionKey::oprf_evaluate]), and the client removes the blinding and hashes the result
+//! ([finalize]). The server learns neither the input nor the output.
+//!
+//! In the verifiable variant ([DecryptionKey::oprf_evaluate_verifiable] and [verify_finalize])
+//! the server also proves, with a [DleqProof], that it used the secret matching its published
+//! [EncryptionKey], so it cannot single out a client by evaluating with a different key.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::{oprf, DecryptionKey};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let server_key = DecryptionKey::new(&mut rng);
+//!
+//! let (blinded, factor) = oprf::blind(b"token", &mut rng);
+//! let evaluated = server_key.oprf_evaluate(&blinded);
+//! let output = oprf::finalize(b"token", &evaluated, &factor);
+//!
+//! let (blinded, factor) = oprf::blind(b"token", &mut rng);
+//! let evaluated = server_key.oprf_evaluate(&blinded);
+//! assert_eq!(oprf::finalize(b"token", &evaluated, &factor), output);
+//! ```
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_to_point;
+use crate::{DecryptionKey, DleqProof, EncryptionKey, Error};
+
+const HASH_TO_POINT_DOMAIN: &[u8] = b"rust-elgamal oprf input";
+const FINALIZE_DOMAIN: &[u8] = b"rust-elgamal oprf output";
+
+/// A hashed input multiplied by the client's blinding factor, sent to the server.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct BlindedElement(pub(crate) RistrettoPoint);
+
+/// A blinded element multiplied by the server's secret, sent back to the client.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EvaluatedElement(pub(crate) RistrettoPoint);
+
+/// The client's secret blinding factor, kept until [finalize].
+#[derive(Clone)]
+pub struct BlindingFactor(pub(crate) Scalar);
+
+impl ConstantTimeEq for BlindingFactor {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for BlindingFactor {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for BlindingFactor {}
+
+impl Debug for BlindingFactor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("BlindingFactor(<redacted>)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for BlindingFactor {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindingFactor {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for BlindingFactor {}
+
+impl BlindedElement {
+    /// Returns the underlying point. Intended for advanced use only.
+    pub fn inner(&self) -> RistrettoPoint {
+        self.0
+    }
+}
+
+impl EvaluatedElement {
+    /// Returns the underlying point. Intended for advanced use only.
+    pub fn inner(&self) -> RistrettoPoint {
+        self.0
+    }
+}
+
+/// Hash `input` to a point and blind it with a random factor.
+pub fn blind<R: RngCore + CryptoRng>(
+    input: &[u8],
+    rng: &mut R,
+) -> (BlindedElement, BlindingFactor) {
+    let mut r = Scalar::random(rng);
+    while r == Scalar::ZERO {
+        r = Scalar::random(rng);
+    }
+    let p = hash_to_point(HASH_TO_POINT_DOMAIN, input);
+    (BlindedElement(p * r), BlindingFactor(r))
+}
+
+/// Remove the blinding from the server's evaluation and hash it, with the input, to the PRF
+/// output.
+pub fn finalize(input: &[u8], evaluated: &EvaluatedElement, factor: &BlindingFactor) -> [u8; 64] {
+    let n = evaluated.0 * factor.0.invert();
+    Sha512::new()
+        .chain_update((FINALIZE_DOMAIN.len() as u64).to_le_bytes())
+        .chain_update(FINALIZE_DOMAIN)
+        .chain_update((input.len() as u64).to_le_bytes())
+        .chain_update(input)
+        .chain_update(n.compress().as_bytes())
+        .finalize()
+        .into()
+}
+
+/// Check the server's proof that it evaluated with the secret of `ek`, then [finalize].
+///
+/// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if the proof does not
+/// verify.
+pub fn verify_finalize(
+    input: &[u8],
+    ek: &EncryptionKey,
+    blinded: &BlindedElement,
+    evaluated: &EvaluatedElement,
+    proof: &DleqProof,
+    factor: &BlindingFactor,
+) -> Result<[u8; 64], Error> {
+    proof.verify(ek, &blinded.0, &evaluated.0)?;
+    Ok(finalize(input, evaluated, factor))
+}
+
+impl DecryptionKey {
+    /// Evaluate the PRF on a client's blinded element.
+    pub fn oprf_evaluate(&self, blinded: &BlindedElement) -> EvaluatedElement {
+        EvaluatedElement(blinded.0 * self.secret)
+    }
+
+    /// Evaluate the PRF on a client's blinded element, with a proof that the secret of this key
+    /// was used.
+    pub fn oprf_evaluate_verifiable<R: RngCore + CryptoRng>(
+        &self,
+        blinded: &BlindedElement,
+        rng: &mut R,
+    ) -> (EvaluatedElement, DleqProof) {
+        let evaluated = self.oprf_evaluate(blinded);
+        let proof = DleqProof::prove(self, &blinded.0, &evaluated.0, rng);
+        (evaluated, proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::oprf;
+    use crate::{DecryptionKey, Error};
+
+    // Test that the output does not depend on the blinding, but does on the input and key.
+    #[test]
+    fn oprf_deterministic() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let eval = |dk: &DecryptionKey, input: &[u8], rng: &mut StdRng| {
+            let (blinded, factor) = oprf::blind(input, rng);
+            oprf::finalize(input, &dk.oprf_evaluate(&blinded), &factor)
+        };
+
+        let (b1, _) = oprf::blind(b"input", &mut rng);
+        let (b2, _) = oprf::blind(b"input", &mut rng);
+        assert_ne!(b1, b2);
+
+        let out = eval(&dk, b"input", &mut rng);
+        for _ in 0..10 {
+            assert_eq!(eval(&dk, b"input", &mut rng), out);
+        }
+        assert_ne!(eval(&dk, b"other input", &mut rng), out);
+        assert_ne!(eval(&other, b"input", &mut rng), out);
+    }
+
+    // Test that the verifiable mode accepts honest evaluations and rejects a swapped key.
+    #[test]
+    fn oprf_verifiable() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let (blinded, factor) = oprf::blind(b"input", &mut rng);
+        let (evaluated, proof) = dk.oprf_evaluate_verifiable(&blinded, &mut rng);
+        let out = oprf::verify_finalize(b"input", ek, &blinded, &evaluated, &proof, &factor);
+        assert_eq!(out, Ok(oprf::finalize(b"input", &evaluated, &factor)));
+
+        // the server evaluates with a different key than the one it published
+        let (evaluated, proof) = other.oprf_evaluate_verifiable(&blinded, &mut rng);
+        assert_eq!(
+            oprf::verify_finalize(b"input", ek, &blinded, &evaluated, &proof, &factor),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that the blinding factor's Debug output does not reveal the scalar.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn blinding_factor_redacted() {
+        let mut rng = StdRng::seed_from_u64(434);
+        let (_, factor) = oprf::blind(b"input", &mut rng);
+
+        let printed = alloc::format!("{:?}", factor);
+        assert_eq!(printed, "BlindingFactor(<redacted>)");
+        assert!(!printed.contains(&alloc::format!("{:?}", factor.0)));
+        assert_eq!(factor.clone(), factor);
+    }
+}
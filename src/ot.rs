@@ -0,0 +1,273 @@
+// Oblivious transfer for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! 1-of-2 oblivious transfer in the style of Bellare and Micali.
+//!
+//! The sender publishes a random base key `C` whose discrete logarithm nobody knows. The receiver,
+//! wanting message `b`, generates a decryption key for slot `b` and sets the other slot's key to
+//! `C` minus it, so it can know the secret of at most one of the two keys and the sender cannot
+//! tell which. The sender encrypts each message under its slot's key, and the receiver decrypts
+//! the one it can.
+//!
+//! Messages are either points, sent as ElGamal ciphertexts, or 32-byte strings, masked with a key
+//! derived from the ElGamal shared point.
+//!
+//! This is secure against semi-honest parties only; a malicious receiver is prevented from
+//! learning both messages by the sum check on its keys, but nothing stops a malicious sender
+//! from sending garbage.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::ot::{self, ReceiverSetup};
+//!
+//! let mut rng = StdRng::from_entropy();
+//!
+//! // sender
+//! let base = ot::sender_base(&mut rng);
+//! // receiver
+//! let (setup, keys) = ReceiverSetup::choose(true, &base, &mut rng);
+//! // sender
+//! let sent = ot::send_bytes(&base, &keys, &[0u8; 32], &[1u8; 32], &mut rng).unwrap();
+//! // receiver
+//! assert_eq!(setup.recover_bytes(&sent), [1u8; 32]);
+//! ```
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{kdf, xor32};
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE};
+
+const KDF_DOMAIN: &[u8] = b"rust-elgamal ot";
+
+/// Generate the sender's base key: a random point whose discrete logarithm is unknown to
+/// everyone, including the sender.
+pub fn sender_base<R: RngCore + CryptoRng>(rng: &mut R) -> EncryptionKey {
+    EncryptionKey::from(RistrettoPoint::random(rng))
+}
+
+/// The receiver's state: its choice and the decryption key of the chosen slot.
+///
+/// Both are secret, so debug output leaves them out.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ReceiverSetup {
+    choice: bool,
+    secret: DecryptionKey,
+}
+
+impl Debug for ReceiverSetup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ReceiverSetup(<redacted>)")
+    }
+}
+
+/// The receiver's message to the sender: one encryption key per slot, summing to the base key.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct ReceiverKeys(pub [EncryptionKey; 2]);
+
+/// The sender's message when transferring points: one ciphertext per slot.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SenderPoints(pub [Ciphertext; 2]);
+
+/// The sender's message when transferring 32-byte strings: for each slot, the blinding point
+/// `rG` and the masked message.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SenderBytes(pub [(RistrettoPoint, [u8; 32]); 2]);
+
+impl ReceiverSetup {
+    /// Choose slot `choice` (`false` for 0, `true` for 1) against the sender's `base` key.
+    ///
+    /// Returns the receiver's state and the keys to send to the sender, of which the receiver
+    /// knows the secret for the chosen slot only.
+    pub fn choose<R: RngCore + CryptoRng>(
+        choice: bool,
+        base: &EncryptionKey,
+        rng: &mut R,
+    ) -> (ReceiverSetup, ReceiverKeys) {
+        let secret = DecryptionKey::new(rng);
+        let chosen = *secret.encryption_key();
+        let other = EncryptionKey::from(base.0 - chosen.0);
+        let keys = if choice {
+            [other, chosen]
+        } else {
+            [chosen, other]
+        };
+        (ReceiverSetup { choice, secret }, ReceiverKeys(keys))
+    }
+
+    /// The slot chosen.
+    pub fn choice(&self) -> bool {
+        self.choice
+    }
+
+    /// Recover the chosen point.
+    pub fn recover_point(&self, sent: &SenderPoints) -> RistrettoPoint {
+        self.secret.decrypt(sent.0[self.choice as usize])
+    }
+
+    /// Recover the chosen 32-byte string.
+    pub fn recover_bytes(&self, sent: &SenderBytes) -> [u8; 32] {
+        let (r_g, masked) = &sent.0[self.choice as usize];
+        let shared = r_g * self.secret.secret;
+        xor32(masked, &kdf(KDF_DOMAIN, &[&shared]))
+    }
+}
+
+// The receiver can only know the secret of one slot if the keys sum to the base.
+fn check_keys(base: &EncryptionKey, keys: &ReceiverKeys) -> Result<(), Error> {
+    if keys.0[0].0 + keys.0[1].0 != base.0 {
+        return Err(Error::VerificationFailed);
+    }
+    Ok(())
+}
+
+/// Encrypt the points `m0` and `m1` under the receiver's keys for slots 0 and 1.
+///
+/// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if the keys do not
+/// sum to `base`, which would let the receiver know both secrets.
+pub fn send_points<R: RngCore + CryptoRng>(
+    base: &EncryptionKey,
+    keys: &ReceiverKeys,
+    m0: RistrettoPoint,
+    m1: RistrettoPoint,
+    rng: &mut R,
+) -> Result<SenderPoints, Error> {
+    check_keys(base, keys)?;
+    Ok(SenderPoints([
+        keys.0[0].encrypt(m0, rng),
+        keys.0[1].encrypt(m1, rng),
+    ]))
+}
+
+/// Mask the strings `m0` and `m1` with keys derived from the receiver's keys for slots 0 and 1.
+///
+/// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if the keys do not
+/// sum to `base`, which would let the receiver know both secrets.
+pub fn send_bytes<R: RngCore + CryptoRng>(
+    base: &EncryptionKey,
+    keys: &ReceiverKeys,
+    m0: &[u8; 32],
+    m1: &[u8; 32],
+    rng: &mut R,
+) -> Result<SenderBytes, Error> {
+    check_keys(base, keys)?;
+    let mut mask = |key: &EncryptionKey, m: &[u8; 32]| {
+        let r = Scalar::random(rng);
+        let shared = key.0 * r;
+        (&r * GENERATOR_TABLE, xor32(m, &kdf(KDF_DOMAIN, &[&shared])))
+    };
+    Ok(SenderBytes([mask(&keys.0[0], m0), mask(&keys.0[1], m1)]))
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::ot::{self, ReceiverKeys, ReceiverSetup};
+    use crate::{EncryptionKey, Error, RistrettoPoint};
+
+    // Test that the receiver gets exactly the chosen message, for both kinds of payload, and that
+    // debug output does not show the choice.
+    #[test]
+    fn ot_receives_chosen() {
+        const N: usize = 20;
+
+        let mut rng = StdRng::from_entropy();
+
+        for i in 0..N {
+            let choice = i % 2 == 1;
+            let base = ot::sender_base(&mut rng);
+            let (setup, keys) = ReceiverSetup::choose(choice, &base, &mut rng);
+            assert_eq!(setup.choice(), choice);
+            assert_eq!(alloc::format!("{:?}", setup), "ReceiverSetup(<redacted>)");
+
+            let m = [
+                RistrettoPoint::random(&mut rng),
+                RistrettoPoint::random(&mut rng),
+            ];
+            let sent = ot::send_points(&base, &keys, m[0], m[1], &mut rng).unwrap();
+            assert_eq!(setup.recover_point(&sent), m[choice as usize]);
+            // the other slot does not decrypt under the receiver's key
+            let flipped = ReceiverSetup {
+                choice: !choice,
+                ..setup.clone()
+            };
+            assert_ne!(flipped.recover_point(&sent), m[!choice as usize]);
+
+            let b = [[1u8; 32], [2u8; 32]];
+            let sent = ot::send_bytes(&base, &keys, &b[0], &b[1], &mut rng).unwrap();
+            assert_eq!(setup.recover_bytes(&sent), b[choice as usize]);
+            assert_ne!(flipped.recover_bytes(&sent), b[!choice as usize]);
+        }
+    }
+
+    // Test that keys which do not sum to the base are refused.
+    #[test]
+    fn ot_rejects_bad_keys() {
+        let mut rng = StdRng::from_entropy();
+        let base = ot::sender_base(&mut rng);
+        let (_, ReceiverKeys([k0, _])) = ReceiverSetup::choose(false, &base, &mut rng);
+        let forged = ReceiverKeys([k0, EncryptionKey::from(RistrettoPoint::random(&mut rng))]);
+
+        let m = RistrettoPoint::random(&mut rng);
+        assert_eq!(
+            ot::send_points(&base, &forged, m, m, &mut rng),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            ot::send_bytes(&base, &forged, &[0; 32], &[0; 32], &mut rng),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that the receiver's keys look the same whichever slot is chosen: each bit of the
+    // slot-0 key is set about half the time for both choices.
+    #[test]
+    fn ot_choice_blindness() {
+        const N: usize = 400;
+
+        let mut rng = StdRng::from_entropy();
+        let base = ot::sender_base(&mut rng);
+
+        for choice in [false, true] {
+            let mut ones = [0usize; 8];
+            for _ in 0..N {
+                let (_, ReceiverKeys([k0, _])) = ReceiverSetup::choose(choice, &base, &mut rng);
+                let byte = k0.as_ref().compress().as_bytes()[1];
+                for (bit, count) in ones.iter_mut().enumerate() {
+                    *count += (byte >> bit & 1) as usize;
+                }
+            }
+            // 5 standard deviations either side of N/2
+            for count in ones {
+                assert!(count > N / 2 - 50 && count < N / 2 + 50, "{}", count);
+            }
+        }
+    }
+}
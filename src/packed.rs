@@ -0,0 +1,256 @@
+// Packed counters for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Several small counters packed into the exponent of a single ciphertext.
+//!
+//! Counters are packed as the limbs of a base-`2^bits_per_counter` integer, least significant
+//! first, so adding two packed ciphertexts adds the counters limb by limb. A limb carries into its
+//! neighbour once it exceeds `2^bits_per_counter - 1`; if every ciphertext added contributes at
+//! most `w` to each counter, `(2^bits_per_counter - 1) / w` of them can be summed safely (255
+//! one-vote ballots with 8-bit counters, for example).
+
+use curve25519_dalek::scalar::Scalar;
+use ff::PrimeField;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::dlog::bounded_dlog;
+#[cfg(feature = "alloc")]
+use crate::DecryptionKey;
+use crate::{Ciphertext, ElGamalGroup, EncryptionKey};
+
+/// Pack `values` into a scalar as the limbs of a base-`2^bits_per_counter` integer, least
+/// significant first.
+///
+/// # Panics
+///
+/// Panics if `bits_per_counter` is zero, if the counters need more than 64 bits in total, or if
+/// a value does not fit in `bits_per_counter` bits.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::{pack_counters, Scalar};
+///
+/// assert_eq!(pack_counters(&[1, 2], 8), Scalar::from(0x0201u64));
+/// ```
+pub fn pack_counters(values: &[u64], bits_per_counter: usize) -> Scalar {
+    pack(values, bits_per_counter)
+}
+
+fn pack<F: PrimeField>(values: &[u64], bits_per_counter: usize) -> F {
+    assert!(bits_per_counter > 0, "bits_per_counter must be positive");
+    assert!(
+        values.len() * bits_per_counter <= 64,
+        "packed counters must fit in 64 bits"
+    );
+
+    let mut packed = 0u64;
+    for (i, value) in values.iter().enumerate() {
+        assert!(
+            bits_per_counter == 64 || *value >> bits_per_counter == 0,
+            "counter does not fit in bits_per_counter bits"
+        );
+        packed |= value << (i * bits_per_counter);
+    }
+    F::from(packed)
+}
+
+impl<G: ElGamalGroup> EncryptionKey<G> {
+    /// Encrypt the counters `values`, packed as by [pack_counters](crate::pack_counters), with a
+    /// randomly-generated blinding factor.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [pack_counters](crate::pack_counters).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let ct = enc_key.encrypt_packed(&[1, 0, 0, 1], 8, &mut rng);
+    /// ```
+    pub fn encrypt_packed<R: RngCore + CryptoRng>(
+        &self,
+        values: &[u64],
+        bits_per_counter: usize,
+        rng: &mut R,
+    ) -> Ciphertext<G> {
+        self.exp_encrypt(pack(values, bits_per_counter), rng)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Decrypt `counters` packed counters of `bits_per_counter` bits each, where every counter is
+    /// known to be at most `max_total`.
+    ///
+    /// Returns `None` if the parameters cannot describe a valid packing (`max_total` must fit in
+    /// a limb and the counters in 64 bits), or if the plaintext is not a packing of counters that
+    /// are all at most `max_total`, which is how overflowing limbs show up. A limb that overflowed
+    /// by a whole multiple of its base cannot be told apart from a carry, so `max_total` must be a
+    /// genuine bound.
+    ///
+    /// The exponent is recovered by a bounded discrete-logarithm search over the largest packing
+    /// allowed by `max_total`, costing about `2 * 2^(counters * bits_per_counter / 2)` group
+    /// operations in the worst case.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let enc_key = dec_key.encryption_key();
+    ///
+    /// let ct1 = enc_key.encrypt_packed(&[1, 0, 0, 1], 8, &mut rng);
+    /// let ct2 = enc_key.encrypt_packed(&[0, 0, 1, 1], 8, &mut rng);
+    /// let counts = dec_key.decrypt_packed(ct1 + ct2, 4, 8, 2);
+    /// assert_eq!(counts, Some(vec![1, 0, 1, 2]));
+    /// ```
+    pub fn decrypt_packed(
+        &self,
+        ct: Ciphertext<G>,
+        counters: usize,
+        bits_per_counter: usize,
+        max_total: u64,
+    ) -> Option<Vec<u64>> {
+        let total_bits = counters.checked_mul(bits_per_counter)?;
+        if counters == 0 || bits_per_counter == 0 || total_bits > 64 {
+            return None;
+        }
+        let mask = u64::MAX >> (64 - bits_per_counter);
+        if max_total > mask {
+            return None;
+        }
+
+        // the largest packing allowed: every limb equal to max_total
+        let bound = (0..counters).fold(0u64, |acc, i| acc | max_total << (i * bits_per_counter));
+        let packed = bounded_dlog(&self.decrypt(ct), bound)?;
+
+        let values: Vec<u64> = (0..counters)
+            .map(|i| (packed >> (i * bits_per_counter)) & mask)
+            .collect();
+        if values.iter().any(|v| *v > max_total) {
+            return None;
+        }
+        Some(values)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use crate::{pack_counters, Ciphertext, DecryptionKey, Identity, Scalar};
+
+    // Test packing against hand-computed values, including full limbs.
+    #[test]
+    fn pack_values() {
+        assert_eq!(pack_counters(&[], 8), Scalar::ZERO);
+        assert_eq!(pack_counters(&[3, 0, 1], 4), Scalar::from(0x103u64));
+        assert_eq!(pack_counters(&[255; 8], 8), Scalar::from(u64::MAX));
+        assert_eq!(pack_counters(&[u64::MAX], 64), Scalar::from(u64::MAX));
+    }
+
+    #[test]
+    #[should_panic]
+    fn pack_value_too_large() {
+        pack_counters(&[0, 16], 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pack_too_many_bits() {
+        pack_counters(&[0; 9], 8);
+    }
+
+    // Test that counters at and just past the limb boundary are recovered or rejected.
+    #[test]
+    fn packed_overflow_boundaries() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        // full limbs decrypt with the largest bound a limb allows
+        let ct = ek.encrypt_packed(&[31, 0, 31], 5, &mut rng);
+        assert_eq!(dk.decrypt_packed(ct, 3, 5, 31), Some(vec![31, 0, 31]));
+
+        // overflowing the top limb takes the packing past the search bound
+        let top = ek.encrypt_packed(&[0, 0, 1], 5, &mut rng);
+        assert_eq!(dk.decrypt_packed(ct + top, 3, 5, 31), None);
+
+        // overflowing a lower limb is indistinguishable from a carry
+        let low = ek.encrypt_packed(&[1, 0, 0], 5, &mut rng);
+        assert_eq!(dk.decrypt_packed(ct + low, 3, 5, 31), Some(vec![0, 1, 31]));
+
+        // counters above max_total are rejected even when they fit in their limb
+        let ct = ek.encrypt_packed(&[3, 2, 1], 5, &mut rng);
+        assert_eq!(dk.decrypt_packed(ct, 3, 5, 3), Some(vec![3, 2, 1]));
+        assert_eq!(dk.decrypt_packed(ct, 3, 5, 2), None);
+
+        // parameters that cannot describe a packing
+        assert_eq!(dk.decrypt_packed(ct, 3, 5, 32), None);
+        assert_eq!(dk.decrypt_packed(ct, 0, 5, 1), None);
+        assert_eq!(dk.decrypt_packed(ct, 3, 0, 0), None);
+        assert_eq!(dk.decrypt_packed(ct, 13, 5, 1), None);
+    }
+
+    // Test that many packed ballots accumulate homomorphically up to the safe limit.
+    #[test]
+    fn packed_accumulation() {
+        const COUNTERS: usize = 4;
+        const BITS: usize = 5;
+        // each ballot adds at most one to each counter, so 31 ballots are safe
+        const BALLOTS: usize = 31;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let mut sum = Ciphertext::identity();
+        let mut expected = [0u64; COUNTERS];
+        for _ in 0..BALLOTS {
+            let ballot: Vec<u64> = (0..COUNTERS).map(|_| rng.gen_range(0..=1)).collect();
+            for (e, v) in expected.iter_mut().zip(&ballot) {
+                *e += v;
+            }
+            sum = sum + ek.encrypt_packed(&ballot, BITS, &mut rng);
+        }
+        assert_eq!(
+            dk.decrypt_packed(sum, COUNTERS, BITS, BALLOTS as u64),
+            Some(expected.to_vec())
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// Precomputed encryption pads for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{Ciphertext, ElGamalGroup, EncryptionKey, Plaintext};
+
+/// The message-independent part of an encryption, `(rG, rY)` for a random `r`, computed ahead of
+/// time with [EncryptionKey::precompute_pad].
+///
+/// [apply](Self::apply) turns a pad into an encryption of a message with one point addition.
+/// Applying one pad to two messages would reveal the difference of the messages, so `apply` takes
+/// the pad by value, and pads are neither `Copy` nor `Clone`:
+///
+/// ```compile_fail
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let pad = dec_key.encryption_key().precompute_pad(&mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct1 = pad.apply(m);
+/// let ct2 = pad.apply(m);
+/// ```
+///
+/// With the `zeroize` feature, a pad is wiped when dropped, whether or not it was applied.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let mut pads = dec_key.encryption_key().precompute_pads(10, &mut rng);
+///
+/// // later, online
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = pads.pop().unwrap().apply(m);
+/// assert_eq!(dec_key.decrypt(ct), m);
+/// ```
+pub struct EncryptionPad {
+    c1: RistrettoPoint,
+    shared: RistrettoPoint,
+}
+
+impl EncryptionPad {
+    /// Encrypt `m` with the pad, using it up.
+    pub fn apply(self, m: impl Into<Plaintext>) -> Ciphertext {
+        Ciphertext(self.c1, m.into().to_point() + self.shared)
+    }
+}
+
+impl Debug for EncryptionPad {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "EncryptionPad {{ .. }}")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for EncryptionPad {
+    fn zeroize(&mut self) {
+        self.c1.zeroize();
+        self.shared.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for EncryptionPad {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for EncryptionPad {}
+
+impl EncryptionKey {
+    /// Precompute a pad for one encryption, at the cost of two scalar multiplications.
+    pub fn precompute_pad<R: RngCore + CryptoRng>(&self, rng: &mut R) -> EncryptionPad {
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut r = Scalar::random(rng);
+        let pad = EncryptionPad {
+            c1: RistrettoPoint::mul_generator(&r),
+            shared: self.0 * r,
+        };
+        #[cfg(feature = "zeroize")]
+        r.zeroize();
+        pad
+    }
+
+    /// Precompute `count` pads, each for one encryption.
+    #[cfg(feature = "alloc")]
+    pub fn precompute_pads<R: RngCore + CryptoRng>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Vec<EncryptionPad> {
+        (0..count).map(|_| self.precompute_pad(rng)).collect()
+    }
+}
+
+/// A supply of [EncryptionPad]s for one key, which wipes the pads left in it when dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, PadStore, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let mut store = PadStore::new(*dec_key.encryption_key());
+/// store.refill(10, &mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = store.encrypt(m).unwrap();
+/// assert_eq!(dec_key.decrypt(ct), m);
+/// assert_eq!(store.len(), 9);
+/// ```
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+#[derive(Debug)]
+pub struct PadStore {
+    key: EncryptionKey,
+    pads: Vec<EncryptionPad>,
+}
+
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+impl PadStore {
+    /// Create an empty store for pads of `key`.
+    pub fn new(key: EncryptionKey) -> PadStore {
+        PadStore {
+            key,
+            pads: Vec::new(),
+        }
+    }
+
+    /// The key the pads are for.
+    pub fn encryption_key(&self) -> &EncryptionKey {
+        &self.key
+    }
+
+    /// The number of pads left.
+    pub fn len(&self) -> usize {
+        self.pads.len()
+    }
+
+    /// Whether the store has run out of pads.
+    pub fn is_empty(&self) -> bool {
+        self.pads.is_empty()
+    }
+
+    /// Precompute `count` more pads.
+    pub fn refill<R: RngCore + CryptoRng>(&mut self, count: usize, rng: &mut R) {
+        self.pads.reserve(count);
+        for _ in 0..count {
+            self.pads.push(self.key.precompute_pad(rng));
+        }
+    }
+
+    /// Take a pad out of the store, if there is one left.
+    pub fn take(&mut self) -> Option<EncryptionPad> {
+        self.pads.pop()
+    }
+
+    /// Encrypt `m` with the next pad, or return `None` if the store is empty.
+    pub fn encrypt(&mut self, m: impl Into<Plaintext>) -> Option<Ciphertext> {
+        self.take().map(|pad| pad.apply(m))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, ElGamalGroup, ExponentMessage, RistrettoPoint, Scalar};
+
+    // Test that pad-based encryptions decrypt, and that every pad is fresh.
+    #[test]
+    fn pad_encrypt_decrypt() {
+        let mut rng = StdRng::seed_from_u64(468);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let pads = ek.precompute_pads(8, &mut rng);
+        assert_eq!(pads.len(), 8);
+        let m = RistrettoPoint::random(&mut rng);
+        let cts: alloc::vec::Vec<_> = pads.into_iter().map(|pad| pad.apply(m)).collect();
+        for (i, ct) in cts.iter().enumerate() {
+            assert_eq!(dk.decrypt(*ct), m);
+            for other in &cts[i + 1..] {
+                assert_ne!(ct.0, other.0);
+            }
+        }
+
+        // pads encrypt anything encryptable, and the result is homomorphic as usual
+        let sum = ek
+            .precompute_pad(&mut rng)
+            .apply(ExponentMessage(Scalar::from(400u32)))
+            + ek.precompute_pad(&mut rng)
+                .apply(ExponentMessage(Scalar::from(68u32)));
+        assert_eq!(
+            dk.decrypt(sum),
+            RistrettoPoint::mul_generator(&Scalar::from(468u32))
+        );
+        assert!(ek.precompute_pads(0, &mut rng).is_empty());
+    }
+
+    // Test that the store hands out each pad once and then runs dry.
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn pad_store() {
+        use crate::PadStore;
+        use zeroize::ZeroizeOnDrop;
+
+        fn wiped_on_drop<T: ZeroizeOnDrop>() {}
+        wiped_on_drop::<crate::EncryptionPad>();
+
+        let mut rng = StdRng::seed_from_u64(468);
+        let dk = DecryptionKey::new(&mut rng);
+        let mut store = PadStore::new(*dk.encryption_key());
+        assert!(store.is_empty());
+        store.refill(3, &mut rng);
+        assert_eq!(store.len(), 3);
+
+        let m = RistrettoPoint::random(&mut rng);
+        for left in (0..3).rev() {
+            assert_eq!(dk.decrypt(store.encrypt(m).unwrap()), m);
+            assert_eq!(store.len(), left);
+        }
+        assert_eq!(store.encrypt(m), None);
+        assert_eq!(alloc::format!("{:?}", store.take()), "None");
+    }
+}
@@ -0,0 +1,417 @@
+// Parallel proof generation for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generating and checking many independent proofs at once on all cores, through
+//! [rayon](https://docs.rs/rayon/).
+//!
+//! The output does not depend on the number of threads or on scheduling. Each function draws a
+//! 32-byte seed from the caller's generator, and item `i` of the batch uses its own generator,
+//! [item_rng]`(&seed, i)`: ChaCha20 keyed with the seed, on stream `i`. So the batch is exactly
+//! what proving the items one after another with those generators gives, and a seeded caller
+//! gets reproducible transcripts.
+//!
+//! Rerandomisation proofs show that `new = old + (δG, δY)` for some `δ`, as a [DleqProof] that
+//! `log_G(A) = log_Y(B)` for the difference `(A, B) = new - old`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::ballot::ProofPolicy;
+//! use rust_elgamal::parallel::{par_encrypt_ballots, par_verify_ballots};
+//! use rust_elgamal::DecryptionKey;
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let enc_key = dec_key.encryption_key();
+//!
+//! let voters: Vec<(Vec<u8>, Vec<bool>)> = (0..100u32)
+//!     .map(|i| (i.to_le_bytes().to_vec(), vec![i % 2 == 0, i % 2 == 1]))
+//!     .collect();
+//! let ballots = par_encrypt_ballots(enc_key, b"referendum", &voters, &mut rng);
+//! let policy = ProofPolicy::ExactSelections(1);
+//! assert_eq!(
+//!     par_verify_ballots(enc_key, b"referendum", 2, policy, &ballots),
+//!     Ok(())
+//! );
+//! ```
+
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+use rayon::prelude::*;
+
+use crate::ballot::{verify_ballot, Ballot, BallotProofs, ProofPolicy, SubmitError, ZeroOneProof};
+use crate::trace::op_span;
+use crate::{Ciphertext, DecryptionKey, DleqProof, EncryptionKey, Error};
+
+/// The generator used for item `index` of a batch whose seed is `seed`.
+///
+/// This is ChaCha20 keyed with `seed`, on stream `index`; it is part of the crate's stable
+/// behaviour, so that batches can be reproduced serially.
+pub fn item_rng(seed: &[u8; 32], index: u64) -> impl RngCore + CryptoRng {
+    let mut rng = ChaCha20Rng::from_seed(*seed);
+    rng.set_stream(index);
+    rng
+}
+
+fn draw_seed<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    seed
+}
+
+fn check_len(expected: usize, got: usize) -> Result<(), Error> {
+    if expected != got {
+        return Err(Error::LengthMismatch { expected, got });
+    }
+    Ok(())
+}
+
+/// Prove in parallel that each `cts[i]`, the exponential encryption of `votes[i]` with randomness
+/// `rs[i]`, encrypts 0 or 1, as [ZeroOneProof::prove] does for one, all under `context`.
+///
+/// Fails with [Error::LengthMismatch] if the slices have different lengths.
+pub fn par_prove_zero_one<R: RngCore + CryptoRng>(
+    ek: &EncryptionKey,
+    cts: &[Ciphertext],
+    votes: &[bool],
+    rs: &[Scalar],
+    context: &[u8],
+    rng: &mut R,
+) -> Result<Vec<ZeroOneProof>, Error> {
+    check_len(cts.len(), votes.len())?;
+    check_len(cts.len(), rs.len())?;
+    let seed = draw_seed(rng);
+    Ok((0..cts.len())
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = item_rng(&seed, i as u64);
+            ZeroOneProof::prove(ek, &cts[i], votes[i], &rs[i], context, &mut rng)
+        })
+        .collect())
+}
+
+/// Verify in parallel that each `proofs[i]`, made under `context`, shows that `cts[i]` encrypts 0
+/// or 1 under `ek`.
+///
+/// Fails with [Error::LengthMismatch] if the slices have different lengths, and with
+/// [Error::VerificationFailed] if any proof does not verify.
+pub fn par_verify_zero_one(
+    ek: &EncryptionKey,
+    cts: &[Ciphertext],
+    proofs: &[ZeroOneProof],
+    context: &[u8],
+) -> Result<(), Error> {
+    check_len(cts.len(), proofs.len())?;
+    if cts
+        .par_iter()
+        .zip(proofs)
+        .all(|(ct, proof)| proof.verify(ek, ct, context))
+    {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+/// Encrypt ballots with their 0/1 and sum proofs in parallel, as [Ballot::encrypt] does for one,
+/// for each voter id and votes in `voters`.
+///
+/// Each ballot is returned with its voter id, as [par_verify_ballots] takes them.
+pub fn par_encrypt_ballots<R: RngCore + CryptoRng>(
+    ek: &EncryptionKey,
+    election: &[u8],
+    voters: &[(Vec<u8>, Vec<bool>)],
+    rng: &mut R,
+) -> Vec<(Vec<u8>, Ballot, BallotProofs)> {
+    let _span = op_span!("par_encrypt_ballots", ballots = voters.len());
+    let seed = draw_seed(rng);
+    voters
+        .par_iter()
+        .enumerate()
+        .map(|(i, (voter_id, votes))| {
+            let mut rng = item_rng(&seed, i as u64);
+            let (ballot, proofs) = Ballot::encrypt(ek, election, voter_id, votes, &mut rng);
+            (voter_id.clone(), ballot, proofs)
+        })
+        .collect()
+}
+
+/// Verify ballots, each with the id of the voter submitting it, in parallel, as
+/// [BallotBox::submit](crate::ballot::BallotBox::submit) does for each, for the election labelled
+/// `election` of `num_options` options with the given policy.
+///
+/// Fails with the position and the reason of the first ballot that does not verify.
+pub fn par_verify_ballots(
+    ek: &EncryptionKey,
+    election: &[u8],
+    num_options: usize,
+    policy: ProofPolicy,
+    ballots: &[(Vec<u8>, Ballot, BallotProofs)],
+) -> Result<(), (usize, SubmitError)> {
+    let _span = op_span!("par_verify_ballots", ballots = ballots.len());
+    let first = ballots
+        .par_iter()
+        .enumerate()
+        .filter_map(|(i, (voter_id, ballot, proofs))| {
+            verify_ballot(ek, election, voter_id, num_options, policy, ballot, proofs)
+                .err()
+                .map(|err| (i, err))
+        })
+        .min_by_key(|(i, _)| *i);
+    match first {
+        Some(failure) => Err(failure),
+        None => Ok(()),
+    }
+}
+
+/// Rerandomise each ciphertext under `ek` in parallel, with a proof that the result is a
+/// rerandomisation of it.
+pub fn par_rerandomise_with_proofs<R: RngCore + CryptoRng>(
+    ek: &EncryptionKey,
+    cts: &[Ciphertext],
+    rng: &mut R,
+) -> Vec<(Ciphertext, DleqProof)> {
+    let seed = draw_seed(rng);
+    cts.par_iter()
+        .enumerate()
+        .map(|(i, ct)| {
+            let mut rng = item_rng(&seed, i as u64);
+            let (new, delta) = ek.rerandomise_returning(*ct, &mut rng);
+            let proof = DleqProof::prove(
+                &DecryptionKey::from(delta),
+                &ek.0,
+                &(new.1 - ct.1),
+                &mut rng,
+            );
+            (new, proof)
+        })
+        .collect()
+}
+
+/// Verify in parallel that each `rerandomised[i]` is a rerandomisation of `originals[i]` under
+/// `ek`.
+///
+/// Fails with [Error::LengthMismatch] if the slices have different lengths, and with
+/// [Error::VerificationFailed] if any proof does not verify.
+pub fn par_verify_rerandomisations(
+    ek: &EncryptionKey,
+    originals: &[Ciphertext],
+    rerandomised: &[(Ciphertext, DleqProof)],
+) -> Result<(), Error> {
+    check_len(originals.len(), rerandomised.len())?;
+    originals
+        .par_iter()
+        .zip(rerandomised)
+        .try_for_each(|(old, (new, proof))| {
+            let diff = new - old;
+            proof.verify(&EncryptionKey(diff.0), &ek.0, &diff.1)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use super::{
+        item_rng, par_encrypt_ballots, par_prove_zero_one, par_rerandomise_with_proofs,
+        par_verify_ballots, par_verify_rerandomisations, par_verify_zero_one,
+    };
+    use crate::ballot::{Ballot, BallotBox, ProofPolicy, SubmitError, ZeroOneProof};
+    use crate::{DecryptionKey, DleqProof, Error, RistrettoPoint, Scalar};
+
+    const ELECTION: &[u8] = b"parallel election";
+
+    // The seed the parallel functions draw from a fresh `StdRng::seed_from_u64(seed)`.
+    fn batch_seed(seed: u64) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        StdRng::seed_from_u64(seed).fill(&mut bytes);
+        bytes
+    }
+
+    // Test that parallel ballots are exactly the serial ones under the split generators, and that
+    // both verify in parallel and through a ballot box.
+    #[test]
+    fn parallel_ballots_match_serial() {
+        let mut rng = StdRng::seed_from_u64(485);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let voters: Vec<(Vec<u8>, Vec<bool>)> = (0..16)
+            .map(|i| {
+                let mut votes = vec![false; 3];
+                votes[i % 3] = true;
+                (vec![i as u8], votes)
+            })
+            .collect();
+
+        let parallel = par_encrypt_ballots(&ek, ELECTION, &voters, &mut StdRng::seed_from_u64(1));
+        let seed = batch_seed(1);
+        let serial: Vec<_> = voters
+            .iter()
+            .enumerate()
+            .map(|(i, (voter_id, votes))| {
+                let mut rng = item_rng(&seed, i as u64);
+                let (ballot, proofs) = Ballot::encrypt(&ek, ELECTION, voter_id, votes, &mut rng);
+                (voter_id.clone(), ballot, proofs)
+            })
+            .collect();
+        assert_eq!(parallel, serial);
+        assert_eq!(
+            par_encrypt_ballots(&ek, ELECTION, &voters, &mut StdRng::seed_from_u64(1)),
+            parallel
+        );
+
+        let policy = ProofPolicy::ExactSelections(1);
+        assert_eq!(
+            par_verify_ballots(&ek, ELECTION, 3, policy, &parallel),
+            Ok(())
+        );
+        let mut ballot_box = BallotBox::new(ek, ELECTION, 3, policy);
+        for (voter_id, ballot, proofs) in serial {
+            assert!(ballot_box.submit(ballot, proofs, &voter_id).is_ok());
+        }
+
+        // the first bad ballot is reported, whichever thread finds it
+        let mut tampered = parallel.clone();
+        tampered[7].2.zero_one.swap(0, 1);
+        tampered[12].2.zero_one.swap(0, 2);
+        assert_eq!(
+            par_verify_ballots(&ek, ELECTION, 3, policy, &tampered),
+            Err((7, SubmitError::InvalidZeroOneProof(0)))
+        );
+        assert_eq!(
+            par_verify_ballots(&ek, ELECTION, 3, ProofPolicy::ExactSelections(2), &parallel),
+            Err((0, SubmitError::InvalidSumProof))
+        );
+
+        // a ballot resubmitted under another voter's id
+        let mut moved = parallel.clone();
+        moved[3].0 = moved[4].0.clone();
+        assert_eq!(
+            par_verify_ballots(&ek, ELECTION, 3, policy, &moved),
+            Err((3, SubmitError::InvalidZeroOneProof(0)))
+        );
+    }
+
+    // Test that parallel 0/1 proofs match the serial ones and verify the same way.
+    #[test]
+    fn parallel_zero_one_match_serial() {
+        let mut rng = StdRng::seed_from_u64(485);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let votes: Vec<bool> = (0..20).map(|_| rng.gen()).collect();
+        let rs: Vec<Scalar> = votes.iter().map(|_| Scalar::random(&mut rng)).collect();
+        let cts: Vec<_> = votes
+            .iter()
+            .zip(&rs)
+            .map(|(&vote, r)| ek.exp_encrypt_with(Scalar::from(vote as u64), *r))
+            .collect();
+
+        let proofs = par_prove_zero_one(
+            &ek,
+            &cts,
+            &votes,
+            &rs,
+            ELECTION,
+            &mut StdRng::seed_from_u64(2),
+        )
+        .unwrap();
+        let seed = batch_seed(2);
+        for (i, proof) in proofs.iter().enumerate() {
+            let serial = ZeroOneProof::prove(
+                &ek,
+                &cts[i],
+                votes[i],
+                &rs[i],
+                ELECTION,
+                &mut item_rng(&seed, i as u64),
+            );
+            assert_eq!(*proof, serial);
+            assert!(proof.verify(&ek, &cts[i], ELECTION));
+        }
+        assert_eq!(par_verify_zero_one(&ek, &cts, &proofs, ELECTION), Ok(()));
+        assert_eq!(
+            par_verify_zero_one(&ek, &cts, &proofs, b"other"),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut shifted = proofs.clone();
+        shifted.rotate_left(1);
+        assert_eq!(
+            par_verify_zero_one(&ek, &cts, &shifted, ELECTION),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            par_prove_zero_one(&ek, &cts, &votes[1..], &rs, ELECTION, &mut rng),
+            Err(Error::LengthMismatch {
+                expected: 20,
+                got: 19
+            })
+        );
+    }
+
+    // Test that parallel rerandomisations keep the plaintexts, match the serial computation, and
+    // prove only what they claim.
+    #[test]
+    fn parallel_rerandomisation_proofs() {
+        let mut rng = StdRng::seed_from_u64(485);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let ms: Vec<_> = (0..12).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let cts: Vec<_> = ms.iter().map(|m| ek.encrypt(*m, &mut rng)).collect();
+
+        let outputs = par_rerandomise_with_proofs(&ek, &cts, &mut StdRng::seed_from_u64(3));
+        let seed = batch_seed(3);
+        for (i, (new, proof)) in outputs.iter().enumerate() {
+            assert_eq!(dk.decrypt(*new), ms[i]);
+            let mut rng = item_rng(&seed, i as u64);
+            let (serial, delta) = ek.rerandomise_returning(cts[i], &mut rng);
+            assert_eq!(*new, serial);
+            let serial_proof = DleqProof::prove(
+                &DecryptionKey::from(delta),
+                &ek.0,
+                &(new.1 - cts[i].1),
+                &mut rng,
+            );
+            assert_eq!(*proof, serial_proof);
+        }
+        assert_eq!(par_verify_rerandomisations(&ek, &cts, &outputs), Ok(()));
+
+        // a different plaintext, or the proof for another ciphertext, does not verify
+        let mut forged = outputs.clone();
+        forged[4].0 = forged[4].0 + ek.encrypt_with(RistrettoPoint::random(&mut rng), Scalar::ZERO);
+        assert_eq!(
+            par_verify_rerandomisations(&ek, &cts, &forged),
+            Err(Error::VerificationFailed)
+        );
+        let mut swapped = outputs.clone();
+        swapped[0].1 = outputs[1].1;
+        assert_eq!(
+            par_verify_rerandomisations(&ek, &cts, &swapped),
+            Err(Error::VerificationFailed)
+        );
+        let other = *DecryptionKey::new(&mut rng).encryption_key();
+        assert_eq!(
+            par_verify_rerandomisations(&other, &cts, &outputs),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
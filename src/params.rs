@@ -0,0 +1,410 @@
+// System parameters with a custom generator for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::{RistrettoBasepointTable, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_to_point;
+use crate::{Ciphertext, Commitment, DecryptionKey, EncryptionKey, Error, Open, GENERATOR_POINT};
+
+const LABEL_DOMAIN: &[u8] = b"rust-elgamal generator";
+const FINGERPRINT_DOMAIN: &[u8] = b"rust-elgamal params";
+
+/// System parameters: the generator used for key generation and for lifting scalars to points,
+/// with a precomputed table for multiplying it.
+///
+/// The plain APIs of the crate always use the ristretto255 basepoint; the `*_with_params`
+/// constructors take parameters instead, and tag what they produce with the parameters'
+/// [fingerprint](Self::fingerprint) in a [WithParams], so that objects made under different
+/// generators cannot be combined by accident.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, ElGamalParams, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let params = ElGamalParams::from_label(b"their-label");
+/// let dec_key = DecryptionKey::new_with_params(&params, &mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = dec_key
+///     .encryption_key()
+///     .encrypt(&params, m, &mut rng)
+///     .unwrap();
+/// assert_eq!(dec_key.decrypt(&ct), Ok(m));
+/// ```
+#[derive(Clone)]
+pub struct ElGamalParams {
+    generator: RistrettoPoint,
+    table: RistrettoBasepointTable,
+    fingerprint: [u8; 32],
+}
+
+/// A value made under some [ElGamalParams], tagged with their fingerprint.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct WithParams<T> {
+    fingerprint: [u8; 32],
+    value: T,
+}
+
+impl ElGamalParams {
+    /// The default parameters, with the ristretto255 basepoint as generator.
+    pub fn standard() -> ElGamalParams {
+        ElGamalParams::with_generator(GENERATOR_POINT)
+    }
+
+    /// Parameters with `generator`, which must not be the identity.
+    pub fn from_generator(generator: RistrettoPoint) -> Result<ElGamalParams, Error> {
+        if generator.is_identity() {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(ElGamalParams::with_generator(generator))
+    }
+
+    /// Parameters whose generator is `label` hashed to a point, so that nobody knows its discrete
+    /// logarithm with respect to the basepoint.
+    ///
+    /// The hash is this crate's own (SHA-512 of the length-prefixed domain separator
+    /// `"rust-elgamal generator"` and the label, mapped with `RistrettoPoint::from_hash`); to
+    /// match another deployment's generator exactly, compute it their way and use
+    /// [from_generator](Self::from_generator).
+    pub fn from_label(label: &[u8]) -> ElGamalParams {
+        ElGamalParams::with_generator(hash_to_point(LABEL_DOMAIN, label))
+    }
+
+    fn with_generator(generator: RistrettoPoint) -> ElGamalParams {
+        let digest = Sha512::new()
+            .chain_update((FINGERPRINT_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update(generator.compress().as_bytes())
+            .finalize();
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&digest[..32]);
+        ElGamalParams {
+            generator,
+            table: RistrettoBasepointTable::create(&generator),
+            fingerprint,
+        }
+    }
+
+    /// The generator.
+    pub fn generator(&self) -> RistrettoPoint {
+        self.generator
+    }
+
+    /// A hash of the generator, identifying the parameters.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    /// Lift the scalar `m` to the point `mG'`, where `G'` is the generator.
+    pub fn lift(&self, m: &Scalar) -> RistrettoPoint {
+        m * &self.table
+    }
+
+    fn tag<T>(&self, value: T) -> WithParams<T> {
+        WithParams {
+            fingerprint: self.fingerprint,
+            value,
+        }
+    }
+
+    fn check<T>(&self, tagged: &WithParams<T>) -> Result<(), Error> {
+        if tagged.fingerprint != self.fingerprint {
+            return Err(Error::ParamsMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for ElGamalParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ElGamalParams {{ generator: {:?} }}",
+            self.generator.compress().as_bytes()
+        )
+    }
+}
+
+impl<T> WithParams<T> {
+    /// The fingerprint of the parameters the value was made under.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    /// The value, without checking the parameters.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The value, if it was made under `params`; fails with
+    /// [Error::ParamsMismatch](crate::Error::ParamsMismatch) otherwise.
+    pub fn check(&self, params: &ElGamalParams) -> Result<&T, Error> {
+        params.check(self)?;
+        Ok(&self.value)
+    }
+
+    /// Remove the tag.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl DecryptionKey {
+    /// Generate a new decryption key whose encryption key is `xG'`, where `G'` is the generator of
+    /// `params`.
+    pub fn new_with_params<R: RngCore + CryptoRng>(
+        params: &ElGamalParams,
+        rng: &mut R,
+    ) -> WithParams<DecryptionKey> {
+        let secret = Scalar::random(rng);
+        let ek = EncryptionKey(params.lift(&secret));
+        params.tag(DecryptionKey { secret, ek })
+    }
+}
+
+impl EncryptionKey {
+    /// Encrypt the point `m` as `(rG', m + rY)`, where `G'` is the generator of `params`, with a
+    /// randomly-generated blinding factor.
+    ///
+    /// The key must belong to a decryption key made with
+    /// [DecryptionKey::new_with_params] under the same parameters.
+    pub fn encrypt_with_params<R: RngCore + CryptoRng>(
+        &self,
+        params: &ElGamalParams,
+        m: RistrettoPoint,
+        rng: &mut R,
+    ) -> WithParams<Ciphertext> {
+        let r = Scalar::random(rng);
+        params.tag(Ciphertext(params.lift(&r), m + self.0 * r))
+    }
+}
+
+impl Commitment {
+    /// Commit to `m` as `(rG', mG' + rY)` under a fresh key `Y`, where `G'` is the generator of
+    /// `params`.
+    pub fn commit_with_params<R: RngCore + CryptoRng>(
+        params: &ElGamalParams,
+        m: Scalar,
+        rng: &mut R,
+    ) -> (Open, WithParams<Commitment>) {
+        let y = params.lift(&Scalar::random(rng));
+        let r = Scalar::random(rng);
+        let ct = Ciphertext(params.lift(&r), params.lift(&m) + y * r);
+        (Open(r, m), params.tag(Commitment(y, ct)))
+    }
+}
+
+impl WithParams<DecryptionKey> {
+    /// The encryption key, tagged with the same parameters.
+    pub fn encryption_key(&self) -> WithParams<EncryptionKey> {
+        WithParams {
+            fingerprint: self.fingerprint,
+            value: self.value.ek,
+        }
+    }
+
+    /// Decrypt `ct`, failing with [Error::ParamsMismatch](crate::Error::ParamsMismatch) if it was
+    /// made under other parameters.
+    pub fn decrypt(&self, ct: &WithParams<Ciphertext>) -> Result<RistrettoPoint, Error> {
+        if ct.fingerprint != self.fingerprint {
+            return Err(Error::ParamsMismatch);
+        }
+        Ok(self.value.decrypt(ct.value))
+    }
+}
+
+impl WithParams<EncryptionKey> {
+    /// Encrypt the point `m` with [EncryptionKey::encrypt_with_params], failing with
+    /// [Error::ParamsMismatch](crate::Error::ParamsMismatch) if the key was made under other
+    /// parameters.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        params: &ElGamalParams,
+        m: RistrettoPoint,
+        rng: &mut R,
+    ) -> Result<WithParams<Ciphertext>, Error> {
+        params.check(self)?;
+        Ok(self.value.encrypt_with_params(params, m, rng))
+    }
+
+    /// Encrypt `mG'`, where `G'` is the generator of `params`.
+    pub fn exp_encrypt<R: RngCore + CryptoRng>(
+        &self,
+        params: &ElGamalParams,
+        m: Scalar,
+        rng: &mut R,
+    ) -> Result<WithParams<Ciphertext>, Error> {
+        self.encrypt(params, params.lift(&m), rng)
+    }
+}
+
+impl WithParams<Ciphertext> {
+    /// Add two ciphertexts made under the same parameters, failing with
+    /// [Error::ParamsMismatch](crate::Error::ParamsMismatch) otherwise.
+    pub fn add(&self, other: &WithParams<Ciphertext>) -> Result<WithParams<Ciphertext>, Error> {
+        if other.fingerprint != self.fingerprint {
+            return Err(Error::ParamsMismatch);
+        }
+        Ok(WithParams {
+            fingerprint: self.fingerprint,
+            value: self.value + other.value,
+        })
+    }
+}
+
+impl WithParams<Commitment> {
+    /// Verify the commitment against `open` under `params`.
+    ///
+    /// Fails with [Error::ParamsMismatch](crate::Error::ParamsMismatch) if the commitment was
+    /// made under other parameters, and with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if the open does not match.
+    pub fn verify(&self, params: &ElGamalParams, open: &Open) -> Result<(), Error> {
+        params.check(self)?;
+        let Commitment(y, ct) = self.value;
+        let Open(r, m) = open;
+        if ct != Ciphertext(params.lift(r), params.lift(m) + y * r) {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::dlog::bounded_dlog;
+    use crate::{
+        Commitment, DecryptionKey, ElGamalParams, Error, RistrettoPoint, Scalar, GENERATOR_POINT,
+    };
+
+    // Test encryption, decryption, homomorphic addition and commitments under a generator other
+    // than the basepoint.
+    #[test]
+    fn params_custom_generator() {
+        let mut rng = StdRng::seed_from_u64(469);
+        let params = ElGamalParams::from_label(b"their-label");
+        assert_ne!(params.generator(), GENERATOR_POINT);
+        assert_eq!(params.lift(&Scalar::ONE), params.generator());
+
+        let dk = DecryptionKey::new_with_params(&params, &mut rng);
+        let ek = dk.encryption_key();
+        assert_eq!(ek.value().0, params.lift(&dk.value().secret));
+
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = ek.encrypt(&params, m, &mut rng).unwrap();
+        assert_eq!(dk.decrypt(&ct), Ok(m));
+        // the plain encryption would use the basepoint, which this key cannot decrypt
+        assert_ne!(dk.value().decrypt(ek.value().encrypt(m, &mut rng)), m);
+
+        // exponential encryption lifts with the custom generator
+        let sum = ek
+            .exp_encrypt(&params, Scalar::from(400u32), &mut rng)
+            .unwrap()
+            .add(
+                &ek.exp_encrypt(&params, Scalar::from(69u32), &mut rng)
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(dk.decrypt(&sum), Ok(params.lift(&Scalar::from(469u32))));
+        assert_eq!(bounded_dlog(&dk.decrypt(&sum).unwrap(), 1000), None);
+
+        let m = Scalar::from(7u32);
+        let (open, commitment) = Commitment::commit_with_params(&params, m, &mut rng);
+        assert_eq!(commitment.verify(&params, &open), Ok(()));
+        let (other, _) = Commitment::commit_with_params(&params, m, &mut rng);
+        assert_eq!(
+            commitment.verify(&params, &other),
+            Err(Error::VerificationFailed)
+        );
+        assert!(!commitment.value().verify(&open));
+    }
+
+    // Test that objects made under different parameters are rejected when mixed.
+    #[test]
+    fn params_mismatch() {
+        let mut rng = StdRng::seed_from_u64(469);
+        let ours = ElGamalParams::from_label(b"ours");
+        let theirs = ElGamalParams::from_label(b"theirs");
+        assert_ne!(ours.fingerprint(), theirs.fingerprint());
+        assert_eq!(
+            ElGamalParams::from_label(b"ours").fingerprint(),
+            ours.fingerprint()
+        );
+
+        let dk = DecryptionKey::new_with_params(&ours, &mut rng);
+        let other = DecryptionKey::new_with_params(&theirs, &mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+        assert_eq!(
+            dk.encryption_key().encrypt(&theirs, m, &mut rng),
+            Err(Error::ParamsMismatch)
+        );
+
+        let ct = dk.encryption_key().encrypt(&ours, m, &mut rng).unwrap();
+        let theirs_ct = other
+            .encryption_key()
+            .encrypt(&theirs, m, &mut rng)
+            .unwrap();
+        assert_eq!(other.decrypt(&ct), Err(Error::ParamsMismatch));
+        assert_eq!(ct.add(&theirs_ct), Err(Error::ParamsMismatch));
+        assert_eq!(ct.check(&theirs), Err(Error::ParamsMismatch));
+        assert_eq!(ct.check(&ours), Ok(ct.value()));
+
+        let (open, commitment) = Commitment::commit_with_params(&ours, Scalar::ONE, &mut rng);
+        assert_eq!(
+            commitment.verify(&theirs, &open),
+            Err(Error::ParamsMismatch)
+        );
+
+        assert_eq!(
+            ElGamalParams::from_generator(RistrettoPoint::random(&mut rng) * Scalar::ZERO)
+                .unwrap_err(),
+            Error::IdentityPoint
+        );
+    }
+
+    // Test that the standard parameters agree with the plain APIs.
+    #[test]
+    fn params_standard() {
+        let mut rng = StdRng::seed_from_u64(469);
+        let params = ElGamalParams::standard();
+        assert_eq!(params.generator(), GENERATOR_POINT);
+
+        let dk = DecryptionKey::new_with_params(&params, &mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+        let plain = dk.value().encryption_key().encrypt(m, &mut rng);
+        assert_eq!(dk.value().decrypt(plain), m);
+
+        let (open, commitment) = Commitment::commit_with_params(&params, Scalar::ONE, &mut rng);
+        assert!(commitment.value().verify(&open));
+        assert_eq!(commitment.into_inner().try_verify(&open), Ok(()));
+    }
+}
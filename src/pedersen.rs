@@ -0,0 +1,304 @@
+// Pedersen commitments linked to ElGamal commitments for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_points_to_scalar;
+use crate::{Commitment, Error, Open, GENERATOR_POINT, GENERATOR_TABLE};
+
+const DOMAIN: &[u8] = b"rust-elgamal pedersen link";
+
+/// The alternative generator `H` for Pedersen commitments `mG + rH`, compressed.
+///
+/// This is the hash of the compressed generator `G` to a point with SHA3-512, so nobody knows
+/// `log_G(H)`; it is the same point as `B_blinding` in the default `PedersenGens` of the
+/// bulletproofs crate, so Pedersen commitments from this crate can be used in its proofs.
+pub const BLINDING_GENERATOR_COMPRESSED: CompressedRistretto = CompressedRistretto([
+    0x8c, 0x92, 0x40, 0xb4, 0x56, 0xa9, 0xe6, 0xdc, 0x65, 0xc3, 0x77, 0xa1, 0x04, 0x8d, 0x74, 0x5f,
+    0x94, 0xa0, 0x8c, 0xdb, 0x7f, 0x44, 0xcb, 0xcd, 0x7b, 0x46, 0xf3, 0x40, 0x48, 0x87, 0x11, 0x34,
+]);
+
+/// The alternative generator `H` for Pedersen commitments; see
+/// [BLINDING_GENERATOR_COMPRESSED](crate::BLINDING_GENERATOR_COMPRESSED).
+pub fn blinding_generator() -> RistrettoPoint {
+    BLINDING_GENERATOR_COMPRESSED
+        .decompress()
+        .expect("the blinding generator is a valid point")
+}
+
+/// The opening of a Pedersen commitment `mG + rH`: the value `m` and the blinding factor `r`.
+///
+/// Like an [Open], it is secret: it is `Clone` but not `Copy`, debug output leaves it out,
+/// equality compares in constant time, through [ConstantTimeEq], it is serialised only with the
+/// `serde-secret` feature, and with the `zeroize` feature it is wiped when dropped.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+pub struct PedersenOpen {
+    value: Scalar,
+    blinding: Scalar,
+}
+
+impl PedersenOpen {
+    /// The committed value `m`.
+    pub fn value(&self) -> Scalar {
+        self.value
+    }
+
+    /// The blinding factor `r`.
+    pub fn blinding(&self) -> Scalar {
+        self.blinding
+    }
+
+    /// The Pedersen commitment `mG + rH` this opens.
+    pub fn commit(&self) -> RistrettoPoint {
+        &self.value * GENERATOR_TABLE + blinding_generator() * self.blinding
+    }
+}
+
+impl ConstantTimeEq for PedersenOpen {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.value.ct_eq(&other.value) & self.blinding.ct_eq(&other.blinding)
+    }
+}
+
+impl PartialEq for PedersenOpen {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PedersenOpen {}
+
+impl Debug for PedersenOpen {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("PedersenOpen(<redacted>)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for PedersenOpen {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for PedersenOpen {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for PedersenOpen {}
+
+/// A non-interactive proof that a Pedersen commitment and an ElGamal [Commitment] hide the same
+/// value, produced by [Commitment::to_pedersen_with_proof] and checked by
+/// [Commitment::verify_link].
+///
+/// For the ElGamal commitment `(Y, (rG, mG + rY))` and the Pedersen commitment `mG + r'H`, this is
+/// a Schnorr-style proof of knowledge of `m`, `r` and `r'` satisfying all three equations.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct PedersenLinkProof {
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) e: Scalar,
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) z_m: Scalar,
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) z_r: Scalar,
+    #[cfg_attr(feature = "ts", ts(as = "crate::ts::Scalar"))]
+    pub(crate) z_b: Scalar,
+}
+
+fn link_challenge(
+    commitment: &Commitment,
+    pedersen: &RistrettoPoint,
+    t: [&RistrettoPoint; 3],
+) -> Scalar {
+    let (y, ct) = commitment.inner();
+    hash_points_to_scalar(
+        DOMAIN,
+        &[
+            &GENERATOR_POINT,
+            &blinding_generator(),
+            &y.0,
+            &ct.0,
+            &ct.1,
+            pedersen,
+            t[0],
+            t[1],
+            t[2],
+        ],
+    )
+}
+
+impl Commitment {
+    /// Translate the commitment, opened by `open`, into a bare Pedersen commitment `mG + rH` to
+    /// the same value, with `H` the [blinding_generator].
+    ///
+    /// The blinding factor `r` of the ElGamal commitment is reused, so the translation is
+    /// deterministic; use [to_pedersen_with_proof](Self::to_pedersen_with_proof) to convince a
+    /// third party that the two commitments match.
+    pub fn to_pedersen(&self, open: &Open) -> (RistrettoPoint, PedersenOpen) {
+        let pedersen_open = PedersenOpen {
+            value: open.1,
+            blinding: open.0,
+        };
+        (pedersen_open.commit(), pedersen_open)
+    }
+
+    /// [to_pedersen](Self::to_pedersen), with a proof that the Pedersen commitment hides the same
+    /// value as this commitment.
+    ///
+    /// The proof only verifies if `open` really opens this commitment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{Commitment, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let (open, commitment) = Commitment::commit(Scalar::from(7u32), &mut rng);
+    ///
+    /// let (pedersen, _, proof) = commitment.to_pedersen_with_proof(&open, &mut rng);
+    /// assert!(commitment.verify_link(&pedersen, &proof).is_ok());
+    /// ```
+    pub fn to_pedersen_with_proof<R: RngCore + CryptoRng>(
+        &self,
+        open: &Open,
+        rng: &mut R,
+    ) -> (RistrettoPoint, PedersenOpen, PedersenLinkProof) {
+        let (pedersen, pedersen_open) = self.to_pedersen(open);
+        let Open(r, m) = *open;
+        let b = pedersen_open.blinding;
+        let y = self.commit_key().0;
+
+        let (k_m, k_r, k_b) = (
+            Scalar::random(rng),
+            Scalar::random(rng),
+            Scalar::random(rng),
+        );
+        let t1 = &k_r * GENERATOR_TABLE;
+        let t2 = &k_m * GENERATOR_TABLE + y * k_r;
+        let t3 = &k_m * GENERATOR_TABLE + blinding_generator() * k_b;
+
+        let e = link_challenge(self, &pedersen, [&t1, &t2, &t3]);
+        let proof = PedersenLinkProof {
+            e,
+            z_m: k_m + e * m,
+            z_r: k_r + e * r,
+            z_b: k_b + e * b,
+        };
+        (pedersen, pedersen_open, proof)
+    }
+
+    /// Verify a proof that `pedersen` hides the same value as this commitment.
+    ///
+    /// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not.
+    pub fn verify_link(
+        &self,
+        pedersen: &RistrettoPoint,
+        proof: &PedersenLinkProof,
+    ) -> Result<(), Error> {
+        let (y, ct) = self.inner();
+        let h = blinding_generator();
+        let e = proof.e;
+
+        // recompute the prover's commitments from the responses
+        let t1 = &proof.z_r * GENERATOR_TABLE - ct.0 * e;
+        let z_m = &proof.z_m * GENERATOR_TABLE;
+        let t2 = z_m + y.0 * proof.z_r - ct.1 * e;
+        let t3 = z_m + h * proof.z_b - pedersen * e;
+
+        if link_challenge(self, pedersen, [&t1, &t2, &t3]) != e {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+    use sha3::Sha3_512;
+
+    use crate::pedersen::blinding_generator;
+    use crate::{Commitment, Error, RistrettoPoint, Scalar, GENERATOR_POINT};
+
+    // Test that the blinding generator is the one bulletproofs derives, and that the Pedersen
+    // commitment passes a `PedersenGens`-style check `C = value * B + blinding * B_blinding`.
+    #[test]
+    fn pedersen_gens_compatible() {
+        let h = RistrettoPoint::hash_from_bytes::<Sha3_512>(GENERATOR_POINT.compress().as_bytes());
+        assert_eq!(blinding_generator(), h);
+
+        let mut rng = StdRng::from_entropy();
+        let value = Scalar::from(1234u32);
+        let (open, commitment) = Commitment::commit(value, &mut rng);
+        let (pedersen, pedersen_open) = commitment.to_pedersen(&open);
+
+        assert_eq!(pedersen_open.value(), value);
+        assert_eq!(
+            pedersen,
+            value * GENERATOR_POINT + pedersen_open.blinding() * h
+        );
+    }
+
+    // Test that honest translations verify, and that mismatched values or commitments do not.
+    #[test]
+    fn pedersen_link() {
+        let mut rng = StdRng::from_entropy();
+        let (open, commitment) = Commitment::commit(Scalar::from(7u32), &mut rng);
+        let (open2, commitment2) = Commitment::commit(Scalar::from(8u32), &mut rng);
+
+        let (pedersen, _, proof) = commitment.to_pedersen_with_proof(&open, &mut rng);
+        assert_eq!(commitment.verify_link(&pedersen, &proof), Ok(()));
+
+        // a Pedersen commitment to a different value
+        let (pedersen2, _, proof2) = commitment2.to_pedersen_with_proof(&open2, &mut rng);
+        assert_eq!(
+            commitment.verify_link(&pedersen2, &proof),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            commitment.verify_link(&pedersen2, &proof2),
+            Err(Error::VerificationFailed)
+        );
+
+        // proving with an opening of a different value
+        let (_, _, bad) = commitment.to_pedersen_with_proof(&open2, &mut rng);
+        let (pedersen_bad, _) = commitment.to_pedersen(&open2);
+        assert_eq!(
+            commitment.verify_link(&pedersen_bad, &bad),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
@@ -0,0 +1,348 @@
+// Permutations for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// A permutation of `0..n`, as used to shuffle lists of ciphertexts.
+///
+/// Entry `i` is the position the element at position `i` comes from, so
+/// [apply](Self::apply) maps `xs` to `[xs[p[0]], xs[p[1]], ...]`. This is the convention of
+/// [ShuffleWitness](crate::deck::ShuffleWitness).
+///
+/// With `enable-serde` a permutation is serialised as its list of entries, and deserialising
+/// checks that the list is a permutation.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_elgamal::Permutation;
+///
+/// let p = Permutation::from_seed(5, [7u8; 32]);
+/// let xs = ['a', 'b', 'c', 'd', 'e'];
+/// let shuffled = p.apply(&xs).unwrap();
+/// assert_eq!(p.inverse().apply(&shuffled).unwrap(), xs);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(try_from = "Vec<usize>", into = "Vec<usize>")
+)]
+pub struct Permutation(Vec<usize>);
+
+// A uniformly random integer below `n`, by rejection sampling.
+fn uniform_below<R: RngCore>(n: usize, rng: &mut R) -> usize {
+    let n = n as u64;
+    let zone = u64::MAX - u64::MAX % n;
+    loop {
+        let x = rng.next_u64();
+        if x < zone {
+            return (x % n) as usize;
+        }
+    }
+}
+
+// Fisher-Yates, from the last position down.
+fn shuffle<R: RngCore>(n: usize, rng: &mut R) -> Permutation {
+    let mut entries: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        entries.swap(i, uniform_below(i + 1, rng));
+    }
+    Permutation(entries)
+}
+
+impl Permutation {
+    /// The identity permutation of `0..n`.
+    pub fn identity(n: usize) -> Permutation {
+        Permutation((0..n).collect())
+    }
+
+    /// A uniformly random permutation of `0..n`.
+    pub fn random<R: RngCore + CryptoRng>(n: usize, rng: &mut R) -> Permutation {
+        shuffle(n, rng)
+    }
+
+    /// The permutation of `0..n` derived from `seed`, so that a shuffle can be re-derived and
+    /// audited later.
+    ///
+    /// This is a Fisher-Yates shuffle, swapping each position `i` from `n - 1` down to `1` with a
+    /// position below `i + 1`, drawn by rejection sampling from successive `u64`s of ChaCha20
+    /// keyed with `seed`. The derivation is part of the crate's stable behaviour.
+    pub fn from_seed(n: usize, seed: [u8; 32]) -> Permutation {
+        shuffle(n, &mut ChaCha20Rng::from_seed(seed))
+    }
+
+    /// The number of elements permuted.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether this is the permutation of no elements.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The entries: entry `i` is the position the element at `i` comes from.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// The entries, by value.
+    pub fn into_vec(self) -> Vec<usize> {
+        self.0
+    }
+
+    /// Whether every element stays where it is.
+    pub fn is_identity(&self) -> bool {
+        self.0.iter().enumerate().all(|(i, &j)| i == j)
+    }
+
+    /// The inverse permutation, which puts every element back.
+    pub fn inverse(&self) -> Permutation {
+        let mut inverse = alloc::vec![0; self.len()];
+        for (i, &j) in self.0.iter().enumerate() {
+            inverse[j] = i;
+        }
+        Permutation(inverse)
+    }
+
+    /// The permutation that applies `self`, then `other`.
+    ///
+    /// Fails with [Error::LengthMismatch] if the permutations have different lengths.
+    pub fn compose(&self, other: &Permutation) -> Result<Permutation, Error> {
+        self.check_len(other.len())?;
+        Ok(Permutation(other.0.iter().map(|&j| self.0[j]).collect()))
+    }
+
+    /// The permuted copy of `xs`: element `i` of the result is `xs[self[i]]`.
+    ///
+    /// Fails with [Error::LengthMismatch] if `xs` does not have [len](Self::len) elements.
+    pub fn apply<T: Clone>(&self, xs: &[T]) -> Result<Vec<T>, Error> {
+        self.check_len(xs.len())?;
+        Ok(self.0.iter().map(|&j| xs[j].clone()).collect())
+    }
+
+    /// Permute `xs` in place, as [apply](Self::apply) would, by following each cycle with swaps.
+    ///
+    /// Fails with [Error::LengthMismatch] if `xs` does not have [len](Self::len) elements.
+    pub fn apply_in_place<T>(&self, xs: &mut [T]) -> Result<(), Error> {
+        self.check_len(xs.len())?;
+        for cycle in self.cycles() {
+            // position c[k] takes the element from c[k + 1]
+            for pair in cycle.windows(2) {
+                xs.swap(pair[0], pair[1]);
+            }
+        }
+        Ok(())
+    }
+
+    /// The cycles of the permutation, for diagnostics: each cycle starts at its smallest
+    /// position `i` and continues `self[i]`, `self[self[i]]`, ..., and the cycles are ordered by
+    /// their first position. Fixed points are cycles of length one.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut seen = alloc::vec![false; self.len()];
+        let mut cycles = Vec::new();
+        for start in 0..self.len() {
+            if seen[start] {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut i = start;
+            while !seen[i] {
+                seen[i] = true;
+                cycle.push(i);
+                i = self.0[i];
+            }
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    fn check_len(&self, got: usize) -> Result<(), Error> {
+        if got != self.len() {
+            return Err(Error::LengthMismatch {
+                expected: self.len(),
+                got,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<Vec<usize>> for Permutation {
+    type Error = Error;
+
+    /// Check that `entries` holds every index below its length exactly once.
+    ///
+    /// Fails with [Error::InvalidPermutation] if an index is out of range or repeated.
+    fn try_from(entries: Vec<usize>) -> Result<Permutation, Error> {
+        let mut seen = alloc::vec![false; entries.len()];
+        for &j in &entries {
+            match seen.get_mut(j) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(Error::InvalidPermutation),
+            }
+        }
+        Ok(Permutation(entries))
+    }
+}
+
+impl From<Permutation> for Vec<usize> {
+    fn from(p: Permutation) -> Vec<usize> {
+        p.0
+    }
+}
+
+impl AsRef<[usize]> for Permutation {
+    fn as_ref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::Permutation;
+    use crate::Error;
+
+    // Test that inverting undoes a permutation, in place or not, over many random permutations.
+    #[test]
+    fn permutation_inverse() {
+        let mut rng = StdRng::seed_from_u64(484);
+        for n in [0, 1, 2, 3, 10, 52, 257] {
+            let xs: Vec<u32> = (0..n as u32).map(|x| x * 7 + 1).collect();
+            for _ in 0..20 {
+                let p = Permutation::random(n, &mut rng);
+                let ys = p.apply(&xs).unwrap();
+                assert_eq!(p.inverse().apply(&ys).unwrap(), xs);
+                assert!(p.compose(&p.inverse()).unwrap().is_identity());
+                assert!(p.inverse().compose(&p).unwrap().is_identity());
+
+                let mut in_place = xs.clone();
+                p.apply_in_place(&mut in_place).unwrap();
+                assert_eq!(in_place, ys);
+                p.inverse().apply_in_place(&mut in_place).unwrap();
+                assert_eq!(in_place, xs);
+            }
+        }
+    }
+
+    // Test that composing matches applying one permutation after the other.
+    #[test]
+    fn permutation_compose() {
+        let mut rng = StdRng::seed_from_u64(484);
+        for n in [1, 5, 52] {
+            let xs: Vec<usize> = (100..100 + n).collect();
+            for _ in 0..20 {
+                let p = Permutation::random(n, &mut rng);
+                let q = Permutation::random(n, &mut rng);
+                let pq = p.compose(&q).unwrap();
+                let sequential = q.apply(&p.apply(&xs).unwrap()).unwrap();
+                assert_eq!(pq.apply(&xs).unwrap(), sequential);
+                assert_eq!(pq.inverse(), q.inverse().compose(&p.inverse()).unwrap());
+            }
+        }
+
+        let p = Permutation::identity(3);
+        assert_eq!(
+            p.compose(&Permutation::identity(4)),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert_eq!(
+            p.apply(&[1, 2]),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+    }
+
+    // Test that seeded permutations are reproducible, and pin one so that re-derivation stays
+    // possible across releases.
+    #[test]
+    fn permutation_from_seed() {
+        let p = Permutation::from_seed(10, [0u8; 32]);
+        assert_eq!(p, Permutation::from_seed(10, [0u8; 32]));
+        assert_ne!(p, Permutation::from_seed(10, [1u8; 32]));
+        assert_eq!(p.as_slice(), &[9, 7, 3, 6, 1, 4, 8, 5, 2, 0]);
+        assert!(Permutation::try_from(p.clone().into_vec()).is_ok());
+        assert!(Permutation::from_seed(0, [0u8; 32]).is_empty());
+    }
+
+    // Test the cycle decomposition and the identity check.
+    #[test]
+    fn permutation_cycles() {
+        let p = Permutation::try_from(vec![2, 0, 1, 3, 5, 4]).unwrap();
+        assert_eq!(p.cycles(), vec![vec![0, 2, 1], vec![3], vec![4, 5]]);
+        assert!(!p.is_identity());
+        assert!(Permutation::identity(4).is_identity());
+        assert_eq!(Permutation::identity(2).cycles(), vec![vec![0], vec![1]]);
+
+        let mut rng = StdRng::seed_from_u64(484);
+        let p = Permutation::random(100, &mut rng);
+        let mut covered: Vec<usize> = p.cycles().concat();
+        covered.sort_unstable();
+        assert_eq!(covered, (0..100).collect::<Vec<_>>());
+    }
+
+    // Test that lists which are not permutations are rejected.
+    #[test]
+    fn permutation_rejects_malformed() {
+        for entries in [vec![0, 0], vec![1, 2], vec![0, 1, 3], vec![usize::MAX]] {
+            assert_eq!(
+                Permutation::try_from(entries),
+                Err(Error::InvalidPermutation)
+            );
+        }
+        assert!(Permutation::try_from(vec![]).is_ok());
+        assert!(Permutation::try_from(vec![1, 0]).is_ok());
+    }
+
+    // Test that serialising round trips, and that deserialising checks the entries.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_permutation() {
+        let mut rng = StdRng::seed_from_u64(484);
+        let p = Permutation::random(20, &mut rng);
+        let encoded = bincode::serialize(&p).unwrap();
+        assert_eq!(encoded, bincode::serialize(p.as_slice()).unwrap());
+        let decoded: Permutation = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, p);
+
+        let malformed = bincode::serialize(&vec![0usize, 2, 2]).unwrap();
+        let err = bincode::deserialize::<Permutation>(&malformed).unwrap_err();
+        assert!(err.to_string().contains("permutation"));
+        let out_of_range = bincode::serialize(&vec![3usize, 0, 1]).unwrap();
+        assert!(bincode::deserialize::<Permutation>(&out_of_range).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// Plaintext encodings for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use crate::backend::DebugPoint;
+#[cfg(feature = "alloc")]
+use crate::dlog::bounded_dlog;
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup};
+
+/// A message that is a group element `M`, encrypted as is.
+///
+/// Any point can be encrypted and decrypted this way, but only points can: to encrypt bytes, see
+/// [encode_to_point](crate::encode_to_point).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PointMessage<G = RistrettoPoint>(pub G);
+
+/// A message that is a scalar `m`, encrypted "in the exponent" as the point `M = mG`.
+///
+/// Exponent messages add up under the homomorphism (the sum of encryptions of `m1` and `m2`
+/// decrypts to `(m1 + m2)G`), which is what tallies and counters need, but can only be recovered
+/// from the decrypted point by a discrete-logarithm search, so they must be small.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExponentMessage<F = Scalar>(pub F);
+
+/// A message in either encoding, as accepted by
+/// [EncryptionKey::encrypt](crate::EncryptionKey::encrypt).
+///
+/// A bare point converts into [Plaintext::Point], so existing calls passing a point are
+/// unchanged; wrap a scalar in [ExponentMessage] to encrypt it in the exponent.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Plaintext<G: ElGamalGroup = RistrettoPoint> {
+    /// The message is the point itself.
+    Point(G),
+    /// The message is the scalar `m`, encoded as `mG`.
+    Exponent(G::Scalar),
+}
+
+impl<G: ElGamalGroup> Plaintext<G> {
+    /// The point that is actually encrypted.
+    pub fn to_point(&self) -> G {
+        match self {
+            Plaintext::Point(point) => *point,
+            Plaintext::Exponent(m) => G::mul_generator(m),
+        }
+    }
+}
+
+impl<G: ElGamalGroup> Debug for Plaintext<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Plaintext::Point(point) => write!(f, "Point({:?})", DebugPoint(point)),
+            Plaintext::Exponent(m) => write!(f, "Exponent({:?})", m),
+        }
+    }
+}
+
+impl<G: ElGamalGroup> From<G> for Plaintext<G> {
+    fn from(point: G) -> Self {
+        Plaintext::Point(point)
+    }
+}
+
+impl<G: ElGamalGroup> From<PointMessage<G>> for Plaintext<G> {
+    fn from(msg: PointMessage<G>) -> Self {
+        Plaintext::Point(msg.0)
+    }
+}
+
+impl<G: ElGamalGroup> From<ExponentMessage<G::Scalar>> for Plaintext<G> {
+    fn from(msg: ExponentMessage<G::Scalar>) -> Self {
+        Plaintext::Exponent(msg.0)
+    }
+}
+
+/// The result of [DecryptionKey::decrypt_message]: the decrypted point, which can be read back as
+/// whichever encoding was encrypted.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct DecryptedMessage<G = RistrettoPoint>(G);
+
+impl<G: ElGamalGroup> DecryptedMessage<G> {
+    /// The decrypted point. This is the message if a [PointMessage] was encrypted, and `mG` if an
+    /// [ExponentMessage] `m` was.
+    pub fn as_point(&self) -> G {
+        self.0
+    }
+
+    /// The exponent message, if one was encrypted and it is at most `bound`.
+    ///
+    /// This is a discrete-logarithm search costing about `2 * sqrt(bound)` group operations, and
+    /// only meaningful for an [ExponentMessage]: for a [PointMessage] it will almost always
+    /// return `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, ExponentMessage, Scalar};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let ct = dec_key
+    ///     .encryption_key()
+    ///     .encrypt(ExponentMessage(Scalar::from(42u32)), &mut rng);
+    /// assert_eq!(dec_key.decrypt_message(ct).try_as_u64(100), Some(42));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn try_as_u64(&self, bound: u64) -> Option<u64> {
+        bounded_dlog(&self.0, bound)
+    }
+}
+
+impl<G: ElGamalGroup> Debug for DecryptedMessage<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "DecryptedMessage({:?})", DebugPoint(&self.0))
+    }
+}
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Decrypt the ciphertext `ct`, to be read back as a point or as a small exponent message.
+    pub fn decrypt_message(&self, ct: Ciphertext<G>) -> DecryptedMessage<G> {
+        DecryptedMessage(self.decrypt(ct))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, Plaintext, PointMessage, RistrettoPoint, Scalar};
+
+    // Test that small integers round-trip through the exponent encoding, including after adding
+    // ciphertexts.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn exponent_roundtrip() {
+        use crate::{ExponentMessage, GENERATOR_TABLE};
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for n in [0u64, 1, 7, 999, 1000] {
+            let ct = ek.encrypt(ExponentMessage(Scalar::from(n)), &mut rng);
+            let decrypted = dk.decrypt_message(ct);
+            assert_eq!(decrypted.try_as_u64(1000), Some(n));
+            assert_eq!(decrypted.as_point(), &Scalar::from(n) * GENERATOR_TABLE);
+        }
+
+        let sum = ek.encrypt(ExponentMessage(Scalar::from(3u32)), &mut rng)
+            + ek.encrypt(ExponentMessage(Scalar::from(4u32)), &mut rng);
+        assert_eq!(dk.decrypt_message(sum).try_as_u64(10), Some(7));
+        assert_eq!(dk.decrypt_message(sum).try_as_u64(6), None);
+    }
+
+    // Test that the point encoding behaves exactly as before.
+    #[test]
+    fn point_unchanged() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let m = RistrettoPoint::random(&mut rng);
+        let r = Scalar::random(&mut rng);
+        assert_eq!(Plaintext::from(m).to_point(), m);
+        assert_eq!(Plaintext::from(PointMessage(m)), Plaintext::Point(m));
+
+        assert_eq!(dk.decrypt(ek.encrypt(m, &mut rng)), m);
+        assert_eq!(dk.decrypt(ek.encrypt(PointMessage(m), &mut rng)), m);
+        assert_eq!(dk.decrypt_message(ek.encrypt_with(m, r)).as_point(), m);
+    }
+}
@@ -0,0 +1,343 @@
+// Feldman polynomial commitments for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::GENERATOR_TABLE;
+
+/// A Feldman commitment to a polynomial `f(X) = a_0 + a_1 X + ... + a_d X^d` over scalars: the
+/// points `a_i G`, one per coefficient.
+///
+/// Anyone holding the commitment can check a claimed evaluation `y = f(x)` at a public point `x`
+/// with [verify_eval](Self::verify_eval), since `yG = sum_i x^i (a_i G)`. The coefficients are only
+/// hidden computationally, so this suits secret sharing (where `a_0` is random) rather than
+/// committing to low-entropy values.
+///
+/// Commitments are additively homomorphic: the sum of the commitments to `f` and `g` is the
+/// commitment to `f + g`, whose degree is the larger of the two.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{PolynomialCommitment, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let coeffs: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+/// let commitment = PolynomialCommitment::commit(&coeffs);
+///
+/// let x = Scalar::from(5u32);
+/// let (y, witness) = commitment.evaluate_with_witness(&coeffs, x);
+/// assert!(commitment.verify_eval_bounded(x, y, &witness, 2));
+/// assert!(!commitment.verify_eval_bounded(x, y, &witness, 1));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct PolynomialCommitment {
+    #[cfg_attr(
+        feature = "enable-serde",
+        serde(deserialize_with = "deserialize_coefficients")
+    )]
+    coefficients: Vec<RistrettoPoint>,
+}
+
+/// The evidence accompanying an evaluation of a committed polynomial.
+///
+/// In the Feldman scheme the commitment alone is enough to check an evaluation, so the witness
+/// only records the degree of the polynomial that was evaluated; [PolynomialCommitment::verify_eval]
+/// rejects a witness made for a commitment of another degree.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EvalWitness {
+    degree: u64,
+}
+
+impl PolynomialCommitment {
+    /// Commit to the polynomial with coefficients `coeffs`, constant term first. An empty slice is
+    /// the zero polynomial, committed as a single zero coefficient.
+    pub fn commit(coeffs: &[Scalar]) -> PolynomialCommitment {
+        if coeffs.is_empty() {
+            return PolynomialCommitment {
+                coefficients: vec![RistrettoPoint::identity()],
+            };
+        }
+        PolynomialCommitment {
+            coefficients: coeffs.iter().map(|a| a * GENERATOR_TABLE).collect(),
+        }
+    }
+
+    /// The degree of the committed polynomial: one less than the number of coefficients. This is
+    /// an upper bound, as the leading coefficients may be zero.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// The commitments `a_i G` to the coefficients, constant term first.
+    pub fn coefficients(&self) -> &[RistrettoPoint] {
+        &self.coefficients
+    }
+
+    /// The commitment `f(x) G` to the evaluation of the polynomial at `x`, computed from the
+    /// coefficient commitments alone.
+    pub fn evaluate_in_exponent(&self, x: Scalar) -> RistrettoPoint {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(RistrettoPoint::identity(), |acc, c| acc * x + c)
+    }
+
+    /// Evaluate the committed polynomial at `x` from its coefficients, returning `f(x)` and the
+    /// witness to pass to [verify_eval](Self::verify_eval).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coeffs` does not have the same number of coefficients as the commitment (an
+    /// empty slice matching a commitment of degree 0).
+    pub fn evaluate_with_witness(&self, coeffs: &[Scalar], x: Scalar) -> (Scalar, EvalWitness) {
+        assert_eq!(
+            coeffs.len().max(1),
+            self.coefficients.len(),
+            "the coefficients do not match the commitment"
+        );
+        let y = coeffs.iter().rev().fold(Scalar::ZERO, |acc, a| acc * x + a);
+        let witness = EvalWitness {
+            degree: self.degree() as u64,
+        };
+        (y, witness)
+    }
+
+    /// Check that the committed polynomial evaluates to `claimed_y` at `x`, and that `witness` was
+    /// made for a polynomial of this commitment's degree.
+    pub fn verify_eval(&self, x: Scalar, claimed_y: Scalar, witness: &EvalWitness) -> bool {
+        witness.degree == self.degree() as u64
+            && self.evaluate_in_exponent(x) == &claimed_y * GENERATOR_TABLE
+    }
+
+    /// As [verify_eval](Self::verify_eval), also checking that the polynomial has degree at most
+    /// `max_degree`.
+    pub fn verify_eval_bounded(
+        &self,
+        x: Scalar,
+        claimed_y: Scalar,
+        witness: &EvalWitness,
+        max_degree: usize,
+    ) -> bool {
+        self.degree() <= max_degree && self.verify_eval(x, claimed_y, witness)
+    }
+}
+
+impl EvalWitness {
+    /// The degree of the evaluated polynomial.
+    pub fn degree(&self) -> u64 {
+        self.degree
+    }
+}
+
+impl Add for &PolynomialCommitment {
+    type Output = PolynomialCommitment;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut sum = self.clone();
+        sum += rhs;
+        sum
+    }
+}
+
+impl Add for PolynomialCommitment {
+    type Output = PolynomialCommitment;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += &rhs;
+        self
+    }
+}
+
+impl AddAssign<&PolynomialCommitment> for PolynomialCommitment {
+    fn add_assign(&mut self, rhs: &PolynomialCommitment) {
+        if self.coefficients.len() < rhs.coefficients.len() {
+            self.coefficients
+                .resize(rhs.coefficients.len(), RistrettoPoint::identity());
+        }
+        for (c, d) in self.coefficients.iter_mut().zip(&rhs.coefficients) {
+            *c += d;
+        }
+    }
+}
+
+// A commitment has at least one coefficient.
+#[cfg(feature = "enable-serde")]
+fn deserialize_coefficients<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<RistrettoPoint>, D::Error> {
+    let coefficients = Vec::<RistrettoPoint>::deserialize(deserializer)?;
+    if coefficients.is_empty() {
+        return Err(serde::de::Error::invalid_length(
+            0,
+            &"at least one coefficient",
+        ));
+    }
+    Ok(coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Identity, PolynomialCommitment, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    fn random_poly(degree: usize, rng: &mut StdRng) -> Vec<Scalar> {
+        (0..=degree).map(|_| Scalar::random(rng)).collect()
+    }
+
+    // Test that evaluations verify at several points, and that wrong values do not.
+    #[test]
+    fn poly_evaluations() {
+        let mut rng = StdRng::seed_from_u64(461);
+        let coeffs = random_poly(4, &mut rng);
+        let commitment = PolynomialCommitment::commit(&coeffs);
+        assert_eq!(commitment.degree(), 4);
+        assert_eq!(commitment.coefficients()[0], &coeffs[0] * GENERATOR_TABLE);
+
+        for x in [0u32, 1, 2, 461] {
+            let x = Scalar::from(x);
+            let (y, witness) = commitment.evaluate_with_witness(&coeffs, x);
+            let expected = coeffs
+                .iter()
+                .enumerate()
+                .map(|(i, a)| a * (0..i).fold(Scalar::ONE, |p, _| p * x))
+                .sum::<Scalar>();
+            assert_eq!(y, expected);
+            assert_eq!(witness.degree(), 4);
+            assert!(commitment.verify_eval(x, y, &witness));
+            assert!(!commitment.verify_eval(x, y + Scalar::ONE, &witness));
+            assert!(!commitment.verify_eval(x + Scalar::ONE, y, &witness));
+        }
+
+        // the zero polynomial
+        let zero = PolynomialCommitment::commit(&[]);
+        assert_eq!(zero.coefficients(), &[RistrettoPoint::identity()]);
+        let (y, witness) = zero.evaluate_with_witness(&[], Scalar::from(3u32));
+        assert_eq!(y, Scalar::ZERO);
+        assert!(zero.verify_eval_bounded(Scalar::from(3u32), y, &witness, 0));
+    }
+
+    // Test that degree bounds and mismatched witnesses are enforced.
+    #[test]
+    fn poly_degree_bounds() {
+        let mut rng = StdRng::seed_from_u64(461);
+        let coeffs = random_poly(3, &mut rng);
+        let commitment = PolynomialCommitment::commit(&coeffs);
+        let x = Scalar::from(7u32);
+        let (y, witness) = commitment.evaluate_with_witness(&coeffs, x);
+
+        assert!(commitment.verify_eval_bounded(x, y, &witness, 3));
+        assert!(commitment.verify_eval_bounded(x, y, &witness, 10));
+        assert!(!commitment.verify_eval_bounded(x, y, &witness, 2));
+        assert!(!commitment.verify_eval_bounded(x, y, &witness, 0));
+
+        // the same polynomial padded with a zero coefficient evaluates the same, but is a
+        // different commitment whose witnesses do not carry over
+        let mut padded = coeffs.clone();
+        padded.push(Scalar::ZERO);
+        let padded = PolynomialCommitment::commit(&padded);
+        assert_eq!(padded.degree(), 4);
+        assert!(!padded.verify_eval(x, y, &witness));
+        let (padded_y, padded_witness) =
+            padded.evaluate_with_witness(&[coeffs.as_slice(), &[Scalar::ZERO]].concat(), x);
+        assert_eq!(padded_y, y);
+        assert!(padded.verify_eval(x, y, &padded_witness));
+        assert!(!commitment.verify_eval(x, y, &padded_witness));
+    }
+
+    // Test that evaluating with the wrong number of coefficients panics.
+    #[test]
+    #[should_panic(expected = "the coefficients do not match the commitment")]
+    fn poly_evaluate_mismatch() {
+        let mut rng = StdRng::seed_from_u64(461);
+        let coeffs = random_poly(3, &mut rng);
+        let commitment = PolynomialCommitment::commit(&coeffs);
+        commitment.evaluate_with_witness(&coeffs[..3], Scalar::ONE);
+    }
+
+    // Test that the sum of two commitments is the commitment to the sum of the polynomials.
+    #[test]
+    fn poly_add() {
+        let mut rng = StdRng::seed_from_u64(461);
+        let f = random_poly(2, &mut rng);
+        let g = random_poly(5, &mut rng);
+        let sum_coeffs: Vec<_> = (0..g.len())
+            .map(|i| f.get(i).copied().unwrap_or(Scalar::ZERO) + g[i])
+            .collect();
+
+        let cf = PolynomialCommitment::commit(&f);
+        let cg = PolynomialCommitment::commit(&g);
+        let sum = &cf + &cg;
+        assert_eq!(sum, PolynomialCommitment::commit(&sum_coeffs));
+        assert_eq!(sum, cg.clone() + cf.clone());
+        assert_eq!(sum.degree(), 5);
+
+        let mut acc = cf.clone();
+        acc += &cg;
+        assert_eq!(acc, sum);
+
+        let x = Scalar::from(461u32);
+        let (yf, _) = cf.evaluate_with_witness(&f, x);
+        let (yg, _) = cg.evaluate_with_witness(&g, x);
+        let (y, witness) = sum.evaluate_with_witness(&sum_coeffs, x);
+        assert_eq!(y, yf + yg);
+        assert!(sum.verify_eval_bounded(x, yf + yg, &witness, 5));
+        assert!(!sum.verify_eval_bounded(x, yf + yg, &witness, 2));
+        assert_eq!(
+            sum.evaluate_in_exponent(x),
+            cf.evaluate_in_exponent(x) + cg.evaluate_in_exponent(x)
+        );
+    }
+
+    // Test that commitments and witnesses survive serialisation, and that empty commitments are
+    // rejected.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_poly() {
+        use crate::EvalWitness;
+
+        let mut rng = StdRng::seed_from_u64(461);
+        let coeffs = random_poly(3, &mut rng);
+        let commitment = PolynomialCommitment::commit(&coeffs);
+        let (y, witness) = commitment.evaluate_with_witness(&coeffs, Scalar::from(2u32));
+
+        let encoded = bincode::serialize(&commitment).unwrap();
+        let decoded: PolynomialCommitment = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, commitment);
+        let encoded = bincode::serialize(&witness).unwrap();
+        let decoded_witness: EvalWitness = bincode::deserialize(&encoded).unwrap();
+        assert!(decoded.verify_eval(Scalar::from(2u32), y, &decoded_witness));
+
+        let empty = bincode::serialize(&Vec::<RistrettoPoint>::new()).unwrap();
+        assert!(bincode::deserialize::<PolynomialCommitment>(&empty).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+// Proxy re-encryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::Field;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, Error};
+
+/// A key with which a proxy turns ciphertexts for one decryption key into ciphertexts for
+/// another, without learning the messages: the BBS98 scheme of Blaze, Bleumer and Strauss.
+///
+/// A ciphertext `(rG, m + r x_a G)` for Alice becomes `((x_a / x_b) rG, m + r x_a G)`, which is
+/// an ordinary encryption of `m` for Bob, with blinding factor `r x_a / x_b`. Bob decrypts it as
+/// usual, and it can be re-randomised, added to others and re-encrypted again.
+///
+/// The trust model is the interactive one: making the key needs both secrets, so it is made by
+/// someone trusted with both (or by Alice and Bob in a two-party computation of their own), and
+/// only the key is handed to the proxy. The proxy alone learns nothing about the messages, but
+/// the scheme is bidirectional, as the inverse key re-encrypts from Bob to Alice, and a proxy
+/// colluding with Bob recovers Alice's secret as `rk x_b`. Debug output leaves the key out.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, GENERATOR_TABLE, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let alice = DecryptionKey::new(&mut rng);
+/// let bob = DecryptionKey::new(&mut rng);
+/// let rk = alice.reencryption_key_to(&bob).unwrap();
+///
+/// let m = &Scalar::from(5u32) * GENERATOR_TABLE;
+/// let ct = alice.encryption_key().encrypt(m, &mut rng);
+/// let for_bob = rk.reencrypt(ct);
+/// assert_eq!(bob.decrypt(for_bob), m);
+/// assert_ne!(alice.decrypt(for_bob), m);
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(
+        serialize = "G::Scalar: Serialize",
+        deserialize = "G::Scalar: Deserialize<'de>"
+    ))
+)]
+pub struct ReEncryptionKey<G: ElGamalGroup = RistrettoPoint>(G::Scalar);
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// The key with which a proxy re-encrypts ciphertexts for this key into ciphertexts for
+    /// `target`: `x_a / x_b`, for this key's secret `x_a` and the target's `x_b`.
+    ///
+    /// See [ReEncryptionKey] for the trust model.
+    ///
+    /// Fails with [Error::InvalidScalar](crate::Error::InvalidScalar) if the target's secret is
+    /// zero, as a key built with `DecryptionKey::from(Scalar::ZERO)` is.
+    pub fn reencryption_key_to(
+        &self,
+        target: &DecryptionKey<G>,
+    ) -> Result<ReEncryptionKey<G>, Error> {
+        let inverse: Option<G::Scalar> = target.secret.invert().into();
+        let inverse = inverse.ok_or(Error::InvalidScalar)?;
+        Ok(ReEncryptionKey(self.secret * inverse))
+    }
+}
+
+impl<G: ElGamalGroup> ReEncryptionKey<G> {
+    /// Re-encrypt `ct` for the target key, by multiplying its first component by the key.
+    ///
+    /// A ciphertext that was not made for the source key re-encrypts to garbage.
+    pub fn reencrypt(&self, ct: Ciphertext<G>) -> Ciphertext<G> {
+        Ciphertext(ct.0 * self.0, ct.1)
+    }
+}
+
+impl<G: ElGamalGroup> Debug for ReEncryptionKey<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ReEncryptionKey(<redacted>)")
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Ciphertext, DecryptionKey, Error, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    // Test that a re-encrypted ciphertext decrypts to the message for the target only, survives
+    // re-randomisation under the target's key, and chains through a second proxy.
+    #[test]
+    fn reencrypt_decrypts_for_target() {
+        let mut rng = StdRng::seed_from_u64(510);
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+        let carol = DecryptionKey::new(&mut rng);
+        let rk = alice.reencryption_key_to(&bob).unwrap();
+
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = alice.encryption_key().encrypt(m, &mut rng);
+        let for_bob = rk.reencrypt(ct);
+        assert_eq!(bob.decrypt(for_bob), m);
+        assert_ne!(alice.decrypt(for_bob), m);
+        assert_ne!(carol.decrypt(for_bob), m);
+
+        let rerandomised = bob.encryption_key().rerandomise(for_bob, &mut rng);
+        assert_ne!(rerandomised, for_bob);
+        assert_eq!(bob.decrypt(rerandomised), m);
+
+        let for_carol = bob
+            .reencryption_key_to(&carol)
+            .unwrap()
+            .reencrypt(rerandomised);
+        assert_eq!(carol.decrypt(for_carol), m);
+
+        // the scheme is bidirectional
+        let back = bob.reencryption_key_to(&alice).unwrap().reencrypt(for_bob);
+        assert_eq!(alice.decrypt(back), m);
+        assert_eq!(back, ct);
+    }
+
+    // Test that a zero target key is rejected rather than panicking.
+    #[test]
+    fn reencrypt_to_zero_key() {
+        let mut rng = StdRng::seed_from_u64(510);
+        let alice = DecryptionKey::new(&mut rng);
+        let zero = DecryptionKey::from(Scalar::ZERO);
+        assert_eq!(
+            alice.reencryption_key_to(&zero).err(),
+            Some(Error::InvalidScalar)
+        );
+        assert!(zero.reencryption_key_to(&alice).is_ok());
+    }
+
+    // Test that sums of ciphertexts re-encrypt to sums, in either order.
+    #[test]
+    fn reencrypt_homomorphic() {
+        let mut rng = StdRng::seed_from_u64(510);
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+        let rk = alice.reencryption_key_to(&bob).unwrap();
+        let ek = alice.encryption_key();
+
+        let cts: [Ciphertext; 3] = [3u32, 4, 5].map(|v| ek.exp_encrypt(Scalar::from(v), &mut rng));
+        let sum = cts[0] + cts[1] + cts[2];
+        let reencrypted_sum = rk.reencrypt(cts[0]) + rk.reencrypt(cts[1]) + rk.reencrypt(cts[2]);
+        assert_eq!(rk.reencrypt(sum), reencrypted_sum);
+        assert_eq!(
+            bob.decrypt(reencrypted_sum),
+            &Scalar::from(12u32) * GENERATOR_TABLE
+        );
+        assert_eq!(
+            bob.decrypt(rk.reencrypt(cts[2] * Scalar::from(2u32))),
+            &Scalar::from(10u32) * GENERATOR_TABLE
+        );
+    }
+
+    // Test that the key survives serialisation and is left out of debug output.
+    #[test]
+    fn reencryption_key_encoding() {
+        let mut rng = StdRng::seed_from_u64(510);
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+        let rk = alice.reencryption_key_to(&bob).unwrap();
+        assert_eq!(alloc::format!("{:?}", rk), "ReEncryptionKey(<redacted>)");
+
+        #[cfg(feature = "enable-serde")]
+        {
+            let encoded = bincode::serialize(&rk).unwrap();
+            assert_eq!(encoded.len(), 32);
+            let decoded: super::ReEncryptionKey = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(decoded, rk);
+        }
+    }
+}
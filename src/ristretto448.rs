@@ -0,0 +1,194 @@
+// ristretto448 backend for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Type aliases and byte encodings for the 448-bit backend, which offers a security level of
+//! roughly 224 bits.
+//!
+//! ristretto448 and decaf448 are two encodings of the same prime-order group built on
+//! Ed448-Goldilocks. This backend uses the decaf448 implementation of `ed448-goldilocks-plus`
+//! ([DecafPoint]), which is the one implementing the [group](https://docs.rs/group/) traits.
+//! Points are encoded in 56 bytes and ciphertexts in 112.
+
+use ed448_goldilocks_plus::CompressedDecaf;
+pub use ed448_goldilocks_plus::{DecafPoint, Scalar as Scalar448};
+
+use crate::{Ciphertext, Commitment, DecryptionKey, ElGamalGroup, EncryptionKey, Error, Open};
+
+/// An ElGamal ciphertext over decaf448.
+pub type Ciphertext448 = Ciphertext<DecafPoint>;
+/// An ElGamal encryption key over decaf448.
+pub type EncryptionKey448 = EncryptionKey<DecafPoint>;
+/// An ElGamal decryption key over decaf448.
+pub type DecryptionKey448 = DecryptionKey<DecafPoint>;
+/// An ElGamal commitment over decaf448.
+pub type Commitment448 = Commitment<DecafPoint>;
+/// The opening of a [Commitment448].
+pub type Open448 = Open<Scalar448>;
+
+impl ElGamalGroup for DecafPoint {}
+crate::backend::scalar_operand!(Scalar448);
+
+fn decompress(bytes: &[u8]) -> Result<DecafPoint, Error> {
+    let mut point = [0u8; 56];
+    point.copy_from_slice(bytes);
+    Option::from(CompressedDecaf(point).decompress()).ok_or(Error::InvalidPoint)
+}
+
+impl Ciphertext<DecafPoint> {
+    /// The 112-byte encoding of the ciphertext: the two compressed points in order.
+    pub fn to_bytes(&self) -> [u8; 112] {
+        let mut bytes = [0u8; 112];
+        bytes[..56].copy_from_slice(&self.0.compress().0);
+        bytes[56..].copy_from_slice(&self.1.compress().0);
+        bytes
+    }
+
+    /// Read a ciphertext from its 112-byte encoding, failing with
+    /// [Error::InvalidPoint](crate::Error::InvalidPoint) if either point is invalid.
+    pub fn from_bytes(bytes: &[u8; 112]) -> Result<Ciphertext448, Error> {
+        Ok(Ciphertext(
+            decompress(&bytes[..56])?,
+            decompress(&bytes[56..])?,
+        ))
+    }
+}
+
+impl EncryptionKey<DecafPoint> {
+    /// The 56-byte encoding of the encryption key.
+    pub fn to_bytes(&self) -> [u8; 56] {
+        self.0.compress().0
+    }
+
+    /// Read an encryption key from its 56-byte encoding.
+    ///
+    /// Fails with [Error::InvalidPoint](crate::Error::InvalidPoint) if the bytes are not a valid
+    /// point, and with [Error::IdentityPoint](crate::Error::IdentityPoint) for the identity.
+    pub fn from_bytes(bytes: &[u8; 56]) -> Result<EncryptionKey448, Error> {
+        let point = decompress(bytes)?;
+        if point == DecafPoint::IDENTITY {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(EncryptionKey(point))
+    }
+}
+
+impl DecryptionKey<DecafPoint> {
+    /// The 56-byte little-endian encoding of the secret scalar.
+    pub fn to_bytes(&self) -> [u8; 56] {
+        self.secret.to_bytes()
+    }
+
+    /// Read a decryption key from its 56-byte encoding, failing with
+    /// [Error::InvalidScalar](crate::Error::InvalidScalar) if the scalar is not canonical or is
+    /// zero.
+    pub fn from_bytes(bytes: &[u8; 56]) -> Result<DecryptionKey448, Error> {
+        let mut wide = [0u8; 57];
+        wide[..56].copy_from_slice(bytes);
+        let secret: Option<Scalar448> = Scalar448::from_canonical_bytes(&wide.into()).into();
+        match secret {
+            Some(secret) if secret != Scalar448::ZERO => Ok(DecryptionKey {
+                secret,
+                ek: EncryptionKey(DecafPoint::mul_generator(&secret)),
+            }),
+            _ => Err(Error::InvalidScalar),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::ristretto448::{
+        Ciphertext448, Commitment448, DecafPoint, DecryptionKey448, EncryptionKey448, Scalar448,
+    };
+    use crate::Error;
+
+    // Test that the 448-bit instantiation encrypts, rerandomises, and is homomorphic.
+    #[test]
+    fn encrypt_decrypt_448() {
+        const N: usize = 20;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey448::random(&mut rng);
+        let ek = dk.encryption_key();
+
+        for _ in 0..N {
+            let m1 = DecafPoint::random(&mut rng);
+            let m2 = DecafPoint::random(&mut rng);
+            let ct1 = ek.encrypt(m1, &mut rng);
+            let ct2 = ek.encrypt(m2, &mut rng);
+            assert_eq!(dk.decrypt(ct1), m1);
+
+            let ct3 = ek.rerandomise(ct1, &mut rng);
+            assert_ne!(ct1, ct3);
+            assert_eq!(dk.decrypt(ct3), m1);
+
+            let s = Scalar448::random(&mut rng);
+            assert_eq!(dk.decrypt(ct1 + ct2), m1 + m2);
+            assert_eq!(dk.decrypt(ct1 - ct2), m1 - m2);
+            assert_eq!(dk.decrypt(ct1 * s), m1 * s);
+
+            let (open, commitment) = Commitment448::commit_with(
+                s,
+                Scalar448::random(&mut rng),
+                &crate::CommitKey::from_encryption_key_unchecked(ek),
+            );
+            assert!(commitment.verify(&open));
+        }
+    }
+
+    // Test the sizes of the byte encodings and that they round-trip.
+    #[test]
+    fn encodings_448() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey448::random(&mut rng);
+        let ek = dk.encryption_key();
+        let ct = ek.encrypt(DecafPoint::random(&mut rng), &mut rng);
+
+        let dk_bytes = dk.to_bytes();
+        let ek_bytes = ek.to_bytes();
+        let ct_bytes = ct.to_bytes();
+        assert_eq!(dk_bytes.len(), 56);
+        assert_eq!(ek_bytes.len(), 56);
+        assert_eq!(ct_bytes.len(), 112);
+
+        assert_eq!(DecryptionKey448::from_bytes(&dk_bytes), Ok(dk.clone()));
+        assert_eq!(EncryptionKey448::from_bytes(&ek_bytes), Ok(*ek));
+        assert_eq!(Ciphertext448::from_bytes(&ct_bytes), Ok(ct));
+
+        assert_eq!(
+            EncryptionKey448::from_bytes(&[0u8; 56]),
+            Err(Error::IdentityPoint)
+        );
+        assert_eq!(
+            EncryptionKey448::from_bytes(&[0xff; 56]),
+            Err(Error::InvalidPoint)
+        );
+        assert_eq!(
+            Ciphertext448::from_bytes(&[0xff; 112]),
+            Err(Error::InvalidPoint)
+        );
+        assert_eq!(
+            DecryptionKey448::from_bytes(&[0u8; 56]),
+            Err(Error::InvalidScalar)
+        );
+        assert_eq!(
+            DecryptionKey448::from_bytes(&[0xff; 56]),
+            Err(Error::InvalidScalar)
+        );
+    }
+}
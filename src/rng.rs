@@ -0,0 +1,253 @@
+// Randomness adapters for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand_core::{CryptoRng, RngCore};
+
+/// A cryptographically secure random number generator from either `rand_core` 0.6 or (with the
+/// `rand09` feature) `rand_core` 0.9, so that the key generation, encryption, re-randomisation and
+/// commitment functions take generators from either ecosystem directly.
+///
+/// Every `rand_core` 0.6 `RngCore + CryptoRng` implements `ElGamalRng<RandCore06>`, and every
+/// `rand_core` 0.9 `CryptoRng` implements `ElGamalRng<RandCore09>`. The version parameter keeps
+/// the two blanket implementations apart and is inferred at the call site, so callers never name
+/// it; generic code forwards it as in `fn f<R: ElGamalRng<V>, V>(rng: &mut R)`. A generator
+/// implementing both versions has to be passed through one of them explicitly, for example as
+/// `Rand09Rng`.
+///
+/// The trait is sealed. It is taken by [DecryptionKey::new](crate::DecryptionKey::new) and
+/// [random](crate::DecryptionKey::random), [KeyPair::generate](crate::KeyPair::generate), the
+/// `encrypt`, `exp_encrypt` and `rerandomise` functions of
+/// [EncryptionKey](crate::EncryptionKey) and [KeyPair](crate::KeyPair), and
+/// [Commitment::commit](crate::Commitment::commit) and
+/// [rerandomise](crate::Commitment::rerandomise). The rest of the crate takes `rand_core` 0.6
+/// generators, and 0.9 generators wrapped in `Rand09Rng`.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, ElGamalRng, EncryptionKey, RistrettoPoint};
+///
+/// fn fresh_pair<R: ElGamalRng<V>, V>(rng: &mut R) -> (DecryptionKey, EncryptionKey) {
+///     let dec_key = DecryptionKey::new(rng);
+///     let enc_key = *dec_key.encryption_key();
+///     (dec_key, enc_key)
+/// }
+///
+/// let mut rng = StdRng::from_entropy();
+/// let (dec_key, enc_key) = fresh_pair(&mut rng);
+/// let m = RistrettoPoint::random(&mut rng);
+/// assert_eq!(dec_key.decrypt(enc_key.encrypt(m, &mut rng)), m);
+/// ```
+pub trait ElGamalRng<V>: private::Sealed<V> {
+    /// The `rand_core` 0.6 view of the generator that the functions of this crate draw from.
+    #[doc(hidden)]
+    type Compat<'a>: RngCore + CryptoRng
+    where
+        Self: 'a;
+
+    #[doc(hidden)]
+    fn compat(&mut self) -> Self::Compat<'_>;
+}
+
+/// Marks the [ElGamalRng] implementation of `rand_core` 0.6 generators.
+#[derive(Copy, Clone, Debug)]
+pub enum RandCore06 {}
+
+/// Marks the [ElGamalRng] implementation of `rand_core` 0.9 generators.
+#[cfg(feature = "rand09")]
+#[derive(Copy, Clone, Debug)]
+pub enum RandCore09 {}
+
+mod private {
+    pub trait Sealed<V> {}
+
+    impl<R: rand_core::RngCore + rand_core::CryptoRng + ?Sized> Sealed<super::RandCore06> for R {}
+
+    #[cfg(feature = "rand09")]
+    impl<R: rand_core09::CryptoRng + ?Sized> Sealed<super::RandCore09> for R {}
+}
+
+impl<R: RngCore + CryptoRng + ?Sized> ElGamalRng<RandCore06> for R {
+    type Compat<'a>
+        = &'a mut R
+    where
+        R: 'a;
+
+    fn compat(&mut self) -> &mut R {
+        self
+    }
+}
+
+#[cfg(feature = "rand09")]
+impl<R: rand_core09::CryptoRng + ?Sized> ElGamalRng<RandCore09> for R {
+    type Compat<'a>
+        = Rand09Rng<&'a mut R>
+    where
+        R: 'a;
+
+    fn compat(&mut self) -> Rand09Rng<&mut R> {
+        Rand09Rng(self)
+    }
+}
+
+/// Adapts a `rand_core` 0.9 random number generator so it can be passed to the functions of this
+/// crate that take `rand_core` 0.6 generators (the version used by `curve25519-dalek`), rather
+/// than an [ElGamalRng].
+///
+/// The wrapper forwards every call to the inner generator, and is only a cryptographically secure
+/// generator if the inner one is.
+///
+/// # Example
+///
+/// ```rust
+/// use rand09::rngs::StdRng;
+/// use rand09::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, Rand09Rng, RistrettoPoint};
+///
+/// let mut rng = Rand09Rng(StdRng::from_os_rng());
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = enc_key.encrypt(m, &mut rng);
+/// assert_eq!(dec_key.decrypt(ct), m);
+/// ```
+#[cfg(feature = "rand09")]
+#[derive(Clone, Debug)]
+pub struct Rand09Rng<R>(pub R);
+
+#[cfg(feature = "rand09")]
+impl<R: rand_core09::RngCore> RngCore for Rand09Rng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand09")]
+impl<R: rand_core09::CryptoRng> CryptoRng for Rand09Rng<R> {}
+
+#[cfg(feature = "rand09")]
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand09::SeedableRng as _;
+    use rand_core::SeedableRng;
+
+    use crate::{
+        Commitment, DecryptionKey, ElGamalRng, KeyPair, Rand09Rng, RistrettoPoint, Scalar,
+    };
+
+    // Exercise every entry point taking an ElGamalRng with one generator, so the test below
+    // compiles it once against each rand_core version.
+    fn entry_points<R: ElGamalRng<V>, V>(rng: &mut R) {
+        let dk = DecryptionKey::new(rng);
+        let ek = dk.encryption_key();
+        let other = DecryptionKey::<RistrettoPoint>::random(rng);
+        assert_ne!(dk, other);
+
+        let m = &Scalar::from(5u32) * crate::GENERATOR_TABLE;
+        let ct = ek.encrypt(m, rng);
+        let ct = ek.rerandomise(ct, rng);
+        let (mut ct, _) = ek.rerandomise_returning(ct, rng);
+        ek.rerandomise_in_place(&mut ct, rng);
+        assert_eq!(dk.decrypt(ct), m);
+        assert_eq!(dk.decrypt(ek.exp_encrypt(Scalar::from(5u32), rng)), m);
+
+        let pair = KeyPair::generate(rng);
+        let ct = pair.rerandomise(pair.encrypt(m, rng), rng);
+        assert_eq!(pair.decrypt(ct), m);
+
+        let (open, mut commitment) = Commitment::commit(Scalar::from(7u32), rng);
+        assert!(commitment.verify(&open));
+        let open = commitment.rerandomise(open, rng);
+        assert!(commitment.verify(&open));
+    }
+
+    // Test that generators from both rand_core versions are taken directly, without the adapter.
+    #[test]
+    fn direct_rng_versions() {
+        entry_points(&mut StdRng::seed_from_u64(513));
+        entry_points(&mut rand09::rngs::StdRng::seed_from_u64(513));
+        entry_points(&mut rand09::rng());
+
+        // the adapter still works, and now simply picks the rand_core 0.6 implementation
+        entry_points(&mut Rand09Rng(rand09::rngs::StdRng::seed_from_u64(513)));
+
+        // called directly, the same rand_core 0.9 seed gives the same key as through the adapter
+        let direct = DecryptionKey::new(&mut rand09::rngs::StdRng::seed_from_u64(513));
+        let adapted = DecryptionKey::new(&mut Rand09Rng(rand09::rngs::StdRng::seed_from_u64(513)));
+        assert_eq!(direct, adapted);
+    }
+
+    // Test that key generation and encryption work with generators from both rand_core versions
+    // in the same build.
+    #[test]
+    fn both_rng_versions() {
+        const N: usize = 100;
+
+        let mut rng06 = StdRng::from_entropy();
+        let mut rng09 = Rand09Rng(rand09::rngs::StdRng::from_os_rng());
+
+        let dk06 = DecryptionKey::new(&mut rng06);
+        let dk09 = DecryptionKey::new(&mut rng09);
+        assert_ne!(dk06, dk09);
+
+        for _ in 0..N {
+            let m = RistrettoPoint::random(&mut rng09);
+            let ct = dk06.encryption_key().encrypt(m, &mut rng09);
+            assert_eq!(dk06.decrypt(ct), m);
+
+            let ct = dk09.encryption_key().encrypt(m, &mut rng06);
+            let ct = dk09.encryption_key().rerandomise(ct, &mut rng09);
+            assert_eq!(dk09.decrypt(ct), m);
+
+            let (open, commitment) = Commitment::commit(Scalar::random(&mut rng09), &mut rng09);
+            assert!(commitment.verify(&open));
+        }
+    }
+
+    // Test that the adapter forwards the inner generator's output unchanged.
+    #[test]
+    fn forwards_output() {
+        let mut a = rand09::rngs::StdRng::seed_from_u64(5);
+        let mut b = Rand09Rng(rand09::rngs::StdRng::seed_from_u64(5));
+
+        assert_eq!(
+            rand_core09::RngCore::next_u64(&mut a),
+            rand_core::RngCore::next_u64(&mut b)
+        );
+
+        let mut x = [0u8; 64];
+        let mut y = [0u8; 64];
+        rand_core09::RngCore::fill_bytes(&mut a, &mut x);
+        rand_core::RngCore::fill_bytes(&mut b, &mut y);
+        assert_eq!(x, y);
+    }
+}
@@ -0,0 +1,166 @@
+// Schnorr signatures under ElGamal keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE};
+
+// The context string of the FROST(ristretto255, SHA-512) ciphersuite of RFC 9591, whose challenge
+// these signatures share so that threshold signatures from the `frost` module verify here.
+pub(crate) const CONTEXT: &[u8] = b"FROST-RISTRETTO255-SHA512-v1";
+
+// One of the ciphersuite's hashes: SHA-512 over the context string, the tag and the data.
+pub(crate) fn hash(tag: &[u8], data: &[&[u8]]) -> Sha512 {
+    let mut hasher = Sha512::new().chain_update(CONTEXT).chain_update(tag);
+    for part in data {
+        hasher.update(part);
+    }
+    hasher
+}
+
+// The challenge `H2(R || Y || msg)`.
+pub(crate) fn challenge(r: &RistrettoPoint, key: &EncryptionKey, msg: &[u8]) -> Scalar {
+    Scalar::from_hash(hash(
+        b"chal",
+        &[r.compress().as_bytes(), key.0.compress().as_bytes(), msg],
+    ))
+}
+
+/// A Schnorr signature `(R, z)` under an [EncryptionKey] `Y`, with `zG = R + cY` for the
+/// challenge `c`, made by [DecryptionKey::sign] or jointly by the holders of shares of the key
+/// through the [frost](crate::frost) module.
+///
+/// The challenge is that of the FROST(ristretto255, SHA-512) ciphersuite of RFC 9591, so these
+/// are the signatures that ciphersuite produces and verifies.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::DecryptionKey;
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let signature = dec_key.sign(b"tally", &mut rng);
+/// assert_eq!(dec_key.encryption_key().verify_signature(b"tally", &signature), Ok(()));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SchnorrSignature {
+    pub(crate) r: RistrettoPoint,
+    pub(crate) z: Scalar,
+}
+
+impl SchnorrSignature {
+    /// Encode the signature as the compressed `R` followed by `z`, as RFC 9591 does.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.r.compress().as_bytes());
+        bytes[32..].copy_from_slice(self.z.as_bytes());
+        bytes
+    }
+
+    /// Decode a signature written by [to_bytes](Self::to_bytes).
+    ///
+    /// Fails with [Error::InvalidPoint] if `R` is not a valid point, and with
+    /// [Error::InvalidScalar] if `z` is not reduced.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<SchnorrSignature, Error> {
+        let r = CompressedRistretto::from_slice(&bytes[..32])
+            .expect("32 bytes")
+            .decompress()
+            .ok_or(Error::InvalidPoint)?;
+        let z = <[u8; 32]>::try_from(&bytes[32..]).expect("32 bytes");
+        let z = Option::from(Scalar::from_canonical_bytes(z)).ok_or(Error::InvalidScalar)?;
+        Ok(SchnorrSignature { r, z })
+    }
+}
+
+impl DecryptionKey {
+    /// Sign `msg` with a fresh nonce, for [EncryptionKey::verify_signature].
+    pub fn sign<R: RngCore + CryptoRng>(&self, msg: &[u8], rng: &mut R) -> SchnorrSignature {
+        let k = Scalar::random(rng);
+        let r = &k * GENERATOR_TABLE;
+        let c = challenge(&r, &self.ek, msg);
+        SchnorrSignature {
+            r,
+            z: k + c * self.secret,
+        }
+    }
+}
+
+impl EncryptionKey {
+    /// Verify a signature on `msg` under this key, whether made by [DecryptionKey::sign] or by a
+    /// threshold of key shares.
+    ///
+    /// Fails with [Error::VerificationFailed] if the signature does not verify.
+    pub fn verify_signature(&self, msg: &[u8], signature: &SchnorrSignature) -> Result<(), Error> {
+        let c = challenge(&signature.r, self, msg);
+        if &signature.z * GENERATOR_TABLE != signature.r + self.0 * c {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::SchnorrSignature;
+    use crate::{DecryptionKey, Error};
+
+    // Test that signatures verify for their message and key only, and survive encoding.
+    #[test]
+    fn schnorr_sign_verify() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let signature = dk.sign(b"tally", &mut rng);
+        assert_eq!(ek.verify_signature(b"tally", &signature), Ok(()));
+        assert_eq!(
+            ek.verify_signature(b"tallY", &signature),
+            Err(Error::VerificationFailed)
+        );
+        let other = DecryptionKey::new(&mut rng);
+        assert_eq!(
+            other
+                .encryption_key()
+                .verify_signature(b"tally", &signature),
+            Err(Error::VerificationFailed)
+        );
+
+        let bytes = signature.to_bytes();
+        assert_eq!(SchnorrSignature::from_bytes(&bytes), Ok(signature));
+        let mut bad = bytes;
+        bad[63] = 0xff;
+        assert_eq!(
+            SchnorrSignature::from_bytes(&bad),
+            Err(Error::InvalidScalar)
+        );
+        bad[0] = 0xff;
+        assert_eq!(SchnorrSignature::from_bytes(&bad), Err(Error::InvalidPoint));
+    }
+}
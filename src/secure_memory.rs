@@ -0,0 +1,234 @@
+// Page-locked storage for decryption keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+use core::mem::size_of;
+use core::ptr::NonNull;
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::{Ciphertext, DecryptionKey, EncryptionKey, GENERATOR_TABLE};
+
+// The secret gets a page of its own, so that locking and unlocking it never touches other data.
+// On platforms with larger pages the page it lands in may still be shared.
+const PAGE: usize = 4096;
+
+/// Whether the memory holding a [SecretDecryptionKey] is protected from swap and core dumps.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MemoryProtection {
+    /// The page is locked into RAM and, where the platform supports it, excluded from core dumps.
+    Locked,
+    /// Locking failed, typically because the process's locked-memory limit (`RLIMIT_MEMLOCK`) is
+    /// exhausted, so the page may be swapped out. The key still works and is still wiped on drop.
+    Unlocked,
+}
+
+/// A decryption key whose secret lives in its own page-locked, no-dump allocation and is wiped
+/// when dropped.
+///
+/// The secret is never copied out: [decrypt](Self::decrypt) uses it in place, and the type is
+/// neither `Copy` nor `Clone`. If the page cannot be locked the key still works, and
+/// [protection](Self::protection) reports [MemoryProtection::Unlocked] so the caller can decide
+/// whether to carry on.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{MemoryProtection, RistrettoPoint, SecretDecryptionKey};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = SecretDecryptionKey::new(&mut rng);
+/// if dec_key.protection() == MemoryProtection::Unlocked {
+///     eprintln!("warning: the decryption key may be swapped to disk");
+/// }
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+/// assert_eq!(dec_key.decrypt(ct), m);
+/// ```
+pub struct SecretDecryptionKey {
+    secret: NonNull<Scalar>,
+    ek: EncryptionKey,
+    protection: MemoryProtection,
+}
+
+// The allocation is owned uniquely and only read through `&self`.
+unsafe impl Send for SecretDecryptionKey {}
+unsafe impl Sync for SecretDecryptionKey {}
+
+impl SecretDecryptionKey {
+    /// Generate a new key in protected memory.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretDecryptionKey {
+        SecretDecryptionKey::from_scalar(Scalar::random(rng))
+    }
+
+    /// Move an existing key into protected memory, wiping the copy passed in.
+    ///
+    /// Clones of the key made before this call are not affected.
+    pub fn from_decryption_key(mut dk: DecryptionKey) -> SecretDecryptionKey {
+        let key = SecretDecryptionKey::from_scalar(dk.secret);
+        dk.secret.zeroize();
+        key
+    }
+
+    fn from_scalar(mut secret: Scalar) -> SecretDecryptionKey {
+        let key =
+            SecretDecryptionKey::with_lock(&secret, |ptr, len| unsafe { memsec::mlock(ptr, len) });
+        secret.zeroize();
+        key
+    }
+
+    // Allocate the page, lock it with `lock`, and only then write the secret into it.
+    fn with_lock(
+        secret: &Scalar,
+        lock: impl FnOnce(*mut u8, usize) -> bool,
+    ) -> SecretDecryptionKey {
+        let layout = layout();
+        let page = unsafe { alloc_zeroed(layout) };
+        let ptr = match NonNull::new(page as *mut Scalar) {
+            Some(ptr) => ptr,
+            None => handle_alloc_error(layout),
+        };
+        let protection = if lock(page, PAGE) {
+            MemoryProtection::Locked
+        } else {
+            MemoryProtection::Unlocked
+        };
+        unsafe { ptr.as_ptr().write(*secret) };
+
+        SecretDecryptionKey {
+            secret: ptr,
+            ek: EncryptionKey(unsafe { ptr.as_ref() } * GENERATOR_TABLE),
+            protection,
+        }
+    }
+
+    /// Whether the secret's memory is locked.
+    pub fn protection(&self) -> MemoryProtection {
+        self.protection
+    }
+
+    /// Decrypt the ciphertext, using the secret where it lies.
+    pub fn decrypt(&self, ct: Ciphertext) -> RistrettoPoint {
+        ct.1 - ct.0 * unsafe { self.secret.as_ref() }
+    }
+
+    /// Retrieve the encryption key corresponding to this decryption key.
+    pub fn encryption_key(&self) -> &EncryptionKey {
+        &self.ek
+    }
+}
+
+fn layout() -> Layout {
+    debug_assert!(size_of::<Scalar>() <= PAGE);
+    Layout::from_size_align(PAGE, PAGE).expect("a page is a valid layout")
+}
+
+impl Drop for SecretDecryptionKey {
+    fn drop(&mut self) {
+        let page = self.secret.as_ptr() as *mut u8;
+        unsafe {
+            // munlock also wipes, but not if the page was never locked
+            memsec::memzero(page, PAGE);
+            if self.protection == MemoryProtection::Locked {
+                memsec::munlock(page, PAGE);
+            }
+            dealloc(page, layout());
+        }
+    }
+}
+
+impl Debug for SecretDecryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "SecretDecryptionKey {{ ek: {:?}, protection: {:?}, .. }}",
+            self.ek, self.protection
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{MemoryProtection, SecretDecryptionKey};
+    use crate::{DecryptionKey, RistrettoPoint, Scalar};
+
+    // Test that a protected key decrypts as the plain key does, whether or not locking worked.
+    #[test]
+    fn secure_memory_decrypt() {
+        let mut rng = StdRng::seed_from_u64(478);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let secret = SecretDecryptionKey::from_decryption_key(dk.clone());
+        assert_eq!(secret.encryption_key(), &ek);
+
+        for _ in 0..10 {
+            let m = RistrettoPoint::random(&mut rng);
+            let ct = ek.encrypt(m, &mut rng);
+            assert_eq!(secret.decrypt(ct), m);
+            assert_eq!(secret.decrypt(ct), dk.decrypt(ct));
+        }
+
+        let fresh = SecretDecryptionKey::new(&mut rng);
+        assert_ne!(fresh.encryption_key(), &ek);
+        let m = RistrettoPoint::random(&mut rng);
+        assert_eq!(
+            fresh.decrypt(fresh.encryption_key().encrypt(m, &mut rng)),
+            m
+        );
+
+        // the secret stays out of debug output
+        let debug = format!("{:?}", fresh);
+        assert!(debug.contains("protection"));
+        assert!(!debug.contains("Scalar"));
+    }
+
+    // Test that a failed lock is reported, and that the key still works and still drops cleanly.
+    #[test]
+    fn secure_memory_fallback() {
+        let mut rng = StdRng::seed_from_u64(478);
+        let x = Scalar::random(&mut rng);
+        let dk = DecryptionKey::from(x);
+
+        let unlocked = SecretDecryptionKey::with_lock(&x, |_, _| false);
+        assert_eq!(unlocked.protection(), MemoryProtection::Unlocked);
+        assert_eq!(unlocked.encryption_key(), dk.encryption_key());
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = dk.encryption_key().encrypt(m, &mut rng);
+        assert_eq!(unlocked.decrypt(ct), m);
+        drop(unlocked);
+
+        // the lock sees the whole page, before the secret is written to it
+        let locked = SecretDecryptionKey::with_lock(&x, |ptr, len| {
+            assert_eq!(len, 4096);
+            assert_eq!(ptr as usize % 4096, 0);
+            assert!(unsafe { core::slice::from_raw_parts(ptr, len) }
+                .iter()
+                .all(|b| *b == 0));
+            true
+        });
+        assert_eq!(locked.protection(), MemoryProtection::Locked);
+        assert_eq!(locked.decrypt(ct), m);
+    }
+}
@@ -0,0 +1,388 @@
+// Shamir shares of decryption keys for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lagrange_coefficients, Ciphertext, DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE,
+};
+
+/// A Shamir share of a [DecryptionKey]: the value `f(i)` at the share's index `i` of a random
+/// polynomial `f` of degree `t - 1` with `f(0)` the secret, so that any `t` shares determine the
+/// key and fewer reveal nothing about it.
+///
+/// Shares are made by [DecryptionKey::split], and carry the index, the threshold `t` and the
+/// [EncryptionKey] of the whole key.
+///
+/// Like a [DecryptionKey], a share is secret: debug output shows only its index and threshold,
+/// equality compares the secrets in constant time, through [ConstantTimeEq], it is serialised
+/// only with the `serde-secret` feature, and with the `zeroize` feature it is wiped when dropped.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde-secret", derive(Serialize, Deserialize))]
+pub struct KeyShare {
+    pub(crate) index: u32,
+    pub(crate) threshold: usize,
+    pub(crate) secret: Scalar,
+    pub(crate) group_key: EncryptionKey,
+}
+
+impl KeyShare {
+    /// The index `i` of the share, from 1.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The number of shares needed to use the key.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The public verification share `f(i) G`, against which the share's contributions are
+    /// checked.
+    pub fn verification_share(&self) -> RistrettoPoint {
+        &self.secret * GENERATOR_TABLE
+    }
+
+    /// The encryption key of the whole key.
+    pub fn group_key(&self) -> &EncryptionKey {
+        &self.group_key
+    }
+
+    /// This share's part of the decryption of `ct`: the first component of `ct` times the
+    /// share's secret.
+    ///
+    /// Parts from at least [threshold](KeyShare::threshold) shares are combined with
+    /// [Ciphertext::combine_shares]. A part reveals nothing about the share's secret, but it is
+    /// not proven correct, so a holder who lies about it makes the combination come out wrong.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::{DecryptionKey, Scalar, GENERATOR_TABLE};
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    /// let shares = dec_key.split(2, 3, &mut rng);
+    ///
+    /// let m = &Scalar::from(7u32) * GENERATOR_TABLE;
+    /// let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+    /// let parts = [shares[0].partial_decrypt(&ct), shares[2].partial_decrypt(&ct)];
+    /// assert_eq!(ct.combine_shares(&parts), Ok(m));
+    /// ```
+    pub fn partial_decrypt(&self, ct: &Ciphertext) -> DecryptionShare {
+        DecryptionShare {
+            index: self.index,
+            threshold: self.threshold,
+            group_key: self.group_key,
+            c1: ct.0,
+            share: ct.0 * self.secret,
+        }
+    }
+}
+
+impl Debug for KeyShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConstantTimeEq for KeyShare {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.index.ct_eq(&other.index)
+            & self.threshold.ct_eq(&other.threshold)
+            & self.secret.ct_eq(&other.secret)
+            & self.group_key.ct_eq(&other.group_key)
+    }
+}
+
+impl PartialEq for KeyShare {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for KeyShare {}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for KeyShare {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for KeyShare {}
+
+/// One [KeyShare]'s part of the decryption of a ciphertext, made by
+/// [KeyShare::partial_decrypt].
+///
+/// The part records the share's index and threshold, the key it was split from and the
+/// ciphertext it decrypts, so that [Ciphertext::combine_shares] can refuse parts that do not
+/// belong together.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct DecryptionShare {
+    pub(crate) index: u32,
+    pub(crate) threshold: usize,
+    pub(crate) group_key: EncryptionKey,
+    pub(crate) c1: RistrettoPoint,
+    pub(crate) share: RistrettoPoint,
+}
+
+impl DecryptionShare {
+    /// The index of the share that made this part.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The number of parts needed to decrypt.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+}
+
+impl Ciphertext {
+    /// Decrypt the ciphertext from the parts of at least `t` of the shares of its key, by
+    /// Lagrange interpolation in the exponent.
+    ///
+    /// Fails with [Error::InsufficientShares] if there are fewer parts than the threshold (or
+    /// none), [Error::KeyMismatch] if the parts come from different keys or splits,
+    /// [Error::InvalidShare] naming the first part made for a different ciphertext, and
+    /// [Error::DuplicateIndex] if two parts come from the same share. Parts are not proven
+    /// correct, so a wrong part from the right share gives a wrong point rather than an error.
+    pub fn combine_shares(&self, shares: &[DecryptionShare]) -> Result<RistrettoPoint, Error> {
+        let first = shares.first().ok_or(Error::InsufficientShares {
+            threshold: 1,
+            got: 0,
+        })?;
+        if shares
+            .iter()
+            .any(|s| s.group_key != first.group_key || s.threshold != first.threshold)
+        {
+            return Err(Error::KeyMismatch);
+        }
+        if let Some(s) = shares.iter().find(|s| s.c1 != self.0) {
+            return Err(Error::InvalidShare { index: s.index });
+        }
+        if shares.len() < first.threshold {
+            return Err(Error::InsufficientShares {
+                threshold: first.threshold,
+                got: shares.len(),
+            });
+        }
+
+        let xs: Vec<_> = shares.iter().map(|s| Scalar::from(s.index)).collect();
+        let lambda = lagrange_coefficients(&xs, Scalar::ZERO)?;
+        Ok(self.1 - RistrettoPoint::multiscalar_mul(&lambda, shares.iter().map(|s| s.share)))
+    }
+}
+
+impl DecryptionKey {
+    /// Split the key into `n` Shamir shares, with indices `1..=n`, any `t` of which can use it.
+    ///
+    /// The caller acts as a trusted dealer: it sees the whole secret, and should discard the key
+    /// once the shares are handed out.
+    ///
+    /// # Panics
+    ///
+    /// If `t` is zero or greater than `n`, or `n` does not fit in a `u32`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let shares = dec_key.split(3, 5, &mut rng);
+    /// assert_eq!(shares.len(), 5);
+    /// assert_eq!(shares[4].index(), 5);
+    /// assert_eq!(shares[0].group_key(), dec_key.encryption_key());
+    /// ```
+    pub fn split<R: RngCore + CryptoRng>(&self, t: usize, n: usize, rng: &mut R) -> Vec<KeyShare> {
+        assert!(t >= 1 && t <= n, "the threshold must be between 1 and n");
+        let n = u32::try_from(n).expect("at most u32::MAX shares");
+
+        let mut coefficients = Vec::with_capacity(t);
+        coefficients.push(self.secret);
+        coefficients.extend((1..t).map(|_| Scalar::random(rng)));
+        (1..=n)
+            .map(|index| {
+                // Horner's rule
+                let x = Scalar::from(index);
+                let secret = coefficients
+                    .iter()
+                    .rev()
+                    .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient);
+                KeyShare {
+                    index,
+                    threshold: t,
+                    secret,
+                    group_key: self.ek,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{lagrange_coefficients, DecryptionKey, Error, Scalar, GENERATOR_TABLE};
+
+    // Test that any t shares interpolate to the secret, and t - 1 shares do not.
+    #[test]
+    fn split_interpolates() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        for &(t, n) in &[(1, 1), (1, 3), (2, 3), (3, 5), (5, 5)] {
+            let shares = dk.split(t, n, &mut rng);
+            for start in 0..=n - t {
+                let subset = &shares[start..start + t];
+                let xs: Vec<_> = subset.iter().map(|s| Scalar::from(s.index())).collect();
+                let lambda = lagrange_coefficients(&xs, Scalar::ZERO).unwrap();
+                let secret: Scalar = lambda.iter().zip(subset).map(|(l, s)| l * s.secret).sum();
+                assert_eq!(secret, dk.secret);
+                if t > 1 {
+                    let lambda = lagrange_coefficients(&xs[1..], Scalar::ZERO).unwrap();
+                    let guess: Scalar = lambda
+                        .iter()
+                        .zip(&subset[1..])
+                        .map(|(l, s)| l * s.secret)
+                        .sum();
+                    assert_ne!(guess, dk.secret);
+                }
+            }
+            for share in &shares {
+                assert_eq!(share.threshold(), t);
+                assert_eq!(share.verification_share(), &share.secret * GENERATOR_TABLE);
+            }
+        }
+    }
+
+    // Test that exactly t parts and more than t parts decrypt, for several thresholds, and that
+    // t - 1 parts, parts for another ciphertext or key, and repeated parts are refused.
+    #[test]
+    fn threshold_decrypt() {
+        let mut rng = StdRng::seed_from_u64(507);
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng).split(2, 3, &mut rng);
+        let m = &Scalar::from(42u32) * GENERATOR_TABLE;
+        let ct = dk.encryption_key().encrypt(m, &mut rng);
+        let ct2 = dk.encryption_key().encrypt(m, &mut rng);
+
+        for &(t, n) in &[(1, 1), (1, 3), (2, 3), (3, 5), (4, 7), (5, 5)] {
+            let shares = dk.split(t, n, &mut rng);
+            let parts: Vec<_> = shares.iter().map(|s| s.partial_decrypt(&ct)).collect();
+            for start in 0..=n - t {
+                assert_eq!(ct.combine_shares(&parts[start..start + t]), Ok(m));
+            }
+            assert_eq!(ct.combine_shares(&parts), Ok(m));
+            assert_eq!(ct.combine_shares(&parts[n - t..]), Ok(m));
+            assert_eq!(
+                ct.combine_shares(&parts[..t - 1]),
+                Err(Error::InsufficientShares {
+                    threshold: t,
+                    got: t - 1
+                })
+            );
+
+            // a part made for another ciphertext
+            let mut mixed = parts[..t].to_vec();
+            mixed[t - 1] = shares[t - 1].partial_decrypt(&ct2);
+            assert_eq!(
+                ct.combine_shares(&mixed),
+                Err(Error::InvalidShare { index: t as u32 })
+            );
+
+            // a part from a share of another key, and a part repeated
+            let mut mixed = parts[..t].to_vec();
+            mixed.push(other[0].partial_decrypt(&ct));
+            assert_eq!(ct.combine_shares(&mixed), Err(Error::KeyMismatch));
+            let mut repeated = parts[..t].to_vec();
+            repeated.push(parts[0]);
+            assert_eq!(ct.combine_shares(&repeated), Err(Error::DuplicateIndex));
+        }
+
+        // a corrupt part from the right share decrypts to the wrong point
+        let shares = dk.split(2, 3, &mut rng);
+        let mut parts = [
+            shares[0].partial_decrypt(&ct),
+            shares[1].partial_decrypt(&ct),
+        ];
+        parts[1].share += GENERATOR_TABLE.basepoint();
+        let wrong = ct.combine_shares(&parts).unwrap();
+        assert_ne!(wrong, m);
+    }
+
+    // Test that debug output shows only the index and threshold of a share, and that equality
+    // tells shares apart by their secrets.
+    #[test]
+    fn key_share_redacted() {
+        let mut rng = StdRng::seed_from_u64(498);
+        let dk = DecryptionKey::new(&mut rng);
+        let shares = dk.split(2, 3, &mut rng);
+
+        assert_eq!(
+            alloc::format!("{:?}", shares[1]),
+            "KeyShare { index: 2, threshold: 2, .. }"
+        );
+        assert_eq!(shares[0], shares[0].clone());
+        assert_ne!(shares[0], shares[1]);
+        let mut tampered = shares[0].clone();
+        tampered.secret += Scalar::ONE;
+        assert_ne!(tampered, shares[0]);
+    }
+
+    // Test that a threshold above the number of shares is refused.
+    #[test]
+    #[should_panic]
+    fn split_rejects_threshold_above_n() {
+        let mut rng = StdRng::seed_from_u64(498);
+        DecryptionKey::new(&mut rng).split(4, 3, &mut rng);
+    }
+}
@@ -0,0 +1,264 @@
+// Signcryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DecryptionKey, EncryptionKey, Error, GENERATOR_TABLE};
+
+const KDF_INFO: &[u8] = b"rust-elgamal signcrypt v1";
+const TAG_DOMAIN: &[u8] = b"rust-elgamal signcrypt tag";
+
+/// A message encrypted to a recipient and signed by its sender in one pass, as produced by
+/// [signcrypt].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct SigncryptedMessage {
+    pub(crate) ciphertext: Vec<u8>,
+    pub(crate) r: Scalar,
+    pub(crate) s: Scalar,
+}
+
+// Derive the encryption and tag keys from the shared point, bound to both identities in order.
+fn derive_keys(
+    shared: &RistrettoPoint,
+    sender: &EncryptionKey,
+    recipient: &EncryptionKey,
+) -> (Key, [u8; 32]) {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha512>::new(None, shared.compress().as_bytes())
+        .expand_multi_info(
+            &[
+                KDF_INFO,
+                sender.0.compress().as_bytes(),
+                recipient.0.compress().as_bytes(),
+            ],
+            &mut okm,
+        )
+        .expect("64 bytes is a valid HKDF-SHA512 output length");
+
+    let mut key = Key::default();
+    let mut tag_key = [0u8; 32];
+    key.copy_from_slice(&okm[..32]);
+    tag_key.copy_from_slice(&okm[32..]);
+    (key, tag_key)
+}
+
+// The keyed hash binding the message to both identities.
+fn tag(
+    tag_key: &[u8; 32],
+    sender: &EncryptionKey,
+    recipient: &EncryptionKey,
+    msg: &[u8],
+) -> Scalar {
+    Scalar::from_hash(
+        Sha512::new()
+            .chain_update((TAG_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(TAG_DOMAIN)
+            .chain_update(tag_key)
+            .chain_update(sender.0.compress().as_bytes())
+            .chain_update(recipient.0.compress().as_bytes())
+            .chain_update(msg),
+    )
+}
+
+/// Encrypt `msg` to `recipient` and sign it with `sender`'s key, using Zheng's elliptic-curve
+/// signcryption.
+///
+/// The sender picks a random `v`, derives keys from `K = vY_r` and both identities, encrypts the
+/// message, and computes the tag `r` (a keyed hash of the message and identities) and
+/// `s = v / (r + x_s)`. The recipient recovers `K = x_r s (Y_s + rG)`, which only works for the
+/// intended sender and recipient, decrypts, and recomputes `r`. Nobody without `sender`'s key,
+/// not even the recipient, can produce a message that verifies as coming from `sender`.
+///
+/// Like [seal](EncryptionKey::seal), this mode is not homomorphic.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{signcrypt, unsigncrypt, DecryptionKey};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let alice = DecryptionKey::new(&mut rng);
+/// let bob = DecryptionKey::new(&mut rng);
+///
+/// let msg = signcrypt(&alice, bob.encryption_key(), b"hi bob", &mut rng);
+/// assert_eq!(unsigncrypt(&bob, alice.encryption_key(), &msg).unwrap(), b"hi bob");
+/// ```
+pub fn signcrypt<R: RngCore + CryptoRng>(
+    sender: &DecryptionKey,
+    recipient: &EncryptionKey,
+    msg: &[u8],
+    rng: &mut R,
+) -> SigncryptedMessage {
+    let sender_ek = sender.encryption_key();
+    loop {
+        let v = Scalar::random(rng);
+        let (key, tag_key) = derive_keys(&(recipient.0 * v), sender_ek, recipient);
+        let r = tag(&tag_key, sender_ek, recipient, msg);
+
+        // `r + x_s` is zero with negligible probability, but has no inverse when it is
+        let denominator = r + sender.secret;
+        if denominator == Scalar::ZERO {
+            continue;
+        }
+
+        // every key is used for exactly one message, so a fixed nonce is safe
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(&Nonce::default(), msg)
+            .expect("message is within the ChaCha20-Poly1305 length limit");
+        return SigncryptedMessage {
+            ciphertext,
+            r,
+            s: v * denominator.invert(),
+        };
+    }
+}
+
+/// Decrypt a message produced by [signcrypt], and check that it was signed by `sender`.
+///
+/// Fails with [Error::VerificationFailed] if the message was not signcrypted by `sender` to
+/// `recipient`, or was tampered with.
+pub fn unsigncrypt(
+    recipient: &DecryptionKey,
+    sender: &EncryptionKey,
+    msg: &SigncryptedMessage,
+) -> Result<Vec<u8>, Error> {
+    // `s = 0` would give the identity as the shared point whatever the keys
+    let shared = (sender.0 + &msg.r * GENERATOR_TABLE) * (msg.s * recipient.secret);
+    if shared.is_identity() {
+        return Err(Error::VerificationFailed);
+    }
+
+    let recipient_ek = recipient.encryption_key();
+    let (key, tag_key) = derive_keys(&shared, sender, recipient_ek);
+    let plaintext = ChaCha20Poly1305::new(&key)
+        .decrypt(&Nonce::default(), msg.ciphertext.as_slice())
+        .map_err(|_| Error::VerificationFailed)?;
+
+    if tag(&tag_key, sender, recipient_ek, &plaintext) != msg.r {
+        return Err(Error::VerificationFailed);
+    }
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::signcrypt::SigncryptedMessage;
+    use crate::{signcrypt, unsigncrypt, DecryptionKey, Error, Scalar};
+
+    // Test that signcrypted messages of various lengths round-trip.
+    #[test]
+    fn signcrypt_roundtrip() {
+        let mut rng = StdRng::from_entropy();
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+
+        for msg in [&b""[..], b"x", &[9u8; 1000]] {
+            let sc = signcrypt(&alice, bob.encryption_key(), msg, &mut rng);
+            assert_eq!(unsigncrypt(&bob, alice.encryption_key(), &sc).unwrap(), msg);
+            assert_ne!(sc, signcrypt(&alice, bob.encryption_key(), msg, &mut rng));
+        }
+    }
+
+    // Test that the wrong sender or recipient, or a swap of the two, is rejected.
+    #[test]
+    fn signcrypt_wrong_parties() {
+        let mut rng = StdRng::from_entropy();
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+        let carol = DecryptionKey::new(&mut rng);
+
+        let sc = signcrypt(&alice, bob.encryption_key(), b"hi bob", &mut rng);
+
+        // wrong sender
+        assert_eq!(
+            unsigncrypt(&bob, carol.encryption_key(), &sc),
+            Err(Error::VerificationFailed)
+        );
+        // wrong recipient
+        assert_eq!(
+            unsigncrypt(&carol, alice.encryption_key(), &sc),
+            Err(Error::VerificationFailed)
+        );
+        // reflected back as though bob had sent it to alice
+        assert_eq!(
+            unsigncrypt(&alice, bob.encryption_key(), &sc),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that a message cannot be replayed into a different context, or have its parts mixed
+    // with another message's.
+    #[test]
+    fn signcrypt_replay() {
+        let mut rng = StdRng::from_entropy();
+        let alice = DecryptionKey::new(&mut rng);
+        let bob = DecryptionKey::new(&mut rng);
+        let carol = DecryptionKey::new(&mut rng);
+
+        let to_bob = signcrypt(&alice, bob.encryption_key(), b"pay 10", &mut rng);
+        let to_carol = signcrypt(&alice, carol.encryption_key(), b"pay 10", &mut rng);
+
+        // bob forwards alice's message to carol
+        assert_eq!(
+            unsigncrypt(&carol, alice.encryption_key(), &to_bob),
+            Err(Error::VerificationFailed)
+        );
+
+        // splice the signature of one message onto the ciphertext of the other
+        let spliced = SigncryptedMessage {
+            ciphertext: to_bob.ciphertext.clone(),
+            ..to_carol.clone()
+        };
+        assert_eq!(
+            unsigncrypt(&carol, alice.encryption_key(), &spliced),
+            Err(Error::VerificationFailed)
+        );
+
+        let mut tampered = to_bob.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(
+            unsigncrypt(&bob, alice.encryption_key(), &tampered),
+            Err(Error::VerificationFailed)
+        );
+
+        // a zero `s` makes the shared point independent of every key
+        let zero = SigncryptedMessage {
+            s: Scalar::ZERO,
+            ..to_bob
+        };
+        assert_eq!(
+            unsigncrypt(&bob, alice.encryption_key(), &zero),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
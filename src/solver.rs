@@ -0,0 +1,220 @@
+// Resumable discrete-log search for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::collections::BTreeMap;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dlog::isqrt;
+use crate::{GENERATOR_POINT, GENERATOR_TABLE};
+
+/// The state of a [DlogSolver] after a call to [step](DlogSolver::step).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SolverStatus {
+    /// The discrete logarithm was found.
+    Found(u64),
+    /// The whole range was searched without finding it.
+    Exhausted,
+    /// The search is not finished; `fraction` of the work has been done.
+    InProgress { fraction: f64 },
+}
+
+/// A baby-step giant-step search for `x` in `[0, max]` with `xG == target`, run a bounded number
+/// of group operations at a time.
+///
+/// The solver does no work outside [step](Self::step) and keeps no threads, so a search can be
+/// interleaved with other work, reported on and abandoned at any point. With the `enable-serde`
+/// feature the solver can be serialised to checkpoint a search: only the target and the giant
+/// steps taken so far are saved, and the baby-step table is rebuilt by the first steps after
+/// resuming.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, DlogSolver, Scalar, SolverStatus};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let ct = dec_key.encryption_key().exp_encrypt(Scalar::from(54321u32), &mut rng);
+///
+/// let mut solver = DlogSolver::new(dec_key.decrypt(ct), 1 << 20);
+/// let result = loop {
+///     match solver.step(100) {
+///         SolverStatus::InProgress { fraction } => println!("{:.0}% done", fraction * 100.0),
+///         status => break status,
+///     }
+/// };
+/// assert_eq!(result, SolverStatus::Found(54321));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct DlogSolver {
+    target: RistrettoPoint,
+    max: u64,
+    baby_steps: u64,
+    next_giant: u64,
+    found: Option<u64>,
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    table: BTreeMap<[u8; 32], u64>,
+}
+
+impl DlogSolver {
+    /// A solver for `x` in `[0, max]` with `xG == target`, with about `sqrt(max)` baby steps.
+    pub fn new(target: RistrettoPoint, max: u64) -> DlogSolver {
+        DlogSolver {
+            target,
+            max,
+            baby_steps: isqrt(max) + 1,
+            next_giant: 0,
+            found: None,
+            table: BTreeMap::new(),
+        }
+    }
+
+    /// Do at most `budget` group operations of the search, and report where it stands.
+    ///
+    /// Once the search has finished, further calls do nothing and return the same status.
+    pub fn step(&mut self, budget: u64) -> SolverStatus {
+        let mut budget = budget;
+
+        // baby steps: j * G for j in [0, m)
+        let built = self.table.len() as u64;
+        if built < self.baby_steps && self.found.is_none() {
+            let n = budget.min(self.baby_steps - built);
+            let mut point = &Scalar::from(built) * GENERATOR_TABLE;
+            for j in built..built + n {
+                self.table.insert(point.compress().to_bytes(), j);
+                point += GENERATOR_POINT;
+            }
+            budget -= n;
+        }
+
+        // giant steps: target - i * m * G for i in [0, max / m]
+        if self.table.len() as u64 == self.baby_steps {
+            let giants = self.max / self.baby_steps + 1;
+            let giant = -(&Scalar::from(self.baby_steps) * GENERATOR_TABLE);
+            let mut point = self.target + giant * Scalar::from(self.next_giant);
+            while budget > 0 && self.found.is_none() && self.next_giant < giants {
+                if let Some(j) = self.table.get(point.compress().as_bytes()) {
+                    let x = self.next_giant as u128 * self.baby_steps as u128 + *j as u128;
+                    if x <= self.max as u128 {
+                        self.found = Some(x as u64);
+                    } else {
+                        self.next_giant = giants;
+                    }
+                    break;
+                }
+                point += giant;
+                self.next_giant += 1;
+                budget -= 1;
+            }
+        }
+
+        self.status()
+    }
+
+    /// Where the search stands, without doing any work.
+    ///
+    /// The fraction counts rebuilding the baby-step table, so it drops back for a while after a
+    /// checkpoint is resumed.
+    pub fn status(&self) -> SolverStatus {
+        if let Some(x) = self.found {
+            return SolverStatus::Found(x);
+        }
+        let giants = self.max / self.baby_steps + 1;
+        if self.next_giant >= giants {
+            return SolverStatus::Exhausted;
+        }
+        let done = self.table.len() as f64 + self.next_giant as f64;
+        let total = self.baby_steps as f64 + giants as f64;
+        SolverStatus::InProgress {
+            fraction: done / total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, DlogSolver, Scalar, SolverStatus, GENERATOR_TABLE};
+
+    fn run(solver: &mut DlogSolver, budget: u64) -> (SolverStatus, usize) {
+        let mut calls = 0;
+        let mut last = 0.0;
+        loop {
+            calls += 1;
+            match solver.step(budget) {
+                SolverStatus::InProgress { fraction } => {
+                    assert!(fraction >= last && fraction < 1.0);
+                    last = fraction;
+                }
+                status => return (status, calls),
+            }
+        }
+    }
+
+    // Test that planted values are found across many steps, and out-of-range ones are not.
+    #[test]
+    fn solver_steps() {
+        const MAX: u64 = 1 << 20;
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        for x in [0, 1, 1024, 1025, 700_001, MAX] {
+            let ct = ek.exp_encrypt(Scalar::from(x), &mut rng);
+            let mut solver = DlogSolver::new(dk.decrypt(ct), MAX);
+            let (status, calls) = run(&mut solver, 64);
+            assert_eq!(status, SolverStatus::Found(x));
+            assert!(calls > 16);
+            assert_eq!(solver.step(64), SolverStatus::Found(x));
+        }
+
+        let mut solver = DlogSolver::new(&Scalar::from(MAX + 1) * GENERATOR_TABLE, MAX);
+        assert_eq!(run(&mut solver, 1000).0, SolverStatus::Exhausted);
+        assert_eq!(solver.status(), SolverStatus::Exhausted);
+
+        let mut solver = DlogSolver::new(&Scalar::from(5u32) * GENERATOR_TABLE, 0);
+        assert_eq!(solver.step(10), SolverStatus::Exhausted);
+    }
+
+    // Test that a search checkpointed half way through resumes and finds the value.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn solver_resume() {
+        const MAX: u64 = 1 << 20;
+        let x = 987_654;
+        let mut solver = DlogSolver::new(&Scalar::from(x) * GENERATOR_TABLE, MAX);
+
+        // build the table and take half of the giant steps
+        for _ in 0..1536 {
+            assert!(matches!(solver.step(1), SolverStatus::InProgress { .. }));
+        }
+        let checkpoint = bincode::serialize(&solver).unwrap();
+        assert!(checkpoint.len() < 100);
+        drop(solver);
+
+        let mut resumed: DlogSolver = bincode::deserialize(&checkpoint).unwrap();
+        assert_eq!(run(&mut resumed, 50).0, SolverStatus::Found(x));
+    }
+}
@@ -0,0 +1,235 @@
+// sqlx integration for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Postgres `BYTEA` columns of ciphertexts, commitments and encryption keys through
+//! [sqlx](https://docs.rs/sqlx/).
+//!
+//! Each type is stored as its canonical encoding: a ciphertext as its 64 compressed bytes, an
+//! encryption key as its 32, and a commitment as the key followed by the ciphertext, 96 bytes in
+//! all. Decoding rejects columns of the wrong length, invalid points, identity keys and
+//! commitments that fail [Commitment::validate].
+
+use std::vec::Vec;
+
+use ::sqlx::decode::Decode;
+use ::sqlx::encode::{Encode, IsNull};
+use ::sqlx::error::BoxDynError;
+use ::sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef, Postgres};
+use ::sqlx::types::Type;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::traits::IsIdentity;
+
+use crate::{Ciphertext, Commitment, CompressedCiphertext, EncryptionKey, Error};
+
+// The canonical encodings, and their checked inverses.
+
+fn key_to_bytes(ek: &EncryptionKey) -> [u8; 32] {
+    ek.0.compress().to_bytes()
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Result<EncryptionKey, Error> {
+    let bytes = fixed::<32>(bytes)?;
+    let point = CompressedRistretto(bytes)
+        .decompress()
+        .ok_or(Error::InvalidPoint)?;
+    if point.is_identity() {
+        return Err(Error::IdentityPoint);
+    }
+    Ok(EncryptionKey(point))
+}
+
+fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Ciphertext, Error> {
+    CompressedCiphertext::from_bytes(&fixed::<64>(bytes)?).decompress()
+}
+
+fn commitment_to_bytes(commitment: &Commitment) -> [u8; 96] {
+    let (ek, ct) = commitment.inner();
+    let mut bytes = [0u8; 96];
+    bytes[..32].copy_from_slice(&key_to_bytes(&ek));
+    bytes[32..].copy_from_slice(&ct.compress().to_bytes());
+    bytes
+}
+
+fn commitment_from_bytes(bytes: &[u8]) -> Result<Commitment, Error> {
+    let bytes = fixed::<96>(bytes)?;
+    let commitment = Commitment::from((
+        key_from_bytes(&bytes[..32])?,
+        ciphertext_from_bytes(&bytes[32..])?,
+    ));
+    commitment.validate()?;
+    Ok(commitment)
+}
+
+fn fixed<const N: usize>(bytes: &[u8]) -> Result<[u8; N], Error> {
+    if bytes.len() != N {
+        return Err(Error::LengthMismatch {
+            expected: N,
+            got: bytes.len(),
+        });
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(bytes);
+    Ok(out)
+}
+
+// `Vec<u8>` decodes BYTEA from both the binary and the text protocol.
+fn decode_bytes(value: PgValueRef<'_>) -> Result<Vec<u8>, BoxDynError> {
+    <Vec<u8> as Decode<Postgres>>::decode(value)
+}
+
+macro_rules! impl_bytea {
+    ($t:ty, $to_bytes:expr, $from_bytes:expr) => {
+        impl Type<Postgres> for $t {
+            fn type_info() -> PgTypeInfo {
+                <Vec<u8> as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <Vec<u8> as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl PgHasArrayType for $t {
+            fn array_type_info() -> PgTypeInfo {
+                <Vec<u8> as PgHasArrayType>::array_type_info()
+            }
+        }
+
+        impl Encode<'_, Postgres> for $t {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+                buf.extend_from_slice(&$to_bytes(self));
+                Ok(IsNull::No)
+            }
+        }
+
+        impl<'r> Decode<'r, Postgres> for $t {
+            fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok($from_bytes(&decode_bytes(value)?)?)
+            }
+        }
+    };
+}
+
+impl_bytea!(
+    Ciphertext,
+    |ct: &Ciphertext| ct.compress().to_bytes(),
+    ciphertext_from_bytes
+);
+impl_bytea!(Commitment, commitment_to_bytes, commitment_from_bytes);
+impl_bytea!(EncryptionKey, key_to_bytes, key_from_bytes);
+
+#[cfg(test)]
+mod tests {
+    use ::sqlx::encode::{Encode, IsNull};
+    use ::sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, Postgres};
+    use ::sqlx::types::Type;
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{ciphertext_from_bytes, commitment_from_bytes, key_from_bytes};
+    use crate::{
+        Ciphertext, Commitment, DecryptionKey, EncryptionKey, Error, Identity, RistrettoPoint,
+        Scalar,
+    };
+
+    fn encode<'q, T: Encode<'q, Postgres>>(value: &T) -> std::vec::Vec<u8> {
+        let mut buf = PgArgumentBuffer::default();
+        assert!(matches!(value.encode_by_ref(&mut buf), Ok(IsNull::No)));
+        buf.to_vec()
+    }
+
+    // Test that every type is a BYTEA, and that decoding what was encoded gives it back.
+    #[test]
+    fn sqlx_encode_decode() {
+        let mut rng = StdRng::seed_from_u64(476);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let (_, commitment) = Commitment::commit(Scalar::from(476u32), &mut rng);
+
+        for ty in [
+            <Ciphertext as Type<Postgres>>::type_info(),
+            <Commitment as Type<Postgres>>::type_info(),
+            <EncryptionKey as Type<Postgres>>::type_info(),
+        ] {
+            assert_eq!(ty, PgTypeInfo::with_name("BYTEA"));
+        }
+
+        let bytes = encode(&ct);
+        assert_eq!(bytes, ct.compress().to_bytes());
+        assert_eq!(ciphertext_from_bytes(&bytes), Ok(ct));
+
+        let bytes = encode(&ek);
+        assert_eq!(bytes, ek.0.compress().to_bytes());
+        assert_eq!(key_from_bytes(&bytes), Ok(ek));
+
+        let bytes = encode(&commitment);
+        assert_eq!(bytes.len(), 96);
+        assert_eq!(commitment_from_bytes(&bytes), Ok(commitment));
+    }
+
+    // Test that malformed column data is rejected.
+    #[test]
+    fn sqlx_malformed() {
+        let mut rng = StdRng::seed_from_u64(476);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+
+        let mut bytes = encode(&ct);
+        assert_eq!(
+            ciphertext_from_bytes(&bytes[..63]),
+            Err(Error::LengthMismatch {
+                expected: 64,
+                got: 63
+            })
+        );
+        // the top bit of an encoded point is always clear
+        bytes[63] |= 0x80;
+        assert_eq!(ciphertext_from_bytes(&bytes), Err(Error::InvalidPoint));
+
+        let identity = EncryptionKey::from(RistrettoPoint::identity());
+        assert_eq!(
+            key_from_bytes(&encode(&identity)),
+            Err(Error::IdentityPoint)
+        );
+        assert_eq!(
+            key_from_bytes(&[]),
+            Err(Error::LengthMismatch {
+                expected: 32,
+                got: 0
+            })
+        );
+
+        // an unblinded commitment has the identity as its first ciphertext component
+        let (_, unblinded) = Commitment::commit_with(
+            Scalar::from(476u32),
+            Scalar::ZERO,
+            &crate::CommitKey::from_encryption_key_unchecked(&ek),
+        );
+        assert_eq!(
+            commitment_from_bytes(&encode(&unblinded)),
+            Err(Error::IdentityPoint)
+        );
+        let mut bytes = encode(&unblinded);
+        bytes.truncate(64);
+        assert_eq!(
+            commitment_from_bytes(&bytes),
+            Err(Error::LengthMismatch {
+                expected: 96,
+                got: 64
+            })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// Stealth addresses for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{hash_points_to_scalar, kdf};
+use crate::{DecryptionKey, EncryptionKey, GENERATOR_TABLE};
+
+const TWEAK_DOMAIN: &[u8] = b"rust-elgamal stealth tweak";
+const CHECK_DOMAIN: &[u8] = b"rust-elgamal stealth check";
+
+/// Derives one-time encryption keys for a recipient from its long-term key.
+///
+/// The sender picks an ephemeral secret `e`, computes the shared point `S = eY` with the
+/// recipient's key `Y`, and uses `Y + H(S)G` as the one-time key. The [EphemeralTag] published
+/// with it carries `eG` and a check value derived from `S`, so the recipient (and only the
+/// recipient) can recompute `S`, recognise the tag as its own, and derive the one-time secret
+/// `x + H(S)` with [DecryptionKey::recover_stealth].
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, RistrettoPoint, StealthAddress};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let recipient = DecryptionKey::new(&mut rng);
+///
+/// let (one_time, tag) = StealthAddress::derive(recipient.encryption_key(), &mut rng);
+/// let m = RistrettoPoint::random(&mut rng);
+/// let ct = one_time.encrypt(m, &mut rng);
+///
+/// let one_time_dk = recipient.recover_stealth(&tag).unwrap();
+/// assert_eq!(one_time_dk.decrypt(ct), m);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StealthAddress;
+
+/// The public data accompanying a one-time key: the sender's ephemeral point and a check value
+/// that lets the recipient recognise the key as its own.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct EphemeralTag {
+    pub(crate) point: RistrettoPoint,
+    pub(crate) check: [u8; 32],
+}
+
+impl StealthAddress {
+    /// Derive a fresh one-time encryption key for `recipient`, with the tag to publish alongside
+    /// it.
+    pub fn derive<R: RngCore + CryptoRng>(
+        recipient: &EncryptionKey,
+        rng: &mut R,
+    ) -> (EncryptionKey, EphemeralTag) {
+        let e = Scalar::random(rng);
+        let shared = recipient.0 * e;
+        let tweak = hash_points_to_scalar(TWEAK_DOMAIN, &[&shared]);
+
+        let one_time = EncryptionKey(recipient.0 + &tweak * GENERATOR_TABLE);
+        let tag = EphemeralTag {
+            point: &e * GENERATOR_TABLE,
+            check: kdf(CHECK_DOMAIN, &[&shared]),
+        };
+        (one_time, tag)
+    }
+}
+
+impl DecryptionKey {
+    /// Derive the one-time decryption key for a stealth address, or `None` if `tag` was not made
+    /// for this key.
+    pub fn recover_stealth(&self, tag: &EphemeralTag) -> Option<DecryptionKey> {
+        let shared = tag.point * self.secret;
+        if !bool::from(kdf(CHECK_DOMAIN, &[&shared]).ct_eq(&tag.check)) {
+            return None;
+        }
+        let tweak = hash_points_to_scalar(TWEAK_DOMAIN, &[&shared]);
+        Some(DecryptionKey::from(self.secret + tweak))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{DecryptionKey, RistrettoPoint, StealthAddress};
+
+    // Test that the recipient recovers the one-time key and decrypts, and nobody else does.
+    #[test]
+    fn stealth_recover() {
+        let mut rng = StdRng::from_entropy();
+        let recipient = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+
+        let (one_time, tag) = StealthAddress::derive(recipient.encryption_key(), &mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = one_time.encrypt(m, &mut rng);
+
+        let dk = recipient.recover_stealth(&tag).unwrap();
+        assert_eq!(dk.encryption_key(), &one_time);
+        assert_eq!(dk.decrypt(ct), m);
+
+        assert_eq!(other.recover_stealth(&tag), None);
+        assert_ne!(recipient.decrypt(ct), m);
+    }
+
+    // Test that two derivations for the same recipient share no components.
+    #[test]
+    fn stealth_unlinkable() {
+        let mut rng = StdRng::from_entropy();
+        let recipient = DecryptionKey::new(&mut rng);
+        let ek = recipient.encryption_key();
+
+        let (k1, t1) = StealthAddress::derive(ek, &mut rng);
+        let (k2, t2) = StealthAddress::derive(ek, &mut rng);
+        assert_ne!(k1, k2);
+        assert_ne!(&k1, ek);
+        assert_ne!(t1.point, t2.point);
+        assert_ne!(t1.check, t2.check);
+
+        // each tag only recovers its own key
+        assert_eq!(
+            recipient.recover_stealth(&t1).unwrap().encryption_key(),
+            &k1
+        );
+        assert_eq!(
+            recipient.recover_stealth(&t2).unwrap().encryption_key(),
+            &k2
+        );
+    }
+
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serde_ephemeral_tag() {
+        let mut rng = StdRng::from_entropy();
+        let recipient = DecryptionKey::new(&mut rng);
+        let (_, tag) = StealthAddress::derive(recipient.encryption_key(), &mut rng);
+
+        let encoded = bincode::serialize(&tag).unwrap();
+        assert_eq!(encoded.len(), 64);
+        let decoded = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(tag, decoded);
+    }
+}
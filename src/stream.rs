@@ -0,0 +1,320 @@
+// Streaming hybrid encryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming hybrid encryption, following the STREAM construction of Hoang, Reyhanitabar,
+//! Rogaway and Vizár.
+//!
+//! A stream is a header (the format version and the encapsulated key) followed by the plaintext
+//! in chunks of [STREAM_CHUNK_LEN] bytes, each sealed with ChaCha20-Poly1305 under a key derived
+//! from the KEM shared secret. The nonce of each chunk is its index, plus a flag set only on the
+//! last chunk, so reordering, dropping or truncating chunks all make decryption fail.
+
+use std::io::{Read, Write};
+use std::vec::Vec;
+
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha512;
+
+use crate::{DecryptionKey, EncapsulatedKey, EncryptionKey, Error, SharedSecret};
+
+/// The format version written as the first byte of every stream.
+pub const STREAM_VERSION: u8 = 1;
+
+/// The number of plaintext bytes in each chunk of a stream; only the last chunk may be shorter.
+pub const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+const INFO: &[u8] = b"rust-elgamal stream v1";
+const HEADER_LEN: usize = 1 + 32;
+const TAG_LEN: usize = 16;
+
+fn cipher(secret: &SharedSecret) -> ChaCha20Poly1305 {
+    let mut key = Key::default();
+    Hkdf::<Sha512>::new(None, secret.as_bytes())
+        .expand(INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA512 output length");
+    ChaCha20Poly1305::new(&key)
+}
+
+fn nonce(counter: u64, last: bool) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn io_error(err: std::io::Error) -> Error {
+    Error::Io(err.kind())
+}
+
+// Append bytes from `reader` to `buf` until it holds `len` bytes or the reader is exhausted.
+fn fill<R: Read>(reader: &mut R, buf: &mut Vec<u8>, len: usize) -> Result<(), Error> {
+    let wanted = len.saturating_sub(buf.len()) as u64;
+    reader
+        .by_ref()
+        .take(wanted)
+        .read_to_end(buf)
+        .map(|_| ())
+        .map_err(io_error)
+}
+
+// Fill `buf` with the next chunk of `len` bytes, carrying over the byte read ahead last time.
+// Returns whether this is the last chunk, which is when fewer than `len` bytes are left or
+// nothing follows them; in the latter case the byte read to check is stashed in `ahead`.
+fn next_chunk<R: Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    len: usize,
+    ahead: &mut Option<u8>,
+) -> Result<bool, Error> {
+    buf.clear();
+    buf.extend(ahead.take());
+    fill(reader, buf, len)?;
+    if buf.len() < len {
+        return Ok(true);
+    }
+
+    let mut peek = Vec::with_capacity(1);
+    fill(reader, &mut peek, 1)?;
+    *ahead = peek.first().copied();
+    Ok(ahead.is_none())
+}
+
+impl EncryptionKey {
+    /// Encrypt everything read from `reader` to this key, writing the result to `writer`.
+    ///
+    /// Memory use is bounded by [STREAM_CHUNK_LEN], whatever the length of the input. The output
+    /// is a 33-byte header followed by the input in chunks of [STREAM_CHUNK_LEN] bytes, each
+    /// extended by a 16-byte tag. Like [seal](Self::seal), this mode is not homomorphic.
+    ///
+    /// Fails with [Error::Io] if reading or writing fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_elgamal::DecryptionKey;
+    ///
+    /// let mut rng = StdRng::from_entropy();
+    /// let dec_key = DecryptionKey::new(&mut rng);
+    ///
+    /// let mut sealed = Vec::new();
+    /// dec_key
+    ///     .encryption_key()
+    ///     .seal_stream(&b"a large file"[..], &mut sealed, &mut rng)
+    ///     .unwrap();
+    ///
+    /// let mut opened = Vec::new();
+    /// dec_key.open_stream(&sealed[..], &mut opened).unwrap();
+    /// assert_eq!(opened, b"a large file");
+    /// ```
+    pub fn seal_stream<R: RngCore + CryptoRng>(
+        &self,
+        mut reader: impl Read,
+        mut writer: impl Write,
+        rng: &mut R,
+    ) -> Result<(), Error> {
+        let (encapsulated, secret) = self.encapsulate(rng);
+        let cipher = cipher(&secret);
+
+        writer.write_all(&[STREAM_VERSION]).map_err(io_error)?;
+        writer
+            .write_all(&encapsulated.to_bytes())
+            .map_err(io_error)?;
+
+        let mut buf = Vec::with_capacity(STREAM_CHUNK_LEN + TAG_LEN);
+        let mut ahead = None;
+        for counter in 0u64.. {
+            let last = next_chunk(&mut reader, &mut buf, STREAM_CHUNK_LEN, &mut ahead)?;
+            cipher
+                .encrypt_in_place(&nonce(counter, last), b"", &mut buf)
+                .expect("chunks are within the ChaCha20-Poly1305 length limit");
+            writer.write_all(&buf).map_err(io_error)?;
+            if last {
+                break;
+            }
+        }
+        writer.flush().map_err(io_error)
+    }
+}
+
+impl DecryptionKey {
+    /// Decrypt a stream produced by [EncryptionKey::seal_stream] from `reader`, writing the
+    /// plaintext to `writer`.
+    ///
+    /// Memory use is bounded by [STREAM_CHUNK_LEN]. Each chunk is written as soon as it has been
+    /// authenticated, so if this fails, whatever was already written must be discarded.
+    ///
+    /// Fails with [Error::VerificationFailed] if a chunk was corrupted, reordered or dropped, if
+    /// the stream was truncated, or if it was encrypted to a different key; with
+    /// [Error::InvalidEncoding] if the version byte is unknown; with [Error::LengthMismatch] if
+    /// the header is incomplete; and with [Error::Io] if reading or writing fails.
+    pub fn open_stream(&self, mut reader: impl Read, mut writer: impl Write) -> Result<(), Error> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        fill(&mut reader, &mut header, HEADER_LEN)?;
+        if header.len() < HEADER_LEN {
+            return Err(Error::LengthMismatch {
+                expected: HEADER_LEN,
+                got: header.len(),
+            });
+        }
+        if header[0] != STREAM_VERSION {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&header[1..]);
+        let encapsulated = EncapsulatedKey::from_bytes(&bytes)?;
+        let cipher = cipher(&self.decapsulate(&encapsulated));
+
+        let mut buf = Vec::with_capacity(STREAM_CHUNK_LEN + TAG_LEN);
+        let mut ahead = None;
+        for counter in 0u64.. {
+            let last = next_chunk(
+                &mut reader,
+                &mut buf,
+                STREAM_CHUNK_LEN + TAG_LEN,
+                &mut ahead,
+            )?;
+            cipher
+                .decrypt_in_place(&nonce(counter, last), b"", &mut buf)
+                .map_err(|_| Error::VerificationFailed)?;
+            writer.write_all(&buf).map_err(io_error)?;
+            if last {
+                break;
+            }
+        }
+        writer.flush().map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand::RngCore;
+    use rand_core::SeedableRng;
+
+    use crate::stream::{HEADER_LEN, STREAM_CHUNK_LEN, TAG_LEN};
+    use crate::{DecryptionKey, Error};
+
+    const CT_CHUNK_LEN: usize = STREAM_CHUNK_LEN + TAG_LEN;
+
+    fn seal(dk: &DecryptionKey, msg: &[u8], rng: &mut StdRng) -> Vec<u8> {
+        let mut sealed = Vec::new();
+        dk.encryption_key()
+            .seal_stream(msg, &mut sealed, rng)
+            .unwrap();
+        sealed
+    }
+
+    fn open(dk: &DecryptionKey, sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut opened = Vec::new();
+        dk.open_stream(sealed, &mut opened).map(|_| opened)
+    }
+
+    // Test that streams of lengths around the chunk boundaries round-trip.
+    #[test]
+    fn stream_lengths() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+
+        for len in [
+            0,
+            1,
+            STREAM_CHUNK_LEN - 1,
+            STREAM_CHUNK_LEN,
+            STREAM_CHUNK_LEN + 1,
+            3 * STREAM_CHUNK_LEN,
+            3 * STREAM_CHUNK_LEN + 100,
+        ] {
+            let mut msg = vec![0u8; len];
+            rng.fill_bytes(&mut msg);
+
+            let sealed = seal(&dk, &msg, &mut rng);
+            let chunks = len.div_ceil(STREAM_CHUNK_LEN).max(1);
+            assert_eq!(sealed.len(), HEADER_LEN + len + chunks * TAG_LEN);
+            assert_eq!(open(&dk, &sealed).unwrap(), msg);
+        }
+    }
+
+    // Test that truncating the stream anywhere, including at a chunk boundary, is detected.
+    #[test]
+    fn stream_truncated() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let sealed = seal(&dk, &vec![1u8; 2 * STREAM_CHUNK_LEN + 10], &mut rng);
+
+        for len in [
+            HEADER_LEN + CT_CHUNK_LEN,
+            HEADER_LEN + 2 * CT_CHUNK_LEN,
+            HEADER_LEN + 2 * CT_CHUNK_LEN + 5,
+            sealed.len() - 1,
+            HEADER_LEN,
+        ] {
+            assert_eq!(open(&dk, &sealed[..len]), Err(Error::VerificationFailed));
+        }
+        assert_eq!(
+            open(&dk, &sealed[..10]),
+            Err(Error::LengthMismatch {
+                expected: HEADER_LEN,
+                got: 10
+            })
+        );
+    }
+
+    // Test that swapping two chunks is detected.
+    #[test]
+    fn stream_reordered() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let sealed = seal(&dk, &vec![1u8; 3 * STREAM_CHUNK_LEN], &mut rng);
+
+        let first = HEADER_LEN..HEADER_LEN + CT_CHUNK_LEN;
+        let second = HEADER_LEN + CT_CHUNK_LEN..HEADER_LEN + 2 * CT_CHUNK_LEN;
+        let mut reordered = sealed.clone();
+        reordered[first.clone()].copy_from_slice(&sealed[second.clone()]);
+        reordered[second].copy_from_slice(&sealed[first]);
+        assert_eq!(open(&dk, &reordered), Err(Error::VerificationFailed));
+    }
+
+    // Test that corrupting any chunk, the header, or using the wrong key is detected.
+    #[test]
+    fn stream_corrupted() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let sealed = seal(&dk, &vec![1u8; 2 * STREAM_CHUNK_LEN + 10], &mut rng);
+
+        for i in [
+            5,
+            HEADER_LEN,
+            HEADER_LEN + CT_CHUNK_LEN + 7,
+            sealed.len() - 1,
+        ] {
+            let mut corrupted = sealed.clone();
+            corrupted[i] ^= 0x01;
+            assert!(open(&dk, &corrupted).is_err());
+        }
+
+        let mut corrupted = sealed.clone();
+        corrupted[0] = 2;
+        assert_eq!(open(&dk, &corrupted), Err(Error::InvalidEncoding));
+
+        let other = DecryptionKey::new(&mut rng);
+        assert_eq!(open(&other, &sealed), Err(Error::VerificationFailed));
+    }
+}
@@ -0,0 +1,267 @@
+// Homomorphic tallying for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dlog::bounded_dlog;
+use crate::trace::op_span;
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, EncryptionKey, Error, Identity};
+
+/// A running, encrypted tally of ballots over a fixed number of options.
+///
+/// A ballot is one ciphertext per option, each an exponential encryption
+/// ([EncryptionKey::exp_encrypt](crate::EncryptionKey::exp_encrypt)) of the number of votes for
+/// that option, typically 0 or 1. Ballots are summed homomorphically, so the tally never sees an
+/// individual vote; only the totals are decrypted by [finalize](Self::finalize).
+///
+/// Tallies over disjoint sets of ballots can be combined with [merge](Self::merge), and with the
+/// `enable-serde` feature a tally can be serialised to checkpoint a count in progress.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, HomomorphicTally, Scalar};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+/// let enc_key = dec_key.encryption_key();
+///
+/// let mut tally = HomomorphicTally::new(*enc_key, 2);
+/// for choice in [0, 1, 1] {
+///     let ballot: Vec<_> = (0..2)
+///         .map(|i| enc_key.exp_encrypt(Scalar::from((i == choice) as u64), &mut rng))
+///         .collect();
+///     tally.add_ballot(&ballot).unwrap();
+/// }
+/// assert_eq!(tally.finalize(&dec_key, 3).unwrap(), vec![1, 2]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(
+        serialize = "G: Serialize",
+        deserialize = "G: ElGamalGroup + Deserialize<'de>"
+    ))
+)]
+pub struct HomomorphicTally<G: ElGamalGroup = RistrettoPoint> {
+    ek: EncryptionKey<G>,
+    totals: Vec<Ciphertext<G>>,
+    ballots: u64,
+}
+
+impl<G: ElGamalGroup> HomomorphicTally<G> {
+    /// Create an empty tally of `num_options` options under the encryption key `ek`.
+    pub fn new(ek: EncryptionKey<G>, num_options: usize) -> Self {
+        HomomorphicTally {
+            ek,
+            totals: vec![Ciphertext::identity(); num_options],
+            ballots: 0,
+        }
+    }
+
+    /// The encryption key the ballots are expected to be encrypted under.
+    pub fn encryption_key(&self) -> &EncryptionKey<G> {
+        &self.ek
+    }
+
+    /// The number of options counted.
+    pub fn num_options(&self) -> usize {
+        self.totals.len()
+    }
+
+    /// The number of ballots added so far, including those merged in from other tallies.
+    pub fn num_ballots(&self) -> u64 {
+        self.ballots
+    }
+
+    /// The encrypted running totals, one per option.
+    pub fn totals(&self) -> &[Ciphertext<G>] {
+        &self.totals
+    }
+
+    /// Add a ballot, which must have one ciphertext per option.
+    ///
+    /// Fails with [Error::LengthMismatch](crate::Error::LengthMismatch) otherwise, leaving the
+    /// tally unchanged.
+    pub fn add_ballot(&mut self, ballot: &[Ciphertext<G>]) -> Result<(), Error> {
+        if ballot.len() != self.totals.len() {
+            return Err(Error::LengthMismatch {
+                expected: self.totals.len(),
+                got: ballot.len(),
+            });
+        }
+        for (total, ct) in self.totals.iter_mut().zip(ballot) {
+            *total = *total + ct;
+        }
+        self.ballots += 1;
+        Ok(())
+    }
+
+    /// Add the ballots counted by `other`, for example by another shard of the count.
+    ///
+    /// Fails with [Error::KeyMismatch](crate::Error::KeyMismatch) if the tallies use different
+    /// keys, or [Error::LengthMismatch](crate::Error::LengthMismatch) if they count a different
+    /// number of options.
+    pub fn merge(&mut self, other: &HomomorphicTally<G>) -> Result<(), Error> {
+        if self.ek != other.ek {
+            return Err(Error::KeyMismatch);
+        }
+        if other.totals.len() != self.totals.len() {
+            return Err(Error::LengthMismatch {
+                expected: self.totals.len(),
+                got: other.totals.len(),
+            });
+        }
+        for (total, ct) in self.totals.iter_mut().zip(&other.totals) {
+            *total = *total + ct;
+        }
+        self.ballots += other.ballots;
+        Ok(())
+    }
+
+    /// Decrypt the totals, each of which must be at most `max_per_option`.
+    ///
+    /// Each total is recovered by a bounded discrete-logarithm search costing about
+    /// `2 * sqrt(max_per_option)` group operations, so the bound should be as tight as is known
+    /// (the number of ballots times the largest vote weight). Fails with
+    /// [Error::KeyMismatch](crate::Error::KeyMismatch) if `dk` does not match the tally's key, or
+    /// [Error::OutOfRange](crate::Error::OutOfRange) if a total exceeds the bound.
+    pub fn finalize(&self, dk: &DecryptionKey<G>, max_per_option: u64) -> Result<Vec<u64>, Error> {
+        if dk.encryption_key() != &self.ek {
+            return Err(Error::KeyMismatch);
+        }
+        let _span = op_span!("finalize_tally", options = self.totals.len());
+        self.totals
+            .iter()
+            .map(|ct| bounded_dlog(&dk.decrypt(*ct), max_per_option).ok_or(Error::OutOfRange))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand::Rng;
+    use rand_core::SeedableRng;
+
+    use crate::{Ciphertext, DecryptionKey, EncryptionKey, Error, HomomorphicTally, Scalar};
+
+    const OPTIONS: usize = 3;
+
+    fn ballot(ek: &EncryptionKey, choice: usize, rng: &mut StdRng) -> Vec<Ciphertext> {
+        (0..OPTIONS)
+            .map(|i| ek.exp_encrypt(Scalar::from((i == choice) as u64), rng))
+            .collect()
+    }
+
+    // Test that a tally of synthetic ballots matches plain counting, including across shards.
+    #[test]
+    fn tally_matches_plain_count() {
+        const N: usize = 300;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+
+        let mut whole = HomomorphicTally::new(ek, OPTIONS);
+        let mut shards = [
+            HomomorphicTally::new(ek, OPTIONS),
+            HomomorphicTally::new(ek, OPTIONS),
+        ];
+        let mut expected = [0u64; OPTIONS];
+
+        for i in 0..N {
+            let choice = rng.gen_range(0..OPTIONS);
+            expected[choice] += 1;
+            let b = ballot(&ek, choice, &mut rng);
+            whole.add_ballot(&b).unwrap();
+            shards[i % 2].add_ballot(&b).unwrap();
+        }
+        assert_eq!(whole.num_ballots(), N as u64);
+        assert_eq!(whole.finalize(&dk, N as u64).unwrap(), expected);
+
+        let [mut merged, other] = shards;
+        merged.merge(&other).unwrap();
+        assert_eq!(merged.num_ballots(), N as u64);
+        assert_eq!(merged.finalize(&dk, N as u64).unwrap(), expected);
+    }
+
+    // Test that malformed ballots, foreign tallies and too-small bounds are rejected.
+    #[test]
+    fn tally_errors() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+        let other_dk = DecryptionKey::new(&mut rng);
+
+        let mut tally = HomomorphicTally::new(ek, OPTIONS);
+        let b = ballot(&ek, 0, &mut rng);
+        assert_eq!(
+            tally.add_ballot(&b[..2]),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(tally.num_ballots(), 0);
+
+        let foreign = HomomorphicTally::new(*other_dk.encryption_key(), OPTIONS);
+        assert_eq!(tally.merge(&foreign), Err(Error::KeyMismatch));
+        let narrow = HomomorphicTally::new(ek, 2);
+        assert!(matches!(
+            tally.merge(&narrow),
+            Err(Error::LengthMismatch { .. })
+        ));
+
+        tally.add_ballot(&b).unwrap();
+        tally.add_ballot(&b).unwrap();
+        assert_eq!(tally.finalize(&other_dk, 2), Err(Error::KeyMismatch));
+        assert_eq!(tally.finalize(&dk, 1), Err(Error::OutOfRange));
+        assert_eq!(tally.finalize(&dk, 2).unwrap(), [2, 0, 0]);
+    }
+
+    // Test that a checkpointed tally can be restored and continued.
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn tally_checkpoint() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = *dk.encryption_key();
+
+        let mut tally = HomomorphicTally::new(ek, OPTIONS);
+        for choice in [0, 1, 2, 2] {
+            tally.add_ballot(&ballot(&ek, choice, &mut rng)).unwrap();
+        }
+
+        let checkpoint = bincode::serialize(&tally).unwrap();
+        let mut restored: HomomorphicTally = bincode::deserialize(&checkpoint).unwrap();
+        assert_eq!(restored, tally);
+
+        restored.add_ballot(&ballot(&ek, 1, &mut rng)).unwrap();
+        assert_eq!(restored.num_ballots(), 5);
+        assert_eq!(restored.finalize(&dk, 5).unwrap(), [1, 2, 2]);
+    }
+}
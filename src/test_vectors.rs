@@ -0,0 +1,434 @@
+// Known-answer test vectors for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reproducible known-answer vectors, for checking other implementations against this one.
+//!
+//! [TestVectorSuite::generate] derives every key, message and random value from a 32-byte seed,
+//! so the same seed always gives the same suite. Each value is
+//! `SHA-512(len(D) || D || seed || len(l) || l || i)` for a label `l` and index `i`, reduced to a
+//! scalar or mapped to a point with the ristretto255 hash-to-group map, where `D` is
+//! `"rust-elgamal test vectors"` and lengths and `i` are 8-byte little-endian integers. Other
+//! implementations need not reproduce the derivation: every vector carries its inputs, including
+//! the randomness, alongside its outputs.
+//!
+//! A suite is serialised to JSON by [to_json](TestVectorSuite::to_json), with the fields in a
+//! fixed order and every point, scalar and ciphertext as the lowercase hex of its canonical
+//! encoding (a ciphertext is its two compressed points in order, 64 bytes). The schema is
+//! identified by [VERSION]; fields are only ever added under a new version.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rust_elgamal::test_vectors::TestVectorSuite;
+//!
+//! let suite = TestVectorSuite::generate([7; 32]);
+//! let json = suite.to_json();
+//! let parsed = TestVectorSuite::from_json(&json).unwrap();
+//! assert_eq!(parsed, suite);
+//! assert!(parsed.verify().is_ok());
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use crate::{
+    Ciphertext, CommitKey, Commitment, CompressedCiphertext, DecryptionKey, DleqProof, Error, Open,
+};
+
+/// The version of the JSON schema written by [TestVectorSuite::to_json].
+pub const VERSION: u32 = 1;
+
+const DOMAIN: &[u8] = b"rust-elgamal test vectors";
+const COUNT: u64 = 4;
+
+/// Encryption of a point under a key, with the randomness used.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionVector {
+    pub secret_key: String,
+    pub encryption_key: String,
+    pub message: String,
+    pub randomness: String,
+    pub ciphertext: String,
+}
+
+/// Re-randomisation of a ciphertext under a key, with the randomness used.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RerandomisationVector {
+    pub encryption_key: String,
+    pub ciphertext: String,
+    pub randomness: String,
+    pub rerandomised: String,
+}
+
+/// A commitment to a scalar under a key, with its opening.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommitmentVector {
+    pub encryption_key: String,
+    pub message: String,
+    pub randomness: String,
+    pub commitment: String,
+}
+
+/// A [DleqProof] that `q = x * p`, with the nonce used.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DleqVector {
+    pub secret_key: String,
+    pub encryption_key: String,
+    pub p: String,
+    pub q: String,
+    pub nonce: String,
+    pub challenge: String,
+    pub response: String,
+}
+
+/// A full set of known-answer vectors generated from one seed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TestVectorSuite {
+    pub version: u32,
+    pub seed: String,
+    pub encryption: Vec<EncryptionVector>,
+    pub rerandomisation: Vec<RerandomisationVector>,
+    pub commitment: Vec<CommitmentVector>,
+    pub dleq: Vec<DleqVector>,
+}
+
+// The derivation of the values of a suite from its seed.
+struct Derive([u8; 32]);
+
+impl Derive {
+    fn hash(&self, label: &str, i: u64) -> Sha512 {
+        Sha512::new()
+            .chain_update((DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(DOMAIN)
+            .chain_update(self.0)
+            .chain_update((label.len() as u64).to_le_bytes())
+            .chain_update(label)
+            .chain_update(i.to_le_bytes())
+    }
+
+    fn scalar(&self, label: &str, i: u64) -> Scalar {
+        Scalar::from_hash(self.hash(label, i))
+    }
+
+    fn point(&self, label: &str, i: u64) -> RistrettoPoint {
+        RistrettoPoint::from_hash(self.hash(label, i))
+    }
+}
+
+impl TestVectorSuite {
+    /// Generate the suite for `seed`.
+    pub fn generate(seed: [u8; 32]) -> TestVectorSuite {
+        let d = Derive(seed);
+
+        let encryption = (0..COUNT)
+            .map(|i| {
+                let dk = DecryptionKey::from(d.scalar("secret_key", i));
+                let m = d.point("message", i);
+                let r = d.scalar("randomness", i);
+                EncryptionVector {
+                    secret_key: scalar_to_hex(&dk.secret),
+                    encryption_key: point_to_hex(&dk.encryption_key().0),
+                    message: point_to_hex(&m),
+                    randomness: scalar_to_hex(&r),
+                    ciphertext: ciphertext_to_hex(&dk.encryption_key().encrypt_with(m, r)),
+                }
+            })
+            .collect();
+
+        let rerandomisation = (0..COUNT)
+            .map(|i| {
+                let ek = DecryptionKey::from(d.scalar("encryption_key", i)).ek;
+                let ct = Ciphertext(d.point("ciphertext", i), d.point("ciphertext", i + COUNT));
+                let r = d.scalar("randomness", i + COUNT);
+                RerandomisationVector {
+                    encryption_key: point_to_hex(&ek.0),
+                    ciphertext: ciphertext_to_hex(&ct),
+                    randomness: scalar_to_hex(&r),
+                    rerandomised: ciphertext_to_hex(&ek.rerandomise_with(ct, r)),
+                }
+            })
+            .collect();
+
+        let commitment = (0..COUNT)
+            .map(|i| {
+                let ek = DecryptionKey::from(d.scalar("encryption_key", i + COUNT)).ek;
+                let m = d.scalar("message", i);
+                let r = d.scalar("randomness", i + 2 * COUNT);
+                let (_, commitment) =
+                    Commitment::commit_with(m, r, &CommitKey::from_encryption_key_unchecked(&ek));
+                CommitmentVector {
+                    encryption_key: point_to_hex(&ek.0),
+                    message: scalar_to_hex(&m),
+                    randomness: scalar_to_hex(&r),
+                    commitment: ciphertext_to_hex(&commitment.1),
+                }
+            })
+            .collect();
+
+        let dleq = (0..COUNT)
+            .map(|i| {
+                let dk = DecryptionKey::from(d.scalar("secret_key", i + COUNT));
+                let p = d.point("p", i);
+                let q = p * dk.secret;
+                let k = d.scalar("nonce", i);
+                let proof = DleqProof::prove_with(&dk, &p, &q, &k);
+                DleqVector {
+                    secret_key: scalar_to_hex(&dk.secret),
+                    encryption_key: point_to_hex(&dk.encryption_key().0),
+                    p: point_to_hex(&p),
+                    q: point_to_hex(&q),
+                    nonce: scalar_to_hex(&k),
+                    challenge: scalar_to_hex(&proof.c),
+                    response: scalar_to_hex(&proof.s),
+                }
+            })
+            .collect();
+
+        TestVectorSuite {
+            version: VERSION,
+            seed: bytes_to_hex(&seed),
+            encryption,
+            rerandomisation,
+            commitment,
+            dleq,
+        }
+    }
+
+    /// Re-check every vector against this implementation: recompute each output from its
+    /// inputs, and check that ciphertexts decrypt, commitments open and proofs verify.
+    ///
+    /// The seed is not consulted, so a suite from another generator can be checked too. Fails
+    /// with [Error::UnsupportedVersion] for another schema version, with the decoding errors of
+    /// the fields if they are malformed, and with [Error::VerificationFailed] if any vector does
+    /// not hold.
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.version != VERSION {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        for v in &self.encryption {
+            let dk = DecryptionKey::from(scalar_from_hex(&v.secret_key)?);
+            let m = point_from_hex(&v.message)?;
+            let ct = ciphertext_from_hex(&v.ciphertext)?;
+            check(dk.encryption_key().0 == point_from_hex(&v.encryption_key)?)?;
+            check(
+                dk.encryption_key()
+                    .encrypt_with(m, scalar_from_hex(&v.randomness)?)
+                    == ct,
+            )?;
+            check(dk.decrypt(ct) == m)?;
+        }
+
+        for v in &self.rerandomisation {
+            let ek = crate::EncryptionKey::from(point_from_hex(&v.encryption_key)?);
+            let ct = ciphertext_from_hex(&v.ciphertext)?;
+            let r = scalar_from_hex(&v.randomness)?;
+            check(ek.rerandomise_with(ct, r) == ciphertext_from_hex(&v.rerandomised)?)?;
+        }
+
+        for v in &self.commitment {
+            let ek = crate::EncryptionKey::from(point_from_hex(&v.encryption_key)?);
+            let open = Open(
+                scalar_from_hex(&v.randomness)?,
+                scalar_from_hex(&v.message)?,
+            );
+            let commitment = Commitment::from((ek, ciphertext_from_hex(&v.commitment)?));
+            check(
+                Commitment::commit_with(
+                    open.1,
+                    open.0,
+                    &CommitKey::from_encryption_key_unchecked(&ek),
+                )
+                .1 == commitment,
+            )?;
+            commitment.try_verify(&open)?;
+        }
+
+        for v in &self.dleq {
+            let dk = DecryptionKey::from(scalar_from_hex(&v.secret_key)?);
+            let p = point_from_hex(&v.p)?;
+            let q = point_from_hex(&v.q)?;
+            let proof = DleqProof {
+                c: scalar_from_hex(&v.challenge)?,
+                s: scalar_from_hex(&v.response)?,
+            };
+            check(dk.encryption_key().0 == point_from_hex(&v.encryption_key)?)?;
+            check(p * dk.secret == q)?;
+            check(DleqProof::prove_with(&dk, &p, &q, &scalar_from_hex(&v.nonce)?) == proof)?;
+            proof.verify(dk.encryption_key(), &p, &q)?;
+        }
+        Ok(())
+    }
+
+    /// The suite as JSON, in the schema described in the [module documentation](self).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serialising to a string cannot fail")
+    }
+
+    /// Read a suite written by [to_json](Self::to_json).
+    ///
+    /// Fails with [Error::InvalidJson] if `json` does not have the schema's shape; the fields
+    /// themselves are decoded by [verify](Self::verify).
+    pub fn from_json(json: &str) -> Result<TestVectorSuite, Error> {
+        serde_json::from_str(json).map_err(|_| Error::InvalidJson)
+    }
+}
+
+fn check(holds: bool) -> Result<(), Error> {
+    if !holds {
+        return Err(Error::VerificationFailed);
+    }
+    Ok(())
+}
+
+// Field encodings
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        write!(out, "{:02x}", byte).expect("writing to a string cannot fail");
+    }
+    out
+}
+
+fn bytes_from_hex<const N: usize>(s: &str) -> Result<[u8; N], Error> {
+    if s.len() != 2 * N {
+        return Err(Error::LengthMismatch {
+            expected: 2 * N,
+            got: s.len(),
+        });
+    }
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+        let pair = core::str::from_utf8(pair).map_err(|_| Error::InvalidJson)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| Error::InvalidJson)?;
+    }
+    Ok(bytes)
+}
+
+fn point_to_hex(point: &RistrettoPoint) -> String {
+    bytes_to_hex(point.compress().as_bytes())
+}
+
+fn point_from_hex(s: &str) -> Result<RistrettoPoint, Error> {
+    CompressedRistretto(bytes_from_hex(s)?)
+        .decompress()
+        .ok_or(Error::InvalidPoint)
+}
+
+fn scalar_to_hex(s: &Scalar) -> String {
+    bytes_to_hex(s.as_bytes())
+}
+
+fn scalar_from_hex(s: &str) -> Result<Scalar, Error> {
+    Option::from(Scalar::from_canonical_bytes(bytes_from_hex(s)?)).ok_or(Error::InvalidScalar)
+}
+
+fn ciphertext_to_hex(ct: &Ciphertext) -> String {
+    bytes_to_hex(&ct.compress().to_bytes())
+}
+
+fn ciphertext_from_hex(s: &str) -> Result<Ciphertext, Error> {
+    CompressedCiphertext::from_bytes(&bytes_from_hex(s)?).decompress()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use sha2::{Digest, Sha256};
+
+    use super::TestVectorSuite;
+    use crate::Error;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/test_vectors.json");
+
+    fn sha256_hex(data: &str) -> String {
+        Sha256::digest(data)
+            .iter()
+            .map(|b| alloc::format!("{:02x}", b))
+            .collect()
+    }
+
+    // Test that the suite for the zero seed matches the published fixture byte for byte, and pin
+    // its hash so that any change to the crate's outputs or to the schema is caught.
+    #[test]
+    fn test_vectors_pinned() {
+        let suite = TestVectorSuite::generate([0; 32]);
+        assert_eq!(suite.verify(), Ok(()));
+        assert_eq!(suite.to_json(), FIXTURE.trim_end());
+        assert_eq!(
+            sha256_hex(&suite.to_json()),
+            "77b4aaa810ba3f236da404bcfc687792bb0752befd348c827c3ba442301e254b"
+        );
+        assert_eq!(TestVectorSuite::from_json(FIXTURE), Ok(suite));
+    }
+
+    // Test that suites are reproducible, differ between seeds, and survive a JSON round trip.
+    #[test]
+    fn test_vectors_generate() {
+        let suite = TestVectorSuite::generate([1; 32]);
+        assert_eq!(TestVectorSuite::generate([1; 32]), suite);
+        assert_ne!(TestVectorSuite::generate([2; 32]), suite);
+        assert_eq!(suite.encryption.len(), 4);
+        assert_eq!(
+            TestVectorSuite::from_json(&suite.to_json()).as_ref(),
+            Ok(&suite)
+        );
+        assert_eq!(TestVectorSuite::from_json("{}"), Err(Error::InvalidJson));
+    }
+
+    // Test that a wrong output in any kind of vector, or a malformed field, is caught.
+    #[test]
+    fn test_vectors_tampered() {
+        let suite = TestVectorSuite::generate([3; 32]);
+
+        let mut bad = suite.clone();
+        bad.encryption[1].ciphertext = suite.encryption[2].ciphertext.clone();
+        assert_eq!(bad.verify(), Err(Error::VerificationFailed));
+
+        let mut bad = suite.clone();
+        bad.rerandomisation[0].randomness = suite.rerandomisation[1].randomness.clone();
+        assert_eq!(bad.verify(), Err(Error::VerificationFailed));
+
+        let mut bad = suite.clone();
+        bad.commitment[3].message = suite.commitment[0].message.clone();
+        assert_eq!(bad.verify(), Err(Error::VerificationFailed));
+
+        let mut bad = suite.clone();
+        bad.dleq[2].response = suite.dleq[2].challenge.clone();
+        assert_eq!(bad.verify(), Err(Error::VerificationFailed));
+
+        let mut bad = suite.clone();
+        bad.dleq[0].p.pop();
+        assert_eq!(
+            bad.verify(),
+            Err(Error::LengthMismatch {
+                expected: 64,
+                got: 63
+            })
+        );
+
+        let mut bad = suite;
+        bad.version = 2;
+        assert_eq!(bad.verify(), Err(Error::UnsupportedVersion));
+    }
+}
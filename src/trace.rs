@@ -0,0 +1,71 @@
+// Tracing spans for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Spans around the expensive operations, for the `tracing` feature. A span carries the sizes it
+// is given and, once it closes, `elapsed_us`; it must never be given anything secret. Without the
+// feature `op_span!` gives a unit struct and does not evaluate its fields.
+
+#[cfg(feature = "tracing")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "tracing")]
+pub(crate) struct OpSpan {
+    span: tracing::span::EnteredSpan,
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl OpSpan {
+    pub(crate) fn enter(span: tracing::Span) -> OpSpan {
+        OpSpan {
+            span: span.entered(),
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for OpSpan {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed().as_micros();
+        self.span
+            .record("elapsed_us", u64::try_from(elapsed).unwrap_or(u64::MAX));
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct OpSpan;
+
+// `let _span = op_span!("name", size = expr, ...);` traces the rest of the scope.
+#[cfg(feature = "tracing")]
+macro_rules! op_span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        $crate::trace::OpSpan::enter(tracing::info_span!(
+            target: "rust_elgamal",
+            $name,
+            $($field = $value,)*
+            elapsed_us = tracing::field::Empty
+        ))
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! op_span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        $crate::trace::OpSpan
+    };
+}
+
+pub(crate) use op_span;
@@ -0,0 +1,379 @@
+// Tracker codes for published ballots for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracker codes: confirmation codes that bind each ballot into the published list.
+//!
+//! The codes form a hash chain. Each one is derived from the code before it, the ballot's
+//! ciphertext and a salt chosen for the whole election (its identifier, say), which voters can
+//! see. A voter keeps the code they were given when casting. Once the list is published, anyone
+//! can recompute every code from the ciphertexts with [verify_inclusion], and the voter checks
+//! that their code is among them. Because each code covers everything before it, substituting
+//! or removing any earlier ballot changes every later code too.
+//!
+//! Code `i` is the first 32 bytes of SHA-512 over the length-prefixed domain
+//! `rust-elgamal tracker`, code `i - 1` (32 zero bytes for the first), the 64-byte compressed
+//! ciphertext, and the first 32 bytes of SHA-512 of the salt.
+//!
+//! Codes display in Crockford's base32, in groups of four separated by hyphens, followed by
+//! Crockford's check symbol (the code as a big-endian integer, modulo 37), so that mistyped codes
+//! are caught when parsed back with [FromStr].
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::tracker::{verify_inclusion, TrackerChain, TrackerCode};
+//! use rust_elgamal::{DecryptionKey, RistrettoPoint};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let enc_key = dec_key.encryption_key();
+//!
+//! let mut chain = TrackerChain::new(b"general election 2024");
+//! let mut published = Vec::new();
+//! let mut codes = Vec::new();
+//! for _ in 0..5 {
+//!     let ct = enc_key.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+//!     codes.push(chain.append(&ct));
+//!     published.push(ct);
+//! }
+//!
+//! // the voter of the third ballot wrote their code down
+//! let mine: TrackerCode = codes[2].to_string().to_lowercase().parse().unwrap();
+//! assert_eq!(verify_inclusion(b"general election 2024", &codes, &published), Ok(()));
+//! assert!(codes.contains(&mine));
+//! ```
+
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
+
+use sha2::{Digest, Sha512};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, Error};
+
+const TRACKER_DOMAIN: &[u8] = b"rust-elgamal tracker";
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CHECK_SYMBOLS: &[u8; 5] = b"*~$=U";
+// 256 bits in five-bit symbols, the last padded with zeros
+const SYMBOLS: usize = 52;
+const GROUP: usize = 4;
+
+/// A tracker code, linking one ballot to the ballots published before it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct TrackerCode([u8; 32]);
+
+impl TrackerCode {
+    /// Read a code from its bytes.
+    pub fn from_bytes(bytes: [u8; 32]) -> TrackerCode {
+        TrackerCode(bytes)
+    }
+
+    /// The bytes of the code.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    fn next(&self, salt: &[u8; 32], ct: &Ciphertext) -> TrackerCode {
+        let digest = Sha512::new()
+            .chain_update((TRACKER_DOMAIN.len() as u64).to_le_bytes())
+            .chain_update(TRACKER_DOMAIN)
+            .chain_update(self.0)
+            .chain_update(ct.compress().to_bytes())
+            .chain_update(salt)
+            .finalize();
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest[..32]);
+        TrackerCode(out)
+    }
+
+    fn check_value(&self) -> usize {
+        self.0
+            .iter()
+            .fold(0, |acc, byte| (acc * 256 + usize::from(*byte)) % 37)
+    }
+}
+
+impl Debug for TrackerCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "TrackerCode({})", self)
+    }
+}
+
+impl Display for TrackerCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut symbols = [0u8; SYMBOLS + 1];
+        let (mut acc, mut bits, mut n) = (0u16, 0, 0);
+        for byte in self.0 {
+            acc = (acc << 8) | u16::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                symbols[n] = ALPHABET[usize::from((acc >> bits) & 31)];
+                n += 1;
+            }
+        }
+        symbols[n] = ALPHABET[usize::from((acc << (5 - bits)) & 31)];
+        let check = self.check_value();
+        symbols[SYMBOLS] = match check {
+            0..=31 => ALPHABET[check],
+            _ => CHECK_SYMBOLS[check - 32],
+        };
+
+        for (i, group) in symbols.chunks(GROUP).enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            // the alphabet is ASCII
+            f.write_str(core::str::from_utf8(group).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TrackerCode {
+    type Err = Error;
+
+    /// Parse a code as displayed, ignoring hyphens and case and reading `O` as `0` and `I` and
+    /// `L` as `1`, as Crockford's base32 does.
+    ///
+    /// Fails with [Error::InvalidEncoding] if the string is not a code, or
+    /// [Error::InvalidChecksum] if the check symbol does not match.
+    fn from_str(s: &str) -> Result<TrackerCode, Error> {
+        let mut bytes = [0u8; 32];
+        let (mut acc, mut bits, mut n, mut symbols) = (0u16, 0, 0, 0);
+        let mut check = None;
+        for c in s.bytes().filter(|c| *c != b'-') {
+            if check.is_some() {
+                return Err(Error::InvalidEncoding);
+            }
+            let c = match c.to_ascii_uppercase() {
+                b'O' => b'0',
+                b'I' | b'L' => b'1',
+                c => c,
+            };
+            if symbols == SYMBOLS {
+                check = Some(
+                    ALPHABET
+                        .iter()
+                        .chain(CHECK_SYMBOLS.iter())
+                        .position(|a| *a == c)
+                        .ok_or(Error::InvalidEncoding)?,
+                );
+                continue;
+            }
+            let value = ALPHABET
+                .iter()
+                .position(|a| *a == c)
+                .ok_or(Error::InvalidEncoding)?;
+            symbols += 1;
+            acc = (acc << 5) | value as u16;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes[n] = (acc >> bits) as u8;
+                n += 1;
+            }
+        }
+        let check = check.ok_or(Error::InvalidEncoding)?;
+        // the padding bits of the last symbol must be zero
+        if acc & ((1 << bits) - 1) != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let code = TrackerCode(bytes);
+        if code.check_value() != check {
+            return Err(Error::InvalidChecksum);
+        }
+        Ok(code)
+    }
+}
+
+/// Hands out the tracker codes of an election, one ballot at a time.
+#[derive(Clone, Debug)]
+pub struct TrackerChain {
+    salt: [u8; 32],
+    last: TrackerCode,
+}
+
+impl TrackerChain {
+    /// Start a chain for the election identified by `salt`.
+    pub fn new(salt: &[u8]) -> TrackerChain {
+        TrackerChain {
+            salt: hash_salt(salt),
+            last: TrackerCode([0; 32]),
+        }
+    }
+
+    /// Add the next ballot to the chain, returning its code.
+    pub fn append(&mut self, ct: &Ciphertext) -> TrackerCode {
+        self.last = self.last.next(&self.salt, ct);
+        self.last
+    }
+}
+
+/// Recompute the codes of the `published` ciphertexts under `salt`, and check that they are
+/// `codes`.
+///
+/// On failure, returns the index of the first code that does not match, or the length of the
+/// shorter list if one is a prefix of the other.
+pub fn verify_inclusion(
+    salt: &[u8],
+    codes: &[TrackerCode],
+    published: &[Ciphertext],
+) -> Result<(), usize> {
+    let mut chain = TrackerChain::new(salt);
+    for (i, (code, ct)) in codes.iter().zip(published).enumerate() {
+        if chain.append(ct) != *code {
+            return Err(i);
+        }
+    }
+    if codes.len() != published.len() {
+        return Err(codes.len().min(published.len()));
+    }
+    Ok(())
+}
+
+fn hash_salt(salt: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&Sha512::digest(salt)[..32]);
+    out
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::{verify_inclusion, TrackerChain, TrackerCode};
+    use crate::{Ciphertext, DecryptionKey, Error, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+    const SALT: &[u8] = b"rust-elgamal tracker test";
+
+    fn ballots(n: u32) -> Vec<Ciphertext> {
+        let ek = *DecryptionKey::from(Scalar::from(7u32)).encryption_key();
+        (0..n)
+            .map(|i| ek.encrypt_with(&Scalar::from(i) * GENERATOR_TABLE, Scalar::from(i + 1)))
+            .collect()
+    }
+
+    fn chain_codes(cts: &[Ciphertext]) -> Vec<TrackerCode> {
+        let mut chain = TrackerChain::new(SALT);
+        cts.iter().map(|ct| chain.append(ct)).collect()
+    }
+
+    // Test the code derivation and display against fixed vectors.
+    #[test]
+    fn tracker_vectors() {
+        let codes = chain_codes(&ballots(3));
+        assert_eq!(
+            codes[0].to_string(),
+            "DCX7-BGKV-GSX6-AD2Z-34AZ-9ZCK-7TND-HACB-KS6M-YESY-FA0B-Q929-12C0-V"
+        );
+        assert_eq!(
+            codes[2].to_bytes(),
+            [
+                221, 174, 24, 89, 164, 86, 155, 37, 98, 149, 76, 14, 65, 70, 74, 171, 176, 49, 151,
+                9, 0, 215, 154, 117, 221, 170, 23, 133, 188, 214, 202, 206
+            ]
+        );
+        assert_eq!(
+            TrackerChain::new(b"another election").append(&ballots(1)[0]),
+            TrackerCode::from_bytes([
+                181, 177, 148, 255, 44, 223, 97, 179, 67, 76, 252, 133, 230, 18, 211, 232, 149,
+                107, 180, 103, 91, 133, 62, 142, 20, 116, 79, 38, 104, 224, 158, 30
+            ])
+        );
+    }
+
+    // Test that the recomputation accepts the published codes, and catches a substituted, missing
+    // or extra ciphertext anywhere in the chain.
+    #[test]
+    fn tracker_substitution() {
+        let mut rng = StdRng::seed_from_u64(488);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let published: Vec<_> = (0..8)
+            .map(|_| ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+            .collect();
+        let codes = chain_codes(&published);
+        assert_eq!(verify_inclusion(SALT, &codes, &published), Ok(()));
+        assert_eq!(verify_inclusion(b"other", &codes, &published), Err(0));
+
+        for i in 0..published.len() {
+            let mut tampered = published.clone();
+            tampered[i] = ek.rerandomise(tampered[i], &mut rng);
+            assert_eq!(verify_inclusion(SALT, &codes, &tampered), Err(i));
+            // honest codes for the tampered list differ from the original from there on
+            let recomputed = chain_codes(&tampered);
+            assert_eq!(recomputed[..i], codes[..i]);
+            assert!(recomputed[i..].iter().zip(&codes[i..]).all(|(a, b)| a != b));
+        }
+
+        let mut swapped = published.clone();
+        swapped.swap(2, 5);
+        assert_eq!(verify_inclusion(SALT, &codes, &swapped), Err(2));
+        assert_eq!(verify_inclusion(SALT, &codes, &published[1..]), Err(0));
+        assert_eq!(verify_inclusion(SALT, &codes, &published[..7]), Err(7));
+        assert_eq!(verify_inclusion(SALT, &codes[..7], &published), Err(7));
+    }
+
+    // Test that codes parse back from their display form, forgivingly, and that typos are caught.
+    #[test]
+    fn tracker_code_format() {
+        let mut rng = StdRng::seed_from_u64(488);
+        let ek = *DecryptionKey::new(&mut rng).encryption_key();
+        let published: Vec<_> = (0..50)
+            .map(|_| ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng))
+            .collect();
+        for code in chain_codes(&published) {
+            let shown = code.to_string();
+            assert_eq!(shown.len(), 53 + 13);
+            assert_eq!(shown.parse(), Ok(code));
+            assert_eq!(shown.to_lowercase().replace('-', "").parse(), Ok(code));
+            assert_eq!(shown.replace('0', "o").replace('1', "l").parse(), Ok(code));
+
+            // change one data symbol
+            let mut typo = shown.into_bytes();
+            typo[0] = if typo[0] == b'A' { b'B' } else { b'A' };
+            let typo = std::string::String::from_utf8(typo).unwrap();
+            assert_eq!(typo.parse::<TrackerCode>(), Err(Error::InvalidChecksum));
+        }
+
+        let shown = chain_codes(&published)[0].to_string();
+        assert_eq!(
+            shown[..shown.len() - 2].parse::<TrackerCode>(),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            std::format!("{}0", shown).parse::<TrackerCode>(),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            shown
+                .replacen(|c: char| c.is_ascii_alphanumeric(), "U", 1)
+                .parse::<TrackerCode>(),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
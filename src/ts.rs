@@ -0,0 +1,115 @@
+// TypeScript definitions for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TypeScript definitions of the JSON form of this crate's types, generated with
+//! [ts-rs](https://docs.rs/ts-rs/).
+//!
+//! [typescript_declarations] returns a `.d.ts` file describing the ciphertexts, keys, commitments,
+//! ballots and proofs as `serde_json` writes them. The crate's types also implement `ts_rs::TS`,
+//! with their group and scalar parameters fixed to the [Point] and [Scalar] stand-ins.
+
+use alloc::string::String;
+
+use ts_rs::TS;
+
+use crate::ballot::{Ballot, BallotProofs, ProofPolicy, Receipt, ZeroOneProof};
+use crate::{
+    Ciphertext, Commitment, CompressedCiphertext, DleqProof, EncryptionKey, Open,
+    PedersenLinkProof,
+};
+
+/// The TypeScript stand-in for a group element, which serde writes as the bytes of its
+/// compressed encoding: an array of numbers in JSON.
+#[derive(TS)]
+#[ts(type = "Array<number>")]
+pub struct Point;
+
+/// The TypeScript stand-in for a scalar, which serde writes as the bytes of its canonical
+/// little-endian encoding: an array of numbers in JSON.
+#[derive(TS)]
+#[ts(type = "Array<number>")]
+pub struct Scalar;
+
+// Opens are generic over secret scalars, which must be wipeable with the `zeroize` feature.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Scalar {
+    fn zeroize(&mut self) {}
+}
+
+/// TypeScript definitions of the JSON form (with `serde_json`) of the ciphertexts, keys,
+/// commitments, ballots and proofs of this crate, as a `.d.ts` file.
+///
+/// Points and scalars appear as the `Point` and `Scalar` aliases of `Array<number>`: the 32 bytes
+/// of their encodings. A snapshot of the output is kept in `bindings/rust_elgamal.d.ts`, and
+/// `cargo test --features ts` fails if it is out of date; run the tests with
+/// `UPDATE_TS_BINDINGS=1` to rewrite it.
+pub fn typescript_declarations() -> String {
+    let decls = [
+        named_alias::<Point>(),
+        named_alias::<Scalar>(),
+        EncryptionKey::<Point>::decl(),
+        Ciphertext::<Point>::decl(),
+        CompressedCiphertext::decl(),
+        Commitment::<Point>::decl(),
+        Open::<Scalar>::decl(),
+        DleqProof::decl(),
+        PedersenLinkProof::decl(),
+        ZeroOneProof::decl(),
+        Ballot::decl(),
+        BallotProofs::decl(),
+        ProofPolicy::decl(),
+        Receipt::decl(),
+    ];
+
+    let mut out = String::from("// Generated by rust-elgamal's `ts` feature. Do not edit.\n");
+    for decl in decls {
+        out.push_str("\nexport ");
+        out.push_str(&decl);
+        out.push('\n');
+    }
+    out
+}
+
+// `decl` of a type with a top-level override only gives the override, so spell out the alias.
+fn named_alias<T: TS>() -> String {
+    alloc::format!("type {} = {};", T::name(), T::inline())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::ts::typescript_declarations;
+
+    // Test that the committed TypeScript definitions match the types, or rewrite them on request.
+    #[test]
+    fn typescript_snapshot() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("bindings/rust_elgamal.d.ts");
+        let generated = typescript_declarations();
+        if std::env::var_os("UPDATE_TS_BINDINGS").is_some() {
+            std::fs::write(&path, &generated).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "{} is out of date; rerun the tests with UPDATE_TS_BINDINGS=1 to regenerate it.\n\
+             generated:\n{}",
+            path.display(),
+            generated
+        );
+    }
+}
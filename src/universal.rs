@@ -0,0 +1,168 @@
+// Universal re-encryption for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::{Debug, Formatter};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::Field;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ciphertext, DecryptionKey, ElGamalGroup, EncryptionKey, Error};
+
+/// A ciphertext that can be rerandomised without knowing the key it was encrypted under
+/// (universal re-encryption, after Golle, Jakobsson, Juels and Syverson).
+///
+/// It carries, alongside the encryption of the message, an encryption of the identity under the
+/// same key. Adding a random multiple of the latter to the former, and scaling the latter,
+/// produces a fresh encryption of the same message under the same key.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rust_elgamal::{DecryptionKey, RistrettoPoint};
+///
+/// let mut rng = StdRng::from_entropy();
+/// let dec_key = DecryptionKey::new(&mut rng);
+///
+/// let m = RistrettoPoint::random(&mut rng);
+/// let mut ct = dec_key.encryption_key().encrypt_universal(m, &mut rng);
+/// ct.rerandomise_universal(&mut rng);
+/// assert_eq!(dec_key.decrypt_universal(&ct), Ok(m));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-serde",
+    serde(bound(serialize = "G: Serialize", deserialize = "G: Deserialize<'de>"))
+)]
+pub struct UniversalCiphertext<G = RistrettoPoint> {
+    pub(crate) message: Ciphertext<G>,
+    pub(crate) identity: Ciphertext<G>,
+}
+
+impl<G: ElGamalGroup> UniversalCiphertext<G> {
+    /// Returns the encryption of the message and the encryption of the identity. Intended for
+    /// advanced use only.
+    pub fn inner(&self) -> (Ciphertext<G>, Ciphertext<G>) {
+        (self.message, self.identity)
+    }
+
+    /// Rerandomise the ciphertext in place, without needing the encryption key.
+    pub fn rerandomise_universal<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        let k1 = G::Scalar::random(&mut *rng);
+        let k2 = G::Scalar::random(rng);
+        self.message = self.message + self.identity * k1;
+        self.identity *= k2;
+    }
+}
+
+impl<G: ElGamalGroup> Debug for UniversalCiphertext<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "UniversalCiphertext({:?}, {:?})",
+            self.message, self.identity
+        )
+    }
+}
+
+impl<G: ElGamalGroup> EncryptionKey<G> {
+    /// Encrypt the curve point `m` as a [UniversalCiphertext], which can later be rerandomised
+    /// without this key.
+    pub fn encrypt_universal<R: RngCore + CryptoRng>(
+        &self,
+        m: G,
+        rng: &mut R,
+    ) -> UniversalCiphertext<G> {
+        UniversalCiphertext {
+            message: self.encrypt(m, rng),
+            identity: self.encrypt(G::identity(), rng),
+        }
+    }
+}
+
+impl<G: ElGamalGroup> DecryptionKey<G> {
+    /// Decrypt a [UniversalCiphertext], checking that its identity component is a valid
+    /// encryption of the identity under this key.
+    ///
+    /// Fails with [Error::VerificationFailed](crate::Error::VerificationFailed) if it is not,
+    /// which happens if the ciphertext was tampered with or was not encrypted under this key.
+    pub fn decrypt_universal(&self, ct: &UniversalCiphertext<G>) -> Result<G, Error> {
+        let Ciphertext(r_g, _) = ct.identity;
+        if bool::from(r_g.is_identity()) || !bool::from(self.decrypt(ct.identity).is_identity()) {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(self.decrypt(ct.message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::{Ciphertext, DecryptionKey, Error, Identity, RistrettoPoint};
+
+    // Test that repeated keyless rerandomisation preserves the plaintext.
+    #[test]
+    fn universal_rerandomise() {
+        const N: usize = 20;
+
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+
+        let mut ct = dk.encryption_key().encrypt_universal(m, &mut rng);
+        for _ in 0..N {
+            let before = ct;
+            ct.rerandomise_universal(&mut rng);
+            assert_ne!(ct.message, before.message);
+            assert_ne!(ct.identity, before.identity);
+            assert_eq!(dk.decrypt_universal(&ct), Ok(m));
+        }
+    }
+
+    // Test that a tampered identity component, or the wrong key, is detected.
+    #[test]
+    fn universal_tampered() {
+        let mut rng = StdRng::from_entropy();
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = dk.encryption_key().encrypt_universal(m, &mut rng);
+
+        assert_eq!(other.decrypt_universal(&ct), Err(Error::VerificationFailed));
+
+        let mut tampered = ct;
+        tampered.identity.1 += RistrettoPoint::random(&mut rng);
+        assert_eq!(
+            dk.decrypt_universal(&tampered),
+            Err(Error::VerificationFailed)
+        );
+
+        // an all-identity component would let anyone "rerandomise" to a fixed ciphertext
+        let mut tampered = ct;
+        tampered.identity = Ciphertext::identity();
+        assert_eq!(
+            dk.decrypt_universal(&tampered),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
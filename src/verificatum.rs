@@ -0,0 +1,321 @@
+// Verificatum byte trees for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verificatum's byte-tree format, for publishing mixnet inputs and outputs to its verifier.
+//!
+//! A byte tree is either a leaf, written as the byte `1`, a 4-byte big-endian length and the data,
+//! or a node, written as the byte `0`, a 4-byte big-endian number of children and the children.
+//! Verificatum writes a list of ElGamal ciphertexts as a node of two arrays, the first
+//! components and the second components, and each array as a node with one child per group
+//! element.
+//!
+//! Here a group element is a leaf holding the 32-byte compressed ristretto255 point. Verificatum's
+//! own groups encode elements differently (a big integer leaf for `ModPGroup`, a node of two
+//! coordinate leaves for `ECqPGroup`); [ciphertexts_from_byte_tree] recognises these and reports
+//! [Error::UnsupportedGroup](crate::Error::UnsupportedGroup) rather than a generic decoding error,
+//! since the election has to be set up over ristretto255 for its ciphertexts to be used here.
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+
+use crate::{Ciphertext, Error};
+
+const LEAF: u8 = 1;
+const NODE: u8 = 0;
+
+// Deeper trees than this are rejected when parsing, so hostile input cannot exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+/// A Verificatum byte tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ByteTree {
+    Leaf(Vec<u8>),
+    Node(Vec<ByteTree>),
+}
+
+impl ByteTree {
+    /// Encode the tree in Verificatum's binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            ByteTree::Leaf(data) => {
+                out.push(LEAF);
+                out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                out.extend_from_slice(data);
+            }
+            ByteTree::Node(children) => {
+                out.push(NODE);
+                out.extend_from_slice(&(children.len() as u32).to_be_bytes());
+                for child in children {
+                    child.write(out);
+                }
+            }
+        }
+    }
+
+    /// Decode a tree from Verificatum's binary format.
+    ///
+    /// Fails with [Error::InvalidByteTree](crate::Error::InvalidByteTree) if the bytes are
+    /// truncated, have trailing data, use an unknown tag or nest too deeply.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ByteTree, Error> {
+        let (tree, rest) = ByteTree::read(bytes, 0)?;
+        if !rest.is_empty() {
+            return Err(Error::InvalidByteTree);
+        }
+        Ok(tree)
+    }
+
+    fn read(bytes: &[u8], depth: usize) -> Result<(ByteTree, &[u8]), Error> {
+        if depth > MAX_DEPTH || bytes.len() < 5 {
+            return Err(Error::InvalidByteTree);
+        }
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&bytes[1..5]);
+        let len = u32::from_be_bytes(len) as usize;
+        let rest = &bytes[5..];
+
+        match bytes[0] {
+            LEAF => {
+                if rest.len() < len {
+                    return Err(Error::InvalidByteTree);
+                }
+                Ok((ByteTree::Leaf(rest[..len].to_vec()), &rest[len..]))
+            }
+            NODE => {
+                // every child takes at least five bytes, which bounds the allocation
+                if rest.len() / 5 < len {
+                    return Err(Error::InvalidByteTree);
+                }
+                let mut children = Vec::with_capacity(len);
+                let mut rest = rest;
+                for _ in 0..len {
+                    let (child, tail) = ByteTree::read(rest, depth + 1)?;
+                    children.push(child);
+                    rest = tail;
+                }
+                Ok((ByteTree::Node(children), rest))
+            }
+            _ => Err(Error::InvalidByteTree),
+        }
+    }
+}
+
+/// Arrange ciphertexts as Verificatum does: a node of the array of first components and the
+/// array of second components.
+pub fn ciphertexts_to_byte_tree(cts: &[Ciphertext]) -> ByteTree {
+    let array = |component: fn(&Ciphertext) -> &RistrettoPoint| {
+        ByteTree::Node(
+            cts.iter()
+                .map(|ct| ByteTree::Leaf(component(ct).compress().to_bytes().to_vec()))
+                .collect(),
+        )
+    };
+    ByteTree::Node(alloc::vec![array(|ct| &ct.0), array(|ct| &ct.1)])
+}
+
+/// Read a list of ciphertexts laid out as by [ciphertexts_to_byte_tree].
+///
+/// Fails with [Error::UnsupportedGroup](crate::Error::UnsupportedGroup) if the elements are
+/// encoded for one of Verificatum's own groups, with
+/// [Error::LengthMismatch](crate::Error::LengthMismatch) if the two arrays differ in length, with
+/// [Error::InvalidPoint](crate::Error::InvalidPoint) if an element does not decode, and with
+/// [Error::InvalidByteTree](crate::Error::InvalidByteTree) if the tree has some other shape.
+pub fn ciphertexts_from_byte_tree(tree: &ByteTree) -> Result<Vec<Ciphertext>, Error> {
+    let (firsts, seconds) = match tree {
+        ByteTree::Node(children) => match children.as_slice() {
+            [ByteTree::Node(firsts), ByteTree::Node(seconds)] => (firsts, seconds),
+            _ => return Err(Error::InvalidByteTree),
+        },
+        ByteTree::Leaf(_) => return Err(Error::InvalidByteTree),
+    };
+    if firsts.len() != seconds.len() {
+        return Err(Error::LengthMismatch {
+            expected: firsts.len(),
+            got: seconds.len(),
+        });
+    }
+
+    firsts
+        .iter()
+        .zip(seconds)
+        .map(|(a, b)| Ok(Ciphertext(element(a)?, element(b)?)))
+        .collect()
+}
+
+/// [ciphertexts_to_byte_tree], encoded in Verificatum's binary format.
+pub fn encode_ciphertexts(cts: &[Ciphertext]) -> Vec<u8> {
+    ciphertexts_to_byte_tree(cts).to_bytes()
+}
+
+/// Read a binary ciphertext list, such as Verificatum's `ciphertexts` files, into ciphertexts.
+///
+/// Fails as [ByteTree::from_bytes] and [ciphertexts_from_byte_tree] do.
+pub fn decode_ciphertexts(bytes: &[u8]) -> Result<Vec<Ciphertext>, Error> {
+    ciphertexts_from_byte_tree(&ByteTree::from_bytes(bytes)?)
+}
+
+fn element(tree: &ByteTree) -> Result<RistrettoPoint, Error> {
+    match tree {
+        ByteTree::Leaf(data) if data.len() == 32 => CompressedRistretto::from_slice(data)
+            .map_err(|_| Error::InvalidPoint)?
+            .decompress()
+            .ok_or(Error::InvalidPoint),
+        // a `ModPGroup` integer, or an `ECqPGroup` pair of coordinates
+        ByteTree::Leaf(_) => Err(Error::UnsupportedGroup),
+        ByteTree::Node(coordinates) if coordinates.len() == 2 => Err(Error::UnsupportedGroup),
+        ByteTree::Node(_) => Err(Error::InvalidByteTree),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use crate::verificatum::{
+        ciphertexts_from_byte_tree, ciphertexts_to_byte_tree, decode_ciphertexts,
+        encode_ciphertexts, ByteTree,
+    };
+    use crate::{DecryptionKey, Error, Scalar, GENERATOR_TABLE};
+
+    // Three encryptions of 1, 2 and 3 times the generator, written by this module from
+    // `StdRng::seed_from_u64(450)`, kept to catch accidental changes to the format.
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/verificatum_ciphertexts.bt");
+
+    // Test that the container format matches Verificatum's tag and length layout.
+    #[test]
+    fn byte_tree_format() {
+        let tree = ByteTree::Node(vec![
+            ByteTree::Leaf(vec![0xab, 0xcd]),
+            ByteTree::Node(vec![]),
+        ]);
+        let bytes = tree.to_bytes();
+        assert_eq!(
+            bytes,
+            [0, 0, 0, 0, 2, 1, 0, 0, 0, 2, 0xab, 0xcd, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(ByteTree::from_bytes(&bytes), Ok(tree));
+
+        // truncated, trailing data, unknown tag, absurd child count
+        assert_eq!(
+            ByteTree::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidByteTree)
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(ByteTree::from_bytes(&trailing), Err(Error::InvalidByteTree));
+        assert_eq!(
+            ByteTree::from_bytes(&[2, 0, 0, 0, 0]),
+            Err(Error::InvalidByteTree)
+        );
+        assert_eq!(
+            ByteTree::from_bytes(&[0, 0xff, 0xff, 0xff, 0xff]),
+            Err(Error::InvalidByteTree)
+        );
+
+        // nesting deeper than the limit
+        let mut deep = Vec::new();
+        for _ in 0..100 {
+            deep.extend_from_slice(&[0, 0, 0, 0, 1]);
+        }
+        deep.extend_from_slice(&[1, 0, 0, 0, 0]);
+        assert_eq!(ByteTree::from_bytes(&deep), Err(Error::InvalidByteTree));
+    }
+
+    // Test that ciphertext lists round-trip, and match the checked-in fixture in both directions.
+    #[test]
+    fn verificatum_ciphertexts() {
+        let mut rng = StdRng::seed_from_u64(450);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let cts: Vec<_> = (1u32..=3)
+            .map(|m| ek.encrypt(&Scalar::from(m) * GENERATOR_TABLE, &mut rng))
+            .collect();
+
+        let bytes = encode_ciphertexts(&cts);
+        assert_eq!(bytes, FIXTURE);
+        assert_eq!(decode_ciphertexts(FIXTURE), Ok(cts.clone()));
+
+        for (m, ct) in (1u32..=3).zip(decode_ciphertexts(FIXTURE).unwrap()) {
+            assert_eq!(dk.decrypt(ct), &Scalar::from(m) * GENERATOR_TABLE);
+        }
+        assert_eq!(decode_ciphertexts(&encode_ciphertexts(&[])), Ok(vec![]));
+    }
+
+    // Test that elements of Verificatum's own groups and malformed lists are reported.
+    #[test]
+    fn verificatum_rejects() {
+        let list = |element: ByteTree| {
+            ByteTree::Node(vec![
+                ByteTree::Node(vec![element.clone()]),
+                ByteTree::Node(vec![element]),
+            ])
+        };
+
+        // an `ECqPGroup` point over P-256, as a node of its affine coordinates
+        let ecq = ByteTree::Node(vec![
+            ByteTree::Leaf(vec![1; 32]),
+            ByteTree::Leaf(vec![2; 32]),
+        ]);
+        assert_eq!(
+            ciphertexts_from_byte_tree(&list(ecq)),
+            Err(Error::UnsupportedGroup)
+        );
+        // a `ModPGroup` element, as a big integer
+        let modp = ByteTree::Leaf(vec![3; 257]);
+        assert_eq!(
+            ciphertexts_from_byte_tree(&list(modp)),
+            Err(Error::UnsupportedGroup)
+        );
+        // a 32-byte leaf that is not a ristretto255 point
+        let bad = ByteTree::Leaf(vec![0xff; 32]);
+        assert_eq!(
+            ciphertexts_from_byte_tree(&list(bad)),
+            Err(Error::InvalidPoint)
+        );
+
+        let empty = ByteTree::Node(vec![ByteTree::Node(vec![]), ByteTree::Node(vec![])]);
+        assert_eq!(ciphertexts_from_byte_tree(&empty), Ok(vec![]));
+        let mut cts = decode_ciphertexts(FIXTURE).unwrap();
+        cts.truncate(1);
+        let uneven = match ciphertexts_to_byte_tree(&cts) {
+            ByteTree::Node(mut arrays) => {
+                arrays[1] = ByteTree::Node(vec![]);
+                ByteTree::Node(arrays)
+            }
+            leaf => leaf,
+        };
+        assert_eq!(
+            ciphertexts_from_byte_tree(&uneven),
+            Err(Error::LengthMismatch {
+                expected: 1,
+                got: 0
+            })
+        );
+        assert_eq!(
+            ciphertexts_from_byte_tree(&ByteTree::Leaf(vec![])),
+            Err(Error::InvalidByteTree)
+        );
+    }
+}
@@ -0,0 +1,603 @@
+// Integration with the zkp crate for rust-elgamal.
+// Copyright 2024 Alvin Hon
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statements about this crate's types as [zkp](https://docs.rs/zkp/) Schnorr proofs.
+//!
+//! `zkp` is built on `curve25519-dalek-ng` 3, whose points and scalars are distinct types from
+//! this crate's; both encode ristretto255 identically, so the adapters here convert through the
+//! encodings. Three statements are defined with `define_proof!`, and their generated modules are
+//! public for direct use (including `batch_verify`):
+//!
+//! * [decryption]: `Y = x * G` and `D = x * C1`, where `D = C2 - M`, so `M` is the decryption of
+//!   `(C1, C2)` under the key `Y`. [DecryptionStatement] wraps it.
+//! * [rerandomisation]: `A = r * G` and `B = r * Y`, where `(A, B)` is the difference of two
+//!   ciphertexts, so one is a re-randomisation of the other under `Y`. [RerandomisationStatement]
+//!   wraps it.
+//! * [possession]: `Y = x * G`. [PossessionStatement] wraps it.
+//!
+//! Each wrapper proves and verifies in both of `zkp`'s forms: [CompactProof]s are smaller, and
+//! [BatchableProof]s can be verified together with the generated `batch_verify`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use rust_elgamal::zkp::{DecryptionStatement, Transcript};
+//! use rust_elgamal::{DecryptionKey, RistrettoPoint};
+//!
+//! let mut rng = StdRng::from_entropy();
+//! let dec_key = DecryptionKey::new(&mut rng);
+//! let m = RistrettoPoint::random(&mut rng);
+//! let ct = dec_key.encryption_key().encrypt(m, &mut rng);
+//!
+//! let statement = DecryptionStatement::new(dec_key.encryption_key(), &ct, &m);
+//! let proof = statement.prove_compact(&mut Transcript::new(b"example"), &dec_key);
+//! assert!(statement
+//!     .verify_compact(&proof, &mut Transcript::new(b"example"))
+//!     .is_ok());
+//! ```
+
+// the code `define_proof!` generates has benchmarks behind zkp's own `bench` feature
+#![allow(unexpected_cfgs)]
+
+use ::zkp::curve25519_dalek::ristretto::{
+    CompressedRistretto as ZkpCompressedRistretto, RistrettoPoint as ZkpRistrettoPoint,
+};
+use ::zkp::curve25519_dalek::scalar::Scalar as ZkpScalar;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+pub use ::zkp::{BatchableProof, CompactProof, ProofError, Transcript};
+
+use crate::{Ciphertext, Commitment, DecryptionKey, EncryptionKey, Error, GENERATOR_POINT};
+
+::zkp::define_proof! {
+    decryption, "rust-elgamal decryption", (x), (Y, C1, D), (G) :
+    Y = (x * G), D = (x * C1)
+}
+
+::zkp::define_proof! {
+    rerandomisation, "rust-elgamal rerandomisation", (r), (A, B, Y), (G) :
+    A = (r * G), B = (r * Y)
+}
+
+::zkp::define_proof! {
+    possession, "rust-elgamal possession", (x), (Y), (G) :
+    Y = (x * G)
+}
+
+// Adapters
+
+/// Convert a point into `zkp`'s point type.
+pub fn point_to_zkp(point: &RistrettoPoint) -> ZkpRistrettoPoint {
+    ZkpCompressedRistretto(point.compress().to_bytes())
+        .decompress()
+        .expect("both versions decode ristretto255 encodings alike")
+}
+
+/// Convert a point from `zkp`'s point type.
+pub fn point_from_zkp(point: &ZkpRistrettoPoint) -> RistrettoPoint {
+    CompressedRistretto(point.compress().to_bytes())
+        .decompress()
+        .expect("both versions decode ristretto255 encodings alike")
+}
+
+/// Convert a scalar into `zkp`'s scalar type.
+pub fn scalar_to_zkp(scalar: &Scalar) -> ZkpScalar {
+    ZkpScalar::from_bytes_mod_order(scalar.to_bytes())
+}
+
+/// Convert a scalar from `zkp`'s scalar type.
+///
+/// Fails with [Error::InvalidScalar](crate::Error::InvalidScalar) if the scalar is not reduced,
+/// which `curve25519-dalek-ng` allows and this crate does not.
+pub fn scalar_from_zkp(scalar: &ZkpScalar) -> Result<Scalar, Error> {
+    Option::from(Scalar::from_canonical_bytes(scalar.to_bytes())).ok_or(Error::InvalidScalar)
+}
+
+/// The point of an encryption key, as a `zkp` point.
+pub fn encryption_key_to_zkp(ek: &EncryptionKey) -> ZkpRistrettoPoint {
+    point_to_zkp(&ek.0)
+}
+
+/// The encryption key with the `zkp` point `y`.
+pub fn encryption_key_from_zkp(y: &ZkpRistrettoPoint) -> EncryptionKey {
+    EncryptionKey(point_from_zkp(y))
+}
+
+/// The two points of a ciphertext, as `zkp` points.
+pub fn ciphertext_to_zkp(ct: &Ciphertext) -> (ZkpRistrettoPoint, ZkpRistrettoPoint) {
+    (point_to_zkp(&ct.0), point_to_zkp(&ct.1))
+}
+
+/// The ciphertext with the `zkp` points `c1` and `c2`.
+pub fn ciphertext_from_zkp(c1: &ZkpRistrettoPoint, c2: &ZkpRistrettoPoint) -> Ciphertext {
+    Ciphertext(point_from_zkp(c1), point_from_zkp(c2))
+}
+
+/// The key and the two ciphertext points of a commitment, as `zkp` points.
+pub fn commitment_to_zkp(
+    commitment: &Commitment,
+) -> (ZkpRistrettoPoint, ZkpRistrettoPoint, ZkpRistrettoPoint) {
+    let Commitment(y, ct) = commitment;
+    (point_to_zkp(y), point_to_zkp(&ct.0), point_to_zkp(&ct.1))
+}
+
+/// The commitment with the key `y` and ciphertext points `c1` and `c2`, as `zkp` points.
+pub fn commitment_from_zkp(
+    y: &ZkpRistrettoPoint,
+    c1: &ZkpRistrettoPoint,
+    c2: &ZkpRistrettoPoint,
+) -> Commitment {
+    Commitment(point_from_zkp(y), ciphertext_from_zkp(c1, c2))
+}
+
+fn check(result: Result<(), ProofError>) -> Result<(), Error> {
+    result.map_err(|_| Error::VerificationFailed)
+}
+
+fn generator() -> ZkpRistrettoPoint {
+    point_to_zkp(&GENERATOR_POINT)
+}
+
+// Statements
+
+/// The statement that `m` is the decryption of a ciphertext under a key, as [decryption] binds
+/// it.
+#[derive(Copy, Clone, Debug)]
+pub struct DecryptionStatement {
+    y: ZkpRistrettoPoint,
+    c1: ZkpRistrettoPoint,
+    d: ZkpRistrettoPoint,
+}
+
+impl DecryptionStatement {
+    /// The statement that `ct` under `ek` decrypts to `m`.
+    pub fn new(ek: &EncryptionKey, ct: &Ciphertext, m: &RistrettoPoint) -> DecryptionStatement {
+        DecryptionStatement {
+            y: encryption_key_to_zkp(ek),
+            c1: point_to_zkp(&ct.0),
+            d: point_to_zkp(&(ct.1 - m)),
+        }
+    }
+
+    fn prove_assignments<'a>(
+        &'a self,
+        x: &'a ZkpScalar,
+        g: &'a ZkpRistrettoPoint,
+    ) -> decryption::ProveAssignments<'a> {
+        decryption::ProveAssignments {
+            x,
+            Y: &self.y,
+            C1: &self.c1,
+            D: &self.d,
+            G: g,
+        }
+    }
+
+    fn verify_assignments<'a>(
+        compressed: &'a [ZkpCompressedRistretto; 4],
+    ) -> decryption::VerifyAssignments<'a> {
+        decryption::VerifyAssignments {
+            Y: &compressed[0],
+            C1: &compressed[1],
+            D: &compressed[2],
+            G: &compressed[3],
+        }
+    }
+
+    fn compressed(&self) -> [ZkpCompressedRistretto; 4] {
+        [
+            self.y.compress(),
+            self.c1.compress(),
+            self.d.compress(),
+            generator().compress(),
+        ]
+    }
+
+    /// Prove the statement with `dk`, the decryption key of the statement's key, in compact form.
+    pub fn prove_compact(&self, transcript: &mut Transcript, dk: &DecryptionKey) -> CompactProof {
+        let (x, g) = (scalar_to_zkp(&dk.secret), generator());
+        decryption::prove_compact(transcript, self.prove_assignments(&x, &g)).0
+    }
+
+    /// Prove the statement with `dk`, the decryption key of the statement's key, in batchable
+    /// form.
+    pub fn prove_batchable(
+        &self,
+        transcript: &mut Transcript,
+        dk: &DecryptionKey,
+    ) -> BatchableProof {
+        let (x, g) = (scalar_to_zkp(&dk.secret), generator());
+        decryption::prove_batchable(transcript, self.prove_assignments(&x, &g)).0
+    }
+
+    /// Verify a compact proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_compact(
+        &self,
+        proof: &CompactProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let compressed = self.compressed();
+        check(decryption::verify_compact(
+            proof,
+            transcript,
+            Self::verify_assignments(&compressed),
+        ))
+    }
+
+    /// Verify a batchable proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_batchable(
+        &self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let compressed = self.compressed();
+        check(decryption::verify_batchable(
+            proof,
+            transcript,
+            Self::verify_assignments(&compressed),
+        ))
+    }
+}
+
+/// The statement that one ciphertext is a re-randomisation of another under a key, as
+/// [rerandomisation] binds it.
+#[derive(Copy, Clone, Debug)]
+pub struct RerandomisationStatement {
+    a: ZkpRistrettoPoint,
+    b: ZkpRistrettoPoint,
+    y: ZkpRistrettoPoint,
+}
+
+impl RerandomisationStatement {
+    /// The statement that `rerandomised` is `original` re-randomised under `ek`.
+    pub fn new(
+        ek: &EncryptionKey,
+        original: &Ciphertext,
+        rerandomised: &Ciphertext,
+    ) -> RerandomisationStatement {
+        RerandomisationStatement {
+            a: point_to_zkp(&(rerandomised.0 - original.0)),
+            b: point_to_zkp(&(rerandomised.1 - original.1)),
+            y: encryption_key_to_zkp(ek),
+        }
+    }
+
+    fn prove_assignments<'a>(
+        &'a self,
+        r: &'a ZkpScalar,
+        g: &'a ZkpRistrettoPoint,
+    ) -> rerandomisation::ProveAssignments<'a> {
+        rerandomisation::ProveAssignments {
+            r,
+            A: &self.a,
+            B: &self.b,
+            Y: &self.y,
+            G: g,
+        }
+    }
+
+    fn verify_assignments<'a>(
+        compressed: &'a [ZkpCompressedRistretto; 4],
+    ) -> rerandomisation::VerifyAssignments<'a> {
+        rerandomisation::VerifyAssignments {
+            A: &compressed[0],
+            B: &compressed[1],
+            Y: &compressed[2],
+            G: &compressed[3],
+        }
+    }
+
+    fn compressed(&self) -> [ZkpCompressedRistretto; 4] {
+        [
+            self.a.compress(),
+            self.b.compress(),
+            self.y.compress(),
+            generator().compress(),
+        ]
+    }
+
+    /// Prove the statement with `r`, the blinding factor the ciphertext was re-randomised with,
+    /// in compact form.
+    pub fn prove_compact(&self, transcript: &mut Transcript, r: &Scalar) -> CompactProof {
+        let (r, g) = (scalar_to_zkp(r), generator());
+        rerandomisation::prove_compact(transcript, self.prove_assignments(&r, &g)).0
+    }
+
+    /// Prove the statement with `r`, the blinding factor the ciphertext was re-randomised with,
+    /// in batchable form.
+    pub fn prove_batchable(&self, transcript: &mut Transcript, r: &Scalar) -> BatchableProof {
+        let (r, g) = (scalar_to_zkp(r), generator());
+        rerandomisation::prove_batchable(transcript, self.prove_assignments(&r, &g)).0
+    }
+
+    /// Verify a compact proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_compact(
+        &self,
+        proof: &CompactProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let compressed = self.compressed();
+        check(rerandomisation::verify_compact(
+            proof,
+            transcript,
+            Self::verify_assignments(&compressed),
+        ))
+    }
+
+    /// Verify a batchable proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_batchable(
+        &self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let compressed = self.compressed();
+        check(rerandomisation::verify_batchable(
+            proof,
+            transcript,
+            Self::verify_assignments(&compressed),
+        ))
+    }
+}
+
+/// The statement that the prover knows the decryption key of an encryption key, as [possession]
+/// binds it.
+#[derive(Copy, Clone, Debug)]
+pub struct PossessionStatement {
+    y: ZkpRistrettoPoint,
+}
+
+impl PossessionStatement {
+    /// The statement that the prover knows the decryption key of `ek`.
+    pub fn new(ek: &EncryptionKey) -> PossessionStatement {
+        PossessionStatement {
+            y: encryption_key_to_zkp(ek),
+        }
+    }
+
+    /// Prove the statement with `dk` in compact form.
+    pub fn prove_compact(&self, transcript: &mut Transcript, dk: &DecryptionKey) -> CompactProof {
+        let (x, g) = (scalar_to_zkp(&dk.secret), generator());
+        let assignments = possession::ProveAssignments {
+            x: &x,
+            Y: &self.y,
+            G: &g,
+        };
+        possession::prove_compact(transcript, assignments).0
+    }
+
+    /// Prove the statement with `dk` in batchable form.
+    pub fn prove_batchable(
+        &self,
+        transcript: &mut Transcript,
+        dk: &DecryptionKey,
+    ) -> BatchableProof {
+        let (x, g) = (scalar_to_zkp(&dk.secret), generator());
+        let assignments = possession::ProveAssignments {
+            x: &x,
+            Y: &self.y,
+            G: &g,
+        };
+        possession::prove_batchable(transcript, assignments).0
+    }
+
+    /// Verify a compact proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_compact(
+        &self,
+        proof: &CompactProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let (y, g) = (self.y.compress(), generator().compress());
+        let assignments = possession::VerifyAssignments { Y: &y, G: &g };
+        check(possession::verify_compact(proof, transcript, assignments))
+    }
+
+    /// Verify a batchable proof of the statement, failing with
+    /// [Error::VerificationFailed](crate::Error::VerificationFailed) if it does not hold.
+    pub fn verify_batchable(
+        &self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), Error> {
+        let (y, g) = (self.y.compress(), generator().compress());
+        let assignments = possession::VerifyAssignments { Y: &y, G: &g };
+        check(possession::verify_batchable(proof, transcript, assignments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::StdRng;
+    use rand_core::SeedableRng;
+
+    use super::*;
+    use crate::DleqProof;
+
+    const LABEL: &[u8] = b"rust-elgamal zkp test";
+
+    fn transcript() -> Transcript {
+        Transcript::new(LABEL)
+    }
+
+    // Test that the adapters round-trip every type and agree with the byte encodings.
+    #[test]
+    fn zkp_adapters() {
+        let mut rng = StdRng::seed_from_u64(472);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let (_, commitment) = Commitment::commit(Scalar::from(472u32), &mut rng);
+        let s = Scalar::random(&mut rng);
+
+        assert_eq!(
+            point_to_zkp(&ct.0).compress().to_bytes(),
+            ct.0.compress().to_bytes()
+        );
+        assert_eq!(encryption_key_from_zkp(&encryption_key_to_zkp(ek)), *ek);
+        let (c1, c2) = ciphertext_to_zkp(&ct);
+        assert_eq!(ciphertext_from_zkp(&c1, &c2), ct);
+        let (y, c1, c2) = commitment_to_zkp(&commitment);
+        assert_eq!(commitment_from_zkp(&y, &c1, &c2), commitment);
+        assert_eq!(scalar_to_zkp(&s).to_bytes(), s.to_bytes());
+        assert_eq!(scalar_from_zkp(&scalar_to_zkp(&s)), Ok(s));
+        assert_eq!(
+            scalar_from_zkp(&ZkpScalar::from_bits([0xff; 32])),
+            Err(Error::InvalidScalar)
+        );
+
+        // arithmetic agrees across the two libraries
+        assert_eq!(
+            point_from_zkp(&(point_to_zkp(&ct.0) * scalar_to_zkp(&s))),
+            ct.0 * s
+        );
+    }
+
+    // Test that decryption proofs in both forms agree with the native Chaum-Pedersen proof.
+    #[test]
+    fn zkp_decryption() {
+        let mut rng = StdRng::seed_from_u64(472);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let m = RistrettoPoint::random(&mut rng);
+        let ct = ek.encrypt(m, &mut rng);
+
+        let statement = DecryptionStatement::new(ek, &ct, &m);
+        let compact = statement.prove_compact(&mut transcript(), &dk);
+        let batchable = statement.prove_batchable(&mut transcript(), &dk);
+        assert_eq!(
+            statement.verify_compact(&compact, &mut transcript()),
+            Ok(())
+        );
+        assert_eq!(
+            statement.verify_batchable(&batchable, &mut transcript()),
+            Ok(())
+        );
+        let native = DleqProof::prove(&dk, &ct.0, &(ct.1 - m), &mut rng);
+        assert_eq!(native.verify(ek, &ct.0, &(ct.1 - m)), Ok(()));
+
+        // the wrong plaintext fails both, and a different transcript fails zkp's
+        let wrong = DecryptionStatement::new(ek, &ct, &(m + GENERATOR_POINT));
+        let wrong_d = ct.1 - m - GENERATOR_POINT;
+        assert_eq!(
+            wrong.verify_compact(&compact, &mut transcript()),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            native.verify(ek, &ct.0, &wrong_d),
+            Err(Error::VerificationFailed)
+        );
+        let forged = wrong.prove_compact(&mut transcript(), &dk);
+        assert_eq!(
+            wrong.verify_compact(&forged, &mut transcript()),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            statement.verify_compact(&compact, &mut Transcript::new(b"other")),
+            Err(Error::VerificationFailed)
+        );
+
+        // batchable proofs verify together through the generated module
+        let ct2 = ek.encrypt(m, &mut rng);
+        let statement2 = DecryptionStatement::new(ek, &ct2, &m);
+        let batchable2 = statement2.prove_batchable(&mut transcript(), &dk);
+        let (mut t1, mut t2) = (transcript(), transcript());
+        let assignments = decryption::BatchVerifyAssignments {
+            Y: vec![statement.y.compress(), statement2.y.compress()],
+            C1: vec![statement.c1.compress(), statement2.c1.compress()],
+            D: vec![statement.d.compress(), statement2.d.compress()],
+            G: generator().compress(),
+        };
+        assert!(decryption::batch_verify(
+            &[batchable, batchable2],
+            vec![&mut t1, &mut t2],
+            assignments
+        )
+        .is_ok());
+    }
+
+    // Test that rerandomisation proofs hold exactly for the native re-randomisation.
+    #[test]
+    fn zkp_rerandomisation() {
+        let mut rng = StdRng::seed_from_u64(472);
+        let dk = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+        let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let r = Scalar::random(&mut rng);
+        let rerandomised = ek.rerandomise_with(ct, r);
+
+        let statement = RerandomisationStatement::new(ek, &ct, &rerandomised);
+        let compact = statement.prove_compact(&mut transcript(), &r);
+        let batchable = statement.prove_batchable(&mut transcript(), &r);
+        assert_eq!(
+            statement.verify_compact(&compact, &mut transcript()),
+            Ok(())
+        );
+        assert_eq!(
+            statement.verify_batchable(&batchable, &mut transcript()),
+            Ok(())
+        );
+
+        // a fresh encryption of another message is not a re-randomisation
+        let other = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+        let statement = RerandomisationStatement::new(ek, &ct, &other);
+        let forged = statement.prove_compact(&mut transcript(), &r);
+        assert_eq!(
+            statement.verify_compact(&forged, &mut transcript()),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    // Test that possession proofs agree with the native proof that `Y = xG`.
+    #[test]
+    fn zkp_possession() {
+        let mut rng = StdRng::seed_from_u64(472);
+        let dk = DecryptionKey::new(&mut rng);
+        let other = DecryptionKey::new(&mut rng);
+        let ek = dk.encryption_key();
+
+        let statement = PossessionStatement::new(ek);
+        let compact = statement.prove_compact(&mut transcript(), &dk);
+        let batchable = statement.prove_batchable(&mut transcript(), &dk);
+        assert_eq!(
+            statement.verify_compact(&compact, &mut transcript()),
+            Ok(())
+        );
+        assert_eq!(
+            statement.verify_batchable(&batchable, &mut transcript()),
+            Ok(())
+        );
+        let g = GENERATOR_POINT;
+        let native = DleqProof::prove(&dk, &g, &ek.0, &mut rng);
+        assert_eq!(native.verify(ek, &g, &ek.0), Ok(()));
+
+        let forged = statement.prove_compact(&mut transcript(), &other);
+        assert_eq!(
+            statement.verify_compact(&forged, &mut transcript()),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            PossessionStatement::new(other.encryption_key())
+                .verify_compact(&compact, &mut transcript()),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
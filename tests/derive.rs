@@ -0,0 +1,141 @@
+// Check the code generated by `#[derive(ElgamalWrapper)]`.
+// Run with `cargo test --features derive,enable-serde --test derive`. The compile-fail cases live in
+// `tests/ui`; refresh their expected output with `TRYBUILD=overwrite` after a compiler upgrade.
+
+#![cfg(feature = "derive")]
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rust_elgamal::{
+    Ciphertext, Commitment, DecryptionKey, ElgamalWrapper, Error, RistrettoPoint, Scalar,
+    GENERATOR_TABLE,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, ElgamalWrapper)]
+struct VoteCiphertext(Ciphertext);
+
+#[derive(Copy, Clone, Debug, PartialEq, ElgamalWrapper)]
+struct WeightCiphertext {
+    ct: rust_elgamal::Ciphertext,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ElgamalWrapper)]
+struct BidCommitment(Commitment);
+
+// Test that the operators of a wrapper act on the wrapped ciphertexts, for tuple and named
+// structs alike.
+#[test]
+// the operators on references are under test too
+#[allow(clippy::op_ref)]
+fn wrapper_homomorphism() {
+    let mut rng = StdRng::seed_from_u64(486);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+    let lift = |m: u32| &Scalar::from(m) * GENERATOR_TABLE;
+
+    let votes: Vec<VoteCiphertext> = (1..=4u32)
+        .map(|m| VoteCiphertext::new(ek.exp_encrypt(Scalar::from(m), &mut rng)))
+        .collect();
+    let total: VoteCiphertext = votes.iter().sum();
+    assert_eq!(dk.decrypt(total.into_inner()), lift(10));
+    let total: VoteCiphertext = votes.clone().into_iter().sum();
+    assert_eq!(dk.decrypt(total.into()), lift(10));
+    let empty: VoteCiphertext = core::iter::empty::<VoteCiphertext>().sum();
+    assert_eq!(dk.decrypt(*empty.as_ref()), lift(0));
+
+    assert_eq!(dk.decrypt((votes[0] + votes[1]).into()), lift(3));
+    assert_eq!(dk.decrypt((votes[0] + &votes[1]).into()), lift(3));
+    assert_eq!(dk.decrypt((&votes[3] - &votes[0]).into()), lift(3));
+    assert_eq!(dk.decrypt((votes[3] - votes[0]).into()), lift(3));
+    assert_eq!(dk.decrypt((votes[2] * Scalar::from(5u32)).into()), lift(15));
+    assert_eq!(
+        dk.decrypt((&votes[2] * Scalar::from(5u32)).into()),
+        lift(15)
+    );
+
+    let w = WeightCiphertext::from(ek.exp_encrypt(Scalar::from(2u32), &mut rng));
+    let w = w + WeightCiphertext::new(ek.exp_encrypt(Scalar::from(3u32), &mut rng));
+    assert_eq!(dk.decrypt(w.into_inner()), lift(5));
+}
+
+// Test that commitment wrappers add under one key and keep verifying against the summed
+// openings.
+#[test]
+// the operators on references are under test too
+#[allow(clippy::op_ref)]
+fn wrapper_commitment() {
+    let mut rng = StdRng::seed_from_u64(486);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+
+    let (o1, c1) = Commitment::commit_with(Scalar::from(7u32), Scalar::random(&mut rng), ek);
+    let (o2, c2) = Commitment::commit_with(Scalar::from(9u32), Scalar::random(&mut rng), ek);
+    let (c1, c2) = (BidCommitment::new(c1), BidCommitment::new(c2));
+
+    assert!((c1 + c2).into_inner().verify(&(&o1 + &o2)));
+    assert!((&c2 - &c1).into_inner().verify(&(&o2 - &o1)));
+    assert!((c1 * Scalar::from(3u32))
+        .as_ref()
+        .verify(&(&o1 * Scalar::from(3u32))));
+}
+
+// Test that wrappers encode as their inner type does, and reject invalid points.
+#[test]
+fn wrapper_bytes() {
+    let mut rng = StdRng::seed_from_u64(486);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+
+    let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+    let vote = VoteCiphertext::new(ct);
+    let bytes = vote.to_bytes();
+    assert_eq!(bytes, ct.compress().to_bytes());
+    assert_eq!(VoteCiphertext::from_bytes(&bytes), Ok(vote));
+    let mut invalid = bytes;
+    invalid[63] |= 0x80;
+    assert_eq!(
+        VoteCiphertext::from_bytes(&invalid),
+        Err(Error::InvalidPoint)
+    );
+
+    let (_, commitment) = Commitment::commit_with(Scalar::from(1u32), Scalar::from(2u32), ek);
+    let bid = BidCommitment::new(commitment);
+    let bytes = bid.to_bytes();
+    assert_eq!(bytes[..32], ek.as_ref().compress().to_bytes());
+    assert_eq!(BidCommitment::from_bytes(&bytes), Ok(bid));
+    let mut invalid = bytes;
+    invalid[31] |= 0x80;
+    assert_eq!(
+        BidCommitment::from_bytes(&invalid),
+        Err(Error::InvalidPoint)
+    );
+}
+
+// Test that wrappers serialise exactly as the type they wrap.
+#[cfg(feature = "enable-serde")]
+#[test]
+fn wrapper_serde() {
+    let mut rng = StdRng::seed_from_u64(486);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+
+    let ct = ek.encrypt(RistrettoPoint::random(&mut rng), &mut rng);
+    let encoded = bincode::serialize(&VoteCiphertext::new(ct)).unwrap();
+    assert_eq!(encoded, bincode::serialize(&ct).unwrap());
+    let decoded: VoteCiphertext = bincode::deserialize(&encoded).unwrap();
+    assert_eq!(decoded.into_inner(), ct);
+
+    let (_, commitment) = Commitment::commit_with(Scalar::from(1u32), Scalar::from(2u32), ek);
+    let encoded = bincode::serialize(&BidCommitment::new(commitment)).unwrap();
+    assert_eq!(encoded, bincode::serialize(&commitment).unwrap());
+    let decoded: BidCommitment = bincode::deserialize(&encoded).unwrap();
+    assert_eq!(decoded.into_inner(), commitment);
+}
+
+// Test that misuse of the derive, and mixing two different wrappers, fail to compile with a
+// clear error.
+#[test]
+fn wrapper_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,43 @@
+"""Reference encoder for the canonical JSON fixtures of rust-elgamal.
+
+Writes the ballot and ballot proofs of the `canonical_json_pinned` test from their fields,
+which are listed here out of order on purpose. Keys are sorted, there is no whitespace, and
+every value is a lower-case hex string or an array of them, so no escaping or number
+formatting is involved.
+
+    python3 canonical_json_reference.py ballot > canonical_json_ballot.json
+    python3 canonical_json_reference.py proofs > canonical_json_proofs.json
+"""
+
+import json
+import sys
+
+BALLOT = {'ciphertexts': [{'c2': '04929a1436b328db3d4cbe7f7d15d614971043c39a3d50997427aa3176b4fb5b',
+                  'c1': '3438f0df74b6520e3c73514074993e302c99194acfaedc42c8faf1f146a27064'},
+                 {'c2': 'b0be0763b4fcd3ddf12ca9ef5b55f479187d8090333c554d9cd721a96600826c',
+                  'c1': 'e612d394a8de9b186264c31fed88a027c3906c7f6271f692c1c63443d7b8c01b'},
+                 {'c2': 'f8481db0a99ad904a50c9f98edda4dc6912210690b10de950ed3319a773eaa49',
+                  'c1': '1ef4df265ca7afced6552d1c07cd84c2ea32a53967892a198ded5bb44e02833a'}]}
+
+PROOFS = {'zero_one': [{'s': ['06ba6490752636a38f26997e3b31ca1dc80443b485dd2c3bbb5faf3109b1db0c',
+                     'de6a6acb270c3fb14b816f6710010443972464ea367740a73747c4d1505c190d'],
+               'c': ['7658b8417a3d333c274ae045a506fd2a5f72e2bd91587bcb8269a06f86762a0d',
+                     'aa557314fec4bd2205d80a0a753c106fecb65e7e0f7a08cefc415d8888746309']},
+              {'s': ['39cc722d3950c04c063fb5d983c6454ff359f73b6f470a7fce27c1caafbc0c02',
+                     'b64e3800806a88e91c2839df01c91286c84040d1b7f2918612a20a409c65170a'],
+               'c': ['727185e9e4f1ad3498a6bdacdd9781932fa5a76beab443173f8ef2efc4ba4504',
+                     '3665184cc14f6105d7c386951752e1bbed67188b77884d8ed71f84a314d4920b']},
+              {'s': ['084f3588bf19305ff9a2c38b9122b7cd51e101338f4a42062dea058c99dfac06',
+                     'a56bc98c543295bed4453785aac63e8d9d91a97b623faa531050594ceaaae308'],
+               'c': ['1574dba5f640a7b3a91fe0e9dad917150483e2f8a75c38ce135af07d0c802206',
+                     'e9d08d9e8d5b953353dc580bd11eea2ea54ac428a49fc4781453290045bf1a0c']}],
+ 'sum': {'s': '045796a0fc8f158a440c36eced4db598bcc00d327488fedcd637be17b4461f0b',
+         'c': 'c89888ac3dfb5c2ac4f38aee6556d22374e847de5ab7fab7a85f04e6ace58704'}}
+
+
+def canonical(value):
+    return json.dumps(value, sort_keys=True, separators=(",", ":"), ensure_ascii=True)
+
+
+if __name__ == "__main__":
+    print(canonical({"ballot": BALLOT, "proofs": PROOFS}[sys.argv[1]]))
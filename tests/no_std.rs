@@ -0,0 +1,47 @@
+// Check that the core API is usable from a `#![no_std]` crate.
+// Run with `cargo test --no-default-features --test no_std` to exercise the paths that do not rely
+// on an allocator.
+
+#![no_std]
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rust_elgamal::{Commitment, DecryptionKey, RistrettoPoint, Scalar, GENERATOR_TABLE};
+
+const TEST_SEED: [u8; 32] = [7; 32];
+
+// Test that encrypting a point and decrypting the result does not change a point.
+#[test]
+fn encrypt_decrypt() {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+
+    let m = RistrettoPoint::random(&mut rng);
+    let ct = ek.encrypt(m, &mut rng);
+    assert_eq!(dk.decrypt(ct), m);
+
+    let ct = ek.rerandomise(ct, &mut rng);
+    assert_eq!(dk.decrypt(ct), m);
+}
+
+// Test that exponential encryption decrypts to the lifted message.
+#[test]
+fn exp_encrypt_decrypt() {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let dk = DecryptionKey::new(&mut rng);
+    let ek = dk.encryption_key();
+
+    let m = Scalar::from(42u32);
+    let ct = ek.exp_encrypt(m, &mut rng);
+    assert_eq!(dk.decrypt(ct), &m * GENERATOR_TABLE);
+}
+
+// Test that a commitment verifies against its own opening.
+#[test]
+fn commit_verify() {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let m = Scalar::from(7u32);
+    let (open, commitment) = Commitment::commit(m, &mut rng);
+    assert!(commitment.verify(&open));
+}
@@ -0,0 +1,191 @@
+// Check the spans of the `tracing` feature: they cover the expensive operations, carry only sizes
+// and durations, and cost nothing when the feature is off.
+// Run with `cargo test --features tracing --test tracing`.
+
+use std::process::Command;
+
+// Test that the default build does not depend on tracing at all.
+#[test]
+fn default_build_has_no_tracing() {
+    let output = Command::new(env!("CARGO"))
+        .args(["tree", "--edges", "normal", "--prefix", "none", "--offline"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let tree = String::from_utf8(output.stdout).unwrap();
+    assert!(tree.starts_with("rust-elgamal "));
+    assert!(
+        !tree.lines().any(|line| line.starts_with("tracing")),
+        "{}",
+        tree
+    );
+}
+
+#[cfg(feature = "tracing")]
+mod spans {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rust_elgamal::deck::{Deck, PartialDecryption};
+    use rust_elgamal::{
+        Ciphertext, DecryptionKey, DiscreteLogTable, HomomorphicTally, Identity, RistrettoPoint,
+        Scalar, GENERATOR_TABLE,
+    };
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    type Fields = Vec<(String, String)>;
+
+    // Every span and event, with every field value as its debug output.
+    #[derive(Default)]
+    struct Captured {
+        spans: Vec<(String, Fields)>,
+        events: Vec<Fields>,
+    }
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Captured>>);
+
+    struct Visitor<'a>(&'a mut Fields);
+
+    impl Visit for Visitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields::new();
+            span.record(&mut Visitor(&mut fields));
+            let mut captured = self.0.lock().unwrap();
+            captured
+                .spans
+                .push((span.metadata().name().to_string(), fields));
+            Id::from_u64(captured.spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut captured = self.0.lock().unwrap();
+            let fields = &mut captured.spans[span.into_u64() as usize - 1].1;
+            values.record(&mut Visitor(fields));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::new();
+            event.record(&mut Visitor(&mut fields));
+            self.0.lock().unwrap().events.push(fields);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn field<'a>(captured: &'a Captured, span: &str, name: &str) -> Vec<&'a str> {
+        captured
+            .spans
+            .iter()
+            .filter(|(s, _)| s == span)
+            .flat_map(|(_, fields)| fields.iter().filter(|(n, _)| n == name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // Run every instrumented operation with known secrets, and check the spans that come out.
+    #[test]
+    fn spans_carry_sizes_not_secrets() {
+        let mut rng = StdRng::seed_from_u64(492);
+        let x = Scalar::random(&mut rng);
+        let dk = DecryptionKey::from(x);
+        let ek = *dk.encryption_key();
+        let ms: Vec<_> = (0..10).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let rs: Vec<_> = (0..10).map(|_| Scalar::random(&mut rng)).collect();
+
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            let mut cts = vec![Ciphertext::identity(); 10];
+            ek.encrypt_batch_into(&ms, &rs, &mut cts).unwrap();
+            let mut out = vec![RistrettoPoint::identity(); 10];
+            dk.decrypt_batch_into(&cts, &mut out).unwrap();
+            assert_eq!(out, ms);
+
+            let mut tally = HomomorphicTally::new(ek, 3);
+            for vote in 0..3u32 {
+                let ballot: Vec<_> = (0..3u32)
+                    .map(|i| ek.exp_encrypt(Scalar::from(u32::from(i == vote)), &mut rng))
+                    .collect();
+                tally.add_ballot(&ballot).unwrap();
+            }
+            assert_eq!(tally.finalize(&dk, 100).unwrap(), vec![1, 1, 1]);
+            DiscreteLogTable::new(100);
+
+            let points: Vec<_> = (1..=3u32)
+                .map(|i| {
+                    let m = &Scalar::from(i) * GENERATOR_TABLE;
+                    (Scalar::from(i), ek.encrypt(m, &mut rng))
+                })
+                .collect();
+            Ciphertext::interpolate(&points, Scalar::ZERO).unwrap();
+
+            let mut deck = Deck::new_standard(&ek);
+            let input = deck.cards().to_vec();
+            let witness = deck.shuffle_pass(&mut rng);
+            witness.verify(&ek, &input, deck.cards()).unwrap();
+            let partial = PartialDecryption::new(&dk, &deck.cards()[0], &mut rng);
+            deck.reveal_card(0, &[partial]).unwrap();
+        });
+        let captured = capture.0.lock().unwrap();
+
+        assert_eq!(field(&captured, "encrypt_batch", "count"), ["10"]);
+        assert_eq!(field(&captured, "decrypt_batch", "count"), ["10"]);
+        assert_eq!(field(&captured, "finalize_tally", "options"), ["3"]);
+        assert_eq!(field(&captured, "bounded_dlog", "max"), ["100"; 3]);
+        assert_eq!(field(&captured, "build_dlog_table", "max"), ["100"]);
+        assert_eq!(field(&captured, "interpolate", "points"), ["3"]);
+        assert_eq!(field(&captured, "shuffle_pass", "cards"), ["52"]);
+        assert_eq!(field(&captured, "verify_shuffle", "cards"), ["52"]);
+        assert_eq!(field(&captured, "reveal_card", "shares"), ["1"]);
+        // every span closed and recorded its duration
+        assert_eq!(
+            captured
+                .spans
+                .iter()
+                .filter(|(_, fields)| fields.iter().any(|(n, _)| n == "elapsed_us"))
+                .count(),
+            captured.spans.len()
+        );
+
+        // every value is a number, and none is a secret
+        let secrets: Vec<String> = std::iter::once(x)
+            .chain(rs.iter().copied())
+            .map(|s| format!("{:?}", s))
+            .chain(ms.iter().map(|m| format!("{:?}", m.compress())))
+            .chain(std::iter::once(format!("{:?}", x.to_bytes())))
+            .collect();
+        let values = captured
+            .spans
+            .iter()
+            .flat_map(|(_, fields)| fields)
+            .chain(captured.events.iter().flatten());
+        for (name, value) in values {
+            assert!(value.parse::<u64>().is_ok(), "{} = {}", name, value);
+            assert!(!secrets.iter().any(|secret| value.contains(secret.as_str())));
+        }
+    }
+}
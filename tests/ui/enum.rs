@@ -0,0 +1,8 @@
+use rust_elgamal::{Ciphertext, ElgamalWrapper};
+
+#[derive(ElgamalWrapper)]
+enum Either {
+    Vote(Ciphertext),
+}
+
+fn main() {}
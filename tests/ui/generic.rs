@@ -0,0 +1,6 @@
+use rust_elgamal::{Ciphertext, ElgamalWrapper};
+
+#[derive(ElgamalWrapper)]
+struct Tagged<T>(Ciphertext, core::marker::PhantomData<T>);
+
+fn main() {}
@@ -0,0 +1,13 @@
+use rust_elgamal::{Ciphertext, ElgamalWrapper};
+
+#[derive(Copy, Clone, ElgamalWrapper)]
+struct VoteCiphertext(Ciphertext);
+
+#[derive(Copy, Clone, ElgamalWrapper)]
+struct WeightCiphertext(Ciphertext);
+
+fn tally(vote: VoteCiphertext, weight: WeightCiphertext) -> VoteCiphertext {
+    vote + weight
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use rust_elgamal::{Ciphertext, ElgamalWrapper};
+
+#[derive(ElgamalWrapper)]
+struct TwoCiphertexts(Ciphertext, Ciphertext);
+
+fn main() {}
@@ -0,0 +1,6 @@
+use rust_elgamal::{ElgamalWrapper, RistrettoPoint};
+
+#[derive(ElgamalWrapper)]
+struct NotACiphertext(RistrettoPoint);
+
+fn main() {}